@@ -9,7 +9,6 @@
 //! so the child runs at the requested epoch quantum.
 
 use std::io;
-use std::process::ExitStatus as StdExitStatus;
 
 // ── Host ABI ──────────────────────────────────────────────────────────────────
 
@@ -23,6 +22,95 @@ extern "C" {
     /// `out_ptr/out_cap`. Returns the number of bytes written, or negative
     /// on error.
     fn host_waitpid(pid: i32, out_ptr: *mut u8, out_cap: usize) -> i32;
+
+    /// Whether `fd` is attached to a terminal. Returns 1 for true, 0 for false.
+    fn host_isatty(fd: i32) -> i32;
+
+    /// Size of the terminal `fd` is attached to, packed as
+    /// `cols << 16 | rows`, or 0 when it is not a terminal.
+    fn host_terminal_size(fd: i32) -> u32;
+}
+
+#[link(wasm_import_module = "wasi_snapshot_preview1")]
+extern "C" {
+    /// Fill `buf` with `buf_len` random bytes. Returns a WASI errno.
+    fn random_get(buf: *mut u8, buf_len: usize) -> i32;
+}
+
+// ── Randomness ────────────────────────────────────────────────────────────────
+
+/// Fill `buf` with random bytes from the host. Hosts that seed their
+/// generator make tools built on this reproducible.
+pub fn random_bytes(buf: &mut [u8]) -> io::Result<()> {
+    let errno = unsafe { random_get(buf.as_mut_ptr(), buf.len()) };
+    if errno != 0 {
+        return Err(io::Error::other(format!("random_get: errno {errno}")));
+    }
+    Ok(())
+}
+
+/// A random `u64` from the host; see [`random_bytes`].
+pub fn random_u64() -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    random_bytes(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+// ── Files ─────────────────────────────────────────────────────────────────────
+
+/// Replace the contents of `path` with `data` in one step: write a new file
+/// beside it, give it the old file's permissions, and rename it over
+/// `path`. A failure part-way leaves the original untouched, so tools can
+/// safely write back to a file they are still reading from (`sed -i`,
+/// `sort -o f f`).
+pub fn write_atomic(path: &str, data: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    let target = std::path::Path::new(path);
+    let dir = match target.parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => std::path::Path::new("."),
+    };
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?
+        .to_string_lossy();
+    let (tmp, mut file) = loop {
+        let tmp = dir.join(format!(".{name}.{:016x}", random_u64()?));
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+        {
+            Ok(file) => break (tmp, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    };
+    let result = file
+        .write_all(data)
+        .and_then(|_| match std::fs::metadata(target) {
+            Ok(meta) => std::fs::set_permissions(&tmp, meta.permissions()),
+            Err(_) => Ok(()),
+        })
+        .and_then(|_| std::fs::rename(&tmp, target));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+// ── Terminal ──────────────────────────────────────────────────────────────────
+
+/// Whether `fd` is attached to a terminal rather than a pipe or file.
+pub fn isatty(fd: i32) -> bool {
+    unsafe { host_isatty(fd) == 1 }
+}
+
+/// `(cols, rows)` of the terminal `fd` is attached to, if it is one.
+pub fn terminal_size(fd: i32) -> Option<(u16, u16)> {
+    let packed = unsafe { host_terminal_size(fd) };
+    (packed != 0).then_some(((packed >> 16) as u16, packed as u16))
 }
 
 // ── ExitStatus ────────────────────────────────────────────────────────────────
@@ -108,10 +196,7 @@ impl Command {
 
         let pid = unsafe { host_spawn_async(req_bytes.as_ptr(), req_bytes.len()) };
         if pid < 0 {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("host_spawn_async failed: {pid}"),
-            ));
+            return Err(io::Error::other(format!("host_spawn_async failed: {pid}")));
         }
 
         // Wait for child; read exit code from JSON response.
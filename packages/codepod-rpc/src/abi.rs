@@ -0,0 +1,191 @@
+//! Structured results from guest entry points.
+//!
+//! An entry point following this convention writes a [`GuestResult`] to a
+//! pointer the host passes in, rather than folding lengths and buffer sizes
+//! into its return value. The status says how the call went:
+//!
+//! - [`STATUS_OK`]: `ptr`/`len` is the output, allocated by the guest. The
+//!   host copies it out and frees it with `__dealloc(ptr, len)`.
+//! - [`STATUS_ERROR`]: the call failed and the guest recovered. `ptr`/`len`
+//!   may still carry output, such as an error response, and `__last_error`
+//!   returns the message.
+//! - [`STATUS_PANIC`]: the guest panicked. Guests built with `panic=abort`,
+//!   as wasm guests are by default, trap instead of returning. A host that
+//!   catches the trap can read the panic message from `__last_error`, since
+//!   [`record_panics`] saves it first, and should then discard the instance.
+//!   `__panic_info` reports the first panic's message and location as JSON.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// The call succeeded.
+pub const STATUS_OK: i32 = 0;
+/// The call failed; see `__last_error`.
+pub const STATUS_ERROR: i32 = 1;
+/// The guest panicked; see `__last_error`.
+pub const STATUS_PANIC: i32 = 2;
+
+/// What an entry point hands back to the host: 12 bytes on wasm32.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GuestResult {
+    pub status: i32,
+    pub ptr: usize,
+    pub len: usize,
+}
+
+impl GuestResult {
+    /// Give `output` to the host, which owns it from now on.
+    pub fn new(status: i32, output: Vec<u8>) -> Self {
+        let len = output.len();
+        let ptr = Box::into_raw(output.into_boxed_slice()) as *mut u8 as usize;
+        Self { status, ptr, len }
+    }
+}
+
+/// The error the last failed call recorded, and its status.
+static LAST_ERROR: Mutex<Option<(i32, String)>> = Mutex::new(None);
+
+/// Record the message `__last_error` reports.
+pub fn set_last_error(status: i32, message: impl Into<String>) {
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some((status, message.into()));
+}
+
+/// Forget the recorded error, at the start of a call that succeeds.
+pub fn clear_last_error() {
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// The recorded error, if the last call failed.
+pub fn last_error() -> Option<(i32, String)> {
+    LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Where and why a guest panicked, as `__panic_info` reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanicReport {
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+}
+
+/// The guest's first panic. Later panics (say, in a destructor while
+/// unwinding) are usually consequences of it, so they do not replace it.
+static FIRST_PANIC: Mutex<Option<PanicReport>> = Mutex::new(None);
+
+/// The panic [`record_panics`] saw first, if any.
+pub fn panic_report() -> Option<PanicReport> {
+    FIRST_PANIC
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Save every panic's message and location before the default hook runs,
+/// so they survive a `panic=abort` trap: the message becomes the last
+/// error, and the first panic is kept for [`panic_report`].
+pub fn record_panics() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let location = info.location();
+        let report = PanicReport {
+            message,
+            file: location.map(|l| l.file().to_string()),
+            line: location.map(|l| l.line()),
+            column: location.map(|l| l.column()),
+        };
+        set_last_error(STATUS_PANIC, info.to_string());
+        FIRST_PANIC
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert(report);
+        previous(info);
+    }));
+}
+
+/// Run an entry point's body and describe the outcome for the host.
+///
+/// `Err` carries output for the host alongside the message to record. A
+/// panic is caught where the guest can unwind, and reported as
+/// [`STATUS_PANIC`] with no output.
+pub fn run_entry(body: impl FnOnce() -> Result<Vec<u8>, (Vec<u8>, String)>) -> GuestResult {
+    clear_last_error();
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(output)) => GuestResult::new(STATUS_OK, output),
+        Ok(Err((output, message))) => {
+            set_last_error(STATUS_ERROR, message);
+            GuestResult::new(STATUS_ERROR, output)
+        }
+        Err(payload) => {
+            if last_error().is_none() {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "guest panicked".to_string());
+                set_last_error(STATUS_PANIC, message);
+            }
+            GuestResult {
+                status: STATUS_PANIC,
+                ..GuestResult::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Take back output handed over by `GuestResult::new`.
+    fn reclaim(result: GuestResult) -> Vec<u8> {
+        if result.ptr == 0 {
+            return Vec::new();
+        }
+        let slice = std::ptr::slice_from_raw_parts_mut(result.ptr as *mut u8, result.len);
+        unsafe { Box::from_raw(slice) }.into_vec()
+    }
+
+    #[test]
+    fn entry_outcomes_map_to_statuses() {
+        record_panics();
+        let ok = run_entry(|| Ok(b"done".to_vec()));
+        assert_eq!(ok.status, STATUS_OK);
+        assert_eq!(reclaim(ok), b"done");
+        assert_eq!(last_error(), None);
+
+        let failed = run_entry(|| Err((b"{}".to_vec(), "no such file".into())));
+        assert_eq!(failed.status, STATUS_ERROR);
+        assert_eq!(reclaim(failed), b"{}");
+        assert_eq!(last_error(), Some((STATUS_ERROR, "no such file".into())));
+
+        let panicked = run_entry(|| panic!("index out of bounds"));
+        assert_eq!(panicked.status, STATUS_PANIC);
+        assert_eq!((panicked.ptr, panicked.len), (0, 0));
+        let (status, message) = last_error().unwrap();
+        assert_eq!(status, STATUS_PANIC);
+        assert!(message.contains("index out of bounds"), "{message}");
+        let report = panic_report().unwrap();
+        assert_eq!(report.message, "index out of bounds");
+        assert!(report.file.unwrap().ends_with("abi.rs"));
+        assert!(report.line.is_some());
+
+        run_entry(|| panic!("second"));
+        assert_eq!(panic_report().unwrap().message, "index out of bounds");
+
+        assert_eq!(run_entry(|| Ok(Vec::new())).status, STATUS_OK);
+        assert_eq!(last_error(), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! A bump arena for the buffers a host borrows from a guest.
+//!
+//! Every call across the boundary allocates a few short-lived buffers with
+//! `__alloc` (the request, the output buffer, a retry's bigger one) and
+//! frees them with `__dealloc` right after. Taking them from an [`Arena`]
+//! instead of the guest's allocator makes each allocation a pointer bump and
+//! keeps them from fragmenting the heap the guest itself lives in. Freeing
+//! an arena buffer does nothing; the host [`reset`](Arena::reset)s the arena
+//! between requests, once none of its buffers are in use. Requests too big
+//! for what is left fall back to the guest's allocator.
+
+use std::alloc::Layout;
+
+pub struct Arena {
+    storage: Box<[u8]>,
+    /// Offset of the first free byte.
+    next: usize,
+}
+
+impl Arena {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            storage: vec![0; capacity].into_boxed_slice(),
+            next: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Bytes handed out since the last reset, padding included.
+    pub fn used(&self) -> usize {
+        self.next
+    }
+
+    /// Carve `layout` out of the free space, or `None` if it does not fit.
+    pub fn alloc(&mut self, layout: Layout) -> Option<*mut u8> {
+        let base = self.storage.as_mut_ptr();
+        let misalign = (base as usize + self.next) % layout.align();
+        let start = self.next + (layout.align() - misalign) % layout.align();
+        let end = start.checked_add(layout.size())?;
+        if end > self.storage.len() {
+            return None;
+        }
+        self.next = end;
+        Some(unsafe { base.add(start) })
+    }
+
+    /// Whether `ptr` was handed out by this arena.
+    pub fn contains(&self, ptr: *const u8) -> bool {
+        let range = self.storage.as_ptr_range();
+        range.start <= ptr && ptr < range.end
+    }
+
+    /// Make all of the arena free again. Every buffer it handed out is
+    /// invalid from here on.
+    pub fn reset(&mut self) {
+        self.next = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bumps_until_full_and_starts_over_on_reset() {
+        let mut arena = Arena::new(64);
+        let a = arena
+            .alloc(Layout::from_size_align(10, 1).unwrap())
+            .unwrap();
+        let b = arena.alloc(Layout::from_size_align(8, 8).unwrap()).unwrap();
+        assert_eq!(b as usize % 8, 0);
+        assert!(b as usize >= a as usize + 10);
+        assert!(arena.contains(a) && arena.contains(b));
+        assert!(!arena.contains(std::ptr::null()));
+
+        assert!(arena
+            .alloc(Layout::from_size_align(64, 1).unwrap())
+            .is_none());
+        let used = arena.used();
+        assert!((18..=25).contains(&used), "{used}");
+
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        let c = arena
+            .alloc(Layout::from_size_align(64, 1).unwrap())
+            .unwrap();
+        assert_eq!(c, a);
+    }
+}
@@ -0,0 +1,43 @@
+//! Interrupting a guest without tearing down its instance.
+//!
+//! A guest exports `__request_cancel()`, which calls [`request`]. The host
+//! calls it while the guest is busy, from a JSPI suspension or another
+//! thread sharing the instance's memory; it touches nothing but a flag, so
+//! it is safe to call in the middle of another export. The guest polls
+//! [`requested`] at points where stopping leaves it consistent, unwinds
+//! from there, and [`clear`]s the flag when its next run starts.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Ask the running call to stop at its next safe point.
+pub fn request() {
+    REQUESTED.store(true, Ordering::Release);
+}
+
+/// Whether an interrupt is pending.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Acquire)
+}
+
+/// Forget a pending interrupt, returning whether there was one.
+pub fn clear() -> bool {
+    REQUESTED.swap(false, Ordering::AcqRel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_stay_pending_until_cleared() {
+        assert!(!requested());
+        request();
+        request();
+        assert!(requested());
+        assert!(clear());
+        assert!(!requested());
+        assert!(!clear());
+    }
+}
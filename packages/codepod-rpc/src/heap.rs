@@ -0,0 +1,118 @@
+//! Reporting how much memory a guest is using.
+//!
+//! Linear memory only grows, so its size says little about what a guest
+//! holds now. A guest that wants to report its heap installs a [`Tracking`]
+//! allocator as its `#[global_allocator]` and exports `__heap_used()` and
+//! `__heap_peak()` from it; hosts read those to show memory statistics and
+//! to stop sessions that go over a soft limit.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An allocator that counts the bytes live in the allocator it wraps.
+pub struct Tracking<A> {
+    inner: A,
+    used: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl<A> Tracking<A> {
+    pub const fn new(inner: A) -> Self {
+        Self {
+            inner,
+            used: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Bytes allocated and not yet freed.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// The most [`used`](Self::used) has been since start-up or the last
+    /// [`reset_peak`](Self::reset_peak).
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// Start measuring the peak again from the current usage, e.g. per
+    /// command.
+    pub fn reset_peak(&self) {
+        self.peak.store(self.used(), Ordering::Relaxed);
+    }
+
+    fn grew(&self, by: usize) {
+        let used = self.used.fetch_add(by, Ordering::Relaxed) + by;
+        self.peak.fetch_max(used, Ordering::Relaxed);
+    }
+
+    fn shrank(&self, by: usize) {
+        self.used.fetch_sub(by, Ordering::Relaxed);
+    }
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for Tracking<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            self.grew(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            self.grew(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+        self.shrank(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = self.inner.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            if new_size > layout.size() {
+                self.grew(new_size - layout.size());
+            } else {
+                self.shrank(layout.size() - new_size);
+            }
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::System;
+
+    #[test]
+    fn counts_live_bytes_and_the_peak() {
+        let heap = Tracking::new(System);
+        let small = Layout::from_size_align(100, 8).unwrap();
+        let large = Layout::from_size_align(4096, 8).unwrap();
+        unsafe {
+            let a = heap.alloc(small);
+            let b = heap.alloc_zeroed(large);
+            assert_eq!(heap.used(), 4196);
+            heap.dealloc(b, large);
+            assert_eq!((heap.used(), heap.peak()), (100, 4196));
+
+            let a = heap.realloc(a, small, 1000);
+            assert_eq!(heap.used(), 1000);
+            let a = heap.realloc(a, Layout::from_size_align(1000, 8).unwrap(), 10);
+            assert_eq!((heap.used(), heap.peak()), (10, 4196));
+
+            heap.reset_peak();
+            assert_eq!(heap.peak(), 10);
+            heap.dealloc(a, Layout::from_size_align(10, 8).unwrap());
+            assert_eq!((heap.used(), heap.peak()), (0, 10));
+        }
+    }
+}
@@ -0,0 +1,404 @@
+//! Request/response protocol for calls across the wasm boundary.
+//!
+//! Hosts call into a guest through its `__rpc` export, and guests call the
+//! host through the `host_rpc` import. Both directions exchange the same
+//! messages, each a frame: a little-endian `u32` byte length followed by
+//! that many bytes of UTF-8 JSON.
+//!
+//! A [`Request`] names a method and carries its parameters, and the
+//! [`Response`] echoes the request's id with either a result or an error:
+//!
+//! ```text
+//! {"id":7,"method":"fs.read","params":{"path":"/tmp/a"}}
+//! {"id":7,"result":{"data":"aGVsbG8K"}}
+//! {"id":7,"error":{"code":-32601,"message":"unknown method: fs.raed"}}
+//! ```
+//!
+//! The callee writes the response frame into a buffer the caller provides
+//! and returns its length, or, when the buffer is too small, returns the
+//! length it needs without writing anything. The caller then retries the
+//! same request with a larger buffer. The callee answers through an
+//! [`Outbox`], which holds on to the undelivered response so the retry does
+//! not run the request a second time.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub mod abi;
+pub mod arena;
+pub mod cancel;
+pub mod heap;
+pub mod ring;
+pub mod stream;
+
+pub use ring::Ring;
+pub use stream::Streams;
+
+// ── Methods ───────────────────────────────────────────────────────────────────
+
+/// Method names understood by the guests and hosts.
+pub mod method {
+    /// Run a shell command: `{"command"}` → the `__run_command` result.
+    /// Guests asking the host to run one may also give `"stdin"`, `"cwd"`
+    /// and an `"env"` object of variables to export.
+    pub const SHELL_RUN: &str = "shell.run";
+    /// Run several commands at once: `{"commands"}`, a list of `shell.run`
+    /// params → their results, in the same order.
+    pub const SHELL_RUN_ALL: &str = "shell.run_all";
+    /// Read a file: `{"path"}` → `{"data"}`, base64-encoded, or with
+    /// `"stream": true` → `{"stream", "size"}` to read in chunks.
+    pub const FS_READ: &str = "fs.read";
+    /// Write a file: `{"path", "data", "append"?}`, data base64-encoded, or
+    /// with `"stream"` in place of `"data"` naming a stream already written.
+    pub const FS_WRITE: &str = "fs.write";
+    /// Stat a path: `{"path"}` → the stat fields.
+    pub const FS_STAT: &str = "fs.stat";
+    /// Report the callee's state, such as the shell's cwd and last status.
+    pub const STATUS: &str = "status";
+    /// Start recording the session's commands and output into a
+    /// transcript, dropping any recorded before → null.
+    pub const TRANSCRIPT_START: &str = "transcript.start";
+    /// Read the transcript so far: `{"format"?}`, `"json"` (the default) or
+    /// `"asciicast"` → `{"transcript"}`. Fails if nothing is being recorded.
+    pub const TRANSCRIPT_GET: &str = "transcript.get";
+    /// Stop recording: as `transcript.get`, and then drop the transcript.
+    pub const TRANSCRIPT_STOP: &str = "transcript.stop";
+    /// Restrict the commands the shell may run: `{"allow"?, "deny"?,
+    /// "deny_args"?, "paths"?}`, where `deny_args` is a list of
+    /// `{"command", "pattern"}` → null. Replaces the policy set before;
+    /// `{}` lifts it.
+    pub const POLICY_SET: &str = "policy.set";
+}
+
+// ── Messages ──────────────────────────────────────────────────────────────────
+
+/// A call to `method` with `params`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Request {
+    pub id: u64,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl Request {
+    pub fn new(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            id,
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// Decode the parameters as `T`, failing with `INVALID_PARAMS`.
+    pub fn params<T: DeserializeOwned>(&self) -> Result<T, RpcError> {
+        serde_json::from_value(self.params.clone()).map_err(|e| {
+            RpcError::new(
+                RpcError::INVALID_PARAMS,
+                format!("{}: invalid params: {e}", self.method),
+            )
+        })
+    }
+}
+
+/// Why a call failed. The codes follow JSON-RPC 2.0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl RpcError {
+    /// The request frame or its JSON could not be decoded.
+    pub const PARSE_ERROR: i32 = -32700;
+    /// The JSON was not a request.
+    pub const INVALID_REQUEST: i32 = -32600;
+    /// No such method.
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    /// The parameters do not fit the method.
+    pub const INVALID_PARAMS: i32 = -32602;
+    /// The callee failed in a way the caller could not have avoided.
+    pub const INTERNAL_ERROR: i32 = -32603;
+    /// The method ran and failed, for example on a missing file.
+    pub const FAILED: i32 = -32000;
+
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(Self::METHOD_NOT_FOUND, format!("unknown method: {method}"))
+    }
+
+    pub fn failed(message: impl fmt::Display) -> Self {
+        Self::new(Self::FAILED, message.to_string())
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// The answer to the request with the same `id`. Exactly one of `result`
+/// and `error` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn new(id: u64, outcome: Result<Value, RpcError>) -> Self {
+        match outcome {
+            Ok(result) => Self {
+                id,
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => Self {
+                id,
+                result: None,
+                error: Some(error),
+            },
+        }
+    }
+
+    pub fn into_result(self) -> Result<Value, RpcError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+// ── Framing ───────────────────────────────────────────────────────────────────
+
+/// Bytes in a frame's length prefix.
+pub const PREFIX_LEN: usize = 4;
+
+/// Encode `message` as a frame.
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    let json = serde_json::to_vec(message).expect("RPC messages serialize to JSON");
+    let mut frame = Vec::with_capacity(PREFIX_LEN + json.len());
+    frame.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&json);
+    frame
+}
+
+/// Decode the frame at the start of `buf`. Bytes after the frame are
+/// ignored, so `buf` may be a whole output buffer.
+pub fn decode<T: DeserializeOwned>(buf: &[u8]) -> Result<T, RpcError> {
+    let Some(prefix) = buf.get(..PREFIX_LEN) else {
+        return Err(RpcError::new(RpcError::PARSE_ERROR, "truncated frame"));
+    };
+    let len = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+    let Some(json) = buf[PREFIX_LEN..].get(..len) else {
+        return Err(RpcError::new(
+            RpcError::PARSE_ERROR,
+            format!(
+                "frame says {len} bytes but {} follow",
+                buf.len() - PREFIX_LEN
+            ),
+        ));
+    };
+    serde_json::from_slice(json).map_err(|e| {
+        let code = if e.is_data() {
+            RpcError::INVALID_REQUEST
+        } else {
+            RpcError::PARSE_ERROR
+        };
+        RpcError::new(code, e.to_string())
+    })
+}
+
+// ── Serving and calling ───────────────────────────────────────────────────────
+
+/// Answers requests into caller-provided buffers, holding on to a response
+/// that did not fit until the caller retries with a larger buffer.
+#[derive(Debug, Default)]
+pub struct Outbox {
+    /// The request frame and the response to it that has not been delivered.
+    held: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Outbox {
+    pub const fn new() -> Self {
+        Self { held: None }
+    }
+
+    /// Answer the request frame `request` into `out`, calling `handle`
+    /// unless this repeats the request whose response did not fit last time.
+    ///
+    /// Returns the response frame's length. If that is more than
+    /// `out.len()`, nothing was written and the response is held for the
+    /// retry.
+    pub fn answer(
+        &mut self,
+        request: &[u8],
+        out: &mut [u8],
+        handle: impl FnOnce(&Request) -> Result<Value, RpcError>,
+    ) -> usize {
+        let response = match self.held.take() {
+            Some((held_request, response)) if held_request == request => response,
+            _ => match decode::<Request>(request) {
+                Ok(req) => encode(&Response::new(req.id, handle(&req))),
+                Err(e) => encode(&Response::new(0, Err(e))),
+            },
+        };
+        if response.len() > out.len() {
+            let len = response.len();
+            self.held = Some((request.to_vec(), response));
+            return len;
+        }
+        out[..response.len()].copy_from_slice(&response);
+        response.len()
+    }
+
+    /// Whether `request` repeats the request whose response is being held,
+    /// so answering it will not call the handler. Hosts whose handlers are
+    /// async run the request first only when this is false.
+    pub fn holds(&self, request: &[u8]) -> bool {
+        matches!(&self.held, Some((held_request, _)) if held_request == request)
+    }
+}
+
+/// Make `request` through `transport`, which passes a request frame and an
+/// output buffer to the callee and returns the callee's result code.
+///
+/// The call is retried once with a larger buffer if the response did not
+/// fit. A negative result code is reported as a failure.
+pub fn call(
+    request: &Request,
+    mut transport: impl FnMut(&[u8], &mut [u8]) -> i32,
+) -> Result<Value, RpcError> {
+    let frame = encode(request);
+    let mut out = vec![0u8; 64 * 1024];
+    let mut rc = transport(&frame, &mut out);
+    if rc > 0 && rc as usize > out.len() {
+        out.resize(rc as usize, 0);
+        rc = transport(&frame, &mut out);
+    }
+    if rc < 0 {
+        return Err(RpcError::failed(format!(
+            "{}: host call failed with error code {rc}",
+            request.method
+        )));
+    }
+    out.truncate(rc as usize);
+    let response: Response = decode(&out)?;
+    if response.id != request.id {
+        return Err(RpcError::new(
+            RpcError::INTERNAL_ERROR,
+            format!(
+                "response to request {} arrived for request {}",
+                response.id, request.id
+            ),
+        ));
+    }
+    response.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn echo(req: &Request) -> Result<Value, RpcError> {
+        match req.method.as_str() {
+            method::STATUS => Ok(req.params.clone()),
+            other => Err(RpcError::method_not_found(other)),
+        }
+    }
+
+    #[test]
+    fn frames_round_trip_and_report_damage() {
+        let req = Request::new(3, method::FS_READ, json!({ "path": "/a" }));
+        let frame = encode(&req);
+        assert_eq!(
+            &frame[..PREFIX_LEN],
+            &(frame.len() as u32 - 4).to_le_bytes()
+        );
+
+        // Trailing bytes (the rest of an output buffer) are ignored.
+        let mut padded = frame.clone();
+        padded.extend_from_slice(&[0; 16]);
+        assert_eq!(decode::<Request>(&padded).unwrap(), req);
+
+        let err = decode::<Request>(&frame[..frame.len() - 1]).unwrap_err();
+        assert_eq!(err.code, RpcError::PARSE_ERROR);
+        let err = decode::<Request>(&encode(&json!({ "id": 1 }))).unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_REQUEST);
+    }
+
+    #[test]
+    fn outbox_holds_responses_that_do_not_fit() {
+        let mut outbox = Outbox::new();
+        let request = encode(&Request::new(
+            1,
+            method::STATUS,
+            json!({ "cwd": "/home/user" }),
+        ));
+        let calls = std::cell::Cell::new(0);
+        let handle = |req: &Request| {
+            calls.set(calls.get() + 1);
+            echo(req)
+        };
+
+        let mut small = [0u8; 8];
+        let needed = outbox.answer(&request, &mut small, handle);
+        assert!(needed > small.len());
+        assert_eq!(small, [0; 8]);
+        assert!(outbox.holds(&request));
+
+        let mut out = vec![0u8; needed];
+        assert_eq!(outbox.answer(&request, &mut out, handle), needed);
+        assert_eq!(calls.get(), 1, "the retry must not run the request again");
+        assert!(!outbox.holds(&request));
+        let response: Response = decode(&out).unwrap();
+        assert_eq!(
+            response.into_result().unwrap(),
+            json!({ "cwd": "/home/user" })
+        );
+
+        // A different request is handled afresh.
+        let other = encode(&Request::new(2, "nope", Value::Null));
+        let mut out = vec![0u8; 256];
+        outbox.answer(&other, &mut out, handle);
+        let err = decode::<Response>(&out).unwrap().into_result().unwrap_err();
+        assert_eq!(err.code, RpcError::METHOD_NOT_FOUND);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn call_retries_through_the_outbox() {
+        let mut outbox = Outbox::new();
+        let mut sizes = Vec::new();
+        let big = "x".repeat(100_000);
+        let request = Request::new(9, method::STATUS, json!({ "big": big }));
+
+        let result = call(&request, |frame, out| {
+            sizes.push(out.len());
+            outbox.answer(frame, out, echo) as i32
+        })
+        .unwrap();
+        assert_eq!(result["big"].as_str().unwrap().len(), 100_000);
+        assert_eq!(sizes.len(), 2);
+
+        let err = call(&request, |_, _| -3).unwrap_err();
+        assert_eq!(err.code, RpcError::FAILED);
+        assert!(err.message.contains("error code -3"), "{err}");
+    }
+}
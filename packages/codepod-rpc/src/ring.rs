@@ -0,0 +1,166 @@
+//! Output the host can watch while a call is still running.
+//!
+//! A [`Ring`] is a single-producer, single-consumer byte ring in guest
+//! memory. The guest appends output as it writes it; the host polls the
+//! guest's exported head and tail accessors whenever it gets control back
+//! (a JSPI suspension, a timer in another worker sharing the memory), copies
+//! the bytes between them straight out of linear memory, and moves the tail
+//! up to say it has taken them.
+//!
+//! Positions are free-running `u32` byte counts; the byte at position `p`
+//! lives at offset `p % capacity` of the data. The capacity is a power of
+//! two, so positions can wrap around `u32::MAX` without breaking that. The
+//! guest never waits for the host: output that does not fit is dropped and
+//! counted instead.
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+pub struct Ring {
+    data: Box<[UnsafeCell<u8>]>,
+    /// Position after the last byte written. Only the producer moves it.
+    head: AtomicU32,
+    /// Position of the first byte not yet taken. Only the consumer moves it.
+    tail: AtomicU32,
+    /// Bytes the producer had to drop because the ring was full.
+    dropped: AtomicU32,
+}
+
+// The producer only writes bytes between head and tail + capacity and the
+// consumer only reads bytes between tail and head, so the two never touch
+// the same byte at once.
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    /// Largest capacity a ring can have.
+    pub const MAX_CAPACITY: u32 = 1 << 31;
+
+    /// A ring holding `capacity` bytes, rounded up to a power of two.
+    pub fn new(capacity: u32) -> Self {
+        let capacity = capacity.clamp(1, Self::MAX_CAPACITY).next_power_of_two();
+        Self {
+            data: (0..capacity).map(|_| UnsafeCell::new(0)).collect(),
+            head: AtomicU32::new(0),
+            tail: AtomicU32::new(0),
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.data.len() as u32
+    }
+
+    /// Where the data starts, for the host to read from.
+    pub fn data_ptr(&self) -> *const u8 {
+        self.data.as_ptr() as *const u8
+    }
+
+    pub fn head(&self) -> u32 {
+        self.head.load(Ordering::Acquire)
+    }
+
+    pub fn tail(&self) -> u32 {
+        self.tail.load(Ordering::Acquire)
+    }
+
+    pub fn dropped(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Append as much of `bytes` as fits and return how much that was,
+    /// counting the rest as dropped. Producer side.
+    pub fn push(&self, bytes: &[u8]) -> usize {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Acquire);
+        let free = self.capacity() - head.wrapping_sub(tail);
+        let n = bytes.len().min(free as usize);
+        self.copy_in(head, &bytes[..n]);
+        self.head
+            .store(head.wrapping_add(n as u32), Ordering::Release);
+        let lost = (bytes.len() - n) as u32;
+        if lost > 0 {
+            self.dropped.fetch_add(lost, Ordering::Relaxed);
+        }
+        n
+    }
+
+    /// Copy the oldest unread bytes into `out`, take them, and return how
+    /// many there were. Consumer side, for hosts that cannot read guest
+    /// memory directly.
+    pub fn pop(&self, out: &mut [u8]) -> usize {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        let n = out.len().min(head.wrapping_sub(tail) as usize);
+        let mask = self.capacity() - 1;
+        for (i, byte) in out[..n].iter_mut().enumerate() {
+            let at = (tail.wrapping_add(i as u32) & mask) as usize;
+            *byte = unsafe { *self.data[at].get() };
+        }
+        self.tail
+            .store(tail.wrapping_add(n as u32), Ordering::Release);
+        n
+    }
+
+    /// Record that the consumer has taken everything before `tail`. Fails,
+    /// leaving the ring alone, unless `tail` lies between the current tail
+    /// and the head.
+    pub fn set_tail(&self, tail: u32) -> bool {
+        let current = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(current) > head.wrapping_sub(current) {
+            return false;
+        }
+        self.tail.store(tail, Ordering::Release);
+        true
+    }
+
+    fn copy_in(&self, at: u32, bytes: &[u8]) {
+        let mask = self.capacity() - 1;
+        for (i, &byte) in bytes.iter().enumerate() {
+            let at = (at.wrapping_add(i as u32) & mask) as usize;
+            unsafe { *self.data[at].get() = byte };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_come_out_in_order_and_overflow_is_counted() {
+        let ring = Ring::new(10);
+        assert_eq!(ring.capacity(), 16);
+
+        assert_eq!(ring.push(b"hello, "), 7);
+        assert_eq!(ring.push(b"world, and more"), 9);
+        assert_eq!(ring.dropped(), 6);
+        assert_eq!(ring.head().wrapping_sub(ring.tail()), 16);
+
+        let mut out = [0u8; 5];
+        assert_eq!(ring.pop(&mut out), 5);
+        assert_eq!(&out, b"hello");
+        // The freed space wraps around to the start of the data.
+        assert_eq!(ring.push(b"!!!"), 3);
+        let mut rest = [0u8; 32];
+        let n = ring.pop(&mut rest);
+        assert_eq!(&rest[..n], b", world, an!!!");
+
+        assert!(!ring.set_tail(ring.head().wrapping_add(1)));
+        ring.push(b"xyz");
+        assert!(ring.set_tail(ring.head()));
+        assert_eq!(ring.pop(&mut rest), 0);
+    }
+
+    #[test]
+    fn positions_wrap_around_u32() {
+        let ring = Ring::new(8);
+        ring.head.store(u32::MAX - 2, Ordering::Relaxed);
+        ring.tail.store(u32::MAX - 2, Ordering::Relaxed);
+        assert_eq!(ring.push(b"abcdef"), 6);
+        assert_eq!(ring.head(), 3);
+        let mut out = [0u8; 8];
+        assert_eq!(ring.pop(&mut out), 6);
+        assert_eq!(&out[..6], b"abcdef");
+    }
+}
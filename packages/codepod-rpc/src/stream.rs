@@ -0,0 +1,113 @@
+//! Moving payloads too large for one allocation across the boundary.
+//!
+//! A guest exports `__write_chunk(stream, ptr, len)` and
+//! `__read_chunk(stream, out_ptr, out_cap)` backed by a [`Streams`]. The host
+//! sends a payload by writing it a chunk at a time through one small guest
+//! buffer, and the guest appends each chunk to the stream's payload, so the
+//! guest never holds the payload twice. In the other direction the guest
+//! [`offer`](Streams::offer)s a payload and the host drains it chunk by
+//! chunk.
+//!
+//! Streams are named in requests by number: `fs.write` takes a `stream` the
+//! host has written in place of inline `data`, and `fs.read` with
+//! `"stream": true` answers with a stream to read instead of the data.
+
+use std::collections::BTreeMap;
+
+/// Payloads being assembled from the host and payloads waiting for it.
+///
+/// Incoming streams are numbered by the host; outgoing ones by
+/// [`offer`](Self::offer). The two are separate namespaces.
+#[derive(Debug, Default)]
+pub struct Streams {
+    incoming: BTreeMap<u32, Vec<u8>>,
+    outgoing: BTreeMap<u32, Outgoing>,
+    next_outgoing: u32,
+}
+
+#[derive(Debug)]
+struct Outgoing {
+    data: Vec<u8>,
+    sent: usize,
+}
+
+impl Streams {
+    pub const fn new() -> Self {
+        Self {
+            incoming: BTreeMap::new(),
+            outgoing: BTreeMap::new(),
+            next_outgoing: 1,
+        }
+    }
+
+    /// Append `chunk` to incoming stream `id`, starting it if needed.
+    pub fn write_chunk(&mut self, id: u32, chunk: &[u8]) {
+        self.incoming
+            .entry(id)
+            .or_default()
+            .extend_from_slice(chunk);
+    }
+
+    /// Take the payload assembled on incoming stream `id`.
+    pub fn take_incoming(&mut self, id: u32) -> Option<Vec<u8>> {
+        self.incoming.remove(&id)
+    }
+
+    /// Hold `data` for the host to read and return its stream number.
+    pub fn offer(&mut self, data: Vec<u8>) -> u32 {
+        let id = self.next_outgoing;
+        self.next_outgoing = self.next_outgoing.wrapping_add(1).max(1);
+        self.outgoing.insert(id, Outgoing { data, sent: 0 });
+        id
+    }
+
+    /// Copy the next piece of outgoing stream `id` into `out` and return its
+    /// length. Returns `Some(0)` once the payload has been read, forgetting
+    /// the stream, and `None` for a stream that does not exist.
+    pub fn read_chunk(&mut self, id: u32, out: &mut [u8]) -> Option<usize> {
+        let stream = self.outgoing.get_mut(&id)?;
+        let n = out.len().min(stream.data.len() - stream.sent);
+        if n == 0 {
+            self.outgoing.remove(&id);
+            return Some(0);
+        }
+        out[..n].copy_from_slice(&stream.data[stream.sent..stream.sent + n]);
+        stream.sent += n;
+        Some(n)
+    }
+
+    /// Drop every stream, finished or not.
+    pub fn clear(&mut self) {
+        self.incoming.clear();
+        self.outgoing.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payloads_cross_in_chunks() {
+        let payload: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+        let mut streams = Streams::new();
+
+        for chunk in payload.chunks(4096) {
+            streams.write_chunk(7, chunk);
+        }
+        assert_eq!(streams.take_incoming(7).as_deref(), Some(&payload[..]));
+        assert_eq!(streams.take_incoming(7), None);
+
+        let id = streams.offer(payload.clone());
+        let mut received = Vec::new();
+        let mut buf = [0u8; 3000];
+        loop {
+            match streams.read_chunk(id, &mut buf).unwrap() {
+                0 => break,
+                n => received.extend_from_slice(&buf[..n]),
+            }
+        }
+        assert_eq!(received, payload);
+        assert_eq!(streams.read_chunk(id, &mut buf), None);
+    }
+}
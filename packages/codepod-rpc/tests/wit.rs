@@ -0,0 +1,50 @@
+//! The guests generate bindings from `wit/sandbox.wit` only when built with
+//! their `component` feature, so a default build would not notice it
+//! failing to parse or its worlds drifting from the guests.
+
+use wit_parser::{PackageId, Resolve, WorldKey};
+
+fn load() -> (Resolve, PackageId) {
+    let mut resolve = Resolve::default();
+    let (package, _) = resolve
+        .push_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/wit"))
+        .expect("sandbox.wit parses");
+    (resolve, package)
+}
+
+/// The interface names behind `keys`, without the package prefix.
+fn names<'a>(resolve: &Resolve, keys: impl Iterator<Item = &'a WorldKey>) -> Vec<String> {
+    let mut names: Vec<String> = keys
+        .map(|key| {
+            let name = resolve.name_world_key(key);
+            let name = name.strip_prefix("codepod:sandbox/").unwrap_or(&name);
+            name.strip_suffix("@0.1.0").unwrap_or(name).to_string()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn worlds_match_the_guests() {
+    let (resolve, package) = load();
+    assert_eq!(
+        resolve.packages[package].name.to_string(),
+        "codepod:sandbox@0.1.0"
+    );
+
+    let shell = &resolve.worlds[resolve.select_world(package, Some("shell-guest")).unwrap()];
+    assert_eq!(
+        names(&resolve, shell.imports.keys()),
+        ["clock", "fetch", "filesystem", "process", "types"]
+    );
+    assert_eq!(names(&resolve, shell.exports.keys()), ["shell"]);
+
+    // Python reaches files through WASI, not the filesystem interface.
+    let python = &resolve.worlds[resolve.select_world(package, Some("python-guest")).unwrap()];
+    assert_eq!(
+        names(&resolve, python.imports.keys()),
+        ["clock", "fetch", "process", "types"]
+    );
+    assert!(python.exports.is_empty());
+}
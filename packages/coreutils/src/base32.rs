@@ -0,0 +1,8 @@
+//! base32 - base32 encode or decode data
+
+use crate::basenc::{self, BASE32};
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    basenc::run(&BASE32, args, io)
+}
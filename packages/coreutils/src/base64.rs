@@ -0,0 +1,8 @@
+//! base64 - base64 encode or decode data
+
+use crate::basenc::{self, BASE64};
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    basenc::run(&BASE64, args, io)
+}
@@ -0,0 +1,46 @@
+//! basename - strip directory and suffix from filenames
+
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    if args.is_empty() {
+        let _ = writeln!(io.stderr(), "basename: missing operand");
+        return 1;
+    }
+
+    let path = &args[0];
+    let suffix = if args.len() > 1 {
+        Some(args[1].as_str())
+    } else {
+        None
+    };
+
+    // Remove trailing slashes
+    let trimmed = path.trim_end_matches('/');
+
+    // If the entire string was slashes, result is "/"
+    if trimmed.is_empty() {
+        let _ = writeln!(io.stdout(), "/");
+        return 0;
+    }
+
+    // Find the last component
+    let base = match trimmed.rfind('/') {
+        Some(pos) => &trimmed[pos + 1..],
+        None => trimmed,
+    };
+
+    // Strip suffix if provided and if the name is longer than the suffix
+    let result = if let Some(sfx) = suffix {
+        if !sfx.is_empty() && base.len() > sfx.len() && base.ends_with(sfx) {
+            &base[..base.len() - sfx.len()]
+        } else {
+            base
+        }
+    } else {
+        base
+    };
+
+    let _ = writeln!(io.stdout(), "{}", result);
+    0
+}
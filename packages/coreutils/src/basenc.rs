@@ -0,0 +1,261 @@
+//! The encoding and option handling shared by base64 and base32.
+
+use std::io::{Read, Write};
+
+use crate::Io;
+
+/// A base-2^n encoding: each character carries `bits` bits, and output is
+/// padded with `=` to a whole number of `group` characters.
+pub struct Encoding {
+    pub name: &'static str,
+    pub alphabet: &'static [u8],
+    pub bits: u32,
+    pub group: usize,
+    /// The character counts a final, padded group can hold data for.
+    pub partial: &'static [usize],
+}
+
+pub const BASE64: Encoding = Encoding {
+    name: "base64",
+    alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+    bits: 6,
+    group: 4,
+    partial: &[2, 3],
+};
+
+pub const BASE32: Encoding = Encoding {
+    name: "base32",
+    alphabet: b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+    bits: 5,
+    group: 8,
+    partial: &[2, 4, 5, 7],
+};
+
+impl Encoding {
+    fn value(&self, c: u8) -> Option<u32> {
+        self.alphabet.iter().position(|&a| a == c).map(|v| v as u32)
+    }
+
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let mask = (1 << self.bits) - 1;
+        let mut output = Vec::with_capacity(input.len() * 8 / self.bits as usize + self.group);
+        let mut acc: u32 = 0;
+        let mut nbits = 0;
+        for &byte in input {
+            acc = (acc << 8) | byte as u32;
+            nbits += 8;
+            while nbits >= self.bits {
+                nbits -= self.bits;
+                output.push(self.alphabet[((acc >> nbits) & mask) as usize]);
+            }
+            acc &= (1 << nbits) - 1;
+        }
+        if nbits > 0 {
+            output.push(self.alphabet[((acc << (self.bits - nbits)) & mask) as usize]);
+        }
+        while output.len() % self.group != 0 {
+            output.push(b'=');
+        }
+        output
+    }
+
+    /// Decode `input`, skipping newlines, and any other character outside
+    /// the alphabet if `ignore_garbage`. On invalid input, returns what
+    /// decoded before it alongside the error.
+    fn decode(&self, input: &[u8], ignore_garbage: bool) -> Result<Vec<u8>, Vec<u8>> {
+        let mut output = Vec::with_capacity(input.len() * self.bits as usize / 8);
+        let mut acc: u32 = 0;
+        let mut nbits = 0;
+        // Characters seen in the current group, and how many were data.
+        let mut seen = 0;
+        let mut data = 0;
+        for &c in input {
+            if c == b'=' {
+                if seen == data && !self.partial.contains(&data) {
+                    return Err(output);
+                }
+                seen += 1;
+            } else if let Some(v) = self.value(c) {
+                if seen > data {
+                    // Data after padding in the same group.
+                    return Err(output);
+                }
+                acc = (acc << self.bits) | v;
+                nbits += self.bits;
+                if nbits >= 8 {
+                    nbits -= 8;
+                    output.push((acc >> nbits) as u8);
+                    acc &= (1 << nbits) - 1;
+                }
+                seen += 1;
+                data += 1;
+            } else if c == b'\n' || ignore_garbage {
+                continue;
+            } else {
+                return Err(output);
+            }
+            if seen == self.group {
+                seen = 0;
+                data = 0;
+                acc = 0;
+                nbits = 0;
+            }
+        }
+        if seen != 0 {
+            return Err(output);
+        }
+        Ok(output)
+    }
+}
+
+fn print_usage(encoding: &Encoding, stderr: &mut dyn Write) {
+    let name = encoding.name;
+    let _ = writeln!(stderr, "Usage: {name} [OPTION]... [FILE]");
+    let _ = writeln!(
+        stderr,
+        "{name} encode or decode FILE, or standard input, to standard output."
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(stderr, "  -d, --decode          decode data");
+    let _ = writeln!(
+        stderr,
+        "  -i, --ignore-garbage  when decoding, ignore non-alphabet characters"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -w, --wrap=COLS       wrap encoded lines after COLS characters (default 76)."
+    );
+    let _ = writeln!(
+        stderr,
+        "                        Use 0 to disable line wrapping"
+    );
+}
+
+/// Run `base64` or `base32`, as given by `encoding`.
+pub fn run(encoding: &Encoding, args: &[String], io: &mut dyn Io) -> i32 {
+    let name = encoding.name;
+    let mut decode = false;
+    let mut ignore_garbage = false;
+    let mut wrap: usize = 76;
+    let mut files: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let mut wrap_value = None;
+        match arg {
+            "--help" => {
+                print_usage(encoding, io.stderr());
+                return 0;
+            }
+            "--decode" => decode = true,
+            "--ignore-garbage" => ignore_garbage = true,
+            "--wrap" => {
+                i += 1;
+                match args.get(i) {
+                    Some(val) => wrap_value = Some(val.as_str()),
+                    None => {
+                        let _ =
+                            writeln!(io.stderr(), "{name}: option '--wrap' requires an argument");
+                        return 1;
+                    }
+                }
+            }
+            _ if arg.starts_with("--wrap=") => wrap_value = Some(&arg["--wrap=".len()..]),
+            "--" => {
+                files.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "{name}: unrecognized option '{arg}'");
+                return 1;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                // A cluster of short options, e.g. -di or -w0.
+                for (pos, c) in arg[1..].char_indices() {
+                    match c {
+                        'd' => decode = true,
+                        'i' => ignore_garbage = true,
+                        'w' => {
+                            let rest = &arg[1 + pos + 1..];
+                            if !rest.is_empty() {
+                                wrap_value = Some(rest);
+                            } else {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(val) => wrap_value = Some(val.as_str()),
+                                    None => {
+                                        let _ = writeln!(
+                                            io.stderr(),
+                                            "{name}: option requires an argument -- 'w'"
+                                        );
+                                        return 1;
+                                    }
+                                }
+                            }
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "{name}: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => files.push(arg),
+        }
+        if let Some(val) = wrap_value {
+            match val.parse::<usize>() {
+                Ok(n) => wrap = n,
+                Err(_) => {
+                    let _ = writeln!(io.stderr(), "{name}: invalid wrap size: '{val}'");
+                    return 1;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if files.len() > 1 {
+        let _ = writeln!(io.stderr(), "{name}: extra operand '{}'", files[1]);
+        return 1;
+    }
+    let file = files.first().copied().unwrap_or("-");
+
+    let mut input = Vec::new();
+    let read = if file == "-" {
+        io.stdin()
+    } else {
+        io.open(file)
+    }
+    .and_then(|mut reader| reader.read_to_end(&mut input));
+    if let Err(e) = read {
+        let _ = writeln!(io.stderr(), "{name}: {file}: {e}");
+        return 1;
+    }
+
+    if decode {
+        let (decoded, ok) = match encoding.decode(&input, ignore_garbage) {
+            Ok(decoded) => (decoded, true),
+            Err(decoded) => (decoded, false),
+        };
+        let _ = io.stdout().write_all(&decoded);
+        if !ok {
+            let _ = writeln!(io.stderr(), "{name}: invalid input");
+            return 1;
+        }
+        return 0;
+    }
+
+    let encoded = encoding.encode(&input);
+    let out = io.stdout();
+    if wrap == 0 {
+        let _ = out.write_all(&encoded);
+    } else {
+        for line in encoded.chunks(wrap) {
+            let _ = out.write_all(line);
+            let _ = out.write_all(b"\n");
+        }
+    }
+    0
+}
@@ -1,4 +1,4 @@
-fn main() {
+pub fn main() {
     // In WASM sandbox, always report wasm32
     println!("wasm32");
 }
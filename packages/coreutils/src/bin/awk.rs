@@ -6,7 +6,6 @@
 
 use regex::Regex;
 use std::collections::HashMap;
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::process;
@@ -1886,8 +1885,8 @@ fn read_input(input: &mut dyn Read) -> Vec<String> {
     reader.lines().map_while(Result::ok).collect()
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut fs = " ".to_string();
     let mut program: Option<String> = None;
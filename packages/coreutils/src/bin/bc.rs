@@ -1784,8 +1784,8 @@ fn run(input: &str, math_lib: bool) {
     let _ = out.flush();
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     let mut math_lib = false;
     let mut files: Vec<String> = Vec::new();
 
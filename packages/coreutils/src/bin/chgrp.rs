@@ -2,7 +2,7 @@
 
 use std::process;
 
-fn main() {
+pub fn main() {
     eprintln!("chgrp: operation not permitted in sandbox");
     process::exit(1);
 }
@@ -1,6 +1,5 @@
 //! cksum - compute CRC-32 checksum and byte count (POSIX)
 
-use std::env;
 use std::fs::File;
 use std::io::{self, Read};
 use std::process;
@@ -70,8 +69,8 @@ fn cksum_reader<R: Read>(mut reader: R, name: &str) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: cksum [FILE...]");
@@ -1,12 +1,11 @@
 //! cmp - compare two files byte by byte
 
-use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: cmp [OPTIONS] FILE1 FILE2");
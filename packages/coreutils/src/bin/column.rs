@@ -104,9 +104,20 @@ fn format_table(lines: &[String], separator: &str, output_sep: &str) -> Vec<Stri
     result
 }
 
+/// Width to fill: `$COLUMNS`, else the width of the terminal stdout is
+/// on, else 80.
+fn terminal_width() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .filter(|&w| w > 0)
+        .or_else(|| codepod_process::terminal_size(1).map(|(cols, _)| cols as usize))
+        .unwrap_or(80)
+}
+
 fn format_columns(lines: &[String]) -> Vec<String> {
     // Fill columns like `ls`
-    let term_width = 80;
+    let term_width = terminal_width();
 
     let max_len = lines.iter().map(|l| l.len()).max().unwrap_or(0);
     if max_len == 0 {
@@ -138,8 +149,8 @@ fn format_columns(lines: &[String]) -> Vec<String> {
     result
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: column [OPTIONS] [FILE...]");
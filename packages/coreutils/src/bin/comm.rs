@@ -1,12 +1,11 @@
 //! comm - compare two sorted files line by line
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: comm [-1] [-2] [-3] FILE1 FILE2");
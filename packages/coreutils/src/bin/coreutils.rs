@@ -0,0 +1,176 @@
+//! coreutils - every tool in one binary
+//!
+//! Run under a tool's name (a host pointing `cat` at this binary) it is
+//! that tool; otherwise its first argument names the tool, as in
+//! `coreutils cat -n file`.
+
+use std::process;
+
+use codepod_coreutils::{Applet, APPLETS};
+
+/// Declare each tool's `src/bin` program as a module and list its `main`.
+macro_rules! tools {
+    ($($name:literal => $module:ident,)*) => {
+        $(mod $module;)*
+
+        /// The tools not in [`APPLETS`], by name.
+        const TOOLS: &[(&str, fn())] = &[$(($name, $module::main),)*];
+    };
+}
+
+tools! {
+    "arch" => arch,
+    "awk" => awk,
+    "bc" => bc,
+    "chgrp" => chgrp,
+    "chown" => chown,
+    "cksum" => cksum,
+    "cmp" => cmp,
+    "column" => column,
+    "comm" => comm,
+    "csplit" => csplit,
+    "cut" => cut,
+    "dc" => dc,
+    "dd" => dd,
+    "df" => df,
+    "diff" => diff,
+    "du" => du,
+    "echo" => echo,
+    "env" => env,
+    "expand" => expand,
+    "expr" => expr,
+    "factor" => factor,
+    "false" => r#false,
+    "find" => find,
+    "fold" => fold,
+    "groups" => groups,
+    "gzip" => gzip,
+    "hostid" => hostid,
+    "hostname" => hostname,
+    "id" => id,
+    "join" => join,
+    "jq" => jq,
+    "link" => link,
+    "ln" => ln,
+    "logname" => logname,
+    "ls" => ls,
+    "mkdir" => mkdir,
+    "mktemp" => mktemp,
+    "mv" => mv,
+    "nice" => nice,
+    "nl" => nl,
+    "nohup" => nohup,
+    "nproc" => nproc,
+    "numfmt" => numfmt,
+    "od" => od,
+    "paste" => paste,
+    "patch" => patch,
+    "printenv" => printenv,
+    "readlink" => readlink,
+    "realpath" => realpath,
+    "rg" => rg,
+    "rm" => rm,
+    "rmdir" => rmdir,
+    "seq" => seq,
+    "shuf" => shuf,
+    "sleep" => sleep,
+    "sort" => sort,
+    "split" => split,
+    "stat" => stat,
+    "strings" => strings,
+    "sudo" => sudo,
+    "sum" => sum,
+    "tail" => tail,
+    "tar" => tar,
+    "tee" => tee,
+    "timeout" => timeout,
+    "touch" => touch,
+    "tr" => tr,
+    "tree" => tree,
+    "true" => r#true,
+    "truncate" => truncate,
+    "tsort" => tsort,
+    "uname" => uname,
+    "unexpand" => unexpand,
+    "uniq" => uniq,
+    "unlink" => unlink,
+    "unzip" => unzip,
+    "uptime" => uptime,
+    "users" => users,
+    "who" => who,
+    "whoami" => whoami,
+    "xargs" => xargs,
+    "yes" => yes,
+    "zip" => zip,
+}
+
+/// Other names for tools, which tell by their `argv[0]` how they were run.
+const ALIASES: &[(&str, &str)] = &[("gunzip", "gzip")];
+
+enum Tool {
+    Applet(Applet),
+    Main(fn()),
+}
+
+fn find(name: &str) -> Option<Tool> {
+    let name = ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |&(_, tool)| tool);
+    if let Some(applet) = codepod_coreutils::lookup(name) {
+        return Some(Tool::Applet(applet));
+    }
+    TOOLS
+        .iter()
+        .find(|(tool, _)| *tool == name)
+        .map(|&(_, main)| Tool::Main(main))
+}
+
+fn names() -> Vec<&'static str> {
+    let mut names: Vec<&str> = APPLETS
+        .iter()
+        .map(|(name, _)| *name)
+        .chain(TOOLS.iter().map(|(name, _)| *name))
+        .chain(ALIASES.iter().map(|(alias, _)| *alias))
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+fn usage() {
+    println!("Usage: coreutils TOOL [ARG]...");
+    println!("   or: TOOL [ARG]...  (this binary run under the tool's name)");
+    println!();
+    println!("Run one of the tools. coreutils --list prints their names.");
+}
+
+fn main() {
+    let argv0 = std::env::args().next().unwrap_or_default();
+    let mut name = argv0.rsplit('/').next().unwrap_or_default().to_string();
+    if find(&name).is_none() {
+        match std::env::args().nth(1).as_deref() {
+            None | Some("--help") => {
+                usage();
+                return;
+            }
+            Some("--list") => {
+                for name in names() {
+                    println!("{name}");
+                }
+                return;
+            }
+            Some(tool) => {
+                codepod_coreutils::skip_args(1);
+                name = tool.to_string();
+            }
+        }
+    }
+    match find(&name) {
+        Some(Tool::Applet(applet)) => codepod_coreutils::run_main(applet),
+        Some(Tool::Main(main)) => main(),
+        None => {
+            eprintln!("coreutils: {name}: applet not found");
+            process::exit(127);
+        }
+    }
+}
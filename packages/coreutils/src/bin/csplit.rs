@@ -1,7 +1,6 @@
 //! csplit - split a file into sections determined by context lines
 
 use regex::Regex;
-use std::env;
 use std::fs;
 use std::io::Write;
 use std::process;
@@ -62,8 +61,8 @@ fn parse_patterns(args: &[String]) -> Vec<Pattern> {
     patterns
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: csplit [OPTIONS] FILE PATTERN...");
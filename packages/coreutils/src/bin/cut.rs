@@ -1,6 +1,5 @@
 //! cut - remove sections from each line of files
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::process;
@@ -126,8 +125,8 @@ fn process_reader<R: BufRead>(
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut delimiter = '\t';
     let mut mode: Option<Mode> = None;
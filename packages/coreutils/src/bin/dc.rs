@@ -591,8 +591,8 @@ fn execute(tokens: &[String], state: &mut DcState, out: &mut BufWriter<io::Stdou
     }
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
 
     let input = if args.len() >= 2 && args[0] == "-e" {
         args[1..].join(" ")
@@ -1,6 +1,5 @@
 //! dd - convert and copy a file
 
-use std::env;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::process;
@@ -86,7 +85,7 @@ fn parse_args() -> Result<DdOptions, String> {
 
     let mut bs_set = false;
 
-    for arg in env::args().skip(1) {
+    for arg in codepod_coreutils::args().skip(1) {
         if let Some((key, val)) = arg.split_once('=') {
             match key {
                 "if" => opts.input_file = Some(val.to_string()),
@@ -320,6 +319,6 @@ fn read_full(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
     Ok(total)
 }
 
-fn main() {
+pub fn main() {
     process::exit(run());
 }
@@ -28,8 +28,8 @@ fn human_size(bytes: u64) -> String {
     }
 }
 
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let human = args.iter().any(|a| a == "-h" || a == "--human-readable");
 
     let json = match fs::read_to_string("/proc/diskstats") {
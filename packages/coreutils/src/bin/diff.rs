@@ -1,7 +1,7 @@
-use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // ---------------------------------------------------------------------------
 // Options
@@ -9,6 +9,9 @@ use std::process;
 
 struct Options {
     unified: bool,
+    side_by_side: bool,
+    width: usize, // -W: total columns of side-by-side output
+    suppress_common: bool,
     context_lines: usize, // default 3 for -u
     brief: bool,
     ignore_all_space: bool,
@@ -17,12 +20,22 @@ struct Options {
     ignore_case: bool,
     label1: Option<String>,
     label2: Option<String>,
+    recursive: bool,
+    new_file: bool, // -N: a file missing on one side compares as empty
+    text: bool,     // -a: compare binary files line by line too
+    exclude: Vec<String>,
+    /// The options as given, echoed in the `diff` line that heads each pair
+    /// of files compared inside directories.
+    switches: Vec<String>,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             unified: false,
+            side_by_side: false,
+            width: 130,
+            suppress_common: false,
             context_lines: 3,
             brief: false,
             ignore_all_space: false,
@@ -31,6 +44,11 @@ impl Default for Options {
             ignore_case: false,
             label1: None,
             label2: None,
+            recursive: false,
+            new_file: false,
+            text: false,
+            exclude: Vec::new(),
+            switches: Vec::new(),
         }
     }
 }
@@ -153,7 +171,7 @@ fn backtrack(
             } else {
                 // Delete
                 x -= 1;
-                ops.push((Op::Delete, x, 0));
+                ops.push((Op::Delete, x, y));
             }
         }
 
@@ -333,25 +351,46 @@ fn output_unified(out: &mut dyn Write, hunks: &[Hunk], label1: &str, label2: &st
     for hunk in hunks {
         let _ = writeln!(
             out,
-            "@@ -{},{} +{},{} @@",
-            hunk.start1, hunk.count1, hunk.start2, hunk.count2
+            "@@ -{} +{} @@",
+            unified_range(hunk.start1, hunk.count1),
+            unified_range(hunk.start2, hunk.count2)
         );
         for (op, line) in &hunk.lines {
-            match op {
-                Op::Equal => {
-                    let _ = writeln!(out, " {line}");
-                }
-                Op::Delete => {
-                    let _ = writeln!(out, "-{line}");
-                }
-                Op::Insert => {
-                    let _ = writeln!(out, "+{line}");
-                }
-            }
+            let prefix = match op {
+                Op::Equal => " ",
+                Op::Delete => "-",
+                Op::Insert => "+",
+            };
+            write_line(out, prefix, line);
+        }
+    }
+}
+
+/// Write a line after `prefix`. Lines keep their newline, so one without
+/// it is the last line of a file that doesn't end in a newline, which GNU
+/// diff notes on a line of its own.
+fn write_line(out: &mut dyn Write, prefix: &str, line: &str) {
+    match line.strip_suffix('\n') {
+        Some(text) => {
+            let _ = writeln!(out, "{prefix}{text}");
+        }
+        None => {
+            let _ = writeln!(out, "{prefix}{line}");
+            let _ = writeln!(out, "\\ No newline at end of file");
         }
     }
 }
 
+/// A hunk's range as GNU diff writes it: the count is left out when it is
+/// 1, and an empty range names the line before it.
+fn unified_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start - 1),
+        1 => format!("{start}"),
+        _ => format!("{start},{count}"),
+    }
+}
+
 fn output_normal(out: &mut dyn Write, ops: &[(Op, usize, usize)], a: &[&str], b: &[&str]) {
     // Group consecutive operations into change blocks
     let mut i = 0;
@@ -380,11 +419,11 @@ fn output_normal(out: &mut dyn Write, ops: &[(Op, usize, usize)], a: &[&str], b:
             let i_range = format_range(&inserts);
             let _ = writeln!(out, "{d_range}c{i_range}");
             for &d in &deletes {
-                let _ = writeln!(out, "< {}", a[d]);
+                write_line(out, "< ", a[d]);
             }
             let _ = writeln!(out, "---");
             for &ins in &inserts {
-                let _ = writeln!(out, "> {}", b[ins]);
+                write_line(out, "> ", b[ins]);
             }
         } else if !deletes.is_empty() {
             // Delete
@@ -402,7 +441,7 @@ fn output_normal(out: &mut dyn Write, ops: &[(Op, usize, usize)], a: &[&str], b:
             };
             let _ = writeln!(out, "{}d{}", d_range, after);
             for &d in &deletes {
-                let _ = writeln!(out, "< {}", a[d]);
+                write_line(out, "< ", a[d]);
             }
         } else if !inserts.is_empty() {
             // Add
@@ -419,10 +458,135 @@ fn output_normal(out: &mut dyn Write, ops: &[(Op, usize, usize)], a: &[&str], b:
             };
             let _ = writeln!(out, "{}a{}", after, i_range);
             for &ins in &inserts {
-                let _ = writeln!(out, "> {}", b[ins]);
+                write_line(out, "> ", b[ins]);
+            }
+        }
+    }
+}
+
+/// Side-by-side output: each line of the first file in the left column,
+/// its counterpart in the right, and a gutter marking changed (`|`),
+/// deleted (`<`) and inserted (`>`) lines. Columns are padded with tabs,
+/// as GNU diff does.
+fn output_side_by_side(
+    out: &mut dyn Write,
+    ops: &[(Op, usize, usize)],
+    a: &[&str],
+    b: &[&str],
+    opts: &Options,
+) {
+    // The right column starts at the tab stop nearest the middle, leaving
+    // at least three columns for the gutter.
+    let offset = (opts.width + 8 + 3) / 16 * 8;
+    let half = offset
+        .saturating_sub(3)
+        .min(opts.width.saturating_sub(offset));
+    let column2 = if half > 0 { offset } else { opts.width };
+    let layout = (half, column2);
+    let mut i = 0;
+    while i < ops.len() {
+        if ops[i].0 == Op::Equal {
+            if !opts.suppress_common {
+                let (_, ai, bi) = ops[i];
+                side_by_side_line(out, layout, Some(a[ai]), ' ', Some(b[bi]));
+            }
+            i += 1;
+            continue;
+        }
+
+        let mut deletes: Vec<usize> = Vec::new();
+        let mut inserts: Vec<usize> = Vec::new();
+        while i < ops.len() && ops[i].0 != Op::Equal {
+            match ops[i].0 {
+                Op::Delete => deletes.push(ops[i].1),
+                Op::Insert => inserts.push(ops[i].2),
+                _ => {}
+            }
+            i += 1;
+        }
+        let paired = deletes.len().min(inserts.len());
+        for (&d, &ins) in deletes.iter().zip(&inserts) {
+            side_by_side_line(out, layout, Some(a[d]), '|', Some(b[ins]));
+        }
+        for &ins in &inserts[paired..] {
+            side_by_side_line(out, layout, None, '>', Some(b[ins]));
+        }
+        for &d in &deletes[paired..] {
+            side_by_side_line(out, layout, Some(a[d]), '<', None);
+        }
+    }
+}
+
+/// Write one row of side-by-side output. `layout` is the width of each
+/// half and the column where the right half starts.
+fn side_by_side_line(
+    out: &mut dyn Write,
+    layout: (usize, usize),
+    left: Option<&str>,
+    gutter: char,
+    right: Option<&str>,
+) {
+    let (half, column2) = layout;
+    let left = left.map(|line| line.strip_suffix('\n').unwrap_or(line));
+    let right = right.map(|line| line.strip_suffix('\n').unwrap_or(line));
+    let mut row = String::new();
+    let mut col = left.map_or(0, |line| push_half_line(&mut row, line, half));
+    if gutter != ' ' {
+        col = pad_to(&mut row, col, (half + column2 - 1) / 2) + 1;
+        row.push(gutter);
+    }
+    if let Some(line) = right.filter(|line| !line.is_empty()) {
+        pad_to(&mut row, col, column2);
+        push_half_line(&mut row, line, half);
+    }
+    let _ = writeln!(out, "{row}");
+}
+
+/// Append as much of `line` as fits in `width` columns, keeping its tabs,
+/// and return the columns used.
+fn push_half_line(row: &mut String, line: &str, width: usize) -> usize {
+    let mut in_col = 0;
+    let mut out_col = 0;
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let stop = in_col + 8 - in_col % 8;
+                if in_col == out_col && stop < width {
+                    row.push('\t');
+                    out_col = stop;
+                }
+                in_col = stop;
+            }
+            '\r' => {
+                row.push(c);
+                in_col = 0;
+                out_col = 0;
+            }
+            _ => {
+                if in_col < width {
+                    row.push(c);
+                    out_col += 1;
+                }
+                in_col += 1;
             }
         }
     }
+    out_col
+}
+
+/// Pad from column `from` to column `to` with tabs, then spaces.
+fn pad_to(row: &mut String, mut from: usize, to: usize) -> usize {
+    let mut stop = from + 8 - from % 8;
+    while stop <= to {
+        row.push('\t');
+        from = stop;
+        stop += 8;
+    }
+    while from < to {
+        row.push(' ');
+        from += 1;
+    }
+    to
 }
 
 fn format_range(indices: &[usize]) -> String {
@@ -433,19 +597,321 @@ fn format_range(indices: &[usize]) -> String {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Glob matching for --exclude (supports *, ?, and [abc] character classes)
+// ---------------------------------------------------------------------------
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    glob_match_inner(&p, &t)
+}
+
+fn glob_match_inner(pattern: &[char], text: &[char]) -> bool {
+    if pattern.is_empty() {
+        return text.is_empty();
+    }
+    match pattern[0] {
+        '*' => (0..=text.len()).any(|i| glob_match_inner(&pattern[1..], &text[i..])),
+        '[' if !text.is_empty() => match pattern.iter().position(|&c| c == ']') {
+            Some(end) => {
+                let class = &pattern[1..end];
+                let ch = text[0];
+                let mut matched = false;
+                let mut ci = 0;
+                while ci < class.len() {
+                    if ci + 2 < class.len() && class[ci + 1] == '-' {
+                        matched |= ch >= class[ci] && ch <= class[ci + 2];
+                        ci += 3;
+                    } else {
+                        matched |= ch == class[ci];
+                        ci += 1;
+                    }
+                }
+                matched && glob_match_inner(&pattern[end + 1..], &text[1..])
+            }
+            // No closing ']', treat '[' as literal
+            None => text[0] == '[' && glob_match_inner(&pattern[1..], &text[1..]),
+        },
+        '?' => !text.is_empty() && glob_match_inner(&pattern[1..], &text[1..]),
+        c => !text.is_empty() && text[0] == c && glob_match_inner(&pattern[1..], &text[1..]),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Stdin support: read from stdin when path is "-"
 // ---------------------------------------------------------------------------
 
-fn read_input(path: &str) -> Result<String, String> {
+fn read_input(path: &str, opts: &Options) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
     if path == "-" {
-        let mut buf = String::new();
         io::stdin()
-            .read_to_string(&mut buf)
+            .read_to_end(&mut buf)
             .map_err(|e| format!("diff: stdin: {e}"))?;
-        Ok(buf)
+        return Ok(buf);
+    }
+    match fs::read(path) {
+        Ok(data) => Ok(data),
+        Err(e) if opts.new_file && e.kind() == io::ErrorKind::NotFound => Ok(buf),
+        Err(e) => Err(format!("diff: {path}: {e}")),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Comparing files and directories
+// ---------------------------------------------------------------------------
+
+/// Compare two files' contents, naming them `name1` and `name2`, or
+/// `labels` in unified headers. With `header`, a `diff` line naming both
+/// comes before the differences, as when comparing inside directories.
+/// Returns the exit status.
+fn diff_contents(
+    out: &mut dyn Write,
+    names: (&str, &str),
+    labels: (&str, &str),
+    data1: &[u8],
+    data2: &[u8],
+    opts: &Options,
+    header: bool,
+) -> i32 {
+    let (name1, name2) = names;
+    if !opts.text && (data1.contains(&0) || data2.contains(&0)) {
+        if data1 == data2 {
+            return 0;
+        }
+        let kind = if opts.brief { "Files" } else { "Binary files" };
+        let _ = writeln!(out, "{kind} {name1} and {name2} differ");
+        return 1;
+    }
+
+    let content1 = String::from_utf8_lossy(data1);
+    let content2 = String::from_utf8_lossy(data2);
+    // Lines keep their newlines, so that a last line without one differs
+    // from the same text with one.
+    let mut lines1: Vec<&str> = content1.split_inclusive('\n').collect();
+    let mut lines2: Vec<&str> = content2.split_inclusive('\n').collect();
+
+    // Filter blank lines if requested
+    if opts.ignore_blank_lines {
+        lines1.retain(|l| !l.trim().is_empty());
+        lines2.retain(|l| !l.trim().is_empty());
+    }
+
+    // Quick check: identical. Side-by-side output still shows the lines.
+    let identical = lines1.len() == lines2.len()
+        && lines1
+            .iter()
+            .zip(lines2.iter())
+            .all(|(a, b)| lines_equal(a, b, opts));
+    if identical && (opts.brief || !opts.side_by_side) {
+        return 0;
+    }
+
+    if opts.brief {
+        let _ = writeln!(out, "Files {name1} and {name2} differ");
+        return 1;
+    }
+
+    let ops = myers_diff(&lines1, &lines2, opts);
+
+    // Check if there are any actual changes
+    let has_changes = ops.iter().any(|&(op, _, _)| op != Op::Equal);
+    if !has_changes && (!opts.side_by_side || opts.suppress_common) {
+        return 0;
+    }
+
+    if header {
+        let mut command = String::from("diff");
+        for switch in &opts.switches {
+            command.push(' ');
+            command.push_str(&shell_quote(switch));
+        }
+        let _ = writeln!(out, "{command} {name1} {name2}");
+    }
+
+    if opts.unified {
+        let hunks = group_hunks(&ops, &lines1, &lines2, opts.context_lines);
+        output_unified(out, &hunks, labels.0, labels.1);
+    } else if opts.side_by_side {
+        output_side_by_side(out, &ops, &lines1, &lines2, opts);
     } else {
-        fs::read_to_string(path).map_err(|e| format!("diff: {path}: {e}"))
+        output_normal(out, &ops, &lines1, &lines2);
+    }
+    i32::from(has_changes)
+}
+
+/// Read and compare two files, reporting them under `names`.
+fn diff_files(
+    out: &mut dyn Write,
+    paths: (&str, &str),
+    names: (&str, &str),
+    opts: &Options,
+    header: bool,
+) -> i32 {
+    let mut data = Vec::new();
+    for path in [paths.0, paths.1] {
+        match read_input(path, opts) {
+            Ok(d) => data.push(d),
+            Err(e) => {
+                eprintln!("{e}");
+                return 2;
+            }
+        }
+    }
+    // Unified headers give each file's modification time after a tab, as
+    // GNU diff does, unless --label named it.
+    let label = |name: &str, path: &str, given: &Option<String>| {
+        if opts.unified && given.is_none() {
+            format!("{name}\t{}", timestamp(path))
+        } else {
+            name.to_string()
+        }
+    };
+    let label1 = label(names.0, paths.0, &opts.label1);
+    let label2 = label(names.1, paths.1, &opts.label2);
+    diff_contents(
+        out,
+        names,
+        (&label1, &label2),
+        &data[0],
+        &data[1],
+        opts,
+        header,
+    )
+}
+
+/// A file's modification time as GNU diff writes it. A file missing under
+/// -N gets the epoch, which tells patch that it is created or removed.
+fn timestamp(path: &str) -> String {
+    let modified = if path == "-" {
+        Ok(SystemTime::now())
+    } else {
+        fs::metadata(path).and_then(|m| m.modified())
+    };
+    let since = modified
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    let secs = since.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let time = secs % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:09} +0000",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        since.subsec_nanos()
+    )
+}
+
+/// The (year, month, day) that falls `days` days after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Compare two directories entry by entry, descending into subdirectories
+/// with `-r`. With `-N` either may be missing, standing for an empty one.
+fn diff_dirs(out: &mut dyn Write, dir1: &str, dir2: &str, opts: &Options) -> i32 {
+    let mut names = Vec::new();
+    for dir in [dir1, dir2] {
+        match fs::read_dir(dir) {
+            Ok(entries) => names.extend(
+                entries
+                    .flatten()
+                    .map(|e| e.file_name().to_string_lossy().into_owned()),
+            ),
+            Err(e) if opts.new_file && e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                eprintln!("diff: {dir}: {e}");
+                return 2;
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names.retain(|name| !opts.exclude.iter().any(|p| glob_match(p, name)));
+
+    let mut status = 0;
+    for name in &names {
+        let path1 = join_path(dir1, name);
+        let path2 = join_path(dir2, name);
+        let paths = (path1.as_str(), path2.as_str());
+        let meta1 = fs::metadata(&path1).ok();
+        let meta2 = fs::metadata(&path2).ok();
+        let is_dir1 = meta1.as_ref().is_some_and(|m| m.is_dir());
+        let is_dir2 = meta2.as_ref().is_some_and(|m| m.is_dir());
+        let result = match (&meta1, &meta2) {
+            (Some(_), Some(_)) if is_dir1 && is_dir2 => {
+                if opts.recursive {
+                    diff_dirs(out, &path1, &path2, opts)
+                } else {
+                    let _ = writeln!(out, "Common subdirectories: {path1} and {path2}");
+                    0
+                }
+            }
+            (Some(_), Some(_)) if is_dir1 || is_dir2 => {
+                let (kind1, kind2) = if is_dir1 {
+                    ("directory", "regular file")
+                } else {
+                    ("regular file", "directory")
+                };
+                let _ = writeln!(
+                    out,
+                    "File {path1} is a {kind1} while file {path2} is a {kind2}"
+                );
+                1
+            }
+            (Some(_), Some(_)) => diff_files(out, paths, paths, opts, true),
+            (Some(_), None) | (None, Some(_)) if opts.new_file => {
+                if !(is_dir1 || is_dir2) {
+                    diff_files(out, paths, paths, opts, true)
+                } else if opts.recursive {
+                    diff_dirs(out, &path1, &path2, opts)
+                } else {
+                    let dir = if is_dir1 { dir1 } else { dir2 };
+                    let _ = writeln!(out, "Only in {dir}: {name}");
+                    1
+                }
+            }
+            (Some(_), None) => {
+                let _ = writeln!(out, "Only in {dir1}: {name}");
+                1
+            }
+            (None, Some(_)) => {
+                let _ = writeln!(out, "Only in {dir2}: {name}");
+                1
+            }
+            (None, None) => 0,
+        };
+        status = status.max(result);
+    }
+    status
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{dir}{name}")
+    } else {
+        format!("{dir}/{name}")
+    }
+}
+
+/// Quote an argument for the `diff` header line if the shell would need it.
+/// Like GNU, `=` counts as special, so `--exclude=x` is quoted.
+fn shell_quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:,+@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
     }
 }
 
@@ -453,13 +919,14 @@ fn read_input(path: &str) -> Result<String, String> {
 // Main
 // ---------------------------------------------------------------------------
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     let mut opts = Options::default();
     let mut paths: Vec<String> = Vec::new();
 
     let mut i = 0;
     while i < args.len() {
+        let first = i;
         let arg = &args[i];
         match arg.as_str() {
             "-u" | "--unified" => {
@@ -480,6 +947,34 @@ fn main() {
             "-i" | "--ignore-case" => {
                 opts.ignore_case = true;
             }
+            "-y" | "--side-by-side" => {
+                opts.side_by_side = true;
+            }
+            "--suppress-common-lines" => {
+                opts.suppress_common = true;
+            }
+            "-W" | "--width" => {
+                i += 1;
+                match args.get(i) {
+                    Some(value) => opts.width = parse_width(value),
+                    None => {
+                        eprintln!("diff: option '{arg}' requires an argument");
+                        process::exit(2);
+                    }
+                }
+            }
+            _ if arg.starts_with("--width=") => {
+                opts.width = parse_width(&arg["--width=".len()..]);
+            }
+            "-r" | "--recursive" => {
+                opts.recursive = true;
+            }
+            "-N" | "--new-file" => {
+                opts.new_file = true;
+            }
+            "-a" | "--text" => {
+                opts.text = true;
+            }
             "--label" => {
                 i += 1;
                 if i < args.len() {
@@ -490,6 +985,24 @@ fn main() {
                     }
                 }
             }
+            "-x" | "--exclude" | "-X" | "--exclude-from" => {
+                i += 1;
+                if i >= args.len() {
+                    eprintln!("diff: option '{arg}' requires an argument");
+                    process::exit(2);
+                }
+                add_exclude(
+                    &mut opts,
+                    arg.starts_with("-X") || arg == "--exclude-from",
+                    &args[i],
+                );
+            }
+            _ if arg.starts_with("--exclude=") => {
+                add_exclude(&mut opts, false, &arg["--exclude=".len()..]);
+            }
+            _ if arg.starts_with("--exclude-from=") => {
+                add_exclude(&mut opts, true, &arg["--exclude-from=".len()..]);
+            }
             _ if arg.starts_with("-U") => {
                 let val = &arg[2..];
                 if let Ok(n) = val.parse::<usize>() {
@@ -505,8 +1018,9 @@ fn main() {
                 }
             }
             _ if arg.starts_with('-') && arg != "-" => {
-                // Parse combined single-char flags like -ubw
-                for ch in arg[1..].chars() {
+                // Parse combined single-char flags like -ubw; x and X take
+                // the rest of the group, or else the next argument
+                for (at, ch) in arg.char_indices().skip(1) {
                     match ch {
                         'u' => opts.unified = true,
                         'q' => opts.brief = true,
@@ -514,6 +1028,40 @@ fn main() {
                         'b' => opts.ignore_space_change = true,
                         'B' => opts.ignore_blank_lines = true,
                         'i' => opts.ignore_case = true,
+                        'y' => opts.side_by_side = true,
+                        'r' => opts.recursive = true,
+                        'N' => opts.new_file = true,
+                        'a' => opts.text = true,
+                        'W' => {
+                            let mut value = arg[at + 1..].to_string();
+                            if value.is_empty() {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(next) => value = next.clone(),
+                                    None => {
+                                        eprintln!("diff: option requires an argument -- 'W'");
+                                        process::exit(2);
+                                    }
+                                }
+                            }
+                            opts.width = parse_width(&value);
+                            break;
+                        }
+                        'x' | 'X' => {
+                            let mut value = arg[at + 1..].to_string();
+                            if value.is_empty() {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(next) => value = next.clone(),
+                                    None => {
+                                        eprintln!("diff: option requires an argument -- '{ch}'");
+                                        process::exit(2);
+                                    }
+                                }
+                            }
+                            add_exclude(&mut opts, ch == 'X', &value);
+                            break;
+                        }
                         _ => {
                             eprintln!("diff: invalid option -- '{ch}'");
                             process::exit(2);
@@ -523,76 +1071,85 @@ fn main() {
             }
             _ => {
                 paths.push(arg.clone());
+                i += 1;
+                continue;
             }
         }
+        opts.switches.extend(args[first..=i].iter().cloned());
         i += 1;
     }
 
-    if paths.len() != 2 {
-        eprintln!("diff: usage: diff [OPTIONS] FILE1 FILE2");
+    if opts.unified && opts.side_by_side {
+        eprintln!("diff: conflicting output style options");
         process::exit(2);
     }
 
-    let content1 = match read_input(&paths[0]) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("{e}");
-            process::exit(2);
-        }
-    };
-    let content2 = match read_input(&paths[1]) {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("{e}");
-            process::exit(2);
-        }
-    };
-
-    let mut lines1: Vec<&str> = content1.lines().collect();
-    let mut lines2: Vec<&str> = content2.lines().collect();
-
-    // Filter blank lines if requested
-    if opts.ignore_blank_lines {
-        lines1.retain(|l| !l.trim().is_empty());
-        lines2.retain(|l| !l.trim().is_empty());
-    }
-
-    // Quick check: identical
-    if lines1.len() == lines2.len()
-        && lines1
-            .iter()
-            .zip(lines2.iter())
-            .all(|(a, b)| lines_equal(a, b, &opts))
-    {
-        process::exit(0);
-    }
-
-    if opts.brief {
-        let label1 = opts.label1.as_deref().unwrap_or(&paths[0]);
-        let label2 = opts.label2.as_deref().unwrap_or(&paths[1]);
-        println!("Files {label1} and {label2} differ");
-        process::exit(1);
-    }
-
-    let ops = myers_diff(&lines1, &lines2, &opts);
-
-    // Check if there are any actual changes
-    let has_changes = ops.iter().any(|&(op, _, _)| op != Op::Equal);
-    if !has_changes {
-        process::exit(0);
+    if paths.len() != 2 {
+        eprintln!("diff: usage: diff [OPTIONS] FILE1 FILE2");
+        process::exit(2);
     }
 
     let stdout = io::stdout();
     let mut out = stdout.lock();
 
-    if opts.unified {
+    let is_dir = |p: &str| p != "-" && fs::metadata(p).is_ok_and(|m| m.is_dir());
+    let (dir1, dir2) = (is_dir(&paths[0]), is_dir(&paths[1]));
+    let status = if dir1 && dir2 {
+        diff_dirs(&mut out, &paths[0], &paths[1], &opts)
+    } else if dir1 || dir2 {
+        // A directory and a file: compare the file with its namesake in the
+        // directory.
+        let (dir, file) = if dir1 {
+            (&paths[0], &paths[1])
+        } else {
+            (&paths[1], &paths[0])
+        };
+        let base = file.rsplit('/').find(|c| !c.is_empty()).unwrap_or(file);
+        let inner = join_path(dir, base);
+        let paths = if dir1 {
+            (inner.as_str(), file.as_str())
+        } else {
+            (file.as_str(), inner.as_str())
+        };
+        diff_files(&mut out, paths, paths, &opts, false)
+    } else {
         let label1 = opts.label1.as_deref().unwrap_or(&paths[0]);
         let label2 = opts.label2.as_deref().unwrap_or(&paths[1]);
-        let hunks = group_hunks(&ops, &lines1, &lines2, opts.context_lines);
-        output_unified(&mut out, &hunks, label1, label2);
-    } else {
-        output_normal(&mut out, &ops, &lines1, &lines2);
+        diff_files(
+            &mut out,
+            (&paths[0], &paths[1]),
+            (label1, label2),
+            &opts,
+            false,
+        )
+    };
+    let _ = out.flush();
+    process::exit(status);
+}
+
+/// Add an --exclude pattern, or with `from_file` each line of a file.
+fn add_exclude(opts: &mut Options, from_file: bool, value: &str) {
+    if !from_file {
+        opts.exclude.push(value.to_string());
+        return;
     }
+    match fs::read_to_string(value) {
+        Ok(content) => opts
+            .exclude
+            .extend(content.lines().filter(|l| !l.is_empty()).map(String::from)),
+        Err(e) => {
+            eprintln!("diff: {value}: {e}");
+            process::exit(2);
+        }
+    }
+}
 
-    process::exit(1);
+fn parse_width(value: &str) -> usize {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => {
+            eprintln!("diff: invalid width '{value}'");
+            process::exit(2);
+        }
+    }
 }
@@ -1,6 +1,5 @@
 //! du - estimate file space usage
 
-use std::env;
 use std::fs;
 use std::path::Path;
 use std::process;
@@ -130,8 +129,8 @@ fn dir_total_size(path: &Path) -> u64 {
     total
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut opts = Options {
         summary: false,
         human: false,
@@ -1,6 +1,5 @@
 //! echo - display a line of text
 
-use std::env;
 use std::io::{self, Write};
 use std::process;
 
@@ -78,7 +77,7 @@ fn process_escapes(s: &str) -> (Vec<u8>, bool) {
 }
 
 fn run() -> i32 {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut trailing_newline = true;
     let mut interpret_escapes = false;
     let mut arg_start = 1;
@@ -139,6 +138,6 @@ fn run() -> i32 {
     0
 }
 
-fn main() {
+pub fn main() {
     process::exit(run());
 }
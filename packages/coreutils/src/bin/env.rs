@@ -2,11 +2,11 @@
 
 use std::env;
 
-fn main() {
+pub fn main() {
     // If no arguments (beyond program name), print all environment variables.
     // In a full implementation with args we would modify the environment and
     // exec a command, but under WASI exec is not available, so we just print.
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.len() <= 1 {
         // Print all environment variables
@@ -1,6 +1,5 @@
 //! expand - convert tabs to spaces
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read};
 use std::process;
@@ -34,8 +33,8 @@ fn expand_reader<R: Read>(reader: R, tab_stop: usize) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: expand [-t N] [FILE...]");
@@ -1,4 +1,3 @@
-use std::env;
 use std::process;
 
 /// Match STRING against anchored BRE REGEX.
@@ -234,8 +233,8 @@ fn simple_regex_match(input: &str, pattern: &str) -> Option<(String, Option<Stri
     })
 }
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     if args.is_empty() {
         eprintln!("expr: missing operand");
         process::exit(2);
@@ -1,5 +1,4 @@
 use std::{
-    env,
     io::{self, BufRead},
     process,
 };
@@ -38,8 +37,8 @@ fn process_number(s: &str) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     if args.is_empty() {
         // Read from stdin
         let stdin = io::stdin();
@@ -1,6 +1,5 @@
 //! find - search for files in a directory hierarchy
 
-use std::env;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
@@ -863,8 +862,8 @@ fn walk(
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     let (paths, min_depth, max_depth, expr) = parse_args(&args);
     let has_act = has_action(&expr);
 
@@ -1,6 +1,5 @@
 //! fold - wrap each input line to fit in specified width
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read};
 use std::process;
@@ -80,8 +79,8 @@ fn fold_line_spaces(line: &str, width: usize) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: fold [-w WIDTH] [-s] [FILE...]");
@@ -1,5 +1,5 @@
 //! groups - print group memberships
 
-fn main() {
+pub fn main() {
     println!("root");
 }
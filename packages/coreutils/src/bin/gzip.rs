@@ -4,7 +4,6 @@
 
 use flate2::read::{GzDecoder, GzEncoder};
 use flate2::Compression;
-use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::Path;
@@ -19,7 +18,7 @@ struct Options {
 }
 
 fn parse_args() -> Options {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     // Detect gunzip via argv[0]
     let prog = Path::new(&args[0])
@@ -82,7 +81,7 @@ fn decompress_stream<R: Read, W: Write>(input: &mut R, output: &mut W) -> io::Re
     Ok(())
 }
 
-fn main() {
+pub fn main() {
     let opts = parse_args();
 
     // No files: stdin/stdout mode
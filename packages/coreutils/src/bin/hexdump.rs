@@ -0,0 +1,5 @@
+//! hexdump - display file contents in hexadecimal
+
+fn main() {
+    codepod_coreutils::run_main(codepod_coreutils::hexdump::run);
+}
@@ -1,5 +1,5 @@
 //! hostid - print the numeric identifier for the current host
 
-fn main() {
+pub fn main() {
     println!("codepod00");
 }
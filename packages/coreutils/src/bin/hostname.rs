@@ -1,9 +1,7 @@
 //! hostname - print the system hostname
 
-use std::env;
-
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     if args.iter().any(|a| a == "--help") {
         println!("Usage: hostname [-f]");
         println!("Print the system hostname.");
@@ -1,3 +1,3 @@
-fn main() {
+pub fn main() {
     println!("uid=1000(user) gid=1000(user) groups=1000(user)");
 }
@@ -1,6 +1,5 @@
 //! join - join lines of two files on a common field
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::process;
@@ -40,8 +39,8 @@ fn get_other_fields(line: &str, field: usize, sep: &Option<char>) -> Vec<String>
     result
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: join [-t CHAR] [-1 FIELD] [-2 FIELD] FILE1 FILE2");
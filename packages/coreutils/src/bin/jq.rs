@@ -13,7 +13,6 @@
 //! object construction {key: value}, array construction [expr], comparison ops
 
 use std::collections::BTreeMap;
-use std::env;
 use std::io::{self, Read};
 
 // ---- JSON Value ----
@@ -2082,8 +2081,8 @@ fn base64_decode(s: &str) -> Vec<u8> {
 
 // ---- Main ----
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut filter_str = ".".to_string();
     let mut raw_output = false;
     let mut compact = false;
@@ -1,6 +1,6 @@
-use std::{env, fs, process};
-fn main() {
-    let args: Vec<String> = env::args().collect();
+use std::{fs, process};
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     if args.len() != 3 {
         eprintln!("link: missing operand");
         process::exit(1);
@@ -1,8 +1,7 @@
-use std::env;
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     let mut symbolic = false;
     let mut force = false;
     let mut paths: Vec<&str> = Vec::new();
@@ -1,5 +1,5 @@
 //! logname - print current login name
 
-fn main() {
+pub fn main() {
     println!("root");
 }
@@ -1,4 +1,3 @@
-use std::env;
 use std::fs;
 use std::path::Path;
 use std::process;
@@ -27,7 +26,7 @@ fn parse_args() -> (Options, Vec<String>) {
     };
     let mut paths = Vec::new();
 
-    for arg in env::args().skip(1) {
+    for arg in codepod_coreutils::args().skip(1) {
         if arg == "--" {
             break;
         }
@@ -310,7 +309,7 @@ fn list_dir(path: &Path, opts: &Options, show_header: bool) -> i32 {
     exit_code
 }
 
-fn main() {
+pub fn main() {
     let (opts, paths) = parse_args();
     let mut exit_code = 0;
     let show_header = paths.len() > 1 || opts.recursive;
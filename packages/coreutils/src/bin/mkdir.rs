@@ -1,14 +1,13 @@
-use std::env;
 use std::fs;
 use std::path::Path;
 use std::process;
 
-fn main() {
+pub fn main() {
     let mut create_parents = false;
     let mut dirs: Vec<String> = Vec::new();
 
     let mut skip_next = false;
-    for arg in env::args().skip(1) {
+    for arg in codepod_coreutils::args().skip(1) {
         if skip_next {
             skip_next = false;
             continue;
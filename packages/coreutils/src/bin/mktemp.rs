@@ -1,19 +1,65 @@
 use std::fs;
+use std::io;
 
-fn main() {
-    // Generate a pseudo-random temp filename.
-    // Use the address of a stack variable as entropy (not cryptographic, just unique).
-    let mut name = String::from("/tmp/tmp.");
-    let val: usize = 0;
-    let addr = &val as *const usize as usize;
-    for i in 0..8 {
-        let c = (((addr >> (i * 4)) & 0xF) as u8).wrapping_add(b'a');
-        name.push(c as char);
+const NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const DEFAULT_TEMPLATE: &str = "/tmp/tmp.XXXXXXXXXX";
+
+pub fn main() {
+    let mut make_dir = false;
+    let mut template: Option<String> = None;
+    for arg in codepod_coreutils::args().skip(1) {
+        if arg == "-d" {
+            make_dir = true;
+        } else if template.is_none() {
+            template = Some(arg);
+        } else {
+            eprintln!("mktemp: too many templates");
+            std::process::exit(1);
+        }
     }
-    // Create the file
-    if let Err(e) = fs::write(&name, "") {
-        eprintln!("mktemp: {e}");
+    let template = template.unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+    let xs = template.len() - template.trim_end_matches('X').len();
+    if xs < 3 {
+        eprintln!("mktemp: too few X's in template '{template}'");
         std::process::exit(1);
     }
-    println!("{name}");
+    let prefix = &template[..template.len() - xs];
+    let kind = if make_dir { "directory" } else { "file" };
+
+    // Pick a random name from the host's entropy, retrying on the rare
+    // collision with an existing file. Creation is exclusive, so a file
+    // made by someone else between attempts is never reused.
+    for _ in 0..100 {
+        let mut bytes = vec![0u8; xs];
+        if let Err(e) = codepod_process::random_bytes(&mut bytes) {
+            eprintln!("mktemp: {e}");
+            std::process::exit(1);
+        }
+        let mut name = prefix.to_string();
+        for b in bytes {
+            name.push(NAME_CHARS[b as usize % NAME_CHARS.len()] as char);
+        }
+        let created = if make_dir {
+            fs::create_dir(&name)
+        } else {
+            fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&name)
+                .map(drop)
+        };
+        match created {
+            Ok(()) => {
+                println!("{name}");
+                return;
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                eprintln!("mktemp: failed to create {kind} via template '{template}': {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+    eprintln!("mktemp: failed to create {kind} via template '{template}'");
+    std::process::exit(1);
 }
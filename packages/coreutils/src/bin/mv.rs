@@ -1,4 +1,3 @@
-use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
@@ -71,10 +70,10 @@ fn move_path(src: &Path, dst: &Path) -> Result<(), String> {
     }
 }
 
-fn main() {
+pub fn main() {
     let mut args: Vec<String> = Vec::new();
 
-    for arg in env::args().skip(1) {
+    for arg in codepod_coreutils::args().skip(1) {
         if arg == "--" {
             break;
         }
@@ -9,11 +9,10 @@
 //! Without a command, prints the current niceness (always 0 in WASM, because
 //! priority is set at sandbox-creation time by the host).
 
-use std::env;
 use codepod_process::Command;
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
 
     if args.is_empty() {
         println!("0");
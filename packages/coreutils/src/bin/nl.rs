@@ -1,6 +1,5 @@
 //! nl - number lines of files
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read};
 use std::process;
@@ -36,8 +35,8 @@ fn nl_reader<R: Read>(reader: R, numbering: &BodyNumbering, line_number: &mut us
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: nl [-b STYLE] [FILE...]");
@@ -1,10 +1,9 @@
 //! nohup - run a command immune to hangups (sandbox stub)
 
-use std::env;
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
 
     if args.is_empty() {
         eprintln!("usage: nohup COMMAND [ARG]...");
@@ -1,5 +1,5 @@
 //! nproc - print the number of processing units available
 
-fn main() {
+pub fn main() {
     println!("1");
 }
@@ -1,6 +1,5 @@
 //! numfmt - convert numbers from/to human-readable strings
 
-use std::env;
 use std::io::{self, BufRead, Write};
 use std::process;
 
@@ -92,8 +91,8 @@ fn format_human(val: f64, scale: &Scale) -> String {
     format!("{val}")
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: numfmt [OPTIONS] [NUMBER...]");
@@ -1,6 +1,5 @@
 //! od - octal dump
 
-use std::env;
 use std::fs::File;
 use std::io::{self, Read};
 use std::process;
@@ -151,8 +150,8 @@ fn dump_data(data: &[u8], output_type: &OutputType, address_radix: &AddressRadix
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: od [-A RADIX] [-t TYPE] [-N COUNT] [FILE...]");
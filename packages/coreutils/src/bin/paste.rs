@@ -1,12 +1,11 @@
 //! paste - merge lines of files
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: paste [-d DELIM] [-s] [FILE...]");
@@ -1,6 +1,5 @@
 //! patch - apply a unified diff to files
 
-use std::env;
 use std::fs;
 use std::io::{self, BufRead, BufReader, Read};
 use std::path::PathBuf;
@@ -13,7 +12,10 @@ struct Options {
 
 struct Hunk {
     old_start: usize,
+    old_count: usize,
     new_lines: Vec<HunkLine>,
+    /// The new side's last line is marked "\ No newline at end of file".
+    no_newline: bool,
 }
 
 enum HunkLine {
@@ -33,20 +35,33 @@ fn strip_path(path: &str, strip: usize) -> String {
     components.join("/")
 }
 
-fn parse_hunk_header(line: &str) -> Option<(usize, usize)> {
-    // @@ -old_start,old_count +new_start,new_count @@
+/// Split a `---` or `+++` header into its path and whether it names no
+/// file: /dev/null, or the epoch timestamp that diff -N gives a missing
+/// file.
+fn parse_file_header(header: &str) -> (&str, bool) {
+    let header = header.trim_end();
+    let (path, stamp) = header.split_once('\t').unwrap_or((header, ""));
+    let absent = path == "/dev/null" || stamp.starts_with("1970-01-01 00:00:00");
+    (path, absent)
+}
+
+/// Parse `@@ -old_start,old_count +new_start,new_count @@`, returning
+/// (old_start, old_count, new_count). A count left out is 1.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
     let line = line.strip_prefix("@@ ")?;
     let end = line.find(" @@")?;
     let ranges = &line[..end];
     let mut parts = ranges.split(' ');
-    let old_part = parts.next()?;
-    let old_part = old_part.strip_prefix('-')?;
-    let old_start: usize = if let Some(comma) = old_part.find(',') {
-        old_part[..comma].parse().ok()?
-    } else {
-        old_part.parse().ok()?
-    };
-    Some((old_start, 0))
+    let (old_start, old_count) = parse_range(parts.next()?.strip_prefix('-')?)?;
+    let (_, new_count) = parse_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old_start, old_count, new_count))
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
 }
 
 fn apply_hunks(original: &str, hunks: &[Hunk]) -> String {
@@ -55,11 +70,12 @@ fn apply_hunks(original: &str, hunks: &[Hunk]) -> String {
     let mut old_idx = 0;
 
     for hunk in hunks {
-        // Copy lines before this hunk
-        let hunk_start = if hunk.old_start > 0 {
-            hunk.old_start - 1
+        // Copy lines before this hunk. A hunk that removes nothing names
+        // the line it comes after.
+        let hunk_start = if hunk.old_count == 0 {
+            hunk.old_start
         } else {
-            0
+            hunk.old_start.saturating_sub(1)
         };
         while old_idx < hunk_start && old_idx < old_lines.len() {
             result.push(old_lines[old_idx].to_string());
@@ -83,21 +99,21 @@ fn apply_hunks(original: &str, hunks: &[Hunk]) -> String {
     }
 
     // Copy remaining lines
+    let tail = old_idx < old_lines.len();
     while old_idx < old_lines.len() {
         result.push(old_lines[old_idx].to_string());
         old_idx += 1;
     }
 
+    // The last line ends in a newline unless the last hunk says it has
+    // none, or it was copied from an original that had none.
+    let newline = if tail {
+        original.ends_with('\n')
+    } else {
+        !hunks.last().is_some_and(|h| h.no_newline)
+    };
     let mut out = result.join("\n");
-    if !out.is_empty()
-        && (original.ends_with('\n')
-            || hunks.iter().any(|h| {
-                h.new_lines
-                    .last()
-                    .map(|l| matches!(l, HunkLine::Add(_)))
-                    .unwrap_or(false)
-            }))
-    {
+    if !out.is_empty() && newline {
         out.push('\n');
     }
     out
@@ -119,8 +135,8 @@ fn read_input(input_file: &Option<String>) -> Vec<String> {
     buf.lines().map(|l| l.unwrap_or_default()).collect()
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: patch [OPTIONS] [FILE]");
@@ -177,19 +193,21 @@ fn main() {
     let mut line_idx = 0;
 
     while line_idx < diff_lines.len() {
-        // Find next file header
+        // Find next file header. When the new side names no file, the
+        // patch removes the old one.
         let mut file_path: Option<String> = None;
+        let mut old_path = "";
+        let mut remove = false;
 
         while line_idx < diff_lines.len() {
-            if diff_lines[line_idx].starts_with("+++ ") {
-                let path_str = diff_lines[line_idx][4..].trim();
-                // Remove timestamp if present
-                let path_str = if let Some(tab_pos) = path_str.find('\t') {
-                    &path_str[..tab_pos]
-                } else {
-                    path_str
-                };
-                file_path = Some(strip_path(path_str, opts.strip));
+            let line = &diff_lines[line_idx];
+            if let Some(header) = line.strip_prefix("--- ") {
+                old_path = parse_file_header(header).0;
+            } else if let Some(header) = line.strip_prefix("+++ ") {
+                let (path, absent) = parse_file_header(header);
+                let path = if path == "/dev/null" { old_path } else { path };
+                file_path = Some(strip_path(path, opts.strip));
+                remove = absent;
                 line_idx += 1;
                 break;
             }
@@ -214,47 +232,63 @@ fn main() {
             }
 
             if diff_lines[line_idx].starts_with("@@ ") {
-                let (old_start, _) = match parse_hunk_header(&diff_lines[line_idx]) {
-                    Some(h) => h,
-                    None => {
-                        eprintln!("patch: malformed hunk header: {}", diff_lines[line_idx]);
-                        process::exit(1);
-                    }
-                };
+                let (old_start, old_count, new_count) =
+                    match parse_hunk_header(&diff_lines[line_idx]) {
+                        Some(h) => h,
+                        None => {
+                            eprintln!("patch: malformed hunk header: {}", diff_lines[line_idx]);
+                            process::exit(1);
+                        }
+                    };
                 line_idx += 1;
 
                 let mut hunk_lines: Vec<HunkLine> = Vec::new();
+                let mut no_newline = false;
+                // The hunk ends once its counts are used up, so text that
+                // follows it, like diff -r's "Only in" lines, is skipped.
+                // Until then a line like "--- x" is a removed "-- x".
+                let (mut old_left, mut new_left) = (old_count, new_count);
 
-                while line_idx < diff_lines.len() {
+                while line_idx < diff_lines.len() && (old_left > 0 || new_left > 0) {
                     let line = &diff_lines[line_idx];
-                    if line.starts_with("@@ ")
-                        || line.starts_with("--- ")
-                        || line.starts_with("+++ ")
-                        || line.starts_with("diff ")
-                    {
+                    if line.starts_with("@@ ") || line.starts_with("diff ") {
                         break;
                     }
 
                     if let Some(rest) = line.strip_prefix('+') {
                         hunk_lines.push(HunkLine::Add(rest.to_string()));
+                        new_left = new_left.saturating_sub(1);
                     } else if let Some(rest) = line.strip_prefix('-') {
                         let _ = rest; // consume but discard
                         hunk_lines.push(HunkLine::Remove(()));
-                    } else if let Some(rest) = line.strip_prefix(' ') {
-                        hunk_lines.push(HunkLine::Context(rest.to_string()));
-                    } else if line.is_empty() {
-                        hunk_lines.push(HunkLine::Context(String::new()));
+                        old_left = old_left.saturating_sub(1);
+                    } else if line.starts_with('\\') {
+                        // "\ No newline at end of file" with no line before it
                     } else {
                         // Treat as context
-                        hunk_lines.push(HunkLine::Context(line.to_string()));
+                        let text = line.strip_prefix(' ').unwrap_or(line);
+                        hunk_lines.push(HunkLine::Context(text.to_string()));
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
                     }
 
                     line_idx += 1;
+                    // "\ No newline at end of file" follows the line it
+                    // describes and counts towards neither side.
+                    if diff_lines
+                        .get(line_idx)
+                        .is_some_and(|l| l.starts_with('\\'))
+                    {
+                        no_newline = !matches!(hunk_lines.last(), Some(HunkLine::Remove(_)));
+                        line_idx += 1;
+                    }
                 }
 
                 hunks.push(Hunk {
                     old_start,
+                    old_count,
                     new_lines: hunk_lines,
+                    no_newline,
                 });
             } else {
                 line_idx += 1;
@@ -272,6 +306,14 @@ fn main() {
 
         let result = apply_hunks(&original, &hunks);
 
+        if remove && result.is_empty() {
+            if let Err(e) = fs::remove_file(actual_path) {
+                eprintln!("patch: {actual_path}: {e}");
+                process::exit(1);
+            }
+            continue;
+        }
+
         // Create parent directories if needed
         let path = PathBuf::from(actual_path);
         if let Some(parent) = path.parent() {
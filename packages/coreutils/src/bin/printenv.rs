@@ -1,8 +1,8 @@
 use std::env;
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     if args.is_empty() {
         for (key, val) in env::vars() {
             println!("{key}={val}");
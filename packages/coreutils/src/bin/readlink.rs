@@ -1,7 +1,5 @@
-use std::env;
-
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     // No real symlinks in VFS — just print the path (-f canonicalizes)
     let paths: Vec<&str> = args
         .iter()
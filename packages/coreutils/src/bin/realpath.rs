@@ -1,7 +1,5 @@
-use std::env;
-
-fn main() {
-    for arg in env::args().skip(1) {
+pub fn main() {
+    for arg in codepod_coreutils::args().skip(1) {
         if arg.starts_with('-') {
             continue;
         }
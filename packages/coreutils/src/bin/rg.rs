@@ -1,7 +1,6 @@
 //! rg - ripgrep-like recursive code search
 
 use std::collections::HashMap;
-use std::env;
 use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader, Read};
 use std::path::Path;
@@ -1093,8 +1092,8 @@ fn walk_dir(
 // Main
 // ---------------------------------------------------------------------------
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
 
     if args.is_empty() {
         print_usage();
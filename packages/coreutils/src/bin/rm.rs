@@ -1,4 +1,3 @@
-use std::env;
 use std::fs;
 use std::path::Path;
 use std::process;
@@ -32,14 +31,14 @@ fn remove_path(path: &Path, opts: &Options) -> Result<(), String> {
     }
 }
 
-fn main() {
+pub fn main() {
     let mut opts = Options {
         recursive: false,
         force: false,
     };
     let mut targets: Vec<String> = Vec::new();
 
-    for arg in env::args().skip(1) {
+    for arg in codepod_coreutils::args().skip(1) {
         if arg == "--" {
             break;
         }
@@ -1,10 +1,9 @@
-use std::env;
 use std::fs;
 use std::process;
 
-fn main() {
+pub fn main() {
     let mut exit_code = 0;
-    for arg in env::args().skip(1) {
+    for arg in codepod_coreutils::args().skip(1) {
         if let Err(e) = fs::remove_dir(&arg) {
             eprintln!("rmdir: failed to remove '{arg}': {e}");
             exit_code = 1;
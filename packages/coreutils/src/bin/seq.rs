@@ -1,9 +1,8 @@
-use std::env;
 use std::io::{self, BufWriter, Write};
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     let (first, step, last) = match args.len() {
         1 => (
             1i64,
@@ -1,5 +1,4 @@
 use std::{
-    env,
     io::{self, BufRead, Write},
     process,
 };
@@ -14,6 +13,16 @@ fn xorshift64(state: &mut u64) -> u64 {
     x
 }
 
+/// Seed drawn from the host, so a host with a seeded generator gets the
+/// same order every run. Falls back to `fallback` if the host has none;
+/// xorshift needs a non-zero seed.
+fn seed(fallback: u64) -> u64 {
+    match codepod_process::random_u64() {
+        Ok(seed) if seed != 0 => seed,
+        _ => fallback,
+    }
+}
+
 fn shuffle(lines: &mut [String], seed: u64) {
     let mut state = seed;
     let n = lines.len();
@@ -23,8 +32,8 @@ fn shuffle(lines: &mut [String], seed: u64) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut input_range: Option<(i64, i64)> = None;
     let mut count: Option<usize> = None;
     let mut files: Vec<String> = Vec::new();
@@ -61,7 +70,7 @@ fn main() {
                 // Rest of args are items to shuffle
                 i += 1;
                 let mut items: Vec<String> = args[i..].to_vec();
-                let seed = items.len() as u64 ^ 0xdeadbeef;
+                let seed = seed(items.len() as u64 ^ 0xdeadbeef);
                 shuffle(&mut items, seed);
                 if let Some(n) = count {
                     items.truncate(n);
@@ -97,7 +106,7 @@ fn main() {
         reader.lines().map_while(Result::ok).collect()
     };
 
-    let seed = lines.len() as u64 ^ 0xcafebabe;
+    let seed = seed(lines.len() as u64 ^ 0xcafebabe);
     shuffle(&mut lines, seed);
     if let Some(n) = count {
         lines.truncate(n);
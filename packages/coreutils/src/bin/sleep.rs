@@ -1,4 +1,4 @@
-fn main() {
+pub fn main() {
     // No-op in WASI sandbox — sleep is a stub that exits immediately.
     // Accepts and ignores all arguments for compatibility.
 }
@@ -1,6 +1,5 @@
 //! sort - sort lines of text
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::process;
@@ -76,8 +75,8 @@ fn version_compare(a: &str, b: &str) -> std::cmp::Ordering {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut reverse = false;
     let mut numeric = false;
@@ -1,6 +1,5 @@
 //! split - split a file into pieces
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::process;
@@ -107,8 +106,8 @@ fn split_by_bytes<R: Read>(mut reader: R, bytes_per_file: usize, prefix: &str) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: split [-l N] [-b N] [FILE [PREFIX]]");
@@ -1,6 +1,5 @@
 //! stat - display file status
 
-use std::env;
 use std::fs;
 use std::process;
 
@@ -108,8 +107,8 @@ fn format_permissions(mode: u32) -> String {
     s
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     if args.iter().any(|a| a == "--help") {
         println!("Usage: stat [-c FORMAT] FILE...");
         println!("Display file status.");
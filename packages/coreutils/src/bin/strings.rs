@@ -1,6 +1,5 @@
 //! strings - find printable strings in files
 
-use std::env;
 use std::fs::File;
 use std::io::{self, Read};
 use std::process;
@@ -30,8 +29,8 @@ fn strings_reader<R: Read>(mut reader: R, min_len: usize) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: strings [-n N] [FILE...]");
@@ -1,5 +1,4 @@
 use std::{
-    env,
     io::{self, Read},
     process,
 };
@@ -25,8 +24,8 @@ fn sysv_sum(data: &[u8]) -> (u16, usize) {
     (r as u16, blocks)
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut sysv = false;
     let mut files: Vec<String> = Vec::new();
     for arg in args.iter().skip(1) {
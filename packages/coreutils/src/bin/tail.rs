@@ -1,9 +1,12 @@
 //! tail - output the last part of files
 
-use std::env;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Write};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::process;
+use std::time::Duration;
+
+/// How often `-f` checks followed files for new data.
+const FOLLOW_POLL: Duration = Duration::from_millis(100);
 
 fn tail_bytes<R: Read>(mut reader: R, count: usize, stdout: &mut impl Write) -> io::Result<()> {
     if count == 0 {
@@ -70,11 +73,59 @@ fn tail_from_line<R: BufRead>(reader: R, start: usize, stdout: &mut impl Write)
     Ok(())
 }
 
+/// `-f`: keep printing data appended to `files`, polling their sizes, until
+/// output fails or the process is stopped. A file that shrinks is reread
+/// from the start, as after truncation.
+fn follow(files: &[String], stdout: &mut impl Write) -> i32 {
+    let multiple = files.len() > 1;
+    let mut offsets: Vec<u64> = files
+        .iter()
+        .map(|f| std::fs::metadata(f).map(|m| m.len()).unwrap_or(0))
+        .collect();
+    let mut last_shown = files.len() - 1;
+    loop {
+        std::thread::sleep(FOLLOW_POLL);
+        for (idx, file) in files.iter().enumerate() {
+            let Ok(len) = std::fs::metadata(file).map(|m| m.len()) else {
+                continue;
+            };
+            if len < offsets[idx] {
+                eprintln!("tail: {file}: file truncated");
+                offsets[idx] = 0;
+            }
+            if len == offsets[idx] {
+                continue;
+            }
+            let mut data = Vec::new();
+            let read = File::open(file).and_then(|mut f| {
+                f.seek(SeekFrom::Start(offsets[idx]))?;
+                f.read_to_end(&mut data)
+            });
+            if read.is_err() {
+                continue;
+            }
+            offsets[idx] += data.len() as u64;
+            if multiple && last_shown != idx {
+                let _ = writeln!(stdout, "\n==> {file} <==");
+                last_shown = idx;
+            }
+            if stdout
+                .write_all(&data)
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                return 1;
+            }
+        }
+    }
+}
+
 fn run() -> i32 {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut count: usize = 10;
     let mut byte_mode = false;
     let mut from_start = false; // +N mode: start from line N
+    let mut follow_files = false;
     let mut files: Vec<String> = Vec::new();
 
     let mut i = 1;
@@ -174,14 +225,14 @@ fn run() -> i32 {
                 }
                 break;
             }
-            "-f" | "--follow" => {} // accept silently (no polling in WASM)
+            "-f" | "--follow" => follow_files = true,
             arg if arg.starts_with('-') && arg.len() > 1 && !arg.starts_with("--") => {
                 // Handle combined short flags
                 let mut valid = true;
                 for ch in arg[1..].chars() {
                     match ch {
-                        'f' => {}                      // follow: accept silently
-                        'q' | 'v' => {}                // quiet/verbose: accept silently
+                        'f' => follow_files = true,
+                        'q' | 'v' => {} // quiet/verbose: accept silently
                         _ if ch.is_ascii_digit() => {} // handled above as -NUM
                         _ => {
                             eprintln!("tail: invalid option -- '{ch}'");
@@ -261,9 +312,16 @@ fn run() -> i32 {
         }
     }
 
+    // Standard input cannot be followed, so `-f` on a pipe just exits.
+    let followed: Vec<String> = files.iter().filter(|f| *f != "-").cloned().collect();
+    if follow_files && !followed.is_empty() {
+        let _ = stdout.flush();
+        return follow(&followed, &mut stdout);
+    }
+
     exit_code
 }
 
-fn main() {
+pub fn main() {
     process::exit(run());
 }
@@ -6,7 +6,6 @@
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
@@ -29,7 +28,7 @@ struct Options {
 }
 
 fn parse_args() -> Options {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = codepod_coreutils::args().collect();
     let mut opts = Options {
         mode: None,
         file: None,
@@ -406,7 +405,7 @@ fn list_archive(opts: &Options) {
     }
 }
 
-fn main() {
+pub fn main() {
     let opts = parse_args();
 
     match opts.mode.as_ref().unwrap() {
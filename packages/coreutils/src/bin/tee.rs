@@ -1,12 +1,11 @@
 //! tee - read from stdin, write to stdout and files
 
-use std::env;
 use std::fs::OpenOptions;
 use std::io::{self, BufRead, Write};
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut append = false;
     let mut files: Vec<String> = Vec::new();
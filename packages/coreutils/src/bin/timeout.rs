@@ -4,11 +4,10 @@
 //! sandbox runtime. This stub accepts the standard syntax for compatibility
 //! so that scripts using `timeout` do not break.
 
-use std::env;
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: timeout DURATION COMMAND [ARG...]");
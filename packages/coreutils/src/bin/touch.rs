@@ -1,11 +1,10 @@
 //! touch - create empty files or update timestamps
 
-use std::env;
 use std::fs::OpenOptions;
 use std::process;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut no_create = false;
     let mut files: Vec<String> = Vec::new();
@@ -1,6 +1,5 @@
 //! tr - translate or delete characters
 
-use std::env;
 use std::io::{self, Read, Write};
 use std::process;
 
@@ -112,8 +111,8 @@ fn expand_set(spec: &str) -> Vec<char> {
     result
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut delete = false;
     let mut squeeze = false;
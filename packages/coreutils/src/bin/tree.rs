@@ -1,6 +1,5 @@
 //! tree - list directory contents in a tree-like format
 
-use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
@@ -72,8 +71,8 @@ fn walk_tree(
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: tree [OPTIONS] [DIR]");
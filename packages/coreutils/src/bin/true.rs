@@ -1,3 +1,3 @@
-fn main() {
+pub fn main() {
     // true always succeeds, ignoring all arguments
 }
@@ -1,6 +1,5 @@
 //! truncate - shrink or extend the size of a file
 
-use std::env;
 use std::fs;
 use std::io::Write;
 use std::process;
@@ -46,8 +45,8 @@ fn parse_size(s: &str) -> Result<SizeSpec, String> {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: truncate -s SIZE FILE...");
@@ -1,13 +1,12 @@
 //! tsort - topological sort
 
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Write};
 use std::process;
 
 fn run() -> i32 {
-    let args: Vec<String> = env::args().collect();
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let input: Box<dyn BufRead> = if args.len() > 1 && args[1] != "-" {
         match File::open(&args[1]) {
@@ -144,6 +143,6 @@ fn run() -> i32 {
     exit_code
 }
 
-fn main() {
+pub fn main() {
     process::exit(run());
 }
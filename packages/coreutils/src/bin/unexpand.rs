@@ -1,6 +1,5 @@
 //! unexpand - convert spaces to tabs
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Read};
 use std::process;
@@ -105,8 +104,8 @@ fn unexpand_all(line: &str, tab_stop: usize) -> String {
     result
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: unexpand [-a] [-t N] [FILE...]");
@@ -1,6 +1,5 @@
 //! uniq - report or omit repeated lines
 
-use std::env;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
 use std::process;
@@ -93,8 +92,8 @@ fn process_lines(lines: &[String], opts: &Options) {
     }
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     let mut opts = Options {
         count: false,
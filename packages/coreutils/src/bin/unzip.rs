@@ -1,6 +1,5 @@
 //! unzip - extract zip archives
 
-use std::env;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::PathBuf;
@@ -166,8 +165,8 @@ fn list_entries(entries: &[ZipEntry]) {
     );
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: unzip [OPTIONS] ARCHIVE");
@@ -1,5 +1,5 @@
 //! uptime - tell how long the system has been running
 
-fn main() {
+pub fn main() {
     println!(" 00:00:00 up 0 min,  1 user,  load average: 0.00, 0.00, 0.00");
 }
@@ -1,5 +1,5 @@
 //! users - print login names of users currently logged in
 
-fn main() {
+pub fn main() {
     println!("root");
 }
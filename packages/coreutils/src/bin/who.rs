@@ -1,5 +1,5 @@
 //! who - show who is logged on
 
-fn main() {
+pub fn main() {
     println!("root     pts/0        Jan  1 00:00");
 }
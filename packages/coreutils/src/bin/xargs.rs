@@ -1,8 +1,7 @@
-use std::env;
 use std::io::{self, BufRead, BufWriter, Read, Write};
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
 
     // Parse flags
     let mut max_args: Option<usize> = None;
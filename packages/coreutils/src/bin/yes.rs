@@ -1,8 +1,7 @@
-use std::env;
 use std::io::{self, BufWriter, Write};
 
-fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().skip(1).collect();
     let line = if args.is_empty() {
         "y".to_string()
     } else {
@@ -1,6 +1,5 @@
 //! zip - create zip archives (store method, no compression)
 
-use std::env;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
@@ -56,8 +55,8 @@ fn collect_files(path: &Path, recursive: bool) -> Vec<String> {
     files
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+pub fn main() {
+    let args: Vec<String> = codepod_coreutils::args().collect();
 
     if args.iter().any(|a| a == "--help") {
         println!("Usage: zip [OPTIONS] ARCHIVE FILE...");
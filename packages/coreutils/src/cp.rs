@@ -0,0 +1,394 @@
+//! cp - copy files and directories
+
+use std::io::{self, Write};
+
+use crate::{join_path, Io, Metadata};
+
+#[derive(Default)]
+struct Options {
+    recursive: bool,
+    preserve_mode: bool,
+    preserve_times: bool,
+    no_clobber: bool,
+    update: bool,
+    verbose: bool,
+    no_target_dir: bool,
+    target_dir: Option<String>,
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: cp [OPTION]... SOURCE DEST");
+    let _ = writeln!(stderr, "  or:  cp [OPTION]... SOURCE... DIRECTORY");
+    let _ = writeln!(stderr, "  or:  cp [OPTION]... -t DIRECTORY SOURCE...");
+    let _ = writeln!(
+        stderr,
+        "Copy SOURCE to DEST, or multiple SOURCE(s) to DIRECTORY."
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(stderr, "  -a, --archive                same as -rp");
+    let _ = writeln!(
+        stderr,
+        "  -n, --no-clobber             do not overwrite an existing file"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -p, --preserve[=ATTR_LIST]   keep mode and timestamps (ATTR_LIST: mode,timestamps,all)"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -r, -R, --recursive          copy directories recursively"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -t, --target-directory=DIR   copy all SOURCE arguments into DIR"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -T, --no-target-directory    treat DEST as a normal file"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -u, --update                 copy only when SOURCE is newer than DEST or DEST is missing"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -v, --verbose                explain what is being done"
+    );
+}
+
+/// Apply a `--preserve` attribute list; `false` for an unknown attribute.
+fn set_preserve(opts: &mut Options, list: &str) -> bool {
+    for attr in list.split(',') {
+        match attr {
+            "mode" => opts.preserve_mode = true,
+            "timestamps" => opts.preserve_times = true,
+            "all" => {
+                opts.preserve_mode = true;
+                opts.preserve_times = true;
+            }
+            // Nothing to keep in the sandbox.
+            "ownership" | "links" | "context" | "xattr" => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// The last component of `path`, ignoring trailing slashes.
+fn file_name(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    trimmed.rsplit('/').next().unwrap_or(trimmed)
+}
+
+fn same_path(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+/// Whether `path` is `dir` or somewhere beneath it.
+fn is_within(path: &str, dir: &str) -> bool {
+    let dir = dir.trim_end_matches('/');
+    let path = path.trim_end_matches('/');
+    path == dir
+        || path
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+struct Copier<'a> {
+    io: &'a mut dyn Io,
+    opts: Options,
+}
+
+impl Copier<'_> {
+    fn error(&mut self, message: std::fmt::Arguments) -> bool {
+        let _ = writeln!(self.io.stderr(), "cp: {message}");
+        false
+    }
+
+    fn copied(&mut self, src: &str, dst: &str) {
+        if self.opts.verbose {
+            let _ = writeln!(self.io.stdout(), "'{src}' -> '{dst}'");
+        }
+    }
+
+    /// Keep `meta`'s mode and mtime on `dst`, as asked. Hosts that can't
+    /// set them are not an error.
+    fn preserve(&mut self, meta: &Metadata, dst: &str) -> bool {
+        let unsupported = |e: &io::Error| e.kind() == io::ErrorKind::Unsupported;
+        if self.opts.preserve_mode {
+            match self.io.set_mode(dst, meta.mode) {
+                Err(e) if !unsupported(&e) => {
+                    return self.error(format_args!("preserving permissions for '{dst}': {e}"));
+                }
+                _ => {}
+            }
+        }
+        if self.opts.preserve_times {
+            match self.io.set_mtime(dst, meta.mtime_ms) {
+                Err(e) if !unsupported(&e) => {
+                    return self.error(format_args!("preserving times for '{dst}': {e}"));
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Copy `src` to exactly `dst`. Returns whether everything copied.
+    fn copy(&mut self, src: &str, dst: &str) -> bool {
+        let meta = match self.io.metadata(src) {
+            Ok(meta) => meta,
+            Err(e) => return self.error(format_args!("cannot stat '{src}': {e}")),
+        };
+        let existing = self.io.metadata(dst).ok();
+        if meta.is_dir {
+            self.copy_dir(src, dst, &meta, existing)
+        } else {
+            self.copy_file(src, dst, &meta, existing)
+        }
+    }
+
+    fn copy_file(
+        &mut self,
+        src: &str,
+        dst: &str,
+        meta: &Metadata,
+        existing: Option<Metadata>,
+    ) -> bool {
+        if let Some(existing) = existing {
+            if existing.is_dir {
+                return self.error(format_args!(
+                    "cannot overwrite directory '{dst}' with non-directory"
+                ));
+            }
+            if same_path(src, dst) {
+                return self.error(format_args!("'{src}' and '{dst}' are the same file"));
+            }
+            if self.opts.no_clobber || (self.opts.update && existing.mtime_ms >= meta.mtime_ms) {
+                return true;
+            }
+        }
+        if let Err(e) = self.io.copy_file(src, dst) {
+            return self.error(format_args!("cannot create regular file '{dst}': {e}"));
+        }
+        self.copied(src, dst);
+        self.preserve(meta, dst)
+    }
+
+    fn copy_dir(
+        &mut self,
+        src: &str,
+        dst: &str,
+        meta: &Metadata,
+        existing: Option<Metadata>,
+    ) -> bool {
+        if !self.opts.recursive {
+            return self.error(format_args!("-r not specified; omitting directory '{src}'"));
+        }
+        if is_within(dst, src) {
+            return self.error(format_args!(
+                "cannot copy a directory, '{src}', into itself, '{dst}'"
+            ));
+        }
+        match existing {
+            Some(existing) if !existing.is_dir => {
+                return self.error(format_args!(
+                    "cannot overwrite non-directory '{dst}' with directory '{src}'"
+                ));
+            }
+            Some(_) => {}
+            None => {
+                if let Err(e) = self.io.create_dir(dst) {
+                    return self.error(format_args!("cannot create directory '{dst}': {e}"));
+                }
+                self.copied(src, dst);
+            }
+        }
+
+        let mut names = match self.io.read_dir(src) {
+            Ok(names) => names,
+            Err(e) => return self.error(format_args!("cannot access '{src}': {e}")),
+        };
+        names.sort();
+        let mut ok = true;
+        for name in names {
+            ok &= self.copy(&join_path(src, &name), &join_path(dst, &name));
+        }
+        // After the contents, whose copying would bump the mtime.
+        ok & self.preserve(meta, dst)
+    }
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut opts = Options::default();
+    let mut operands: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" => {
+                print_usage(io.stderr());
+                return 0;
+            }
+            "--recursive" => opts.recursive = true,
+            "--archive" => {
+                opts.recursive = true;
+                set_preserve(&mut opts, "all");
+            }
+            "--preserve" => {
+                set_preserve(&mut opts, "mode,timestamps");
+            }
+            "--no-clobber" => opts.no_clobber = true,
+            "--force" | "--no-dereference" | "--dereference" => {}
+            "--update" => opts.update = true,
+            "--verbose" => opts.verbose = true,
+            "--no-target-directory" => opts.no_target_dir = true,
+            "--target-directory" => {
+                i += 1;
+                match args.get(i) {
+                    Some(dir) => opts.target_dir = Some(dir.clone()),
+                    None => {
+                        let _ = writeln!(
+                            io.stderr(),
+                            "cp: option '--target-directory' requires an argument"
+                        );
+                        return 1;
+                    }
+                }
+            }
+            _ if arg.starts_with("--target-directory=") => {
+                opts.target_dir = Some(arg["--target-directory=".len()..].to_string());
+            }
+            _ if arg.starts_with("--preserve=") => {
+                let list = &arg["--preserve=".len()..];
+                if !set_preserve(&mut opts, list) {
+                    let _ = writeln!(
+                        io.stderr(),
+                        "cp: invalid argument '{list}' for '--preserve'"
+                    );
+                    return 1;
+                }
+            }
+            "--" => {
+                operands.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "cp: unrecognized option '{arg}'");
+                return 1;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for (pos, c) in arg[1..].char_indices() {
+                    match c {
+                        'r' | 'R' => opts.recursive = true,
+                        'a' => {
+                            opts.recursive = true;
+                            set_preserve(&mut opts, "all");
+                        }
+                        'p' => {
+                            set_preserve(&mut opts, "mode,timestamps");
+                        }
+                        'n' => opts.no_clobber = true,
+                        'u' => opts.update = true,
+                        'v' => opts.verbose = true,
+                        'T' => opts.no_target_dir = true,
+                        // Overwriting is the default, and symlinks are
+                        // always followed.
+                        'f' | 'd' | 'P' | 'L' => {}
+                        't' => {
+                            let rest = &arg[1 + pos + 1..];
+                            let dir = if !rest.is_empty() {
+                                rest
+                            } else {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(dir) => dir.as_str(),
+                                    None => {
+                                        let _ = writeln!(
+                                            io.stderr(),
+                                            "cp: option requires an argument -- 't'"
+                                        );
+                                        return 1;
+                                    }
+                                }
+                            };
+                            opts.target_dir = Some(dir.to_string());
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "cp: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => operands.push(arg),
+        }
+        i += 1;
+    }
+
+    // Work out the destination directory, if copying into one, and the
+    // sources.
+    let (sources, dest_dir, dest) = match opts.target_dir.take() {
+        Some(dir) => {
+            if opts.no_target_dir {
+                let _ = writeln!(
+                    io.stderr(),
+                    "cp: cannot combine --target-directory (-t) and --no-target-directory (-T)"
+                );
+                return 1;
+            }
+            (operands, true, dir)
+        }
+        None => {
+            if operands.is_empty() {
+                let _ = writeln!(io.stderr(), "cp: missing file operand");
+                return 1;
+            }
+            if operands.len() == 1 {
+                let _ = writeln!(
+                    io.stderr(),
+                    "cp: missing destination file operand after '{}'",
+                    operands[0]
+                );
+                return 1;
+            }
+            let dest = operands.pop().unwrap().to_string();
+            if opts.no_target_dir {
+                if operands.len() > 1 {
+                    let _ = writeln!(io.stderr(), "cp: extra operand '{}'", operands[1]);
+                    return 1;
+                }
+                (operands, false, dest)
+            } else {
+                let into_dir = operands.len() > 1 || io.is_dir(&dest);
+                (operands, into_dir, dest)
+            }
+        }
+    };
+    if sources.is_empty() {
+        let _ = writeln!(io.stderr(), "cp: missing file operand");
+        return 1;
+    }
+    if dest_dir && !io.is_dir(&dest) {
+        let _ = writeln!(io.stderr(), "cp: target '{dest}' is not a directory");
+        return 1;
+    }
+
+    let mut copier = Copier { io, opts };
+    let mut ok = true;
+    for src in sources {
+        let target = if dest_dir {
+            join_path(&dest, file_name(src))
+        } else {
+            dest.clone()
+        };
+        ok &= copier.copy(src, &target);
+    }
+    if ok {
+        0
+    } else {
+        1
+    }
+}
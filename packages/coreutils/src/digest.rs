@@ -0,0 +1,828 @@
+//! Message digests, and the `*sum` tools that print and check them.
+//!
+//! md5sum, sha1sum, sha224sum, sha256sum, sha384sum and sha512sum differ
+//! only in their [`Algorithm`]; each one's `run` is [`run`] with its own.
+
+use std::io::{self, Read, Write};
+
+use crate::Io;
+
+/// A digest the `*sum` tools compute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    /// The name BSD-style (`--tag`) lines give it, e.g. `SHA256`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha224 => "SHA224",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha384 => "SHA384",
+            Algorithm::Sha512 => "SHA512",
+        }
+    }
+
+    /// The tool that computes it, e.g. `sha256sum`.
+    pub fn tool(self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "md5sum",
+            Algorithm::Sha1 => "sha1sum",
+            Algorithm::Sha224 => "sha224sum",
+            Algorithm::Sha256 => "sha256sum",
+            Algorithm::Sha384 => "sha384sum",
+            Algorithm::Sha512 => "sha512sum",
+        }
+    }
+
+    /// The length of its digests, in bytes.
+    pub fn digest_len(self) -> usize {
+        match self {
+            Algorithm::Md5 => 16,
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha224 => 28,
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha384 => 48,
+            Algorithm::Sha512 => 64,
+        }
+    }
+}
+
+/// MD5 per-round shift amounts
+const S: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// MD5 round constants: floor(2^32 * abs(sin(i+1))) for i in 0..63
+const T: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// SHA-1 initial hash values
+const H1: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+/// SHA-224 initial hash values (second 32 bits of fractional parts of square roots of 9th-16th primes)
+const H224: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939, 0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+/// SHA-256 initial hash values (first 32 bits of fractional parts of square roots of first 8 primes)
+const H256: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// SHA-224/256 round constants (first 32 bits of fractional parts of cube roots of first 64 primes)
+const K256: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-384 initial hash values (first 64 bits of fractional parts of square roots of 9th-16th primes)
+const H384: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+/// SHA-512 initial hash values (first 64 bits of fractional parts of square roots of first 8 primes)
+const H512: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// SHA-384/512 round constants (first 64 bits of fractional parts of cube roots of first 80 primes)
+const K512: [u64; 80] = [
+    0x428a2f98d728ae22,
+    0x7137449123ef65cd,
+    0xb5c0fbcfec4d3b2f,
+    0xe9b5dba58189dbbc,
+    0x3956c25bf348b538,
+    0x59f111f1b605d019,
+    0x923f82a4af194f9b,
+    0xab1c5ed5da6d8118,
+    0xd807aa98a3030242,
+    0x12835b0145706fbe,
+    0x243185be4ee4b28c,
+    0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f,
+    0x80deb1fe3b1696b1,
+    0x9bdc06a725c71235,
+    0xc19bf174cf692694,
+    0xe49b69c19ef14ad2,
+    0xefbe4786384f25e3,
+    0x0fc19dc68b8cd5b5,
+    0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275,
+    0x4a7484aa6ea6e483,
+    0x5cb0a9dcbd41fbd4,
+    0x76f988da831153b5,
+    0x983e5152ee66dfab,
+    0xa831c66d2db43210,
+    0xb00327c898fb213f,
+    0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2,
+    0xd5a79147930aa725,
+    0x06ca6351e003826f,
+    0x142929670a0e6e70,
+    0x27b70a8546d22ffc,
+    0x2e1b21385c26c926,
+    0x4d2c6dfc5ac42aed,
+    0x53380d139d95b3df,
+    0x650a73548baf63de,
+    0x766a0abb3c77b2a8,
+    0x81c2c92e47edaee6,
+    0x92722c851482353b,
+    0xa2bfe8a14cf10364,
+    0xa81a664bbc423001,
+    0xc24b8b70d0f89791,
+    0xc76c51a30654be30,
+    0xd192e819d6ef5218,
+    0xd69906245565a910,
+    0xf40e35855771202a,
+    0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8,
+    0x1e376c085141ab53,
+    0x2748774cdf8eeb99,
+    0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63,
+    0x4ed8aa4ae3418acb,
+    0x5b9cca4f7763e373,
+    0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc,
+    0x78a5636f43172f60,
+    0x84c87814a1f0ab72,
+    0x8cc702081a6439ec,
+    0x90befffa23631e28,
+    0xa4506cebde82bde9,
+    0xbef9a3f7b2c67915,
+    0xc67178f2e372532b,
+    0xca273eceea26619c,
+    0xd186b8c721c0c207,
+    0xeada7dd6cde0eb1e,
+    0xf57d4f7fee6ed178,
+    0x06f067aa72176fba,
+    0x0a637dc5a2c898a6,
+    0x113f9804bef90dae,
+    0x1b710b35131c471b,
+    0x28db77f523047d84,
+    0x32caab7b40c72493,
+    0x3c9ebe0a15c9bebc,
+    0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6,
+    0x597f299cfc657e2a,
+    0x5fcb6fab3ad6faec,
+    0x6c44198c4a475817,
+];
+
+/// The chaining state of each family of digests.
+enum State {
+    Md5([u32; 4]),
+    Sha1([u32; 5]),
+    Sha256([u32; 8]),
+    Sha512([u64; 8]),
+}
+
+impl State {
+    fn process_block(&mut self, block: &[u8]) {
+        match self {
+            State::Md5(state) => md5_block(state, block),
+            State::Sha1(state) => sha1_block(state, block),
+            State::Sha256(state) => sha256_block(state, block),
+            State::Sha512(state) => sha512_block(state, block),
+        }
+    }
+}
+
+/// A digest being computed: feed it with [`update`](Hasher::update), then
+/// [`finish`](Hasher::finish) it.
+pub struct Hasher {
+    algorithm: Algorithm,
+    state: State,
+    buffer: Vec<u8>,
+    total_len: u128,
+}
+
+impl Hasher {
+    pub fn new(algorithm: Algorithm) -> Self {
+        let state = match algorithm {
+            Algorithm::Md5 => State::Md5([0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476]),
+            Algorithm::Sha1 => State::Sha1(H1),
+            Algorithm::Sha224 => State::Sha256(H224),
+            Algorithm::Sha256 => State::Sha256(H256),
+            Algorithm::Sha384 => State::Sha512(H384),
+            Algorithm::Sha512 => State::Sha512(H512),
+        };
+        Hasher {
+            algorithm,
+            state,
+            buffer: Vec::with_capacity(128),
+            total_len: 0,
+        }
+    }
+
+    fn block_len(&self) -> usize {
+        match self.state {
+            State::Sha512(_) => 128,
+            _ => 64,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.total_len += data.len() as u128;
+        self.buffer.extend_from_slice(data);
+
+        let block_len = self.block_len();
+        let whole = self.buffer.len() - self.buffer.len() % block_len;
+        for block in self.buffer[..whole].chunks(block_len) {
+            self.state.process_block(block);
+        }
+        self.buffer.drain(..whole);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let block_len = self.block_len();
+        let bit_len = self.total_len * 8;
+
+        // Append the padding bit, then zeros up to the length field
+        // (8 bytes, or 16 for SHA-384/512) at the end of a block.
+        self.buffer.push(0x80);
+        while self.buffer.len() % block_len != block_len - block_len / 8 {
+            self.buffer.push(0x00);
+        }
+        match self.state {
+            State::Md5(_) => self
+                .buffer
+                .extend_from_slice(&(bit_len as u64).to_le_bytes()),
+            State::Sha512(_) => self.buffer.extend_from_slice(&bit_len.to_be_bytes()),
+            _ => self
+                .buffer
+                .extend_from_slice(&(bit_len as u64).to_be_bytes()),
+        }
+        for block in self.buffer.chunks(block_len) {
+            self.state.process_block(block);
+        }
+
+        // SHA-224 and SHA-384 keep only the leading words of their state.
+        let mut digest: Vec<u8> = match &self.state {
+            State::Md5(state) => state.iter().flat_map(|w| w.to_le_bytes()).collect(),
+            State::Sha1(state) => state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+            State::Sha256(state) => state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+            State::Sha512(state) => state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+        };
+        digest.truncate(self.algorithm.digest_len());
+        digest
+    }
+}
+
+fn md5_block(state: &mut [u32; 4], block: &[u8]) {
+    // Parse block into sixteen 32-bit little-endian words
+    let mut m = [0u32; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+
+    let [mut a, mut b, mut c, mut d] = *state;
+
+    for i in 0..64 {
+        let (f, g) = match i {
+            0..=15 => ((b & c) | ((!b) & d), i),
+            16..=31 => ((d & b) | ((!d) & c), (5 * i + 1) % 16),
+            32..=47 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | (!d)), (7 * i) % 16),
+        };
+
+        let temp = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(
+            a.wrapping_add(f)
+                .wrapping_add(T[i])
+                .wrapping_add(m[g])
+                .rotate_left(S[i]),
+        );
+        a = temp;
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+fn sha1_block(state: &mut [u32; 5], block: &[u8]) {
+    // Prepare message schedule
+    let mut w = [0u32; 80];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..80 {
+        w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+
+    for (i, &wi) in w.iter().enumerate() {
+        let (f, k) = match i {
+            0..=19 => ((b & c) | ((!b) & d), 0x5a827999u32),
+            20..=39 => (b ^ c ^ d, 0x6ed9eba1u32),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdcu32),
+            _ => (b ^ c ^ d, 0xca62c1d6u32),
+        };
+
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(wi);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+fn sha256_block(state: &mut [u32; 8], block: &[u8]) {
+    // Prepare message schedule
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (&k, &wi) in K256.iter().zip(&w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(k)
+            .wrapping_add(wi);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+fn sha512_block(state: &mut [u64; 8], block: &[u8]) {
+    // Prepare message schedule
+    let mut w = [0u64; 80];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(8)) {
+        *word = u64::from_be_bytes(bytes.try_into().unwrap());
+    }
+    for i in 16..80 {
+        let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+        let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+
+    for (&k, &wi) in K512.iter().zip(&w) {
+        let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+        let ch = (e & f) ^ ((!e) & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(k)
+            .wrapping_add(wi);
+        let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word = word.wrapping_add(value);
+    }
+}
+
+/// The `algorithm` digest of everything `reader` yields.
+pub fn digest_reader<R: Read>(algorithm: Algorithm, mut reader: R) -> io::Result<Vec<u8>> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buf = [0u8; 8192];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hasher.finish())
+}
+
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Default)]
+struct Options {
+    check: bool,
+    binary: bool,
+    tag: bool,
+    zero: bool,
+    quiet: bool,
+    status: bool,
+    warn: bool,
+    strict: bool,
+    ignore_missing: bool,
+}
+
+fn print_usage(algorithm: Algorithm, stderr: &mut dyn Write) {
+    let tool = algorithm.tool();
+    let tag = algorithm.tag();
+    let _ = writeln!(stderr, "Usage: {tool} [OPTION]... [FILE]...");
+    let _ = writeln!(stderr, "Print or check {tag} checksums.");
+    let _ = writeln!(
+        stderr,
+        "With no FILE, or when FILE is -, read standard input."
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(stderr, "  -b, --binary          read in binary mode");
+    let _ = writeln!(
+        stderr,
+        "  -c, --check           read checksums from the FILEs and check them"
+    );
+    let _ = writeln!(
+        stderr,
+        "      --tag             create a BSD-style checksum"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -t, --text            read in text mode (default)"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -z, --zero            end each output line with NUL, not newline"
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(stderr, "When checking:");
+    let _ = writeln!(
+        stderr,
+        "      --ignore-missing  don't fail or report status for missing files"
+    );
+    let _ = writeln!(
+        stderr,
+        "      --quiet           don't print OK for each successfully verified file"
+    );
+    let _ = writeln!(
+        stderr,
+        "      --status          don't output anything, status code shows success"
+    );
+    let _ = writeln!(
+        stderr,
+        "      --strict          exit non-zero for improperly formatted checksum lines"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -w, --warn            warn about improperly formatted checksum lines"
+    );
+}
+
+/// Run the `*sum` tool for `algorithm`.
+pub fn run(algorithm: Algorithm, args: &[String], io: &mut dyn Io) -> i32 {
+    let tool = algorithm.tool();
+    let mut opts = Options::default();
+    let mut files: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" => {
+                print_usage(algorithm, io.stderr());
+                return 0;
+            }
+            "--binary" => opts.binary = true,
+            "--text" => opts.binary = false,
+            "--check" => opts.check = true,
+            "--tag" => opts.tag = true,
+            "--zero" => opts.zero = true,
+            "--quiet" => opts.quiet = true,
+            "--status" => opts.status = true,
+            "--warn" => opts.warn = true,
+            "--strict" => opts.strict = true,
+            "--ignore-missing" => opts.ignore_missing = true,
+            "--" => {
+                files.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "{tool}: unrecognized option '{arg}'");
+                return 1;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for c in arg[1..].chars() {
+                    match c {
+                        'b' => opts.binary = true,
+                        't' => opts.binary = false,
+                        'c' => opts.check = true,
+                        'z' => opts.zero = true,
+                        'w' => opts.warn = true,
+                        _ => {
+                            let _ = writeln!(io.stderr(), "{tool}: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => files.push(arg),
+        }
+        i += 1;
+    }
+
+    if files.is_empty() {
+        files.push("-");
+    }
+
+    if opts.check {
+        let mut exit_code = 0;
+        for file in files {
+            exit_code |= check(algorithm, file, &opts, io);
+        }
+        return exit_code;
+    }
+
+    let mut exit_code = 0;
+    let end = if opts.zero { '\0' } else { '\n' };
+    for file in files {
+        let digest = if file == "-" {
+            io.stdin()
+        } else {
+            io.open(file)
+        }
+        .and_then(|reader| digest_reader(algorithm, reader));
+        match digest {
+            Ok(digest) => {
+                let hex = hex_string(&digest);
+                let (escape, file) = if opts.zero {
+                    ("", file.to_string())
+                } else {
+                    escape_name(file)
+                };
+                let _ = if opts.tag {
+                    write!(
+                        io.stdout(),
+                        "{escape}{} ({file}) = {hex}{end}",
+                        algorithm.tag()
+                    )
+                } else {
+                    let mode = if opts.binary { '*' } else { ' ' };
+                    write!(io.stdout(), "{escape}{hex} {mode}{file}{end}")
+                };
+            }
+            Err(e) => {
+                let _ = writeln!(io.stderr(), "{tool}: {file}: {e}");
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
+}
+
+/// `name` as a checksum line gives it, and the `\` the line must start
+/// with if that differs from `name`: as in GNU, a backslash, newline or
+/// carriage return in the name is escaped.
+fn escape_name(name: &str) -> (&'static str, String) {
+    if !name.contains(['\\', '\n', '\r']) {
+        return ("", name.to_string());
+    }
+    let escaped = name
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    ("\\", escaped)
+}
+
+/// Undo [`escape_name`]; `None` for an escape it would not produce.
+fn unescape_name(escaped: &str) -> Option<String> {
+    let mut name = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            name.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => name.push('\\'),
+            'n' => name.push('\n'),
+            'r' => name.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(name)
+}
+
+/// Split a checksum line into its hex digest and file name, in either
+/// the `HASH  FILE` form the tools print or the BSD `TAG (FILE) = HASH`.
+/// A line starting with `\` has its file name escaped.
+fn parse_check_line(algorithm: Algorithm, line: &str) -> Option<(&str, String)> {
+    if let Some(line) = line.strip_prefix('\\') {
+        let (hex, file) = parse_unescaped_line(algorithm, line)?;
+        return Some((hex, unescape_name(file)?));
+    }
+    parse_unescaped_line(algorithm, line).map(|(hex, file)| (hex, file.to_string()))
+}
+
+fn parse_unescaped_line(algorithm: Algorithm, line: &str) -> Option<(&str, &str)> {
+    let hex_len = algorithm.digest_len() * 2;
+    let is_hex = |s: &str| s.len() == hex_len && s.bytes().all(|b| b.is_ascii_hexdigit());
+
+    if let Some(rest) = line
+        .strip_prefix(algorithm.tag())
+        .and_then(|rest| rest.strip_prefix(" ("))
+    {
+        let (file, hex) = rest.rsplit_once(") = ")?;
+        return is_hex(hex).then_some((hex, file));
+    }
+
+    let (hex, rest) = line.split_at_checked(hex_len)?;
+    let file = rest
+        .strip_prefix("  ")
+        .or_else(|| rest.strip_prefix(" *"))?;
+    (is_hex(hex) && !file.is_empty()).then_some((hex, file))
+}
+
+/// Check the sums listed in `list`, returning the exit status.
+fn check(algorithm: Algorithm, list: &str, opts: &Options, io: &mut dyn Io) -> i32 {
+    let tool = algorithm.tool();
+    let list_name = if list == "-" { "standard input" } else { list };
+    let reader = if list == "-" {
+        io.stdin()
+    } else {
+        io.open(list)
+    };
+    let mut data = Vec::new();
+    if let Err(e) = reader.and_then(|mut r| r.read_to_end(&mut data)) {
+        let _ = writeln!(io.stderr(), "{tool}: {list}: {e}");
+        return 1;
+    }
+
+    let mut improper = 0;
+    let mut unreadable = 0;
+    let mut mismatched = 0;
+    let mut matched_any = false;
+    for (lineno, line) in data.split(|&b| b == b'\n').enumerate() {
+        let line = String::from_utf8_lossy(line);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((expected, file)) = parse_check_line(algorithm, line) else {
+            improper += 1;
+            if opts.warn {
+                let _ = writeln!(
+                    io.stderr(),
+                    "{tool}: {list_name}: {}: improperly formatted {} checksum line",
+                    lineno + 1,
+                    algorithm.tag()
+                );
+            }
+            continue;
+        };
+        matched_any = true;
+
+        let digest = if file == "-" {
+            io.stdin()
+        } else {
+            io.open(&file)
+        }
+        .and_then(|reader| digest_reader(algorithm, reader));
+        let (escape, shown) = escape_name(&file);
+        let file = format!("{escape}{shown}");
+        match digest {
+            Ok(digest) if hex_string(&digest).eq_ignore_ascii_case(expected) => {
+                if !opts.quiet && !opts.status {
+                    let _ = writeln!(io.stdout(), "{file}: OK");
+                }
+            }
+            Ok(_) => {
+                mismatched += 1;
+                if !opts.status {
+                    let _ = writeln!(io.stdout(), "{file}: FAILED");
+                }
+            }
+            Err(e) if opts.ignore_missing && e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                unreadable += 1;
+                if !opts.status {
+                    let _ = writeln!(io.stderr(), "{tool}: {file}: {e}");
+                    let _ = writeln!(io.stdout(), "{file}: FAILED open or read");
+                }
+            }
+        }
+    }
+
+    if !matched_any {
+        let _ = writeln!(
+            io.stderr(),
+            "{tool}: {list_name}: no properly formatted {} checksum lines found",
+            algorithm.tag()
+        );
+        return 1;
+    }
+    if !opts.status {
+        let plural = |n: usize, one: &'static str, many: &'static str| {
+            if n == 1 {
+                one
+            } else {
+                many
+            }
+        };
+        if improper > 0 {
+            let _ = writeln!(
+                io.stderr(),
+                "{tool}: WARNING: {improper} {} improperly formatted",
+                plural(improper, "line is", "lines are")
+            );
+        }
+        if unreadable > 0 {
+            let _ = writeln!(
+                io.stderr(),
+                "{tool}: WARNING: {unreadable} listed {} could not be read",
+                plural(unreadable, "file", "files")
+            );
+        }
+        if mismatched > 0 {
+            let _ = writeln!(
+                io.stderr(),
+                "{tool}: WARNING: {mismatched} computed {} did NOT match",
+                plural(mismatched, "checksum", "checksums")
+            );
+        }
+    }
+    let failed = mismatched > 0 || unreadable > 0 || (opts.strict && improper > 0);
+    i32::from(failed)
+}
@@ -0,0 +1,43 @@
+//! dirname - strip last component from file name
+
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    if args.is_empty() {
+        let _ = writeln!(io.stderr(), "dirname: missing operand");
+        return 1;
+    }
+
+    let path = &args[0];
+
+    // Remove trailing slashes
+    let trimmed = path.trim_end_matches('/');
+
+    if trimmed.is_empty() {
+        // Path was all slashes
+        let _ = writeln!(io.stdout(), "/");
+        return 0;
+    }
+
+    match trimmed.rfind('/') {
+        Some(0) => {
+            // The slash is at the root
+            let _ = writeln!(io.stdout(), "/");
+        }
+        Some(pos) => {
+            // Return everything up to the last slash
+            let dir = &trimmed[..pos];
+            // Handle case where dir would be empty (shouldn't happen since pos > 0)
+            if dir.is_empty() {
+                let _ = writeln!(io.stdout(), "/");
+            } else {
+                let _ = writeln!(io.stdout(), "{}", dir);
+            }
+        }
+        None => {
+            // No slash in the path, directory is "."
+            let _ = writeln!(io.stdout(), ".");
+        }
+    }
+    0
+}
@@ -0,0 +1,439 @@
+//! file - determine file type
+
+use std::io::{Read, Write};
+
+use crate::Io;
+
+/// How much of a file to look at.
+const READ_LIMIT: u64 = 1 << 20;
+
+/// A fixed byte signature at a fixed offset.
+struct Magic {
+    offset: usize,
+    bytes: &'static [u8],
+    description: &'static str,
+    mime: &'static str,
+}
+
+const MAGIC: &[Magic] = &[
+    Magic {
+        offset: 0,
+        bytes: b"\x89PNG\r\n\x1a\n",
+        description: "PNG image data",
+        mime: "image/png",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"\xff\xd8\xff",
+        description: "JPEG image data",
+        mime: "image/jpeg",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"GIF87a",
+        description: "GIF image data, version 87a",
+        mime: "image/gif",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"GIF89a",
+        description: "GIF image data, version 89a",
+        mime: "image/gif",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"%PDF-",
+        description: "PDF document",
+        mime: "application/pdf",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"\x1f\x8b",
+        description: "gzip compressed data",
+        mime: "application/gzip",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"BZh",
+        description: "bzip2 compressed data",
+        mime: "application/x-bzip2",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"\xfd7zXZ\x00",
+        description: "XZ compressed data",
+        mime: "application/x-xz",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"\x28\xb5\x2f\xfd",
+        description: "Zstandard compressed data",
+        mime: "application/zstd",
+    },
+    Magic {
+        offset: 257,
+        bytes: b"ustar\x0000",
+        description: "POSIX tar archive",
+        mime: "application/x-tar",
+    },
+    Magic {
+        offset: 257,
+        bytes: b"ustar  \x00",
+        description: "POSIX tar archive (GNU)",
+        mime: "application/x-tar",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"PK\x03\x04",
+        description: "Zip archive data",
+        mime: "application/zip",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"PK\x05\x06",
+        description: "Zip archive data (empty)",
+        mime: "application/zip",
+    },
+    Magic {
+        offset: 0,
+        bytes: b"SQLite format 3\x00",
+        description: "SQLite 3.x database",
+        mime: "application/vnd.sqlite3",
+    },
+];
+
+/// What a file was found to be.
+struct Kind {
+    description: String,
+    mime: &'static str,
+    /// The character set for text; `binary` otherwise.
+    encoding: &'static str,
+}
+
+impl Kind {
+    fn binary(description: impl Into<String>, mime: &'static str) -> Self {
+        Kind {
+            description: description.into(),
+            mime,
+            encoding: "binary",
+        }
+    }
+}
+
+/// Describe an ELF header: class, byte order and object type.
+fn elf(data: &[u8]) -> Option<Kind> {
+    if !data.starts_with(b"\x7fELF") || data.len() < 18 {
+        return None;
+    }
+    let class = match data[4] {
+        1 => "32-bit",
+        2 => "64-bit",
+        _ => return Some(Kind::binary("ELF", "application/octet-stream")),
+    };
+    let little_endian = data[5] == 1;
+    let e_type = if little_endian {
+        u16::from_le_bytes([data[16], data[17]])
+    } else {
+        u16::from_be_bytes([data[16], data[17]])
+    };
+    let (what, mime) = match e_type {
+        1 => ("relocatable", "application/x-object"),
+        2 => ("executable", "application/x-executable"),
+        3 => ("shared object", "application/x-sharedlib"),
+        4 => ("core file", "application/x-coredump"),
+        _ => ("unknown type", "application/octet-stream"),
+    };
+    let order = if little_endian { "LSB" } else { "MSB" };
+    Some(Kind::binary(format!("ELF {class} {order} {what}"), mime))
+}
+
+/// Describe a WebAssembly module header.
+fn wasm(data: &[u8]) -> Option<Kind> {
+    if !data.starts_with(b"\0asm") {
+        return None;
+    }
+    let mut description = String::from("WebAssembly (wasm) binary module");
+    if data.len() >= 8 {
+        let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        description.push_str(&format!(" version {version:#x}"));
+        if version == 1 {
+            description.push_str(" (MVP)");
+        }
+    }
+    Some(Kind::binary(description, "application/wasm"))
+}
+
+/// Bytes that turn up in text besides the printable ones.
+fn is_text_control(b: u8) -> bool {
+    matches!(
+        b,
+        b'\t' | b'\n' | b'\r' | b'\x0c' | b'\x1b' | b'\x08' | b'\x07'
+    )
+}
+
+/// The character set `data` is text in, if it is text.
+fn text_encoding(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    if data
+        .iter()
+        .all(|&b| b.is_ascii_graphic() || b == b' ' || is_text_control(b))
+    {
+        return Some(("ASCII", "us-ascii"));
+    }
+    if data
+        .iter()
+        .any(|&b| b < 0x20 && !is_text_control(b) || b == 0x7f)
+    {
+        return None;
+    }
+    if std::str::from_utf8(data).is_ok() {
+        return Some(("UTF-8 Unicode", "utf-8"));
+    }
+    if !data.iter().any(|b| (0x80..0xa0).contains(b)) {
+        return Some(("ISO-8859", "iso-8859-1"));
+    }
+    None
+}
+
+/// What kind of text `data` holds, from how it starts.
+fn text_kind(data: &[u8]) -> (&'static str, &'static str) {
+    if let Some(line) = data.strip_prefix(b"#!") {
+        let end = line.iter().position(|&b| b == b'\n').unwrap_or(line.len());
+        let line = String::from_utf8_lossy(&line[..end]);
+        let interpreter = line
+            .split_whitespace()
+            .find(|word| !word.ends_with("/env"))
+            .map(|word| word.rsplit('/').next().unwrap_or(word))
+            .unwrap_or("");
+        return match interpreter {
+            i if i.starts_with("python") => ("Python script", "text/x-script.python"),
+            "node" | "deno" | "bun" => ("JavaScript script", "application/javascript"),
+            "bash" => ("Bourne-Again shell script", "text/x-shellscript"),
+            "perl" => ("Perl script", "text/x-perl"),
+            "ruby" => ("Ruby script", "text/x-ruby"),
+            _ => ("POSIX shell script", "text/x-shellscript"),
+        };
+    }
+    let head = &data[..data.len().min(512)];
+    let start = head
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(head.len());
+    let head = &head[start..];
+    if head.starts_with(b"<?xml") {
+        return ("XML document", "text/xml");
+    }
+    let lower = head.to_ascii_lowercase();
+    if lower.starts_with(b"<!doctype html") || lower.starts_with(b"<html") {
+        return ("HTML document", "text/html");
+    }
+    if head.starts_with(b"{") || head.starts_with(b"[") {
+        return ("JSON text data", "application/json");
+    }
+    ("", "text/plain")
+}
+
+/// Describe text: its kind, character set and line endings.
+fn text(data: &[u8]) -> Option<Kind> {
+    let (charset, encoding) = text_encoding(data)?;
+    let (kind, mime) = text_kind(data);
+    let mut description = if kind.is_empty() {
+        format!("{charset} text")
+    } else if data.starts_with(b"#!") {
+        format!("{kind}, {charset} text executable")
+    } else if mime == "application/json" {
+        kind.to_string()
+    } else {
+        format!("{kind}, {charset} text")
+    };
+    let crlf = data.windows(2).any(|w| w == b"\r\n");
+    if !data.contains(&b'\n') {
+        description.push_str(", with no line terminators");
+    } else if crlf {
+        description.push_str(", with CRLF line terminators");
+    }
+    Some(Kind {
+        description,
+        mime,
+        encoding,
+    })
+}
+
+fn classify(data: &[u8]) -> Kind {
+    if data.is_empty() {
+        return Kind::binary("empty", "inode/x-empty");
+    }
+    if let Some(kind) = elf(data).or_else(|| wasm(data)) {
+        return kind;
+    }
+    let magic = MAGIC.iter().find(|m| {
+        data.get(m.offset..m.offset + m.bytes.len())
+            .is_some_and(|bytes| bytes == m.bytes)
+    });
+    if let Some(m) = magic {
+        return Kind::binary(m.description, m.mime);
+    }
+    text(data).unwrap_or_else(|| Kind::binary("data", "application/octet-stream"))
+}
+
+/// What to print for each file.
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    Description,
+    Mime,
+    MimeType,
+    MimeEncoding,
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: file [OPTION]... FILE...");
+    let _ = writeln!(stderr, "Determine the type of each FILE.");
+    let _ = writeln!(stderr);
+    let _ = writeln!(
+        stderr,
+        "  -b, --brief            do not prepend file names to output lines"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -E                     exit with an error if a file can't be read"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -F, --separator=SEP    separate file names from types with SEP (default ':')"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -i, --mime             output MIME type and character set"
+    );
+    let _ = writeln!(stderr, "      --mime-type        output MIME type only");
+    let _ = writeln!(stderr, "      --mime-encoding    output character set only");
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut brief = false;
+    let mut output = Output::Description;
+    let mut fail_on_error = false;
+    let mut separator = String::from(":");
+    let mut files: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" => {
+                print_usage(io.stderr());
+                return 0;
+            }
+            "--brief" => brief = true,
+            "--mime" => output = Output::Mime,
+            "--mime-type" => output = Output::MimeType,
+            "--mime-encoding" => output = Output::MimeEncoding,
+            "--dereference" | "--no-dereference" => {}
+            "--separator" => {
+                i += 1;
+                match args.get(i) {
+                    Some(sep) => separator = sep.clone(),
+                    None => {
+                        let _ = writeln!(
+                            io.stderr(),
+                            "file: option '--separator' requires an argument"
+                        );
+                        return 1;
+                    }
+                }
+            }
+            _ if arg.starts_with("--separator=") => {
+                separator = arg["--separator=".len()..].to_string();
+            }
+            "--" => {
+                files.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "file: unrecognized option '{arg}'");
+                return 1;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for (pos, c) in arg[1..].char_indices() {
+                    match c {
+                        'b' => brief = true,
+                        'i' => output = Output::Mime,
+                        'E' => fail_on_error = true,
+                        // Symlinks are always followed.
+                        'L' | 'h' => {}
+                        'F' => {
+                            let rest = &arg[1 + pos + 1..];
+                            if !rest.is_empty() {
+                                separator = rest.to_string();
+                            } else {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(sep) => separator = sep.clone(),
+                                    None => {
+                                        let _ = writeln!(
+                                            io.stderr(),
+                                            "file: option requires an argument -- 'F'"
+                                        );
+                                        return 1;
+                                    }
+                                }
+                            }
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "file: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => files.push(arg),
+        }
+        i += 1;
+    }
+
+    if files.is_empty() {
+        let _ = writeln!(io.stderr(), "Usage: file [OPTION]... FILE...");
+        return 1;
+    }
+
+    let mut exit_code = 0;
+    for file in files {
+        let kind = if file == "-" {
+            let mut data = Vec::new();
+            io.stdin()
+                .and_then(|reader| reader.take(READ_LIMIT).read_to_end(&mut data))
+                .map(|_| classify(&data))
+        } else if io.is_dir(file) {
+            Ok(Kind::binary("directory", "inode/directory"))
+        } else {
+            let mut data = Vec::new();
+            io.open(file)
+                .and_then(|reader| reader.take(READ_LIMIT).read_to_end(&mut data))
+                .map(|_| classify(&data))
+        };
+        let name = if file == "-" { "/dev/stdin" } else { file };
+        let line = match kind {
+            Ok(kind) => match output {
+                Output::Description => kind.description,
+                Output::Mime => format!("{}; charset={}", kind.mime, kind.encoding),
+                Output::MimeType => kind.mime.to_string(),
+                Output::MimeEncoding => kind.encoding.to_string(),
+            },
+            Err(e) if fail_on_error => {
+                let _ = writeln!(io.stderr(), "file: cannot open `{name}' ({e})");
+                exit_code = 1;
+                continue;
+            }
+            // Like other implementations, an unreadable file is a
+            // description rather than a failure.
+            Err(e) => format!("cannot open `{name}' ({e})"),
+        };
+        let out = io.stdout();
+        let _ = if brief {
+            writeln!(out, "{line}")
+        } else {
+            writeln!(out, "{name}{separator} {line}")
+        };
+    }
+    exit_code
+}
@@ -0,0 +1,296 @@
+//! fmt - rewrap text to a specified width
+
+use std::io::{self, BufRead, Write};
+
+use crate::Io;
+
+struct Options {
+    width: usize,
+    uniform: bool,
+    split_only: bool,
+    prefix: String,
+}
+
+/// A line split around its prefix: what comes before the text, and the
+/// text.
+struct Line<'a> {
+    /// Leading whitespace, the prefix and the indentation after it.
+    lead: &'a str,
+    text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// Split `line`, or `None` if it lacks the prefix and so is left
+    /// alone.
+    fn parse(line: &'a str, prefix: &str) -> Option<Self> {
+        let before = line.len() - line.trim_start().len();
+        let rest = line[before..].strip_prefix(prefix)?;
+        let indent = rest.len() - rest.trim_start().len();
+        let lead_len = line.len() - rest.len() + indent;
+        Some(Line {
+            lead: &line[..lead_len],
+            text: &line[lead_len..],
+        })
+    }
+}
+
+/// A word and the space that followed it in the input.
+struct Word<'a> {
+    text: &'a str,
+    gap: &'a str,
+    /// Whether the word ended its input line.
+    line_end: bool,
+}
+
+impl Word<'_> {
+    fn ends_sentence(&self) -> bool {
+        let text = self.text.trim_end_matches([')', ']', '"', '\'']);
+        text.ends_with(['.', '?', '!']) && (self.line_end || self.gap.len() >= 2)
+    }
+
+    /// The space to put after this word, when the next stays on the same
+    /// output line.
+    fn spacing<'s>(&'s self, opts: &Options) -> &'s str {
+        if self.ends_sentence() {
+            if opts.uniform || self.line_end {
+                return "  ";
+            }
+        } else if opts.uniform || self.line_end {
+            return " ";
+        }
+        self.gap
+    }
+}
+
+fn words(text: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let after = &rest[end..];
+        let gap_len = after.len() - after.trim_start().len();
+        words.push(Word {
+            text: &rest[..end],
+            gap: &after[..gap_len],
+            line_end: gap_len == after.len(),
+        });
+        rest = &after[gap_len..];
+    }
+    words
+}
+
+/// Fill lines up to the width with `words`, each line starting with
+/// `lead`.
+fn fill(words: &[Word], lead: &str, opts: &Options, out: &mut dyn Write) -> io::Result<()> {
+    let mut line = String::from(lead);
+    let mut empty = true;
+    let mut previous: Option<&Word> = None;
+    for word in words {
+        if let Some(prev) = previous.filter(|_| !empty) {
+            let gap = prev.spacing(opts);
+            let len = line.chars().count() + gap.chars().count() + word.text.chars().count();
+            if len <= opts.width {
+                line.push_str(gap);
+                line.push_str(word.text);
+                previous = Some(word);
+                continue;
+            }
+            writeln!(out, "{line}")?;
+            line.truncate(0);
+            line.push_str(lead);
+        }
+        line.push_str(word.text);
+        empty = false;
+        previous = Some(word);
+    }
+    if !empty {
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
+
+/// Reformat `reader` a paragraph at a time: runs of lines with the same
+/// prefix and indentation, ended by blank lines or a change of either.
+fn fmt_reader(reader: &mut dyn BufRead, opts: &Options, out: &mut dyn Write) -> io::Result<()> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+
+    let mut paragraph: Vec<Word> = Vec::new();
+    let mut lead: Option<&str> = None;
+    for raw in input.lines() {
+        let line = Line::parse(raw, &opts.prefix).filter(|l| !l.text.trim().is_empty());
+        let continues = match (&line, lead) {
+            (Some(line), Some(lead)) => !opts.split_only && line.lead == lead,
+            _ => false,
+        };
+        if !continues {
+            if let Some(lead) = lead.take() {
+                fill(&paragraph, lead, opts, out)?;
+                paragraph.clear();
+            }
+        }
+        match line {
+            Some(line) => {
+                lead = Some(line.lead);
+                paragraph.extend(words(line.text));
+            }
+            None => writeln!(out, "{raw}")?,
+        }
+    }
+    if let Some(lead) = lead {
+        fill(&paragraph, lead, opts, out)?;
+    }
+    Ok(())
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: fmt [OPTION]... [FILE]...");
+    let _ = writeln!(
+        stderr,
+        "Reformat each paragraph in the FILE(s), writing to standard output."
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(
+        stderr,
+        "  -p, --prefix=STRING     reformat only lines beginning with STRING,"
+    );
+    let _ = writeln!(
+        stderr,
+        "                          reattaching the prefix to reformatted lines"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -s, --split-only        split long lines, but do not refill"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -u, --uniform-spacing   one space between words, two after sentences"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -w, --width=WIDTH       maximum line width (default of 75 columns)"
+    );
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut opts = Options {
+        width: 75,
+        uniform: false,
+        split_only: false,
+        prefix: String::new(),
+    };
+    let mut files: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let mut width = None;
+        match arg {
+            "--help" => {
+                print_usage(io.stderr());
+                return 0;
+            }
+            "--uniform-spacing" => opts.uniform = true,
+            "--split-only" => opts.split_only = true,
+            "--width" | "--prefix" => {
+                i += 1;
+                let Some(val) = args.get(i) else {
+                    let _ = writeln!(io.stderr(), "fmt: option '{arg}' requires an argument");
+                    return 1;
+                };
+                if arg == "--width" {
+                    width = Some(val.as_str());
+                } else {
+                    opts.prefix = val.clone();
+                }
+            }
+            _ if arg.starts_with("--width=") => width = Some(&arg["--width=".len()..]),
+            _ if arg.starts_with("--prefix=") => {
+                opts.prefix = arg["--prefix=".len()..].to_string();
+            }
+            "--" => {
+                files.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "fmt: unrecognized option '{arg}'");
+                return 1;
+            }
+            // -WIDTH shorthand for -w WIDTH
+            _ if arg.len() > 1
+                && arg.starts_with('-')
+                && arg[1..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                width = Some(&arg[1..]);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for (pos, c) in arg[1..].char_indices() {
+                    match c {
+                        'u' => opts.uniform = true,
+                        's' => opts.split_only = true,
+                        'w' | 'p' => {
+                            let rest = &arg[1 + pos + 1..];
+                            let val = if !rest.is_empty() {
+                                rest
+                            } else {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(val) => val.as_str(),
+                                    None => {
+                                        let _ = writeln!(
+                                            io.stderr(),
+                                            "fmt: option requires an argument -- '{c}'"
+                                        );
+                                        return 1;
+                                    }
+                                }
+                            };
+                            if c == 'w' {
+                                width = Some(val);
+                            } else {
+                                opts.prefix = val.to_string();
+                            }
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "fmt: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => files.push(arg),
+        }
+        if let Some(val) = width {
+            match val.parse::<usize>() {
+                Ok(n) => opts.width = n,
+                Err(_) => {
+                    let _ = writeln!(io.stderr(), "fmt: invalid width: '{val}'");
+                    return 1;
+                }
+            }
+        }
+        i += 1;
+    }
+
+    if files.is_empty() {
+        files.push("-");
+    }
+    let mut exit_code = 0;
+    for file in files {
+        let reader = if file == "-" {
+            io.stdin()
+        } else {
+            io.open(file)
+        };
+        let result = reader.and_then(|mut reader| fmt_reader(&mut reader, &opts, io.stdout()));
+        if let Err(e) = result {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return 0;
+            }
+            let _ = writeln!(io.stderr(), "fmt: {file}: {e}");
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
@@ -0,0 +1,765 @@
+//! grep - search for patterns in files (using regex crate)
+
+use regex::RegexBuilder;
+use std::io::{self, BufRead, Read, Write};
+
+use crate::pattern::translate;
+use crate::{join_path, Io};
+
+struct Options {
+    ignore_case: bool,
+    invert: bool,
+    count_only: bool,
+    line_numbers: bool,
+    files_with_matches: bool,
+    recursive: bool,
+    extended: bool,
+    only_matching: bool,
+    word_match: bool,
+    quiet: bool,
+    fixed_string: bool,
+    suppress_errors: bool,
+    after_context: usize,
+    before_context: usize,
+    max_count: usize,
+    no_filename: bool,
+    with_filename: bool,
+    files_without_match: bool,
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+    exclude_dir_globs: Vec<String>,
+    whole_line: bool,
+    patterns: Vec<String>,
+    pattern_files: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// grep logic
+// ---------------------------------------------------------------------------
+
+fn grep_reader<R: BufRead>(
+    buf: R,
+    re: &regex::Regex,
+    opts: &Options,
+    filename: &str,
+    show_filename: bool,
+    out: &mut dyn Write,
+) -> io::Result<bool> {
+    let mut match_count: usize = 0;
+    let mut line_match_count: usize = 0;
+    let mut found = false;
+    let has_context = opts.before_context > 0 || opts.after_context > 0;
+
+    // For -B context: ring buffer of previous lines
+    let mut before_buf: Vec<(usize, String)> = Vec::new();
+    // Track how many after-context lines remain to print
+    let mut after_remaining: usize = 0;
+    // Track last printed line number to insert "--" separators
+    let mut last_printed_line: Option<usize> = None;
+
+    // Lines that aren't UTF-8 are still searched, as far as they can be.
+    let lines: Vec<String> = buf
+        .split(b'\n')
+        .map(|line| line.map(|l| String::from_utf8_lossy(&l).into_owned()))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    for (i, line) in lines.iter().enumerate() {
+        let is_match = re.is_match(line);
+        let selected = if opts.invert { !is_match } else { is_match };
+
+        if selected {
+            found = true;
+            line_match_count += 1;
+
+            if opts.quiet {
+                return Ok(true);
+            }
+
+            if opts.files_with_matches {
+                writeln!(out, "{}", filename)?;
+                return Ok(true);
+            }
+
+            if opts.files_without_match {
+                return Ok(true); // found a match, so don't list this file
+            }
+
+            // -o with -v is undefined; ignore -o in that case. -c still
+            // counts lines.
+            if opts.only_matching && !opts.invert && !opts.count_only {
+                for m in re.find_iter(line).filter(|m| !m.is_empty()) {
+                    let mut prefix = String::new();
+                    if show_filename {
+                        prefix.push_str(filename);
+                        prefix.push(':');
+                    }
+                    if opts.line_numbers {
+                        prefix.push_str(&format!("{}:", i + 1));
+                    }
+                    writeln!(out, "{}{}", prefix, m.as_str())?;
+                }
+                if opts.max_count > 0 && line_match_count >= opts.max_count {
+                    break;
+                }
+                continue;
+            }
+
+            if opts.count_only {
+                match_count += 1;
+                if opts.max_count > 0 && line_match_count >= opts.max_count {
+                    break;
+                }
+                continue;
+            }
+
+            // Print before-context lines
+            if has_context {
+                for (bi, bline) in &before_buf {
+                    // Add group separator if there's a gap
+                    if let Some(lp) = last_printed_line {
+                        if *bi > lp + 1 {
+                            writeln!(out, "--")?;
+                        }
+                    }
+                    let mut prefix = String::new();
+                    if show_filename {
+                        prefix.push_str(filename);
+                        prefix.push('-');
+                    }
+                    if opts.line_numbers {
+                        prefix.push_str(&format!("{}-", bi + 1));
+                    }
+                    writeln!(out, "{}{}", prefix, bline)?;
+                    last_printed_line = Some(*bi);
+                }
+                before_buf.clear();
+            }
+
+            // Add group separator if there's a gap
+            if has_context {
+                if let Some(lp) = last_printed_line {
+                    if i > lp + 1 {
+                        writeln!(out, "--")?;
+                    }
+                }
+            }
+
+            let mut prefix = String::new();
+            if show_filename {
+                prefix.push_str(filename);
+                prefix.push(':');
+            }
+            if opts.line_numbers {
+                prefix.push_str(&format!("{}:", i + 1));
+            }
+            writeln!(out, "{}{}", prefix, line)?;
+            last_printed_line = Some(i);
+            after_remaining = opts.after_context;
+            if opts.max_count > 0 && line_match_count >= opts.max_count {
+                // Still need to print after-context lines
+                // But stop matching new lines
+                for (j, aline) in lines.iter().enumerate().skip(i + 1) {
+                    if after_remaining == 0 {
+                        break;
+                    }
+                    let mut apfx = String::new();
+                    if show_filename {
+                        apfx.push_str(filename);
+                        apfx.push('-');
+                    }
+                    if opts.line_numbers {
+                        apfx.push_str(&format!("{}-", j + 1));
+                    }
+                    writeln!(out, "{}{}", apfx, aline)?;
+                    after_remaining -= 1;
+                }
+                break;
+            }
+        } else if after_remaining > 0 && !opts.count_only && !opts.quiet && !opts.files_with_matches
+        {
+            // Print after-context line
+            let mut prefix = String::new();
+            if show_filename {
+                prefix.push_str(filename);
+                prefix.push('-');
+            }
+            if opts.line_numbers {
+                prefix.push_str(&format!("{}-", i + 1));
+            }
+            writeln!(out, "{}{}", prefix, line)?;
+            last_printed_line = Some(i);
+            after_remaining -= 1;
+        } else {
+            // Buffer for before-context
+            if opts.before_context > 0 {
+                before_buf.push((i, line.clone()));
+                if before_buf.len() > opts.before_context {
+                    before_buf.remove(0);
+                }
+            }
+            after_remaining = 0;
+        }
+    }
+
+    if opts.count_only {
+        if opts.quiet {
+            return Ok(found);
+        }
+        if show_filename {
+            writeln!(out, "{}:{}", filename, match_count)?;
+        } else {
+            writeln!(out, "{}", match_count)?;
+        }
+    }
+
+    if opts.files_without_match {
+        writeln!(out, "{}", filename)?;
+    }
+
+    Ok(found)
+}
+
+/// Simple glob match supporting * and ? wildcards.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (plen, nlen) = (p.len(), n.len());
+    let mut dp = vec![vec![false; nlen + 1]; plen + 1];
+    dp[0][0] = true;
+    for i in 1..=plen {
+        if p[i - 1] == '*' {
+            dp[i][0] = dp[i - 1][0];
+        }
+    }
+    for i in 1..=plen {
+        for j in 1..=nlen {
+            if p[i - 1] == '*' {
+                dp[i][j] = dp[i - 1][j] || dp[i][j - 1];
+            } else if p[i - 1] == '?' || p[i - 1] == n[j - 1] {
+                dp[i][j] = dp[i - 1][j - 1];
+            }
+        }
+    }
+    dp[plen][nlen]
+}
+
+/// Search the entries of directory `dir`, or of the working directory,
+/// naming them without a `./`, if `dir` is empty.
+fn grep_dir(dir: &str, re: &regex::Regex, opts: &Options, io: &mut dyn Io) -> io::Result<bool> {
+    let mut found = false;
+    let mut entries = io.read_dir(if dir.is_empty() { "." } else { dir })?;
+    entries.sort();
+    for entry in entries {
+        let child = if dir.is_empty() {
+            entry.clone()
+        } else {
+            join_path(dir, &entry)
+        };
+        if opts.exclude_dir_globs.iter().any(|g| glob_matches(g, &entry)) && io.is_dir(&child) {
+            continue;
+        }
+        match grep_path(&child, re, opts, !opts.no_filename, io) {
+            Ok(f) => {
+                if f {
+                    found = true;
+                }
+            }
+            Err(e) => {
+                if !opts.suppress_errors {
+                    let _ = writeln!(io.stderr(), "grep: {}: {}", child, e);
+                }
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn grep_path(
+    path: &str,
+    re: &regex::Regex,
+    opts: &Options,
+    show_filename: bool,
+    io: &mut dyn Io,
+) -> io::Result<bool> {
+    if io.is_dir(path) {
+        if !opts.recursive {
+            return Err(io::Error::other("Is a directory"));
+        }
+        let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+        let excluded = opts.exclude_dir_globs.iter().any(|g| glob_matches(g, name));
+        if excluded && !matches!(name, "" | "." | "..") {
+            return Ok(false);
+        }
+        grep_dir(path, re, opts, io)
+    } else {
+        // Apply --include/--exclude filters on filename
+        let fname = path.trim_end_matches('/').rsplit('/').next();
+        if let Some(fname) = fname.filter(|n| !n.is_empty() && *n != "..") {
+            if !opts.include_globs.is_empty()
+                && !opts.include_globs.iter().any(|g| glob_matches(g, fname))
+            {
+                return Ok(false);
+            }
+            if opts.exclude_globs.iter().any(|g| glob_matches(g, fname)) {
+                return Ok(false);
+            }
+        }
+        let f = io.open(path)?;
+        grep_reader(f, re, opts, path, show_filename, io.stdout())
+    }
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut opts = Options {
+        ignore_case: false,
+        invert: false,
+        count_only: false,
+        line_numbers: false,
+        files_with_matches: false,
+        recursive: false,
+        extended: false,
+        only_matching: false,
+        word_match: false,
+        quiet: false,
+        fixed_string: false,
+        suppress_errors: false,
+        after_context: 0,
+        before_context: 0,
+        max_count: 0,
+        no_filename: false,
+        with_filename: false,
+        files_without_match: false,
+        include_globs: Vec::new(),
+        exclude_globs: Vec::new(),
+        exclude_dir_globs: Vec::new(),
+        whole_line: false,
+        patterns: Vec::new(),
+        pattern_files: Vec::new(),
+    };
+    let mut positional: Vec<String> = Vec::new();
+    let mut past_flags = false;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if past_flags {
+            positional.push(arg.clone());
+            i += 1;
+            continue;
+        }
+        if arg == "--" {
+            past_flags = true;
+            i += 1;
+            continue;
+        }
+        // Long options and options with values
+        if let Some(val) = arg.strip_prefix("--include=") {
+            opts.include_globs.push(val.to_string());
+            i += 1;
+            continue;
+        }
+        if arg == "--include" {
+            i += 1;
+            if i < args.len() {
+                opts.include_globs.push(args[i].clone());
+            }
+            i += 1;
+            continue;
+        }
+        if let Some(val) = arg.strip_prefix("--exclude=") {
+            opts.exclude_globs.push(val.to_string());
+            i += 1;
+            continue;
+        }
+        if arg == "--exclude" {
+            i += 1;
+            if i < args.len() {
+                opts.exclude_globs.push(args[i].clone());
+            }
+            i += 1;
+            continue;
+        }
+        if arg == "-A" || arg == "--after-context" {
+            i += 1;
+            if i < args.len() {
+                opts.after_context = args[i].parse().unwrap_or(0);
+            }
+            i += 1;
+            continue;
+        }
+        if arg == "-B" || arg == "--before-context" {
+            i += 1;
+            if i < args.len() {
+                opts.before_context = args[i].parse().unwrap_or(0);
+            }
+            i += 1;
+            continue;
+        }
+        if arg == "-C" || arg == "--context" {
+            i += 1;
+            if i < args.len() {
+                let n = args[i].parse().unwrap_or(0);
+                opts.before_context = n;
+                opts.after_context = n;
+            }
+            i += 1;
+            continue;
+        }
+        if arg == "-m" || arg == "--max-count" {
+            i += 1;
+            if i < args.len() {
+                opts.max_count = args[i].parse().unwrap_or(0);
+            }
+            i += 1;
+            continue;
+        }
+        if arg == "-e" || arg == "--regexp" {
+            i += 1;
+            if i < args.len() {
+                opts.patterns.push(args[i].clone());
+            }
+            i += 1;
+            continue;
+        }
+        if let Some(val) = arg.strip_prefix("--regexp=") {
+            opts.patterns.push(val.to_string());
+            i += 1;
+            continue;
+        }
+        if arg == "-f" || arg == "--file" {
+            i += 1;
+            if i < args.len() {
+                opts.pattern_files.push(args[i].clone());
+            }
+            i += 1;
+            continue;
+        }
+        if let Some(val) = arg.strip_prefix("--file=") {
+            opts.pattern_files.push(val.to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(val) = arg.strip_prefix("--exclude-dir=") {
+            opts.exclude_dir_globs.push(val.to_string());
+            i += 1;
+            continue;
+        }
+        if arg == "--exclude-dir" {
+            i += 1;
+            if i < args.len() {
+                opts.exclude_dir_globs.push(args[i].clone());
+            }
+            i += 1;
+            continue;
+        }
+        if let Some((name, val)) = arg.split_once('=').filter(|(name, _)| {
+            matches!(
+                *name,
+                "--after-context" | "--before-context" | "--context" | "--max-count"
+            )
+        }) {
+            let n = val.parse().unwrap_or(0);
+            match name {
+                "--after-context" => opts.after_context = n,
+                "--before-context" => opts.before_context = n,
+                "--context" => {
+                    opts.before_context = n;
+                    opts.after_context = n;
+                }
+                _ => opts.max_count = n,
+            }
+            i += 1;
+            continue;
+        }
+        if arg.starts_with("--") {
+            match arg.as_str() {
+                "--ignore-case" => opts.ignore_case = true,
+                "--no-ignore-case" => opts.ignore_case = false,
+                "--invert-match" => opts.invert = true,
+                "--count" => opts.count_only = true,
+                "--line-number" => opts.line_numbers = true,
+                "--files-with-matches" => opts.files_with_matches = true,
+                "--files-without-match" => opts.files_without_match = true,
+                "--recursive" | "--dereference-recursive" => opts.recursive = true,
+                "--extended-regexp" => opts.extended = true,
+                "--basic-regexp" => opts.extended = false,
+                "--fixed-strings" => opts.fixed_string = true,
+                "--only-matching" => opts.only_matching = true,
+                "--word-regexp" => opts.word_match = true,
+                "--line-regexp" => opts.whole_line = true,
+                "--quiet" | "--silent" => opts.quiet = true,
+                "--no-messages" => opts.suppress_errors = true,
+                "--no-filename" => opts.no_filename = true,
+                "--with-filename" => opts.with_filename = true,
+                // Output is never colored.
+                "--color" | "--colour" => {}
+                _ if arg.starts_with("--color=") || arg.starts_with("--colour=") => {}
+                _ => {
+                    let _ = writeln!(io.stderr(), "grep: unrecognized option '{}'", arg);
+                    return 2;
+                }
+            }
+            i += 1;
+            continue;
+        }
+        // -NUM is short for -C NUM.
+        if arg.len() > 1 && arg.starts_with('-') && arg[1..].bytes().all(|b| b.is_ascii_digit()) {
+            let n = arg[1..].parse().unwrap_or(0);
+            opts.before_context = n;
+            opts.after_context = n;
+            i += 1;
+            continue;
+        }
+        if arg.starts_with('-') && arg.len() > 1 && !arg.starts_with("--") {
+            let chars: Vec<char> = arg[1..].chars().collect();
+            let mut ci = 0;
+            while ci < chars.len() {
+                match chars[ci] {
+                    'i' => opts.ignore_case = true,
+                    'v' => opts.invert = true,
+                    'c' => opts.count_only = true,
+                    'n' => opts.line_numbers = true,
+                    'l' => opts.files_with_matches = true,
+                    'L' => opts.files_without_match = true,
+                    'r' | 'R' => opts.recursive = true,
+                    'E' => opts.extended = true,
+                    'G' => opts.extended = false,
+                    'o' => opts.only_matching = true,
+                    'w' => opts.word_match = true,
+                    'q' => opts.quiet = true,
+                    'F' => opts.fixed_string = true,
+                    's' => opts.suppress_errors = true,
+                    'h' => opts.no_filename = true,
+                    'H' => opts.with_filename = true,
+                    'x' => opts.whole_line = true,
+                    'e' => {
+                        // -ePattern or -e Pattern (next arg)
+                        let val = if ci + 1 < chars.len() {
+                            chars[ci + 1..].iter().collect::<String>()
+                        } else {
+                            i += 1;
+                            if i < args.len() { args[i].clone() } else { String::new() }
+                        };
+                        opts.patterns.push(val);
+                        break;
+                    }
+                    'f' => {
+                        // -fFILE or -f FILE (next arg)
+                        let val = if ci + 1 < chars.len() {
+                            chars[ci + 1..].iter().collect::<String>()
+                        } else {
+                            i += 1;
+                            if i < args.len() { args[i].clone() } else { String::new() }
+                        };
+                        opts.pattern_files.push(val);
+                        break;
+                    }
+                    'A' | 'B' | 'C' | 'm' => {
+                        // Value may be remainder of this arg or the next arg
+                        let val_str = if ci + 1 < chars.len() {
+                            chars[ci + 1..].iter().collect::<String>()
+                        } else {
+                            i += 1;
+                            if i < args.len() {
+                                args[i].clone()
+                            } else {
+                                "0".to_string()
+                            }
+                        };
+                        let val: usize = val_str.parse().unwrap_or(0);
+                        match chars[ci] {
+                            'A' => opts.after_context = val,
+                            'B' => opts.before_context = val,
+                            'C' => {
+                                opts.before_context = val;
+                                opts.after_context = val;
+                            }
+                            'm' => opts.max_count = val,
+                            _ => unreachable!(),
+                        }
+                        break; // consumed rest of this arg
+                    }
+                    _ => {
+                        let _ = writeln!(io.stderr(), "grep: invalid option -- '{}'", chars[ci]);
+                        return 2;
+                    }
+                }
+                ci += 1;
+            }
+        } else {
+            positional.push(arg.clone());
+        }
+        i += 1;
+    }
+
+    // Collect patterns from -e flags and (if no -e/-f flags) from first positional arg
+    if opts.patterns.is_empty() && opts.pattern_files.is_empty() {
+        if positional.is_empty() {
+            let _ = writeln!(io.stderr(), "grep: missing pattern");
+            let _ = writeln!(io.stderr(), "Usage: grep [OPTION]... PATTERN [FILE]...");
+            return 2;
+        }
+        opts.patterns.push(positional.remove(0));
+    }
+
+    // Load patterns from -f files
+    for pfile in &opts.pattern_files {
+        let mut content = String::new();
+        if pfile == "-" {
+            if let Ok(mut stdin) = io.stdin() {
+                stdin.read_to_string(&mut content).unwrap_or(0);
+            }
+        } else if let Err(e) = io
+            .open(pfile)
+            .and_then(|mut f| f.read_to_string(&mut content))
+        {
+            let _ = writeln!(io.stderr(), "grep: {}: {}", pfile, e);
+            return 2;
+        }
+        for line in content.lines() {
+            opts.patterns.push(line.to_string());
+        }
+    }
+
+    // Newlines within a single pattern string are treated as pattern separators
+    // (GNU grep behavior). Expand patterns by splitting on '\n'.
+    let expanded_patterns: Vec<String> = opts.patterns.iter()
+        .flat_map(|p| p.lines().map(|l| l.to_string()).collect::<Vec<_>>())
+        .collect();
+
+    // Build combined regex from all patterns
+    // Empty pattern list (e.g. -f empty_file) matches nothing by default.
+    let pattern_str = if expanded_patterns.is_empty() {
+        // No patterns — use a pattern that never matches any non-empty input.
+        // We handle this as a special case: with -v it inverts to "match all".
+        // Use "$a" which always fails (no content after end of string).
+        String::from("$a")
+    } else {
+        let parts: Result<Vec<String>, String> = expanded_patterns.iter().map(|p| {
+            let mut s = if opts.fixed_string {
+                regex::escape(p)
+            } else {
+                translate(p, opts.extended)?
+            };
+            if opts.whole_line {
+                s = format!("^(?:{})$", s);
+            } else if opts.word_match {
+                // Neither preceded nor followed by a word character.
+                s = format!(r"\b{{start-half}}(?:{})\b{{end-half}}", s);
+            }
+            Ok(s)
+        }).collect();
+        let parts = match parts {
+            Ok(parts) => parts,
+            Err(e) => {
+                let _ = writeln!(io.stderr(), "grep: {}", e);
+                return 2;
+            }
+        };
+        if parts.len() == 1 {
+            parts.into_iter().next().unwrap()
+        } else {
+            parts.iter().map(|p| format!("(?:{})", p)).collect::<Vec<_>>().join("|")
+        }
+    };
+    let re = match RegexBuilder::new(&pattern_str)
+        .case_insensitive(opts.ignore_case)
+        .build()
+    {
+        Ok(re) => re,
+        Err(e) => {
+            let _ = writeln!(io.stderr(), "grep: Invalid regular expression: {}", e);
+            return 2;
+        }
+    };
+    let files = &positional;
+
+    let mut found_any = false;
+    let mut had_error = false;
+
+    if files.is_empty() && opts.recursive {
+        match grep_dir("", &re, &opts, io) {
+            Ok(found) => found_any = found,
+            Err(e) => {
+                if !opts.suppress_errors {
+                    let _ = writeln!(io.stderr(), "grep: .: {}", e);
+                }
+                had_error = true;
+            }
+        }
+    } else if files.is_empty() {
+        let stdin = io.stdin();
+        let name = "(standard input)";
+        match stdin.and_then(|r| grep_reader(r, &re, &opts, name, false, io.stdout())) {
+            Ok(found) => {
+                if found {
+                    found_any = true;
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(io.stderr(), "grep: (standard input): {}", e);
+                had_error = true;
+            }
+        }
+    } else {
+        let show_filename = if opts.no_filename {
+            false
+        } else if opts.with_filename {
+            true
+        } else {
+            files.len() > 1 || opts.recursive
+        };
+        for file in files {
+            if file == "-" {
+                let stdin = io.stdin();
+                let name = "(standard input)";
+                match stdin
+                    .and_then(|r| grep_reader(r, &re, &opts, name, show_filename, io.stdout()))
+                {
+                    Ok(found) => {
+                        if found {
+                            found_any = true;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = writeln!(io.stderr(), "grep: (standard input): {}", e);
+                        had_error = true;
+                    }
+                }
+                continue;
+            }
+            match grep_path(file, &re, &opts, show_filename, io) {
+                Ok(found) => {
+                    if found {
+                        found_any = true;
+                    }
+                }
+                Err(e) => {
+                    if !opts.suppress_errors {
+                        let _ = writeln!(io.stderr(), "grep: {}: {}", file, e);
+                    }
+                    had_error = true;
+                }
+            }
+        }
+    }
+
+    if opts.files_without_match {
+        // With -L, exit 0 if any file had no matches
+        if had_error {
+            2
+        } else if found_any {
+            1
+        } else {
+            0
+        }
+    } else if had_error {
+        // Errors always produce exit code 2, even when a match was found
+        // (GNU grep behavior; -s suppresses error messages but not exit code)
+        2
+    } else if found_any {
+        0
+    } else {
+        1
+    }
+}
@@ -0,0 +1,290 @@
+//! head - output the first part of files
+
+use std::io::{self, BufRead, Read, Write};
+
+use crate::Io;
+
+/// What to print of each file.
+#[derive(Clone, Copy)]
+enum Amount {
+    /// The first N lines.
+    Lines(usize),
+    /// All but the last N lines.
+    LinesExceptLast(usize),
+    /// The first N bytes.
+    Bytes(usize),
+    /// All but the last N bytes.
+    BytesExceptLast(usize),
+}
+
+fn head_lines<R: BufRead>(mut reader: R, count: usize, stdout: &mut dyn Write) -> io::Result<()> {
+    let mut line = Vec::new();
+    for _ in 0..count {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        stdout.write_all(&line)?;
+    }
+    Ok(())
+}
+
+fn head_lines_except_last<R: BufRead>(
+    mut reader: R,
+    skip_last: usize,
+    stdout: &mut dyn Write,
+) -> io::Result<()> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        lines.push(line);
+    }
+    let end = lines.len().saturating_sub(skip_last);
+    for line in &lines[..end] {
+        stdout.write_all(line)?;
+    }
+    Ok(())
+}
+
+fn head_bytes<R: Read>(reader: R, count: usize, stdout: &mut dyn Write) -> io::Result<()> {
+    let mut buf = Vec::new();
+    reader.take(count as u64).read_to_end(&mut buf)?;
+    stdout.write_all(&buf)
+}
+
+fn head_bytes_except_last<R: Read>(
+    mut reader: R,
+    skip_last: usize,
+    stdout: &mut dyn Write,
+) -> io::Result<()> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    let end = buf.len().saturating_sub(skip_last);
+    stdout.write_all(&buf[..end])
+}
+
+/// Parse a count given to `-n` or `-c`: a number with an optional
+/// multiplier suffix, and a leading `-` meaning "all but the last".
+fn parse_count(val: &str) -> Option<(usize, bool)> {
+    let (val, except_last) = match val.strip_prefix('-') {
+        Some(rest) => (rest, true),
+        None => (val.strip_prefix('+').unwrap_or(val), false),
+    };
+    let digits = val.find(|c: char| !c.is_ascii_digit()).unwrap_or(val.len());
+    if digits == 0 {
+        return None;
+    }
+    let multiplier: usize = match &val[digits..] {
+        "" => 1,
+        "b" => 512,
+        "kB" => 1000,
+        "K" | "KiB" => 1024,
+        "MB" => 1000 * 1000,
+        "M" | "MiB" => 1024 * 1024,
+        "GB" => 1000 * 1000 * 1000,
+        "G" | "GiB" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    let n = val[..digits]
+        .parse::<usize>()
+        .ok()?
+        .checked_mul(multiplier)?;
+    Some((n, except_last))
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: head [OPTION]... [FILE]...");
+    let _ = writeln!(
+        stderr,
+        "Print the first 10 lines of each FILE to standard output."
+    );
+    let _ = writeln!(
+        stderr,
+        "With more than one FILE, precede each with a header giving the file name."
+    );
+    let _ = writeln!(
+        stderr,
+        "With no FILE, or when FILE is -, read standard input."
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(
+        stderr,
+        "  -c, --bytes=[-]NUM   print the first NUM bytes; with -, all but the last NUM"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -n, --lines=[-]NUM   print the first NUM lines; with -, all but the last NUM"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -q, --quiet          never print headers giving file names"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -v, --verbose        always print headers giving file names"
+    );
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut amount = Amount::Lines(10);
+    // Some(true) for -v, Some(false) for -q; the last one given wins.
+    let mut headers: Option<bool> = None;
+    let mut files: Vec<String> = Vec::new();
+
+    // Set `amount` from the count `val` given to -n (`bytes` false) or -c.
+    let set_amount = |amount: &mut Amount, val: &str, bytes: bool, io: &mut dyn Io| {
+        match (parse_count(val), bytes) {
+            (Some((n, false)), false) => *amount = Amount::Lines(n),
+            (Some((n, true)), false) => *amount = Amount::LinesExceptLast(n),
+            (Some((n, false)), true) => *amount = Amount::Bytes(n),
+            (Some((n, true)), true) => *amount = Amount::BytesExceptLast(n),
+            (None, _) => {
+                let what = if bytes { "bytes" } else { "lines" };
+                let _ = writeln!(io.stderr(), "head: invalid number of {what}: '{val}'");
+                return false;
+            }
+        }
+        true
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" => {
+                print_usage(io.stderr());
+                return 0;
+            }
+            "-q" | "--quiet" | "--silent" => headers = Some(false),
+            "-v" | "--verbose" => headers = Some(true),
+            "--lines" | "--bytes" => {
+                i += 1;
+                if i >= args.len() {
+                    let _ = writeln!(io.stderr(), "head: option '{arg}' requires an argument");
+                    return 1;
+                }
+                if !set_amount(&mut amount, &args[i], arg == "--bytes", io) {
+                    return 1;
+                }
+            }
+            _ if arg.starts_with("--lines=") || arg.starts_with("--bytes=") => {
+                let (name, val) = arg.split_once('=').unwrap();
+                if !set_amount(&mut amount, val, name == "--bytes", io) {
+                    return 1;
+                }
+            }
+            "--" => {
+                files.extend(args[i + 1..].iter().cloned());
+                break;
+            }
+            _ if arg.len() > 1
+                && arg.starts_with('-')
+                && arg[1..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                // -NUM shorthand for -n NUM
+                if !set_amount(&mut amount, &arg[1..], false, io) {
+                    return 1;
+                }
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 && !arg.starts_with("--") => {
+                // A cluster of short options, e.g. -qn5 or -c 100.
+                for (pos, c) in arg[1..].char_indices() {
+                    match c {
+                        'q' => headers = Some(false),
+                        'v' => headers = Some(true),
+                        'n' | 'c' => {
+                            let rest = &arg[1 + pos + 1..];
+                            let val = if !rest.is_empty() {
+                                rest
+                            } else {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(val) => val.as_str(),
+                                    None => {
+                                        let _ = writeln!(
+                                            io.stderr(),
+                                            "head: option requires an argument -- '{c}'"
+                                        );
+                                        return 1;
+                                    }
+                                }
+                            };
+                            if !set_amount(&mut amount, val, c == 'c', io) {
+                                return 1;
+                            }
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "head: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "head: unrecognized option '{arg}'");
+                return 1;
+            }
+            _ => files.push(args[i].clone()),
+        }
+        i += 1;
+    }
+
+    let mut exit_code = 0;
+    let show_headers = headers.unwrap_or(files.len() > 1);
+
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+
+    for (idx, file) in files.iter().enumerate() {
+        let name = if file == "-" {
+            "standard input"
+        } else {
+            file.as_str()
+        };
+        let reader = if file == "-" {
+            io.stdin()
+        } else {
+            match io.open(file) {
+                Ok(f) => Ok(f),
+                Err(e) => {
+                    let _ = writeln!(
+                        io.stderr(),
+                        "head: cannot open '{}' for reading: {}",
+                        file,
+                        e
+                    );
+                    exit_code = 1;
+                    continue;
+                }
+            }
+        };
+
+        if show_headers {
+            if idx > 0 {
+                let _ = writeln!(io.stdout());
+            }
+            let _ = writeln!(io.stdout(), "==> {} <==", name);
+        }
+
+        let result = reader.and_then(|reader| match amount {
+            Amount::Lines(n) => head_lines(reader, n, io.stdout()),
+            Amount::LinesExceptLast(n) => head_lines_except_last(reader, n, io.stdout()),
+            Amount::Bytes(n) => head_bytes(reader, n, io.stdout()),
+            Amount::BytesExceptLast(n) => head_bytes_except_last(reader, n, io.stdout()),
+        });
+        if let Err(e) = result {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return 0;
+            }
+            let _ = writeln!(io.stderr(), "head: {}: {}", name, e);
+            exit_code = 1;
+        }
+    }
+
+    exit_code
+}
@@ -0,0 +1,214 @@
+//! hexdump - display file contents in hexadecimal
+
+use std::io::{Read, Write};
+
+use crate::Io;
+
+/// How a byte shows in a dump's text column.
+pub(crate) fn printable(byte: u8) -> char {
+    if (0x20..=0x7e).contains(&byte) {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+/// Parse a length or offset: decimal, octal with a leading `0` or hex
+/// with `0x`, optionally scaled by a `b` (512), `k` or `m` suffix.
+pub(crate) fn parse_size(val: &str) -> Option<u64> {
+    let (digits, multiplier) = match val.as_bytes().last()? {
+        b'b' => (&val[..val.len() - 1], 512),
+        b'k' => (&val[..val.len() - 1], 1024),
+        b'm' => (&val[..val.len() - 1], 1024 * 1024),
+        _ => (val, 1),
+    };
+    let n = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16).ok()?
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    n.checked_mul(multiplier)
+}
+
+/// One line of the canonical `-C` format: offset, sixteen bytes in two
+/// groups of eight, and the bytes as text.
+fn canonical_line(out: &mut dyn Write, offset: u64, line: &[u8]) -> std::io::Result<()> {
+    let mut text = String::with_capacity(line.len());
+    write!(out, "{offset:08x} ")?;
+    for i in 0..16 {
+        if i == 8 {
+            write!(out, " ")?;
+        }
+        match line.get(i) {
+            Some(byte) => {
+                write!(out, " {byte:02x}")?;
+                text.push(printable(*byte));
+            }
+            None => write!(out, "   ")?,
+        }
+    }
+    writeln!(out, "  |{text}|")
+}
+
+/// One line of the default format: offset and eight little-endian
+/// 16-bit words, the last zero-padded.
+fn words_line(out: &mut dyn Write, offset: u64, line: &[u8]) -> std::io::Result<()> {
+    write!(out, "{offset:07x}")?;
+    for pair in line.chunks(2) {
+        let word = u16::from_le_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]);
+        write!(out, " {word:04x}")?;
+    }
+    writeln!(out)
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: hexdump [OPTION]... [FILE]...");
+    let _ = writeln!(stderr, "Display FILEs, or standard input, in hexadecimal.");
+    let _ = writeln!(stderr);
+    let _ = writeln!(stderr, "  -C            canonical hex+ASCII display");
+    let _ = writeln!(
+        stderr,
+        "  -n LENGTH     interpret only LENGTH bytes of input"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -s OFFSET     skip OFFSET bytes from the beginning"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -v            display all input data, without squeezing repeated lines"
+    );
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut canonical = false;
+    let mut squeeze = true;
+    let mut length: Option<u64> = None;
+    let mut skip: u64 = 0;
+    let mut files: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--help" => {
+                print_usage(io.stderr());
+                return 0;
+            }
+            "--canonical" => canonical = true,
+            "--no-squeezing" => squeeze = false,
+            "--" => {
+                files.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with("--") => {
+                let _ = writeln!(io.stderr(), "hexdump: unrecognized option '{arg}'");
+                return 1;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                for (pos, c) in arg[1..].char_indices() {
+                    match c {
+                        'C' => canonical = true,
+                        'v' => squeeze = false,
+                        'n' | 's' => {
+                            let rest = &arg[1 + pos + 1..];
+                            let val = if !rest.is_empty() {
+                                rest
+                            } else {
+                                i += 1;
+                                match args.get(i) {
+                                    Some(val) => val.as_str(),
+                                    None => {
+                                        let _ = writeln!(
+                                            io.stderr(),
+                                            "hexdump: option requires an argument -- '{c}'"
+                                        );
+                                        return 1;
+                                    }
+                                }
+                            };
+                            let Some(n) = parse_size(val) else {
+                                let what = if c == 'n' { "length" } else { "offset" };
+                                let _ = writeln!(io.stderr(), "hexdump: invalid {what}: '{val}'");
+                                return 1;
+                            };
+                            if c == 'n' {
+                                length = Some(n);
+                            } else {
+                                skip = n;
+                            }
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "hexdump: invalid option -- '{c}'");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => files.push(arg),
+        }
+        i += 1;
+    }
+
+    // The files make one stream, as if concatenated.
+    let mut exit_code = 0;
+    let mut data = Vec::new();
+    if files.is_empty() {
+        files.push("-");
+    }
+    for file in files {
+        let read = if file == "-" {
+            io.stdin()
+        } else {
+            io.open(file)
+        }
+        .and_then(|mut reader| reader.read_to_end(&mut data));
+        if let Err(e) = read {
+            let _ = writeln!(io.stderr(), "hexdump: {file}: {e}");
+            exit_code = 1;
+        }
+    }
+
+    let start = (skip as usize).min(data.len());
+    let end = match length {
+        Some(n) => start.saturating_add(n as usize).min(data.len()),
+        None => data.len(),
+    };
+    let data = &data[start..end];
+
+    let out = io.stdout();
+    let mut previous: Option<&[u8]> = None;
+    let mut squeezing = false;
+    for (n, line) in data.chunks(16).enumerate() {
+        let offset = start as u64 + n as u64 * 16;
+        if squeeze && line.len() == 16 && previous == Some(line) {
+            if !squeezing {
+                let _ = writeln!(out, "*");
+                squeezing = true;
+            }
+            continue;
+        }
+        squeezing = false;
+        previous = Some(line);
+        let _ = if canonical {
+            canonical_line(out, offset, line)
+        } else {
+            words_line(out, offset, line)
+        };
+    }
+    if !data.is_empty() {
+        let offset = (start + data.len()) as u64;
+        let _ = if canonical {
+            writeln!(out, "{offset:08x}")
+        } else {
+            writeln!(out, "{offset:07x}")
+        };
+    }
+    exit_code
+}
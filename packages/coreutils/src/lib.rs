@@ -0,0 +1,274 @@
+//! Utilities shared between the coreutils binaries and the shell.
+//!
+//! Every tool is a program under `src/bin`. The ones listed in [`APPLETS`]
+//! are written against [`Io`] instead of the process's own stdio and files,
+//! so the shell can run them in-process, without a host spawn; their
+//! binaries are thin wrappers that run them over [`StdIo`].
+//!
+//! The `coreutils` binary holds every tool: it runs the one named by its
+//! `argv[0]`, or by its first argument, so a host can ship one module in
+//! place of a hundred.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+
+pub mod base32;
+pub mod base64;
+pub mod basename;
+pub mod basenc;
+pub mod cat;
+pub mod cp;
+pub mod digest;
+pub mod dirname;
+pub mod file;
+pub mod fmt;
+pub mod grep;
+pub mod head;
+pub mod hexdump;
+pub mod md5sum;
+mod pattern;
+pub mod printf;
+pub mod rev;
+pub mod sed;
+pub mod sha1sum;
+pub mod sha224sum;
+pub mod sha256sum;
+pub mod sha384sum;
+pub mod sha512sum;
+pub mod tac;
+pub mod wc;
+pub mod xxd;
+
+/// A utility's entry point: its arguments, without the program name, and
+/// the I/O it runs against. Returns the exit status.
+pub type Applet = fn(&[String], &mut dyn Io) -> i32;
+
+/// The utilities that can run against any [`Io`], by name.
+pub const APPLETS: &[(&str, Applet)] = &[
+    ("base32", base32::run),
+    ("base64", base64::run),
+    ("basename", basename::run),
+    ("cat", cat::run),
+    ("cp", cp::run),
+    ("dirname", dirname::run),
+    ("file", file::run),
+    ("fmt", fmt::run),
+    ("grep", grep::run),
+    ("head", head::run),
+    ("hexdump", hexdump::run),
+    ("md5sum", md5sum::run),
+    ("printf", printf::run),
+    ("rev", rev::run),
+    ("sed", sed::run),
+    ("sha1sum", sha1sum::run),
+    ("sha224sum", sha224sum::run),
+    ("sha256sum", sha256sum::run),
+    ("sha384sum", sha384sum::run),
+    ("sha512sum", sha512sum::run),
+    ("tac", tac::run),
+    ("wc", wc::run),
+    ("xxd", xxd::run),
+];
+
+/// The applet called `name`, if there is one.
+pub fn lookup(name: &str) -> Option<Applet> {
+    APPLETS
+        .iter()
+        .find(|(applet, _)| *applet == name)
+        .map(|&(_, run)| run)
+}
+
+/// Standard streams and files, as seen by an applet. Relative paths are
+/// relative to the applet's working directory.
+pub trait Io {
+    /// Standard input. Whatever one reader consumes is gone for the next.
+    fn stdin(&mut self) -> io::Result<Box<dyn BufRead>>;
+    /// Open the file at `path` for reading.
+    fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>>;
+    /// Whether `path` is a directory.
+    fn is_dir(&self, path: &str) -> bool;
+    /// The names of the entries of directory `path`, in no particular order.
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>>;
+    /// Replace the contents of `path` such that a failed write leaves the
+    /// old contents in place.
+    fn write_atomic(&self, path: &str, data: &[u8]) -> io::Result<()>;
+    /// Append `data` to `path`, creating it if needed.
+    fn append(&self, path: &str, data: &[u8]) -> io::Result<()>;
+    /// What `path` is, following symlinks.
+    fn metadata(&self, path: &str) -> io::Result<Metadata>;
+    /// Create the directory `path`; its parent must exist.
+    fn create_dir(&self, path: &str) -> io::Result<()>;
+    /// Copy the file `from` to `to`, with its permission bits, replacing
+    /// any file there.
+    fn copy_file(&self, from: &str, to: &str) -> io::Result<()>;
+    /// Set the permission bits of `path`.
+    fn set_mode(&self, path: &str, mode: u32) -> io::Result<()>;
+    /// Set the modification time of `path`, in ms since the Unix epoch.
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> io::Result<()>;
+    fn stdout(&mut self) -> &mut dyn Write;
+    fn stderr(&mut self) -> &mut dyn Write;
+}
+
+/// What [`Io::metadata`] reports about a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    /// Permission bits, e.g. `0o644`.
+    pub mode: u32,
+    /// Modification time, in ms since the Unix epoch.
+    pub mtime_ms: u64,
+}
+
+/// The process's own stdio and filesystem.
+pub struct StdIo {
+    stdout: io::StdoutLock<'static>,
+    stderr: io::Stderr,
+}
+
+impl StdIo {
+    pub fn new() -> Self {
+        StdIo {
+            stdout: io::stdout().lock(),
+            stderr: io::stderr(),
+        }
+    }
+}
+
+impl Default for StdIo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Io for StdIo {
+    fn stdin(&mut self) -> io::Result<Box<dyn BufRead>> {
+        Ok(Box::new(io::stdin().lock()))
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        Path::new(path).is_dir()
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        Ok(fs::read_dir(path)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect())
+    }
+
+    fn write_atomic(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        codepod_process::write_atomic(path, data)
+    }
+
+    fn append(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(data)
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let meta = fs::metadata(path)?;
+        let mtime_ms = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as u64);
+        Ok(Metadata {
+            is_dir: meta.is_dir(),
+            mode: permission_bits(&meta),
+            mtime_ms,
+        })
+    }
+
+    fn create_dir(&self, path: &str) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+
+    fn copy_file(&self, from: &str, to: &str) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    #[cfg(unix)]
+    fn set_mode(&self, path: &str, mode: u32) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(not(unix))]
+    fn set_mode(&self, _path: &str, _mode: u32) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> io::Result<()> {
+        File::open(path)?.set_modified(UNIX_EPOCH + Duration::from_millis(mtime_ms))
+    }
+
+    fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.stdout
+    }
+
+    fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.stderr
+    }
+}
+
+#[cfg(unix)]
+fn permission_bits(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+/// WASI has no permission bits; report the usual defaults.
+#[cfg(not(unix))]
+fn permission_bits(meta: &fs::Metadata) -> u32 {
+    match (meta.is_dir(), meta.permissions().readonly()) {
+        (true, _) => 0o755,
+        (false, false) => 0o644,
+        (false, true) => 0o444,
+    }
+}
+
+/// Leading process arguments that [`args`] leaves out.
+static ARGS_SKIPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// The program's arguments, its name first, like [`std::env::args`]. Tools
+/// read their arguments through this rather than the process's, so that
+/// `coreutils cat -n` hands `cat -n` to cat.
+pub fn args() -> std::iter::Skip<std::env::Args> {
+    std::env::args().skip(ARGS_SKIPPED.load(Ordering::Relaxed))
+}
+
+/// Leave the first `n` process arguments out of [`args`], for a multi-call
+/// binary told which tool to run by its first argument.
+pub fn skip_args(n: usize) {
+    ARGS_SKIPPED.store(n, Ordering::Relaxed);
+}
+
+/// Run `applet` as the whole program: over [`StdIo`], with the program's
+/// [`args`], exiting with its status.
+pub fn run_main(applet: Applet) -> ! {
+    let args: Vec<String> = args().skip(1).collect();
+    let mut io = StdIo::new();
+    let code = applet(&args, &mut io);
+    let _ = io.stdout.flush();
+    process::exit(code);
+}
+
+/// `dir`'s entry `name`, joined the way `Path::join` would.
+pub(crate) fn join_path(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{dir}{name}")
+    } else {
+        format!("{dir}/{name}")
+    }
+}
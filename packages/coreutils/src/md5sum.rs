@@ -0,0 +1,8 @@
+//! md5sum - compute and check MD5 message digests
+
+use crate::digest::{self, Algorithm};
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    digest::run(Algorithm::Md5, args, io)
+}
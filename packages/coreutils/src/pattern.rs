@@ -0,0 +1,195 @@
+//! POSIX regular expressions, as grep and sed read them, translated into
+//! the syntax of the `regex` crate.
+
+/// Translate a basic (`extended` false) or extended regular expression.
+///
+/// Fails, with GNU's message, on a BRE `\{` that does not open a valid
+/// interval. The result may still fail to compile, e.g. for an unmatched
+/// `(`; the caller reports that.
+pub(crate) fn translate(pattern: &str, extended: bool) -> Result<String, String> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::new();
+    // Whether the next token starts an expression, where `*` is literal
+    // and a BRE `^` is an anchor.
+    let mut at_start = true;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let starting = at_start;
+        at_start = false;
+        match c {
+            '[' => i = bracket(&chars, i, &mut out),
+            '\\' if i + 1 < chars.len() => {
+                let next = chars[i + 1];
+                i += 2;
+                match next {
+                    '(' | '|' if !extended => {
+                        out.push(next);
+                        at_start = true;
+                    }
+                    ')' | '+' | '?' if !extended => out.push(next),
+                    '{' if !extended => {
+                        let Some(end) = interval(&chars, i, true) else {
+                            let closed = chars[i..].windows(2).any(|w| w == ['\\', '}']);
+                            return Err(if closed {
+                                r"Invalid content of \{\}".to_string()
+                            } else {
+                                r"Unmatched \{".to_string()
+                            });
+                        };
+                        push_interval(&mut out, &chars[i..end - 2]);
+                        i = end;
+                    }
+                    '<' => out.push_str(r"\b{start}"),
+                    '>' => out.push_str(r"\b{end}"),
+                    '`' => out.push_str(r"\A"),
+                    '\'' => out.push_str(r"\z"),
+                    _ => {
+                        out.push('\\');
+                        out.push(next);
+                    }
+                }
+            }
+            '(' | '|' if extended => {
+                out.push(c);
+                at_start = true;
+                i += 1;
+            }
+            '{' if extended => {
+                // Not an interval, `{` is an ordinary character.
+                match interval(&chars, i + 1, false) {
+                    Some(end) => {
+                        push_interval(&mut out, &chars[i + 1..end - 1]);
+                        i = end;
+                    }
+                    None => {
+                        out.push_str(r"\{");
+                        i += 1;
+                    }
+                }
+            }
+            '(' | ')' | '+' | '?' | '{' | '}' | '|' if !extended => {
+                out.push('\\');
+                out.push(c);
+                i += 1;
+            }
+            '*' if starting => {
+                out.push_str(r"\*");
+                i += 1;
+            }
+            '^' => {
+                if starting || extended {
+                    out.push('^');
+                    at_start = true;
+                } else {
+                    out.push_str(r"\^");
+                }
+                i += 1;
+            }
+            '$' => {
+                let anchors = extended
+                    || i + 1 == chars.len()
+                    || (chars[i + 1] == '\\' && matches!(chars.get(i + 2), Some(')' | '|')));
+                out.push_str(if anchors { "$" } else { r"\$" });
+                i += 1;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// If an interval's bounds start at `chars[start]`, the index just past
+/// its closing brace: `}`, or `\}` for a BRE.
+fn interval(chars: &[char], start: usize, basic: bool) -> Option<usize> {
+    let mut i = start;
+    let mut digits = 0;
+    let mut comma = false;
+    while let Some(&c) = chars.get(i) {
+        match c {
+            '0'..='9' => digits += 1,
+            ',' if !comma => comma = true,
+            '\\' if basic && chars.get(i + 1) == Some(&'}') => {
+                return (digits > 0).then_some(i + 2);
+            }
+            '}' if !basic => return (digits > 0).then_some(i + 1),
+            _ => return None,
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Write an interval with the given bounds, `n`, `n,`, `n,m` or `,m`;
+/// the last means `0,m`.
+fn push_interval(out: &mut String, bounds: &[char]) {
+    out.push('{');
+    if bounds.first() == Some(&',') {
+        out.push('0');
+    }
+    out.extend(bounds);
+    out.push('}');
+}
+
+/// Copy the bracket expression opening at `chars[start]`, returning the
+/// index past it. Backslashes are literal inside one, and a `]` first in
+/// the list is a member rather than the end.
+fn bracket(chars: &[char], start: usize, out: &mut String) -> usize {
+    let mut i = start + 1;
+    let mut set = String::from("[");
+    if chars.get(i) == Some(&'^') {
+        set.push('^');
+        i += 1;
+    }
+    let first = i;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ']' if i > first => {
+                set.push(']');
+                out.push_str(&set);
+                return i + 1;
+            }
+            '[' if matches!(chars.get(i + 1), Some(':' | '.' | '=')) => {
+                let kind = chars[i + 1];
+                let close = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == kind && chars[j + 1] == ']');
+                match close {
+                    Some(j) if kind == ':' => {
+                        set.extend(&chars[i..j + 2]);
+                        i = j + 2;
+                    }
+                    // A collating element or equivalence class stands for
+                    // its one character.
+                    Some(j) => {
+                        for &member in &chars[i + 2..j] {
+                            push_member(&mut set, member);
+                        }
+                        i = j + 2;
+                    }
+                    None => {
+                        push_member(&mut set, c);
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                push_member(&mut set, c);
+                i += 1;
+            }
+        }
+    }
+    // Unterminated: leave it for the regex compiler to reject.
+    out.push_str(&set);
+    chars.len()
+}
+
+fn push_member(set: &mut String, c: char) {
+    if matches!(c, '\\' | '[' | ']' | '&' | '~' | '^') {
+        set.push('\\');
+    }
+    set.push(c);
+}
@@ -0,0 +1,671 @@
+//! printf - format and print data
+
+use std::io::Write;
+
+use crate::Io;
+
+/// A conversion specification's flags, width and precision.
+#[derive(Default)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    /// `sign` and `digits` padded out to the width. Zeros go between the
+    /// two when `zeros` allows it.
+    fn pad_number(&self, sign: &str, digits: &str, zeros: bool, out: &mut Vec<u8>) {
+        let len = sign.len() + digits.len();
+        let fill = self.width.saturating_sub(len);
+        if self.left {
+            out.extend_from_slice(sign.as_bytes());
+            out.extend_from_slice(digits.as_bytes());
+            out.resize(out.len() + fill, b' ');
+        } else if self.zero && zeros {
+            out.extend_from_slice(sign.as_bytes());
+            out.resize(out.len() + fill, b'0');
+            out.extend_from_slice(digits.as_bytes());
+        } else {
+            out.resize(out.len() + fill, b' ');
+            out.extend_from_slice(sign.as_bytes());
+            out.extend_from_slice(digits.as_bytes());
+        }
+    }
+
+    /// `text` padded with spaces out to the width, counted in characters.
+    fn pad_text(&self, text: &[u8], out: &mut Vec<u8>) {
+        let len = String::from_utf8_lossy(text).chars().count();
+        let fill = self.width.saturating_sub(len);
+        if !self.left {
+            out.resize(out.len() + fill, b' ');
+        }
+        out.extend_from_slice(text);
+        if self.left {
+            out.resize(out.len() + fill, b' ');
+        }
+    }
+}
+
+/// Interpret the escape at `bytes[*i]`, a backslash, appending what it
+/// stands for to `out` and moving `*i` past it. In `%b` arguments an
+/// octal escape may also be written `\0NNN`. Returns `false` for `\c`,
+/// which ends all output.
+fn escape(bytes: &[u8], i: &mut usize, in_arg: bool, out: &mut Vec<u8>) -> bool {
+    *i += 1;
+    let Some(&c) = bytes.get(*i) else {
+        out.push(b'\\');
+        return true;
+    };
+    *i += 1;
+    let digits = |i: &mut usize, radix: u32, max: usize| {
+        let mut value: u32 = 0;
+        let mut count = 0;
+        while count < max {
+            match bytes.get(*i).and_then(|&b| (b as char).to_digit(radix)) {
+                Some(d) => value = value * radix + d,
+                None => break,
+            }
+            *i += 1;
+            count += 1;
+        }
+        (value, count)
+    };
+    match c {
+        b'a' => out.push(0x07),
+        b'b' => out.push(0x08),
+        b'c' => return false,
+        b'e' => out.push(0x1b),
+        b'f' => out.push(0x0c),
+        b'n' => out.push(b'\n'),
+        b'r' => out.push(b'\r'),
+        b't' => out.push(b'\t'),
+        b'v' => out.push(0x0b),
+        b'\\' | b'"' | b'\'' | b'?' => out.push(c),
+        b'0' if in_arg => out.push(digits(i, 8, 3).0 as u8),
+        b'0'..=b'7' => {
+            *i -= 1;
+            out.push(digits(i, 8, 3).0 as u8);
+        }
+        b'x' => match digits(i, 16, 2) {
+            (_, 0) => out.extend_from_slice(b"\\x"),
+            (value, _) => out.push(value as u8),
+        },
+        b'u' | b'U' => {
+            let len = if c == b'u' { 4 } else { 8 };
+            match digits(i, 16, len) {
+                (value, n) if n > 0 => {
+                    let ch = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+                    out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => {
+                    out.push(b'\\');
+                    out.push(c);
+                }
+            }
+        }
+        _ => {
+            out.push(b'\\');
+            out.push(c);
+        }
+    }
+    true
+}
+
+/// Quote `s` so a shell reads it back as the same word.
+fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./:,+@=%^".contains(c);
+    if s.is_empty() {
+        return "''".to_string();
+    }
+    // `~` and `#` are only special at the start of a word.
+    if s.starts_with(safe) && s.chars().all(|c| safe(c) || c == '~' || c == '#') {
+        return s.to_string();
+    }
+    if !s.chars().any(char::is_control) {
+        if !s.contains('\'') {
+            return format!("'{s}'");
+        }
+        if !s.contains(['"', '$', '`', '\\', '!']) {
+            return format!("\"{s}\"");
+        }
+    }
+    // Quote printable runs, and write control characters as $'...'.
+    let mut quoted = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_control() {
+            quoted.push_str("$'");
+            while let Some(&c) = chars.peek().filter(|c| c.is_control()) {
+                match c {
+                    '\x07' => quoted.push_str("\\a"),
+                    '\x08' => quoted.push_str("\\b"),
+                    '\t' => quoted.push_str("\\t"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\x0b' => quoted.push_str("\\v"),
+                    '\x0c' => quoted.push_str("\\f"),
+                    '\r' => quoted.push_str("\\r"),
+                    '\x1b' => quoted.push_str("\\E"),
+                    _ => {
+                        for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                            quoted.push_str(&format!("\\{byte:03o}"));
+                        }
+                    }
+                }
+                chars.next();
+            }
+            quoted.push('\'');
+        } else {
+            quoted.push('\'');
+            while let Some(c) = chars.next_if(|c| !c.is_control()) {
+                if c == '\'' {
+                    quoted.push_str("'\\''");
+                } else {
+                    quoted.push(c);
+                }
+            }
+            quoted.push('\'');
+        }
+    }
+    quoted
+}
+
+/// Format `abs`, a non-negative finite number, as `%e` does, with the
+/// exponent signed and at least two digits.
+fn exponent_form(abs: f64, precision: usize, upper: bool, alt: bool) -> String {
+    let formatted = format!("{abs:.precision$e}");
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let point = if alt && precision == 0 { "." } else { "" };
+    let e = if upper { 'E' } else { 'e' };
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{mantissa}{point}{e}{sign}{:02}", exponent.abs())
+}
+
+/// Drop trailing zeros after the decimal point, and the point if nothing
+/// follows it, keeping any exponent.
+fn strip_zeros(s: &str) -> String {
+    let (number, exponent) = match s.find(['e', 'E']) {
+        Some(pos) => s.split_at(pos),
+        None => (s, ""),
+    };
+    if !number.contains('.') {
+        return s.to_string();
+    }
+    let number = number.trim_end_matches('0').trim_end_matches('.');
+    format!("{number}{exponent}")
+}
+
+/// Format `abs` for the floating-point conversion `conv`.
+fn format_float(abs: f64, conv: u8, spec: &Spec) -> String {
+    let upper = conv.is_ascii_uppercase();
+    if !abs.is_finite() {
+        let text = if abs.is_nan() { "nan" } else { "inf" };
+        return if upper {
+            text.to_ascii_uppercase()
+        } else {
+            text.to_string()
+        };
+    }
+    let precision = spec.precision.unwrap_or(6);
+    match conv.to_ascii_lowercase() {
+        b'f' => {
+            let mut s = format!("{abs:.precision$}");
+            if spec.alt && precision == 0 {
+                s.push('.');
+            }
+            s
+        }
+        b'e' => exponent_form(abs, precision, upper, spec.alt),
+        _ => {
+            let precision = precision.max(1);
+            let exponent = if abs == 0.0 {
+                0
+            } else {
+                let formatted = format!("{abs:.*e}", precision - 1);
+                formatted.split_once('e').unwrap().1.parse::<i32>().unwrap()
+            };
+            let s = if exponent < -4 || exponent >= precision as i32 {
+                exponent_form(abs, precision - 1, upper, spec.alt)
+            } else {
+                let decimals = (precision as i32 - 1 - exponent) as usize;
+                let mut s = format!("{abs:.decimals$}");
+                if spec.alt && decimals == 0 {
+                    s.push('.');
+                }
+                s
+            };
+            if spec.alt {
+                s
+            } else {
+                strip_zeros(&s)
+            }
+        }
+    }
+}
+
+/// Parse an integer the way C's strtol does with base 0: `0x` hex, a
+/// leading `0` octal, else decimal. Returns the value and the length of
+/// the text it used.
+fn parse_integer(text: &str) -> Option<(i128, usize)> {
+    let trimmed = text.trim_start();
+    let mut pos = text.len() - trimmed.len();
+    let negative = match trimmed.as_bytes().first() {
+        Some(b'-') => {
+            pos += 1;
+            true
+        }
+        Some(b'+') => {
+            pos += 1;
+            false
+        }
+        _ => false,
+    };
+    let rest = &text[pos..];
+    let (radix, skip) = if (rest.starts_with("0x") || rest.starts_with("0X"))
+        && rest[2..].starts_with(|c: char| c.is_ascii_hexdigit())
+    {
+        (16, 2)
+    } else if rest.starts_with('0') {
+        (8, 0)
+    } else {
+        (10, 0)
+    };
+    let digits = &rest[skip..];
+    let len = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(digits.len());
+    if len == 0 {
+        return None;
+    }
+    let mut value: i128 = 0;
+    for c in digits[..len].chars() {
+        value = value
+            .saturating_mul(radix as i128)
+            .saturating_add(c.to_digit(radix).unwrap() as i128);
+    }
+    Some((if negative { -value } else { value }, pos + skip + len))
+}
+
+/// The longest prefix of `text` that reads as a float, and its length.
+fn parse_float(text: &str) -> Option<(f64, usize)> {
+    let start = text.len() - text.trim_start().len();
+    (start + 1..=text.len())
+        .rev()
+        .filter(|&end| text.is_char_boundary(end))
+        .find_map(|end| {
+            text[start..end]
+                .parse::<f64>()
+                .ok()
+                .map(|value| (value, end))
+        })
+}
+
+/// The character code a numeric argument written `'c` or `"c` stands for.
+fn char_constant(text: &str) -> Option<u32> {
+    let rest = text.strip_prefix(['\'', '"'])?;
+    Some(rest.chars().next().map_or(0, |c| c as u32))
+}
+
+struct Printer<'a> {
+    args: &'a [String],
+    next: usize,
+    out: Vec<u8>,
+    errors: Vec<String>,
+}
+
+impl<'a> Printer<'a> {
+    fn next_arg(&mut self) -> Option<&'a str> {
+        let args = self.args;
+        let arg = args.get(self.next)?;
+        self.next += 1;
+        Some(arg)
+    }
+
+    /// The next argument as an integer, or 0 with a complaint if it isn't
+    /// one.
+    fn int_arg(&mut self) -> i128 {
+        let Some(text) = self.next_arg() else {
+            return 0;
+        };
+        if let Some(code) = char_constant(text) {
+            return code as i128;
+        }
+        let text = text.to_string();
+        match parse_integer(&text) {
+            Some((value, len)) => {
+                if len < text.len() {
+                    self.errors
+                        .push(format!("'{text}': value not completely converted"));
+                }
+                value
+            }
+            None => {
+                self.errors
+                    .push(format!("'{text}': expected a numeric value"));
+                0
+            }
+        }
+    }
+
+    fn float_arg(&mut self) -> f64 {
+        let Some(text) = self.next_arg() else {
+            return 0.0;
+        };
+        if let Some(code) = char_constant(text) {
+            return code as f64;
+        }
+        let text = text.to_string();
+        match parse_float(&text) {
+            Some((value, len)) => {
+                if len < text.len() {
+                    self.errors
+                        .push(format!("'{text}': value not completely converted"));
+                }
+                value
+            }
+            None => {
+                self.errors
+                    .push(format!("'{text}': expected a numeric value"));
+                0.0
+            }
+        }
+    }
+
+    /// An integer in `min..=max`, complaining if it had to be clamped.
+    fn clamp(&mut self, value: i128, min: i128, max: i128) -> i128 {
+        if value < min || value > max {
+            let text = &self.args[self.next - 1];
+            self.errors
+                .push(format!("'{text}': Numerical result out of range"));
+        }
+        value.clamp(min, max)
+    }
+
+    fn integer(&mut self, conv: u8, spec: &Spec) {
+        let value = self.int_arg();
+        let (sign, mut digits) = if matches!(conv, b'd' | b'i') {
+            let value = self.clamp(value, i64::MIN as i128, i64::MAX as i128);
+            let sign = if value < 0 {
+                "-"
+            } else if spec.plus {
+                "+"
+            } else if spec.space {
+                " "
+            } else {
+                ""
+            };
+            (sign, value.unsigned_abs().to_string())
+        } else {
+            // Negative numbers wrap around, as in C.
+            let value = self.clamp(value, i64::MIN as i128, u64::MAX as i128);
+            let value = if value < 0 {
+                value as i64 as u64
+            } else {
+                value as u64
+            };
+            let digits = match conv {
+                b'o' => format!("{value:o}"),
+                b'x' => format!("{value:x}"),
+                b'X' => format!("{value:X}"),
+                _ => value.to_string(),
+            };
+            let sign = match conv {
+                b'x' if spec.alt && value != 0 => "0x",
+                b'X' if spec.alt && value != 0 => "0X",
+                _ => "",
+            };
+            (sign, digits)
+        };
+        if let Some(precision) = spec.precision {
+            if precision == 0 && digits == "0" {
+                digits.clear();
+            } else if digits.len() < precision {
+                digits.insert_str(0, &"0".repeat(precision - digits.len()));
+            }
+        }
+        if conv == b'o' && spec.alt && !digits.starts_with('0') {
+            digits.insert(0, '0');
+        }
+        spec.pad_number(sign, &digits, spec.precision.is_none(), &mut self.out);
+    }
+
+    fn float(&mut self, conv: u8, spec: &Spec) {
+        let value = self.float_arg();
+        let sign = if value.is_sign_negative() && !value.is_nan() {
+            "-"
+        } else if spec.plus {
+            "+"
+        } else if spec.space {
+            " "
+        } else {
+            ""
+        };
+        let body = format_float(value.abs(), conv, spec);
+        spec.pad_number(sign, &body, value.is_finite(), &mut self.out);
+    }
+
+    /// Write `format` once, consuming arguments as it goes. Returns
+    /// `false` once output must stop: at `\c`, or an invalid conversion.
+    fn format_once(&mut self, format: &[u8]) -> bool {
+        let mut i = 0;
+        while i < format.len() {
+            match format[i] {
+                b'\\' => {
+                    if !escape(format, &mut i, false, &mut self.out) {
+                        return false;
+                    }
+                }
+                b'%' => {
+                    if !self.conversion(format, &mut i) {
+                        return false;
+                    }
+                }
+                c => {
+                    self.out.push(c);
+                    i += 1;
+                }
+            }
+        }
+        true
+    }
+
+    /// Write the conversion starting at `format[*i]`, a `%`.
+    fn conversion(&mut self, format: &[u8], i: &mut usize) -> bool {
+        let start = *i;
+        *i += 1;
+        if format.get(*i) == Some(&b'%') {
+            self.out.push(b'%');
+            *i += 1;
+            return true;
+        }
+
+        let mut spec = Spec::default();
+        while let Some(&c) = format.get(*i) {
+            match c {
+                b'-' => spec.left = true,
+                b'+' => spec.plus = true,
+                b' ' => spec.space = true,
+                b'#' => spec.alt = true,
+                b'0' => spec.zero = true,
+                // Thousands grouping, which the C locale doesn't do.
+                b'\'' => {}
+                _ => break,
+            }
+            *i += 1;
+        }
+        if format.get(*i) == Some(&b'*') {
+            *i += 1;
+            let width = self.int_arg();
+            if width < 0 {
+                spec.left = true;
+            }
+            spec.width = width.unsigned_abs().min(usize::MAX as u128) as usize;
+        } else {
+            while let Some(d) = format.get(*i).filter(|c| c.is_ascii_digit()) {
+                spec.width = spec
+                    .width
+                    .saturating_mul(10)
+                    .saturating_add((d - b'0') as usize);
+                *i += 1;
+            }
+        }
+        if format.get(*i) == Some(&b'.') {
+            *i += 1;
+            if format.get(*i) == Some(&b'*') {
+                *i += 1;
+                let precision = self.int_arg();
+                // A negative precision counts as none.
+                spec.precision = (precision >= 0).then_some(precision as usize);
+            } else {
+                let mut precision: usize = 0;
+                while let Some(d) = format.get(*i).filter(|c| c.is_ascii_digit()) {
+                    precision = precision
+                        .saturating_mul(10)
+                        .saturating_add((d - b'0') as usize);
+                    *i += 1;
+                }
+                spec.precision = Some(precision);
+            }
+        }
+        while format.get(*i).is_some_and(|c| b"hlLjzt".contains(c)) {
+            *i += 1;
+        }
+
+        let Some(&conv) = format.get(*i) else {
+            let text = String::from_utf8_lossy(&format[start..]);
+            self.errors
+                .push(format!("{text}: invalid conversion specification"));
+            return false;
+        };
+        *i += 1;
+        match conv {
+            b'd' | b'i' | b'o' | b'u' | b'x' | b'X' => self.integer(conv, &spec),
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' => self.float(conv, &spec),
+            b's' => {
+                let arg = self.next_arg().unwrap_or("");
+                let text: String = match spec.precision {
+                    Some(precision) => arg.chars().take(precision).collect(),
+                    None => arg.to_string(),
+                };
+                spec.pad_text(text.as_bytes(), &mut self.out);
+            }
+            b'c' => {
+                let arg = self.next_arg().unwrap_or("");
+                let text = match arg.chars().next() {
+                    Some(c) => c.to_string(),
+                    None => "\0".to_string(),
+                };
+                spec.pad_text(text.as_bytes(), &mut self.out);
+            }
+            b'b' => {
+                let arg = self.next_arg().unwrap_or("").as_bytes();
+                let mut text = Vec::new();
+                let mut j = 0;
+                let mut more = true;
+                while j < arg.len() {
+                    if arg[j] == b'\\' {
+                        if !escape(arg, &mut j, true, &mut text) {
+                            more = false;
+                            break;
+                        }
+                    } else {
+                        text.push(arg[j]);
+                        j += 1;
+                    }
+                }
+                if let Some(precision) = spec.precision {
+                    text.truncate(precision);
+                }
+                spec.pad_text(&text, &mut self.out);
+                return more;
+            }
+            b'q' => {
+                let text = shell_quote(self.next_arg().unwrap_or(""));
+                spec.pad_text(text.as_bytes(), &mut self.out);
+            }
+            _ => {
+                let text = String::from_utf8_lossy(&format[start..*i]);
+                self.errors
+                    .push(format!("{text}: invalid conversion specification"));
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: printf FORMAT [ARGUMENT]...");
+    let _ = writeln!(
+        stderr,
+        "Print ARGUMENT(s) according to FORMAT, reusing FORMAT while arguments remain."
+    );
+    let _ = writeln!(stderr);
+    let _ = writeln!(
+        stderr,
+        "FORMAT takes the escapes \\\\ \\a \\b \\c \\e \\f \\n \\r \\t \\v \\NNN \\xHH \\uHHHH \\UHHHHHHHH,"
+    );
+    let _ = writeln!(
+        stderr,
+        "and the conversions %d %i %o %u %x %X %f %F %e %E %g %G %c %s, plus"
+    );
+    let _ = writeln!(
+        stderr,
+        "%b (ARGUMENT with its escapes interpreted) and %q (ARGUMENT quoted for the shell)."
+    );
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let args = match args.first().map(String::as_str) {
+        Some("--help") if args.len() == 1 => {
+            print_usage(io.stderr());
+            return 0;
+        }
+        Some("--") => &args[1..],
+        _ => args,
+    };
+    let Some((format, args)) = args.split_first() else {
+        let _ = writeln!(io.stderr(), "printf: missing operand");
+        return 1;
+    };
+
+    let mut printer = Printer {
+        args,
+        next: 0,
+        out: Vec::new(),
+        errors: Vec::new(),
+    };
+    // The format is reused while it consumes arguments and some remain.
+    let mut exit_code = 0;
+    loop {
+        let before = printer.next;
+        if !printer.format_once(format.as_bytes()) {
+            break;
+        }
+        if printer.next == before {
+            if let Some(extra) = args.get(printer.next) {
+                printer.errors.push(format!(
+                    "warning: ignoring excess arguments, starting with '{extra}'"
+                ));
+            }
+            break;
+        }
+        if printer.next >= args.len() {
+            break;
+        }
+    }
+
+    let _ = io.stdout().write_all(&printer.out);
+    for error in &printer.errors {
+        let _ = writeln!(io.stderr(), "printf: {error}");
+        if !error.starts_with("warning:") {
+            exit_code = 1;
+        }
+    }
+    exit_code
+}
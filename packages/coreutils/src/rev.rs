@@ -0,0 +1,43 @@
+//! rev - reverse lines of a file or stdin
+
+use std::io::{self, BufRead, Write};
+
+use crate::Io;
+
+fn rev_reader(reader: Box<dyn BufRead>, out: &mut dyn Write) -> io::Result<()> {
+    for line in reader.lines() {
+        let reversed: String = line?.chars().rev().collect();
+        let _ = writeln!(out, "{}", reversed);
+    }
+    Ok(())
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    if args.iter().any(|a| a == "--help") {
+        let _ = writeln!(io.stdout(), "Usage: rev [FILE...]");
+        let _ = writeln!(io.stdout(), "Reverse each line of input.");
+        return 0;
+    }
+
+    if args.is_empty() {
+        if let Err(e) = io.stdin().and_then(|r| rev_reader(r, io.stdout())) {
+            let _ = writeln!(io.stderr(), "rev: {}", e);
+            return 1;
+        }
+    } else {
+        for path in args {
+            let reader = match io.open(path) {
+                Ok(f) => f,
+                Err(e) => {
+                    let _ = writeln!(io.stderr(), "rev: {}: {}", path, e);
+                    return 1;
+                }
+            };
+            if let Err(e) = rev_reader(reader, io.stdout()) {
+                let _ = writeln!(io.stderr(), "rev: {}", e);
+                return 1;
+            }
+        }
+    }
+    0
+}
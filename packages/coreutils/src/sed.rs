@@ -0,0 +1,1569 @@
+//! sed - stream editor
+//!
+//! Supports: s/pattern/replacement/[flags], d, D, p, P, n, N, q, c, y, w,
+//! h, H, g, G, x, r, R, =, F, :label with b, t and T, line-number and
+//! /pattern/ addressing, ranges, negation (!), -n flag, -e expressions and
+//! -f script files, -E extended regexes, -z NUL-separated lines, -s
+//! separate files, multiple commands via ; and { } blocks, and # comments.
+//! Patterns may use `\b`, `\B`, `\<` and `\>` for word boundaries and take
+//! the `I` and `M` flags. Files are read as one continuous stream unless -s
+//! or -i is given.
+//!
+//! Patterns are POSIX regular expressions, matched with `fancy_regex` after
+//! [`translate`] so that back-references like `\(.\)\1` work in the pattern
+//! as well as in the replacement.
+
+use fancy_regex::{Captures, Regex, RegexBuilder};
+use std::cell::Cell;
+use std::io::{Read, Write};
+
+use crate::pattern::translate;
+use crate::Io;
+
+thread_local! {
+    /// ERE mode flag for the current run — when true, patterns are read as
+    /// EREs rather than BREs.
+    static ERE_MODE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Compile a sed pattern. With `multi_line` (the `M` flag), `^` and `$`
+/// also match around embedded newlines; `` \` `` and `\'` always match only
+/// at the ends of the pattern space.
+fn compile_pattern(pattern: &str, ignore_case: bool, multi_line: bool) -> Result<Regex, String> {
+    let mut translated = translate(pattern, ERE_MODE.get())?;
+    if multi_line {
+        // An inline flag rather than RegexBuilder::multi_line, which would
+        // turn \A and \z into line anchors too.
+        translated.insert_str(0, "(?m)");
+    }
+    RegexBuilder::new(&translated)
+        .case_insensitive(ignore_case)
+        .dot_matches_new_line(true)
+        .build()
+        .map_err(|_| format!("invalid regular expression: {}", pattern))
+}
+
+/// A regex written between `delim`s, with `\delim` meaning a literal
+/// `delim`.
+fn strip_delim_escapes(s: &str, delim: char) -> String {
+    if delim == '\\' || delim == '\n' {
+        return s.to_string();
+    }
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next) if next == delim => result.push(next),
+                Some(next) => {
+                    result.push(c);
+                    result.push(next);
+                }
+                None => result.push(c),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+// ---------------------------------------------------------------------------
+// sed data structures
+// ---------------------------------------------------------------------------
+
+#[derive(Clone)]
+enum Address {
+    None,
+    Line(usize),
+    Last,
+    Pattern(Regex),
+    Range(Box<Address>, Box<Address>),
+    Negated(Box<Address>),
+}
+
+#[derive(Clone)]
+enum SedCmd {
+    Substitute {
+        pattern: Regex,
+        replacement: String,
+        global: bool,
+        print: bool,
+        nth: usize, // 0 = first (default), N = Nth occurrence
+        write_file: Option<String>,
+    },
+    Delete,
+    DeleteFirstLine, // D - delete up to the first newline, restart the cycle
+    Print,
+    PrintFirstLine, // P - print up to the first newline
+    Next,           // n - print the pattern space, read the next line
+    NextAppend,     // N - append the next line to the pattern space
+    Quit,
+    AppendText(String),
+    InsertText(String),
+    ChangeText(String),
+    Transliterate(Vec<char>, Vec<char>),
+    WriteFile(String),
+    ReadFile(String), // r - queue a file's contents for output
+    ReadLine(String), // R - queue the next line of a file for output
+    LineNumber,       // = - print the line number
+    FileName,         // F - print the input file's name
+    HoldCopy,         // h - copy pattern to hold
+    HoldAppend,       // H - append pattern to hold
+    GetCopy,          // g - copy hold to pattern
+    GetAppend,        // G - append hold to pattern
+    Exchange,         // x - exchange hold and pattern
+    /// `{`: when its address does not match, execution skips to `end`, the
+    /// index of the rule after the block.
+    Block {
+        end: usize,
+    },
+    /// `:label`, a target for branches.
+    Label(String),
+    /// `b`, `t` or `T`: jump to the rule at `target`, the end of the
+    /// script if `label` is empty, when `when` allows.
+    Branch {
+        label: String,
+        when: BranchWhen,
+        target: usize,
+    },
+}
+
+#[derive(Clone, Copy)]
+enum BranchWhen {
+    Always,         // b
+    Substituted,    // t - if a substitution was made since the last line was read
+    NotSubstituted, // T - if none was
+}
+
+#[derive(Clone)]
+struct Rule {
+    address: Address,
+    command: SedCmd,
+}
+
+// ---------------------------------------------------------------------------
+// Parsing
+// ---------------------------------------------------------------------------
+
+fn unescape_replacement(s: &str) -> String {
+    // We don't unescape the replacement here — we handle & and \1 at apply time.
+    // But we do handle \\n -> \n and \\t -> \t literal escapes.
+    let mut result = String::new();
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            match chars[i + 1] {
+                'n' => {
+                    result.push('\n');
+                    i += 2;
+                }
+                't' => {
+                    result.push('\t');
+                    i += 2;
+                }
+                _ => {
+                    // Keep as-is for later processing (\1, \2, etc. and \\)
+                    result.push('\\');
+                    result.push(chars[i + 1]);
+                    i += 2;
+                }
+            }
+        } else {
+            result.push(chars[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
+fn parse_substitute(s: &str) -> Result<SedCmd, String> {
+    let unterminated = || "unterminated `s' command".to_string();
+    let delim = s.chars().next().ok_or_else(unterminated)?;
+    let rest = &s[delim.len_utf8()..];
+
+    // Find second delimiter (respecting backslash escapes)
+    let second = find_unescaped_delim(rest, delim).ok_or_else(unterminated)?;
+    let pattern_str = strip_delim_escapes(&rest[..second], delim);
+    let rest = &rest[second + delim.len_utf8()..];
+
+    // Find third delimiter. It is required: without it the replacement
+    // would run on into the commands that follow.
+    let third = find_unescaped_delim(rest, delim).ok_or_else(unterminated)?;
+    let (replacement_raw, flags_str) =
+        (rest[..third].to_string(), &rest[third + delim.len_utf8()..]);
+
+    let replacement = unescape_replacement(&replacement_raw);
+
+    let mut global = false;
+    let mut print = false;
+    let mut ignore_case = false;
+    let mut multi_line = false;
+    let mut nth: usize = 0;
+    let mut write_file: Option<String> = None;
+
+    // The flags run to the end of the command, as measure_substitute
+    // finds it.
+    let mut flags_chars: Vec<char> = Vec::new();
+    let mut in_filename = false;
+    for c in flags_str.chars() {
+        if matches!(c, ';' | '\n' | '}') || (c == ' ' && !in_filename) {
+            break;
+        }
+        in_filename |= c == 'w';
+        flags_chars.push(c);
+    }
+    let mut fi = 0;
+    while fi < flags_chars.len() {
+        match flags_chars[fi] {
+            'g' => global = true,
+            'p' => print = true,
+            'i' | 'I' => ignore_case = true,
+            'm' | 'M' => multi_line = true,
+            'w' => {
+                // Rest is filename
+                let fname: String = flags_chars[fi + 1..].iter().collect();
+                write_file = Some(fname.trim().to_string());
+                break;
+            }
+            c if c.is_ascii_digit() => {
+                // Nth occurrence
+                let mut num_str = String::new();
+                while fi < flags_chars.len() && flags_chars[fi].is_ascii_digit() {
+                    num_str.push(flags_chars[fi]);
+                    fi += 1;
+                }
+                nth = num_str.parse().unwrap_or(0);
+                continue; // don't increment fi again
+            }
+            _ => {}
+        }
+        fi += 1;
+    }
+
+    let re = compile_pattern(&pattern_str, ignore_case, multi_line)?;
+
+    Ok(SedCmd::Substitute {
+        pattern: re,
+        replacement,
+        global,
+        print,
+        nth,
+        write_file,
+    })
+}
+
+fn find_unescaped_delim(s: &str, delim: char) -> Option<usize> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    let mut byte_offset = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            byte_offset += chars[i].len_utf8();
+            i += 1;
+            if i < chars.len() {
+                byte_offset += chars[i].len_utf8();
+                i += 1;
+            }
+            continue;
+        }
+        if chars[i] == delim {
+            return Some(byte_offset);
+        }
+        byte_offset += chars[i].len_utf8();
+        i += 1;
+    }
+    None
+}
+
+fn parse_address(s: &str) -> Result<(Address, &str), String> {
+    if s.is_empty() {
+        return Ok((Address::None, s));
+    }
+
+    let ch = s.as_bytes()[0];
+
+    if ch == b'$' {
+        let rest = &s[1..];
+        let addr = Address::Last;
+        return finish_address(addr, rest);
+    }
+
+    if ch.is_ascii_digit() {
+        let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let n: usize = s[..end].parse().unwrap_or(0);
+        let rest = &s[end..];
+        let addr = Address::Line(n);
+        return finish_address(addr, rest);
+    }
+
+    // /regex/, or \cregexc with any delimiter c, optionally followed by I
+    // and M.
+    if ch == b'/' || ch == b'\\' {
+        let unterminated = || "unterminated address regex".to_string();
+        let delim = if ch == b'/' {
+            '/'
+        } else {
+            s[1..].chars().next().ok_or_else(unterminated)?
+        };
+        let start = if ch == b'/' { 1 } else { 1 + delim.len_utf8() };
+        let rest = &s[start..];
+        let end = find_unescaped_delim(rest, delim).ok_or_else(unterminated)?;
+        let pattern = strip_delim_escapes(&rest[..end], delim);
+        let mut after = &rest[end + delim.len_utf8()..];
+        let (mut ignore_case, mut multi_line) = (false, false);
+        loop {
+            match after.as_bytes().first() {
+                Some(b'I') => ignore_case = true,
+                Some(b'M') => multi_line = true,
+                _ => break,
+            }
+            after = &after[1..];
+        }
+        let re = compile_pattern(&pattern, ignore_case, multi_line)?;
+        return finish_address(Address::Pattern(re), after);
+    }
+
+    Ok((Address::None, s))
+}
+
+fn finish_address(addr: Address, rest: &str) -> Result<(Address, &str), String> {
+    if let Some(after_comma) = rest.strip_prefix(',') {
+        let (addr2, rest2) = parse_address(after_comma)?;
+        Ok((Address::Range(Box::new(addr), Box::new(addr2)), rest2))
+    } else {
+        Ok((addr, rest))
+    }
+}
+
+/// Parse `script` into `rules`. On failure, also returns the line of
+/// the script the parser had reached.
+fn parse_script(script: &str, rules: &mut Vec<Rule>) -> Result<(), (usize, String)> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut pos = 0;
+    parse_commands(&chars, &mut pos, rules, false).map_err(|e| {
+        let line = chars[..pos.min(chars.len())]
+            .iter()
+            .filter(|&&c| c == '\n')
+            .count();
+        (line, e)
+    })
+}
+
+/// Point every branch in the finished program at its label.
+fn resolve_labels(rules: &mut [Rule]) -> Result<(), String> {
+    let labels: Vec<(String, usize)> = rules
+        .iter()
+        .enumerate()
+        .filter_map(|(i, rule)| match &rule.command {
+            SedCmd::Label(name) => Some((name.clone(), i)),
+            _ => None,
+        })
+        .collect();
+    let end = rules.len();
+    for rule in rules.iter_mut() {
+        if let SedCmd::Branch { label, target, .. } = &mut rule.command {
+            *target = if label.is_empty() {
+                end
+            } else {
+                labels
+                    .iter()
+                    .find(|(name, _)| name == label)
+                    .map(|&(_, i)| i)
+                    .ok_or_else(|| format!("can't find label for jump to `{}'", label))?
+            };
+        }
+    }
+    Ok(())
+}
+
+fn parse_commands(
+    chars: &[char],
+    pos: &mut usize,
+    rules: &mut Vec<Rule>,
+    in_block: bool,
+) -> Result<(), String> {
+    while *pos < chars.len() {
+        // Skip whitespace and semicolons
+        while *pos < chars.len()
+            && (chars[*pos] == ' '
+                || chars[*pos] == '\t'
+                || chars[*pos] == ';'
+                || chars[*pos] == '\n')
+        {
+            *pos += 1;
+        }
+        if *pos >= chars.len() {
+            break;
+        }
+
+        if chars[*pos] == '#' {
+            // A comment, to the end of the line.
+            while *pos < chars.len() && chars[*pos] != '\n' {
+                *pos += 1;
+            }
+            continue;
+        }
+
+        if chars[*pos] == '}' {
+            if !in_block {
+                return Err("unexpected `}'".to_string());
+            }
+            *pos += 1;
+            return Ok(()); // end of block
+        }
+
+        // Parse address
+        let remaining: String = chars[*pos..].iter().collect();
+        let (address, after_addr) = parse_address(&remaining)?;
+        let consumed = remaining.len() - after_addr.len();
+        *pos += remaining[..consumed].chars().count();
+
+        // Skip whitespace
+        while *pos < chars.len() && (chars[*pos] == ' ' || chars[*pos] == '\t') {
+            *pos += 1;
+        }
+
+        if *pos >= chars.len() {
+            break;
+        }
+
+        // Check for negation
+        let (address, _negated) = if *pos < chars.len() && chars[*pos] == '!' {
+            *pos += 1;
+            // Skip whitespace after !
+            while *pos < chars.len() && (chars[*pos] == ' ' || chars[*pos] == '\t') {
+                *pos += 1;
+            }
+            (Address::Negated(Box::new(address)), true)
+        } else {
+            (address, false)
+        };
+
+        if *pos >= chars.len() {
+            break;
+        }
+
+        // Parse command
+        let cmd_char = chars[*pos];
+        match cmd_char {
+            's' => {
+                *pos += 1;
+                let remaining: String = chars[*pos..].iter().collect();
+                let sub = parse_substitute(&remaining)?;
+                // Figure out how many chars were consumed
+                let sub_consumed = measure_substitute(&remaining);
+                *pos += sub_consumed;
+                rules.push(Rule {
+                    address,
+                    command: sub,
+                });
+            }
+            'd' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Delete,
+                });
+            }
+            'D' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::DeleteFirstLine,
+                });
+            }
+            'p' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Print,
+                });
+            }
+            'P' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::PrintFirstLine,
+                });
+            }
+            'n' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Next,
+                });
+            }
+            'N' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::NextAppend,
+                });
+            }
+            'q' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Quit,
+                });
+            }
+            'a' => {
+                *pos += 1;
+                let text = parse_text_arg(chars, pos);
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::AppendText(text),
+                });
+            }
+            'i' => {
+                *pos += 1;
+                let text = parse_text_arg(chars, pos);
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::InsertText(text),
+                });
+            }
+            'c' => {
+                *pos += 1;
+                let text = parse_text_arg(chars, pos);
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::ChangeText(text),
+                });
+            }
+            'y' => {
+                *pos += 1;
+                if *pos < chars.len() {
+                    let delim = chars[*pos];
+                    *pos += 1;
+                    let from = collect_until(chars, pos, delim);
+                    if *pos < chars.len() && chars[*pos] == delim {
+                        *pos += 1;
+                    }
+                    let to = collect_until(chars, pos, delim);
+                    if *pos < chars.len() && chars[*pos] == delim {
+                        *pos += 1;
+                    }
+                    let from_chars: Vec<char> = from.chars().collect();
+                    let to_chars: Vec<char> = to.chars().collect();
+                    rules.push(Rule {
+                        address,
+                        command: SedCmd::Transliterate(from_chars, to_chars),
+                    });
+                }
+            }
+            'w' | 'r' | 'R' => {
+                *pos += 1;
+                // Skip one space
+                if *pos < chars.len() && chars[*pos] == ' ' {
+                    *pos += 1;
+                }
+                let filename = collect_filename(chars, pos).trim().to_string();
+                let command = match cmd_char {
+                    'w' => SedCmd::WriteFile(filename),
+                    'r' => SedCmd::ReadFile(filename),
+                    _ => SedCmd::ReadLine(filename),
+                };
+                rules.push(Rule { address, command });
+            }
+            '=' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::LineNumber,
+                });
+            }
+            'F' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::FileName,
+                });
+            }
+            'h' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::HoldCopy,
+                });
+            }
+            'H' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::HoldAppend,
+                });
+            }
+            'g' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::GetCopy,
+                });
+            }
+            'G' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::GetAppend,
+                });
+            }
+            'x' => {
+                *pos += 1;
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Exchange,
+                });
+            }
+            ':' => {
+                *pos += 1;
+                let label = parse_label(chars, pos);
+                if label.is_empty() {
+                    return Err("\":\" lacks a label".to_string());
+                }
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Label(label),
+                });
+            }
+            'b' | 't' | 'T' => {
+                *pos += 1;
+                let when = match cmd_char {
+                    'b' => BranchWhen::Always,
+                    't' => BranchWhen::Substituted,
+                    _ => BranchWhen::NotSubstituted,
+                };
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Branch {
+                        label: parse_label(chars, pos),
+                        when,
+                        target: 0,
+                    },
+                });
+            }
+            '{' => {
+                *pos += 1;
+                let start = rules.len();
+                rules.push(Rule {
+                    address,
+                    command: SedCmd::Block { end: 0 },
+                });
+                parse_commands(chars, pos, rules, true)?;
+                rules[start].command = SedCmd::Block { end: rules.len() };
+            }
+            _ => {
+                *pos += 1;
+            }
+        }
+    }
+    if in_block {
+        return Err("unmatched `{'".to_string());
+    }
+    Ok(())
+}
+
+/// The label after `:`, `b`, `t` or `T`, up to the end of the command.
+fn parse_label(chars: &[char], pos: &mut usize) -> String {
+    while *pos < chars.len() && (chars[*pos] == ' ' || chars[*pos] == '\t') {
+        *pos += 1;
+    }
+    let mut label = String::new();
+    while *pos < chars.len() && !matches!(chars[*pos], ';' | '\n' | '}') {
+        label.push(chars[*pos]);
+        *pos += 1;
+    }
+    label.trim_end().to_string()
+}
+
+fn parse_text_arg(chars: &[char], pos: &mut usize) -> String {
+    // Handle a\ text or a\text
+    if *pos < chars.len() && chars[*pos] == '\\' {
+        *pos += 1;
+        if *pos < chars.len() && chars[*pos] == '\n' {
+            *pos += 1;
+            return parse_text_lines(chars, pos);
+        }
+    }
+    // Collect until end of command (semicolon, newline, or end of input)
+    let mut text = String::new();
+    while *pos < chars.len() && chars[*pos] != ';' && chars[*pos] != '\n' && chars[*pos] != '}' {
+        text.push(chars[*pos]);
+        *pos += 1;
+    }
+    text
+}
+
+/// The text of `a\`, `i\` or `c\` written on the lines after the command,
+/// as in a script file: it runs to the end of the line, and a backslash at
+/// the end of a line continues it onto the next.
+fn parse_text_lines(chars: &[char], pos: &mut usize) -> String {
+    let mut text = String::new();
+    while *pos < chars.len() && chars[*pos] != '\n' {
+        if chars[*pos] == '\\' && *pos + 1 < chars.len() {
+            *pos += 1;
+        }
+        text.push(chars[*pos]);
+        *pos += 1;
+    }
+    text
+}
+
+fn collect_until(chars: &[char], pos: &mut usize, delim: char) -> String {
+    let mut result = String::new();
+    while *pos < chars.len() && chars[*pos] != delim {
+        if chars[*pos] == '\\' && *pos + 1 < chars.len() {
+            result.push(chars[*pos]);
+            result.push(chars[*pos + 1]);
+            *pos += 2;
+        } else {
+            result.push(chars[*pos]);
+            *pos += 1;
+        }
+    }
+    result
+}
+
+/// A `r`, `R` or `w` filename: as in GNU sed, it runs to the end of the
+/// line, so `;` and `}` are part of it.
+fn collect_filename(chars: &[char], pos: &mut usize) -> String {
+    let mut result = String::new();
+    while *pos < chars.len() && chars[*pos] != '\n' {
+        result.push(chars[*pos]);
+        *pos += 1;
+    }
+    result
+}
+
+fn measure_substitute(s: &str) -> usize {
+    if s.is_empty() {
+        return 0;
+    }
+    let chars: Vec<char> = s.chars().collect();
+    let delim = chars[0];
+    let mut i = 1;
+    let mut delim_count = 0;
+
+    while i < chars.len() && delim_count < 2 {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            i += 2;
+            continue;
+        }
+        if chars[i] == delim {
+            delim_count += 1;
+        }
+        i += 1;
+    }
+
+    // Now consume flags
+    while i < chars.len()
+        && chars[i] != ';'
+        && chars[i] != '\n'
+        && chars[i] != '}'
+        && chars[i] != ' '
+    {
+        if chars[i] == 'w' {
+            // w flag: rest until end of command is filename
+            i += 1;
+            while i < chars.len() && chars[i] != ';' && chars[i] != '\n' && chars[i] != '}' {
+                i += 1;
+            }
+            break;
+        }
+        i += 1;
+    }
+
+    i
+}
+
+// ---------------------------------------------------------------------------
+// Substitution
+// ---------------------------------------------------------------------------
+
+fn build_replacement(caps: &Captures, replacement: &str) -> String {
+    let matched = caps.get(0).unwrap();
+    let mut result = String::new();
+    let rchars: Vec<char> = replacement.chars().collect();
+    let mut i = 0;
+    while i < rchars.len() {
+        if rchars[i] == '&' {
+            result.push_str(matched.as_str());
+            i += 1;
+        } else if rchars[i] == '\\' && i + 1 < rchars.len() {
+            let next = rchars[i + 1];
+            if next.is_ascii_digit() && next != '0' {
+                let gid = (next as usize) - ('0' as usize);
+                if let Some(m) = caps.get(gid) {
+                    result.push_str(m.as_str());
+                }
+                i += 2;
+            } else if next == '\\' {
+                result.push('\\');
+                i += 2;
+            } else if next == '&' {
+                result.push('&');
+                i += 2;
+            } else {
+                result.push(rchars[i + 1]);
+                i += 2;
+            }
+        } else {
+            result.push(rchars[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
+fn apply_substitute(
+    line: &str,
+    re: &Regex,
+    replacement: &str,
+    global: bool,
+    nth: usize,
+) -> (String, bool) {
+    if global {
+        // Replace all occurrences
+        let caps_vec: Vec<_> = re.captures_iter(line).filter_map(Result::ok).collect();
+        if caps_vec.is_empty() {
+            return (line.to_string(), false);
+        }
+        let mut result = String::new();
+        let mut last_end = 0;
+        for caps in &caps_vec {
+            let m = caps.get(0).unwrap();
+            result.push_str(&line[last_end..m.start()]);
+            result.push_str(&build_replacement(caps, replacement));
+            last_end = m.end();
+        }
+        result.push_str(&line[last_end..]);
+        (result, true)
+    } else if nth > 0 {
+        // Replace the Nth occurrence only
+        let caps_vec: Vec<_> = re.captures_iter(line).filter_map(Result::ok).collect();
+        if caps_vec.len() < nth {
+            return (line.to_string(), false);
+        }
+        let caps = &caps_vec[nth - 1];
+        let m = caps.get(0).unwrap();
+        let mut result = String::new();
+        result.push_str(&line[..m.start()]);
+        result.push_str(&build_replacement(caps, replacement));
+        result.push_str(&line[m.end()..]);
+        (result, true)
+    } else {
+        // Replace first occurrence
+        if let Ok(Some(caps)) = re.captures(line) {
+            let m = caps.get(0).unwrap();
+            let mut result = String::new();
+            result.push_str(&line[..m.start()]);
+            result.push_str(&build_replacement(&caps, replacement));
+            result.push_str(&line[m.end()..]);
+            (result, true)
+        } else {
+            (line.to_string(), false)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Execution
+// ---------------------------------------------------------------------------
+
+/// How a command leaves the rest of the cycle.
+enum Flow {
+    /// On to the next command.
+    Continue,
+    /// End the cycle, auto-printing the pattern space.
+    EndCycle,
+    /// End the cycle without auto-printing (`d`).
+    Delete,
+    /// Start the next cycle on what is left of the pattern space, without
+    /// reading a line (`D`).
+    Restart,
+    /// Auto-print the pattern space and stop (`q`, or `n`/`N` at the end
+    /// of input).
+    Quit,
+    /// Go on from the rule at this index.
+    Jump(usize),
+}
+
+/// One input file, read whole and split into lines.
+struct Input {
+    /// The name `F` prints: the path, or `-` for standard input.
+    name: String,
+    lines: Vec<String>,
+    /// With `-z`, whether the last line had no NUL after it. The pattern
+    /// space is then printed without one on that line, as GNU sed does; a
+    /// missing final newline is always added.
+    unterminated: bool,
+}
+
+impl Input {
+    fn new(name: &str, data: &[u8], delim: u8) -> Input {
+        let unterminated = delim != b'\n' && data.last().is_some_and(|&b| b != delim);
+        let body = data.strip_suffix(&[delim]).unwrap_or(data);
+        let lines = if body.is_empty() && !unterminated {
+            Vec::new()
+        } else {
+            body.split(|&b| b == delim)
+                .map(|line| String::from_utf8_lossy(line).into_owned())
+                .collect()
+        };
+        Input {
+            name: name.to_string(),
+            lines,
+            unterminated,
+        }
+    }
+}
+
+/// Options from the command line that shape a run.
+struct Options {
+    suppress: bool,
+    /// With `-s` or `-i`, each input is edited on its own: line numbers,
+    /// `$`, ranges and the hold space start over with every file.
+    separate: bool,
+    in_place: bool,
+    /// What ends a line: `\n`, or NUL with `-z`.
+    delim: u8,
+}
+
+/// The state of one run of a script over its input.
+struct Sed<'a> {
+    rules: &'a [Rule],
+    suppress: bool,
+    separate: bool,
+    /// What ends a line. It also joins lines in the pattern and hold
+    /// spaces.
+    delim: char,
+    inputs: Vec<Input>,
+    /// The index in `inputs` of the file the current line came from.
+    file: usize,
+    /// The index in that file of the next line to read.
+    next: usize,
+    /// The number of the current line, counted across files unless
+    /// `separate`.
+    line_number: usize,
+    /// Whether the output ends with an unterminated last line, so that the
+    /// delimiter is owed before anything else is written.
+    owed: bool,
+    pattern_space: String,
+    hold_space: String,
+    /// For each rule with a range address, by index, whether we are
+    /// currently inside the range.
+    range_active: Vec<bool>,
+    /// Text queued by `a`, `r` and `R`, output at the end of the cycle or
+    /// when the next line is read.
+    append_queue: Vec<String>,
+    /// The files `r` and `R` read, by name, loaded before the run.
+    read_files: &'a [(String, String)],
+    /// For each of `read_files`, how far `R` has read it.
+    read_offsets: Vec<usize>,
+    /// Text written by `w` commands and `s///w` flags, one entry per file.
+    file_writes: Vec<(String, String)>,
+    /// Whether a substitution was made since the last line was read or
+    /// `t` or `T` last tested it.
+    substituted: bool,
+    /// With `-i`, the output for each file in `inputs`; otherwise output
+    /// goes to `out`.
+    outputs: Option<Vec<Vec<u8>>>,
+    out: &'a mut dyn Write,
+}
+
+impl Sed<'_> {
+    fn line_num(&self) -> usize {
+        self.line_number
+    }
+
+    /// Whether the current line is the last of its file.
+    fn is_last_in_file(&self) -> bool {
+        self.next >= self.inputs[self.file].lines.len()
+    }
+
+    /// Whether no input at all follows the current line.
+    fn at_end(&self) -> bool {
+        self.is_last_in_file()
+            && self.inputs[self.file + 1..]
+                .iter()
+                .all(|i| i.lines.is_empty())
+    }
+
+    /// Whether the current line is the last, the one `$` matches.
+    fn is_last(&self) -> bool {
+        if self.separate {
+            self.is_last_in_file()
+        } else {
+            self.at_end()
+        }
+    }
+
+    /// Move to the next line of input, returning it.
+    fn read_line(&mut self) -> Option<String> {
+        while self.next >= self.inputs.get(self.file)?.lines.len() {
+            if self.file + 1 >= self.inputs.len() {
+                return None;
+            }
+            self.file += 1;
+            self.next = 0;
+            if self.separate {
+                self.line_number = 0;
+                self.hold_space.clear();
+                self.range_active.fill(false);
+            }
+            if self.outputs.is_some() {
+                // The last file's output keeps its missing delimiter.
+                self.owed = false;
+            }
+        }
+        let line = self.inputs[self.file].lines[self.next].clone();
+        self.next += 1;
+        self.line_number += 1;
+        self.substituted = false;
+        Some(line)
+    }
+
+    /// Where output goes: the current file's buffer with `-i`.
+    fn sink(&mut self) -> &mut dyn Write {
+        match &mut self.outputs {
+            Some(outputs) => &mut outputs[self.file],
+            None => &mut *self.out,
+        }
+    }
+
+    fn run(&mut self) {
+        let mut restart = false;
+        loop {
+            if !restart {
+                match self.read_line() {
+                    Some(line) => self.pattern_space = line,
+                    None => break,
+                }
+            }
+            let flow = self.execute();
+            if matches!(flow, Flow::EndCycle | Flow::Quit) && !self.suppress {
+                self.print_pattern_space();
+            }
+            self.flush_appends();
+            match flow {
+                Flow::Quit => break,
+                Flow::Restart => restart = true,
+                _ => restart = false,
+            }
+        }
+    }
+
+    /// Write raw output, after any delimiter still owed.
+    fn write_raw(&mut self, text: &str) {
+        let delim = self.delim;
+        if self.owed {
+            let _ = write!(self.sink(), "{}", delim);
+            self.owed = false;
+        }
+        let _ = self.sink().write_all(text.as_bytes());
+    }
+
+    /// Write `text` as a line of output.
+    fn emit(&mut self, text: &str) {
+        self.write_raw(text);
+        let delim = self.delim;
+        let _ = write!(self.sink(), "{}", delim);
+    }
+
+    /// Write the pattern space as a line of output, leaving off the
+    /// delimiter if the last input line had none.
+    fn print_pattern_space(&mut self) {
+        let text = std::mem::take(&mut self.pattern_space);
+        if self.inputs[self.file].unterminated && self.is_last_in_file() {
+            self.write_raw(&text);
+            self.owed = true;
+        } else {
+            self.emit(&text);
+        }
+        self.pattern_space = text;
+    }
+
+    fn flush_appends(&mut self) {
+        for text in std::mem::take(&mut self.append_queue) {
+            self.write_raw(&text);
+        }
+    }
+
+    /// Run the script once over the pattern space.
+    fn execute(&mut self) -> Flow {
+        let rules = self.rules;
+        let mut pc = 0;
+        while pc < rules.len() {
+            let rule = &rules[pc];
+            if !self.matches(&rule.address, pc) {
+                pc = match rule.command {
+                    SedCmd::Block { end } => end,
+                    _ => pc + 1,
+                };
+                continue;
+            }
+            match self.command(&rule.command, pc) {
+                Flow::Continue => pc += 1,
+                Flow::Jump(target) => pc = target,
+                flow => return flow,
+            }
+        }
+        Flow::EndCycle
+    }
+
+    fn matches(&mut self, addr: &Address, idx: usize) -> bool {
+        match addr {
+            Address::None => true,
+            Address::Line(n) => self.line_num() == *n,
+            Address::Last => self.is_last(),
+            Address::Pattern(re) => re.is_match(&self.pattern_space).unwrap_or(false),
+            Address::Negated(inner) => !self.matches(inner, idx),
+            Address::Range(start, end) => {
+                if self.range_active[idx] {
+                    // We're in the range — check if this line ends it
+                    let ends = match **end {
+                        Address::Line(n) => self.line_num() >= n,
+                        ref end => self.matches(end, idx),
+                    };
+                    if ends {
+                        self.range_active[idx] = false;
+                    }
+                    true
+                } else if self.matches(start, idx) {
+                    // A line-number end at or before the start makes a
+                    // one-line range; a regex end is only tried from the
+                    // next line on.
+                    self.range_active[idx] = match **end {
+                        Address::Line(n) => n > self.line_num(),
+                        Address::Last => !self.is_last(),
+                        _ => true,
+                    };
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    fn command(&mut self, command: &SedCmd, idx: usize) -> Flow {
+        match command {
+            SedCmd::Substitute {
+                pattern,
+                replacement,
+                global,
+                print,
+                nth,
+                write_file,
+            } => {
+                let (new_line, changed) =
+                    apply_substitute(&self.pattern_space, pattern, replacement, *global, *nth);
+                self.pattern_space = new_line;
+                self.substituted |= changed;
+                if changed {
+                    if *print {
+                        self.print_pattern_space();
+                    }
+                    if let Some(fname) = write_file {
+                        self.write_to_file(fname);
+                    }
+                }
+            }
+            SedCmd::Delete => return Flow::Delete,
+            SedCmd::DeleteFirstLine => {
+                return match self.pattern_space.find(self.delim) {
+                    Some(nl) => {
+                        self.pattern_space.drain(..=nl);
+                        Flow::Restart
+                    }
+                    None => Flow::Delete,
+                };
+            }
+            SedCmd::Print => self.print_pattern_space(),
+            SedCmd::PrintFirstLine => match self.pattern_space.find(self.delim) {
+                Some(end) => {
+                    let first = self.pattern_space[..end].to_string();
+                    self.emit(&first);
+                }
+                None => self.print_pattern_space(),
+            },
+            // With `-s`, neither reads on into the next file.
+            SedCmd::Next | SedCmd::NextAppend if self.is_last() => {
+                return if self.at_end() {
+                    Flow::Quit
+                } else {
+                    Flow::EndCycle
+                };
+            }
+            SedCmd::Next => {
+                if !self.suppress {
+                    self.print_pattern_space();
+                }
+                self.flush_appends();
+                self.pattern_space = self.read_line().unwrap_or_default();
+            }
+            SedCmd::NextAppend => {
+                self.flush_appends();
+                let line = self.read_line().unwrap_or_default();
+                self.pattern_space.push(self.delim);
+                self.pattern_space.push_str(&line);
+            }
+            SedCmd::Quit => return Flow::Quit,
+            SedCmd::AppendText(text) => self.append_queue.push(format!("{}\n", text)),
+            SedCmd::ReadFile(fname) => {
+                // A file that can't be read is silently skipped.
+                if let Some((_, content)) = self.read_files.iter().find(|(f, _)| f == fname) {
+                    self.append_queue.push(content.clone());
+                }
+            }
+            SedCmd::ReadLine(fname) => {
+                if let Some(i) = self.read_files.iter().position(|(f, _)| f == fname) {
+                    let rest = &self.read_files[i].1[self.read_offsets[i]..];
+                    if !rest.is_empty() {
+                        let len = rest.find(self.delim).map_or(rest.len(), |end| end + 1);
+                        let mut line = rest[..len].to_string();
+                        if !line.ends_with(self.delim) {
+                            line.push(self.delim);
+                        }
+                        self.read_offsets[i] += len;
+                        self.append_queue.push(line);
+                    }
+                }
+            }
+            SedCmd::LineNumber => self.emit(&self.line_num().to_string()),
+            SedCmd::FileName => {
+                let name = self.inputs[self.file].name.clone();
+                self.emit(&name);
+            }
+            SedCmd::InsertText(text) => self.emit(text),
+            SedCmd::ChangeText(text) => {
+                // Over a range, the text replaces the range as a whole.
+                if !self.range_active[idx] {
+                    self.emit(text);
+                }
+                return Flow::Delete;
+            }
+            SedCmd::Transliterate(from, to) => {
+                self.pattern_space = self
+                    .pattern_space
+                    .chars()
+                    .map(|ch| match from.iter().position(|&c| c == ch) {
+                        Some(idx) if idx < to.len() => to[idx],
+                        _ => ch,
+                    })
+                    .collect();
+            }
+            SedCmd::WriteFile(fname) => self.write_to_file(fname),
+            SedCmd::HoldCopy => self.hold_space = self.pattern_space.clone(),
+            SedCmd::HoldAppend => {
+                self.hold_space.push(self.delim);
+                self.hold_space.push_str(&self.pattern_space);
+            }
+            SedCmd::GetCopy => self.pattern_space = self.hold_space.clone(),
+            SedCmd::GetAppend => {
+                self.pattern_space.push(self.delim);
+                self.pattern_space.push_str(&self.hold_space);
+            }
+            SedCmd::Exchange => std::mem::swap(&mut self.pattern_space, &mut self.hold_space),
+            SedCmd::Block { .. } | SedCmd::Label(_) => {}
+            SedCmd::Branch { when, target, .. } => {
+                let jump = match when {
+                    BranchWhen::Always => true,
+                    BranchWhen::Substituted => self.substituted,
+                    BranchWhen::NotSubstituted => !self.substituted,
+                };
+                if !matches!(when, BranchWhen::Always) {
+                    self.substituted = false;
+                }
+                if jump {
+                    return Flow::Jump(*target);
+                }
+            }
+        }
+        Flow::Continue
+    }
+
+    fn write_to_file(&mut self, fname: &str) {
+        let text = match self.file_writes.iter_mut().position(|(f, _)| f == fname) {
+            Some(i) => &mut self.file_writes[i].1,
+            None => {
+                self.file_writes.push((fname.to_string(), String::new()));
+                &mut self.file_writes.last_mut().unwrap().1
+            }
+        };
+        text.push_str(&self.pattern_space);
+        text.push(self.delim);
+    }
+}
+
+/// What a run leaves for the caller to write to files.
+struct Outcome {
+    /// Text written by `w`, to append to each file.
+    file_writes: Vec<(String, String)>,
+    /// With `-i`, the new contents of each input the run reached, by name.
+    edited: Vec<(String, Vec<u8>)>,
+}
+
+/// Run `rules` over `inputs`.
+fn run_sed(
+    inputs: Vec<Input>,
+    rules: &[Rule],
+    opts: &Options,
+    read_files: &[(String, String)],
+    out: &mut dyn Write,
+) -> Outcome {
+    let mut sed = Sed {
+        rules,
+        suppress: opts.suppress,
+        separate: opts.separate,
+        delim: opts.delim as char,
+        outputs: opts.in_place.then(|| vec![Vec::new(); inputs.len()]),
+        inputs,
+        file: 0,
+        next: 0,
+        line_number: 0,
+        owed: false,
+        pattern_space: String::new(),
+        hold_space: String::new(),
+        range_active: vec![false; rules.len()],
+        append_queue: Vec::new(),
+        read_files,
+        read_offsets: vec![0; read_files.len()],
+        file_writes: Vec::new(),
+        substituted: false,
+        out,
+    };
+    sed.run();
+    // After `q`, the files not yet reached are left alone.
+    let edited = match sed.outputs {
+        Some(outputs) => sed
+            .inputs
+            .iter()
+            .zip(outputs)
+            .take(sed.file + 1)
+            .map(|(input, output)| (input.name.clone(), output))
+            .collect(),
+        None => Vec::new(),
+    };
+    Outcome {
+        file_writes: sed.file_writes,
+        edited,
+    }
+}
+
+/// Load the files that `r` and `R` commands in `rules` read, skipping any
+/// that can't be read.
+fn load_read_files(io: &dyn Io, rules: &[Rule]) -> Vec<(String, String)> {
+    let mut files: Vec<(String, String)> = Vec::new();
+    for rule in rules {
+        let (SedCmd::ReadFile(fname) | SedCmd::ReadLine(fname)) = &rule.command else {
+            continue;
+        };
+        if files.iter().any(|(f, _)| f == fname) {
+            continue;
+        }
+        let mut content = Vec::new();
+        if io
+            .open(fname)
+            .and_then(|mut f| f.read_to_end(&mut content))
+            .is_ok()
+        {
+            files.push((
+                fname.clone(),
+                String::from_utf8_lossy(&content).into_owned(),
+            ));
+        }
+    }
+    files
+}
+
+/// Append what the rules wrote to each file.
+fn flush_file_writes(io: &mut dyn Io, writes: Vec<(String, String)>) {
+    for (fname, text) in writes {
+        let _ = io.append(&fname, text.as_bytes());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Main
+// ---------------------------------------------------------------------------
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut opts = Options {
+        suppress: false,
+        separate: false,
+        in_place: false,
+        delim: b'\n',
+    };
+    // Each piece of the script, from -e or -f, after the name errors in it
+    // are reported under.
+    let mut scripts: Vec<(String, String)> = Vec::new();
+    let mut expressions = 0;
+    let mut operands: Vec<String> = Vec::new();
+
+    ERE_MODE.set(false);
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        // The option and argument of -e or -f, in any of their forms.
+        let mut script_arg: Option<(char, Option<String>)> = None;
+        match arg {
+            "-n" | "--quiet" | "--silent" => opts.suppress = true,
+            "-i" | "--in-place" => opts.in_place = true,
+            "-s" | "--separate" => opts.separate = true,
+            "-E" | "-r" | "--regexp-extended" => ERE_MODE.set(true),
+            "-z" | "--null-data" => opts.delim = b'\0',
+            "--expression" => script_arg = Some(('e', None)),
+            "--file" => script_arg = Some(('f', None)),
+            "--" => {
+                operands.extend(args[i + 1..].iter().cloned());
+                break;
+            }
+            _ if arg.starts_with("--expression=") => {
+                script_arg = Some(('e', Some(arg["--expression=".len()..].to_string())));
+            }
+            _ if arg.starts_with("--file=") => {
+                script_arg = Some(('f', Some(arg["--file=".len()..].to_string())));
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                // Combined flags like -ni or -nE; an e or f takes the rest
+                // of the group, or else the next argument.
+                for (at, c) in arg.char_indices().skip(1) {
+                    match c {
+                        'n' => opts.suppress = true,
+                        'i' => opts.in_place = true,
+                        's' => opts.separate = true,
+                        'E' | 'r' => ERE_MODE.set(true),
+                        'z' => opts.delim = b'\0',
+                        'e' | 'f' => {
+                            let rest = &arg[at + 1..];
+                            script_arg = Some((c, (!rest.is_empty()).then(|| rest.to_string())));
+                            break;
+                        }
+                        _ => {
+                            let _ = writeln!(io.stderr(), "sed: invalid option -- '{}'", c);
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => operands.push(arg.to_string()),
+        }
+        if let Some((opt, value)) = script_arg {
+            let value = match value {
+                Some(value) => value,
+                None => {
+                    i += 1;
+                    match args.get(i) {
+                        Some(value) => value.clone(),
+                        None => {
+                            let _ = writeln!(
+                                io.stderr(),
+                                "sed: option requires an argument -- '{}'",
+                                opt
+                            );
+                            return 1;
+                        }
+                    }
+                }
+            };
+            if opt == 'e' {
+                expressions += 1;
+                scripts.push((format!("-e expression #{}", expressions), value));
+            } else {
+                let mut content = String::new();
+                if let Err(e) = io
+                    .open(&value)
+                    .and_then(|mut f| f.read_to_string(&mut content))
+                {
+                    // Another of GNU's panics, which exit 4.
+                    let _ = writeln!(io.stderr(), "sed: couldn't open file {}: {}", value, e);
+                    return 4;
+                }
+                scripts.push((format!("file {}", value), content));
+            }
+        }
+        i += 1;
+    }
+
+    // Without -e or -f, the first operand is the script.
+    if scripts.is_empty() && !operands.is_empty() {
+        scripts.push(("-e expression #1".to_string(), operands.remove(0)));
+    }
+    if scripts.is_empty() {
+        let _ = writeln!(io.stderr(), "sed: no script given");
+        return 1;
+    }
+    // A script that starts with a `#n` line acts as -n.
+    if scripts[0].1 == "#n" || scripts[0].1.starts_with("#n\n") {
+        opts.suppress = true;
+    }
+    opts.separate |= opts.in_place;
+
+    // The pieces form one script, so a block may open in one -e and
+    // close in the next. Remember the line each piece starts on so that
+    // errors still name the piece they came from.
+    let mut starts = Vec::new();
+    let mut line = 0;
+    for (name, script) in &scripts {
+        starts.push((line, name.as_str()));
+        line += script.matches('\n').count() + 1;
+    }
+    let joined = scripts
+        .iter()
+        .map(|(_, script)| script.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut rules = Vec::new();
+    if let Err((line, e)) = parse_script(&joined, &mut rules) {
+        let name = starts
+            .iter()
+            .rev()
+            .find(|&&(start, _)| start <= line)
+            .map_or("-e expression #1", |&(_, name)| name);
+        let _ = writeln!(io.stderr(), "sed: {}: {}", name, e);
+        return 1;
+    }
+    if let Err(e) = resolve_labels(&mut rules) {
+        // GNU reports this through its panic path, which exits 4.
+        let _ = writeln!(io.stderr(), "sed: {}", e);
+        return 4;
+    }
+    let read_files = load_read_files(io, &rules);
+
+    // With no files, or `-`, read standard input.
+    if operands.is_empty() {
+        operands.push("-".to_string());
+    }
+    let mut status = 0;
+    let mut inputs = Vec::new();
+    for file in &operands {
+        let mut data = Vec::new();
+        let read = if file == "-" && !opts.in_place {
+            io.stdin().and_then(|mut f| f.read_to_end(&mut data))
+        } else {
+            io.open(file).and_then(|mut f| f.read_to_end(&mut data))
+        };
+        match read {
+            Ok(_) => inputs.push(Input::new(file, &data, opts.delim)),
+            Err(e) => {
+                let _ = writeln!(io.stderr(), "sed: can't read {}: {}", file, e);
+                status = 2;
+            }
+        }
+    }
+
+    let outcome = run_sed(inputs, &rules, &opts, &read_files, io.stdout());
+    flush_file_writes(io, outcome.file_writes);
+    for (file, output) in outcome.edited {
+        // Write back through a temp file, so a failed write cannot
+        // truncate the original.
+        if let Err(e) = io.write_atomic(&file, &output) {
+            let _ = writeln!(io.stderr(), "sed: {}: {}", file, e);
+            return 1;
+        }
+    }
+    status
+}
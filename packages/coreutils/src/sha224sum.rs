@@ -0,0 +1,8 @@
+//! sha224sum - compute and check SHA-224 message digests
+
+use crate::digest::{self, Algorithm};
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    digest::run(Algorithm::Sha224, args, io)
+}
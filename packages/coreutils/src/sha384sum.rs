@@ -0,0 +1,8 @@
+//! sha384sum - compute and check SHA-384 message digests
+
+use crate::digest::{self, Algorithm};
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    digest::run(Algorithm::Sha384, args, io)
+}
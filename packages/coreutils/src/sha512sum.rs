@@ -0,0 +1,8 @@
+//! sha512sum - compute and check SHA-512 message digests
+
+use crate::digest::{self, Algorithm};
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    digest::run(Algorithm::Sha512, args, io)
+}
@@ -0,0 +1,43 @@
+//! tac - concatenate and print files in reverse
+
+use std::io::Read;
+
+use crate::Io;
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let input = if args.is_empty() || args[0] == "-" {
+        let mut buf = String::new();
+        if let Ok(mut stdin) = io.stdin() {
+            stdin.read_to_string(&mut buf).unwrap_or(0);
+        }
+        buf
+    } else {
+        let mut combined = String::new();
+        for path in args {
+            match io
+                .open(path)
+                .and_then(|mut f| f.read_to_string(&mut combined))
+            {
+                Ok(_) => {}
+                Err(e) => {
+                    let _ = writeln!(io.stderr(), "tac: {path}: {e}");
+                    return 1;
+                }
+            }
+        }
+        combined
+    };
+
+    let mut lines: Vec<&str> = input.split('\n').collect();
+    // Remove trailing empty element from final newline
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.reverse();
+
+    let out = io.stdout();
+    for line in lines {
+        let _ = writeln!(out, "{line}");
+    }
+    0
+}
@@ -0,0 +1,209 @@
+//! wc - word, line, and byte count
+
+use std::io::{self, BufRead, Write};
+
+use crate::Io;
+
+struct Counts {
+    lines: usize,
+    words: usize,
+    bytes: usize,
+    chars: usize,
+    max_line_len: usize,
+}
+
+impl Counts {
+    fn new() -> Self {
+        Counts {
+            lines: 0,
+            words: 0,
+            bytes: 0,
+            chars: 0,
+            max_line_len: 0,
+        }
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        if other.max_line_len > self.max_line_len {
+            self.max_line_len = other.max_line_len;
+        }
+    }
+}
+
+fn count_reader<R: BufRead>(mut buf: R) -> io::Result<Counts> {
+    let mut counts = Counts::new();
+    let mut line_buf = Vec::new();
+
+    // Lines are read as bytes so binary input is counted, not rejected;
+    // characters are those in the valid UTF-8 runs, as GNU wc counts them.
+    loop {
+        line_buf.clear();
+        let n = buf.read_until(b'\n', &mut line_buf)?;
+        if n == 0 {
+            break;
+        }
+        let content = line_buf.strip_suffix(b"\n");
+        let has_newline = content.is_some();
+        let content = content.unwrap_or(&line_buf);
+
+        counts.lines += if has_newline { 1 } else { 0 };
+        counts.bytes += n;
+        counts.chars += content
+            .utf8_chunks()
+            .map(|chunk| chunk.valid().chars().count())
+            .sum::<usize>()
+            + if has_newline { 1 } else { 0 };
+        counts.words += content
+            .split(|&b| matches!(b, b' ' | b'\t' | b'\r' | 0x0b | 0x0c))
+            .filter(|word| !word.is_empty())
+            .count();
+        if content.len() > counts.max_line_len {
+            counts.max_line_len = content.len();
+        }
+    }
+    Ok(counts)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn print_counts(
+    out: &mut dyn Write,
+    counts: &Counts,
+    show_lines: bool,
+    show_words: bool,
+    show_bytes: bool,
+    show_chars: bool,
+    show_max_line_len: bool,
+    name: &str,
+) {
+    let mut parts: Vec<String> = Vec::new();
+    if show_lines {
+        parts.push(format!("{:>8}", counts.lines));
+    }
+    if show_words {
+        parts.push(format!("{:>8}", counts.words));
+    }
+    if show_chars {
+        parts.push(format!("{:>8}", counts.chars));
+    }
+    if show_bytes {
+        parts.push(format!("{:>8}", counts.bytes));
+    }
+    if show_max_line_len {
+        parts.push(format!("{:>8}", counts.max_line_len));
+    }
+    let line = parts.join("");
+    if name.is_empty() {
+        let _ = writeln!(out, "{}", line);
+    } else {
+        let _ = writeln!(out, "{} {}", line, name);
+    }
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut show_lines = false;
+    let mut show_words = false;
+    let mut show_bytes = false;
+    let mut show_chars = false;
+    let mut show_max_line_len = false;
+    let mut files: Vec<String> = Vec::new();
+
+    for arg in args {
+        if arg == "--" {
+            // Everything after -- is a filename
+            continue;
+        }
+        if arg.starts_with('-') && arg.len() > 1 && !arg.starts_with("--") {
+            for ch in arg[1..].chars() {
+                match ch {
+                    'l' => show_lines = true,
+                    'w' => show_words = true,
+                    'c' => show_bytes = true,
+                    'm' => show_chars = true,
+                    'L' => show_max_line_len = true,
+                    _ => {
+                        let _ = writeln!(io.stderr(), "wc: invalid option -- '{}'", ch);
+                        return 1;
+                    }
+                }
+            }
+        } else {
+            files.push(arg.clone());
+        }
+    }
+
+    // If no specific flag is set, show all three (but not -L)
+    if !show_lines && !show_words && !show_bytes && !show_chars && !show_max_line_len {
+        show_lines = true;
+        show_words = true;
+        show_bytes = true;
+    }
+
+    let mut exit_code = 0;
+
+    if files.is_empty() {
+        // Read from stdin
+        match io.stdin().and_then(count_reader) {
+            Ok(counts) => print_counts(
+                io.stdout(),
+                &counts,
+                show_lines,
+                show_words,
+                show_bytes,
+                show_chars,
+                show_max_line_len,
+                "",
+            ),
+            Err(e) => {
+                let _ = writeln!(io.stderr(), "wc: {}", e);
+                exit_code = 1;
+            }
+        }
+    } else {
+        let mut total = Counts::new();
+        for file in &files {
+            match io.open(file) {
+                Ok(f) => match count_reader(f) {
+                    Ok(counts) => {
+                        print_counts(
+                            io.stdout(),
+                            &counts,
+                            show_lines,
+                            show_words,
+                            show_bytes,
+                            show_chars,
+                            show_max_line_len,
+                            file,
+                        );
+                        total.add(&counts);
+                    }
+                    Err(e) => {
+                        let _ = writeln!(io.stderr(), "wc: {}: {}", file, e);
+                        exit_code = 1;
+                    }
+                },
+                Err(e) => {
+                    let _ = writeln!(io.stderr(), "wc: {}: {}", file, e);
+                    exit_code = 1;
+                }
+            }
+        }
+        if files.len() > 1 {
+            print_counts(
+                io.stdout(),
+                &total,
+                show_lines,
+                show_words,
+                show_bytes,
+                show_chars,
+                show_max_line_len,
+                "total",
+            );
+        }
+    }
+
+    exit_code
+}
@@ -0,0 +1,291 @@
+//! xxd - make a hexdump or do the reverse
+
+use std::io::{Read, Write};
+
+use crate::hexdump::{parse_size, printable};
+use crate::Io;
+
+#[derive(Default)]
+struct Options {
+    cols: Option<usize>,
+    group: Option<usize>,
+    length: Option<u64>,
+    /// Where to start: from the end of the input if negative.
+    seek: i64,
+    plain: bool,
+    revert: bool,
+    upper: bool,
+}
+
+/// Dump `data`, which starts `offset` bytes into the input.
+fn dump(data: &[u8], offset: u64, opts: &Options) -> Vec<u8> {
+    let mut out = Vec::new();
+    let hex = |byte: u8| {
+        if opts.upper {
+            format!("{byte:02X}")
+        } else {
+            format!("{byte:02x}")
+        }
+    };
+    if opts.plain {
+        let cols = opts.cols.unwrap_or(30).max(1);
+        for line in data.chunks(cols) {
+            for &byte in line {
+                out.extend_from_slice(hex(byte).as_bytes());
+            }
+            out.push(b'\n');
+        }
+        return out;
+    }
+
+    let cols = opts.cols.unwrap_or(16).max(1);
+    let group = match opts.group.unwrap_or(2) {
+        0 => cols,
+        n => n,
+    };
+    for (n, line) in data.chunks(cols).enumerate() {
+        let _ = write!(out, "{:08x}: ", offset + (n * cols) as u64);
+        for i in 0..cols {
+            match line.get(i) {
+                Some(&byte) => out.extend_from_slice(hex(byte).as_bytes()),
+                None => out.extend_from_slice(b"  "),
+            }
+            if (i + 1) % group == 0 || i + 1 == cols {
+                out.push(b' ');
+            }
+        }
+        out.push(b' ');
+        out.extend(line.iter().map(|&byte| printable(byte) as u8));
+        out.push(b'\n');
+    }
+    out
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Write the bytes of a plain hex dump into `out` from `base` on,
+/// ignoring anything not hex.
+fn revert_plain(input: &[u8], base: u64, out: &mut Vec<u8>) {
+    let mut pos = base as usize;
+    let mut high = None;
+    for &c in input {
+        let Some(v) = hex_value(c) else { continue };
+        match high.take() {
+            Some(h) => {
+                put(out, pos, h << 4 | v);
+                pos += 1;
+            }
+            None => high = Some(v),
+        }
+    }
+}
+
+/// Set `out[pos]`, growing `out` with zeros as needed.
+fn put(out: &mut Vec<u8>, pos: usize, byte: u8) {
+    if pos >= out.len() {
+        out.resize(pos + 1, 0);
+    }
+    out[pos] = byte;
+}
+
+/// Write a dump's bytes into `out` at the offsets its lines give, plus
+/// `base`. The hex on a line ends at the two spaces before the text
+/// column.
+fn revert(input: &[u8], base: u64, out: &mut Vec<u8>) {
+    for line in input.split(|&b| b == b'\n') {
+        let Some(colon) = line.iter().position(|&b| b == b':') else {
+            continue;
+        };
+        let Ok(address) = std::str::from_utf8(&line[..colon])
+            .map_err(|_| ())
+            .and_then(|s| u64::from_str_radix(s.trim(), 16).map_err(|_| ()))
+        else {
+            continue;
+        };
+        let mut pos = (address + base) as usize;
+        let mut high = None;
+        let mut spaces = 0;
+        for &c in &line[colon + 1..] {
+            if c == b' ' || c == b'\t' {
+                spaces += 1;
+                // Two spaces once hex has begun mark the text column.
+                if spaces >= 2 && pos > (address + base) as usize {
+                    break;
+                }
+                continue;
+            }
+            spaces = 0;
+            let Some(v) = hex_value(c) else { break };
+            match high.take() {
+                Some(h) => {
+                    put(out, pos, h << 4 | v);
+                    pos += 1;
+                }
+                None => high = Some(v),
+            }
+        }
+    }
+}
+
+fn print_usage(stderr: &mut dyn Write) {
+    let _ = writeln!(stderr, "Usage: xxd [OPTION]... [INFILE [OUTFILE]]");
+    let _ = writeln!(stderr, "   or: xxd -r [-p] [-s OFFSET] [INFILE [OUTFILE]]");
+    let _ = writeln!(stderr);
+    let _ = writeln!(
+        stderr,
+        "  -c COLS       format COLS octets per line (default 16; -p: 30)"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -g BYTES      number of octets per group (default 2)"
+    );
+    let _ = writeln!(stderr, "  -l LEN        stop after LEN octets");
+    let _ = writeln!(stderr, "  -p            output in plain hexdump style");
+    let _ = writeln!(
+        stderr,
+        "  -r            reverse: convert a hexdump into binary, patching OUTFILE"
+    );
+    let _ = writeln!(
+        stderr,
+        "  -s [-]OFFSET  start at OFFSET bytes (from the end if negative); with -r, add OFFSET to addresses"
+    );
+    let _ = writeln!(stderr, "  -u            use upper case hex letters");
+}
+
+pub fn run(args: &[String], io: &mut dyn Io) -> i32 {
+    let mut opts = Options::default();
+    let mut files: Vec<&str> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        // xxd's options are single words, which some take a value after
+        // or attached, as in -c8.
+        let (name, attached) = match arg {
+            "-cols" | "-groupsize" | "-len" | "-seek" => (arg, None),
+            _ if arg.len() > 2 && matches!(&arg[..2], "-c" | "-g" | "-l" | "-s") => {
+                (&arg[..2], Some(&arg[2..]))
+            }
+            _ => (arg, None),
+        };
+        match name {
+            "-h" | "-help" | "--help" => {
+                print_usage(io.stderr());
+                return 0;
+            }
+            "-p" | "-ps" | "-postscript" | "-plain" => opts.plain = true,
+            "-r" | "-revert" => opts.revert = true,
+            "-u" => opts.upper = true,
+            "-c" | "-cols" | "-g" | "-groupsize" | "-l" | "-len" | "-s" | "-seek" => {
+                let val = match attached {
+                    Some(val) => val,
+                    None => {
+                        i += 1;
+                        match args.get(i) {
+                            Some(val) => val.as_str(),
+                            None => {
+                                let _ = writeln!(
+                                    io.stderr(),
+                                    "xxd: option '{arg}' requires an argument"
+                                );
+                                return 1;
+                            }
+                        }
+                    }
+                };
+                let (negative, digits) = match val.strip_prefix('-') {
+                    Some(rest) if name.starts_with("-s") => (true, rest),
+                    _ => (false, val.strip_prefix('+').unwrap_or(val)),
+                };
+                let Some(n) = parse_size(digits) else {
+                    let _ = writeln!(io.stderr(), "xxd: invalid number '{val}' for '{name}'");
+                    return 1;
+                };
+                match name {
+                    "-c" | "-cols" => opts.cols = Some(n as usize),
+                    "-g" | "-groupsize" => opts.group = Some(n as usize),
+                    "-l" | "-len" => opts.length = Some(n),
+                    _ => opts.seek = if negative { -(n as i64) } else { n as i64 },
+                }
+            }
+            "--" => {
+                files.extend(args[i + 1..].iter().map(String::as_str));
+                break;
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                let _ = writeln!(io.stderr(), "xxd: invalid option '{arg}'");
+                return 1;
+            }
+            _ => files.push(arg),
+        }
+        i += 1;
+    }
+
+    if files.len() > 2 {
+        let _ = writeln!(io.stderr(), "xxd: extra operand '{}'", files[2]);
+        return 1;
+    }
+    let infile = files.first().copied().unwrap_or("-");
+    let outfile = files.get(1).copied().filter(|&f| f != "-");
+
+    let mut input = Vec::new();
+    let read = if infile == "-" {
+        io.stdin()
+    } else {
+        io.open(infile)
+    }
+    .and_then(|mut reader| reader.read_to_end(&mut input));
+    if let Err(e) = read {
+        let _ = writeln!(io.stderr(), "xxd: {infile}: {e}");
+        return 1;
+    }
+
+    let output = if opts.revert {
+        if opts.seek < 0 {
+            let _ = writeln!(
+                io.stderr(),
+                "xxd: sorry, cannot revert with a negative offset"
+            );
+            return 1;
+        }
+        // Patch whatever is already in the output file.
+        let mut output = Vec::new();
+        if let Some(mut existing) = outfile.and_then(|f| io.open(f).ok()) {
+            let _ = existing.read_to_end(&mut output);
+        }
+        if opts.plain {
+            revert_plain(&input, opts.seek as u64, &mut output);
+        } else {
+            revert(&input, opts.seek as u64, &mut output);
+        }
+        output
+    } else {
+        let start = if opts.seek < 0 {
+            input
+                .len()
+                .saturating_sub(opts.seek.unsigned_abs() as usize)
+        } else {
+            (opts.seek as usize).min(input.len())
+        };
+        let end = match opts.length {
+            Some(n) => start.saturating_add(n as usize).min(input.len()),
+            None => input.len(),
+        };
+        dump(&input[start..end], start as u64, &opts)
+    };
+
+    match outfile {
+        Some(path) => {
+            if let Err(e) = io.write_atomic(path, &output) {
+                let _ = writeln!(io.stderr(), "xxd: {path}: {e}");
+                return 1;
+            }
+        }
+        None => {
+            let _ = io.stdout().write_all(&output);
+        }
+    }
+    0
+}
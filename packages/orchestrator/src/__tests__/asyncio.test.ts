@@ -0,0 +1,64 @@
+/**
+ * Integration tests for Python asyncio support.
+ *
+ * Verifies that asyncio.run() drives coroutines on the host-driven event
+ * loop from _codepod_asyncio.py: timers, gathered tasks and executor calls.
+ */
+import { describe, it, afterEach } from '@std/testing/bdd';
+import { expect } from '@std/expect';
+import { resolve } from 'node:path';
+import { Sandbox } from '../sandbox.js';
+import { NodeAdapter } from '../platform/node-adapter.js';
+
+const WASM_DIR = resolve(import.meta.dirname!, '../platform/__tests__/fixtures');
+
+describe('Python asyncio', () => {
+  let sandbox: Sandbox;
+
+  afterEach(() => {
+    sandbox?.destroy();
+  });
+
+  it('asyncio.run gathers sleeping coroutines', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    sandbox.writeFile('/tmp/gather.py', new TextEncoder().encode([
+      'import asyncio',
+      'async def work(name, delay):',
+      '    await asyncio.sleep(delay)',
+      '    return name',
+      'async def main():',
+      '    print(await asyncio.gather(work("a", 0.1), work("b", 0.05)))',
+      '    print(type(asyncio.get_running_loop()).__name__)',
+      'asyncio.run(main())',
+    ].join('\n')));
+    const result = await sandbox.run('python3 /tmp/gather.py');
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe("['a', 'b']\nHostEventLoop");
+  });
+
+  it('to_thread and wait_for work without threads', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    sandbox.writeFile('/tmp/executor.py', new TextEncoder().encode([
+      'import asyncio',
+      'async def main():',
+      '    print(await asyncio.to_thread(sum, [1, 2, 3]))',
+      '    try:',
+      '        await asyncio.wait_for(asyncio.sleep(5), 0.05)',
+      '    except TimeoutError:',
+      '        print("timed out")',
+      'asyncio.run(main())',
+    ].join('\n')));
+    const result = await sandbox.run('python3 /tmp/executor.py');
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('6\ntimed out');
+  });
+
+  it('a loop with nothing left to wait for raises instead of hanging', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    const result = await sandbox.run(
+      'python3 -c "import asyncio; asyncio.run(asyncio.Event().wait())"'
+    );
+    expect(result.exitCode).not.toBe(0);
+    expect(result.stderr).toContain('no timers or I/O pending');
+  });
+});
@@ -0,0 +1,73 @@
+/**
+ * Integration tests for Python multiprocessing support.
+ *
+ * Verifies that multiprocessing.Pool is the host-run pool from
+ * _codepod_multiprocessing.py: map, queued apply_async calls with their
+ * callbacks, and errors raised by tasks.
+ */
+import { describe, it, afterEach } from '@std/testing/bdd';
+import { expect } from '@std/expect';
+import { resolve } from 'node:path';
+import { Sandbox } from '../sandbox.js';
+import { NodeAdapter } from '../platform/node-adapter.js';
+
+const WASM_DIR = resolve(import.meta.dirname!, '../platform/__tests__/fixtures');
+
+describe('Python multiprocessing', () => {
+  let sandbox: Sandbox;
+
+  afterEach(() => {
+    sandbox?.destroy();
+  });
+
+  it('Pool.map runs a script function over its inputs', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    sandbox.writeFile('/tmp/pool_map.py', new TextEncoder().encode([
+      'from multiprocessing import Pool',
+      'def square(x):',
+      '    return x * x',
+      'if __name__ == "__main__":',
+      '    with Pool(3) as pool:',
+      '        print(pool.map(square, range(7)))',
+      '        print(type(pool).__module__)',
+    ].join('\n')));
+    const result = await sandbox.run('python3 /tmp/pool_map.py');
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('[0, 1, 4, 9, 16, 25, 36]\n_codepod_multiprocessing');
+  });
+
+  it('apply_async queues tasks until a result is waited for', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    sandbox.writeFile('/tmp/pool_async.py', new TextEncoder().encode([
+      'import multiprocessing',
+      'def double(x):',
+      '    return 2 * x',
+      'if __name__ == "__main__":',
+      '    with multiprocessing.get_context("spawn").Pool(2) as pool:',
+      '        results = [pool.apply_async(double, (i,), callback=print) for i in range(3)]',
+      '        print("queued", [r.ready() for r in results])',
+      '        print([r.get() for r in results])',
+    ].join('\n')));
+    const result = await sandbox.run('python3 /tmp/pool_async.py');
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('queued [False, False, False]\n0\n2\n4\n[0, 2, 4]');
+  });
+
+  it('a task\'s exception is raised by get()', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    sandbox.writeFile('/tmp/pool_error.py', new TextEncoder().encode([
+      'from multiprocessing import Pool',
+      'def fail(x):',
+      '    raise ValueError(f"bad {x}")',
+      'if __name__ == "__main__":',
+      '    with Pool(2) as pool:',
+      '        try:',
+      '            pool.apply(fail, (5,))',
+      '        except ValueError as e:',
+      '            print("caught", e)',
+    ].join('\n')));
+    const result = await sandbox.run('python3 /tmp/pool_error.py');
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('caught bad 5');
+  });
+});
@@ -213,6 +213,33 @@ describe('Sandbox', { sanitizeResources: false, sanitizeOps: false }, () => {
     });
   });
 
+  describe('randomSeed', () => {
+    const script = 'python3 -c "import random, secrets; print(random.random(), secrets.token_hex(8))"';
+
+    it('makes Python randomness reproducible', async () => {
+      sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter(), randomSeed: 1234 });
+      const first = await sandbox.run(script);
+      const other = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter(), randomSeed: 1234 });
+      try {
+        const second = await other.run(script);
+        expect(first.exitCode).toBe(0);
+        expect(second.stdout).toBe(first.stdout);
+      } finally {
+        other.destroy();
+      }
+    });
+
+    it('different seeds give different draws', async () => {
+      sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter(), randomSeed: 1 });
+      const other = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter(), randomSeed: 2 });
+      try {
+        expect((await other.run(script)).stdout).not.toBe((await sandbox.run(script)).stdout);
+      } finally {
+        other.destroy();
+      }
+    });
+  });
+
   describe('fork', () => {
     it('creates an independent sandbox with COW VFS', async () => {
       sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
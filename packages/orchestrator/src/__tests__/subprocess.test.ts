@@ -81,4 +81,49 @@ describe('Python subprocess', () => {
     expect(result.exitCode).toBe(0);
     expect(result.stdout.trim()).toBe('popen');
   });
+
+  it('subprocess.run honours cwd', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    const result = await sandbox.run(
+      'mkdir -p /tmp/work && python3 -c "import subprocess; r = subprocess.run([\'pwd\'], cwd=\'/tmp/work\', capture_output=True, text=True); print(r.stdout.strip())"'
+    );
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('/tmp/work');
+  });
+
+  it('subprocess.run rejects a missing cwd', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    const result = await sandbox.run(
+      'python3 -c "import subprocess\ntry:\n    subprocess.run([\'true\'], cwd=\'/no/such/dir\')\nexcept FileNotFoundError:\n    print(\'missing\')"'
+    );
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('missing');
+  });
+
+  it('subprocess.run passes env and inherits os.environ', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    const result = await sandbox.run(
+      'python3 -c "import os, subprocess; os.environ[\'INHERITED\'] = \'a\'; r = subprocess.run(\'echo \\$INHERITED \\$GIVEN\', shell=True, env={**os.environ, \'GIVEN\': \'b\'}, capture_output=True, text=True); print(r.stdout.strip()); print(subprocess.getoutput(\'echo \\$INHERITED\'))"'
+    );
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('a b\na');
+  });
+
+  it('subprocess.Popen writes to a stdin pipe', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    const result = await sandbox.run(
+      'python3 -c "import subprocess; p = subprocess.Popen([\'tr\', \'a-z\', \'A-Z\'], stdin=subprocess.PIPE, stdout=subprocess.PIPE, text=True); out, _ = p.communicate(\'shout\'); print(out, p.returncode)"'
+    );
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('SHOUT 0');
+  });
+
+  it('uncaptured output reaches the caller', async () => {
+    sandbox = await Sandbox.create({ wasmDir: WASM_DIR, adapter: new NodeAdapter() });
+    const result = await sandbox.run(
+      'python3 -c "import subprocess; subprocess.run([\'echo\', \'through\'])"'
+    );
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout.trim()).toBe('through');
+  });
 });
@@ -17,6 +17,7 @@ import {
 } from './proxy-protocol.js';
 import { ProcessManager } from '../process/manager.js';
 import { ShellInstance } from '../shell/shell-instance.js';
+import { seededRandomSource } from '../wasi/wasi-host.js';
 import type { RunResult } from '../shell/shell-types.js';
 import { CancelledError } from '../security.js';
 
@@ -36,6 +37,7 @@ interface InitMessage {
   bridgeSab?: SharedArrayBuffer;
   networkPolicy?: { allowedHosts?: string[]; blockedHosts?: string[] };
   hasExtensions?: boolean;
+  randomSeed?: number;
 }
 
 interface RunMessage {
@@ -99,7 +101,9 @@ parentPort.on('message', async (msg: InitMessage | RunMessage) => {
     // Pre-load all tool modules so spawnSync can use them synchronously
     await mgr.preloadModules();
 
-    runner = await ShellInstance.create(vfs, mgr, adapter, shellExecWasmPath);
+    runner = await ShellInstance.create(vfs, mgr, adapter, shellExecWasmPath, {
+      randomSource: msg.randomSeed === undefined ? undefined : seededRandomSource(msg.randomSeed),
+    });
 
     if (msg.stdoutBytes !== undefined || msg.stderrBytes !== undefined) {
       runner.setOutputLimits(msg.stdoutBytes, msg.stderrBytes);
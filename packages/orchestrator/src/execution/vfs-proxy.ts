@@ -175,6 +175,10 @@ export class VfsProxy {
     this.call('chmod', { path, mode });
   }
 
+  utimes(path: string, atime: Date, mtime: Date): void {
+    this.call('utimes', { path, atime: atime.getTime(), mtime: mtime.getTime() });
+  }
+
   symlink(target: string, path: string): void {
     this.call('symlink', { target, path });
   }
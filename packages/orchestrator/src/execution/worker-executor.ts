@@ -31,6 +31,8 @@ export interface WorkerConfig {
   bridgeSab?: SharedArrayBuffer;
   networkPolicy?: { allowedHosts?: string[]; blockedHosts?: string[] };
   extensionRegistry?: ExtensionRegistry;
+  /** Seed for the worker shell's random source; see SandboxOptions. */
+  randomSeed?: number;
 }
 
 export interface WorkerRunResult extends RunResult {
@@ -202,6 +204,7 @@ export class WorkerExecutor {
       bridgeSab: this.config.bridgeSab,
       networkPolicy: this.config.networkPolicy,
       hasExtensions: this.config.extensionRegistry != null,
+      randomSeed: this.config.randomSeed,
     });
 
     await readyPromise;
@@ -318,6 +321,16 @@ export class WorkerExecutor {
           Atomics.store(this.int32, 0, STATUS_RESPONSE);
           break;
         }
+        case 'utimes': {
+          vfs.utimes(
+            metadata.path as string,
+            new Date(metadata.atime as number),
+            new Date(metadata.mtime as number),
+          );
+          encodeResponse(this.sab, { ok: true });
+          Atomics.store(this.int32, 0, STATUS_RESPONSE);
+          break;
+        }
         case 'symlink': {
           vfs.symlink(metadata.target as string, metadata.path as string);
           encodeResponse(this.sab, { ok: true });
@@ -0,0 +1,136 @@
+/**
+ * Guest ABI handshake.
+ *
+ * The shell, python and native-module guests export `__abi_version()` and
+ * `__abi_capabilities()` next to `__alloc`/`__dealloc`. Checking the version
+ * right after instantiation turns a host/guest build mismatch into a clear
+ * error instead of memory corruption on the first buffer exchange.
+ */
+
+/** The guest ABI version this host speaks. */
+export const GUEST_ABI_VERSION = 1;
+
+/**
+ * Throw if `instance` was built for a different guest ABI, and return its
+ * capability bitmask. Guests that predate the handshake export neither
+ * function and are accepted with no capabilities.
+ */
+export function checkGuestAbi(instance: WebAssembly.Instance, name: string): number {
+  const abiVersion = instance.exports.__abi_version as (() => number) | undefined;
+  if (typeof abiVersion !== 'function') return 0;
+
+  const version = abiVersion() >>> 0;
+  if (version !== GUEST_ABI_VERSION) {
+    throw new Error(
+      `${name} speaks guest ABI version ${version}, but this host speaks version ` +
+        `${GUEST_ABI_VERSION}; rebuild the guest or the host`,
+    );
+  }
+  const abiCapabilities = instance.exports.__abi_capabilities as (() => number) | undefined;
+  return typeof abiCapabilities === 'function' ? abiCapabilities() >>> 0 : 0;
+}
+
+/** Where and why a guest panicked, as its `__panic_info` export reports. */
+export interface GuestPanic {
+  message: string;
+  file?: string;
+  line?: number;
+  column?: number;
+}
+
+/** A guest trapped after panicking; `panic` says where and why. */
+export class GuestPanicError extends Error {
+  constructor(readonly guest: string, readonly panic: GuestPanic, options?: { cause?: unknown }) {
+    const at = panic.file ? ` at ${panic.file}:${panic.line ?? 0}:${panic.column ?? 0}` : '';
+    super(`${guest} panicked${at}: ${panic.message}`, options);
+    this.name = 'GuestPanicError';
+  }
+}
+
+/**
+ * The first panic `instance` recorded, or null if it has not panicked or
+ * does not export `__panic_info`. Safe to call after the guest trapped.
+ */
+export function readGuestPanic(instance: WebAssembly.Instance): GuestPanic | null {
+  return readGuestJson<GuestPanic>(instance, '__panic_info');
+}
+
+/** One frame of a GuestException's traceback, innermost last. */
+export interface GuestExceptionFrame {
+  file: string;
+  line: number;
+  endLine?: number | null;
+  /** 1-based and inclusive, indexing into `source`. */
+  column?: number | null;
+  endColumn?: number | null;
+  /** The function, or `<module>`. */
+  name: string;
+  /** The source line, read from the VFS. */
+  source?: string | null;
+}
+
+/** The uncaught exception a script died of, as `__exception_info` reports. */
+export interface GuestException {
+  /** The exception class, e.g. `KeyError`. */
+  type: string;
+  message: string;
+  /** The traceback as printed to stderr, with source lines and carets. */
+  traceback: string;
+  frames: GuestExceptionFrame[];
+}
+
+/**
+ * The uncaught exception `instance` reported before exiting, or null if it
+ * exited cleanly or does not export `__exception_info`.
+ */
+export function readGuestException(instance: WebAssembly.Instance): GuestException | null {
+  try {
+    return readGuestJson<GuestException>(instance, '__exception_info');
+  } catch {
+    // A trapped or half-written report is no report.
+    return null;
+  }
+}
+
+/**
+ * Call an `(outPtr, outCap) -> len` export that writes JSON into a guest
+ * buffer, growing the buffer once if the first one was too small. Null
+ * when the export is missing or has nothing to report.
+ */
+function readGuestJson<T>(instance: WebAssembly.Instance, name: string): T | null {
+  const report = instance.exports[name] as
+    | ((outPtr: number, outCap: number) => number)
+    | undefined;
+  const alloc = instance.exports.__alloc as ((size: number) => number) | undefined;
+  const dealloc = instance.exports.__dealloc as ((ptr: number, size: number) => void) | undefined;
+  if (!report || !alloc || !dealloc) return null;
+
+  const memory = instance.exports.memory as WebAssembly.Memory;
+  let cap = 512;
+  let ptr = alloc(cap);
+  let n = report(ptr, cap);
+  if (n > cap) {
+    dealloc(ptr, cap);
+    cap = n;
+    ptr = alloc(cap);
+    n = report(ptr, cap);
+  }
+  const json = n > 0 ? new TextDecoder().decode(new Uint8Array(memory.buffer, ptr, n)) : null;
+  dealloc(ptr, cap);
+  return json === null ? null : (JSON.parse(json) as T);
+}
+
+/**
+ * Turn a trap out of `instance` into a GuestPanicError when the guest
+ * recorded a panic before trapping; return anything else unchanged.
+ */
+export function explainTrap(instance: WebAssembly.Instance, guest: string, error: unknown): unknown {
+  if (!(error instanceof WebAssembly.RuntimeError)) return error;
+  try {
+    const panic = readGuestPanic(instance);
+    return panic ? new GuestPanicError(guest, panic, { cause: error }) : error;
+  } catch {
+    // The instance may be too broken to ask; report the bare trap.
+    return error;
+  }
+}
@@ -15,6 +15,8 @@
  *   - host_network_fetch: HTTP fetch via NetworkBridge (async/JSPI)
  *   - host_extension_invoke: call a host extension (Python only; shell uses host_spawn)
  *   - host_run_command: run a shell command and collect output (async/JSPI, Python subprocess)
+ *   - host_rpc: answer a codepod-rpc request frame (async/JSPI); serves shell.run
+ *     and shell.run_all
  */
 
 import type { NetworkBridgeLike } from '../network/bridge.js';
@@ -23,7 +25,30 @@ import type { NativeModuleRegistry } from '../process/native-modules.js';
 import type { ProcessKernel, SpawnRequest } from '../process/kernel.js';
 import type { FdTarget } from '../wasi/fd-target.js';
 import { createStaticTarget } from '../wasi/fd-target.js';
-import { readString, writeJson } from './common.js';
+import { readBytes, readString, writeBytes, writeJson } from './common.js';
+import { RPC_ERROR, RPC_METHOD, RpcError, RpcOutbox, type RpcRequest } from './rpc.js';
+
+/** Where and how a command asked for by a guest (Python subprocess) runs. */
+export interface RunCommandOptions {
+  /** Directory to run in; the new shell's default when unset. */
+  cwd?: string;
+  /** Variables exported before the command runs. */
+  env?: Record<string, string>;
+}
+
+/** A guest's shell.run request, before validation. */
+interface ShellRunParams {
+  command?: unknown;
+  stdin?: unknown;
+  cwd?: unknown;
+  env?: unknown;
+}
+
+export type RunCommandFn = (
+  cmd: string,
+  stdin: string,
+  options?: RunCommandOptions,
+) => Promise<{ exitCode: number; stdout: string; stderr: string }>;
 
 export interface KernelImportsOptions {
   memory: WebAssembly.Memory;
@@ -52,19 +77,85 @@ export interface KernelImportsOptions {
   extensionHandler?: (cmd: Record<string, unknown>) => Record<string, unknown>;
 
   /** Run a shell command and collect output. Used by Python _codepod.spawn(). */
-  runCommand?: (cmd: string, stdin: string) => Promise<{ exitCode: number; stdout: string; stderr: string }>;
+  runCommand?: RunCommandFn;
 
   /** Called by host_spawn to actually create and start a WASM process. */
   spawnProcess?: (req: SpawnRequest, fdTable: Map<number, FdTarget>) => number;
 
   /** Registry of dynamically loaded native Python module WASMs. */
   nativeModules?: NativeModuleRegistry;
+
+  /**
+   * Polled by host_should_cancel. Returns 0 to keep running, 1 if the run
+   * was cancelled, 2 if its deadline passed. Defaults to never cancelling.
+   */
+  shouldCancel?: () => number;
+
+  /**
+   * Terminal the sandbox's output is displayed on. When set, fds writing to
+   * the run's output buffers report as terminals of this size; pipes and
+   * files never do. Defaults to no terminal.
+   */
+  terminal?: TerminalSize;
+}
+
+/** Size of a terminal, in character cells. */
+export interface TerminalSize {
+  cols: number;
+  rows: number;
 }
 
 export function createKernelImports(opts: KernelImportsOptions): Record<string, WebAssembly.ImportValue> {
   const { memory } = opts;
   const callerPid = opts.callerPid ?? 0;
 
+  const onTerminal = (fd: number): boolean =>
+    opts.terminal !== undefined && opts.kernel?.getFdTarget(callerPid, fd)?.type === 'buffer';
+
+  const rpcOutbox = new RpcOutbox();
+  /** host_read_fd data that did not fit the caller's buffer, keyed by fd. */
+  const pendingReads = new Map<number, Uint8Array>();
+  const runFromParams = async (
+    method: string,
+    params: ShellRunParams | undefined,
+  ): Promise<{ exit_code: number; stdout: string; stderr: string }> => {
+    if (typeof params?.command !== 'string') {
+      throw new RpcError(RPC_ERROR.invalidParams, `${method}: missing command`);
+    }
+    if (!opts.runCommand) {
+      return { exit_code: 1, stdout: '', stderr: 'subprocess not available\n' };
+    }
+    const stdin = typeof params.stdin === 'string' ? params.stdin : '';
+    const options: RunCommandOptions = {};
+    if (typeof params.cwd === 'string') options.cwd = params.cwd;
+    if (params.env !== undefined && params.env !== null) {
+      if (typeof params.env !== 'object' || Array.isArray(params.env)
+        || Object.values(params.env).some((v) => typeof v !== 'string')) {
+        throw new RpcError(RPC_ERROR.invalidParams, `${method}: env must map names to strings`);
+      }
+      options.env = params.env as Record<string, string>;
+    }
+    const result = await opts.runCommand(params.command, stdin, options);
+    return { exit_code: result.exitCode, stdout: result.stdout, stderr: result.stderr };
+  };
+  const handleRpc = async (req: RpcRequest): Promise<unknown> => {
+    switch (req.method) {
+      case RPC_METHOD.shellRun:
+        return runFromParams(req.method, req.params as ShellRunParams | undefined);
+      case RPC_METHOD.shellRunAll: {
+        // Each command gets a fresh shell (see runCommand), so they can
+        // all be in flight at once.
+        const commands = (req.params as { commands?: unknown } | undefined)?.commands;
+        if (!Array.isArray(commands)) {
+          throw new RpcError(RPC_ERROR.invalidParams, 'shell.run_all: commands must be a list');
+        }
+        return Promise.all(commands.map((c) => runFromParams(req.method, c)));
+      }
+      default:
+        throw new RpcError(RPC_ERROR.methodNotFound, `unknown method: ${req.method}`);
+    }
+  };
+
   return {
     // ── Process management (new) ──
 
@@ -105,6 +196,24 @@ export function createKernelImports(opts: KernelImportsOptions): Record<string,
       return writeJson(memory, outPtr, outCap, { exit_code: exitCode });
     },
 
+    // host_waitpid_timeout(pid, timeout_ms, out_ptr, out_cap) -> i32
+    // Async — must be wrapped with WebAssembly.Suspending for JSPI.
+    // Like host_waitpid, but writes { timed_out: true } if the child is
+    // still running after timeout_ms.
+    async host_waitpid_timeout(pid: number, timeoutMs: number, outPtr: number, outCap: number): Promise<number> {
+      if (!opts.kernel) {
+        return writeJson(memory, outPtr, outCap, { exit_code: -1 });
+      }
+      let timer: ReturnType<typeof setTimeout> | undefined;
+      const expired = new Promise<null>((resolve) => { timer = setTimeout(() => resolve(null), timeoutMs); });
+      const exitCode = await Promise.race([opts.kernel.waitpid(pid), expired]);
+      clearTimeout(timer);
+      if (exitCode === null) {
+        return writeJson(memory, outPtr, outCap, { timed_out: true });
+      }
+      return writeJson(memory, outPtr, outCap, { exit_code: exitCode });
+    },
+
     // host_close_fd(fd) -> i32
     // Closes a file descriptor in the caller's fd table.
     host_close_fd(fd: number): number {
@@ -123,13 +232,19 @@ export function createKernelImports(opts: KernelImportsOptions): Record<string,
       if (!target || target.type !== 'pipe_read') {
         return writeJson(memory, outPtr, outCap, { error: `not a readable fd: ${fd}` });
       }
-      const data = target.pipe.drainSync();
-      const str = new TextDecoder().decode(data);
-      const buf = new Uint8Array(memory.buffer, outPtr, outCap);
-      const encoded = new TextEncoder().encode(str);
-      if (encoded.length > outCap) return encoded.length; // signal retry with larger buffer
-      buf.set(encoded);
-      return encoded.length;
+      // Data drained by a call whose buffer was too small is held here so
+      // the retry with a larger buffer still gets it.
+      let data = pendingReads.get(fd);
+      if (data) {
+        pendingReads.delete(fd);
+      } else {
+        data = target.pipe.drainSync();
+      }
+      if (data.length > outCap) {
+        pendingReads.set(fd, data);
+        return data.length; // signal retry with larger buffer
+      }
+      return writeBytes(memory, outPtr, outCap, data);
     },
 
     // host_write_fd(fd, data_ptr, data_len) -> i32
@@ -172,6 +287,27 @@ export function createKernelImports(opts: KernelImportsOptions): Record<string,
       await Promise.resolve();
     },
 
+    // host_should_cancel() -> i32
+    // Polled by the shell between pipeline stages, loop iterations and
+    // expansions: 0 = keep running, 1 = cancelled, 2 = timed out.
+    host_should_cancel(): number {
+      return opts.shouldCancel?.() ?? 0;
+    },
+
+    // host_isatty(fd) -> i32
+    // 1 if fd is attached to the terminal, 0 otherwise.
+    host_isatty(fd: number): number {
+      return onTerminal(fd) ? 1 : 0;
+    },
+
+    // host_terminal_size(fd) -> u32
+    // Terminal size packed as cols << 16 | rows, or 0 when fd is not a terminal.
+    host_terminal_size(fd: number): number {
+      if (!opts.terminal || !onTerminal(fd)) return 0;
+      const { cols, rows } = opts.terminal;
+      return ((cols & 0xffff) * 0x10000 + (rows & 0xffff)) >>> 0;
+    },
+
     // host_waitpid_nohang(pid) -> i32
     // Non-blocking: returns exit code if process exited, -1 if still running.
     host_waitpid_nohang(pid: number): number {
@@ -179,6 +315,13 @@ export function createKernelImports(opts: KernelImportsOptions): Record<string,
       return opts.kernel.waitpidNohang(pid);
     },
 
+    // host_kill(pid, signal) -> i32
+    // Terminate a child process. Returns 0 on success, -1 if no such process.
+    host_kill(pid: number, signal: number): number {
+      if (!opts.kernel) return -1;
+      return opts.kernel.kill(pid, signal);
+    },
+
     // host_list_processes(out_ptr, out_cap) -> i32
     // Returns JSON array of all processes.
     host_list_processes(outPtr: number, outCap: number): number {
@@ -449,5 +592,20 @@ export function createKernelImports(opts: KernelImportsOptions): Record<string,
       }
     },
 
+    // host_rpc(req_ptr, req_len, out_ptr, out_cap) -> i32 (async/JSPI)
+    // Answers a codepod-rpc request frame (see rpc.ts). A retry after a
+    // too-small buffer gets the held response without re-running the call.
+    async host_rpc(
+      reqPtr: number, reqLen: number,
+      outPtr: number, outCap: number,
+    ): Promise<number> {
+      const request = readBytes(memory, reqPtr, reqLen);
+      const response = await rpcOutbox.answer(request, outCap, handleRpc);
+      if (response.byteLength <= outCap) {
+        new Uint8Array(memory.buffer, outPtr, response.byteLength).set(response);
+      }
+      return response.byteLength;
+    },
+
   };
 }
@@ -0,0 +1,158 @@
+/**
+ * Request/response frames for calls across the WASM boundary — the host
+ * side of the `codepod-rpc` crate.
+ *
+ * A frame is a little-endian u32 byte length followed by that many bytes of
+ * UTF-8 JSON. Requests are `{id, method, params}`; responses echo the id with
+ * either `result` or `error: {code, message}`.
+ *
+ * The callee writes the response frame into the caller's output buffer and
+ * returns its length, or returns the length it needs without writing when
+ * the buffer is too small. The caller retries the same request with a
+ * bigger buffer, and the callee's RpcOutbox hands over the held response
+ * instead of running the request again.
+ */
+
+/** Method names shared with the guests (see `codepod_rpc::method`). */
+export const RPC_METHOD = {
+  shellRun: 'shell.run',
+  shellRunAll: 'shell.run_all',
+  fsRead: 'fs.read',
+  fsWrite: 'fs.write',
+  fsStat: 'fs.stat',
+  status: 'status',
+  transcriptStart: 'transcript.start',
+  transcriptGet: 'transcript.get',
+  transcriptStop: 'transcript.stop',
+  policySet: 'policy.set',
+} as const;
+
+/** Error codes, following JSON-RPC 2.0. */
+export const RPC_ERROR = {
+  parseError: -32700,
+  invalidRequest: -32600,
+  methodNotFound: -32601,
+  invalidParams: -32602,
+  internalError: -32603,
+  failed: -32000,
+} as const;
+
+/**
+ * Statuses in the GuestResult `{status: i32, ptr: u32, len: u32}` that
+ * structured entry points such as `__rpc_call` write for the host (see
+ * `codepod_rpc::abi`). On error or panic, `__last_error` has the message.
+ */
+export const GUEST_STATUS = {
+  ok: 0,
+  error: 1,
+  panic: 2,
+} as const;
+
+/** Bytes in a GuestResult. */
+export const GUEST_RESULT_SIZE = 12;
+
+export interface RpcRequest {
+  id: number;
+  method: string;
+  params?: unknown;
+}
+
+export interface RpcResponse {
+  id: number;
+  result?: unknown;
+  error?: { code: number; message: string };
+}
+
+export class RpcError extends Error {
+  constructor(readonly code: number, message: string) {
+    super(message);
+    this.name = 'RpcError';
+  }
+}
+
+const PREFIX_LEN = 4;
+
+/** Encode `message` as a frame. */
+export function encodeFrame(message: RpcRequest | RpcResponse): Uint8Array {
+  const json = new TextEncoder().encode(JSON.stringify(message));
+  const frame = new Uint8Array(PREFIX_LEN + json.byteLength);
+  new DataView(frame.buffer).setUint32(0, json.byteLength, true);
+  frame.set(json, PREFIX_LEN);
+  return frame;
+}
+
+/** Decode the frame at the start of `bytes`; trailing bytes are ignored. */
+export function decodeFrame<T extends RpcRequest | RpcResponse>(bytes: Uint8Array): T {
+  if (bytes.byteLength < PREFIX_LEN) {
+    throw new RpcError(RPC_ERROR.parseError, 'truncated frame');
+  }
+  const len = new DataView(bytes.buffer, bytes.byteOffset, PREFIX_LEN).getUint32(0, true);
+  if (bytes.byteLength - PREFIX_LEN < len) {
+    throw new RpcError(
+      RPC_ERROR.parseError,
+      `frame says ${len} bytes but ${bytes.byteLength - PREFIX_LEN} follow`,
+    );
+  }
+  const json = new TextDecoder().decode(bytes.subarray(PREFIX_LEN, PREFIX_LEN + len));
+  try {
+    return JSON.parse(json) as T;
+  } catch (e: unknown) {
+    throw new RpcError(RPC_ERROR.parseError, e instanceof Error ? e.message : String(e));
+  }
+}
+
+/** The outcome carried by `response`, throwing its error as an RpcError. */
+export function responseResult(response: RpcResponse): unknown {
+  if (response.error) {
+    throw new RpcError(response.error.code, response.error.message);
+  }
+  return response.result ?? null;
+}
+
+/**
+ * Answers request frames, holding on to a response that did not fit the
+ * caller's buffer until the caller retries the same request.
+ */
+export class RpcOutbox {
+  private held: { request: Uint8Array; response: Uint8Array } | null = null;
+
+  /**
+   * Return the response frame for `request`, calling `handle` unless this
+   * repeats the request whose response did not fit last time. If the frame
+   * is longer than `outCap` it is kept for the retry.
+   */
+  async answer(
+    request: Uint8Array,
+    outCap: number,
+    handle: (req: RpcRequest) => unknown | Promise<unknown>,
+  ): Promise<Uint8Array> {
+    let response: Uint8Array;
+    const held = this.held;
+    this.held = null;
+    if (held && sameBytes(held.request, request)) {
+      response = held.response;
+    } else {
+      let id = 0;
+      try {
+        const req = decodeFrame<RpcRequest>(request);
+        if (typeof req.id !== 'number' || typeof req.method !== 'string') {
+          throw new RpcError(RPC_ERROR.invalidRequest, 'not a request');
+        }
+        id = req.id;
+        response = encodeFrame({ id, result: (await handle(req)) ?? null });
+      } catch (e: unknown) {
+        const code = e instanceof RpcError ? e.code : RPC_ERROR.failed;
+        const message = e instanceof Error ? e.message : String(e);
+        response = encodeFrame({ id, error: { code, message } });
+      }
+    }
+    if (response.byteLength > outCap) {
+      this.held = { request: request.slice(), response };
+    }
+    return response;
+  }
+}
+
+function sameBytes(a: Uint8Array, b: Uint8Array): boolean {
+  return a.byteLength === b.byteLength && a.every((byte, i) => byte === b[i]);
+}
@@ -15,8 +15,22 @@ import { readString, readBytes, writeJson, writeString, writeBytes } from './com
 
 // Error codes matching Rust's rc_to_error convention
 const ERR_NOT_FOUND = -1;
-const _ERR_PERMISSION_DENIED = -2;
+const ERR_PERMISSION_DENIED = -2;
 const ERR_IO = -3;
+const ERR_IS_A_DIRECTORY = -4;
+const ERR_QUOTA_EXCEEDED = -7;
+
+/** Map a VFS error to the matching error code, or `fallback` if none fits. */
+function vfsErrorCode(err: unknown, fallback: number): number {
+  switch ((err as { errno?: string }).errno) {
+    case 'ENOENT': return ERR_NOT_FOUND;
+    case 'EACCES':
+    case 'EROFS': return ERR_PERMISSION_DENIED;
+    case 'EISDIR': return ERR_IS_A_DIRECTORY;
+    case 'ENOSPC': return ERR_QUOTA_EXCEEDED;
+    default: return fallback;
+  }
+}
 
 // ── Glob helpers ──
 
@@ -196,6 +210,10 @@ export function createShellImports(opts: ShellImportsOptions): Record<string, We
       return Date.now() / 1000;
     },
 
+    host_monotonic_ns(): bigint {
+      return BigInt(Math.round(performance.now() * 1e6));
+    },
+
     // ── Filesystem ──
 
     host_stat(pathPtr: number, pathLen: number, outPtr: number, outCap: number): number {
@@ -222,8 +240,8 @@ export function createShellImports(opts: ShellImportsOptions): Record<string, We
       try {
         const data = vfs.readFile(path);
         return writeBytes(memory, outPtr, outCap, data);
-      } catch {
-        return ERR_NOT_FOUND;
+      } catch (err) {
+        return vfsErrorCode(err, ERR_NOT_FOUND);
       }
     },
 
@@ -236,24 +254,25 @@ export function createShellImports(opts: ShellImportsOptions): Record<string, We
       const data = readBytes(memory, dataPtr, dataLen);
       try {
         if (mode === 1) {
-          // Append mode
+          // Append mode. Only a failed read means the file is new; a
+          // failed write (e.g. ENOSPC) must not fall back to truncating.
+          let existing: Uint8Array;
           try {
-            const existing = vfs.readFile(path);
-            const combined = new Uint8Array(existing.length + data.length);
-            combined.set(existing);
-            combined.set(data, existing.length);
-            vfs.writeFile(path, combined);
+            existing = vfs.readFile(path);
           } catch {
-            // File doesn't exist yet — create it
-            vfs.writeFile(path, data);
+            existing = new Uint8Array(0);
           }
+          const combined = new Uint8Array(existing.length + data.length);
+          combined.set(existing);
+          combined.set(data, existing.length);
+          vfs.writeFile(path, combined);
         } else {
           // Truncate mode (mode 0)
           vfs.writeFile(path, data);
         }
         return 0;
-      } catch {
-        return ERR_IO;
+      } catch (err) {
+        return vfsErrorCode(err, ERR_IO);
       }
     },
 
@@ -309,6 +328,16 @@ export function createShellImports(opts: ShellImportsOptions): Record<string, We
       }
     },
 
+    host_set_mtime(pathPtr: number, pathLen: number, mtimeMs: bigint): number {
+      const path = readString(memory, pathPtr, pathLen);
+      try {
+        vfs.utimes(path, vfs.stat(path).atime, new Date(Number(mtimeMs)));
+        return 0;
+      } catch (err) {
+        return vfsErrorCode(err, ERR_IO);
+      }
+    },
+
     host_glob(
       patternPtr: number, patternLen: number,
       outPtr: number, outCap: number,
@@ -74,6 +74,28 @@ describe('socket shim source', () => {
     expect(SITE_CUSTOMIZE_SOURCE).toContain('_inject_shim("ssl"');
   });
 
+  it('sitecustomize arms the interpreter limit hook when limits are set', () => {
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('_codepod, "limits_active"');
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('sys.setprofile(_codepod.limit_hook)');
+  });
+
+  it('sitecustomize reports uncaught exceptions to the host', () => {
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('sys.excepthook = _report_exception');
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('_codepod.report_exception(json.dumps(');
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('sys.__excepthook__(exc_type, exc, tb)');
+  });
+
+  it('sitecustomize defers the asyncio and multiprocessing shims to the first import', () => {
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('sys.meta_path.insert(0, _ShimFinder)');
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('"asyncio": ["_codepod_asyncio"]');
+    expect(SITE_CUSTOMIZE_SOURCE).toContain(
+      '"multiprocessing": ["_codepod_asyncio", "_codepod_multiprocessing"]',
+    );
+    expect(SITE_CUSTOMIZE_SOURCE).toContain('"/usr/lib/python/" + shim + ".py"');
+    expect(SITE_CUSTOMIZE_SOURCE).not.toContain('import asyncio');
+    expect(SITE_CUSTOMIZE_SOURCE).not.toContain('import multiprocessing');
+  });
+
   it('exports SSL_SHIM_SOURCE with required ssl API surface', () => {
     expect(typeof SSL_SHIM_SOURCE).toBe('string');
     expect(SSL_SHIM_SOURCE.length).toBeGreaterThan(100);
@@ -47,7 +47,8 @@ export function getSslShimSource(): string {
  *
  * subprocess.py is always installed in /usr/lib/python, so injecting it here
  * ensures `os.popen` is patched at interpreter start even without an explicit
- * `import subprocess`.
+ * `import subprocess`. It also hooks the first `import asyncio` to install
+ * the host-driven event loop from /usr/lib/python/_codepod_asyncio.py.
  */
 export function buildSiteCustomizeSource(opts: { networking?: boolean } = {}): string {
   let src = `\
@@ -73,11 +74,107 @@ def _inject_shim(name, path):
 
 
 _inject_shim("subprocess", "/usr/lib/python/subprocess.py")
+
+# Interpreters started with CODEPOD_PYTHON_* limits enforce them from a
+# profile hook, so a runaway script raises MemoryError/TimeoutError.
+import _codepod
+if getattr(_codepod, "limits_active", lambda: False)():
+    sys.setprofile(_codepod.limit_hook)
+
+
+def _report_exception(exc_type, exc, tb):
+    """Print the traceback as usual, and hand the host a structured copy:
+    each frame's location, column span and source line."""
+    sys.__excepthook__(exc_type, exc, tb)
+    try:
+        import json
+        import linecache
+        import traceback
+        frames = []
+        for frame in traceback.extract_tb(tb):
+            colno = getattr(frame, "colno", None)
+            # linecache reads files from the VFS; frame.line also covers -c.
+            line = linecache.getline(frame.filename, frame.lineno).rstrip("\\r\\n")
+            frames.append({
+                "file": frame.filename,
+                "line": frame.lineno,
+                "endLine": getattr(frame, "end_lineno", None),
+                # 1-based and inclusive, like the line numbers; they index
+                # into source, which keeps its indentation.
+                "column": None if colno is None else colno + 1,
+                "endColumn": getattr(frame, "end_colno", None),
+                "name": frame.name,
+                "source": line or frame.line or None,
+            })
+        _codepod.report_exception(json.dumps({
+            "type": exc_type.__qualname__,
+            "message": str(exc),
+            "traceback": "".join(traceback.format_exception(exc_type, exc, tb)),
+            "frames": frames,
+        }))
+    except Exception:
+        pass
+
+
+if hasattr(_codepod, "report_exception"):
+    sys.excepthook = _report_exception
+
+
+# Shims for stdlib packages the WASI build cannot run as they are, loaded
+# around the package's first import so scripts that never use it do not
+# pay for them. Each shim is imported before the package runs, the last
+# one's install() patches it afterwards.
+_DEFERRED_SHIMS = {
+    "asyncio": ["_codepod_asyncio"],
+    # multiprocessing imports socket, which the asyncio shim stubs out
+    # when networking is off.
+    "multiprocessing": ["_codepod_asyncio", "_codepod_multiprocessing"],
+}
+
+
+class _ShimLoader:
+    """Runs a package between importing its shims and their install()."""
+
+    def __init__(self, loader, shims):
+        self._loader = loader
+        self._shims = shims
+
+    def create_module(self, spec):
+        return self._loader.create_module(spec)
+
+    def exec_module(self, module):
+        for shim in self._shims:
+            if shim not in sys.modules:
+                _inject_shim(shim, "/usr/lib/python/" + shim + ".py")
+        self._loader.exec_module(module)
+        sys.modules[self._shims[-1]].install(module)
+
+
+class _ShimFinder:
+    """Wraps the loader of the first import of each shimmed package."""
+
+    @staticmethod
+    def find_spec(name, path=None, target=None):
+        shims = _DEFERRED_SHIMS.pop(name, None)
+        if shims is None:
+            return None
+        if not _DEFERRED_SHIMS:
+            sys.meta_path.remove(_ShimFinder)
+        spec = importlib.util.find_spec(name)
+        if spec is not None and spec.loader is not None:
+            spec.loader = _ShimLoader(spec.loader, shims)
+        return spec
+
+
+sys.meta_path.insert(0, _ShimFinder)
 `;
 
   if (opts.networking) {
     src += `\n_inject_shim("socket", "/usr/lib/python/socket.py")\n`;
     src += `_inject_shim("ssl", "/usr/lib/python/ssl.py")\n`;
+    // python3.wasm may have a native requests module, which would shadow
+    // requests.py; the shim has the fuller API (Session, http.client fallback).
+    src += `_inject_shim("requests", "/usr/lib/python/requests.py")\n`;
   }
 
   return src;
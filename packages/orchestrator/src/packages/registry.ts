@@ -348,12 +348,12 @@ class Session:
   {
     name: 'sklearn',
     version: '1.3.0',
-    summary: 'Machine learning (linfa-backed)',
+    summary: 'Machine learning (native trees, linear models, k-means)',
     dependencies: ['numpy'],
     native: true,
-    pythonFiles: {
-      'sklearn/__init__.py': '# placeholder - real impl in Task 11\n',
-    },
+    pythonFiles: {},
+    pythonDir: 'python/crates/sklearn/python',
+    pythonDirPrefix: 'python/crates/sklearn/python',
   },
   {
     name: 'sqlite3',
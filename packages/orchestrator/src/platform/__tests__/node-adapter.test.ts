@@ -1,6 +1,8 @@
 import { describe, it } from '@std/testing/bdd';
 import { expect } from '@std/expect';
-import { resolve } from 'node:path';
+import { mkdtemp, rm, writeFile } from 'node:fs/promises';
+import { tmpdir } from 'node:os';
+import { join, resolve } from 'node:path';
 
 import { NodeAdapter } from '../node-adapter.js';
 import { WasiHost } from '../../wasi/wasi-host.js';
@@ -110,4 +112,21 @@ describe('NodeAdapter', () => {
     expect(host.getStdout()).toBe('');
     expect(host.getExitCode()).toBe(0);
   });
+
+  it('maps tools without a module of their own to coreutils.wasm', async () => {
+    const dir = await mkdtemp(join(tmpdir(), 'codepod-scan-'));
+    try {
+      for (const file of ['coreutils.wasm', 'cat.wasm', 'true-cmd.wasm']) {
+        await writeFile(join(dir, file), '');
+      }
+      const tools = await new NodeAdapter().scanTools(dir);
+      expect(tools.get('cat')).toBe(resolve(dir, 'cat.wasm'));
+      expect(tools.get('true')).toBe(resolve(dir, 'true-cmd.wasm'));
+      expect(tools.get('grep')).toBe(resolve(dir, 'coreutils.wasm'));
+      expect(tools.get('gunzip')).toBe(resolve(dir, 'coreutils.wasm'));
+      expect(tools.get('coreutils')).toBe(resolve(dir, 'coreutils.wasm'));
+    } finally {
+      await rm(dir, { recursive: true, force: true });
+    }
+  });
 });
@@ -9,6 +9,26 @@ import type { PlatformAdapter } from './adapter.js';
 
 const EXCLUDED = new Set(['python3.wasm']);
 
+/**
+ * The tools in coreutils.wasm, the multi-call build of packages/coreutils,
+ * which runs the one its argv[0] names. Keep in sync with its `--list`.
+ */
+export const COREUTILS_TOOLS = [
+  'arch', 'awk', 'base32', 'base64', 'basename', 'bc', 'cat', 'chgrp',
+  'chown', 'cksum', 'cmp', 'column', 'comm', 'cp', 'csplit', 'cut', 'dc',
+  'dd', 'df', 'diff', 'dirname', 'du', 'echo', 'env', 'expand', 'expr',
+  'factor', 'false', 'file', 'find', 'fmt', 'fold', 'grep', 'groups',
+  'gunzip', 'gzip', 'head', 'hostid', 'hostname', 'id', 'join', 'jq', 'link',
+  'ln', 'logname', 'ls', 'md5sum', 'mkdir', 'mktemp', 'mv', 'nice', 'nl',
+  'nohup', 'nproc', 'numfmt', 'od', 'paste', 'patch', 'printenv', 'printf',
+  'readlink', 'realpath', 'rev', 'rg', 'rm', 'rmdir', 'sed', 'seq',
+  'sha1sum', 'sha224sum', 'sha256sum', 'sha384sum', 'sha512sum', 'shuf',
+  'sleep', 'sort', 'split', 'stat', 'strings', 'sudo', 'sum', 'tac', 'tail',
+  'tar', 'tee', 'timeout', 'touch', 'tr', 'tree', 'true', 'truncate',
+  'tsort', 'uname', 'unexpand', 'uniq', 'unlink', 'unzip', 'uptime', 'users',
+  'wc', 'who', 'whoami', 'xargs', 'xxd', 'yes', 'zip',
+];
+
 function wasmToToolName(filename: string): string {
   if (filename === 'true-cmd.wasm') return 'true';
   if (filename === 'false-cmd.wasm') return 'false';
@@ -50,6 +70,13 @@ export class NodeAdapter implements PlatformAdapter {
       const name = wasmToToolName(entry);
       tools.set(name, resolve(wasmDir, entry));
     }
+    // coreutils.wasm stands in for each of its tools without a module of its own
+    const coreutils = tools.get('coreutils');
+    if (coreutils) {
+      for (const name of COREUTILS_TOOLS) {
+        if (!tools.has(name)) tools.set(name, coreutils);
+      }
+    }
     // gunzip is an alias for gzip (same binary, argv[0] detection)
     if (tools.has('gzip') && !tools.has('gunzip')) {
       tools.set('gunzip', tools.get('gzip')!);
@@ -28,4 +28,14 @@ describe('ProcessKernel', () => {
     expect(target!.type).toBe('pipe_read');
     kernel.dispose();
   });
+
+  it('takeLastException hands over the recorded exception once', () => {
+    const kernel = new ProcessKernel();
+    expect(kernel.takeLastException()).toBeNull();
+    const exception = { type: 'ValueError', message: 'bad', traceback: 'ValueError: bad\n', frames: [] };
+    kernel.recordException(exception);
+    expect(kernel.takeLastException()).toEqual(exception);
+    expect(kernel.takeLastException()).toBeNull();
+    kernel.dispose();
+  });
 });
@@ -1,6 +1,7 @@
 import type { FdTarget } from '../wasi/fd-target.js';
 import { createAsyncPipe, type AsyncPipeReadEnd, type AsyncPipeWriteEnd } from '../vfs/pipe.js';
 import type { WasiHost } from '../wasi/wasi-host.js';
+import type { GuestException } from '../guest-abi.js';
 
 export interface SpawnRequest {
   prog: string;
@@ -12,6 +13,8 @@ export interface SpawnRequest {
   stdout_fd: number;
   stderr_fd: number;
   stdin_data?: string;
+  /** Per-command budget (`SpawnLimits` on the Rust side). */
+  limits?: { max_output_bytes?: number; max_wall_ms?: number; max_memory_bytes?: number };
 }
 
 export interface ProcessEntry {
@@ -29,6 +32,7 @@ export class ProcessKernel {
   private nextPid = 1;
   private fdTables = new Map<number, Map<number, FdTarget>>();
   private nextFds = new Map<number, number>();
+  private lastException: GuestException | null = null;
 
   constructor() {
     // Process 0 (shell) gets a default fd table
@@ -101,6 +105,7 @@ export class ProcessKernel {
     entry.promise = promise;
     entry.wasiHost = wasiHost;
     const onExit = () => {
+      if (entry.state === 'exited') return; // already killed
       entry.state = 'exited';
       entry.exitCode = wasiHost?.getExitCode() ?? 0;
       // Close the child's fds (decrements pipe refcounts, signals EOF).
@@ -117,7 +122,7 @@ export class ProcessKernel {
     });
     const onExit = () => {
       const entry = this.processTable.get(pid);
-      if (entry) {
+      if (entry && entry.state !== 'exited') {
         entry.state = 'exited';
         entry.exitCode = wasiHost.getExitCode() ?? 0;
         for (const waiter of entry.waiters) waiter(entry.exitCode);
@@ -129,6 +134,21 @@ export class ProcessKernel {
 
   allocPid(): number { return this.nextPid++; }
 
+  /** Keep the uncaught exception a process reported as it exited. */
+  recordException(exception: GuestException): void {
+    this.lastException = exception;
+  }
+
+  /**
+   * The exception most recently recorded since the last call, or null;
+   * either way the slot is cleared for the next run.
+   */
+  takeLastException(): GuestException | null {
+    const exception = this.lastException;
+    this.lastException = null;
+    return exception;
+  }
+
   /** Register a process as already exited (used for synchronous spawn). */
   registerExited(pid: number, exitCode: number): void {
     const existing = this.processTable.get(pid);
@@ -152,6 +172,24 @@ export class ProcessKernel {
     return new Promise<number>((resolve) => { entry.waiters.push(resolve); });
   }
 
+  /**
+   * Terminate a running process. Waiters see exit code 128 + signal right
+   * away; the WASM instance itself stops at its next syscall.
+   * Returns 0 on success, -1 if there is no such process.
+   */
+  kill(pid: number, signal: number): number {
+    const entry = this.processTable.get(pid);
+    if (!entry) return -1;
+    if (entry.state === 'exited') return 0;
+    entry.wasiHost?.cancelExecution();
+    entry.state = 'exited';
+    entry.exitCode = 128 + signal;
+    this.cleanupFds(pid);
+    for (const waiter of entry.waiters) waiter(entry.exitCode);
+    entry.waiters.length = 0;
+    return 0;
+  }
+
   waitpidNohang(pid: number): number {
     const entry = this.processTable.get(pid);
     if (!entry) return -1;
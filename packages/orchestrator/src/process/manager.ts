@@ -12,6 +12,7 @@ import { S_TOOL } from '../vfs/inode.js';
 import { WasiHost } from '../wasi/wasi-host.js';
 import type { NetworkBridgeLike } from '../network/bridge.js';
 import { createKernelImports } from '../host-imports/kernel-imports.js';
+import { checkGuestAbi, readGuestException } from '../guest-abi.js';
 
 import type { SpawnOptions, SpawnResult } from './process.js';
 import type { ExtensionHandler } from '../extension/types.js';
@@ -203,7 +204,8 @@ export class ProcessManager {
     const host = new WasiHost({
       vfs: this.vfs,
       args: [command, ...opts.args],
-      env: opts.env,
+      // WASI has no working directory; guests take theirs from PWD.
+      env: opts.cwd === undefined ? opts.env : { ...opts.env, PWD: opts.cwd },
       preopens: { '/': '/' },
       stdin: stdinData,
       stdoutLimit: opts.stdoutLimit,
@@ -254,6 +256,7 @@ export class ProcessManager {
     }
 
     const instance = await this.adapter.instantiate(module, imports);
+    checkGuestAbi(instance, command);
 
     // Wire up the real memory reference for the codepod import proxy
     if (setMemoryRef) {
@@ -299,6 +302,7 @@ export class ProcessManager {
 
     const stdoutTruncated = host.isStdoutTruncated();
     const stderrTruncated = host.isStderrTruncated();
+    const exception = exitCode !== 0 ? readGuestException(instance) : null;
 
     return {
       exitCode,
@@ -306,6 +310,7 @@ export class ProcessManager {
       stderr: host.getStderr(),
       executionTimeMs,
       truncated: (stdoutTruncated || stderrTruncated) ? { stdout: stdoutTruncated, stderr: stderrTruncated } : undefined,
+      ...(exception ? { exception } : {}),
     };
   }
 
@@ -380,7 +385,8 @@ export class ProcessManager {
     const host = new WasiHost({
       vfs: this.vfs,
       args: [command, ...args],
-      env,
+      // WASI has no working directory; guests take theirs from PWD.
+      env: { ...env, PWD: cwd },
       preopens: { '/': '/' },
       stdin,
       stdoutLimit: opts?.stdoutLimit,
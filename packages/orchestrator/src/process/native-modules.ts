@@ -9,11 +9,14 @@
  * Expected WASM exports:
  *   __alloc(size: i32) -> i32          — allocate `size` bytes, return ptr
  *   __dealloc(ptr: i32, size: i32)     — optional, free previously allocated memory
+ *   __abi_version() -> i32             — optional, checked against GUEST_ABI_VERSION on load
  *   invoke(method_ptr, method_len, args_ptr, args_len, out_ptr, out_cap) -> i32
  *       positive return = bytes written to out_ptr
  *       negative return = negated required capacity (caller must retry with larger buffer)
  */
 
+import { checkGuestAbi } from '../guest-abi.js';
+
 const encoder = new TextEncoder();
 const decoder = new TextDecoder();
 
@@ -42,6 +45,7 @@ export class NativeModuleRegistry {
 
     const importObject = buildImportObject(imports, memory);
     const instance = await WebAssembly.instantiate(compiled, importObject);
+    checkGuestAbi(instance, `NativeModuleRegistry: module "${name}"`);
 
     // Resolve the memory: either provided by host or exported by module.
     const resolvedMemory = memory ?? (instance.exports.memory as WebAssembly.Memory);
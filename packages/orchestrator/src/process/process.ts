@@ -6,6 +6,8 @@
  * ProcessManager (manager.ts) orchestrates creation and teardown.
  */
 
+import type { GuestException } from '../guest-abi.js';
+
 export interface SpawnOptions {
   args: string[];
   env: Record<string, string>;
@@ -24,4 +26,6 @@ export interface SpawnResult {
   stderr: string;
   executionTimeMs: number;
   truncated?: { stdout: boolean; stderr: boolean };
+  /** The uncaught exception the process died of, if it reported one. */
+  exception?: GuestException;
 }
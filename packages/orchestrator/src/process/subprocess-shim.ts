@@ -1,36 +1,72 @@
 /**
  * Python source for /usr/lib/python/subprocess.py — subprocess shim for the
- * WASI sandbox. Routes execution through _codepod.spawn() which calls back
- * into the TypeScript host via host_run_command.
+ * WASI sandbox. Routes execution through _codepod.spawn(), which asks the
+ * host to run the command (with the caller's cwd and environment) in a
+ * fresh shell via the shell.run RPC.
  *
  * Also patches os.popen at module level so code using os.popen works without
  * an explicit `import subprocess`.
  */
 export const SUBPROCESS_PY_SOURCE = `\
-"""subprocess shim for codepod WASI — routes via _codepod.spawn()."""
+"""subprocess shim for codepod WASI — routes via _codepod.spawn().
+
+Commands run to completion in a fresh sandbox shell; their output is
+collected rather than streamed, so a Popen with a stdin pipe runs once
+that pipe is closed or communicate()/wait() is called.
+"""
 import _codepod
 import io
+import os
 import shlex
+import sys
 
 PIPE = -1
 DEVNULL = -2
 STDOUT = -3
 
+__all__ = [
+    'run', 'call', 'check_call', 'check_output', 'getoutput',
+    'getstatusoutput', 'Popen', 'CompletedProcess', 'SubprocessError',
+    'CalledProcessError', 'TimeoutExpired', 'PIPE', 'DEVNULL', 'STDOUT',
+]
+
+
+class SubprocessError(Exception):
+    pass
 
-class CalledProcessError(Exception):
+
+class CalledProcessError(SubprocessError):
     def __init__(self, returncode, cmd, output=None, stderr=None):
         self.returncode = returncode
         self.cmd = cmd
         self.output = output
-        self.stdout = output
         self.stderr = stderr
 
+    @property
+    def stdout(self):
+        return self.output
+
+    @stdout.setter
+    def stdout(self, value):
+        self.output = value
+
     def __str__(self):
-        return f"Command '{self.cmd}' returned non-zero exit status {self.returncode}"
+        return f"Command '{self.cmd}' returned non-zero exit status {self.returncode}."
 
 
-class TimeoutExpired(Exception):
-    pass
+class TimeoutExpired(SubprocessError):
+    def __init__(self, cmd, timeout, output=None, stderr=None):
+        self.cmd = cmd
+        self.timeout = timeout
+        self.output = output
+        self.stderr = stderr
+
+    @property
+    def stdout(self):
+        return self.output
+
+    def __str__(self):
+        return f"Command '{self.cmd}' timed out after {self.timeout} seconds"
 
 
 class CompletedProcess:
@@ -45,137 +81,277 @@ class CompletedProcess:
             raise CalledProcessError(self.returncode, self.args, self.stdout, self.stderr)
 
     def __repr__(self):
-        return (
-            f"CompletedProcess(args={self.args!r}, returncode={self.returncode!r}, "
-            f"stdout={self.stdout!r}, stderr={self.stderr!r})"
-        )
-
-
-def _invoke(cmd, stdin=''):
+        parts = [f"args={self.args!r}", f"returncode={self.returncode!r}"]
+        if self.stdout is not None:
+            parts.append(f"stdout={self.stdout!r}")
+        if self.stderr is not None:
+            parts.append(f"stderr={self.stderr!r}")
+        return f"CompletedProcess({', '.join(parts)})"
+
+
+def _command(args):
+    """The shell command line for args: a string is run as-is, a sequence
+    is quoted word by word."""
+    if isinstance(args, (str, bytes, os.PathLike)):
+        return os.fsdecode(args)
+    return shlex.join(os.fsdecode(a) for a in args)
+
+
+def _spawn_options(cwd, env):
+    if cwd is not None:
+        cwd = os.path.abspath(os.fsdecode(cwd))
+        if not os.path.isdir(cwd):
+            if os.path.exists(cwd):
+                raise NotADirectoryError(20, 'Not a directory', cwd)
+            raise FileNotFoundError(2, 'No such file or directory', cwd)
+    else:
+        try:
+            cwd = os.getcwd()
+        except OSError:
+            cwd = None
+    if env is None:
+        env = os.environ
+    exported = {}
+    for key, value in env.items():
+        key, value = os.fsdecode(key), os.fsdecode(value)
+        if '=' in key:
+            raise ValueError('illegal environment variable name')
+        exported[key] = value
+    return cwd, exported
+
+
+def _invoke(cmd, stdin='', cwd=None, env=None):
     """Run cmd via the host shell."""
-    r = _codepod.spawn(cmd, stdin)
+    cwd, env = _spawn_options(cwd, env)
+    r = _codepod.spawn(cmd, stdin, cwd, env)
     return r.get('exit_code', 0), r.get('stdout', ''), r.get('stderr', '')
 
 
-def run(args, *, stdin=None, stdout=None, stderr=None, capture_output=False,
-        text=False, shell=False, input=None, check=False, env=None, cwd=None,
-        timeout=None, encoding=None, errors=None, **_kwargs):
-    is_text = text or encoding is not None
+def _as_text(data, encoding, errors):
+    if isinstance(data, (bytes, bytearray)):
+        return bytes(data).decode(encoding or 'utf-8', errors or 'strict')
+    return '' if data is None else str(data)
+
+
+def _emit(data, target, fallback):
+    """Send output the caller did not capture where it asked for it."""
+    if target == DEVNULL or not data:
+        return
+    if target is None:
+        target = fallback
+    if isinstance(target, int):
+        os.write(target, data.encode())
+        return
+    try:
+        target.write(data)
+    except TypeError:
+        target.write(data.encode())
+    try:
+        target.flush()
+    except Exception:
+        pass
 
-    if isinstance(args, (list, tuple)):
-        cmd_str = shlex.join(str(a) for a in args)
-    else:
-        cmd_str = str(args)
 
-    stdin_str = ''
+def run(*popenargs, input=None, capture_output=False, timeout=None, check=False, **kwargs):
     if input is not None:
-        stdin_str = input.decode('utf-8', errors='replace') if isinstance(input, bytes) else str(input)
-    elif stdin not in (None, PIPE, DEVNULL) and hasattr(stdin, 'read'):
-        stdin_str = stdin.read()
-    elif isinstance(stdin, (str, bytes)):
-        stdin_str = stdin.decode('utf-8', errors='replace') if isinstance(stdin, bytes) else stdin
+        if kwargs.get('stdin') is not None:
+            raise ValueError('stdin and input arguments may not both be used.')
+        kwargs['stdin'] = PIPE
+    if capture_output:
+        if kwargs.get('stdout') is not None or kwargs.get('stderr') is not None:
+            raise ValueError('stdout and stderr arguments may not be used with capture_output.')
+        kwargs['stdout'] = PIPE
+        kwargs['stderr'] = PIPE
+
+    with Popen(*popenargs, **kwargs) as process:
+        stdout, stderr = process.communicate(input, timeout=timeout)
+    result = CompletedProcess(process.args, process.returncode, stdout, stderr)
+    if check:
+        result.check_returncode()
+    return result
 
-    exit_code, out_str, err_str = _invoke(cmd_str, stdin_str)
 
-    cap_out = capture_output or stdout == PIPE
-    cap_err = capture_output or stderr == PIPE
+def call(*popenargs, timeout=None, **kwargs):
+    with Popen(*popenargs, **kwargs) as p:
+        return p.wait(timeout=timeout)
 
-    if stderr == STDOUT:
-        out_str = out_str + err_str
-        err_str = ''
-        cap_err = False
 
-    if is_text:
-        out = out_str if cap_out else None
-        err = err_str if cap_err else None
-    else:
-        out = out_str.encode() if cap_out else None
-        err = err_str.encode() if cap_err else None
+def check_call(*popenargs, **kwargs):
+    retcode = call(*popenargs, **kwargs)
+    if retcode:
+        cmd = kwargs.get('args')
+        if cmd is None:
+            cmd = popenargs[0]
+        raise CalledProcessError(retcode, cmd)
+    return 0
 
-    if not cap_out and stdout not in (None, PIPE, DEVNULL):
-        import sys
-        print(out_str, end='', file=sys.stdout)
-    if not cap_err and stderr not in (None, PIPE, DEVNULL, STDOUT):
-        import sys
-        print(err_str, end='', file=sys.stderr)
 
-    result = CompletedProcess(args, exit_code, stdout=out, stderr=err)
-    if check:
-        result.check_returncode()
-    return result
+def check_output(*popenargs, timeout=None, **kwargs):
+    if 'stdout' in kwargs:
+        raise ValueError('stdout argument not allowed, it will be overridden.')
+    return run(*popenargs, stdout=PIPE, timeout=timeout, check=True, **kwargs).stdout
 
 
-def check_output(args, *, stderr=None, **kwargs):
-    kwargs.pop('stdout', None)
-    return run(args, stdout=PIPE, stderr=stderr, check=True, **kwargs).stdout
+def getstatusoutput(cmd, *, encoding=None, errors=None):
+    r = run(cmd, shell=True, stdout=PIPE, stderr=STDOUT, text=True,
+            encoding=encoding, errors=errors)
+    data = r.stdout
+    if data[-1:] == '\\n':
+        data = data[:-1]
+    return r.returncode, data
 
 
-def check_call(args, **kwargs):
-    run(args, check=True, **kwargs)
-    return 0
+def _args_from_interpreter_flags():
+    """Flags to give a child python3 (multiprocessing.util imports this);
+    the sandbox's interpreter is started without any."""
+    return []
 
 
-def call(args, **kwargs):
-    return run(args, **kwargs).returncode
+def getoutput(cmd, *, encoding=None, errors=None):
+    return getstatusoutput(cmd, encoding=encoding, errors=errors)[1]
 
 
-def getoutput(cmd):
-    return run(cmd, shell=True, stdout=PIPE, stderr=STDOUT, text=True).stdout
+class _StdinPipe:
+    """The writing end of a Popen's stdin; closing it runs the command."""
 
+    def __init__(self, process, text):
+        self._process = process
+        self._buffer = io.StringIO() if text else io.BytesIO()
+        self.closed = False
 
-def getstatusoutput(cmd):
-    r = run(cmd, shell=True, stdout=PIPE, stderr=STDOUT, text=True)
-    return r.returncode, r.stdout.rstrip('\\n')
+    def write(self, data):
+        if self.closed:
+            raise ValueError('write to closed file')
+        return self._buffer.write(data)
 
+    def writelines(self, lines):
+        for line in lines:
+            self.write(line)
 
-class Popen:
-    """Minimal non-streaming Popen — runs the command eagerly on __init__."""
+    def flush(self):
+        pass
 
-    def __init__(self, args, bufsize=-1, executable=None, stdin=None,
-                 stdout=None, stderr=None, close_fds=True, shell=False,
-                 cwd=None, env=None, universal_newlines=False, text=False,
-                 encoding=None, errors=None, **_kwargs):
-        self._text = text or universal_newlines or encoding is not None
-        cap_out = stdout == PIPE
-        cap_err = stderr == PIPE
+    def writable(self):
+        return True
 
-        if isinstance(args, (list, tuple)):
-            cmd_str = shlex.join(str(a) for a in args)
-        else:
-            cmd_str = str(args)
+    def close(self):
+        if not self.closed:
+            self.closed = True
+            self._process._run(self._buffer.getvalue())
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *_):
+        self.close()
 
-        stdin_str = ''
-        if isinstance(stdin, str):
-            stdin_str = stdin
-        elif isinstance(stdin, bytes):
-            stdin_str = stdin.decode('utf-8', errors='replace')
 
-        self.returncode, self._out, self._err = _invoke(cmd_str, stdin_str)
+class Popen:
+    """Popen over a run-to-completion spawn: the command runs when __init__
+    returns, or once its stdin pipe is closed."""
+
+    def __init__(self, args, bufsize=-1, executable=None, stdin=None,
+                 stdout=None, stderr=None, preexec_fn=None, close_fds=True,
+                 shell=False, cwd=None, env=None, universal_newlines=None,
+                 startupinfo=None, creationflags=0, restore_signals=True,
+                 start_new_session=False, pass_fds=(), *, text=None,
+                 encoding=None, errors=None, **_kwargs):
+        self.args = args
         self.pid = -1
-        self.stdin = None
-        self._cap_out = cap_out
-        self._cap_err = cap_err
+        self.returncode = None
+        self.encoding = encoding
+        self.errors = errors
+        self.text_mode = bool(text or universal_newlines or encoding or errors)
+        self._cmd = _command(args)
+        if executable is not None and not shell:
+            self._cmd = shlex.join([os.fsdecode(executable)] + shlex.split(self._cmd)[1:])
+        # Checked now so a bad cwd raises from the constructor, as in CPython.
+        self._cwd, self._env = _spawn_options(cwd, env)
+        self._stdout_target = stdout
+        self._stderr_target = stderr
+        self._out = self._err = None
+        self._communicated = False
 
-        if cap_out:
-            self.stdout = io.StringIO(self._out) if self._text else io.BytesIO(self._out.encode())
-        else:
-            self.stdout = None
-        if cap_err:
-            self.stderr = io.StringIO(self._err) if self._text else io.BytesIO(self._err.encode())
+        self.stdin = None
+        self.stdout = None
+        self.stderr = None
+        if stdin == PIPE:
+            self.stdin = _StdinPipe(self, self.text_mode)
+            return
+        data = ''
+        if stdin not in (None, DEVNULL):
+            if isinstance(stdin, int):
+                data = _read_fd(stdin)
+            elif hasattr(stdin, 'read'):
+                data = stdin.read()
+            else:
+                data = stdin
+        self._run(data)
+
+    def _run(self, data):
+        if self.returncode is not None:
+            return
+        stdin_str = _as_text(data, self.encoding, self.errors)
+        code, out, err = _invoke(self._cmd, stdin_str, self._cwd, self._env)
+        self.returncode = code
+        if self._stderr_target == STDOUT:
+            out, err = out + err, ''
+        if self._stdout_target == PIPE:
+            self._out = out
+            self.stdout = self._stream(out)
         else:
-            self.stderr = None
+            _emit(out, self._stdout_target, sys.stdout)
+        if self._stderr_target == PIPE:
+            self._err = err
+            self.stderr = self._stream(err)
+        elif self._stderr_target != STDOUT:
+            _emit(err, self._stderr_target, sys.stderr)
+
+    def _convert(self, data):
+        if data is None:
+            return None
+        if self.text_mode:
+            return data
+        return data.encode(self.encoding or 'utf-8', self.errors or 'strict')
+
+    def _stream(self, data):
+        if self.text_mode:
+            return io.StringIO(data)
+        return io.BytesIO(self._convert(data))
 
     def communicate(self, input=None, timeout=None):
-        out = (self._out if self._text else self._out.encode()) if self._cap_out else None
-        err = (self._err if self._text else self._err.encode()) if self._cap_err else None
+        if self._communicated and input:
+            raise ValueError('Cannot send input after starting communication')
+        if self.stdin is not None and not self.stdin.closed:
+            if input:
+                self.stdin.write(input)
+            self.stdin.close()
+        elif input:
+            raise ValueError('input given but stdin is not a pipe')
+        self._communicated = True
+        out = self._read_rest(self.stdout)
+        err = self._read_rest(self.stderr)
         return out, err
 
+    @staticmethod
+    def _read_rest(stream):
+        if stream is None:
+            return None
+        data = stream.read()
+        stream.close()
+        return data
+
     def wait(self, timeout=None):
+        if self.returncode is None:
+            self.stdin.close()
         return self.returncode
 
     def poll(self):
         return self.returncode
 
+    def send_signal(self, sig):
+        pass
+
     def kill(self):
         pass
 
@@ -186,18 +362,31 @@ class Popen:
         return self
 
     def __exit__(self, *_):
-        pass
+        for stream in (self.stdout, self.stderr):
+            if stream is not None:
+                stream.close()
+        if self.stdin is not None:
+            self.stdin.close()
+
+    def __repr__(self):
+        return f"<Popen: returncode: {self.returncode} args: {self.args!r}>"
+
+
+def _read_fd(fd):
+    chunks = []
+    while True:
+        chunk = os.read(fd, 65536)
+        if not chunk:
+            break
+        chunks.append(chunk)
+    return b''.join(chunks)
 
 
 # Patch os.popen so code using it without importing subprocess works.
-try:
-    import os as _os
+def _popen_shim(cmd, mode='r', buffering=-1):
+    r = run(cmd, shell=True, capture_output=True, text=True)
+    return io.StringIO(r.stdout)
 
-    def _popen_shim(cmd, mode='r', buffering=-1):
-        r = run(cmd, shell=True, capture_output=True, text=True)
-        return io.StringIO(r.stdout)
 
-    _os.popen = _popen_shim
-except Exception:
-    pass
+os.popen = _popen_shim
 `;
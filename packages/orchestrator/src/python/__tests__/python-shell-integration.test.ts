@@ -80,4 +80,35 @@ describe('Python via ShellInstance', () => {
       'written by python',
     );
   });
+
+  it('python resolves relative paths against the shell cwd', async () => {
+    vfs.mkdirp('/home/user/proj');
+    vfs.writeFile('/home/user/proj/in.txt', new TextEncoder().encode('from shell'));
+    const script = [
+      'import os, pathlib',
+      'print(os.getcwd())',
+      'print(open("in.txt").read())',
+      'os.makedirs("out/deep")',
+      'pathlib.Path("out/deep/note.txt").write_text("from python")',
+      'print(sorted(p.name for p in pathlib.Path(".").iterdir()))',
+    ].join('\n');
+    const result = await runner.run(
+      `cd /home/user/proj && python3 -c '${script}' && cat /home/user/proj/out/deep/note.txt`,
+    );
+    expect(result.exitCode).toBe(0);
+    expect(result.stdout).toBe(
+      "/home/user/proj\nfrom shell\n['in.txt', 'out']\nfrom python",
+    );
+  });
+
+  it('python removes and touches files the shell sees', async () => {
+    vfs.writeFile('/home/user/gone.txt', new TextEncoder().encode('x'));
+    vfs.writeFile('/home/user/kept.txt', new TextEncoder().encode('12345'));
+    const result = await runner.run(
+      'python3 -c "import os; os.remove(\'/home/user/gone.txt\'); os.utime(\'/home/user/kept.txt\', (0, 86400)); print(os.stat(\'/home/user/kept.txt\').st_size)"',
+    );
+    expect(result.stdout.trim()).toBe('5');
+    expect(() => vfs.stat('/home/user/gone.txt')).toThrow(/ENOENT/);
+    expect(vfs.stat('/home/user/kept.txt').mtime.getTime()).toBe(86_400_000);
+  });
 });
@@ -0,0 +1,156 @@
+/**
+ * Python source for /usr/lib/python/_codepod_asyncio.py — the asyncio event
+ * loop for the WASI sandbox. Its selector waits in _codepod.poll() (WASI
+ * poll_oneoff), so asyncio.sleep() timers and fd readiness are both the
+ * host's, and run_in_executor() runs blocking calls such as fetches inline.
+ *
+ * sitecustomize.py hooks the first `import asyncio` to load it, so scripts
+ * that never use asyncio do not pay for importing it.
+ */
+export const ASYNCIO_PY_SOURCE = `\
+"""asyncio event loop for codepod WASI, driven by the host.
+
+Timers and I/O readiness both come from the host: the selector waits in
+_codepod.poll(), which is WASI poll_oneoff. The guest has no threads or
+signals, so the loop has no self-pipe, and run_in_executor() (and with it
+asyncio.to_thread()) calls its function inline: awaiting a blocking call
+such as a fetch holds the loop while the host does the work.
+
+sitecustomize loads this around the first import of asyncio: prepare()
+before asyncio runs, install() after.
+"""
+import selectors
+import signal
+import sys
+import time
+import types
+
+try:
+    from _codepod import poll as _poll
+except ImportError:
+    _poll = None
+
+
+def _socket_stub():
+    """Just enough of socket for asyncio to import with networking off."""
+    mod = types.ModuleType('socket', 'Networking is not enabled in this sandbox.')
+
+    def disabled(*args, **kwargs):
+        raise OSError('networking is not enabled in this sandbox')
+
+    class socket:
+        def __init__(self, *args, **kwargs):
+            disabled()
+
+    mod.__dict__.update(
+        AF_UNSPEC=0, AF_UNIX=1, AF_INET=2, AF_INET6=10,
+        SOCK_STREAM=1, SOCK_DGRAM=2, SOL_SOCKET=1, SO_REUSEADDR=2,
+        IPPROTO_TCP=6, IPPROTO_UDP=17, TCP_NODELAY=1, AI_PASSIVE=1,
+        has_ipv6=False, error=OSError, timeout=TimeoutError,
+        gaierror=OSError, herror=OSError,
+        socket=socket, SocketType=socket, socketpair=disabled,
+        getaddrinfo=disabled, create_connection=disabled,
+    )
+    return mod
+
+
+def _get_clock_info(name):
+    return types.SimpleNamespace(
+        implementation='clock_time_get()', monotonic=name != 'time',
+        adjustable=name == 'time', resolution=1e-09)
+
+
+def prepare():
+    """Fill the gaps in the WASI stdlib that asyncio trips over."""
+    try:
+        import socket  # noqa: F401
+    except ImportError:
+        sys.modules['socket'] = _socket_stub()
+    if not hasattr(time, 'get_clock_info'):
+        time.get_clock_info = _get_clock_info
+    # asyncio.run() checks whether SIGINT has its default handler; WASI has
+    # no signals, so it finds none and leaves signal handling alone.
+    if not hasattr(signal, 'SIGINT'):
+        signal.SIGINT = 2
+
+
+class HostSelector(selectors._BaseSelectorImpl):
+    """A selector that waits in the host."""
+
+    def select(self, timeout=None):
+        readers, writers = [], []
+        for key in self._fd_to_key.values():
+            if key.events & selectors.EVENT_READ:
+                readers.append(key.fd)
+            if key.events & selectors.EVENT_WRITE:
+                writers.append(key.fd)
+        if timeout is not None:
+            timeout = max(timeout, 0)
+        if not readers and not writers:
+            if timeout is None:
+                # Nothing could ever wake the loop: there are no other
+                # threads or signals to call it from.
+                raise RuntimeError('event loop is waiting with no timers or I/O pending')
+            if timeout:
+                time.sleep(timeout)
+            return []
+        if _poll is None:
+            # No host wait available: report everything ready.
+            ready_readers, ready_writers = readers, writers
+        else:
+            ready_readers, ready_writers = _poll(readers, writers, timeout)
+        ready = dict.fromkeys(ready_readers, selectors.EVENT_READ)
+        for fd in ready_writers:
+            ready[fd] = ready.get(fd, 0) | selectors.EVENT_WRITE
+        return [(self._fd_to_key[fd], events & self._fd_to_key[fd].events)
+                for fd, events in ready.items() if fd in self._fd_to_key]
+
+
+def install(asyncio):
+    """Make the host-driven loop asyncio's default."""
+    from asyncio import events, selector_events
+
+    class HostEventLoop(selector_events.BaseSelectorEventLoop):
+        """Selector event loop without a self-pipe or executor threads."""
+
+        def __init__(self):
+            super().__init__(HostSelector())
+
+        def _make_self_pipe(self):
+            pass
+
+        def _close_self_pipe(self):
+            pass
+
+        def _write_to_self(self):
+            pass
+
+        def run_in_executor(self, executor, func, *args):
+            if executor is not None:
+                return super().run_in_executor(executor, func, *args)
+            self._check_closed()
+            future = self.create_future()
+            try:
+                future.set_result(func(*args))
+            except (SystemExit, KeyboardInterrupt):
+                raise
+            except BaseException as exc:
+                future.set_exception(exc)
+            return future
+
+    # 3.14 deprecates the public policy names.
+    base = getattr(events, '_BaseDefaultEventLoopPolicy', None)
+    set_policy = getattr(events, '_set_event_loop_policy', None)
+    if base is None:
+        base, set_policy = events.BaseDefaultEventLoopPolicy, events.set_event_loop_policy
+
+    class HostEventLoopPolicy(base):
+        _loop_factory = HostEventLoop
+
+    set_policy(HostEventLoopPolicy())
+
+    asyncio.EventLoop = HostEventLoop
+
+
+prepare()
+`;
@@ -0,0 +1,362 @@
+/**
+ * Python source for /usr/lib/python/_codepod_multiprocessing.py — a
+ * multiprocessing.Pool for the WASI sandbox, which cannot fork. Tasks are
+ * pickled and handed to the host with _codepod.spawn_all(), which runs
+ * them in fresh python3 instances, one per pool worker, all at once.
+ *
+ * sitecustomize.py hooks the first `import multiprocessing` to install it.
+ * The same file is the worker: run as a script, it reads its tasks from
+ * stdin and writes their pickled outcomes to stdout.
+ */
+export const MULTIPROCESSING_PY_SOURCE = `\
+"""multiprocessing.Pool for codepod WASI, run by the host.
+
+There is no fork, so the pool works like the "spawn" start method: tasks
+are pickled and sent to the host, which starts a fresh python3 instance
+per worker, and each worker first re-imports the main script as
+__mp_main__ (so code under \`if __name__ == "__main__":\` does not run
+again) to find the functions it is asked to call. The host runs the
+workers concurrently, but the instances share the host's thread: they
+overlap while waiting on the host, and take turns at pure computation.
+
+There are no worker threads either, so asynchronous calls are queued
+and run together, in batches of \`processes\` workers, when a result is
+first waited for. Callbacks run then, in the calling interpreter.
+
+Scripts without a file (python3 -c, the REPL) cannot be re-imported, and
+older hosts cannot spawn workers; there the pool runs tasks in-process.
+"""
+import base64
+import json
+import os
+import pickle
+import sys
+import types
+
+import _codepod
+
+__all__ = ['Pool', 'AsyncResult', 'MapResult']
+
+# WASI reports a single CPU, which would make the default pool serial.
+_DEFAULT_PROCESSES = 4
+
+_WORKER_COMMAND = 'python3 /usr/lib/python/_codepod_multiprocessing.py'
+
+# Ends a worker's stdout; what follows is its pickled outcomes. Anything
+# before it is the tasks' own output.
+_RESULT_MARKER = '__codepod_multiprocessing_result__:'
+
+# True while a worker re-imports the main script; see Pool.__init__.
+_bootstrapping = False
+
+
+class AsyncResult:
+    """The pending result of one task."""
+
+    def __init__(self, pool, callback=None, error_callback=None):
+        self._pool = pool
+        self._callback = callback
+        self._error_callback = error_callback
+        self._done = False
+        self._ok = None
+        self._value = None
+        self._notify = None
+
+    def ready(self):
+        return self._done
+
+    def successful(self):
+        if not self._done:
+            raise ValueError(f'{self!r} not ready')
+        return self._ok
+
+    def wait(self, timeout=None):
+        # Runs every queued task, not just this one, so they share workers.
+        if not self._done:
+            self._pool._flush()
+
+    def get(self, timeout=None):
+        self.wait(timeout)
+        if not self._done:
+            raise TimeoutError('the pool was terminated before the task ran')
+        if self._ok:
+            return self._value
+        raise self._value
+
+    def _set(self, ok, value):
+        self._done, self._ok, self._value = True, ok, value
+        if ok and self._callback is not None:
+            self._callback(value)
+        if not ok and self._error_callback is not None:
+            self._error_callback(value)
+        if self._notify is not None:
+            self._notify()
+
+
+class MapResult(AsyncResult):
+    """The pending results of a map(): a list, or the first task's error."""
+
+    def __init__(self, pool, parts, callback=None, error_callback=None):
+        super().__init__(pool, callback, error_callback)
+        self._parts = parts
+        for part in parts:
+            part._notify = self._part_done
+        if not parts:
+            self._set(True, [])
+
+    def _part_done(self):
+        if self._done or not all(part._done for part in self._parts):
+            return
+        for part in self._parts:
+            if not part._ok:
+                self._set(False, part._value)
+                return
+        self._set(True, [part._value for part in self._parts])
+
+
+class Pool:
+    """A multiprocessing.Pool whose workers are python3 instances run by
+    the host."""
+
+    def __init__(self, processes=None, initializer=None, initargs=(),
+                 maxtasksperchild=None, context=None):
+        if _bootstrapping:
+            raise RuntimeError(
+                'An attempt has been made to start a new process before the '
+                'current process has finished its bootstrapping phase. '
+                'Start the pool under if __name__ == "__main__":')
+        if processes is None:
+            processes = _DEFAULT_PROCESSES
+        if processes < 1:
+            raise ValueError('Number of processes must be at least 1')
+        if initializer is not None and not callable(initializer):
+            raise TypeError('initializer must be a callable')
+        self._processes = processes
+        self._initializer = initializer
+        self._initargs = tuple(initargs)
+        self._initialized = False
+        self._pending = []
+        self._state = 'RUN'
+
+    def __repr__(self):
+        return f'<{type(self).__name__} state={self._state} pool_size={self._processes}>'
+
+    def __enter__(self):
+        self._check_running()
+        return self
+
+    def __exit__(self, *exc_info):
+        self.terminate()
+
+    def _check_running(self):
+        if self._state != 'RUN':
+            raise ValueError('Pool not running')
+
+    def apply(self, func, args=(), kwds={}):
+        return self.apply_async(func, args, kwds).get()
+
+    def apply_async(self, func, args=(), kwds={}, callback=None, error_callback=None):
+        self._check_running()
+        result = AsyncResult(self, callback, error_callback)
+        self._pending.append((func, tuple(args), dict(kwds), result))
+        return result
+
+    def map(self, func, iterable, chunksize=None):
+        return self.map_async(func, iterable, chunksize).get()
+
+    def map_async(self, func, iterable, chunksize=None, callback=None, error_callback=None):
+        return self._map_async(func, [(item,) for item in iterable], callback, error_callback)
+
+    def starmap(self, func, iterable, chunksize=None):
+        return self.starmap_async(func, iterable, chunksize).get()
+
+    def starmap_async(self, func, iterable, chunksize=None, callback=None, error_callback=None):
+        return self._map_async(func, [tuple(args) for args in iterable], callback, error_callback)
+
+    def imap(self, func, iterable, chunksize=1):
+        return iter(self.map(func, iterable))
+
+    def imap_unordered(self, func, iterable, chunksize=1):
+        return iter(self.map(func, iterable))
+
+    def _map_async(self, func, arglists, callback, error_callback):
+        self._check_running()
+        parts = [self.apply_async(func, args) for args in arglists]
+        return MapResult(self, parts, callback, error_callback)
+
+    def close(self):
+        if self._state == 'RUN':
+            self._state = 'CLOSE'
+
+    def terminate(self):
+        # Queued tasks have not started, so there is nothing to kill.
+        self._state = 'TERMINATE'
+        self._pending = []
+
+    def join(self):
+        if self._state == 'RUN':
+            raise ValueError('Pool is still running')
+        self._flush()
+
+    def _flush(self):
+        pending, self._pending = self._pending, []
+        if not pending:
+            return
+        tasks = [(func, args, kwds) for func, args, kwds, _ in pending]
+        main = getattr(sys.modules.get('__main__'), '__file__', None)
+        if main is None or not hasattr(_codepod, 'spawn_all'):
+            outcomes = self._run_inline(tasks)
+        else:
+            outcomes = self._run_workers(main, tasks)
+        for (_, _, _, result), (ok, value) in zip(pending, outcomes):
+            result._set(ok, value)
+
+    def _run_inline(self, tasks):
+        if not self._initialized and self._initializer is not None:
+            self._initializer(*self._initargs)
+        self._initialized = True
+        return [_call(func, args, kwds) for func, args, kwds in tasks]
+
+    def _run_workers(self, main, tasks):
+        # Tasks that cannot be pickled fail here, without a worker.
+        outcomes = [None] * len(tasks)
+        runnable = []
+        for index, task in enumerate(tasks):
+            try:
+                runnable.append((index, pickle.dumps(task)))
+            except Exception as exc:
+                outcomes[index] = (False, exc)
+        if not runnable:
+            return outcomes
+
+        # Workers name the script's functions and classes __mp_main__.
+        sys.modules.setdefault('__mp_main__', sys.modules['__main__'])
+        count = min(self._processes, len(runnable))
+        size, extra = divmod(len(runnable), count)
+        chunks, start = [], 0
+        for i in range(count):
+            end = start + size + (1 if i < extra else 0)
+            chunks.append(runnable[start:end])
+            start = end
+
+        header = json.dumps({'main': os.path.abspath(main), 'path': sys.path})
+        try:
+            cwd = os.getcwd()
+        except OSError:
+            cwd = None
+        commands = []
+        for chunk in chunks:
+            payload = pickle.dumps((self._initializer, self._initargs,
+                                    [task for _, task in chunk]))
+            commands.append({
+                'command': _WORKER_COMMAND,
+                'stdin': header + '\\n' + base64.b64encode(payload).decode('ascii'),
+                'cwd': cwd,
+                'env': dict(os.environ),
+            })
+
+        for chunk, result in zip(chunks, _codepod.spawn_all(commands)):
+            for (index, _), outcome in zip(chunk, _worker_outcomes(len(chunk), result)):
+                outcomes[index] = outcome
+        return outcomes
+
+
+def _call(func, args, kwds):
+    try:
+        return True, func(*args, **kwds)
+    except Exception as exc:
+        return False, exc
+
+
+def _worker_outcomes(count, result):
+    """Echo a worker's output and unpickle the outcomes of its tasks."""
+    stdout = result.get('stdout', '')
+    stderr = result.get('stderr', '')
+    output, marker, data = stdout.rpartition('\\n' + _RESULT_MARKER)
+    if not marker:
+        output, data = stdout, ''
+    sys.stdout.write(output)
+    sys.stderr.write(stderr)
+    if not data:
+        error = RuntimeError(
+            f"pool worker exited with status {result.get('exit_code')} "
+            f'before returning {count} result(s)')
+        return [(False, error)] * count
+
+    outcomes = []
+    for item in pickle.loads(base64.b64decode(data)):
+        ok, value, remote_tb = pickle.loads(item)
+        if not ok and remote_tb:
+            from multiprocessing.pool import RemoteTraceback
+            value.__cause__ = RemoteTraceback(remote_tb)
+        outcomes.append((ok, value))
+    return outcomes
+
+
+def _outcome(ok, value, remote_tb=None):
+    """Pickle a task's outcome, or an error saying why it cannot be."""
+    try:
+        return pickle.dumps((ok, value, remote_tb))
+    except Exception as exc:
+        error = RuntimeError(f'Error sending result: {value!r}. Reason: {exc!r}')
+        return pickle.dumps((False, error, None))
+
+
+def _load_main(path):
+    """Re-import the parent's script the way the spawn start method does."""
+    global _bootstrapping
+    main = types.ModuleType('__mp_main__')
+    main.__file__ = path
+    sys.modules['__main__'] = sys.modules['__mp_main__'] = main
+    with open(path) as f:
+        code = compile(f.read(), path, 'exec')
+    _bootstrapping = True
+    try:
+        exec(code, main.__dict__)
+    finally:
+        _bootstrapping = False
+
+
+def _worker():
+    import traceback
+    # Let the script's own import of multiprocessing find this module, so
+    # it sees _bootstrapping.
+    sys.modules['_codepod_multiprocessing'] = sys.modules[__name__]
+    header, _, data = sys.stdin.read().partition('\\n')
+    header = json.loads(header)
+    sys.path[:] = header['path']
+    _load_main(header['main'])
+
+    initializer, initargs, tasks = pickle.loads(base64.b64decode(data))
+    if initializer is not None:
+        initializer(*initargs)
+    outcomes = []
+    for task in tasks:
+        try:
+            func, args, kwds = pickle.loads(task)
+            outcomes.append(_outcome(True, func(*args, **kwds)))
+        except Exception as exc:
+            outcomes.append(_outcome(False, exc, traceback.format_exc()))
+
+    sys.stdout.flush()
+    sys.stdout.write('\\n' + _RESULT_MARKER
+                     + base64.b64encode(pickle.dumps(outcomes)).decode('ascii'))
+    sys.stdout.flush()
+
+
+def _context_pool(self, processes=None, initializer=None, initargs=(), maxtasksperchild=None):
+    return Pool(processes, initializer, initargs, maxtasksperchild)
+
+
+def install(multiprocessing):
+    """Make multiprocessing's Pool, and every context's, this one."""
+    import multiprocessing.context
+    import multiprocessing.pool
+    multiprocessing.context.BaseContext.Pool = _context_pool
+    multiprocessing.pool.Pool = Pool
+    multiprocessing.Pool = multiprocessing.context._default_context.Pool
+
+
+if __name__ == '__main__':
+    _worker()
+`;
@@ -23,6 +23,7 @@ export interface StorageCallbacks {
 }
 import type { RunResult } from './shell/shell-types.js';
 import type { HistoryEntry } from './shell/history.js';
+import { seededRandomSource } from './wasi/wasi-host.js';
 import type { PlatformAdapter } from './platform/adapter.js';
 import type { DirEntry, StatResult } from './vfs/inode.js';
 import { NetworkGateway } from './network/gateway.js';
@@ -42,6 +43,8 @@ import { ExtensionRegistry } from './extension/registry.js';
 import type { ExtensionConfig } from './extension/types.js';
 import { CODEPOD_EXT_SOURCE, generateCommandShim } from './extension/codepod-ext-shim.js';
 import { SUBPROCESS_PY_SOURCE } from './process/subprocess-shim.js';
+import { ASYNCIO_PY_SOURCE } from './python/asyncio-shim.js';
+import { MULTIPROCESSING_PY_SOURCE } from './python/multiprocessing-shim.js';
 import { PackageRegistry } from './packages/registry.js';
 import { ToolRegistry } from './packages/tool-registry.js';
 
@@ -84,6 +87,14 @@ export interface SandboxOptions {
   tools?: string[];
   /** Callbacks for offloading sandbox state to external storage. */
   storage?: StorageCallbacks;
+  /**
+   * Seed for every random draw in the sandbox: Python's `random`, `secrets`
+   * and `os.urandom`, `$RANDOM`, `mktemp`, `shuf`. The same seed and the same
+   * commands give the same output, for grading and tests. Anyone who knows
+   * the seed can predict `secrets`, so leave it unset otherwise; the default
+   * draws from `crypto.getRandomValues`.
+   */
+  randomSeed?: number;
   /**
    * Pre-seed the pip registry cache with a custom index JSON string.
    * The shell reads this as `/etc/codepod/registry-index.json` on first pip install,
@@ -112,6 +123,7 @@ interface SandboxParts {
   workerExecutor?: WorkerExecutor;
   extensionRegistry?: ExtensionRegistry;
   storage?: StorageCallbacks;
+  randomSeed?: number;
 }
 
 export class Sandbox {
@@ -127,6 +139,7 @@ export class Sandbox {
   private shellExecWasmPath: string;
   private mgr: ProcessManager;
   private envSnapshots: Map<string, Map<string, string>> = new Map();
+  private shellCheckpoints: Map<string, Uint8Array> = new Map();
   private bridge: NetworkBridge | null = null;
   private networkPolicy: NetworkPolicy | undefined;
   private security: SecurityOptions | undefined;
@@ -135,6 +148,7 @@ export class Sandbox {
   private workerExecutor: WorkerExecutor | null = null;
   private persistenceManager: PersistenceManager | null = null;
   private extensionRegistry: ExtensionRegistry | null = null;
+  private randomSeed: number | undefined;
 
   private constructor(parts: SandboxParts) {
     this.vfs = parts.vfs;
@@ -154,6 +168,7 @@ export class Sandbox {
     this.workerExecutor = parts.workerExecutor ?? null;
     this.extensionRegistry = parts.extensionRegistry ?? null;
     this.storage = parts.storage ?? null;
+    this.randomSeed = parts.randomSeed;
   }
 
   private audit(type: string, data?: Record<string, unknown>): void {
@@ -228,6 +243,7 @@ export class Sandbox {
       extensionRegistry,
       toolAllowlist: options.security?.toolAllowlist,
       memoryBytes: secLimits?.memoryBytes,
+      randomSource: options.randomSeed === undefined ? undefined : seededRandomSource(options.randomSeed),
     });
 
     // Wire output limits
@@ -258,13 +274,16 @@ export class Sandbox {
       });
     }
 
-    // Bootstrap subprocess shim and sitecustomize.py (always installed).
-    // If networking is enabled, also install socket/ssl/requests shims.
+    // Bootstrap the subprocess, asyncio and multiprocessing shims and
+    // sitecustomize.py (always installed). If networking is enabled, also
+    // install socket/ssl/requests shims.
     {
       const enc = new TextEncoder();
       vfs.withWriteAccess(() => {
         vfs.mkdirp('/usr/lib/python');
         vfs.writeFile('/usr/lib/python/subprocess.py', enc.encode(SUBPROCESS_PY_SOURCE));
+        vfs.writeFile('/usr/lib/python/_codepod_asyncio.py', enc.encode(ASYNCIO_PY_SOURCE));
+        vfs.writeFile('/usr/lib/python/_codepod_multiprocessing.py', enc.encode(MULTIPROCESSING_PY_SOURCE));
         // sitecustomize.py pre-loads our shims into sys.modules at interpreter
         // startup, bypassing RustPython's frozen modules which would otherwise
         // take priority over PYTHONPATH files.
@@ -418,7 +437,7 @@ export class Sandbox {
     // Create WorkerExecutor for hard-kill preemption when enabled.
     const workerExecutor = await Sandbox.createWorkerExecutor(
       vfs, options.wasmDir, shellExecWasmPath, tools, adapter,
-      options.security, bridge, options.network, extensionRegistry,
+      options.security, bridge, options.network, extensionRegistry, options.randomSeed,
     );
 
     const sb = new Sandbox({
@@ -426,7 +445,7 @@ export class Sandbox {
       wasmDir: options.wasmDir, shellExecWasmPath,
       mgr, bridge, networkPolicy: options.network,
       security: options.security, workerExecutor,
-      extensionRegistry, storage: options.storage,
+      extensionRegistry, storage: options.storage, randomSeed: options.randomSeed,
     });
 
     // Wire persistence if configured
@@ -509,6 +528,7 @@ export class Sandbox {
     bridge?: NetworkBridge,
     networkPolicy?: NetworkPolicy,
     extensionRegistry?: ExtensionRegistry,
+    randomSeed?: number,
   ): Promise<WorkerExecutor | undefined> {
     if (!security?.hardKill || !adapter.supportsWorkerExecution) return undefined;
     const { WorkerExecutor: WE } = await import('./execution/worker-executor.js');
@@ -531,6 +551,7 @@ export class Sandbox {
         blockedHosts: networkPolicy.blockedHosts,
       } : undefined,
       extensionRegistry: extensionRegistry?.list().length ? extensionRegistry : undefined,
+      randomSeed,
     });
   }
 
@@ -731,14 +752,19 @@ export class Sandbox {
     this.assertAlive();
     const id = this.vfs.snapshot();
     this.envSnapshots.set(id, this.runner.getEnvMap());
+    const checkpoint = this.runner.checkpoint?.();
+    if (checkpoint) this.shellCheckpoints.set(id, checkpoint);
     return id;
   }
 
   restore(id: string): void {
     this.assertAlive();
     this.vfs.restore(id);
+    const checkpoint = this.shellCheckpoints.get(id);
     const envSnap = this.envSnapshots.get(id);
-    if (envSnap) {
+    if (checkpoint && this.runner.restoreCheckpoint) {
+      this.runner.restoreCheckpoint(checkpoint);
+    } else if (envSnap) {
       this.runner.setEnvMap(envSnap);
     }
   }
@@ -824,12 +850,13 @@ export class Sandbox {
 
     const secLimits = this.security?.limits;
 
-    // Fork as ShellInstance — create a fresh instance and copy env
+    // Fork as ShellInstance — create a fresh instance and carry the session over
     const childRunner = await ShellInstance.create(childVfs, childMgr, this.adapter, this.shellExecWasmPath, {
       networkBridge: bridge,
       extensionRegistry: this.extensionRegistry ?? undefined,
       toolAllowlist: this.security?.toolAllowlist,
       memoryBytes: this.security?.limits?.memoryBytes,
+      randomSource: this.randomSeed === undefined ? undefined : seededRandomSource(this.randomSeed),
     });
 
     // Wire output limits to forked runner
@@ -837,16 +864,21 @@ export class Sandbox {
       childRunner.setOutputLimits(secLimits.stdoutBytes, secLimits.stderrBytes);
     }
 
-    // Copy env
-    const envMap = this.runner.getEnvMap();
-    for (const [k, v] of envMap) {
-      childRunner.setEnv(k, v);
+    // Carry the whole shell session (functions, aliases, cwd, ...) over when
+    // the runner can checkpoint it; otherwise just the env.
+    const checkpoint = this.runner.checkpoint?.();
+    if (checkpoint) {
+      childRunner.restoreCheckpoint(checkpoint);
+    } else {
+      for (const [k, v] of this.runner.getEnvMap()) {
+        childRunner.setEnv(k, v);
+      }
     }
 
     // Create WorkerExecutor for the child if parent uses hard-kill
     const childWorkerExecutor = await Sandbox.createWorkerExecutor(
       childVfs, this.wasmDir, this.shellExecWasmPath, tools, this.adapter,
-      this.security, bridge, this.networkPolicy, this.extensionRegistry ?? undefined,
+      this.security, bridge, this.networkPolicy, this.extensionRegistry ?? undefined, this.randomSeed,
     );
 
     return new Sandbox({
@@ -855,6 +887,7 @@ export class Sandbox {
       mgr: childMgr, bridge, networkPolicy: this.networkPolicy,
       security: this.security, workerExecutor: childWorkerExecutor,
       extensionRegistry: this.extensionRegistry ?? undefined,
+      randomSeed: this.randomSeed,
     });
   }
 
@@ -22,6 +22,11 @@ export interface PipPolicy {
   blockedPackages?: string[];
   /** Maximum number of pip-installed packages. */
   maxPackages?: number;
+  /**
+   * Simple (PEP 503) package index for pure-Python packages the codepod
+   * registry does not serve. Fetched through the network policy.
+   */
+  indexUrl?: string;
 }
 
 /** Security configuration for sandbox instances. */
@@ -9,6 +9,8 @@
  *   - Empty input: no output
  *   - Round-trip: encode then decode recovers original
  *   - Padding: 1-byte remainder → "==", 2-byte remainder → "="
+ *   - -w/--wrap width, -i/--ignore-garbage, FILE operands
+ *   - Invalid input: error and exit 1 after what decoded
  */
 import { describe, it, beforeEach } from '@std/testing/bdd';
 import { expect } from '@std/expect';
@@ -193,5 +195,84 @@ describe('base64 conformance', () => {
       expect(r.exitCode).toBe(0);
       expect(r.stdout).toBe('aGVsbG8=\n');
     });
+
+    it('reads a FILE operand', async () => {
+      vfs.writeFile('/home/user/msg.txt', new TextEncoder().encode('hello'));
+      const r = await runner.run('base64 /home/user/msg.txt');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aGVsbG8=\n');
+    });
+
+    it('a missing FILE is an error', async () => {
+      const r = await runner.run('base64 /home/user/nope');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toContain('base64: /home/user/nope: ');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // -w/--wrap
+  // ---------------------------------------------------------------------------
+  describe('-w/--wrap', () => {
+    it('-w 4 wraps at 4 columns', async () => {
+      const r = await runner.run("printf 'hello world' | base64 -w 4");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aGVs\nbG8g\nd29y\nbGQ=\n');
+    });
+
+    it('--wrap=8 wraps at 8 columns', async () => {
+      const r = await runner.run("printf 'hello world' | base64 --wrap=8");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aGVsbG8g\nd29ybGQ=\n');
+    });
+
+    it('-w 0 disables wrapping and the trailing newline', async () => {
+      const r = await runner.run("printf 'hello world' | base64 -w 0");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aGVsbG8gd29ybGQ=');
+    });
+
+    it('rejects an invalid wrap size', async () => {
+      const r = await runner.run("printf 'hi' | base64 -w x");
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("base64: invalid wrap size: 'x'\n");
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Invalid input and -i/--ignore-garbage
+  // ---------------------------------------------------------------------------
+  describe('invalid input', () => {
+    it('a stray character fails after what decoded', async () => {
+      const r = await runner.run("printf 'aGVs*bG8=' | base64 -d");
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('hel');
+      expect(r.stderr).toBe('base64: invalid input\n');
+    });
+
+    it('a truncated final group fails', async () => {
+      const r = await runner.run("printf 'aGVsbG8' | base64 -d");
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('hello');
+      expect(r.stderr).toBe('base64: invalid input\n');
+    });
+
+    it('-i skips characters outside the alphabet', async () => {
+      const r = await runner.run("printf 'aGVs*bG8=' | base64 -d -i");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('hello');
+    });
+
+    it('--ignore-garbage is the long form', async () => {
+      const r = await runner.run("printf 'aGVs*bG8=' | base64 --ignore-garbage -d");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('hello');
+    });
+
+    it('concatenated padded blocks decode', async () => {
+      const r = await runner.run("printf 'aGk=aGk=' | base64 -d");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('hihi');
+    });
   });
 });
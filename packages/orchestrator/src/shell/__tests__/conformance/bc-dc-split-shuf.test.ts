@@ -171,6 +171,24 @@ describe('bc/dc/split/misc conformance', () => {
       expect(r.exitCode).toBe(0);
       expect(r.stdout.trim().length).toBeGreaterThan(0);
     });
+
+    it('fills the trailing X run of a template', async () => {
+      const r = await runner.run('mktemp /tmp/fooXXXX');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toMatch(/^\/tmp\/foo[A-Za-z0-9]{4}\n$/);
+    });
+
+    it('names the template when it has too few Xs', async () => {
+      const r = await runner.run('mktemp /tmp/fooXX');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("mktemp: too few X's in template '/tmp/fooXX'\n");
+    });
+
+    it('names the template when creation fails', async () => {
+      const r = await runner.run('mktemp /nodir/aXXXX');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toContain("mktemp: failed to create file via template '/nodir/aXXXX'");
+    });
   });
 
   // ---------------------------------------------------------------------------
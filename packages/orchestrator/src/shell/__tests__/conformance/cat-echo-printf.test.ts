@@ -1,5 +1,8 @@
 /**
  * Conformance tests for cat, echo, printf — core text output commands.
+ *
+ * The shell's printf builtin shadows the printf binary, so the binary
+ * (used by env, xargs and find -exec) is spawned directly.
  */
 import { describe, it, beforeEach } from '@std/testing/bdd';
 import { expect } from '@std/expect';
@@ -24,11 +27,12 @@ function wasmName(tool: string): string {
 describe('cat/echo/printf conformance', () => {
   let vfs: VFS;
   let runner: ShellInstance;
+  let mgr: ProcessManager;
 
   beforeEach(async () => {
     const adapter = new NodeAdapter();
     vfs = new VFS();
-    const mgr = new ProcessManager(vfs, adapter);
+    mgr = new ProcessManager(vfs, adapter);
     for (const tool of TOOLS) {
       mgr.registerTool(tool, resolve(FIXTURES, wasmName(tool)));
     }
@@ -159,4 +163,130 @@ describe('cat/echo/printf conformance', () => {
       expect(r.stdout).toBe('hello');
     });
   });
+
+  // ---------------------------------------------------------------------------
+  // printf binary
+  // ---------------------------------------------------------------------------
+  describe('printf binary', () => {
+    function printf(...args: string[]) {
+      return mgr.spawn('printf', { args, env: {} });
+    }
+
+    it('reuses the format while arguments remain', async () => {
+      const r = await printf('%s=%d\n', 'a', '1', 'b');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a=1\nb=0\n');
+    });
+
+    it('missing arguments are empty or zero', async () => {
+      const r = await printf('[%s][%d]\n');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('[][0]\n');
+    });
+
+    it('warns about unused arguments', async () => {
+      const r = await printf('x\n', 'a');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('x\n');
+      expect(r.stderr).toBe("printf: warning: ignoring excess arguments, starting with 'a'\n");
+    });
+
+    it('%g and %G', async () => {
+      const r = await printf('%g %g %G\n', '0.0001', '123456789', '1e-10');
+      expect(r.stdout).toBe('0.0001 1.23457e+08 1E-10\n');
+    });
+
+    it('%e, %f and %F', async () => {
+      const r = await printf('%e|%.2f|%F\n', '1234.5', '3.14159', '2');
+      expect(r.stdout).toBe('1.234500e+03|3.14|2.000000\n');
+    });
+
+    it('%o, %x, %X and %u, with #', async () => {
+      const r = await printf('%o %x %X %#x %#o %u\n', '8', '255', '255', '255', '8', '42');
+      expect(r.stdout).toBe('10 ff FF 0xff 010 42\n');
+    });
+
+    it('%c prints the first character', async () => {
+      const r = await printf('%c%c\n', 'hello', 'w');
+      expect(r.stdout).toBe('hw\n');
+    });
+
+    it('%b expands escapes in its argument', async () => {
+      const r = await printf('%b\n', 'a\\tb\\0101');
+      expect(r.stdout).toBe('a\tbA\n');
+    });
+
+    it('\\c in a %b argument ends all output', async () => {
+      const r = await printf('%s%b%s\n', 'a', 'b\\cz', 'c');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('ab');
+    });
+
+    it('\\c in the format ends all output', async () => {
+      const r = await printf('a\\cb\n');
+      expect(r.stdout).toBe('a');
+    });
+
+    it('%q quotes for the shell', async () => {
+      const r = await printf('%q\n', "it's", 'a b', 'tab\there', '');
+      expect(r.stdout).toBe(`"it's"\n'a b'\n'tab'$'\\t''here'\n''\n`);
+    });
+
+    it('flags, width and precision', async () => {
+      const r = await printf('[%-5s][%5s][%+d][% d][%05d][%.3d][%5.1s]\n', 'ab', 'cd', '3', '4', '42', '5', 'xyz');
+      expect(r.stdout).toBe('[ab   ][   cd][+3][ 4][00042][005][    x]\n');
+    });
+
+    it('* takes width and precision from arguments', async () => {
+      const r = await printf('[%*d][%-*s][%.*f]\n', '5', '42', '4', 'ab', '2', '3.14159');
+      expect(r.stdout).toBe('[   42][ab  ][3.14]\n');
+    });
+
+    it('hex, octal and character constants', async () => {
+      const r = await printf('%d %d %d %d\n', '0x1F', '017', "'A", '"b');
+      expect(r.stdout).toBe('31 15 65 98\n');
+    });
+
+    it('escapes in the format', async () => {
+      const r = await printf('\\a\\e\\x41\\101%%\n');
+      expect(r.stdout).toBe('\x07\x1bAA%\n');
+    });
+
+    it('a non-numeric argument is an error', async () => {
+      const r = await printf('%d\n', 'abc');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('0\n');
+      expect(r.stderr).toBe("printf: 'abc': expected a numeric value\n");
+    });
+
+    it('a partly numeric argument is an error', async () => {
+      const r = await printf('%d|%f\n', '12abc', '1e3x');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('12|1000.000000\n');
+      expect(r.stderr).toBe(
+        "printf: '12abc': value not completely converted\n" +
+        "printf: '1e3x': value not completely converted\n",
+      );
+    });
+
+    it('an out-of-range integer is clamped and reported', async () => {
+      const r = await printf('%d\n', '99999999999999999999');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('9223372036854775807\n');
+      expect(r.stderr).toBe("printf: '99999999999999999999': Numerical result out of range\n");
+    });
+
+    it('an invalid conversion stops output', async () => {
+      const r = await printf('a%yb\n');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('a');
+      expect(r.stderr).toBe('printf: %y: invalid conversion specification\n');
+    });
+
+    it('no format is an error', async () => {
+      const r = await printf();
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toContain('printf: missing operand\n');
+    });
+  });
 });
@@ -72,6 +72,120 @@ describe('cp/mv/rm/mkdir conformance', () => {
       const r2 = await runner.run('cat /tmp/d2/f.txt');
       expect(r2.stdout).toBe('inside\n');
     });
+
+    it('-r into an existing directory copies beneath it', async () => {
+      await runner.run('mkdir -p /tmp/d1/sub /tmp/dest');
+      writeFile('/tmp/d1/f', 'one\n');
+      writeFile('/tmp/d1/sub/g', 'two\n');
+      const r = await runner.run('cp -r /tmp/d1 /tmp/dest && cat /tmp/dest/d1/f /tmp/dest/d1/sub/g');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('one\ntwo\n');
+    });
+
+    it('-v names each copy', async () => {
+      await runner.run('mkdir -p /tmp/d1/sub');
+      writeFile('/tmp/d1/f', 'one\n');
+      const r = await runner.run('cp -rv /tmp/d1 /tmp/d2');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        "'/tmp/d1' -> '/tmp/d2'\n'/tmp/d1/f' -> '/tmp/d2/f'\n'/tmp/d1/sub' -> '/tmp/d2/sub'\n",
+      );
+    });
+
+    it('copies several sources into a directory, or one given with -t', async () => {
+      await runner.run('mkdir -p /tmp/dest');
+      writeFile('/tmp/a', 'a\n');
+      writeFile('/tmp/b', 'b\n');
+      const r = await runner.run('cp /tmp/a /tmp/b /tmp/dest && cp -t /tmp/dest /tmp/a && cat /tmp/dest/a /tmp/dest/b');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\nb\n');
+    });
+
+    it('-n does not overwrite an existing file', async () => {
+      writeFile('/tmp/new', 'new\n');
+      writeFile('/tmp/old', 'old\n');
+      const r = await runner.run('cp -n /tmp/new /tmp/old && cat /tmp/old');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('old\n');
+    });
+
+    it('-u does not overwrite a newer file', async () => {
+      writeFile('/tmp/new', 'new\n');
+      writeFile('/tmp/old', 'old\n');
+      vfs.utimes('/tmp/new', new Date('2020-01-01T00:00:00Z'), new Date('2020-01-01T00:00:00Z'));
+      const r = await runner.run('cp -u /tmp/new /tmp/old && cat /tmp/old');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('old\n');
+    });
+
+    it('-p keeps the mode and modification time', async () => {
+      writeFile('/tmp/src', 'data\n');
+      const mtime = new Date('2020-01-02T03:04:05Z');
+      vfs.chmod('/tmp/src', 0o600);
+      vfs.utimes('/tmp/src', mtime, mtime);
+      const r = await runner.run('cp -p /tmp/src /tmp/dst');
+      expect(r.exitCode).toBe(0);
+      const st = vfs.stat('/tmp/dst');
+      expect(st.mtime.getTime()).toBe(mtime.getTime());
+      expect(st.permissions & 0o777).toBe(0o600);
+    });
+
+    it('a directory without -r is omitted', async () => {
+      await runner.run('mkdir -p /tmp/d1');
+      const r = await runner.run('cp /tmp/d1 /tmp/d2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("cp: -r not specified; omitting directory '/tmp/d1'\n");
+    });
+
+    it('refuses to copy a directory into itself', async () => {
+      await runner.run('mkdir -p /tmp/d1/sub');
+      const r = await runner.run('cp -r /tmp/d1 /tmp/d1/sub/in');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("cp: cannot copy a directory, '/tmp/d1', into itself, '/tmp/d1/sub/in'\n");
+    });
+
+    it('refuses to copy a file onto itself', async () => {
+      writeFile('/tmp/a', 'a\n');
+      const r = await runner.run('cp /tmp/a /tmp/a');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("cp: '/tmp/a' and '/tmp/a' are the same file\n");
+    });
+
+    it('-T refuses a directory destination for a file', async () => {
+      await runner.run('mkdir -p /tmp/dest');
+      writeFile('/tmp/a', 'a\n');
+      const r = await runner.run('cp -T /tmp/a /tmp/dest');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("cp: cannot overwrite directory '/tmp/dest' with non-directory\n");
+    });
+
+    it('several sources need a directory target', async () => {
+      writeFile('/tmp/a', 'a\n');
+      writeFile('/tmp/b', 'b\n');
+      const r = await runner.run('cp /tmp/a /tmp/b /tmp/nodir');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("cp: target '/tmp/nodir' is not a directory\n");
+    });
+
+    it('reports a missing source or destination', async () => {
+      writeFile('/tmp/a', 'a\n');
+      const r1 = await runner.run('cp /tmp/nope /tmp/b');
+      expect(r1.exitCode).toBe(1);
+      expect(r1.stderr).toContain("cp: cannot stat '/tmp/nope': ");
+      const r2 = await runner.run('cp /tmp/a');
+      expect(r2.exitCode).toBe(1);
+      expect(r2.stderr).toBe("cp: missing destination file operand after '/tmp/a'\n");
+    });
+
+    it('rejects unknown options and bad --preserve lists', async () => {
+      writeFile('/tmp/a', 'a\n');
+      const r1 = await runner.run('cp -z /tmp/a /tmp/b');
+      expect(r1.exitCode).toBe(1);
+      expect(r1.stderr).toBe("cp: invalid option -- 'z'\n");
+      const r2 = await runner.run('cp --preserve=bogus /tmp/a /tmp/b');
+      expect(r2.exitCode).toBe(1);
+      expect(r2.stderr).toBe("cp: invalid argument 'bogus' for '--preserve'\n");
+    });
   });
 
   // ---------------------------------------------------------------------------
@@ -14,7 +14,7 @@ const FIXTURES = resolve(import.meta.dirname, '../../../platform/__tests__/fixtu
 const SHELL_EXEC_WASM = resolve(import.meta.dirname, '../fixtures/codepod-shell-exec.wasm');
 
 const TOOLS = [
-  'cat', 'echo', 'diff', 'printf', 'true', 'false',
+  'cat', 'echo', 'diff', 'patch', 'printf', 'true', 'false',
 ];
 
 function wasmName(tool: string): string {
@@ -239,5 +239,286 @@ describe('diff conformance', () => {
       expect(r.stdout).toContain('-line25');
       expect(r.stdout).toContain('+CHANGED');
     });
+
+    it('marks a last line without a newline', async () => {
+      writeFile('/tmp/a.txt', 'a\nb');
+      writeFile('/tmp/b.txt', 'a\nb\n');
+      const r = await runner.run('diff /tmp/a.txt /tmp/b.txt');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('2c2\n< b\n\\ No newline at end of file\n---\n> b\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Directories
+  // ---------------------------------------------------------------------------
+  describe('directories', () => {
+    beforeEach(() => {
+      vfs.mkdirp('/tmp/a/sub');
+      vfs.mkdirp('/tmp/a/kind');
+      vfs.mkdirp('/tmp/b/sub');
+      writeFile('/tmp/a/same', 'same\n');
+      writeFile('/tmp/b/same', 'same\n');
+      writeFile('/tmp/a/f', 'x\ny\n');
+      writeFile('/tmp/b/f', 'x\nz\n');
+      writeFile('/tmp/a/f.log', 'skip\n');
+      writeFile('/tmp/b/f.log', 'skip2\n');
+      writeFile('/tmp/a/sub/s', 'one\n');
+      writeFile('/tmp/b/sub/s', 'two\n');
+      writeFile('/tmp/a/onlya', 'o\n');
+      writeFile('/tmp/b/onlyb', 'o\n');
+      writeFile('/tmp/b/kind', 'k\n');
+    });
+
+    const F = '2c2\n< y\n---\n> z\n';
+    const LOG = '1c1\n< skip\n---\n> skip2\n';
+    const SUB = '1c1\n< one\n---\n> two\n';
+    const ONLY =
+      'File a/kind is a directory while file b/kind is a regular file\n' +
+      'Only in a: onlya\n' +
+      'Only in b: onlyb\n';
+
+    it('compares two directories entry by entry', async () => {
+      const r = await runner.run('cd /tmp && diff a b');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe(
+        'diff a/f b/f\n' + F +
+        'diff a/f.log b/f.log\n' + LOG +
+        ONLY +
+        'Common subdirectories: a/sub and b/sub\n',
+      );
+    });
+
+    it('-r descends into common subdirectories', async () => {
+      const r = await runner.run('cd /tmp && diff -r a b');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe(
+        'diff -r a/f b/f\n' + F +
+        'diff -r a/f.log b/f.log\n' + LOG +
+        ONLY +
+        'diff -r a/sub/s b/sub/s\n' + SUB,
+      );
+    });
+
+    it('-rq reports only which files differ', async () => {
+      const r = await runner.run('cd /tmp && diff -rq a b');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe(
+        'Files a/f and b/f differ\n' +
+        'Files a/f.log and b/f.log differ\n' +
+        ONLY +
+        'Files a/sub/s and b/sub/s differ\n',
+      );
+    });
+
+    it('-x skips names matching a glob', async () => {
+      const r = await runner.run("cd /tmp && diff -r -x '*.log' a b");
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe(
+        "diff -r -x '*.log' a/f b/f\n" + F +
+        ONLY +
+        "diff -r -x '*.log' a/sub/s b/sub/s\n" + SUB,
+      );
+    });
+
+    it('--exclude also skips directories', async () => {
+      const r = await runner.run('cd /tmp && diff -r --exclude=sub a b');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe(
+        "diff -r '--exclude=sub' a/f b/f\n" + F +
+        "diff -r '--exclude=sub' a/f.log b/f.log\n" + LOG +
+        ONLY,
+      );
+    });
+
+    it('-X reads globs from a file', async () => {
+      writeFile('/tmp/excl', '*.log\nsub\n');
+      const r = await runner.run('cd /tmp && diff -r -X excl a b');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('diff -r -X excl a/f b/f\n' + F + ONLY);
+    });
+
+    it('a directory and a file compare the file with its namesake', async () => {
+      const r = await runner.run('cd /tmp && diff a/f b; diff a b/f');
+      expect(r.stdout).toBe(F + F);
+    });
+
+    it('identical directories print nothing', async () => {
+      const r = await runner.run('cd /tmp && diff -r a a');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // -N and binary files
+  // ---------------------------------------------------------------------------
+  describe('-N and binary files', () => {
+    it('-N treats a missing file as empty', async () => {
+      writeFile('/tmp/nf', 'new\n');
+      const r = await runner.run('diff -N /tmp/nf /tmp/missing; diff --new-file /tmp/missing /tmp/nf');
+      expect(r.stdout).toBe('1d0\n< new\n0a1\n> new\n');
+    });
+
+    it('without -N a missing file is an error', async () => {
+      writeFile('/tmp/nf', 'new\n');
+      const r = await runner.run('diff /tmp/nf /tmp/missing');
+      expect(r.exitCode).toBe(2);
+      expect(r.stderr).toContain('diff: /tmp/missing: ');
+    });
+
+    it('files with NUL bytes are binary', async () => {
+      writeFile('/tmp/bin1', 'a\0b\n');
+      writeFile('/tmp/bin2', 'a\0c\n');
+      const r = await runner.run('cd /tmp && diff bin1 bin2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('Binary files bin1 and bin2 differ\n');
+    });
+
+    it('-a compares binary files as text', async () => {
+      writeFile('/tmp/bin1', 'a\0b\n');
+      writeFile('/tmp/bin2', 'a\0c\n');
+      const r = await runner.run('cd /tmp && diff --text bin1 bin2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('1c1\n< a\0b\n---\n> a\0c\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Unified hunk ranges
+  // ---------------------------------------------------------------------------
+  describe('unified hunk ranges', () => {
+    it('a one-line range omits its count', async () => {
+      writeFile('/tmp/u1', '1\n2\n3\n4\n5\n');
+      writeFile('/tmp/u2', '1\nX\n3\n4\n5\n6\n');
+      const r = await runner.run('diff -U0 /tmp/u1 /tmp/u2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toContain('\n@@ -2 +2 @@\n-2\n+X\n@@ -5,0 +6 @@\n+6\n');
+    });
+
+    it('an empty range points at the line before it', async () => {
+      writeFile('/tmp/u1', '1\n2\n3\n4\n5\n');
+      writeFile('/tmp/u3', '2\n3\n');
+      const r = await runner.run('diff -U0 /tmp/u3 /tmp/u1');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toContain('\n@@ -0,0 +1 @@\n+1\n@@ -2,0 +4,2 @@\n+4\n+5\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Side by side (-y)
+  // ---------------------------------------------------------------------------
+  describe('side by side (-y)', () => {
+    beforeEach(() => {
+      writeFile('/tmp/s1', 'a\nb\nc\nd\n');
+      writeFile('/tmp/s2', 'a\nB\nc\ne\nf\n');
+    });
+
+    it('marks changed and inserted lines', async () => {
+      const r = await runner.run('diff -y -W 30 /tmp/s1 /tmp/s2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('a\t\ta\nb\t      |\tB\nc\t\tc\nd\t      |\te\n\t      >\tf\n');
+    });
+
+    it('marks deleted lines', async () => {
+      const r = await runner.run('diff -y -W 30 /tmp/s2 /tmp/s1');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('a\t\ta\nB\t      |\tb\nc\t\tc\ne\t      |\td\nf\t      <\n');
+    });
+
+    it('--suppress-common-lines and --width', async () => {
+      const r = await runner.run('diff --side-by-side --width=40 --suppress-common-lines /tmp/s1 /tmp/s2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('b\t\t   |\tB\nd\t\t   |\te\n\t\t   >\tf\n');
+    });
+
+    it('cuts lines too long for their half', async () => {
+      writeFile('/tmp/t1', 'x\n\tindented\nlonglonglonglonglonglonglong\n');
+      writeFile('/tmp/t2', 'x\n\tindent2\nlonglonglonglonglonglonglong\n');
+      const r = await runner.run('diff -y -W 20 /tmp/t1 /tmp/t2');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('x\tx\n      |\t\nlongl\tlongl\n');
+    });
+
+    it('identical files are shown in full and exit 0', async () => {
+      const r = await runner.run('diff -y -W 30 /tmp/s1 /tmp/s1');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\t\ta\nb\t\tb\nc\t\tc\nd\t\td\n');
+    });
+
+    it('an empty file puts every line on one side', async () => {
+      writeFile('/tmp/empty', '');
+      const r = await runner.run('diff -y -W 30 /tmp/empty /tmp/s1');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('\t      >\ta\n\t      >\tb\n\t      >\tc\n\t      >\td\n');
+    });
+
+    it('-y with -u is an error', async () => {
+      const r = await runner.run('diff -y -u /tmp/s1 /tmp/s2');
+      expect(r.exitCode).toBe(2);
+      expect(r.stdout).toBe('');
+      expect(r.stderr).toContain('diff: conflicting output style options\n');
+    });
+
+    it('an invalid width is an error', async () => {
+      const r = await runner.run('diff -y -W x /tmp/s1 /tmp/s2');
+      expect(r.exitCode).toBe(2);
+      expect(r.stderr).toContain("diff: invalid width 'x'\n");
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Round trip through patch
+  // ---------------------------------------------------------------------------
+  describe('diff -ruN | patch -p1', () => {
+    function writeTree(root: string, files: Record<string, string>) {
+      vfs.mkdirp(`${root}/sub`);
+      for (const [name, content] of Object.entries(files)) {
+        writeFile(`${root}/${name}`, content);
+      }
+    }
+
+    // One file changes, one is removed, one is added without a final
+    // newline, and one gains its final newline.
+    const OLD = { 'keep': 'x\ny\n', 'gone': 'bye\n', 'sub/nonl': 'p\nq' };
+    const NEW = { 'keep': 'x\nz\n', 'sub/added': 'new\nfile', 'sub/nonl': 'p\nq\n' };
+
+    beforeEach(() => {
+      writeTree('/tmp/a', OLD);
+      writeTree('/tmp/b', NEW);
+    });
+
+    it('headers carry timestamps, the epoch for a missing file', async () => {
+      const r = await runner.run('cd /tmp && diff -ruN a b');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toMatch(/^--- a\/gone\t\d{4}-\d\d-\d\d \d\d:\d\d:\d\d\.\d{9} \+0000$/m);
+      expect(r.stdout).toContain('+++ b/gone\t1970-01-01 00:00:00.000000000 +0000\n@@ -1 +0,0 @@\n-bye\n');
+      expect(r.stdout).toContain('--- a/sub/added\t1970-01-01 00:00:00.000000000 +0000\n');
+      expect(r.stdout).toContain('+new\n+file\n\\ No newline at end of file\n');
+      expect(r.stdout).toContain(' p\n-q\n\\ No newline at end of file\n+q\n');
+    });
+
+    it('patch reproduces the new tree', async () => {
+      writeTree('/tmp/work', OLD);
+      const d = await runner.run('cd /tmp && diff -ruN a b > /tmp/changes.diff');
+      expect(d.exitCode).toBe(1);
+      const p = await runner.run('cd /tmp/work && patch -p1 < /tmp/changes.diff');
+      expect(p.exitCode).toBe(0);
+      const r = await runner.run('diff -r /tmp/work /tmp/b');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('');
+      expect(new TextDecoder().decode(vfs.readFile('/tmp/work/sub/added'))).toBe('new\nfile');
+    });
+
+    it('the reverse diff restores the old tree', async () => {
+      writeTree('/tmp/work', NEW);
+      const d = await runner.run('cd /tmp && diff -ruN b a > /tmp/undo.diff');
+      expect(d.exitCode).toBe(1);
+      const p = await runner.run('cd /tmp/work && patch -p1 < /tmp/undo.diff');
+      expect(p.exitCode).toBe(0);
+      const r = await runner.run('diff -r /tmp/work /tmp/a');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('');
+    });
   });
 });
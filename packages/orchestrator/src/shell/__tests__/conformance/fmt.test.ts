@@ -0,0 +1,182 @@
+/**
+ * fmt conformance tests — paragraph reflowing.
+ * Based on GNU coreutils fmt behavior.
+ *
+ * Covers:
+ *   - Joining short lines into one paragraph
+ *   - -w N, -N and --width=N: fill to a width
+ *   - Spacing kept as written, or made uniform with -u
+ *   - Paragraphs by indentation: indented blocks and list items
+ *   - -p/--prefix: reformat only prefixed lines
+ *   - -s: split long lines without joining short ones
+ *   - Invalid width, unknown option and missing file errors
+ */
+import { describe, it, beforeEach } from '@std/testing/bdd';
+import { expect } from '@std/expect';
+import { resolve } from 'node:path';
+
+import { ShellInstance } from '../../shell-instance.js';
+import { ProcessManager } from '../../../process/manager.js';
+import { VFS } from '../../../vfs/vfs.js';
+import { NodeAdapter } from '../../../platform/node-adapter.js';
+
+const FIXTURES = resolve(import.meta.dirname, '../../../platform/__tests__/fixtures');
+const SHELL_EXEC_WASM = resolve(import.meta.dirname, '../fixtures/codepod-shell-exec.wasm');
+
+const TOOLS = [
+  'cat', 'echo', 'fmt', 'printf', 'true', 'false',
+];
+
+function wasmName(tool: string): string {
+  if (tool === 'true') return 'true-cmd.wasm';
+  if (tool === 'false') return 'false-cmd.wasm';
+  return `${tool}.wasm`;
+}
+
+describe('fmt conformance', () => {
+  let vfs: VFS;
+  let runner: ShellInstance;
+
+  beforeEach(async () => {
+    const adapter = new NodeAdapter();
+    vfs = new VFS();
+    const mgr = new ProcessManager(vfs, adapter);
+    for (const tool of TOOLS) {
+      mgr.registerTool(tool, resolve(FIXTURES, wasmName(tool)));
+    }
+    await mgr.preloadModules();
+    runner = await ShellInstance.create(vfs, mgr, adapter, SHELL_EXEC_WASM, {
+      syncSpawn: (cmd, args, env, stdin, cwd) => mgr.spawnSync(cmd, args, env, stdin, cwd),
+    });
+  });
+
+  function writeFile(path: string, content: string) {
+    vfs.writeFile(path, new TextEncoder().encode(content));
+  }
+
+  // ---------------------------------------------------------------------------
+  // Filling
+  // ---------------------------------------------------------------------------
+  describe('filling', () => {
+    it('joins short lines into one', async () => {
+      const r = await runner.run("printf 'short\\nline\\n' | fmt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('short line\n');
+    });
+
+    it('-w N fills to the width', async () => {
+      writeFile('/tmp/g', 'aaaa bbbb cccc dddd\n');
+      const r = await runner.run('fmt -w 10 /tmp/g');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aaaa bbbb\ncccc dddd\n');
+    });
+
+    it('-N and --width=N are the same as -w N', async () => {
+      writeFile('/tmp/g', 'aaaa bbbb cccc dddd\n');
+      const r = await runner.run('fmt -10 /tmp/g; fmt --width=10 /tmp/g');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aaaa bbbb\ncccc dddd\naaaa bbbb\ncccc dddd\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Spacing
+  // ---------------------------------------------------------------------------
+  describe('spacing', () => {
+    const TEXT = 'This is  spaced.   Next sentence here\nand more.\n';
+
+    it('keeps spacing within a line', async () => {
+      writeFile('/tmp/b', TEXT);
+      const r = await runner.run('fmt /tmp/b');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('This is  spaced.   Next sentence here and more.\n');
+    });
+
+    it('-u uses one space between words and two after sentences', async () => {
+      writeFile('/tmp/b', TEXT);
+      const r = await runner.run('fmt -u /tmp/b');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('This is spaced.  Next sentence here and more.\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Paragraphs
+  // ---------------------------------------------------------------------------
+  describe('paragraphs', () => {
+    it('a change of indentation starts a new paragraph', async () => {
+      writeFile('/tmp/j', '  aaaa bbbb cccc dddd\n  eeee\nplain one\n');
+      const r = await runner.run('fmt /tmp/j');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('  aaaa bbbb cccc dddd eeee\nplain one\n');
+    });
+
+    it('list items keep their shape', async () => {
+      writeFile('/tmp/f', '- item one\n  continues\n- item two\n');
+      const r = await runner.run('fmt -w 15 /tmp/f');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('- item one\n  continues\n- item two\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // -p/--prefix
+  // ---------------------------------------------------------------------------
+  describe('-p/--prefix', () => {
+    it('reformats only prefixed lines', async () => {
+      writeFile('/tmp/k', '# aaaa bbbb cccc dddd\n# eeee\ncode\n');
+      const r = await runner.run("fmt -p '#' /tmp/k");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('# aaaa bbbb cccc dddd eeee\ncode\n');
+    });
+
+    it('puts the prefix back on every line', async () => {
+      writeFile('/tmp/d', '# comment one\n# comment two is here\ncode line\n# three\n');
+      const r = await runner.run("fmt --prefix='#' -w 15 /tmp/d");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('# comment one\n# comment two\n# is here\ncode line\n# three\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // -s/--split-only
+  // ---------------------------------------------------------------------------
+  describe('-s/--split-only', () => {
+    it('does not join short lines', async () => {
+      const r = await runner.run("printf 'short\\nline\\n' | fmt -s");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('short\nline\n');
+    });
+
+    it('still splits long lines', async () => {
+      writeFile('/tmp/l', 'aaaa bbbb cccc dddd\nx\n');
+      const r = await runner.run('fmt -s -w 10 /tmp/l');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('aaaa bbbb\ncccc dddd\nx\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Errors
+  // ---------------------------------------------------------------------------
+  describe('errors', () => {
+    it('rejects an invalid width', async () => {
+      const r = await runner.run("printf 'x\\n' | fmt -w x");
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("fmt: invalid width: 'x'\n");
+    });
+
+    it('rejects an unknown option', async () => {
+      const r = await runner.run("printf 'x\\n' | fmt -z");
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toContain("fmt: invalid option -- 'z'\n");
+    });
+
+    it('a missing file is an error', async () => {
+      const r = await runner.run('fmt /tmp/nope');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toContain('fmt: ');
+      expect(r.stderr).toContain('/tmp/nope');
+    });
+  });
+});
@@ -151,6 +151,90 @@ describe('grep conformance', () => {
       expect(r.exitCode).toBe(0);
       expect(r.stdout).toContain('hello world');
     });
+
+    it('an unmatched \\{ is an error', async () => {
+      const r = await runner.run("grep 'l\\{' /home/user/test.txt");
+      expect(r.exitCode).toBe(2);
+      expect(r.stdout).toBe('');
+      expect(r.stderr).toBe('grep: Unmatched \\{\n');
+    });
+
+    it('an interval with only an upper bound starts at 0', async () => {
+      const r = await runner.run("printf 'a\\naa\\naaa\\n' | grep -x 'a\\{,2\\}'");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\naa\n');
+    });
+
+    it('a \\{\\} without bounds is an error', async () => {
+      const r = await runner.run("grep 'l\\{x\\}' /home/user/test.txt");
+      expect(r.exitCode).toBe(2);
+      expect(r.stderr).toBe('grep: Invalid content of \\{\\}\n');
+    });
+  });
+
+  // ---- Bracket expressions ----
+  describe('bracket expressions', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/br.txt', new TextEncoder().encode(
+        'a\\b\n]x\nfoo-bar\n{1}\na{x\n'
+      ));
+    });
+
+    it('a backslash is literal inside brackets', async () => {
+      const r = await runner.run("grep '[\\]' /home/user/br.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\\b\n');
+    });
+
+    it('a leading ] is a member', async () => {
+      const r = await runner.run("grep '^[]]' /home/user/br.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(']x\n');
+    });
+
+    it('collating elements and equivalence classes', async () => {
+      const r = await runner.run("grep '[[.-.]]' /home/user/br.txt; grep -c '^[[=a=]]' /home/user/br.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('foo-bar\n2\n');
+    });
+
+    it('a { that opens no interval is literal', async () => {
+      const r = await runner.run("grep '{1}' /home/user/br.txt; grep -E 'a{x' /home/user/br.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('{1}\na{x\n');
+    });
+  });
+
+  // ---- BRE context and GNU anchors ----
+  describe('BRE context and GNU anchors', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/anchors.txt', new TextEncoder().encode('*star\na^b\na$b\nab\n'));
+      vfs.writeFile('/home/user/w.txt', new TextEncoder().encode('cat\nconcat\ncats\nthe cat sat\n'));
+    });
+
+    it('a leading * is literal', async () => {
+      const r = await runner.run("grep '*star' /home/user/anchors.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('*star\n');
+    });
+
+    it('^ and $ are literal in the middle', async () => {
+      const r = await runner.run("grep 'a^b' /home/user/anchors.txt; grep 'a$b' /home/user/anchors.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a^b\na$b\n');
+    });
+
+    it('\\< and \\> match word edges', async () => {
+      const r = await runner.run("grep '\\<cat\\>' /home/user/w.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('cat\nthe cat sat\n');
+    });
+
+    it('\\` and \\\' match the ends of the line', async () => {
+      const r = await runner.run("grep '\\`cat'\"\\\\'\" /home/user/w.txt");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('cat\n');
+    });
   });
 
   // ---- Extended regex (-E flag) ----
@@ -307,10 +391,16 @@ describe('grep conformance', () => {
       expect(r.stdout).toBe('2:123\n4:456\n');
     });
 
-    it('-oc counts total matches not lines', async () => {
-      const r = await runner.run("echo 'aXbXcX' | grep -ocE 'X'");
+    it('-oc counts matching lines, not matches', async () => {
+      const r = await runner.run("printf 'aXbXcX\\nX\\nno\\n' | grep -ocE 'X'");
       expect(r.exitCode).toBe(0);
-      expect(r.stdout.trim()).toBe('3');
+      expect(r.stdout).toBe('2\n');
+    });
+
+    it('-o skips empty matches', async () => {
+      const r = await runner.run("echo hello | grep -o 'x*'");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('');
     });
 
     it('-oi case insensitive', async () => {
@@ -381,6 +471,53 @@ describe('grep conformance', () => {
       expect(r.stdout).toContain('src/main.rs');
       expect(r.stdout).toContain('readme.txt');
     });
+
+    function makeProject() {
+      vfs.mkdirp('/home/user/proj/src');
+      vfs.mkdirp('/home/user/proj/node_modules/x');
+      vfs.writeFile('/home/user/proj/src/main.rs', new TextEncoder().encode('fn main() {}\n'));
+      vfs.writeFile('/home/user/proj/readme.txt', new TextEncoder().encode('main project\n'));
+      vfs.writeFile('/home/user/proj/node_modules/x/i.js', new TextEncoder().encode('main junk\n'));
+    }
+
+    it('--exclude-dir skips directories met while recursing', async () => {
+      makeProject();
+      const r = await runner.run('grep -r --exclude-dir=node_modules main /home/user/proj');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '/home/user/proj/readme.txt:main project\n' +
+        '/home/user/proj/src/main.rs:fn main() {}\n',
+      );
+    });
+
+    it('--exclude-dir also applies to directory operands', async () => {
+      makeProject();
+      const r = await runner.run('grep -r --exclude-dir=proj main /home/user/proj');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('');
+    });
+
+    it('-r with no file searches the working directory', async () => {
+      makeProject();
+      const r = await runner.run('cd /home/user/proj && grep -r --exclude-dir=node_modules main');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('readme.txt:main project\nsrc/main.rs:fn main() {}\n');
+    });
+
+    it('-h drops file names while recursing', async () => {
+      makeProject();
+      const r = await runner.run('grep -rh --exclude-dir=node_modules main /home/user/proj');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('main project\nfn main() {}\n');
+    });
+
+    it('a directory without -r is an error', async () => {
+      makeProject();
+      const r = await runner.run('grep main /home/user/proj');
+      expect(r.exitCode).toBe(2);
+      expect(r.stdout).toBe('');
+      expect(r.stderr).toBe('grep: /home/user/proj: Is a directory\n');
+    });
   });
 
   // ---- Word match (-w) ----
@@ -407,6 +544,18 @@ describe('grep conformance', () => {
       const r = await runner.run('echo "testing" | grep -w "test"');
       expect(r.exitCode).toBe(1);
     });
+
+    it('-w needs no word character on either side', async () => {
+      const r = await runner.run('echo "concat cat_x cat" | grep -ow cat');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('cat\n');
+    });
+
+    it('-w applies to each branch of an alternation', async () => {
+      const r = await runner.run("echo 'cats the cat sat' | grep -ow 'cat\\|sat'");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('cat\nsat\n');
+    });
   });
 
   // ---- Quiet mode (-q) ----
@@ -518,4 +667,50 @@ describe('grep conformance', () => {
       expect(r.exitCode).toBe(1);
     });
   });
+
+  // ---- Long options ----
+  describe('long options', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/w.txt', new TextEncoder().encode('cat\nconcat\ncats\nthe cat sat\n'));
+    });
+
+    it('--ignore-case --count', async () => {
+      const r = await runner.run('grep --ignore-case --count CAT /home/user/w.txt');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('4\n');
+    });
+
+    it('--word-regexp --line-number', async () => {
+      const r = await runner.run('grep --word-regexp --line-number cat /home/user/w.txt');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('1:cat\n4:the cat sat\n');
+    });
+
+    it('--invert-match --fixed-strings --line-regexp', async () => {
+      const r = await runner.run('grep --invert-match --fixed-strings --line-regexp cat /home/user/w.txt');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('concat\ncats\nthe cat sat\n');
+    });
+
+    it('--after-context=N and --before-context=N', async () => {
+      const r = await runner.run('grep --after-context=1 concat /home/user/w.txt; grep --before-context=1 sat /home/user/w.txt');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('concat\ncats\ncats\nthe cat sat\n');
+    });
+
+    it('-NUM is the same as -C NUM', async () => {
+      const r = await runner.run('grep -1 concat /home/user/w.txt');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('cat\nconcat\ncats\n');
+    });
+  });
+
+  // ---- Input ----
+  describe('input', () => {
+    it('non-UTF-8 lines are still searched', async () => {
+      const r = await runner.run("printf 'ok \\377 main\\n' | grep -o 'ma.n'");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('main\n');
+    });
+  });
 });
@@ -15,7 +15,7 @@ const FIXTURES = resolve(import.meta.dirname, '../../../platform/__tests__/fixtu
 const SHELL_EXEC_WASM = resolve(import.meta.dirname, '../fixtures/codepod-shell-exec.wasm');
 
 const TOOLS = [
-  'cat', 'echo', 'printf', 'md5sum', 'sha256sum', 'sha1sum',
+  'cat', 'echo', 'printf', 'md5sum', 'sha256sum', 'sha1sum', 'sha512sum',
   'cksum', 'base64', 'base32', 'true', 'false',
 ];
 
@@ -69,6 +69,144 @@ describe('hash/encoding conformance', () => {
       const r = await runner.run('md5sum /tmp/x.txt');
       expect(r.stdout).toMatch(/^[0-9a-f]{32}\s/);
     });
+
+    it('-b marks the file name with *', async () => {
+      writeFile('/tmp/a', 'abc');
+      const r = await runner.run('md5sum -b /tmp/a');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('900150983cd24fb0d6963f7d28e17f72 */tmp/a\n');
+    });
+
+    it('--tag prints BSD-style lines', async () => {
+      writeFile('/tmp/a', 'abc');
+      const r = await runner.run('md5sum --tag /tmp/a');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('MD5 (/tmp/a) = 900150983cd24fb0d6963f7d28e17f72\n');
+    });
+
+    it('-z ends lines with NUL', async () => {
+      writeFile('/tmp/a', 'abc');
+      const r = await runner.run('md5sum -z /tmp/a');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('900150983cd24fb0d6963f7d28e17f72  /tmp/a\0');
+    });
+
+    it('a missing file is an error', async () => {
+      writeFile('/tmp/a', 'abc');
+      const r = await runner.run('md5sum /tmp/nope /tmp/a');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('900150983cd24fb0d6963f7d28e17f72  /tmp/a\n');
+      expect(r.stderr).toContain('md5sum: /tmp/nope: ');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Checksum verification (-c)
+  // ---------------------------------------------------------------------------
+  describe('checksum verification (-c)', () => {
+    const SUMS =
+      '900150983cd24fb0d6963f7d28e17f72  /tmp/a\n' +
+      '5d41402abc4b2a76b9719d911017c592  /tmp/h\n';
+
+    beforeEach(() => {
+      writeFile('/tmp/a', 'abc');
+      writeFile('/tmp/h', 'hello');
+      writeFile('/tmp/sums', SUMS);
+    });
+
+    it('prints OK for each matching file', async () => {
+      const r = await runner.run('md5sum -c /tmp/sums');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/a: OK\n/tmp/h: OK\n');
+    });
+
+    it('verifies a list it wrote itself', async () => {
+      const r = await runner.run('md5sum /tmp/a /tmp/h > /tmp/list; md5sum -c /tmp/list');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/a: OK\n/tmp/h: OK\n');
+    });
+
+    it('reads --tag lines', async () => {
+      writeFile('/tmp/tagged', 'MD5 (/tmp/a) = 900150983cd24fb0d6963f7d28e17f72\n');
+      const r = await runner.run('md5sum -c /tmp/tagged');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/a: OK\n');
+    });
+
+    it('reports a mismatch as FAILED and exits 1', async () => {
+      writeFile('/tmp/h', 'changed');
+      const r = await runner.run('md5sum -c /tmp/sums');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('/tmp/a: OK\n/tmp/h: FAILED\n');
+      expect(r.stderr).toBe('md5sum: WARNING: 1 computed checksum did NOT match\n');
+    });
+
+    it('--quiet prints only failures', async () => {
+      writeFile('/tmp/h', 'changed');
+      const r = await runner.run('md5sum -c --quiet /tmp/sums');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('/tmp/h: FAILED\n');
+    });
+
+    it('--status prints nothing', async () => {
+      writeFile('/tmp/h', 'changed');
+      const r = await runner.run('md5sum -c --status /tmp/sums');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('');
+      expect(r.stderr).toBe('');
+    });
+
+    it('counts improperly formatted lines', async () => {
+      writeFile('/tmp/sums', SUMS + 'junk line\n');
+      const r = await runner.run('md5sum -c /tmp/sums');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/a: OK\n/tmp/h: OK\n');
+      expect(r.stderr).toBe('md5sum: WARNING: 1 line is improperly formatted\n');
+    });
+
+    it('--strict fails on an improperly formatted line', async () => {
+      writeFile('/tmp/sums', SUMS + 'junk line\n');
+      const r = await runner.run('md5sum -c --strict /tmp/sums');
+      expect(r.exitCode).toBe(1);
+    });
+
+    it('-w names each improperly formatted line', async () => {
+      writeFile('/tmp/sums', SUMS + 'junk line\n');
+      const r = await runner.run('md5sum -c -w /tmp/sums');
+      expect(r.exitCode).toBe(0);
+      expect(r.stderr).toContain('md5sum: /tmp/sums: 3: improperly formatted MD5 checksum line\n');
+    });
+
+    it('a list with no checksum lines is an error', async () => {
+      writeFile('/tmp/sums', 'nothing here\n');
+      const r = await runner.run('md5sum -c /tmp/sums');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe('md5sum: /tmp/sums: no properly formatted MD5 checksum lines found\n');
+    });
+
+    it('a missing listed file fails to open', async () => {
+      vfs.unlink('/tmp/h');
+      const r = await runner.run('md5sum -c /tmp/sums');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('/tmp/a: OK\n/tmp/h: FAILED open or read\n');
+      expect(r.stderr).toContain('md5sum: WARNING: 1 listed file could not be read\n');
+    });
+
+    it('--ignore-missing skips missing files', async () => {
+      vfs.unlink('/tmp/h');
+      const r = await runner.run('md5sum -c --ignore-missing /tmp/sums');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/a: OK\n');
+    });
+
+    it('escapes a backslash in a file name and reads it back', async () => {
+      writeFile('/tmp/b\\sl', 'x');
+      const r = await runner.run("md5sum '/tmp/b\\sl' > /tmp/list; cat /tmp/list; md5sum -c /tmp/list");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '\\9dd4e461268c8034f5c8564e155c67a6  /tmp/b\\\\sl\n\\/tmp/b\\\\sl: OK\n',
+      );
+    });
   });
 
   // ---------------------------------------------------------------------------
@@ -100,6 +238,20 @@ describe('hash/encoding conformance', () => {
     });
   });
 
+  // ---------------------------------------------------------------------------
+  // sha512sum
+  // ---------------------------------------------------------------------------
+  describe('sha512sum', () => {
+    it('hashes "abc"', async () => {
+      const r = await runner.run("printf 'abc' | sha512sum");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        'ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a' +
+          '2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f  -\n',
+      );
+    });
+  });
+
   // ---------------------------------------------------------------------------
   // cksum
   // ---------------------------------------------------------------------------
@@ -158,5 +310,23 @@ describe('hash/encoding conformance', () => {
       expect(r.exitCode).toBe(0);
       expect(r.stdout).toBe('hello');
     });
+
+    it('-w 0 disables wrapping', async () => {
+      const r = await runner.run("printf 'hello world' | base32 -w 0");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('NBSWY3DPEB3W64TMMQ======');
+    });
+
+    it('rejects lowercase input', async () => {
+      const r = await runner.run("printf 'nbswy3dp' | base32 -d");
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe('base32: invalid input\n');
+    });
+
+    it('-i skips garbage when decoding', async () => {
+      const r = await runner.run("printf 'NBSW*Y3DP' | base32 -di");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('hello');
+    });
   });
 });
@@ -12,6 +12,9 @@
  *   - -n 0: produces no output
  *   - Multi-line and single-line inputs
  *   - File input
+ *
+ * Plus GNU options beyond the busybox suite: -c -N, attached and long
+ * counts with suffixes, -q/-v headers, clustered flags, and bad counts.
  */
 import { describe, it, beforeEach } from '@std/testing/bdd';
 import { expect } from '@std/expect';
@@ -211,4 +214,76 @@ describe('head busybox', () => {
       expect(r.stdout).toBe('one\ntwo\n');
     });
   });
+
+  // ---------------------------------------------------------------------------
+  // GNU options
+  // ---------------------------------------------------------------------------
+  describe('GNU options', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/x', new TextEncoder().encode('a\nb\nc\n'));
+      vfs.writeFile('/home/user/y', new TextEncoder().encode('1\n2\n3\n'));
+    });
+
+    it('-c -N outputs all but the last N bytes', async () => {
+      const r = await runner.run('head -c -2 /home/user/x');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\nb\n');
+    });
+
+    it('counts can be attached or given long', async () => {
+      const r = await runner.run('head -n2 /home/user/x; head --bytes=3 /home/user/y');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\nb\n1\n2');
+    });
+
+    it('counts take multiplier suffixes', async () => {
+      const r = await runner.run('head -c 1b /home/user/x');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\nb\nc\n');
+    });
+
+    it('multiple files get headers', async () => {
+      const r = await runner.run('head -n 1 /home/user/x /home/user/y');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('==> /home/user/x <==\na\n\n==> /home/user/y <==\n1\n');
+    });
+
+    it('-q drops the headers, clustered with -n', async () => {
+      const r = await runner.run('head -qn1 /home/user/x /home/user/y');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('a\n1\n');
+    });
+
+    it('-v prints a header for a single file, and the last of -q/-v wins', async () => {
+      const r = await runner.run('head -v -n 1 /home/user/x; head -q -v -n 1 /home/user/y');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('==> /home/user/x <==\na\n==> /home/user/y <==\n1\n');
+    });
+
+    it('a missing file gets no header and sets the exit status', async () => {
+      const r = await runner.run('head -n 1 /home/user/x /home/user/nope /home/user/y');
+      expect(r.exitCode).toBe(1);
+      expect(r.stdout).toBe('==> /home/user/x <==\na\n\n==> /home/user/y <==\n1\n');
+      expect(r.stderr).toContain("head: cannot open '/home/user/nope' for reading");
+    });
+
+    it('non-UTF-8 bytes and a missing final newline pass through', async () => {
+      vfs.writeFile('/home/user/bin', new Uint8Array([0x61, 0xff, 0x0a, 0x62]));
+      const r = await runner.run('head -n 5 /home/user/bin | od -A n -t x1');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout.trim()).toBe('61 ff 0a 62');
+    });
+
+    it('rejects an invalid line count', async () => {
+      const r = await runner.run('head -n abc /home/user/x');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("head: invalid number of lines: 'abc'\n");
+    });
+
+    it('rejects an invalid byte count', async () => {
+      const r = await runner.run('head -c 2x /home/user/x');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("head: invalid number of bytes: '2x'\n");
+    });
+  });
 });
@@ -274,6 +274,153 @@ describe('sed conformance', () => {
       expect(result.exitCode).toBe(0);
       expect(result.stdout).toBe('KEEP\nstay\n');
     });
+
+    it('a block can open in one -e and close in the next', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a\nb\nc\n'));
+      const result = await runner.run('sed -n -e \'1{p\' -e \'}\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a\n');
+    });
+
+    it('a block can span -f script files', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a\nb\nc\n'));
+      vfs.writeFile('/home/user/open.sed', new TextEncoder().encode('2{\n'));
+      vfs.writeFile('/home/user/close.sed', new TextEncoder().encode('p\n}\n'));
+      const result = await runner.run('sed -n -f /home/user/open.sed -f /home/user/close.sed /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('b\n');
+    });
+
+    it('errors name the expression they come from', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a\n'));
+      const result = await runner.run('sed -e p -e \'}\' /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stderr).toBe('sed: -e expression #2: unexpected `}\'\n');
+    });
+
+    it('an s command cannot run on into the next -e', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a\n'));
+      const result = await runner.run('sed -e \'s/a/b\' -e p /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stderr).toBe('sed: -e expression #1: unterminated `s\' command\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Script files
+  // ---------------------------------------------------------------------------
+  describe('script files', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('1\n2\n'));
+      vfs.writeFile('/home/user/s1.sed', new TextEncoder().encode(
+        '# comment\ns/1/one/\n# another\n2a\\\nadded \\\nmore\n'
+      ));
+      vfs.writeFile('/home/user/s2.sed', new TextEncoder().encode('#n\n/2/p\n'));
+    });
+
+    it('-f and --file read the script from a file', async () => {
+      const result = await runner.run('sed -f /home/user/s1.sed /home/user/input.txt; sed --file=/home/user/s1.sed /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('one\n2\nadded \nmore\n'.repeat(2));
+    });
+
+    it('a first line of #n acts as -n', async () => {
+      const result = await runner.run('sed -f /home/user/s2.sed /home/user/input.txt; sed -nf /home/user/s2.sed /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('2\n2\n');
+    });
+
+    it('-e and -f run in the order given', async () => {
+      const result = await runner.run(
+        'sed -e \'s/1/A/\' -f /home/user/s1.sed /home/user/input.txt; ' +
+        'sed -f /home/user/s1.sed -e \'s/one/B/\' /home/user/input.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('A\n2\nadded \nmore\nB\n2\nadded \nmore\n');
+    });
+
+    it('with -e, every operand is an input file', async () => {
+      const result = await runner.run('sed -e p p');
+      expect(result.exitCode).toBe(2);
+      expect(result.stdout).toBe('');
+      expect(result.stderr).toContain('sed: can\'t read ');
+    });
+
+    it('errors name the script file', async () => {
+      vfs.writeFile('/home/user/bad.sed', new TextEncoder().encode('s/3/x/\n}\n'));
+      const result = await runner.run('sed -f /home/user/bad.sed /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stderr).toBe('sed: file /home/user/bad.sed: unexpected `}\'\n');
+    });
+
+    it('a script file that cannot be opened is an error', async () => {
+      const result = await runner.run('sed -f /home/user/nope.sed /home/user/input.txt');
+      expect(result.exitCode).toBe(4);
+      expect(result.stdout).toBe('');
+      expect(result.stderr).toContain('sed: couldn\'t open file /home/user/nope.sed: ');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Multiple input files
+  // ---------------------------------------------------------------------------
+  describe('multiple input files', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/f1.txt', new TextEncoder().encode('1\n2\n'));
+      vfs.writeFile('/home/user/f2.txt', new TextEncoder().encode('3\n4\n'));
+    });
+
+    it('files are one stream for line numbers and $', async () => {
+      const result = await runner.run('sed -n \'$=;3p\' /home/user/f1.txt /home/user/f2.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('3\n4\n');
+    });
+
+    it('-s starts line numbers, $ and the hold space over', async () => {
+      const result = await runner.run(
+        'sed -s -n \'$=\' /home/user/f1.txt /home/user/f2.txt; ' +
+        'sed -s 1d /home/user/f1.txt /home/user/f2.txt; ' +
+        'sed -s -n \'H;${x;s/\\n/,/g;p}\' /home/user/f1.txt /home/user/f2.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('2\n2\n2\n4\n,1,2\n,3,4\n');
+    });
+
+    it('N stops at the end of each file only with -s', async () => {
+      vfs.writeFile('/home/user/f3.txt', new TextEncoder().encode('1\n2\n3\n'));
+      vfs.writeFile('/home/user/f4.txt', new TextEncoder().encode('4\n'));
+      const result = await runner.run(
+        'sed \'N;s/\\n/+/\' /home/user/f3.txt /home/user/f4.txt; ' +
+        'sed -s \'N;s/\\n/+/\' /home/user/f3.txt /home/user/f4.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1+2\n3+4\n1+2\n3\n4\n');
+    });
+
+    it('-i edits each file on its own', async () => {
+      const result = await runner.run('sed -i \'$s/$/!/\' /home/user/f1.txt /home/user/f2.txt && cat /home/user/f1.txt /home/user/f2.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n2!\n3\n4!\n');
+    });
+
+    it('q under -i leaves later files untouched', async () => {
+      const result = await runner.run('sed -i \'s/^/X/;2q\' /home/user/f1.txt /home/user/f2.txt; cat /home/user/f1.txt /home/user/f2.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('X1\nX2\n3\n4\n');
+    });
+
+    it('F prints the file name, or - for standard input', async () => {
+      const result = await runner.run('sed -n \'1F\' /home/user/f1.txt; cat /home/user/f1.txt | sed -n \'1F\'');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('/home/user/f1.txt\n-\n');
+    });
+
+    it('a missing file is reported and the rest are processed', async () => {
+      const result = await runner.run('sed p /home/user/nope.txt /home/user/f1.txt');
+      expect(result.exitCode).toBe(2);
+      expect(result.stdout).toBe('1\n1\n2\n2\n');
+      expect(result.stderr).toContain('sed: can\'t read /home/user/nope.txt: ');
+    });
   });
 
   // ---------------------------------------------------------------------------
@@ -315,6 +462,235 @@ describe('sed conformance', () => {
     });
   });
 
+  // ---------------------------------------------------------------------------
+  // Multi-line commands and the cycle
+  // ---------------------------------------------------------------------------
+  describe('multi-line commands', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('1\n2\n3\n4\n5\n'));
+    });
+
+    it('p prints the line as it is when p runs', async () => {
+      const result = await runner.run('sed -n \'p;s/1/X/p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\nX\n2\n3\n4\n5\n');
+    });
+
+    it('s flags end at the command separator', async () => {
+      const result = await runner.run('sed -n \'s/1/X/g;p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('X\n2\n3\n4\n5\n');
+    });
+
+    it('n prints the line and reads the next', async () => {
+      const result = await runner.run('sed \'n;d\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n3\n5\n');
+    });
+
+    it('n flushes queued a text first', async () => {
+      const result = await runner.run('sed -e \'1a\\\' -e X -e \'1n;s/^/>/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\nX\n>2\n>3\n>4\n>5\n');
+    });
+
+    it('N joins lines with a newline', async () => {
+      const result = await runner.run('sed \'N;s/\\n/-/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1-2\n3-4\n5\n');
+    });
+
+    it('N at the end of input prints and stops', async () => {
+      const result = await runner.run('sed \'N;N;s/.*/[&]/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('[1\n2\n3]\n4\n5\n');
+    });
+
+    it('P prints up to the first newline', async () => {
+      const result = await runner.run('sed -n \'$!N;P\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n3\n5\n');
+    });
+
+    it('$!N;P;D removes duplicate lines', async () => {
+      vfs.writeFile('/home/user/dup.txt', new TextEncoder().encode('a\na\nb\nb\nb\nc\n'));
+      const result = await runner.run('sed \'$!N;/^\\(.*\\)\\n\\1$/!P;D\' /home/user/dup.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a\nb\nc\n');
+    });
+
+    it('D without a newline acts like d', async () => {
+      const result = await runner.run('sed \'$!N;s/\\n/ /;P;D\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1 2\n3 4\n5\n');
+    });
+
+    it('c over a range prints its text once', async () => {
+      const result = await runner.run('sed -e \'2,3c\\\' -e gone /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\ngone\n4\n5\n');
+    });
+
+    it('a line-number end before the start is a one-line range', async () => {
+      const result = await runner.run('sed -n \'2,1p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('2\n');
+    });
+
+    it('a regex end is first tried on the next line', async () => {
+      const result = await runner.run('sed -n \'/2/,/[0-9]/p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('2\n3\n');
+    });
+
+    it('unbalanced braces are errors', async () => {
+      const open = await runner.run('sed \'{p\' /home/user/input.txt');
+      expect(open.exitCode).toBe(1);
+      expect(open.stderr).toBe('sed: -e expression #1: unmatched `{\'\n');
+      const close = await runner.run('sed \'p}\' /home/user/input.txt');
+      expect(close.exitCode).toBe(1);
+      expect(close.stderr).toBe('sed: -e expression #1: unexpected `}\'\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Labels and branches
+  // ---------------------------------------------------------------------------
+  describe('labels and branches', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('1\n2\n3\n4\n5\n'));
+    });
+
+    it(':a;N;$!ba joins every line', async () => {
+      const result = await runner.run('sed \':a;N;$!ba;s/\\n/,/g\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1,2,3,4,5\n');
+    });
+
+    it('a t loop joins continuation lines', async () => {
+      vfs.writeFile('/home/user/cont.txt', new TextEncoder().encode('one \\\ntwo \\\nthree\nfour\n'));
+      const result = await runner.run('sed -e :a -e \'/\\\\$/N; s/\\\\\\n//; ta\' /home/user/cont.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('one two three\nfour\n');
+    });
+
+    it('a t loop groups digits', async () => {
+      vfs.writeFile('/home/user/num.txt', new TextEncoder().encode('1234567\n12\n'));
+      const result = await runner.run('sed \':a;s/\\([0-9]\\)\\([0-9]\\{3\\}\\)\\($\\|,\\)/\\1,\\2\\3/;ta\' /home/user/num.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1,234,567\n12\n');
+    });
+
+    it('b with no label jumps to the end', async () => {
+      const result = await runner.run('sed \'/3/b;s/$/./\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1.\n2.\n3\n4.\n5.\n');
+    });
+
+    it('t jumps only after a substitution', async () => {
+      const result = await runner.run('sed \'s/2/X/;t;s/$/!/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1!\nX\n3!\n4!\n5!\n');
+    });
+
+    it('T jumps when no substitution was made', async () => {
+      const result = await runner.run('sed \'s/2/X/;T;s/$/!/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\nX!\n3\n4\n5\n');
+    });
+
+    it('n clears the substitution flag', async () => {
+      const result = await runner.run('sed \'s/1/X/;n;t skip;s/$/?/;:skip\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('X\n2?\n3\n4?\n5\n');
+    });
+
+    it('a branch can name a label from a later -e', async () => {
+      const result = await runner.run('sed -e \'b end\' -e \'s/1/X/\' -e \':end\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n2\n3\n4\n5\n');
+    });
+
+    it('a branch to an unknown label is an error', async () => {
+      const result = await runner.run('sed \'b nope\' /home/user/input.txt');
+      expect(result.exitCode).toBe(4);
+      expect(result.stdout).toBe('');
+      expect(result.stderr).toBe('sed: can\'t find label for jump to `nope\'\n');
+    });
+
+    it('a : without a label is an error', async () => {
+      const result = await runner.run('sed : /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stderr).toBe('sed: -e expression #1: ":" lacks a label\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // r, R and =
+  // ---------------------------------------------------------------------------
+  describe('r, R and =', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('1\n2\n3\n'));
+      vfs.writeFile('/home/user/ins.txt', new TextEncoder().encode('x\ny\n'));
+    });
+
+    it('r outputs a file at the end of the cycle', async () => {
+      const result = await runner.run('sed \'2r /home/user/ins.txt\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n2\nx\ny\n3\n');
+    });
+
+    it('r and a text come out in script order', async () => {
+      const result = await runner.run(
+        'sed -e \'1r /home/user/ins.txt\' -e \'1a\\\' -e after /home/user/input.txt; ' +
+        'sed -e \'1a\\\' -e after -e \'1r /home/user/ins.txt\' /home/user/input.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\nx\ny\nafter\n2\n3\n1\nafter\nx\ny\n2\n3\n');
+    });
+
+    it('r output survives d and is flushed by N', async () => {
+      const result = await runner.run(
+        'sed -e \'1r /home/user/ins.txt\' -e 1d /home/user/input.txt; ' +
+        'sed -e \'1r /home/user/ins.txt\' -e N /home/user/input.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('x\ny\n2\n3\nx\ny\n1\n2\n3\n');
+    });
+
+    it('r copies the file exactly', async () => {
+      vfs.writeFile('/home/user/raw.txt', new TextEncoder().encode('xy'));
+      const result = await runner.run('sed \'1r /home/user/raw.txt\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\nxy2\n3\n');
+    });
+
+    it('r skips a file it cannot read', async () => {
+      const result = await runner.run('sed \'r /home/user/nope.txt\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n2\n3\n');
+      expect(result.stderr).toBe('');
+    });
+
+    it('the filename runs to the end of the line', async () => {
+      const result = await runner.run('sed \'1r /home/user/ins.txt;p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n2\n3\n');
+    });
+
+    it('R outputs one line of the file each time', async () => {
+      const result = await runner.run('sed \'R /home/user/ins.txt\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\nx\n2\ny\n3\n');
+    });
+
+    it('= prints the line number', async () => {
+      const result = await runner.run('sed -n \'$=\' /home/user/input.txt; sed -n \'/2/{=;p}\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('3\n2\n2\n');
+    });
+  });
+
   // ---------------------------------------------------------------------------
   // Regex features
   // ---------------------------------------------------------------------------
@@ -348,6 +724,253 @@ describe('sed conformance', () => {
       // 'abbc' -> MATCH (bb matches b\+), 'abc' -> MATCH (b matches b\+), 'ac' -> ac (no b, \+ needs at least one)
       expect(result.stdout).toBe('MATCH\nMATCH\nac\n');
     });
+
+    it('an unmatched \\{ is reported, not matched literally', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a{\n'));
+      const result = await runner.run('sed \'s/a\\{/x/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stdout).toBe('');
+      expect(result.stderr).toBe('sed: -e expression #1: Unmatched \\{\n');
+    });
+
+    it('intervals: exact, open-ended and ranged', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a\naa\naaa\naaaa\nabab\n'));
+      const result = await runner.run(
+        'sed -n \'/^a\\{3\\}$/p\' /home/user/input.txt; ' +
+        'sed -n \'/^a\\{3,\\}$/p\' /home/user/input.txt; ' +
+        'sed -n \'/^a\\{2,3\\}$/p\' /home/user/input.txt; ' +
+        'sed -n \'/^\\(ab\\)\\{2\\}$/p\' /home/user/input.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('aaa\naaa\naaaa\naa\naaa\nabab\n');
+    });
+
+    it('an interval with only an upper bound starts at 0', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('a\naa\naaa\n'));
+      const result = await runner.run('sed -n \'/^a\\{,2\\}$/p\' /home/user/input.txt; sed -En \'/^a{,2}$/p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a\naa\na\naa\n');
+    });
+
+    it('intervals in substitutions', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('2024-01-02\n'));
+      const result = await runner.run('sed \'s/[0-9]\\{4\\}/YYYY/\' /home/user/input.txt; sed -E \'s/([0-9]{2}-){2}/X/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('YYYY-01-02\n20X02\n');
+    });
+
+    it('back-references match in addresses', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('the the cat\nno dup here\nabcabc\n'));
+      const result = await runner.run('sed -n \'/\\([a-z]\\+\\) \\1/p\' /home/user/input.txt; sed -En \'/^(abc)\\1$/p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('the the cat\nabcabc\n');
+    });
+
+    it('back-references match in s patterns', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('the the cat\nno dup here\n'));
+      const result = await runner.run('sed \'s/\\([a-z]\\+\\) \\1/\\1/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('the cat\nno dup here\n');
+    });
+
+    it('a back-reference to a missing group is an error', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('aa\n'));
+      const result = await runner.run('sed -n \'/\\(a\\)\\2/p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stdout).toBe('');
+      expect(result.stderr).toContain('sed: -e expression #1: ');
+    });
+
+    it('\\b and \\B match at and away from word edges', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('cat concat\n'));
+      const result = await runner.run('sed \'s/\\bcat\\b/DOG/g\' /home/user/input.txt; sed \'s/\\Bcat/X/g\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('DOG concat\ncat conX\n');
+    });
+
+    it('\\< and \\> match word starts and ends', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('cat concat\n'));
+      const result = await runner.run('sed \'s/\\</[/g;s/\\>/]/g\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('[cat] [concat]\n');
+    });
+
+    it('s///g makes an empty match at every boundary', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('cat concat\n'));
+      const result = await runner.run('sed \'s/\\b/|/g\' /home/user/input.txt; sed \'s/\\B/-/g\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('|cat| |concat|\nc-a-t c-o-n-c-a-t\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // The M flag
+  // ---------------------------------------------------------------------------
+  describe('the M flag', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('1\n2\n3\n4\n5\n'));
+    });
+
+    it('without M, ^ matches only at the start of the pattern space', async () => {
+      const result = await runner.run('sed \'N;s/^/>/g\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('>1\n2\n>3\n4\n5\n');
+    });
+
+    it('M makes ^ and $ match at embedded newlines', async () => {
+      const result = await runner.run('sed \'N;s/^/>/Mg\' /home/user/input.txt; sed \'N;s/$/</Mg\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('>1\n>2\n>3\n>4\n5\n1<\n2<\n3<\n4<\n5\n');
+    });
+
+    it('\\` and \\\' still match only at the ends with M', async () => {
+      const result = await runner.run('sed \'N;s/\\`/>/Mg\' /home/user/input.txt; sed "N;s/\\\\\'/</Mg" /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('>1\n2\n>3\n4\n5\n1\n2<\n3\n4<\n5\n');
+    });
+
+    it('M works on addresses, in either order with I', async () => {
+      vfs.writeFile('/home/user/ab.txt', new TextEncoder().encode('a\nB\n'));
+      const result = await runner.run(
+        'sed -n \'N;/^2$/Mp\' /home/user/input.txt; ' +
+        'sed -n \'N;/^b$/MIp\' /home/user/ab.txt; ' +
+        'sed -n \'N;/^b$/IMp\' /home/user/ab.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('1\n2\na\nB\na\nB\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Extended regex mode and long options
+  // ---------------------------------------------------------------------------
+  describe('extended regex mode', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode(
+        'a/b\nfoo\nFOO bar\nab\ncafé au lait\n'
+      ));
+    });
+
+    it('--regexp-extended is the same as -E', async () => {
+      const result = await runner.run('sed --regexp-extended \'s/(a)(b)/\\2\\1/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a/b\nfoo\nFOO bar\nba\ncafé au lait\n');
+    });
+
+    it('-E supports + and {n} and |', async () => {
+      const result = await runner.run('sed -E \'s/a+|o{2}/_/g\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('_/b\nf_\nFOO b_r\n_b\nc_fé _u l_it\n');
+    });
+
+    it('--quiet, --silent and --expression', async () => {
+      const result = await runner.run(
+        'sed --quiet --expression=/foo/p /home/user/input.txt; sed --silent --expression \'$p\' /home/user/input.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('foo\ncafé au lait\n');
+    });
+
+    it('--in-place edits the file', async () => {
+      const result = await runner.run('sed --in-place \'s/foo/baz/\' /home/user/input.txt && cat /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a/b\nbaz\nFOO bar\nab\ncafé au lait\n');
+    });
+
+    it('a pattern that does not compile is an error', async () => {
+      const result = await runner.run('sed -E \'s/(a/x/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stdout).toBe('');
+      expect(result.stderr).toContain('sed: -e expression #1: ');
+    });
+
+    it('an unterminated address regex is an error', async () => {
+      const result = await runner.run('sed -n \'/foo\' /home/user/input.txt');
+      expect(result.exitCode).toBe(1);
+      expect(result.stderr).toBe('sed: -e expression #1: unterminated address regex\n');
+    });
+
+    it('address regexes take escaped delimiters and \\cREGEXc', async () => {
+      const result = await runner.run('sed -n \'/a\\/b/p\' /home/user/input.txt; sed -n \'\\,a/b,p\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a/b\na/b\n');
+    });
+
+    it('address regexes take the I flag', async () => {
+      const result = await runner.run('sed -n \'\\%foo%Ip\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('foo\nFOO bar\n');
+    });
+
+    it('an escaped custom delimiter is literal', async () => {
+      vfs.writeFile('/home/user/delim.txt', new TextEncoder().encode('a|b\naxb\nab\n'));
+      const result = await runner.run('sed -e \'s|a\\|b|X|\' -e \'sxa\\xbxYx\' /home/user/delim.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('X\nY\nab\n');
+    });
+
+    it('non-ASCII text does not throw off the parser', async () => {
+      const result = await runner.run('sed \'/é/s/au/à/;s/é/E/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a/b\nfoo\nFOO bar\nab\ncafE à lait\n');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // -z/--null-data
+  // ---------------------------------------------------------------------------
+  describe('-z/--null-data', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/z.txt', new TextEncoder().encode('a\0b\0c\0'));
+    });
+
+    it('splits input at NUL', async () => {
+      const result = await runner.run('sed -z \'s/^/>/\' /home/user/z.txt; sed --null-data \'s/$/!/\' /home/user/z.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('>a\0>b\0>c\0a!\0b!\0c!\0');
+    });
+
+    it('a text file is one record, with no NUL added', async () => {
+      vfs.writeFile('/home/user/t.txt', new TextEncoder().encode('one\ntwo\nthree\n'));
+      const result = await runner.run('sed -z \'s/\\n/,/g\' /home/user/t.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('one,two,three,');
+    });
+
+    it('N, G, P and D use NUL', async () => {
+      const result = await runner.run('sed -z \'N;P;D\' /home/user/z.txt; sed -z G /home/user/z.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('a\0b\0c\0a\0\0b\0\0c\0\0');
+    });
+
+    it('= and i end with NUL, a text with a newline', async () => {
+      const result = await runner.run(
+        'sed -n -z \'$=\' /home/user/z.txt; ' +
+        'sed -z -e \'1i\\\' -e hi /home/user/z.txt; ' +
+        'sed -z -e \'1a\\\' -e hi /home/user/z.txt',
+      );
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('3\0hi\0a\0b\0c\0a\0hi\nb\0c\0');
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // Line endings and encoding
+  // ---------------------------------------------------------------------------
+  describe('line endings and encoding', () => {
+    it('a carriage return before the newline is kept', async () => {
+      vfs.writeFile('/home/user/input.txt', new TextEncoder().encode('x\r\ny\r\n'));
+      const result = await runner.run('sed \'s/\\r$/<CR>/\' /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('x<CR>\ny<CR>\n');
+    });
+
+    it('reading goes on past a line that is not UTF-8', async () => {
+      vfs.writeFile('/home/user/input.txt', new Uint8Array([0x6f, 0x6b, 0xff, 0x0a, 0x6e, 0x65, 0x78, 0x74, 0x0a]));
+      const result = await runner.run('sed -n 2p /home/user/input.txt');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout).toBe('next\n');
+    });
   });
 
   // ---------------------------------------------------------------------------
@@ -85,6 +85,100 @@ describe('stat/file/du/df/ls/tree conformance', () => {
       expect(r.exitCode).toBe(0);
       expect(r.stdout.toLowerCase()).toContain('empty');
     });
+
+    function writeBytes(path: string, bytes: number[]) {
+      vfs.writeFile(path, new Uint8Array(bytes));
+    }
+
+    it('recognizes magic numbers', async () => {
+      writeBytes('/tmp/p', [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0, 0, 0, 0x0d]);
+      writeBytes('/tmp/g', [0x1f, 0x8b, 0x08, 0, 0, 0, 0, 0, 0, 0x03]);
+      writeBytes('/tmp/z', [0x50, 0x4b, 0x03, 0x04, 0x14, 0]);
+      writeFile('/tmp/d', '%PDF-1.4\n');
+      const r = await runner.run('file /tmp/p /tmp/g /tmp/z /tmp/d');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toContain('/tmp/p: PNG image data');
+      expect(r.stdout).toContain('/tmp/g: gzip compressed data');
+      expect(r.stdout).toContain('/tmp/z: Zip archive data');
+      expect(r.stdout).toContain('/tmp/d: PDF document');
+    });
+
+    it('decodes wasm and ELF headers', async () => {
+      writeBytes('/tmp/m', [0x00, 0x61, 0x73, 0x6d, 0x01, 0, 0, 0]);
+      writeBytes('/tmp/e', [
+        0x7f, 0x45, 0x4c, 0x46, 0x02, 0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x02, 0, 0x3e, 0,
+      ]);
+      const r = await runner.run('file /tmp/m /tmp/e');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toContain('/tmp/m: WebAssembly (wasm) binary module version 0x1 (MVP)\n');
+      expect(r.stdout).toContain('/tmp/e: ELF 64-bit LSB executable');
+    });
+
+    it('classifies text by character set, kind and line endings', async () => {
+      writeFile('/tmp/u', 'caf\u00e9\n');
+      writeFile('/tmp/s', '#!/bin/sh\necho hi\n');
+      writeFile('/tmp/j', '{"a": 1}\n');
+      writeFile('/tmp/c', 'a\r\nb\r\n');
+      writeFile('/tmp/n', 'no newline');
+      const r = await runner.run('file /tmp/u /tmp/s /tmp/j /tmp/c /tmp/n');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '/tmp/u: UTF-8 Unicode text\n' +
+          '/tmp/s: POSIX shell script, ASCII text executable\n' +
+          '/tmp/j: JSON text data\n' +
+          '/tmp/c: ASCII text, with CRLF line terminators\n' +
+          '/tmp/n: ASCII text, with no line terminators\n',
+      );
+    });
+
+    it('calls unrecognized binary data "data"', async () => {
+      writeBytes('/tmp/b', [0x01, 0x02, 0x03, 0xfe, 0x00]);
+      const r = await runner.run('file /tmp/b');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/b: data\n');
+    });
+
+    it('-b leaves out the file name', async () => {
+      writeFile('/tmp/s', '#!/bin/sh\necho hi\n');
+      const r = await runner.run('file -b /tmp/s');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('POSIX shell script, ASCII text executable\n');
+    });
+
+    it('-i, --mime-type and --mime-encoding print MIME strings', async () => {
+      writeFile('/tmp/u', 'caf\u00e9\n');
+      writeBytes('/tmp/g', [0x1f, 0x8b, 0x08, 0, 0, 0, 0, 0, 0, 0x03]);
+      const r = await runner.run(
+        'file -i /tmp/u; file -b --mime-type /tmp/g; file --mime-encoding /tmp/u',
+      );
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '/tmp/u: text/plain; charset=utf-8\napplication/gzip\n/tmp/u: utf-8\n',
+      );
+    });
+
+    it('-F changes the separator', async () => {
+      writeFile('/tmp/n', 'no newline');
+      const r = await runner.run("file -F ' =>' /tmp/n");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('/tmp/n => ASCII text, with no line terminators\n');
+    });
+
+    it('an unreadable file is reported, and an error only with -E', async () => {
+      const r1 = await runner.run('file /tmp/nope');
+      expect(r1.exitCode).toBe(0);
+      expect(r1.stdout).toContain("/tmp/nope: cannot open `/tmp/nope'");
+      const r2 = await runner.run('file -E /tmp/nope');
+      expect(r2.exitCode).toBe(1);
+      expect(r2.stderr).toContain("file: cannot open `/tmp/nope'");
+    });
+
+    it('rejects an unknown option', async () => {
+      writeFile('/tmp/n', 'x\n');
+      const r = await runner.run('file -x /tmp/n');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("file: invalid option -- 'x'\n");
+    });
   });
 
   // ---------------------------------------------------------------------------
@@ -15,6 +15,9 @@
  *   - "ABCDEFGHIJKLMNOP" (16 bytes) → full line, no padding
  *   - "hello" (5 bytes) → partial line with 29 spaces before ASCII
  *   - Non-printable bytes → "." in ASCII column
+ *
+ * Also covers xxd's -c/-g/-l/-s/-u/-p/-r options and hexdump's default
+ * and canonical (-C) formats.
  */
 import { describe, it, beforeEach } from '@std/testing/bdd';
 import { expect } from '@std/expect';
@@ -41,7 +44,7 @@ const TOOLS = [
   'gzip', 'gunzip', 'tar',
   'bc', 'dc',
   'sqlite3',
-  'hostname', 'base64', 'sha256sum', 'md5sum', 'stat', 'xxd', 'rev', 'nproc',
+  'hostname', 'base64', 'sha256sum', 'md5sum', 'stat', 'xxd', 'hexdump', 'rev', 'nproc',
   'fmt', 'fold', 'nl', 'expand', 'unexpand', 'paste', 'comm', 'join',
   'split', 'strings', 'od', 'cksum', 'truncate',
   'tree', 'patch', 'file', 'column', 'cmp', 'timeout', 'numfmt', 'csplit', 'zip', 'unzip',
@@ -192,4 +195,130 @@ describe('xxd conformance', () => {
       );
     });
   });
+
+  // ---------------------------------------------------------------------------
+  // xxd options
+  // ---------------------------------------------------------------------------
+  describe('xxd options', () => {
+    beforeEach(() => {
+      vfs.writeFile('/home/user/ab', new TextEncoder().encode('ABCDEFGHIJ'));
+    });
+
+    it('-c sets the bytes per line', async () => {
+      const r = await runner.run('xxd -c 8 /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('00000000: 4142 4344 4546 4748  ABCDEFGH\n00000008: 494a                 IJ\n');
+    });
+
+    it('-g sets the group size', async () => {
+      const r = await runner.run('xxd -g 4 /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('00000000: 41424344 45464748 494a               ABCDEFGHIJ\n');
+    });
+
+    it('-l stops after a length', async () => {
+      const r = await runner.run('xxd -l 4 /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('00000000: 4142 4344                                ABCD\n');
+    });
+
+    it('-s skips ahead, or counts from the end when negative', async () => {
+      const r = await runner.run('xxd -s 4 /home/user/ab; xxd -s -3 /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '00000004: 4546 4748 494a                           EFGHIJ\n' +
+          '00000007: 4849 4a                                  HIJ\n',
+      );
+    });
+
+    it('-p prints plain hex, upper-case with -u', async () => {
+      const r = await runner.run('xxd -p /home/user/ab; xxd -u -p /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('4142434445464748494a\n4142434445464748494A\n');
+    });
+
+    it('-r turns a dump back into bytes', async () => {
+      const r = await runner.run('xxd /home/user/ab | xxd -r');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('ABCDEFGHIJ');
+    });
+
+    it('-r -p reads plain hex, and -s shifts where it lands', async () => {
+      const r = await runner.run("printf '414243\\n' | xxd -r -p -s 2 | xxd -p");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('0000414243\n');
+    });
+
+    it('-r patches an existing output file in place', async () => {
+      vfs.writeFile('/home/user/patch.hex', new TextEncoder().encode('00000004: 5a5a  ZZ\n'));
+      const r = await runner.run('xxd -r /home/user/patch.hex /home/user/ab && cat /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('ABCDZZGHIJ');
+    });
+
+    it('rejects an invalid count', async () => {
+      const r = await runner.run('xxd -c x /home/user/ab');
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("xxd: invalid number 'x' for '-c'\n");
+    });
+  });
+
+  // ---------------------------------------------------------------------------
+  // hexdump
+  // ---------------------------------------------------------------------------
+  describe('hexdump', () => {
+    it('default format prints 16-bit words', async () => {
+      const r = await runner.run("printf 'ABCDEFGHIJ' | hexdump");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe('0000000 4241 4443 4645 4847 4a49\n000000a\n');
+    });
+
+    it('-C prints the canonical hex+ASCII format', async () => {
+      const r = await runner.run("printf 'hello world, this is hexdump!\\n' | hexdump -C");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '00000000  68 65 6c 6c 6f 20 77 6f  72 6c 64 2c 20 74 68 69  |hello world, thi|\n' +
+          '00000010  73 20 69 73 20 68 65 78  64 75 6d 70 21 0a        |s is hexdump!.|\n' +
+          '0000001e\n',
+      );
+    });
+
+    it('squeezes repeated lines into *, unless -v', async () => {
+      vfs.writeFile('/home/user/z', new Uint8Array(48));
+      const r = await runner.run('hexdump -C /home/user/z; hexdump -C -v -n 20 /home/user/z');
+      expect(r.exitCode).toBe(0);
+      const zeros = '00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00';
+      expect(r.stdout).toBe(
+        `00000000  ${zeros}  |................|\n*\n00000030\n` +
+          `00000000  ${zeros}  |................|\n` +
+          '00000010  00 00 00 00                                       |....|\n' +
+          '00000014\n',
+      );
+    });
+
+    it('-s skips and -n limits the length', async () => {
+      const r = await runner.run("printf 'hello world' | hexdump -C -s 6 -n 5");
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '00000006  77 6f 72 6c 64                                    |world|\n0000000b\n',
+      );
+    });
+
+    it('reads several files as one stream', async () => {
+      vfs.writeFile('/home/user/ab', new TextEncoder().encode('ABCDEFGHIJ'));
+      const r = await runner.run('hexdump -C /home/user/ab /home/user/ab');
+      expect(r.exitCode).toBe(0);
+      expect(r.stdout).toBe(
+        '00000000  41 42 43 44 45 46 47 48  49 4a 41 42 43 44 45 46  |ABCDEFGHIJABCDEF|\n' +
+          '00000010  47 48 49 4a                                       |GHIJ|\n' +
+          '00000014\n',
+      );
+    });
+
+    it('rejects an invalid length', async () => {
+      const r = await runner.run("printf 'x' | hexdump -n x");
+      expect(r.exitCode).toBe(1);
+      expect(r.stderr).toBe("hexdump: invalid length: 'x'\n");
+    });
+  });
 });
@@ -1301,6 +1301,14 @@ describe('Coreutils Integration', () => {
       expect(result.stdout).toContain('-c');
     });
 
+    it('script gets argv as typed, the cwd and prefix env', async () => {
+      vfs.writeFile('/tmp/argv.py', new TextEncoder().encode('import os, sys\nprint(sys.argv, os.getcwd(), os.environ["GREETING"])\n'));
+      vfs.writeFile('/tmp/data.csv', new TextEncoder().encode('a,b\n'));
+      const result = await runner.run('cd /tmp && GREETING=hi python3 argv.py data.csv');
+      expect(result.exitCode).toBe(0);
+      expect(result.stdout.trim()).toBe("['argv.py', 'data.csv'] /tmp hi");
+    });
+
     it('os.environ reads shell env', async () => {
       runner.setEnv('MY_VAR', 'hello123');
       const result = await runner.run('python3 -c "import os; print(os.environ.get(\'MY_VAR\', \'missing\'))"');
@@ -19,12 +19,19 @@ import type { RunResult } from './shell-types.js';
 import type { HistoryEntry } from './history.js';
 import type { ShellLike, StreamCallbacks } from './shell-like.js';
 import { AsyncifyAsyncBridge } from '../async-bridge.js';
+import { checkGuestAbi, explainTrap, GuestPanicError, readGuestException, readGuestPanic } from '../guest-abi.js';
 import { createShellImports } from '../host-imports/shell-imports.js';
-import { createKernelImports } from '../host-imports/kernel-imports.js';
+import {
+  decodeFrame, encodeFrame, GUEST_RESULT_SIZE, GUEST_STATUS, responseResult, type RpcResponse,
+} from '../host-imports/rpc.js';
+import { createKernelImports, type RunCommandFn, type TerminalSize } from '../host-imports/kernel-imports.js';
 import { ProcessKernel, type SpawnRequest } from '../process/kernel.js';
-import { WasiHost } from '../wasi/wasi-host.js';
+import { WasiHost, type RandomSource } from '../wasi/wasi-host.js';
 import { createBufferTarget, createNullTarget, createStaticTarget, bufferToString, type FdTarget } from '../wasi/fd-target.js';
 
+/** Bytes moved per __write_chunk / __read_chunk call. */
+const STREAM_CHUNK = 64 * 1024;
+
 /** Default environment variables for a new ShellInstance. */
 const DEFAULT_ENV: [string, string][] = [
   ['HOME', '/home/user'],
@@ -57,6 +64,18 @@ export interface ShellInstanceOptions {
   toolAllowlist?: string[];
   /** Max WASM linear memory in bytes for spawned child processes. */
   memoryBytes?: number;
+  /** Random source for the shell and every process it spawns (`$RANDOM`,
+   *  `mktemp`, `shuf`, Python's `random`). Defaults to
+   *  `crypto.getRandomValues`. */
+  randomSource?: RandomSource;
+  /** Terminal the sandbox's output is shown on, for `isatty` and
+   *  `COLUMNS`/`LINES`. Unset means output is never a terminal. */
+  terminal?: TerminalSize;
+  /** Take the buffers the host lends the shell for each command (the
+   *  command text, output buffers) from a bump arena of this many bytes,
+   *  reset once the command is done, instead of the shell's heap. Unset
+   *  keeps them on the heap. */
+  bufferArenaBytes?: number;
 }
 
 export class ShellInstance implements ShellLike {
@@ -83,6 +102,17 @@ export class ShellInstance implements ShellLike {
   // JSPI-wrapped __run_command (or raw export if JSPI unavailable).
   // Stored separately because V8 makes WASM exports read-only.
   private runCommandFn: Function | undefined;
+  // __rpc, wrapped the same way.
+  private rpcFn: Function | undefined;
+  // __rpc_call, wrapped the same way; preferred over __rpc when exported.
+  private rpcCallFn: Function | undefined;
+  private nextRpcId = 1;
+  // Capacity of the guest's output rings; 0 until enableOutputRing.
+  private outputRingCapacity = 0;
+  // Whether __alloc is served from the guest's buffer arena, and how many
+  // run/rpc calls may still be using buffers from it.
+  private arenaEnabled = false;
+  private callsInFlight = 0;
 
   // Process kernel for pipe/spawn support.
   // Needed to extract buffer-captured output from spawned pipeline stages.
@@ -143,12 +173,21 @@ export class ShellInstance implements ShellLike {
 
     // Build runCommand callback for Python _codepod.spawn() / subprocess support.
     // Each call creates a fresh ShellInstance so we don't re-enter the busy one.
-    const runCommand = async (cmd: string, stdin: string) => {
+    const runCommand: RunCommandFn = async (cmd, stdin, runOptions) => {
       const sub = await ShellInstance.create(vfs, mgr, adapter, wasmPath, {
         networkBridge: options?.networkBridge,
         extensionRegistry: options?.extensionRegistry,
       });
       try {
+        for (const [name, value] of Object.entries(runOptions?.env ?? {})) {
+          sub.setEnv(name, value);
+        }
+        if (runOptions?.cwd !== undefined) {
+          const cd = await sub.run(`cd '${runOptions.cwd.replace(/'/g, "'\\''")}'`);
+          if (cd.exitCode !== 0) {
+            return { exitCode: cd.exitCode ?? 1, stdout: '', stderr: cd.stderr ?? '' };
+          }
+        }
         const result = await sub.run(cmd, { stdinData: new TextEncoder().encode(stdin) });
         return { exitCode: result.exitCode ?? 0, stdout: result.stdout ?? '', stderr: result.stderr ?? '' };
       } finally {
@@ -164,11 +203,13 @@ export class ShellInstance implements ShellLike {
       networkBridge: options?.networkBridge,
       nativeModules: mgr.nativeModules,
       runCommand,
+      shouldCancel: () => shellRef?.cancelState() ?? 0,
+      terminal: options?.terminal,
       spawnProcess: (req: SpawnRequest, fdTable: Map<number, FdTarget>) => {
         if (options?.syncSpawn) {
           return spawnSyncProcess(req, fdTable, kernel, options.syncSpawn);
         }
-        return spawnAsyncProcess(req, fdTable, mgr, kernel, adapter, shellRef?.getDeadlineMs(), options?.memoryBytes, options?.networkBridge, options?.extensionRegistry, runCommand);
+        return spawnAsyncProcess(req, fdTable, mgr, kernel, adapter, shellRef?.getDeadlineMs(), options?.memoryBytes, options?.networkBridge, options?.extensionRegistry, runCommand, options?.randomSource, options?.terminal);
       },
     });
 
@@ -193,6 +234,9 @@ export class ShellInstance implements ShellLike {
       codepodImports.host_waitpid = new WebAssembly.Suspending(
         kernelImports.host_waitpid as (...args: number[]) => Promise<number>,
       ) as unknown as WebAssembly.ImportValue;
+      codepodImports.host_waitpid_timeout = new WebAssembly.Suspending(
+        kernelImports.host_waitpid_timeout as (...args: number[]) => Promise<number>,
+      ) as unknown as WebAssembly.ImportValue;
       // host_yield: cooperative scheduling primitive
       codepodImports.host_yield = new WebAssembly.Suspending(
         kernelImports.host_yield as () => Promise<void>,
@@ -209,6 +253,9 @@ export class ShellInstance implements ShellLike {
       codepodImports.host_run_command = new WebAssembly.Suspending(
         kernelImports.host_run_command as (...args: number[]) => Promise<number>,
       ) as unknown as WebAssembly.ImportValue;
+      codepodImports.host_rpc = new WebAssembly.Suspending(
+        kernelImports.host_rpc as (...args: number[]) => Promise<number>,
+      ) as unknown as WebAssembly.ImportValue;
     } else {
       // Asyncify fallback: wrap async imports with the unwind/rewind bridge.
       // The bridge is initialised after instantiation (needs the WASM exports).
@@ -217,10 +264,12 @@ export class ShellInstance implements ShellLike {
         asyncifyBridge!.wrapImport(fn as (...args: number[]) => Promise<number> | number) as WebAssembly.ImportValue;
 
       codepodImports.host_waitpid       = aw(kernelImports.host_waitpid);
+      codepodImports.host_waitpid_timeout = aw(kernelImports.host_waitpid_timeout);
       codepodImports.host_yield         = aw(kernelImports.host_yield);
       codepodImports.host_network_fetch = aw(kernelImports.host_network_fetch);
       codepodImports.host_register_tool = aw(shellImports.host_register_tool);
       codepodImports.host_run_command   = aw(kernelImports.host_run_command);
+      codepodImports.host_rpc           = aw(kernelImports.host_rpc);
       // fd_read and poll_oneoff are wrapped below, after wasiImports is defined.
     }
 
@@ -376,7 +425,7 @@ export class ShellInstance implements ShellLike {
       },
       random_get: (ptr: number, len: number) => {
         const buf = new Uint8Array(getMemory().buffer, ptr, len);
-        crypto.getRandomValues(buf);
+        (options?.randomSource ?? ((b: Uint8Array) => crypto.getRandomValues(b)))(buf);
         return 0;
       },
       sched_yield: () => 0,
@@ -467,6 +516,7 @@ export class ShellInstance implements ShellLike {
     };
 
     const instance = await adapter.instantiate(module, imports);
+    checkGuestAbi(instance, 'shell WASM module');
     memoryRef = instance.exports.memory as WebAssembly.Memory;
 
     // JSPI: Wrap the __run_command export so it returns a Promise when
@@ -478,6 +528,16 @@ export class ShellInstance implements ShellLike {
     if (rawRunCommand && typeof (WebAssembly as any).promising === 'function') {
       wrappedRunCommand = (WebAssembly as any).promising(rawRunCommand);
     }
+    const rawRpc = instance.exports.__rpc as Function | undefined;
+    let wrappedRpc: Function | undefined = rawRpc;
+    if (rawRpc && typeof (WebAssembly as any).promising === 'function') {
+      wrappedRpc = (WebAssembly as any).promising(rawRpc);
+    }
+    const rawRpcCall = instance.exports.__rpc_call as Function | undefined;
+    let wrappedRpcCall: Function | undefined = rawRpcCall;
+    if (rawRpcCall && typeof (WebAssembly as any).promising === 'function') {
+      wrappedRpcCall = (WebAssembly as any).promising(rawRpcCall);
+    }
 
     // Call _start to initialize (runs main() which is a no-op for WASM).
     // wasm32-wasip1 binaries call proc_exit(0) when main returns.
@@ -505,11 +565,21 @@ export class ShellInstance implements ShellLike {
       memView.setUint32(dataAddr + 4, dataAddr + ASYNCIFY_BUF, true); // end of save area
       asyncifyBridge.initFromInstance(instance, dataAddr);
       wrappedRunCommand = asyncifyBridge.wrapExport(rawRunCommand as (...args: number[]) => number);
+      if (rawRpc) wrappedRpc = asyncifyBridge.wrapExport(rawRpc as (...args: number[]) => number);
+      if (rawRpcCall) {
+        wrappedRpcCall = asyncifyBridge.wrapExport(rawRpcCall as (...args: number[]) => number);
+      }
     }
 
     const shell = new ShellInstance(instance);
     shell.runCommandFn = wrappedRunCommand;
+    shell.rpcFn = wrappedRpc;
+    shell.rpcCallFn = wrappedRpcCall;
     shell.kernel = kernel;
+    if (options?.bufferArenaBytes) {
+      const arenaEnable = instance.exports.__arena_enable as ((capacity: number) => number) | undefined;
+      shell.arenaEnabled = arenaEnable !== undefined && arenaEnable(options.bufferArenaBytes) > 0;
+    }
     shellRef = shell;
 
     // Populate /bin/ in VFS with entries for registered tools so that
@@ -580,6 +650,16 @@ export class ShellInstance implements ShellLike {
     this.deadlineMs = timeoutMs !== undefined ? Date.now() + timeoutMs : Infinity;
   }
 
+  /**
+   * Cancellation state polled by the shell via host_should_cancel:
+   * 0 = keep running, 1 = cancelled, 2 = deadline passed.
+   */
+  cancelState(): number {
+    if (this.cancelledReason === 'TIMEOUT' || Date.now() > this.deadlineMs) return 2;
+    if (this.cancelledReason === 'CANCELLED') return 1;
+    return 0;
+  }
+
   /** Return the current deadline (epoch ms, or Infinity if none). */
   getDeadlineMs(): number {
     return this.deadlineMs;
@@ -594,6 +674,194 @@ export class ShellInstance implements ShellLike {
 
   // ── Command execution ──
 
+  /**
+   * Call a codepod-rpc method (see host-imports/rpc.ts) on the shell
+   * through its __rpc export, e.g. `rpc('fs.stat', { path })` or
+   * `rpc('status')`. Throws an RpcError if the method fails.
+   */
+  async rpc(method: string, params: unknown = null): Promise<unknown> {
+    this.callsInFlight++;
+    try {
+      return await this.rpcOnce(method, params);
+    } finally {
+      this.endCall();
+    }
+  }
+
+  /** Free the buffer arena once no call is using buffers from it. */
+  private endCall(): void {
+    if (--this.callsInFlight === 0 && this.arenaEnabled) {
+      (this.instance.exports.__arena_reset as () => void)();
+    }
+  }
+
+  private async rpcOnce(method: string, params: unknown): Promise<unknown> {
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    const request = encodeFrame({ id: this.nextRpcId++, method, params });
+    const reqPtr = alloc(request.byteLength);
+    new Uint8Array(this.memory.buffer, reqPtr, request.byteLength).set(request);
+    try {
+      const frame = this.rpcCallFn
+        ? await this.rpcStructured(reqPtr, request.byteLength)
+        : await this.rpcSized(reqPtr, request.byteLength);
+      return responseResult(decodeFrame<RpcResponse>(frame));
+    } finally {
+      dealloc(reqPtr, request.byteLength);
+    }
+  }
+
+  /** Call __rpc_call, which hands the response back as a GuestResult. */
+  private async rpcStructured(reqPtr: number, reqLen: number): Promise<Uint8Array> {
+    const call = this.rpcCallFn as (
+      reqPtr: number, reqLen: number, resultPtr: number,
+    ) => number | Promise<number>;
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    const resultPtr = alloc(GUEST_RESULT_SIZE);
+    try {
+      try {
+        await call(reqPtr, reqLen, resultPtr);
+      } catch (e: unknown) {
+        // A panic traps (wasm guests abort on panic) after the guest has
+        // recorded where and why.
+        throw explainTrap(this.instance, 'shell', e);
+      }
+      const view = new DataView(this.memory.buffer);
+      const status = view.getInt32(resultPtr, true);
+      const ptr = view.getUint32(resultPtr + 4, true);
+      const len = view.getUint32(resultPtr + 8, true);
+      if (status === GUEST_STATUS.panic) {
+        const panic = readGuestPanic(this.instance) ?? { message: this.lastError() ?? 'unknown panic' };
+        throw new GuestPanicError('shell', panic);
+      }
+      const frame = new Uint8Array(this.memory.buffer, ptr, len).slice();
+      if (len > 0) dealloc(ptr, len);
+      return frame;
+    } finally {
+      dealloc(resultPtr, GUEST_RESULT_SIZE);
+    }
+  }
+
+  /** Call __rpc, sizing the output buffer by the guest's answer. */
+  private async rpcSized(reqPtr: number, reqLen: number): Promise<Uint8Array> {
+    const call = this.rpcFn as
+      | ((reqPtr: number, reqLen: number, outPtr: number, outCap: number) => number | Promise<number>)
+      | undefined;
+    if (!call) {
+      throw new Error('WASM module does not export __rpc');
+    }
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+
+    let outCap = 4096;
+    let outPtr = alloc(outCap);
+    let needed = await call(reqPtr, reqLen, outPtr, outCap);
+    if (needed > outCap) {
+      // The guest holds the response, so the retry does not run it again.
+      dealloc(outPtr, outCap);
+      outCap = needed;
+      outPtr = alloc(outCap);
+      needed = await call(reqPtr, reqLen, outPtr, outCap);
+    }
+    const frame = new Uint8Array(this.memory.buffer, outPtr, needed).slice();
+    dealloc(outPtr, outCap);
+    return frame;
+  }
+
+  /**
+   * The message the guest recorded for its last failed call (a shell
+   * error, an rpc error or a panic), or null if that call succeeded or the
+   * guest does not export __last_error.
+   */
+  lastError(): string | null {
+    const lastError = this.instance.exports.__last_error as
+      | ((outPtr: number, outCap: number) => number)
+      | undefined;
+    if (!lastError) return null;
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    let cap = 1024;
+    let ptr = alloc(cap);
+    let n = lastError(ptr, cap);
+    if (n > cap) {
+      dealloc(ptr, cap);
+      cap = n;
+      ptr = alloc(cap);
+      n = lastError(ptr, cap);
+    }
+    const message = n > 0 ? new TextDecoder().decode(new Uint8Array(this.memory.buffer, ptr, n)) : null;
+    dealloc(ptr, cap);
+    return message;
+  }
+
+  /**
+   * Send `data` to the shell as incoming stream `stream`, for an rpc
+   * request to name in place of inline data (e.g. `fs.write` with
+   * `{ path, stream }`). Each chunk goes through the same small guest
+   * buffer, so the guest never holds the payload twice.
+   */
+  writeStream(stream: number, data: Uint8Array, chunkSize = STREAM_CHUNK): void {
+    const writeChunk = this.instance.exports.__write_chunk as
+      | ((stream: number, ptr: number, len: number) => number)
+      | undefined;
+    if (!writeChunk) {
+      throw new Error('WASM module does not export __write_chunk');
+    }
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    const cap = Math.max(1, Math.min(chunkSize, data.byteLength));
+    const ptr = alloc(cap);
+    try {
+      // An empty payload still opens the stream.
+      let offset = 0;
+      do {
+        const chunk = data.subarray(offset, offset + cap);
+        new Uint8Array(this.memory.buffer, ptr, chunk.byteLength).set(chunk);
+        writeChunk(stream, ptr, chunk.byteLength);
+        offset += chunk.byteLength;
+      } while (offset < data.byteLength);
+    } finally {
+      dealloc(ptr, cap);
+    }
+  }
+
+  /**
+   * Read outgoing stream `stream` (e.g. from `fs.read` with
+   * `{ path, stream: true }`) to the end, a chunk at a time.
+   */
+  readStream(stream: number, chunkSize = STREAM_CHUNK): Uint8Array {
+    const readChunk = this.instance.exports.__read_chunk as
+      | ((stream: number, outPtr: number, outCap: number) => number)
+      | undefined;
+    if (!readChunk) {
+      throw new Error('WASM module does not export __read_chunk');
+    }
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    const ptr = alloc(chunkSize);
+    const chunks: Uint8Array[] = [];
+    let total = 0;
+    try {
+      for (;;) {
+        const n = readChunk(stream, ptr, chunkSize);
+        if (n < 0) throw new Error(`no such stream: ${stream}`);
+        if (n === 0) break;
+        chunks.push(new Uint8Array(this.memory.buffer, ptr, n).slice());
+        total += n;
+      }
+    } finally {
+      dealloc(ptr, chunkSize);
+    }
+    const data = new Uint8Array(total);
+    let offset = 0;
+    for (const chunk of chunks) {
+      data.set(chunk, offset);
+      offset += chunk.byteLength;
+    }
+    return data;
+  }
+
   /**
    * Run a shell command and return the result.
    *
@@ -602,6 +870,15 @@ export class ShellInstance implements ShellLike {
    * operations, etc.), and returns a JSON-encoded RunResult.
    */
   async run(command: string, options?: { stdinData?: Uint8Array }): Promise<RunResult> {
+    this.callsInFlight++;
+    try {
+      return await this.runCommandOnce(command, options);
+    } finally {
+      this.endCall();
+    }
+  }
+
+  private async runCommandOnce(command: string, options?: { stdinData?: Uint8Array }): Promise<RunResult> {
     // When JSPI is active, runCommandFn is wrapped with WebAssembly.promising()
     // and returns a Promise<number>. When JSPI is not active, it returns number.
     // Either way, `await` handles both correctly.
@@ -629,6 +906,8 @@ export class ShellInstance implements ShellLike {
     // If stdinData is provided, install it as a static target on fd 0 for this run.
     // After the run, restore the null target so the shell's stdin is clean again.
     const hadStdinData = options?.stdinData && options.stdinData.byteLength > 0;
+    // Drop any exception left over from a command that was not waited for.
+    this.kernel?.takeLastException();
     if (hadStdinData && this.kernel) {
       this.kernel.setFdTarget(0, 0, createStaticTarget(options!.stdinData!));
     }
@@ -697,14 +976,19 @@ export class ShellInstance implements ShellLike {
     let outCap = 4096;
     let outPtr = alloc(outCap);
 
-    let needed = await runCommand(cmdPtr, cmdBytes.length, outPtr, outCap);
-
-    // If buffer was too small, reallocate and retry
-    if (needed > outCap) {
-      dealloc(outPtr, outCap);
-      outCap = needed;
-      outPtr = alloc(outCap);
+    let needed: number;
+    try {
       needed = await runCommand(cmdPtr, cmdBytes.length, outPtr, outCap);
+
+      // If buffer was too small, reallocate and retry
+      if (needed > outCap) {
+        dealloc(outPtr, outCap);
+        outCap = needed;
+        outPtr = alloc(outCap);
+        needed = await runCommand(cmdPtr, cmdBytes.length, outPtr, outCap);
+      }
+    } catch (e: unknown) {
+      throw explainTrap(this.instance, 'shell', e);
     }
 
     // Read result JSON from output buffer
@@ -786,6 +1070,7 @@ export class ShellInstance implements ShellLike {
     if (hadStdinData && this.kernel) {
       this.kernel.setFdTarget(0, 0, createNullTarget());
     }
+    const exception = this.kernel?.takeLastException();
 
     return {
       exitCode,
@@ -794,6 +1079,7 @@ export class ShellInstance implements ShellLike {
       executionTimeMs: result.execution_time_ms ?? 0,
       ...(truncated ? { truncated } : {}),
       ...(errorClass ? { errorClass } : {}),
+      ...(exception ? { exception } : {}),
     };
   }
 
@@ -806,6 +1092,133 @@ export class ShellInstance implements ShellLike {
     if (stderrTarget?.type === 'buffer') stderrTarget.onChunk = callbacks?.onStderr ?? undefined;
   }
 
+  /**
+   * Bytes the shell's heap holds now and at its peak, or null if the guest
+   * does not report them. Linear memory never shrinks, so this is a better
+   * measure of what a session is using than `memory.buffer.byteLength`.
+   */
+  heapStats(): { used: number; peak: number } | null {
+    const used = this.instance.exports.__heap_used as (() => number) | undefined;
+    const peak = this.instance.exports.__heap_peak as (() => number) | undefined;
+    if (!used || !peak) return null;
+    return { used: used() >>> 0, peak: peak() >>> 0 };
+  }
+
+  /** Measure the heap peak again from the current usage. */
+  resetHeapPeak(): void {
+    (this.instance.exports.__heap_reset_peak as (() => void) | undefined)?.();
+  }
+
+  /**
+   * Serialize the shell session (variables, functions, aliases, options,
+   * cwd, history and file-backed fds) so `restoreCheckpoint` can resume it
+   * in another instance. Returns null if the guest cannot take snapshots.
+   * Only valid between commands.
+   */
+  checkpoint(): Uint8Array | null {
+    const snapshot = this.instance.exports.__snapshot_state as
+      | ((outPtr: number, outCap: number) => number)
+      | undefined;
+    if (!snapshot) return null;
+    if (this.callsInFlight > 0) throw new Error('cannot checkpoint while a command is running');
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    let cap = 4096;
+    let ptr = alloc(cap);
+    let n = snapshot(ptr, cap);
+    if (n > cap) {
+      dealloc(ptr, cap);
+      cap = n;
+      ptr = alloc(cap);
+      n = snapshot(ptr, cap);
+    }
+    const bytes = new Uint8Array(this.memory.buffer, ptr, n).slice();
+    dealloc(ptr, cap);
+    return bytes;
+  }
+
+  /**
+   * Replace this instance's shell session with one taken by `checkpoint`,
+   * typically from another instance. Jobs and pipes of this instance are
+   * left alone.
+   */
+  restoreCheckpoint(checkpoint: Uint8Array): void {
+    const restore = this.instance.exports.__restore_state as
+      | ((ptr: number, len: number) => number)
+      | undefined;
+    if (!restore) throw new Error('WASM module does not export __restore_state');
+    if (this.callsInFlight > 0) throw new Error('cannot restore while a command is running');
+    const alloc = this.instance.exports.__alloc as (size: number) => number;
+    const dealloc = this.instance.exports.__dealloc as (ptr: number, size: number) => void;
+    const ptr = alloc(checkpoint.length);
+    new Uint8Array(this.memory.buffer, ptr, checkpoint.length).set(checkpoint);
+    const status = restore(ptr, checkpoint.length);
+    dealloc(ptr, checkpoint.length);
+    if (status !== 0) throw new Error('invalid shell checkpoint');
+
+    // The guest now holds the checkpoint's env; mirror it so the next run
+    // does not export stale values over it.
+    const { env } = JSON.parse(new TextDecoder().decode(checkpoint)) as { env?: Record<string, string> };
+    this.env = new Map(Object.entries(env ?? {}));
+    this.syncedEnv = new Map(this.env);
+  }
+
+  /**
+   * Return the shell to a fresh session without reinstantiating it, so a
+   * warm instance can serve the next user. Returns false if the guest has
+   * no __reset export and must be recreated instead.
+   */
+  reset(): boolean {
+    const reset = this.instance.exports.__reset as (() => number) | undefined;
+    if (!reset) return false;
+    if (this.callsInFlight > 0) throw new Error('cannot reset while a command is running');
+    reset();
+    this.env = new Map(DEFAULT_ENV);
+    this.syncedEnv = new Map(DEFAULT_ENV);
+    this.clearHistory();
+    this.cancelledReason = null;
+    this.deadlineMs = Infinity;
+    return true;
+  }
+
+  /**
+   * Ask the shell to copy what it writes to the terminal into output rings
+   * in its memory, which `readOutputRing` can poll while a command is still
+   * running. Returns the ring capacity, or 0 if the guest has no rings.
+   */
+  enableOutputRing(capacity = 64 * 1024): number {
+    const enable = this.instance.exports.__output_ring_enable as
+      | ((capacity: number) => number)
+      | undefined;
+    if (!enable) return 0;
+    this.outputRingCapacity = enable(capacity);
+    return this.outputRingCapacity;
+  }
+
+  /**
+   * Take the bytes written to `fd` (1 or 2) since the last read, and the
+   * running count of bytes the ring had to drop because it was full.
+   */
+  readOutputRing(fd: 1 | 2): { data: Uint8Array; dropped: number } {
+    const exports = this.instance.exports as Record<string, Function>;
+    const cap = this.outputRingCapacity;
+    const base = cap ? (exports.__output_ring_data(fd) as number) : 0;
+    if (!base) return { data: new Uint8Array(0), dropped: 0 };
+    const head = exports.__output_head(fd) as number;
+    const tail = exports.__output_tail(fd) as number;
+    const len = (head - tail) >>> 0;
+    const data = new Uint8Array(len);
+    const mem = new Uint8Array(this.memory.buffer, base, cap);
+    // Positions count bytes modulo 2^32; the capacity is a power of two, so
+    // position p lives at p % cap and the unread bytes wrap at most once.
+    const start = tail % cap;
+    const first = Math.min(len, cap - start);
+    data.set(mem.subarray(start, start + first));
+    data.set(mem.subarray(0, len - first), first);
+    exports.__output_set_tail(fd, head);
+    return { data, dropped: exports.__output_dropped(fd) as number };
+  }
+
   /** Release the WASM instance (will be GC'd). */
   destroy(): void {
     // WASM instance will be garbage collected
@@ -835,8 +1248,22 @@ function spawnAsyncProcess(
   memoryBytes?: number,
   networkBridge?: NetworkBridgeLike,
   extensionRegistry?: ExtensionRegistry,
-  runCommand?: (cmd: string, stdin: string) => Promise<{ exitCode: number; stdout: string; stderr: string }>,
+  runCommand?: RunCommandFn,
+  randomSource?: RandomSource,
+  terminal?: TerminalSize,
 ): number {
+  // A per-command budget from the shell can only tighten the sandbox limits.
+  const limits = req.limits;
+  if (limits?.max_wall_ms !== undefined) {
+    const wallDeadline = Date.now() + limits.max_wall_ms;
+    deadlineMs = deadlineMs === undefined ? wallDeadline : Math.min(deadlineMs, wallDeadline);
+  }
+  if (limits?.max_memory_bytes !== undefined) {
+    memoryBytes = memoryBytes === undefined
+      ? limits.max_memory_bytes
+      : Math.min(memoryBytes, limits.max_memory_bytes);
+  }
+
   // Tool allowlist check
   if (!mgr.isToolAllowed(req.prog)) {
     const pid = kernel.allocPid();
@@ -923,10 +1350,13 @@ function spawnAsyncProcess(
   const host = new WasiHost({
     vfs: mgr.getVfs(),
     args: [req.prog, ...req.args],
-    env: Object.fromEntries(req.env),
+    // WASI has no working directory; guests take theirs from PWD.
+    env: { ...Object.fromEntries(req.env), PWD: req.cwd },
     preopens: { '/': '/' },
     ioFds: fdTable,
     deadlineMs,
+    outputLimitBytes: limits?.max_output_bytes,
+    randomSource,
   });
 
   const imports = host.getImports() as WebAssembly.Imports & Record<string, Record<string, unknown>>;
@@ -1009,7 +1439,9 @@ function spawnAsyncProcess(
       extensionRegistry,
       nativeModules: mgr.nativeModules,
       runCommand,
-      spawnProcess: (req2, fdTable2) => spawnAsyncProcess(req2, fdTable2, mgr, kernel, adapter, deadlineMs, memoryBytes, networkBridge, extensionRegistry, runCommand),
+      shouldCancel: () => (deadlineMs !== undefined && Date.now() > deadlineMs ? 2 : 0),
+      terminal,
+      spawnProcess: (req2, fdTable2) => spawnAsyncProcess(req2, fdTable2, mgr, kernel, adapter, deadlineMs, memoryBytes, networkBridge, extensionRegistry, runCommand, randomSource, terminal),
     });
     imports.codepod = childKernelImports as unknown as Record<string, WebAssembly.ImportValue>;
 
@@ -1021,6 +1453,9 @@ function spawnAsyncProcess(
       imports.codepod.host_waitpid = new WebAssembly.Suspending(
         childKernelImports.host_waitpid as (...args: number[]) => Promise<number>,
       ) as unknown as WebAssembly.ImportValue;
+      imports.codepod.host_waitpid_timeout = new WebAssembly.Suspending(
+        childKernelImports.host_waitpid_timeout as (...args: number[]) => Promise<number>,
+      ) as unknown as WebAssembly.ImportValue;
       imports.codepod.host_yield = new WebAssembly.Suspending(
         childKernelImports.host_yield as () => Promise<void>,
       ) as unknown as WebAssembly.ImportValue;
@@ -1031,15 +1466,20 @@ function spawnAsyncProcess(
       imports.codepod.host_extension_invoke = new WebAssembly.Suspending(
         childKernelImports.host_extension_invoke as (...args: number[]) => Promise<number>,
       ) as unknown as WebAssembly.ImportValue;
-      // Python uses host_run_command for _codepod.spawn() / subprocess support
+      // Python uses host_rpc (older builds host_run_command) for
+      // _codepod.spawn() / subprocess support
       imports.codepod.host_run_command = new WebAssembly.Suspending(
         childKernelImports.host_run_command as (...args: number[]) => Promise<number>,
       ) as unknown as WebAssembly.ImportValue;
+      imports.codepod.host_rpc = new WebAssembly.Suspending(
+        childKernelImports.host_rpc as (...args: number[]) => Promise<number>,
+      ) as unknown as WebAssembly.ImportValue;
     }
 
     // Start the process asynchronously
     adapter.instantiate(module, imports).then((instance) => {
       if (checkMemLimit(instance)) return;
+      checkGuestAbi(instance, req.prog);
       childMemRef = instance.exports.memory as WebAssembly.Memory;
       host.setMemory(childMemRef);
 
@@ -1048,13 +1488,20 @@ function spawnAsyncProcess(
         startFn = (WebAssembly as any).promising(startFn);
       }
 
-      const promise = Promise.resolve().then(() => startFn()).catch(() => {});
+      const promise = Promise.resolve().then(() => startFn()).catch(() => {})
+        .then(() => {
+          // Python reports the exception a script died of; keep it for
+          // the shell's RunResult.
+          const exception = host.getExitCode() ? readGuestException(instance) : null;
+          if (exception) kernel.recordException(exception);
+        });
       kernel.attachProcess(pid, promise, host);
     }).catch(handleInstantiationError);
   } else {
     // Module doesn't need codepod imports — simpler path
     adapter.instantiate(module, imports).then((instance) => {
       if (checkMemLimit(instance)) return;
+      checkGuestAbi(instance, req.prog);
       host.setMemory(instance.exports.memory as WebAssembly.Memory);
 
       let startFn = instance.exports._start as Function;
@@ -29,6 +29,10 @@ export interface ShellLike {
   getHistory(): HistoryEntry[];
   clearHistory(): void;
 
+  // Session checkpoints: the whole shell session as an opaque buffer.
+  checkpoint?(): Uint8Array | null;
+  restoreCheckpoint?(checkpoint: Uint8Array): void;
+
   // Lifecycle
   cancel(reason: string): void;
   setDeadlineNow(): void;
@@ -3,6 +3,7 @@
  */
 
 import type { ErrorClass } from '../security.js';
+import type { GuestException } from '../guest-abi.js';
 
 // ---- Result types ----
 
@@ -13,6 +14,8 @@ export interface RunResult {
   executionTimeMs: number;
   truncated?: { stdout: boolean; stderr: boolean };
   errorClass?: ErrorClass;
+  /** The uncaught exception a Python script in the command died of. */
+  exception?: GuestException;
 }
 
 export const EMPTY_RESULT: RunResult = {
@@ -84,6 +84,16 @@ describe('VFS', () => {
     vfs.writeFile('/home/user/file.txt', new Uint8Array());
     expect(() => vfs.mkdir('/home/user/file.txt/sub')).toThrow(/ENOTDIR/);
   });
+
+  it('sets access and modification times', () => {
+    const vfs = new VFS();
+    vfs.writeFile('/home/user/test.txt', new Uint8Array());
+    vfs.utimes('/home/user/test.txt', new Date(1_000), new Date(2_000));
+    const s = vfs.stat('/home/user/test.txt');
+    expect(s.atime.getTime()).toBe(1_000);
+    expect(s.mtime.getTime()).toBe(2_000);
+    expect(() => vfs.utimes('/home/user/missing', new Date(), new Date())).toThrow(/ENOENT/);
+  });
 });
 
 describe('VFS symlinks', () => {
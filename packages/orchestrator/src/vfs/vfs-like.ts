@@ -20,5 +20,6 @@ export interface VfsLike {
   symlink(target: string, path: string): void;
   readlink(path: string): string;
   chmod(path: string, mode: number): void;
+  utimes(path: string, atime: Date, mtime: Date): void;
   withWriteAccess(fn: () => void): void;
 }
@@ -607,6 +607,17 @@ export class VFS {
     this.notifyChange();
   }
 
+  /** Set the access and modification times of `path` (following symlinks). */
+  utimes(path: string, atime: Date, mtime: Date): void {
+    const { parent } = this.resolveParent(path);
+    this.assertWritePermission(parent);
+    const inode = this.resolve(path);
+    inode.metadata.atime = atime;
+    inode.metadata.mtime = mtime;
+    inode.metadata.ctime = new Date();
+    this.notifyChange();
+  }
+
   readlink(path: string): string {
     const inode = this.resolve(path, false);
 
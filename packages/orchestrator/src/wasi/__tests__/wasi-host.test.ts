@@ -1,9 +1,10 @@
 import { describe, it, beforeEach } from '@std/testing/bdd';
 import { expect } from '@std/expect';
-import { WasiHost } from '../wasi-host.js';
+import { WasiExitError, WasiHost, seededRandomSource } from '../wasi-host.js';
 import { VFS } from '../../vfs/vfs.js';
 import {
   WASI_EBADF,
+  WASI_EINVAL,
   WASI_ENOENT,
   WASI_ENOSYS,
   WASI_ESUCCESS,
@@ -145,6 +146,55 @@ describe('WasiHost', () => {
     });
   });
 
+  describe('output limit', () => {
+    it('cuts the crossing write short and exits 153', () => {
+      host = new WasiHost({
+        vfs,
+        args: ['program'],
+        env: {},
+        preopens: { '/': '/' },
+        outputLimitBytes: 8,
+      });
+      host.setMemory(memory);
+      const { wasi, view, bytes } = getImportsAndView(host, memory);
+
+      bytes.set(new TextEncoder().encode('hello'), 200);
+      view.setUint32(100, 200, true);
+      view.setUint32(104, 5, true);
+
+      expect(wasi.fd_write(1, 100, 1, 300)).toBe(WASI_ESUCCESS);
+      // stderr shares the budget: 3 bytes remain.
+      expect(() => wasi.fd_write(2, 100, 1, 300)).toThrow(WasiExitError);
+      expect(host.getExitCode()).toBe(153);
+      expect(host.getStdout()).toBe('hello');
+      expect(host.getStderr()).toBe('hel');
+    });
+
+    it('does not count writes to files', () => {
+      host = new WasiHost({
+        vfs,
+        args: ['program'],
+        env: {},
+        preopens: { '/': '/' },
+        outputLimitBytes: 0,
+      });
+      host.setMemory(memory);
+      const { wasi, view, bytes } = getImportsAndView(host, memory);
+
+      const pathStr = 'tmp/out.txt';
+      bytes.set(new TextEncoder().encode(pathStr), 500);
+      expect(wasi.path_open(3, 0, 500, pathStr.length, 0x09, BigInt(0), BigInt(0), 0, 400))
+        .toBe(WASI_ESUCCESS);
+      const fd = view.getUint32(400, true);
+
+      bytes.set(new TextEncoder().encode('data'), 200);
+      view.setUint32(100, 200, true);
+      view.setUint32(104, 4, true);
+      expect(wasi.fd_write(fd, 100, 1, 300)).toBe(WASI_ESUCCESS);
+      expect(host.getExitCode()).toBe(null);
+    });
+  });
+
   describe('fd_write to file', () => {
     it('writes data to a VFS file via FdTable', () => {
       const { wasi, view, bytes } = getImportsAndView(host, memory);
@@ -262,6 +312,47 @@ describe('WasiHost', () => {
       const filled = bytes.slice(100, 116);
       expect(filled.some(b => b !== 0)).toBe(true);
     });
+
+    it('draws from a host-supplied random source', () => {
+      const seeded = new WasiHost({
+        vfs,
+        args: ['program'],
+        env: {},
+        preopens: { '/': '/' },
+        randomSource: (buf) => buf.forEach((_, i) => { buf[i] = i + 1; }),
+      });
+      seeded.setMemory(memory);
+      const { wasi, bytes } = getImportsAndView(seeded, memory);
+      expect(wasi.random_get(100, 4)).toBe(WASI_ESUCCESS);
+      expect(Array.from(bytes.slice(100, 104))).toEqual([1, 2, 3, 4]);
+    });
+  });
+
+  describe('seededRandomSource', () => {
+    const draw = (seed: number, n: number) => {
+      const buf = new Uint8Array(n);
+      seededRandomSource(seed)(buf);
+      return Array.from(buf);
+    };
+
+    it('gives the same bytes for the same seed', () => {
+      expect(draw(42, 37)).toEqual(draw(42, 37));
+      expect(draw(42, 37).some((b) => b !== 0)).toBe(true);
+    });
+
+    it('gives different bytes for different seeds, including large ones', () => {
+      expect(draw(42, 16)).not.toEqual(draw(43, 16));
+      expect(draw(2 ** 40, 16)).not.toEqual(draw(0, 16));
+    });
+
+    it('continues the stream across calls', () => {
+      const source = seededRandomSource(7);
+      const a = new Uint8Array(8);
+      const b = new Uint8Array(8);
+      source(a);
+      source(b);
+      expect(Array.from(b)).not.toEqual(Array.from(a));
+    });
   });
 
   describe('proc_exit', () => {
@@ -458,6 +549,39 @@ describe('WasiHost', () => {
     });
   });
 
+  describe('path_filestat_set_times', () => {
+    const ATIM = 1, ATIM_NOW = 2, MTIM = 4;
+
+    it('sets the given times and leaves unflagged ones alone', () => {
+      vfs.writeFile('/tmp/touch-me.txt', new Uint8Array());
+      vfs.utimes('/tmp/touch-me.txt', new Date(1_000), new Date(2_000));
+      const { wasi, bytes } = getImportsAndView(host, memory);
+
+      const pathStr = 'tmp/touch-me.txt';
+      bytes.set(new TextEncoder().encode(pathStr), 500);
+
+      const mtim = BigInt(5_000) * BigInt(1_000_000);
+      const errno = wasi.path_filestat_set_times(3, 1, 500, pathStr.length, BigInt(0), mtim, MTIM);
+      expect(errno).toBe(WASI_ESUCCESS);
+      const stat = vfs.stat('/tmp/touch-me.txt');
+      expect(stat.atime.getTime()).toBe(1_000);
+      expect(stat.mtime.getTime()).toBe(5_000);
+    });
+
+    it('rejects a time that is both given and now', () => {
+      vfs.writeFile('/tmp/touch-me.txt', new Uint8Array());
+      const { wasi, bytes } = getImportsAndView(host, memory);
+
+      const pathStr = 'tmp/touch-me.txt';
+      bytes.set(new TextEncoder().encode(pathStr), 500);
+
+      const errno = wasi.path_filestat_set_times(
+        3, 1, 500, pathStr.length, BigInt(0), BigInt(0), ATIM | ATIM_NOW,
+      );
+      expect(errno).toBe(WASI_EINVAL);
+    });
+  });
+
   describe('fd_readdir', () => {
     it('lists directory entries', () => {
       vfs.writeFile('/home/user/a.txt', new Uint8Array(0));
@@ -111,6 +111,12 @@ export const WASI_OFLAGS_DIRECTORY = 2;
 export const WASI_OFLAGS_EXCL = 4;
 export const WASI_OFLAGS_TRUNC = 8;
 
+// Timestamp flags (fd_filestat_set_times / path_filestat_set_times)
+export const WASI_FSTFLAGS_ATIM = 1;
+export const WASI_FSTFLAGS_ATIM_NOW = 2;
+export const WASI_FSTFLAGS_MTIM = 4;
+export const WASI_FSTFLAGS_MTIM_NOW = 8;
+
 // Whence
 export const WASI_WHENCE_SET = 0;
 export const WASI_WHENCE_CUR = 1;
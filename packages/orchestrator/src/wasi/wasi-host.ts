@@ -11,11 +11,13 @@ import type { OpenMode, SeekWhence } from '../vfs/fd-table.js';
 import { VfsError } from '../vfs/inode.js';
 import type { InodeType } from '../vfs/inode.js';
 import type { VfsLike } from '../vfs/vfs-like.js';
+import { explainTrap } from '../guest-abi.js';
 import { fdErrorToWasi, vfsErrnoToWasi } from './errors.js';
 import type { FdTarget } from './fd-target.js';
 import { createBufferTarget, createStaticTarget, createNullTarget, bufferToString } from './fd-target.js';
 import {
   WASI_EBADF,
+  WASI_EEXIST,
   WASI_EINVAL,
   WASI_ENOSYS,
   WASI_ENOTSUP,
@@ -28,8 +30,13 @@ import {
   WASI_FILETYPE_DIRECTORY,
   WASI_FILETYPE_REGULAR_FILE,
   WASI_FILETYPE_SYMBOLIC_LINK,
+  WASI_FSTFLAGS_ATIM,
+  WASI_FSTFLAGS_ATIM_NOW,
+  WASI_FSTFLAGS_MTIM,
+  WASI_FSTFLAGS_MTIM_NOW,
   WASI_OFLAGS_CREAT,
   WASI_OFLAGS_DIRECTORY,
+  WASI_OFLAGS_EXCL,
   WASI_OFLAGS_TRUNC,
   WASI_PREOPENTYPE_DIR,
   WASI_RIGHTS_ALL,
@@ -62,8 +69,54 @@ export interface WasiHostOptions {
   stdoutLimit?: number;
   stderrLimit?: number;
   deadlineMs?: number;
+  /** Stdout and stderr together may carry this many bytes; the write that
+   *  crosses it is cut short and the guest exits 153 (`128 + SIGXFSZ`). */
+  outputLimitBytes?: number;
   /** Per-fd I/O targets. If provided, overrides stdin/stdoutLimit/stderrLimit. */
   ioFds?: Map<number, FdTarget>;
+  /** Fills `random_get` buffers. Defaults to `crypto.getRandomValues`; pass
+   *  a seeded generator for reproducible runs. */
+  randomSource?: RandomSource;
+}
+
+/** Fills `buf` with random bytes. */
+export type RandomSource = (buf: Uint8Array) => void;
+
+/**
+ * A RandomSource that yields the same bytes for the same `seed`, for
+ * reproducible runs. xoshiro128**, with its state expanded from the seed by
+ * SplitMix32. Not for secrets: anyone who knows the seed knows the bytes.
+ */
+export function seededRandomSource(seed: number): RandomSource {
+  let mix = (seed >>> 0) ^ Math.floor(seed / 0x1_0000_0000);
+  const splitmix32 = (): number => {
+    mix = (mix + 0x9e3779b9) | 0;
+    let z = mix;
+    z = Math.imul(z ^ (z >>> 16), 0x85ebca6b);
+    z = Math.imul(z ^ (z >>> 13), 0xc2b2ae35);
+    return (z ^ (z >>> 16)) >>> 0;
+  };
+  let s0 = splitmix32(), s1 = splitmix32(), s2 = splitmix32(), s3 = splitmix32();
+  const rotl = (x: number, k: number) => (x << k) | (x >>> (32 - k));
+  const next = (): number => {
+    const result = Math.imul(rotl(Math.imul(s1, 5), 7), 9) >>> 0;
+    const t = s1 << 9;
+    s2 ^= s0;
+    s3 ^= s1;
+    s1 ^= s2;
+    s0 ^= s3;
+    s2 ^= t;
+    s3 = rotl(s3, 11);
+    return result;
+  };
+  return (buf) => {
+    for (let i = 0; i < buf.length; i += 4) {
+      const word = next();
+      for (let j = 0; j < 4 && i + j < buf.length; j++) {
+        buf[i + j] = (word >>> (8 * j)) & 0xff;
+      }
+    }
+  };
 }
 
 interface PreopenEntry {
@@ -136,6 +189,9 @@ export class WasiHost {
 
   private cancelled = false;
   private deadlineMs: number = Infinity;
+  /** Bytes stdout and stderr may still carry before the guest is killed. */
+  private outputBudget: number = Infinity;
+  private randomSource: RandomSource;
 
   constructor(options: WasiHostOptions) {
     this.vfs = options.vfs;
@@ -145,6 +201,8 @@ export class WasiHost {
       ([k, v]) => `${k}=${v}`,
     );
     this.deadlineMs = options.deadlineMs ?? Infinity;
+    this.outputBudget = options.outputLimitBytes ?? Infinity;
+    this.randomSource = options.randomSource ?? ((buf) => crypto.getRandomValues(buf));
     this.preopens = [];
 
     // Build I/O fd table: use provided ioFds or build from legacy options.
@@ -240,6 +298,7 @@ export class WasiHost {
   /** Throw WasiExitError(124) if cancelled or past deadline. */
   private checkDeadline(): void {
     if (this.cancelled || Date.now() > this.deadlineMs) {
+      this.exitCode = 124;
       throw new WasiExitError(124);
     }
   }
@@ -279,7 +338,7 @@ export class WasiHost {
           return 141;
         }
       }
-      throw e;
+      throw explainTrap(instance, this.args[0] ?? 'wasm guest', e);
     }
   }
 
@@ -311,7 +370,9 @@ export class WasiHost {
         random_get: this.randomGet.bind(this),
         proc_exit: this.procExit.bind(this),
         sched_yield: this.schedYield.bind(this),
-        // Safe no-op stubs (single-threaded sandbox — sync/timestamps/flags are harmless to skip)
+        fd_filestat_set_times: this.fdFilestatSetTimes.bind(this),
+        path_filestat_set_times: this.pathFilestatSetTimes.bind(this),
+        // Safe no-op stubs (single-threaded sandbox — sync/flags are harmless to skip)
         fd_advise: this.fdNoOp.bind(this),
         fd_allocate: this.fdNoOp.bind(this),
         fd_datasync: this.fdNoOp.bind(this),
@@ -319,8 +380,6 @@ export class WasiHost {
         fd_fdstat_set_flags: this.fdNoOp.bind(this),
         fd_fdstat_set_rights: this.fdNoOp.bind(this),
         fd_filestat_set_size: this.fdFilestatSetSize.bind(this),
-        fd_filestat_set_times: this.fdNoOp.bind(this),
-        path_filestat_set_times: this.fdNoOp.bind(this),
         fd_pread: this.fdPread.bind(this),
         fd_pwrite: this.fdPwrite.bind(this),
         // Stubs that must remain ENOSYS (masking bugs or unimplemented semantics)
@@ -448,8 +507,12 @@ export class WasiHost {
     let totalWritten = 0;
     const target = this.ioFds.get(fd);
 
+    const counted = fd === 1 || fd === 2;
     for (const iov of iovecs) {
-      const data = bytes.slice(iov.buf, iov.buf + iov.len);
+      let data = bytes.slice(iov.buf, iov.buf + iov.len);
+      const overBudget = counted && data.byteLength > this.outputBudget;
+      if (overBudget) data = data.slice(0, this.outputBudget);
+      if (counted) this.outputBudget -= data.byteLength;
 
       if (target) {
         switch (target.type) {
@@ -499,6 +562,11 @@ export class WasiHost {
           return fdErrorToWasi(err);
         }
       }
+
+      if (overBudget) {
+        this.exitCode = 153;
+        throw new WasiExitError(153);
+      }
     }
 
     // Re-fetch view in case writes caused memory growth
@@ -876,8 +944,21 @@ export class WasiHost {
       const wantCreate = (oflags & WASI_OFLAGS_CREAT) !== 0;
       const wantTrunc = (oflags & WASI_OFLAGS_TRUNC) !== 0;
       const wantDir = (oflags & WASI_OFLAGS_DIRECTORY) !== 0;
+      const wantExcl = (oflags & WASI_OFLAGS_EXCL) !== 0;
       const wantAppend = (fdflags & WASI_FDFLAGS_APPEND) !== 0;
 
+      // O_CREAT|O_EXCL: fail if the path exists. Nothing else runs between
+      // this check and the create below, so it is exclusive.
+      if (wantCreate && wantExcl) {
+        let exists = true;
+        try {
+          this.vfs.stat(absPath);
+        } catch {
+          exists = false;
+        }
+        if (exists) return WASI_EEXIST;
+      }
+
       // If opening a directory, just register it and return
       if (wantDir) {
         // Verify the path is actually a directory
@@ -1101,7 +1182,7 @@ export class WasiHost {
     this.checkDeadline();
     const bytes = this.getBytes();
     const target = bytes.subarray(bufPtr, bufPtr + bufLen);
-    crypto.getRandomValues(target);
+    this.randomSource(target);
     return WASI_ESUCCESS;
   }
 
@@ -1185,6 +1266,57 @@ export class WasiHost {
     }
   }
 
+  /** fd_filestat_set_times — futimens. */
+  private fdFilestatSetTimes(fd: number, atim: bigint, mtim: bigint, fstFlags: number): number {
+    this.checkDeadline();
+    const path = this.dirFds.get(fd) ?? this.fdTable.getPath(fd);
+    if (path === undefined) return WASI_EBADF;
+    return this.setTimes(path, atim, mtim, fstFlags);
+  }
+
+  /** path_filestat_set_times — utimensat (os.utime, touch). */
+  private pathFilestatSetTimes(
+    dirFd: number,
+    _flags: number,
+    pathPtr: number,
+    pathLen: number,
+    atim: bigint,
+    mtim: bigint,
+    fstFlags: number,
+  ): number {
+    this.checkDeadline();
+    try {
+      const absPath = this.resolvePath(dirFd, this.readString(pathPtr, pathLen));
+      return this.setTimes(absPath, atim, mtim, fstFlags);
+    } catch (err) {
+      return fdErrorToWasi(err);
+    }
+  }
+
+  /** Apply WASI timestamps (nanoseconds) to `absPath`; a time whose flags
+   *  are both clear is left as it is. */
+  private setTimes(absPath: string, atim: bigint, mtim: bigint, fstFlags: number): number {
+    const pick = (set: number, now: number, nanos: bigint, current: Date): Date | null => {
+      if ((fstFlags & set) && (fstFlags & now)) return null;
+      if (fstFlags & now) return new Date();
+      if (fstFlags & set) return new Date(Number(nanos / BigInt(1_000_000)));
+      return current;
+    };
+    try {
+      const stat = this.vfs.stat(absPath);
+      const atime = pick(WASI_FSTFLAGS_ATIM, WASI_FSTFLAGS_ATIM_NOW, atim, stat.atime);
+      const mtime = pick(WASI_FSTFLAGS_MTIM, WASI_FSTFLAGS_MTIM_NOW, mtim, stat.mtime);
+      if (atime === null || mtime === null) return WASI_EINVAL;
+      this.vfs.utimes(absPath, atime, mtime);
+      return WASI_ESUCCESS;
+    } catch (err) {
+      if (err instanceof VfsError) {
+        return vfsErrnoToWasi(err.errno);
+      }
+      return fdErrorToWasi(err);
+    }
+  }
+
   private clockResGet(clockId: number, resPtr: number): number {
     const view = this.getView();
     switch (clockId) {
@@ -9,6 +9,9 @@ class CommandResult:
     execution_time_ms: float
     truncated: dict[str, bool] | None = None
     error_class: str | None = None
+    # Uncaught Python exception the command died of: type, message,
+    # traceback and frames (file, line, column, name, source).
+    exception: dict | None = None
 
 
 @dataclass
@@ -41,4 +41,5 @@ class Commands:
             execution_time_ms=result["executionTimeMs"],
             truncated=result.get("truncated"),
             error_class=result.get("errorClass"),
+            exception=result.get("exception"),
         )
@@ -122,6 +122,10 @@ class Sandbox:
         extensions: List of :class:`~codepod.Extension` instances to register.
         nice: CPU scheduling priority, 0–19. 0 = default (10ms quantum),
             19 = lowest priority (1ms quantum). Wasmtime only; ignored on deno.
+        random_seed: Seed for all randomness in the sandbox (Python's
+            ``random`` and ``secrets``, ``$RANDOM``, ``mktemp``, ``shuf``), so
+            the same commands give the same output. Makes ``secrets``
+            predictable; leave unset outside grading and tests. Deno only.
     """
 
     def __init__(
@@ -135,6 +139,7 @@ class Sandbox:
         python_path: list[str] | None = None,
         extensions: list[Extension] | None = None,
         storage: "dict[str, Callable] | None" = None,
+        random_seed: int | None = None,
         _sandbox_id: str | None = None,
         _client: RpcClient | None = None,
     ):
@@ -182,6 +187,9 @@ class Sandbox:
         if python_path:
             create_params["pythonPath"] = python_path
 
+        if random_seed is not None:
+            create_params["randomSeed"] = random_seed
+
         # Serialize extensions for RPC and register callback handlers
         if extensions:
             ext_specs = []
@@ -0,0 +1,139 @@
+//! Host calls through the `python-guest` world in
+//! `codepod-rpc/wit/sandbox.wit`, for a component build of the interpreter.
+//!
+//! `_codepod.fetch` goes through the world's `fetch` import. The world has
+//! no extension, native-module, socket or `codepod_rpc` calls yet, so with
+//! the `component` feature the functions below stand in for those `codepod`
+//! imports and fail with the host's "unsupported" code; the `_codepod`
+//! functions built on them raise as they do on a host that refuses them.
+
+use codepod::sandbox::fetch;
+
+wit_bindgen::generate!({
+    path: "../../../codepod-rpc/wit",
+    world: "python-guest",
+});
+
+/// The `codepod` imports' return code for a call the host does not offer.
+const UNSUPPORTED: i32 = -5;
+
+/// Make the fetch described by `request_json` (as built for
+/// `host_network_fetch`) and describe the response in the same JSON the
+/// `codepod` host answers with.
+pub(crate) fn fetch(request_json: &str) -> Result<String, String> {
+    let request: serde_json::Value =
+        serde_json::from_str(request_json).map_err(|e| format!("invalid request: {e}"))?;
+    let headers = request["headers"]
+        .as_object()
+        .map(|headers| {
+            headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string);
+                    (name.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let body = match &request["body"] {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(body) => Some(body.clone().into_bytes()),
+        other => Some(other.to_string().into_bytes()),
+    };
+    let request = fetch::Request {
+        method: request["method"].as_str().unwrap_or("GET").to_string(),
+        url: request["url"].as_str().unwrap_or_default().to_string(),
+        headers,
+        body,
+    };
+    let response = match fetch::fetch(&request) {
+        Ok(response) => {
+            let headers: serde_json::Map<String, serde_json::Value> = response
+                .headers
+                .into_iter()
+                .map(|(name, value)| (name, value.into()))
+                .collect();
+            serde_json::json!({
+                "ok": (200..400).contains(&response.status),
+                "status": response.status,
+                "headers": headers,
+                "body": String::from_utf8_lossy(&response.body),
+                "body_base64": crate::base64_encode(&response.body),
+                "error": null,
+            })
+        }
+        Err(reason) => serde_json::json!({
+            "ok": false,
+            "status": 0,
+            "headers": {},
+            "body": "",
+            "error": reason,
+        }),
+    };
+    Ok(response.to_string())
+}
+
+pub(crate) unsafe extern "C" fn host_extension_invoke(
+    _req_ptr: *const u8,
+    _req_len: u32,
+    _out_ptr: *mut u8,
+    _out_cap: u32,
+) -> i32 {
+    UNSUPPORTED
+}
+
+pub(crate) unsafe extern "C" fn host_socket_connect(
+    _req_ptr: *const u8,
+    _req_len: u32,
+    _out_ptr: *mut u8,
+    _out_cap: u32,
+) -> i32 {
+    UNSUPPORTED
+}
+
+pub(crate) unsafe extern "C" fn host_socket_send(
+    _req_ptr: *const u8,
+    _req_len: u32,
+    _out_ptr: *mut u8,
+    _out_cap: u32,
+) -> i32 {
+    UNSUPPORTED
+}
+
+pub(crate) unsafe extern "C" fn host_socket_recv(
+    _req_ptr: *const u8,
+    _req_len: u32,
+    _out_ptr: *mut u8,
+    _out_cap: u32,
+) -> i32 {
+    UNSUPPORTED
+}
+
+pub(crate) unsafe extern "C" fn host_socket_close(_req_ptr: *const u8, _req_len: u32) -> i32 {
+    UNSUPPORTED
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe extern "C" fn host_native_invoke(
+    _module_ptr: *const u8,
+    _module_len: u32,
+    _method_ptr: *const u8,
+    _method_len: u32,
+    _args_ptr: *const u8,
+    _args_len: u32,
+    _out_ptr: *mut u8,
+    _out_cap: u32,
+) -> i32 {
+    UNSUPPORTED
+}
+
+pub(crate) unsafe extern "C" fn host_rpc(
+    _req_ptr: *const u8,
+    _req_len: u32,
+    _out_ptr: *mut u8,
+    _out_cap: u32,
+) -> i32 {
+    UNSUPPORTED
+}
@@ -8,6 +8,14 @@
 //! - `_codepod.fetch(method, url, headers=None, body=None)` -> dict
 //! - `_codepod.extension_call(extension, method, **kwargs)` -> result
 //! - `_codepod.extension_call(extension, method, **kwargs)` -> result (also checks existence)
+//! - `_codepod.limit_hook(frame, event, arg)` -> profile hook enforcing [`limits`]
+//! - `_codepod.poll(readers, writers, timeout=None)` -> ready fds, via [`poll`]
+//! - `_codepod.report_exception(report)` -> keeps an uncaught exception for the host
+
+#[cfg(all(target_arch = "wasm32", feature = "component"))]
+mod component;
+pub mod limits;
+mod poll;
 
 use rustpython_vm as vm;
 use vm::AsObject;
@@ -16,7 +24,7 @@ use vm::AsObject;
 // WASM host imports — provided by the TypeScript host at instantiation time
 // ---------------------------------------------------------------------------
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", not(feature = "component")))]
 #[link(wasm_import_module = "codepod")]
 extern "C" {
     /// Fetch a URL. Request is JSON, response is JSON.
@@ -53,10 +61,17 @@ extern "C" {
         out_ptr: *mut u8, out_cap: u32,
     ) -> i32;
 
-    /// Run a shell command and collect output (for Python subprocess).
-    fn host_run_command(req_ptr: *const u8, req_len: u32, out_ptr: *mut u8, out_cap: u32) -> i32;
+    /// Answer a `codepod_rpc` request frame with a response frame. Used for
+    /// `shell.run` (Python subprocess) and `shell.run_all` (multiprocessing).
+    fn host_rpc(req_ptr: *const u8, req_len: u32, out_ptr: *mut u8, out_cap: u32) -> i32;
 }
 
+#[cfg(all(target_arch = "wasm32", feature = "component"))]
+use component::{
+    host_extension_invoke, host_native_invoke, host_rpc, host_socket_close, host_socket_connect,
+    host_socket_recv, host_socket_send,
+};
+
 // ---------------------------------------------------------------------------
 // Helper: call a host import with JSON request string, get JSON response string
 // ---------------------------------------------------------------------------
@@ -106,6 +121,28 @@ fn call_host_json(import_fn: HostJsonFn, request: &str) -> Result<String, String
     String::from_utf8(out_buf).map_err(|e| format!("invalid UTF-8 in response: {}", e))
 }
 
+/// Make a `codepod_rpc` call to the host through `host_rpc`. Unlike
+/// `call_host_json`, a retry after a too-small buffer does not repeat the
+/// call on the host side.
+#[cfg(target_arch = "wasm32")]
+fn call_host_rpc(request: &codepod_rpc::Request) -> Result<serde_json::Value, codepod_rpc::RpcError> {
+    codepod_rpc::call(request, |frame, out| unsafe {
+        host_rpc(
+            frame.as_ptr(),
+            frame.len() as u32,
+            out.as_mut_ptr(),
+            out.len() as u32,
+        )
+    })
+}
+
+/// Ids for requests made with `call_host_rpc`.
+fn next_request_id() -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 // ---------------------------------------------------------------------------
 // JSON helpers — minimal manual builders to avoid serde dependency
 // ---------------------------------------------------------------------------
@@ -301,6 +338,40 @@ fn base64_decode(s: &str) -> Result<Vec<u8>, String> {
     Ok(out)
 }
 
+/// Ask the running script to stop. Backs the interpreter's
+/// `__request_cancel` export.
+pub fn request_cancel() {
+    codepod_rpc::cancel::request();
+}
+
+/// The JSON report of the script's uncaught exception, if it died of one.
+static EXCEPTION_REPORT: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// The uncaught exception `_codepod.report_exception` recorded, as JSON.
+/// Backs the interpreter's `__exception_info` export.
+pub fn exception_report() -> Option<String> {
+    EXCEPTION_REPORT.lock().unwrap().clone()
+}
+
+/// Forget the recorded exception before the instance runs again.
+pub fn clear_exception_report() {
+    *EXCEPTION_REPORT.lock().unwrap() = None;
+}
+
+/// Raise KeyboardInterrupt if the host asked the script to stop, or the
+/// script's [`limits`] error if it is over one. Every `_codepod` call
+/// starts here, so a script blocked on the host, or looping around host
+/// calls, is interrupted at its next one.
+fn check_cancel(py_vm: &vm::VirtualMachine) -> vm::PyResult<()> {
+    if codepod_rpc::cancel::clear() {
+        return Err(py_vm.new_exception_msg(
+            py_vm.ctx.exceptions.keyboard_interrupt.to_owned(),
+            "interrupted by host".to_owned(),
+        ));
+    }
+    limits::check(py_vm)
+}
+
 // ---------------------------------------------------------------------------
 // Python module: _codepod
 // ---------------------------------------------------------------------------
@@ -326,6 +397,7 @@ pub mod _codepod {
         body: vm::function::OptionalArg<vm::PyObjectRef>,
         py_vm: &VirtualMachine,
     ) -> PyResult<vm::PyObjectRef> {
+        check_cancel(py_vm)?;
         // Build headers JSON
         let headers_json = match headers {
             vm::function::OptionalArg::Present(ref h) if !py_vm.is_none(h) => py_to_json(h, py_vm),
@@ -348,7 +420,11 @@ pub mod _codepod {
 
         #[cfg(target_arch = "wasm32")]
         {
-            let response_str = call_host_json(host_network_fetch, &request_json).map_err(|e| {
+            #[cfg(not(feature = "component"))]
+            let response = call_host_json(host_network_fetch, &request_json);
+            #[cfg(feature = "component")]
+            let response = component::fetch(&request_json);
+            let response_str = response.map_err(|e| {
                 py_vm.new_exception_msg(
                     py_vm.ctx.exceptions.runtime_error.to_owned(),
                     format!("fetch failed: {}", e),
@@ -379,6 +455,7 @@ pub mod _codepod {
         kwargs: vm::function::KwArgs,
         py_vm: &VirtualMachine,
     ) -> PyResult<vm::PyObjectRef> {
+        check_cancel(py_vm)?;
         // Build kwargs JSON object
         let mut kw_parts = Vec::new();
         for (key, value) in kwargs.into_iter() {
@@ -433,6 +510,7 @@ pub mod _codepod {
         args_json: vm::builtins::PyStrRef,
         py_vm: &VirtualMachine,
     ) -> PyResult<vm::PyObjectRef> {
+        check_cancel(py_vm)?;
         #[cfg(target_arch = "wasm32")]
         {
             let module_bytes = module.as_str().as_bytes();
@@ -503,43 +581,62 @@ pub mod _codepod {
 
     /// Run a shell command and capture its output.
     ///
-    /// Usage: `_codepod.spawn(cmd, stdin='') -> dict`
+    /// Usage: `_codepod.spawn(cmd, stdin='', cwd=None, env=None) -> dict`
     ///
     /// Returns a dict: `{"exit_code": int, "stdout": str, "stderr": str}`
     ///
-    /// `cmd` is executed by the sandbox shell (same as running it in bash).
+    /// `cmd` is executed by the sandbox shell (same as running it in bash),
+    /// in `cwd` when given and with the `env` dict exported.
     /// On non-WASM platforms, always raises RuntimeError.
     #[pyfunction]
     fn spawn(
         cmd: vm::builtins::PyStrRef,
         stdin: vm::function::OptionalArg<vm::builtins::PyStrRef>,
+        cwd: vm::function::OptionalArg<vm::PyObjectRef>,
+        env: vm::function::OptionalArg<vm::PyObjectRef>,
         py_vm: &VirtualMachine,
     ) -> PyResult<vm::PyObjectRef> {
+        check_cancel(py_vm)?;
         let stdin_str = match stdin {
             vm::function::OptionalArg::Present(ref s) => s.as_str().to_owned(),
             vm::function::OptionalArg::Missing => String::new(),
         };
-        let request_json = format!(
-            "{{\"cmd\":\"{}\",\"stdin\":\"{}\"}}",
-            json_escape(cmd.as_str()),
-            json_escape(&stdin_str),
+        let mut params = serde_json::json!({ "command": cmd.as_str(), "stdin": stdin_str });
+        if let vm::function::OptionalArg::Present(ref cwd) = cwd {
+            if !py_vm.is_none(cwd) {
+                params["cwd"] = cwd.str(py_vm)?.as_str().into();
+            }
+        }
+        if let vm::function::OptionalArg::Present(ref env) = env {
+            if !py_vm.is_none(env) {
+                params["env"] = serde_json::from_str(&py_to_json(env, py_vm)).map_err(|e| {
+                    py_vm.new_exception_msg(
+                        py_vm.ctx.exceptions.type_error.to_owned(),
+                        format!("spawn: env: {e}"),
+                    )
+                })?;
+            }
+        }
+        let request = codepod_rpc::Request::new(
+            next_request_id(),
+            codepod_rpc::method::SHELL_RUN,
+            params,
         );
 
         #[cfg(target_arch = "wasm32")]
         {
-            let response_str =
-                call_host_json(host_run_command, &request_json).map_err(|e| {
-                    py_vm.new_exception_msg(
-                        py_vm.ctx.exceptions.runtime_error.to_owned(),
-                        format!("spawn failed: {}", e),
-                    )
-                })?;
-            json_to_py(&response_str, py_vm)
+            let response = call_host_rpc(&request).map_err(|e| {
+                py_vm.new_exception_msg(
+                    py_vm.ctx.exceptions.runtime_error.to_owned(),
+                    format!("spawn failed: {}", e.message),
+                )
+            })?;
+            json_to_py(&response.to_string(), py_vm)
         }
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            let _ = request_json;
+            let _ = request;
             Err(py_vm.new_exception_msg(
                 py_vm.ctx.exceptions.runtime_error.to_owned(),
                 "_codepod.spawn() is only available inside a WASM sandbox".to_owned(),
@@ -547,6 +644,51 @@ pub mod _codepod {
         }
     }
 
+    /// Run several shell commands at once and capture each one's output.
+    ///
+    /// Usage: `_codepod.spawn_all(commands) -> list`
+    ///
+    /// `commands` is a list of dicts with `command` and, as for `spawn`,
+    /// optional `stdin`, `cwd` and `env`. The host runs them concurrently,
+    /// each in its own fresh shell, and returns their result dicts in order.
+    /// On non-WASM platforms, always raises RuntimeError.
+    #[pyfunction]
+    fn spawn_all(commands: vm::PyObjectRef, py_vm: &VirtualMachine) -> PyResult<vm::PyObjectRef> {
+        check_cancel(py_vm)?;
+        let commands: serde_json::Value =
+            serde_json::from_str(&py_to_json(&commands, py_vm)).map_err(|e| {
+                py_vm.new_exception_msg(
+                    py_vm.ctx.exceptions.type_error.to_owned(),
+                    format!("spawn_all: commands: {e}"),
+                )
+            })?;
+        let request = codepod_rpc::Request::new(
+            next_request_id(),
+            codepod_rpc::method::SHELL_RUN_ALL,
+            serde_json::json!({ "commands": commands }),
+        );
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let response = call_host_rpc(&request).map_err(|e| {
+                py_vm.new_exception_msg(
+                    py_vm.ctx.exceptions.runtime_error.to_owned(),
+                    format!("spawn_all failed: {}", e.message),
+                )
+            })?;
+            json_to_py(&response.to_string(), py_vm)
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = request;
+            Err(py_vm.new_exception_msg(
+                py_vm.ctx.exceptions.runtime_error.to_owned(),
+                "_codepod.spawn_all() is only available inside a WASM sandbox".to_owned(),
+            ))
+        }
+    }
+
     // ----- Socket operations (full mode) -----
 
     /// Open a TCP or TLS socket to host:port.
@@ -561,6 +703,7 @@ pub mod _codepod {
         tls: vm::function::OptionalArg<bool>,
         py_vm: &VirtualMachine,
     ) -> PyResult<u32> {
+        check_cancel(py_vm)?;
         let use_tls = tls.unwrap_or(false);
         let request_json = format!(
             "{{\"host\":\"{}\",\"port\":{},\"tls\":{}}}",
@@ -606,6 +749,7 @@ pub mod _codepod {
         data: vm::builtins::PyBytesRef,
         py_vm: &VirtualMachine,
     ) -> PyResult<usize> {
+        check_cancel(py_vm)?;
         let data_bytes = data.as_ref();
         let data_b64 = base64_encode(data_bytes);
         let request_json = format!(
@@ -648,6 +792,7 @@ pub mod _codepod {
         max_bytes: usize,
         py_vm: &VirtualMachine,
     ) -> PyResult<vm::PyObjectRef> {
+        check_cancel(py_vm)?;
         let request_json = format!(
             "{{\"socket_id\":{},\"max_bytes\":{}}}",
             socket_id, max_bytes,
@@ -708,6 +853,73 @@ pub mod _codepod {
             ))
         }
     }
+
+    // ----- Event loop support -----
+
+    /// Wait for file descriptors to become ready, as `select.select` would.
+    ///
+    /// Usage: `_codepod.poll(readers, writers, timeout=None) -> (readers, writers)`
+    ///
+    /// `timeout` is in seconds; None waits until an fd is ready. Timers and
+    /// readiness both come from the host's `poll_oneoff`.
+    #[pyfunction]
+    fn poll(
+        readers: vm::function::ArgIterable<u32>,
+        writers: vm::function::ArgIterable<u32>,
+        timeout: vm::function::OptionalOption<f64>,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(Vec<u32>, Vec<u32>)> {
+        check_cancel(py_vm)?;
+        let readers = readers.iter(py_vm)?.collect::<PyResult<Vec<_>>>()?;
+        let writers = writers.iter(py_vm)?.collect::<PyResult<Vec<_>>>()?;
+        let timeout = match timeout.flatten() {
+            Some(secs) if !(secs >= 0.0 && secs.is_finite()) => {
+                return Err(py_vm.new_value_error("timeout must be a non-negative number".to_owned()));
+            }
+            Some(secs) => Some(std::time::Duration::from_secs_f64(secs)),
+            None if readers.is_empty() && writers.is_empty() => {
+                return Err(py_vm.new_value_error("nothing to wait for".to_owned()));
+            }
+            None => None,
+        };
+        let ready = super::poll::wait(&readers, &writers, timeout)
+            .map_err(|errno| os_err(py_vm, &format!("poll_oneoff failed: errno {errno}")))?;
+        check_cancel(py_vm)?;
+        Ok((ready.readers, ready.writers))
+    }
+
+    // ----- Resource limits -----
+
+    /// Whether the interpreter was started with resource limits.
+    ///
+    /// Usage: `if _codepod.limits_active(): sys.setprofile(_codepod.limit_hook)`
+    #[pyfunction]
+    fn limits_active() -> bool {
+        limits::active()
+    }
+
+    /// Profile hook counting each call and return as a step and raising
+    /// MemoryError or TimeoutError once the script is over a limit. Host
+    /// interrupts are picked up here too.
+    ///
+    /// Usage: `sys.setprofile(_codepod.limit_hook)`
+    #[pyfunction]
+    fn limit_hook(_args: vm::function::PosArgs, py_vm: &VirtualMachine) -> PyResult<()> {
+        limits::step(py_vm)?;
+        check_cancel(py_vm)
+    }
+
+    // ----- Uncaught exceptions -----
+
+    /// Keep the JSON report of the exception the script is dying of, for
+    /// the host to read through the interpreter's `__exception_info`
+    /// export. `sitecustomize` calls this from `sys.excepthook`.
+    ///
+    /// Usage: `_codepod.report_exception(json.dumps(report))`
+    #[pyfunction]
+    fn report_exception(report: vm::builtins::PyStrRef) {
+        *EXCEPTION_REPORT.lock().unwrap() = Some(report.as_str().to_owned());
+    }
 }
 
 /// Public entry point for module registration.
@@ -0,0 +1,123 @@
+//! Resource limits for the running script.
+//!
+//! The interpreter binary calls [`install`] once at start-up with the
+//! limits it was configured with. They are checked at the interpreter's
+//! safe points: every `_codepod` call, and every Python function call and
+//! return once `sitecustomize` hands [`_codepod.limit_hook`] to
+//! `sys.setprofile`. A script over its heap budget gets `MemoryError`, one
+//! past its time or step budget `TimeoutError`, instead of running until
+//! the host kills the whole instance. As with any profile function, the
+//! hook is dropped once it raises; a script that catches the error is then
+//! stopped again at its next `_codepod` call. Loops that call nothing are
+//! left to the host's own timeout.
+//!
+//! [`_codepod.limit_hook`]: crate::_codepod
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use rustpython_vm as vm;
+
+/// Budgets for one interpreter run. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Bytes the interpreter's heap may hold.
+    pub max_heap_bytes: Option<usize>,
+    /// Function calls and returns the script may make. RustPython has no
+    /// per-instruction hook, so these profiler events are the step count.
+    pub max_steps: Option<u64>,
+    /// Wall-clock time from start-up, measured with the host's clock.
+    pub max_time: Option<Duration>,
+}
+
+impl Limits {
+    /// Read the limits from `CODEPOD_PYTHON_MAX_HEAP_BYTES`,
+    /// `CODEPOD_PYTHON_MAX_STEPS` and `CODEPOD_PYTHON_TIMEOUT_MS`. Unset,
+    /// unparsable or zero values leave that limit off.
+    pub fn from_env() -> Self {
+        fn var(name: &str) -> Option<u64> {
+            std::env::var(name)
+                .ok()?
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&n| n > 0)
+        }
+        Limits {
+            max_heap_bytes: var("CODEPOD_PYTHON_MAX_HEAP_BYTES").map(|n| n as usize),
+            max_steps: var("CODEPOD_PYTHON_MAX_STEPS"),
+            max_time: var("CODEPOD_PYTHON_TIMEOUT_MS").map(Duration::from_millis),
+        }
+    }
+
+    /// Whether any limit is set.
+    pub fn any(&self) -> bool {
+        *self != Limits::default()
+    }
+}
+
+struct State {
+    limits: Limits,
+    heap_used: fn() -> usize,
+    started: Instant,
+}
+
+static STATE: OnceLock<State> = OnceLock::new();
+static STEPS: AtomicU64 = AtomicU64::new(0);
+
+/// Enforce `limits` for the rest of the process. `heap_used` reports the
+/// bytes live on the heap, as the binary's tracking allocator counts them.
+/// Only the first call has any effect.
+pub fn install(limits: Limits, heap_used: fn() -> usize) {
+    let _ = STATE.set(State {
+        limits,
+        heap_used,
+        started: Instant::now(),
+    });
+}
+
+/// Whether the script runs under any limit, i.e. whether the profile hook
+/// is worth installing.
+pub fn active() -> bool {
+    STATE.get().is_some_and(|state| state.limits.any())
+}
+
+/// Count one step, raising once the script is past its step budget.
+pub(crate) fn step(py_vm: &vm::VirtualMachine) -> vm::PyResult<()> {
+    let steps = STEPS.fetch_add(1, Ordering::Relaxed) + 1;
+    if let Some(max) = STATE.get().and_then(|state| state.limits.max_steps) {
+        if steps > max {
+            return Err(py_vm.new_exception_msg(
+                py_vm.ctx.exceptions.timeout_error.to_owned(),
+                format!("step limit exceeded: more than {max} calls"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Raise if the script is over its heap or time budget.
+pub(crate) fn check(py_vm: &vm::VirtualMachine) -> vm::PyResult<()> {
+    let Some(state) = STATE.get() else {
+        return Ok(());
+    };
+    if let Some(max) = state.limits.max_heap_bytes {
+        let used = (state.heap_used)();
+        if used > max {
+            return Err(py_vm.new_exception_msg(
+                py_vm.ctx.exceptions.memory_error.to_owned(),
+                format!("heap limit exceeded: {used} > {max} bytes"),
+            ));
+        }
+    }
+    if let Some(max) = state.limits.max_time {
+        if state.started.elapsed() > max {
+            return Err(py_vm.new_exception_msg(
+                py_vm.ctx.exceptions.timeout_error.to_owned(),
+                format!("time limit exceeded: {} ms", max.as_millis()),
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Waiting on file descriptors and timers through WASI `poll_oneoff`.
+//!
+//! Backs `_codepod.poll`, which the asyncio event loop in the sandbox uses
+//! as its selector: timers and fd readiness both come from the host. The
+//! host answers fd subscriptions with what is ready now rather than waiting
+//! for it, so a longer wait on fds is made of short clock ticks.
+
+use std::time::{Duration, Instant};
+
+const SUBSCRIPTION_LEN: usize = 48;
+const EVENT_LEN: usize = 32;
+
+const EVENTTYPE_CLOCK: u8 = 0;
+const EVENTTYPE_FD_READ: u8 = 1;
+const EVENTTYPE_FD_WRITE: u8 = 2;
+const CLOCKID_MONOTONIC: u32 = 1;
+
+/// Userdata of the timer subscription; fd subscriptions use their index.
+const TIMER: u64 = u64::MAX;
+
+/// Longest single wait while fds are watched.
+const TICK: Duration = Duration::from_millis(20);
+
+/// The descriptors found ready.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Ready {
+    pub readers: Vec<u32>,
+    pub writers: Vec<u32>,
+}
+
+impl Ready {
+    fn is_empty(&self) -> bool {
+        self.readers.is_empty() && self.writers.is_empty()
+    }
+}
+
+/// Wait until one of `readers` can be read, one of `writers` written, or
+/// `timeout` has passed; None waits for an fd. Returns early, with nothing
+/// ready, when the host asks the script to stop. Errors are WASI errnos.
+pub fn wait(readers: &[u32], writers: &[u32], timeout: Option<Duration>) -> Result<Ready, u16> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let watching = !readers.is_empty() || !writers.is_empty();
+    loop {
+        let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+        let tick = match (watching, remaining) {
+            (false, remaining) => remaining,
+            (true, Some(remaining)) => Some(remaining.min(TICK)),
+            (true, None) => Some(TICK),
+        };
+        let ready = poll_once(readers, writers, tick)?;
+        let expired = remaining.is_some_and(|r| r <= tick.unwrap_or_default());
+        if !ready.is_empty() || expired || !watching || codepod_rpc::cancel::requested() {
+            return Ok(ready);
+        }
+    }
+}
+
+/// The subscriptions for one `poll_oneoff` call.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+fn subscriptions(readers: &[u32], writers: &[u32], timeout: Option<Duration>) -> Vec<u8> {
+    let fds = readers
+        .iter()
+        .map(|&fd| (EVENTTYPE_FD_READ, fd))
+        .chain(writers.iter().map(|&fd| (EVENTTYPE_FD_WRITE, fd)));
+    let mut subs = Vec::with_capacity((readers.len() + writers.len() + 1) * SUBSCRIPTION_LEN);
+    for (index, (kind, fd)) in fds.enumerate() {
+        let mut sub = [0u8; SUBSCRIPTION_LEN];
+        sub[0..8].copy_from_slice(&(index as u64).to_le_bytes());
+        sub[8] = kind;
+        sub[16..20].copy_from_slice(&fd.to_le_bytes());
+        subs.extend_from_slice(&sub);
+    }
+    if let Some(timeout) = timeout {
+        let nanos = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+        let mut sub = [0u8; SUBSCRIPTION_LEN];
+        sub[0..8].copy_from_slice(&TIMER.to_le_bytes());
+        sub[8] = EVENTTYPE_CLOCK;
+        sub[16..20].copy_from_slice(&CLOCKID_MONOTONIC.to_le_bytes());
+        sub[24..32].copy_from_slice(&nanos.to_le_bytes());
+        subs.extend_from_slice(&sub);
+    }
+    subs
+}
+
+/// Sort the events `poll_oneoff` wrote back into ready readers and
+/// writers. An fd event carrying an error counts as ready, as with
+/// `select`, so the next read or write reports it.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+fn ready(events: &[u8], readers: &[u32], writers: &[u32]) -> Ready {
+    let mut ready = Ready::default();
+    for event in events.chunks_exact(EVENT_LEN) {
+        let userdata = u64::from_le_bytes(event[0..8].try_into().unwrap());
+        if userdata == TIMER {
+            continue;
+        }
+        let index = userdata as usize;
+        if let Some(&fd) = readers.get(index) {
+            ready.readers.push(fd);
+        } else if let Some(&fd) = writers.get(index - readers.len()) {
+            ready.writers.push(fd);
+        }
+    }
+    ready
+}
+
+#[cfg(target_arch = "wasm32")]
+#[link(wasm_import_module = "wasi_snapshot_preview1")]
+extern "C" {
+    #[link_name = "poll_oneoff"]
+    fn wasi_poll_oneoff(
+        subs: *const u8,
+        events: *mut u8,
+        nsubscriptions: u32,
+        nevents: *mut u32,
+    ) -> u16;
+}
+
+#[cfg(target_arch = "wasm32")]
+fn poll_once(readers: &[u32], writers: &[u32], timeout: Option<Duration>) -> Result<Ready, u16> {
+    let subs = subscriptions(readers, writers, timeout);
+    let count = subs.len() / SUBSCRIPTION_LEN;
+    let mut events = vec![0u8; count * EVENT_LEN];
+    let mut nevents = 0u32;
+    let errno = unsafe {
+        wasi_poll_oneoff(
+            subs.as_ptr(),
+            events.as_mut_ptr(),
+            count as u32,
+            &mut nevents,
+        )
+    };
+    if errno != 0 {
+        return Err(errno);
+    }
+    events.truncate(nevents as usize * EVENT_LEN);
+    Ok(ready(&events, readers, writers))
+}
+
+/// Outside a WASI guest there is no host to ask.
+#[cfg(not(target_arch = "wasm32"))]
+fn poll_once(_readers: &[u32], _writers: &[u32], _timeout: Option<Duration>) -> Result<Ready, u16> {
+    const ENOSYS: u16 = 52;
+    Err(ENOSYS)
+}
@@ -0,0 +1,121 @@
+//! The hash functions behind the module, free of interpreter types.
+
+use blake2::digest::{Digest, Update, VariableOutput};
+use blake2::{Blake2bVar, Blake2sVar};
+
+/// A running hash.
+#[derive(Clone)]
+pub enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha224(sha2::Sha224),
+    Sha256(sha2::Sha256),
+    Sha384(sha2::Sha384),
+    Sha512(sha2::Sha512),
+    Blake2b(Blake2bVar),
+    Blake2s(Blake2sVar),
+}
+
+/// The longest digest BLAKE2b and BLAKE2s can produce.
+pub const BLAKE2B_MAX_DIGEST: usize = 64;
+pub const BLAKE2S_MAX_DIGEST: usize = 32;
+
+impl Hasher {
+    /// A fresh hasher for a `hashlib` algorithm name, with the default
+    /// digest size. None for names this module does not implement.
+    pub fn new(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "md5" => Hasher::Md5(Default::default()),
+            "sha1" => Hasher::Sha1(Default::default()),
+            "sha224" => Hasher::Sha224(Default::default()),
+            "sha256" => Hasher::Sha256(Default::default()),
+            "sha384" => Hasher::Sha384(Default::default()),
+            "sha512" => Hasher::Sha512(Default::default()),
+            "blake2b" => Hasher::blake2b(BLAKE2B_MAX_DIGEST)?,
+            "blake2s" => Hasher::blake2s(BLAKE2S_MAX_DIGEST)?,
+            _ => return None,
+        })
+    }
+
+    /// Unkeyed BLAKE2b with a `digest_size` of 1 to 64 bytes.
+    pub fn blake2b(digest_size: usize) -> Option<Self> {
+        Blake2bVar::new(digest_size).ok().map(Hasher::Blake2b)
+    }
+
+    /// Unkeyed BLAKE2s with a `digest_size` of 1 to 32 bytes.
+    pub fn blake2s(digest_size: usize) -> Option<Self> {
+        Blake2sVar::new(digest_size).ok().map(Hasher::Blake2s)
+    }
+
+    /// The `hashlib` name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hasher::Md5(_) => "md5",
+            Hasher::Sha1(_) => "sha1",
+            Hasher::Sha224(_) => "sha224",
+            Hasher::Sha256(_) => "sha256",
+            Hasher::Sha384(_) => "sha384",
+            Hasher::Sha512(_) => "sha512",
+            Hasher::Blake2b(_) => "blake2b",
+            Hasher::Blake2s(_) => "blake2s",
+        }
+    }
+
+    pub fn digest_size(&self) -> usize {
+        match self {
+            Hasher::Md5(_) => 16,
+            Hasher::Sha1(_) => 20,
+            Hasher::Sha224(_) => 28,
+            Hasher::Sha256(_) => 32,
+            Hasher::Sha384(_) => 48,
+            Hasher::Sha512(_) => 64,
+            Hasher::Blake2b(h) => h.output_size(),
+            Hasher::Blake2s(h) => h.output_size(),
+        }
+    }
+
+    pub fn block_size(&self) -> usize {
+        match self {
+            Hasher::Sha384(_) | Hasher::Sha512(_) | Hasher::Blake2b(_) => 128,
+            _ => 64,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => Digest::update(h, data),
+            Hasher::Sha1(h) => Digest::update(h, data),
+            Hasher::Sha224(h) => Digest::update(h, data),
+            Hasher::Sha256(h) => Digest::update(h, data),
+            Hasher::Sha384(h) => Digest::update(h, data),
+            Hasher::Sha512(h) => Digest::update(h, data),
+            Hasher::Blake2b(h) => Update::update(h, data),
+            Hasher::Blake2s(h) => Update::update(h, data),
+        }
+    }
+
+    /// The digest of the data so far. The hasher can keep going.
+    pub fn digest(&self) -> Vec<u8> {
+        match self.clone() {
+            Hasher::Md5(h) => h.finalize().to_vec(),
+            Hasher::Sha1(h) => h.finalize().to_vec(),
+            Hasher::Sha224(h) => h.finalize().to_vec(),
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha384(h) => h.finalize().to_vec(),
+            Hasher::Sha512(h) => h.finalize().to_vec(),
+            Hasher::Blake2b(h) => variable(h),
+            Hasher::Blake2s(h) => variable(h),
+        }
+    }
+
+    pub fn hexdigest(&self) -> String {
+        self.digest().iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+fn variable(h: impl VariableOutput) -> Vec<u8> {
+    let mut out = vec![0; h.output_size()];
+    h.finalize_variable(&mut out)
+        .expect("buffer is output_size long");
+    out
+}
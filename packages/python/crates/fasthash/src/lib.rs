@@ -0,0 +1,309 @@
+//! Native `hashlib`-compatible module for RustPython.
+//!
+//! `import fasthash as hashlib` gives `new`, `file_digest` and the `md5`,
+//! `sha1`, `sha224`, `sha256`, `sha384`, `sha512`, `blake2b` and `blake2s`
+//! constructors, hashing in Rust so checksumming large buffers does not
+//! crawl through the interpreter. Hash objects have the stdlib's `update`,
+//! `digest`, `hexdigest`, `copy`, `name`, `digest_size` and `block_size`.
+//!
+//! Algorithms it does not implement, and BLAKE2 keys, salts, personal
+//! strings and tree parameters, are handed to the stdlib `hashlib`
+//! unchanged.
+
+mod algo;
+
+use std::fmt;
+use std::sync::Mutex;
+
+use rustpython_vm as vm;
+
+use algo::Hasher;
+use vm::builtins::{PyBytesRef, PyStr, PyTypeRef};
+use vm::convert::ToPyObject;
+use vm::function::{ArgBytesLike, FuncArgs};
+use vm::{PyObjectRef, PyResult, TryFromObject, VirtualMachine};
+
+/// Call the stdlib's `hashlib.<function>` with `args`.
+fn stdlib(function: &str, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    py_vm
+        .import("hashlib", 0)?
+        .get_attr(function, py_vm)?
+        .call(args, py_vm)
+}
+
+/// Feed a bytes-like `data` to `hasher`, rejecting str as the stdlib does.
+#[allow(deprecated)] // payload_is() usage
+fn feed(hasher: &mut Hasher, data: PyObjectRef, py_vm: &VirtualMachine) -> PyResult<()> {
+    if data.payload_is::<PyStr>() {
+        return Err(py_vm.new_type_error("Strings must be encoded before hashing".to_owned()));
+    }
+    ArgBytesLike::try_from_object(py_vm, data)?.with_ref(|bytes| hasher.update(bytes));
+    Ok(())
+}
+
+/// Build `hasher` from a constructor call's `data`/`string` argument, or
+/// None when the call has arguments this module does not handle. Any
+/// `digest_size` has already been taken out by the caller.
+fn construct(
+    mut hasher: Hasher,
+    args: &FuncArgs,
+    py_vm: &VirtualMachine,
+) -> PyResult<Option<PyHash>> {
+    let known = ["data", "string", "usedforsecurity"];
+    if args.args.len() > 1 || args.kwargs.keys().any(|key| !known.contains(&key.as_str())) {
+        return Ok(None);
+    }
+    let data = args
+        .args
+        .first()
+        .or_else(|| args.kwargs.get("data"))
+        .or_else(|| args.kwargs.get("string"));
+    if let Some(data) = data {
+        feed(&mut hasher, data.clone(), py_vm)?;
+    }
+    Ok(Some(PyHash::new(hasher)))
+}
+
+/// `hashlib.<name>(...)`, from Rust when possible.
+fn fixed(name: &str, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    let hasher = Hasher::new(name).expect("a supported algorithm");
+    match construct(hasher, &args, py_vm)? {
+        Some(hash) => Ok(hash.to_pyobject(py_vm)),
+        None => stdlib(name, args, py_vm),
+    }
+}
+
+/// `hashlib.blake2b(...)`/`blake2s(...)`: unkeyed, any digest size.
+fn blake2(name: &str, mut args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    let Some(size) = args.kwargs.get("digest_size") else {
+        return fixed(name, args, py_vm);
+    };
+    let size = usize::try_from_object(py_vm, size.clone())?;
+    let (hasher, max) = match name {
+        "blake2b" => (Hasher::blake2b(size), algo::BLAKE2B_MAX_DIGEST),
+        _ => (Hasher::blake2s(size), algo::BLAKE2S_MAX_DIGEST),
+    };
+    let Some(hasher) = hasher else {
+        return Err(py_vm.new_value_error(format!("digest_size must be between 1 and {max} bytes")));
+    };
+    let size = args.kwargs.swap_remove("digest_size").unwrap();
+    match construct(hasher, &args, py_vm)? {
+        Some(hash) => Ok(hash.to_pyobject(py_vm)),
+        None => {
+            args.kwargs.insert("digest_size".to_owned(), size);
+            stdlib(name, args, py_vm)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Hash objects
+// ---------------------------------------------------------------------------
+
+#[vm::pyclass(module = "fasthash", name = "HASH")]
+#[derive(vm::PyPayload)]
+struct PyHash {
+    inner: Mutex<Hasher>,
+}
+
+impl PyHash {
+    fn new(hasher: Hasher) -> Self {
+        PyHash {
+            inner: Mutex::new(hasher),
+        }
+    }
+}
+
+impl fmt::Debug for PyHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} HASH object>", self.inner.lock().unwrap().name())
+    }
+}
+
+#[vm::pyclass]
+impl PyHash {
+    #[pygetset]
+    fn name(&self) -> &'static str {
+        self.inner.lock().unwrap().name()
+    }
+
+    #[pygetset]
+    fn digest_size(&self) -> usize {
+        self.inner.lock().unwrap().digest_size()
+    }
+
+    #[pygetset]
+    fn block_size(&self) -> usize {
+        self.inner.lock().unwrap().block_size()
+    }
+
+    #[pymethod]
+    fn update(&self, data: PyObjectRef, py_vm: &VirtualMachine) -> PyResult<()> {
+        feed(&mut self.inner.lock().unwrap(), data, py_vm)
+    }
+
+    #[pymethod]
+    fn digest(&self, py_vm: &VirtualMachine) -> PyBytesRef {
+        py_vm.ctx.new_bytes(self.inner.lock().unwrap().digest())
+    }
+
+    #[pymethod]
+    fn hexdigest(&self) -> String {
+        self.inner.lock().unwrap().hexdigest()
+    }
+
+    #[pymethod]
+    fn copy(&self) -> PyHash {
+        PyHash::new(self.inner.lock().unwrap().clone())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Python module: fasthash
+// ---------------------------------------------------------------------------
+
+#[allow(non_snake_case)]
+#[vm::pymodule]
+pub mod fasthash {
+    use super::*;
+    use vm::class::PyClassImpl;
+
+    /// Read size for `file_digest`, as in the stdlib.
+    const FILE_CHUNK: usize = 1 << 18;
+
+    #[pyattr]
+    fn HASH(vm: &VirtualMachine) -> PyTypeRef {
+        PyHash::make_class(&vm.ctx)
+    }
+
+    /// The stdlib's `hashlib.<name>`, for the sets shared with it.
+    fn from_hashlib(name: &str, vm: &VirtualMachine) -> PyObjectRef {
+        vm.import("hashlib", 0)
+            .and_then(|hashlib| hashlib.get_attr(name, vm))
+            .unwrap_or_else(|_| vm.ctx.none())
+    }
+
+    #[pyattr]
+    fn algorithms_guaranteed(vm: &VirtualMachine) -> PyObjectRef {
+        from_hashlib("algorithms_guaranteed", vm)
+    }
+
+    #[pyattr]
+    fn algorithms_available(vm: &VirtualMachine) -> PyObjectRef {
+        from_hashlib("algorithms_available", vm)
+    }
+
+    /// `hashlib.new(name, data=b'', **kwargs)`.
+    #[allow(deprecated)] // payload() usage
+    #[pyfunction]
+    fn new(mut args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let name = match args.args.first() {
+            Some(name) => Some(name.clone()),
+            None => args.kwargs.get("name").cloned(),
+        };
+        let hasher = name
+            .as_ref()
+            .and_then(|name| name.payload::<PyStr>())
+            .and_then(|name| Hasher::new(name.as_str()));
+        let Some(hasher) = hasher else {
+            return stdlib("new", args, py_vm);
+        };
+        if hasher.name().starts_with("blake2") {
+            strip_name(&mut args);
+            return super::blake2(hasher.name(), args, py_vm);
+        }
+        let mut rest = args.clone();
+        strip_name(&mut rest);
+        match construct(hasher, &rest, py_vm)? {
+            Some(hash) => Ok(hash.to_pyobject(py_vm)),
+            None => stdlib("new", args, py_vm),
+        }
+    }
+
+    /// Drop `new`'s `name` argument, leaving the constructor's.
+    fn strip_name(args: &mut FuncArgs) {
+        if args.kwargs.swap_remove("name").is_none() && !args.args.is_empty() {
+            args.args.remove(0);
+        }
+    }
+
+    #[pyfunction]
+    fn md5(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        fixed("md5", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn sha1(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        fixed("sha1", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn sha224(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        fixed("sha224", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn sha256(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        fixed("sha256", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn sha384(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        fixed("sha384", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn sha512(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        fixed("sha512", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn blake2b(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        super::blake2("blake2b", args, py_vm)
+    }
+
+    #[pyfunction]
+    fn blake2s(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        super::blake2("blake2s", args, py_vm)
+    }
+
+    /// `hashlib.file_digest(fileobj, digest)`: hash a binary file object
+    /// read in chunks. Digests given as a callable go to the stdlib.
+    #[allow(deprecated)] // payload() usage
+    #[pyfunction]
+    fn file_digest(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let hasher = match args.args.as_slice() {
+            [_, digest] if args.kwargs.is_empty() => digest
+                .payload::<PyStr>()
+                .and_then(|name| Hasher::new(name.as_str())),
+            _ => None,
+        };
+        let Some(mut hasher) = hasher else {
+            return stdlib("file_digest", args, py_vm);
+        };
+        let read = args.args[0].get_attr("read", py_vm)?;
+        loop {
+            let chunk = read.call((FILE_CHUNK,), py_vm)?;
+            if py_vm.is_none(&chunk) {
+                // A non-blocking file with nothing ready; the stdlib
+                // refuses these too.
+                return Err(py_vm.new_exception_msg(
+                    py_vm.ctx.exceptions.blocking_io_error.to_owned(),
+                    "I/O operation would block.".to_owned(),
+                ));
+            }
+            let done = ArgBytesLike::try_from_object(py_vm, chunk)?.with_ref(|bytes| {
+                hasher.update(bytes);
+                bytes.is_empty()
+            });
+            if done {
+                return Ok(PyHash::new(hasher).to_pyobject(py_vm));
+            }
+        }
+    }
+}
+
+/// Public entry point for module registration.
+pub fn module_def(ctx: &vm::Context) -> &'static vm::builtins::PyModuleDef {
+    fasthash::module_def(ctx)
+}
@@ -0,0 +1,283 @@
+//! A JSON parser that accepts what Python's `json.loads` accepts, with its
+//! error messages, building a plain tree for `lib.rs` to turn into Python
+//! objects.
+
+/// A parsed JSON value. Objects keep their members in document order, as
+/// Python dicts do.
+#[derive(Debug, PartialEq)]
+pub enum Value<'a> {
+    Null,
+    Bool(bool),
+    /// An integer, as its digits, since Python ints have no size limit.
+    Int(&'a str),
+    Float(f64),
+    Str(String),
+    Array(Vec<Value<'a>>),
+    Object(Vec<(String, Value<'a>)>),
+}
+
+/// Why a document was not parsed.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Invalid JSON: `json.JSONDecodeError` with this message at this byte
+    /// offset.
+    Syntax(&'static str, usize),
+    /// Valid JSON that has no `String` form (a lone surrogate escape) or
+    /// nests too deeply for this parser; the stdlib handles it.
+    Unsupported,
+}
+
+/// How deeply arrays and objects may nest before the stdlib takes over, to
+/// keep the recursion well inside the wasm stack.
+const MAX_DEPTH: usize = 512;
+
+/// Parse a whole document.
+pub fn parse(doc: &str) -> Result<Value<'_>, Error> {
+    let mut parser = Parser {
+        doc,
+        bytes: doc.as_bytes(),
+        at: 0,
+        depth: 0,
+    };
+    parser.skip_whitespace();
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.at != doc.len() {
+        return Err(Error::Syntax("Extra data", parser.at));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    doc: &'a str,
+    bytes: &'a [u8],
+    at: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.at).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.at += 1;
+        }
+    }
+
+    fn eat(&mut self, literal: &str) -> bool {
+        if self.doc[self.at..].starts_with(literal) {
+            self.at += literal.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<Value<'a>, Error> {
+        match self.peek() {
+            Some(b'"') => Ok(Value::Str(self.string()?)),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            _ if self.eat("null") => Ok(Value::Null),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            _ if self.eat("NaN") => Ok(Value::Float(f64::NAN)),
+            _ if self.eat("Infinity") => Ok(Value::Float(f64::INFINITY)),
+            _ if self.eat("-Infinity") => Ok(Value::Float(f64::NEG_INFINITY)),
+            _ => self.number(),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value<'a>, Error>,
+    ) -> Result<Value<'a>, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::Unsupported);
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][-+]?[0-9]+)?`
+    fn number(&mut self) -> Result<Value<'a>, Error> {
+        let start = self.at;
+        let digits = |parser: &mut Self| {
+            let from = parser.at;
+            while matches!(parser.peek(), Some(b'0'..=b'9')) {
+                parser.at += 1;
+            }
+            parser.at - from
+        };
+        if self.peek() == Some(b'-') {
+            self.at += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.at += 1,
+            Some(b'1'..=b'9') => {
+                digits(self);
+            }
+            _ => return Err(Error::Syntax("Expecting value", start)),
+        }
+        let int_end = self.at;
+        if self.peek() == Some(b'.') && matches!(self.bytes.get(self.at + 1), Some(b'0'..=b'9')) {
+            self.at += 1;
+            digits(self);
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let before = self.at;
+            self.at += 1;
+            if matches!(self.peek(), Some(b'-' | b'+')) {
+                self.at += 1;
+            }
+            if digits(self) == 0 {
+                self.at = before;
+            }
+        }
+        let text = &self.doc[start..self.at];
+        if self.at == int_end {
+            Ok(Value::Int(text))
+        } else {
+            Ok(Value::Float(text.parse().unwrap()))
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.at;
+        self.at += 1;
+        let mut out = String::new();
+        loop {
+            // Copy the run up to the next quote, backslash or control
+            // character in one go.
+            let run = self.bytes[self.at..]
+                .iter()
+                .position(|&b| b == b'"' || b == b'\\' || b < 0x20)
+                .ok_or(Error::Syntax("Unterminated string starting at", start))?;
+            out.push_str(&self.doc[self.at..self.at + run]);
+            self.at += run;
+            match self.bytes[self.at] {
+                b'"' => {
+                    self.at += 1;
+                    return Ok(out);
+                }
+                b'\\' => {
+                    self.at += 1;
+                    self.escape(&mut out, start)?;
+                }
+                _ => return Err(Error::Syntax("Invalid control character at", self.at)),
+            }
+        }
+    }
+
+    /// The escape after a backslash, with `self.at` just past the backslash.
+    fn escape(&mut self, out: &mut String, start: usize) -> Result<(), Error> {
+        let Some(escape) = self.peek() else {
+            return Err(Error::Syntax("Unterminated string starting at", start));
+        };
+        let simple = match escape {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\x08',
+            b'f' => '\x0c',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let unit = self.hex4(self.at + 1)?;
+                self.at += 5;
+                let code = match unit {
+                    0xd800..=0xdbff if self.doc[self.at..].starts_with("\\u") => {
+                        let low = self.hex4(self.at + 2)?;
+                        if !(0xdc00..=0xdfff).contains(&low) {
+                            return Err(Error::Unsupported);
+                        }
+                        self.at += 6;
+                        0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00)
+                    }
+                    0xd800..=0xdfff => return Err(Error::Unsupported),
+                    unit => unit,
+                };
+                out.push(char::from_u32(code).unwrap());
+                return Ok(());
+            }
+            // Python reports the backslash's position.
+            _ => return Err(Error::Syntax("Invalid \\escape", self.at - 1)),
+        };
+        out.push(simple);
+        self.at += 1;
+        Ok(())
+    }
+
+    /// The four hex digits at `at`, which follow `\u`.
+    fn hex4(&self, at: usize) -> Result<u32, Error> {
+        self.doc
+            .get(at..at + 4)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .map(|hex| u32::from_str_radix(hex, 16).unwrap())
+            .ok_or(Error::Syntax("Invalid \\uXXXX escape", at - 1))
+    }
+
+    fn array(&mut self) -> Result<Value<'a>, Error> {
+        self.at += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b']') => {
+                    self.at += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(Error::Syntax("Expecting ',' delimiter", self.at)),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value<'a>, Error> {
+        self.at += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(Error::Syntax(
+                    "Expecting property name enclosed in double quotes",
+                    self.at,
+                ));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(Error::Syntax("Expecting ':' delimiter", self.at));
+            }
+            self.at += 1;
+            self.skip_whitespace();
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => {
+                    self.at += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(Error::Syntax("Expecting ',' delimiter", self.at)),
+            }
+        }
+    }
+}
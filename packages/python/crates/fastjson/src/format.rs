@@ -0,0 +1,77 @@
+//! Text that `json.dumps` writes for strings and floats, byte for byte.
+
+use std::fmt::Write;
+
+/// Append `s` as a JSON string literal. With `ensure_ascii`, everything
+/// outside ASCII is written as `\uXXXX` escapes, using surrogate pairs
+/// beyond the BMP.
+pub fn write_str(out: &mut String, s: &str, ensure_ascii: bool) {
+    out.push('"');
+    let mut run = 0;
+    for (at, c) in s.char_indices() {
+        let escape = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\x08' => "\\b",
+            '\x0c' => "\\f",
+            c if c < ' ' || (ensure_ascii && !c.is_ascii()) => "",
+            _ => continue,
+        };
+        out.push_str(&s[run..at]);
+        run = at + c.len_utf8();
+        if !escape.is_empty() {
+            out.push_str(escape);
+            continue;
+        }
+        let mut units = [0; 2];
+        for unit in c.encode_utf16(&mut units) {
+            write!(out, "\\u{unit:04x}").unwrap();
+        }
+    }
+    out.push_str(&s[run..]);
+    out.push('"');
+}
+
+/// Python's `repr` of a finite float: the shortest digits that round-trip,
+/// in positional notation for exponents from -4 to 15 and scientific
+/// notation (`1e+16`, `1.5e-05`) otherwise.
+pub fn float_repr(value: f64) -> String {
+    // `{:e}` gives the shortest round-trip digits, e.g. `1.5e-5`.
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+
+    if !(-4..16).contains(&exponent) {
+        let (first, rest) = digits.split_at(1);
+        let fraction = if rest.is_empty() {
+            String::new()
+        } else {
+            format!(".{rest}")
+        };
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        return format!(
+            "{sign}{first}{fraction}e{exponent_sign}{:02}",
+            exponent.abs()
+        );
+    }
+    if exponent < 0 {
+        let zeros = "0".repeat((-exponent - 1) as usize);
+        return format!("{sign}0.{zeros}{digits}");
+    }
+    let point = exponent as usize + 1;
+    if digits.len() <= point {
+        let zeros = "0".repeat(point - digits.len());
+        format!("{sign}{digits}{zeros}.0")
+    } else {
+        let (whole, fraction) = digits.split_at(point);
+        format!("{sign}{whole}.{fraction}")
+    }
+}
@@ -0,0 +1,472 @@
+//! Native `json`-compatible module for RustPython.
+//!
+//! `import fastjson as json` gives `loads`, `dumps`, `load` and `dump` with
+//! the stdlib's output and errors, parsing and encoding in Rust instead of
+//! the interpreter. `dumps` supports `indent`, `separators`, `sort_keys`,
+//! `ensure_ascii`, `allow_nan`, `skipkeys` and `default`.
+//!
+//! Calls it cannot answer the way the stdlib would — a `cls` or an
+//! `object_hook`, bytes input, keys of other types, very deep nesting,
+//! objects `dumps` has no rule for — are passed to the stdlib `json`
+//! unchanged, which also produces its usual exceptions for them.
+
+mod decode;
+mod format;
+
+use rustpython_vm as vm;
+
+use decode::Value;
+use vm::builtins::{PyBaseExceptionRef, PyDict, PyFloat, PyInt, PyList, PyStr, PyTuple};
+use vm::function::FuncArgs;
+use vm::{AsObject, PyObjectRef, PyResult, VirtualMachine};
+
+/// Call the stdlib's `json.<function>` with `args`.
+fn stdlib(function: &str, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    py_vm
+        .import("json", 0)?
+        .get_attr(function, py_vm)?
+        .call(args, py_vm)
+}
+
+// ---------------------------------------------------------------------------
+// Decoding
+// ---------------------------------------------------------------------------
+
+fn to_py(value: Value, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+    Ok(match value {
+        Value::Null => py_vm.ctx.none(),
+        Value::Bool(b) => py_vm.ctx.new_bool(b).into(),
+        Value::Int(digits) => match digits.parse::<i64>() {
+            Ok(i) => py_vm.ctx.new_int(i).into(),
+            Err(_) => {
+                let digits: PyObjectRef = py_vm.ctx.new_str(digits).into();
+                py_vm
+                    .builtins
+                    .get_attr("int", py_vm)?
+                    .call((digits,), py_vm)?
+            }
+        },
+        Value::Float(f) => py_vm.ctx.new_float(f).into(),
+        Value::Str(s) => py_vm.ctx.new_str(s).into(),
+        Value::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| to_py(item, py_vm))
+                .collect::<PyResult<Vec<_>>>()?;
+            py_vm.ctx.new_list(items).into()
+        }
+        Value::Object(members) => {
+            let dict = py_vm.ctx.new_dict();
+            for (key, value) in members {
+                dict.set_item(key.as_str(), to_py(value, py_vm)?, py_vm)?;
+            }
+            dict.into()
+        }
+    })
+}
+
+/// `json.loads`, or None when the stdlib should handle the call.
+#[allow(deprecated)] // payload() usage
+fn loads(args: &FuncArgs, py_vm: &VirtualMachine) -> PyResult<Option<PyObjectRef>> {
+    // Hooks, parse_* callbacks and `strict=False` all change the result.
+    if args.args.len() != 1 || args.kwargs.values().any(|value| !py_vm.is_none(value)) {
+        return Ok(None);
+    }
+    let Some(doc) = args.args[0].payload::<PyStr>() else {
+        return Ok(None);
+    };
+    let doc = doc.as_str();
+    if doc.starts_with('\u{feff}') {
+        return Ok(None);
+    }
+    match decode::parse(doc) {
+        Ok(value) => to_py(value, py_vm).map(Some),
+        Err(decode::Error::Unsupported) => Ok(None),
+        Err(decode::Error::Syntax(message, at)) => {
+            let message: PyObjectRef = py_vm.ctx.new_str(message).into();
+            let position: PyObjectRef = py_vm.ctx.new_int(doc[..at].chars().count()).into();
+            let error = py_vm
+                .import("json", 0)?
+                .get_attr("JSONDecodeError", py_vm)?
+                .call((message, args.args[0].clone(), position), py_vm)?;
+            Err(error
+                .downcast()
+                .unwrap_or_else(|_| py_vm.new_type_error("bad JSONDecodeError".to_owned())))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Encoding
+// ---------------------------------------------------------------------------
+
+/// Why `dumps` stopped.
+enum EncodeError {
+    Raised(PyBaseExceptionRef),
+    /// Something to leave to the stdlib.
+    Unsupported,
+}
+
+impl From<PyBaseExceptionRef> for EncodeError {
+    fn from(err: PyBaseExceptionRef) -> Self {
+        EncodeError::Raised(err)
+    }
+}
+
+/// How deeply `dumps` recurses into containers before leaving the value to
+/// the stdlib.
+const MAX_DEPTH: usize = 512;
+
+/// `json.dumps` with the stdlib's keyword arguments.
+struct Encoder<'v> {
+    py_vm: &'v VirtualMachine,
+    indent: Option<String>,
+    item_separator: String,
+    key_separator: String,
+    sort_keys: bool,
+    skipkeys: bool,
+    ensure_ascii: bool,
+    allow_nan: bool,
+    default: Option<PyObjectRef>,
+    /// Ids of the containers being encoded, to catch cycles.
+    stack: Vec<usize>,
+    /// How many arrays and objects enclose the current value.
+    level: usize,
+    out: String,
+}
+
+impl<'v> Encoder<'v> {
+    /// An encoder for the keyword arguments of a `dumps` call, or None if
+    /// one of them is for the stdlib (`cls` or an unknown one).
+    #[allow(deprecated)] // payload() usage
+    fn new(args: &FuncArgs, py_vm: &'v VirtualMachine) -> PyResult<Option<Self>> {
+        let mut encoder = Encoder {
+            py_vm,
+            indent: None,
+            item_separator: ", ".to_owned(),
+            key_separator: ": ".to_owned(),
+            sort_keys: false,
+            skipkeys: false,
+            ensure_ascii: true,
+            allow_nan: true,
+            default: None,
+            stack: Vec::new(),
+            level: 0,
+            out: String::new(),
+        };
+        let mut separators = None;
+        for (name, value) in &args.kwargs {
+            let value = value.clone();
+            if py_vm.is_none(&value) {
+                continue;
+            }
+            match name.as_str() {
+                "indent" => {
+                    encoder.indent = Some(match value.payload::<PyInt>() {
+                        Some(n) => " ".repeat(n.try_to_primitive::<usize>(py_vm).unwrap_or(0)),
+                        None => value.str(py_vm)?.as_str().to_owned(),
+                    })
+                }
+                "separators" => separators = Some(value),
+                "default" => encoder.default = Some(value),
+                "sort_keys" => encoder.sort_keys = value.try_to_bool(py_vm)?,
+                "skipkeys" => encoder.skipkeys = value.try_to_bool(py_vm)?,
+                "ensure_ascii" => encoder.ensure_ascii = value.try_to_bool(py_vm)?,
+                "allow_nan" => encoder.allow_nan = value.try_to_bool(py_vm)?,
+                // Cycles are always checked; the stdlib would overflow instead.
+                "check_circular" => {}
+                _ => return Ok(None),
+            }
+        }
+        if encoder.indent.is_some() {
+            encoder.item_separator = ",".to_owned();
+        }
+        if let Some(separators) = separators {
+            let Some(pair) = separators.payload::<PyTuple>() else {
+                return Ok(None);
+            };
+            let [item, key] = pair.as_slice() else {
+                return Ok(None);
+            };
+            encoder.item_separator = item.str(py_vm)?.as_str().to_owned();
+            encoder.key_separator = key.str(py_vm)?.as_str().to_owned();
+        }
+        Ok(Some(encoder))
+    }
+
+    /// A newline and the indentation for `depth`, when indenting.
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = &self.indent {
+            self.out.push('\n');
+            for _ in 0..depth {
+                self.out.push_str(indent);
+            }
+        }
+    }
+
+    fn float(&mut self, f: f64) -> Result<(), EncodeError> {
+        if f.is_finite() {
+            self.out.push_str(&format::float_repr(f));
+            return Ok(());
+        }
+        let (text, repr) = if f.is_nan() {
+            ("NaN", "nan")
+        } else if f > 0.0 {
+            ("Infinity", "inf")
+        } else {
+            ("-Infinity", "-inf")
+        };
+        if !self.allow_nan {
+            return Err(self
+                .py_vm
+                .new_value_error(format!(
+                    "Out of range float values are not JSON compliant: {repr}"
+                ))
+                .into());
+        }
+        self.out.push_str(text);
+        Ok(())
+    }
+
+    /// Enter a container, failing on a cycle or very deep nesting.
+    fn enter(&mut self, obj: &PyObjectRef) -> Result<(), EncodeError> {
+        let id = obj.get_id();
+        if self.stack.contains(&id) {
+            let err = self
+                .py_vm
+                .new_value_error("Circular reference detected".to_owned());
+            return Err(err.into());
+        }
+        if self.stack.len() == MAX_DEPTH {
+            return Err(EncodeError::Unsupported);
+        }
+        self.stack.push(id);
+        Ok(())
+    }
+
+    #[allow(deprecated)] // payload() usage
+    fn encode(&mut self, obj: &PyObjectRef) -> Result<(), EncodeError> {
+        let py_vm = self.py_vm;
+        if let Some(s) = obj.payload::<PyStr>() {
+            format::write_str(&mut self.out, s.as_str(), self.ensure_ascii);
+        } else if py_vm.is_none(obj) {
+            self.out.push_str("null");
+        } else if obj.is(&py_vm.ctx.true_value) {
+            self.out.push_str("true");
+        } else if obj.is(&py_vm.ctx.false_value) {
+            self.out.push_str("false");
+        } else if let Some(i) = obj.payload::<PyInt>() {
+            self.out.push_str(&i.to_string());
+        } else if let Some(f) = obj.payload::<PyFloat>() {
+            self.float(f.to_f64())?;
+        } else if let Some(list) = obj.payload::<PyList>() {
+            // Copied, so a `default` that changes the list cannot upset us.
+            let items = list.borrow_vec().to_vec();
+            self.array(obj, &items)?;
+        } else if let Some(tuple) = obj.payload::<PyTuple>() {
+            self.array(obj, tuple.as_slice())?;
+        } else if let Some(dict) = obj.payload::<PyDict>() {
+            let items: Vec<_> = dict.into_iter().collect();
+            self.object(obj, items)?;
+        } else if let Some(default) = self.default.clone() {
+            self.enter(obj)?;
+            let replacement = default.call((obj.clone(),), py_vm)?;
+            self.encode(&replacement)?;
+            self.stack.pop();
+        } else {
+            return Err(EncodeError::Unsupported);
+        }
+        Ok(())
+    }
+
+    fn array(&mut self, obj: &PyObjectRef, items: &[PyObjectRef]) -> Result<(), EncodeError> {
+        if items.is_empty() {
+            self.out.push_str("[]");
+            return Ok(());
+        }
+        self.enter(obj)?;
+        self.level += 1;
+        let depth = self.level;
+        self.out.push('[');
+        self.newline(depth);
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(&self.item_separator);
+                self.newline(depth);
+            }
+            self.encode(item)?;
+        }
+        self.newline(depth - 1);
+        self.out.push(']');
+        self.level -= 1;
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// The string a dict key is written as, or None to skip it.
+    #[allow(deprecated)] // payload() usage
+    fn key(&mut self, key: &PyObjectRef) -> Result<Option<String>, EncodeError> {
+        let py_vm = self.py_vm;
+        if let Some(s) = key.payload::<PyStr>() {
+            return Ok(Some(s.as_str().to_owned()));
+        }
+        let text = if key.is(&py_vm.ctx.true_value) {
+            "true".to_owned()
+        } else if key.is(&py_vm.ctx.false_value) {
+            "false".to_owned()
+        } else if py_vm.is_none(key) {
+            "null".to_owned()
+        } else if let Some(i) = key.payload::<PyInt>() {
+            i.to_string()
+        } else if let Some(f) = key.payload::<PyFloat>() {
+            let start = self.out.len();
+            self.float(f.to_f64())?;
+            self.out.split_off(start)
+        } else if self.skipkeys {
+            return Ok(None);
+        } else {
+            return Err(EncodeError::Unsupported);
+        };
+        Ok(Some(text))
+    }
+
+    #[allow(deprecated)] // payload() usage
+    fn object(
+        &mut self,
+        obj: &PyObjectRef,
+        items: Vec<(PyObjectRef, PyObjectRef)>,
+    ) -> Result<(), EncodeError> {
+        if items.is_empty() {
+            self.out.push_str("{}");
+            return Ok(());
+        }
+        if self.sort_keys
+            && items
+                .iter()
+                .any(|(key, _)| key.payload::<PyStr>().is_none())
+        {
+            // Python sorts the original keys, so mixed types sort or fail
+            // there.
+            return Err(EncodeError::Unsupported);
+        }
+        let mut members = Vec::with_capacity(items.len());
+        for (key, value) in items {
+            if let Some(key) = self.key(&key)? {
+                members.push((key, value));
+            }
+        }
+        if self.sort_keys {
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        self.enter(obj)?;
+        self.level += 1;
+        let depth = self.level;
+        self.out.push('{');
+        self.newline(depth);
+        for (index, (key, value)) in members.iter().enumerate() {
+            if index > 0 {
+                self.out.push_str(&self.item_separator);
+                self.newline(depth);
+            }
+            format::write_str(&mut self.out, key, self.ensure_ascii);
+            self.out.push_str(&self.key_separator);
+            self.encode(value)?;
+        }
+        self.newline(depth - 1);
+        self.out.push('}');
+        self.level -= 1;
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// `json.dumps`, or None when the stdlib should handle the call.
+fn dumps(args: &FuncArgs, py_vm: &VirtualMachine) -> PyResult<Option<String>> {
+    let [obj] = args.args.as_slice() else {
+        return Ok(None);
+    };
+    let Some(mut encoder) = Encoder::new(args, py_vm)? else {
+        return Ok(None);
+    };
+    match encoder.encode(obj) {
+        Ok(()) => Ok(Some(encoder.out)),
+        Err(EncodeError::Raised(err)) => Err(err),
+        Err(EncodeError::Unsupported) => Ok(None),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Python module: fastjson
+// ---------------------------------------------------------------------------
+
+#[allow(non_snake_case)]
+#[vm::pymodule]
+pub mod fastjson {
+    use super::*;
+
+    /// The stdlib's `json.<name>`, for the classes shared with it.
+    fn from_json(name: &str, vm: &VirtualMachine) -> PyObjectRef {
+        vm.import("json", 0)
+            .and_then(|json| json.get_attr(name, vm))
+            .unwrap_or_else(|_| vm.ctx.none())
+    }
+
+    #[pyattr]
+    fn JSONDecodeError(vm: &VirtualMachine) -> PyObjectRef {
+        from_json("JSONDecodeError", vm)
+    }
+
+    #[pyattr]
+    fn JSONDecoder(vm: &VirtualMachine) -> PyObjectRef {
+        from_json("JSONDecoder", vm)
+    }
+
+    #[pyattr]
+    fn JSONEncoder(vm: &VirtualMachine) -> PyObjectRef {
+        from_json("JSONEncoder", vm)
+    }
+
+    #[pyfunction]
+    fn loads(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        match super::loads(&args, py_vm)? {
+            Some(value) => Ok(value),
+            None => stdlib("loads", args, py_vm),
+        }
+    }
+
+    #[pyfunction]
+    fn dumps(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        match super::dumps(&args, py_vm)? {
+            Some(text) => Ok(py_vm.ctx.new_str(text).into()),
+            None => stdlib("dumps", args, py_vm),
+        }
+    }
+
+    /// `json.load(fp, **kw)`: `loads(fp.read(), **kw)`.
+    #[pyfunction]
+    fn load(mut args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        if args.args.len() != 1 {
+            return stdlib("load", args, py_vm);
+        }
+        args.args[0] = args.args[0].get_attr("read", py_vm)?.call((), py_vm)?;
+        loads(args, py_vm)
+    }
+
+    /// `json.dump(obj, fp, **kw)`: `fp.write(dumps(obj, **kw))`.
+    #[pyfunction]
+    fn dump(mut args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<()> {
+        if args.args.len() != 2 {
+            stdlib("dump", args, py_vm)?;
+            return Ok(());
+        }
+        let fp = args.args.pop().unwrap();
+        let text = dumps(args, py_vm)?;
+        fp.get_attr("write", py_vm)?.call((text,), py_vm)?;
+        Ok(())
+    }
+}
+
+/// Public entry point for module registration.
+pub fn module_def(ctx: &vm::Context) -> &'static vm::builtins::PyModuleDef {
+    fastjson::module_def(ctx)
+}
@@ -0,0 +1,321 @@
+//! The parts of `fastre` that do not touch the interpreter: deciding whether
+//! the regex crate can run a pattern, Python's rules for successive matches,
+//! and replacement templates.
+
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+pub const IGNORECASE: u32 = 2;
+pub const MULTILINE: u32 = 8;
+pub const DOTALL: u32 = 16;
+pub const UNICODE: u32 = 32;
+pub const VERBOSE: u32 = 64;
+pub const ASCII: u32 = 256;
+
+/// A pattern compiled for the regex crate.
+#[derive(Debug)]
+pub struct Compiled {
+    pub pattern: String,
+    pub flags: u32,
+    pub regex: Regex,
+    /// Inline flags equivalent to `flags`, e.g. `(?is)`.
+    prefix: String,
+    /// The pattern anchored at the start, for `match` from position 0.
+    anchored: OnceLock<Regex>,
+    /// The pattern anchored at the end, for `fullmatch`.
+    tail: OnceLock<Regex>,
+}
+
+impl Compiled {
+    /// Compile `pattern` with the `re` `flags`, or `None` if the regex crate
+    /// cannot run it with the meaning Python gives it. The caller then falls
+    /// back to the stdlib.
+    pub fn new(pattern: &str, flags: u32) -> Option<Self> {
+        if flags & !(IGNORECASE | MULTILINE | DOTALL | UNICODE | ASCII) != 0 {
+            return None;
+        }
+        if !same_meaning(pattern, flags & MULTILINE != 0) {
+            return None;
+        }
+        let mut prefix = String::new();
+        for (flag, letter) in [(IGNORECASE, 'i'), (MULTILINE, 'm'), (DOTALL, 's')] {
+            if flags & flag != 0 {
+                prefix.push(letter);
+            }
+        }
+        if flags & ASCII != 0 {
+            prefix.push_str("-u");
+        }
+        if !prefix.is_empty() {
+            prefix = format!("(?{prefix})");
+        }
+        let regex = Regex::new(&format!("{prefix}{pattern}")).ok()?;
+        Some(Self {
+            pattern: pattern.to_owned(),
+            flags,
+            regex,
+            prefix,
+            anchored: OnceLock::new(),
+            tail: OnceLock::new(),
+        })
+    }
+
+    /// Number of capturing groups.
+    pub fn groups(&self) -> usize {
+        self.regex.captures_len() - 1
+    }
+
+    /// Index of the group called `name`.
+    pub fn group_named(&self, name: &str) -> Option<usize> {
+        self.regex
+            .capture_names()
+            .position(|group| group == Some(name))
+    }
+
+    /// `re.match`: a match starting exactly at `pos`.
+    pub fn match_at<'h>(&self, hay: &'h str, pos: usize) -> Option<Captures<'h>> {
+        if pos == 0 {
+            let anchored = self.anchored.get_or_init(|| self.wrap(r"\A(?:", ")"));
+            return anchored.captures(hay);
+        }
+        self.regex
+            .captures_at(hay, pos)
+            .filter(|caps| caps.get(0).unwrap().start() == pos)
+    }
+
+    /// `re.fullmatch`: a match from `pos` to the end of `hay`.
+    pub fn fullmatch_at<'h>(&self, hay: &'h str, pos: usize) -> Option<Captures<'h>> {
+        let tail = self.tail.get_or_init(|| self.wrap("(?:", r")\z"));
+        tail.captures_at(hay, pos)
+            .filter(|caps| caps.get(0).unwrap().start() == pos)
+    }
+
+    fn wrap(&self, before: &str, after: &str) -> Regex {
+        // The pattern compiled on its own, so it compiles inside a group too.
+        Regex::new(&format!("{}{before}{}{after}", self.prefix, self.pattern)).unwrap()
+    }
+}
+
+/// Whether the regex crate reads `pattern` as Python's `re` does, as far as
+/// it will compile it at all. Syntax it rejects (backreferences, lookaround,
+/// `\Z`, possessive quantifiers...) needs no check here, since compiling it
+/// fails. This catches what it accepts with another meaning: `$` without
+/// MULTILINE, which in Python also matches before a final newline; nested
+/// and POSIX classes and set operations inside `[...]`; and verbose mode,
+/// which the regex crate applies inside classes too.
+fn same_meaning(pattern: &str, mut multiline: bool) -> bool {
+    let mut chars = pattern.chars().peekable();
+    let mut in_class = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' if in_class => return false,
+            '[' => {
+                in_class = true;
+                // A `]` right after `[` or `[^` is a literal.
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                }
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                }
+            }
+            ']' if in_class => in_class = false,
+            '&' | '-' | '~' if in_class && chars.peek() == Some(&c) => return false,
+            '$' if !in_class && !multiline => return false,
+            '(' if !in_class && chars.peek() == Some(&'?') => {
+                chars.next();
+                let mut inline = String::new();
+                while let Some(&flag) = chars.peek() {
+                    if !flag.is_ascii_alphabetic() && flag != '-' {
+                        break;
+                    }
+                    inline.push(flag);
+                    chars.next();
+                }
+                let enabled = inline.split('-').next().unwrap_or("");
+                if inline.contains('x') {
+                    return false;
+                }
+                if enabled.contains('m') {
+                    multiline = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Call `f` with each match in `hay` from `pos`, until it returns false.
+///
+/// Follows Python's rule for empty matches rather than the regex crate's:
+/// an empty match may come right after a non-empty one (`a*` finds `""`,
+/// `"aaa"`, `""`, `""` in `"baaab"`), but not right after another empty
+/// match at the same place.
+pub fn for_each_match(regex: &Regex, hay: &str, pos: usize, mut f: impl FnMut(&Captures) -> bool) {
+    let mut at = pos;
+    let mut last_empty = None;
+    while at <= hay.len() {
+        let Some(caps) = regex.captures_at(hay, at) else {
+            break;
+        };
+        let m = caps.get(0).unwrap();
+        if m.is_empty() && last_empty == Some(m.start()) {
+            match hay[m.start()..].chars().next() {
+                Some(c) => {
+                    at = m.start() + c.len_utf8();
+                    continue;
+                }
+                None => break,
+            }
+        }
+        if !f(&caps) {
+            break;
+        }
+        last_empty = m.is_empty().then_some(m.end());
+        at = m.end();
+    }
+}
+
+/// A piece of a parsed replacement template.
+#[derive(Debug, PartialEq)]
+pub enum Piece {
+    Literal(String),
+    Group(usize),
+}
+
+/// Parse an `re.sub` replacement template: `\1`, `\g<1>` and `\g<name>`
+/// refer to groups, `\n`-style escapes are processed, and other escapes of
+/// ASCII letters are errors, as in Python.
+pub fn parse_template(template: &str, re: &Compiled) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    let mut group = |literal: &mut String, pieces: &mut Vec<Piece>, index: usize| {
+        if index > re.groups() {
+            return Err(format!("invalid group reference {index}"));
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(std::mem::take(literal)));
+        }
+        pieces.push(Piece::Group(index));
+        Ok(())
+    };
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            literal.push(c);
+            continue;
+        }
+        let Some(escape) = chars.next() else {
+            return Err("bad escape (end of pattern)".to_owned());
+        };
+        match escape {
+            'g' => {
+                if chars.next() != Some('<') {
+                    return Err("missing <".to_owned());
+                }
+                let name: String = chars.by_ref().take_while(|&c| c != '>').collect();
+                let index = match name.parse::<usize>() {
+                    Ok(index) => index,
+                    Err(_) if name.is_empty() => return Err("missing group name".to_owned()),
+                    Err(_) => re
+                        .group_named(&name)
+                        .ok_or_else(|| format!("unknown group name '{name}'"))?,
+                };
+                group(&mut literal, &mut pieces, index)?;
+            }
+            '0' => {
+                // Up to two more octal digits.
+                let mut value = 0;
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                literal.push(char::from_u32(value).unwrap());
+            }
+            '1'..='9' => {
+                let mut digits = String::from(escape);
+                if let Some(&next) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    digits.push(next);
+                    chars.next();
+                    // Three octal digits are an octal escape.
+                    if let Some(&third) = chars.peek().filter(|c| c.is_digit(8)) {
+                        if digits.chars().all(|c| c.is_digit(8)) {
+                            digits.push(third);
+                            chars.next();
+                            let value = u32::from_str_radix(&digits, 8).unwrap();
+                            if value > 0o377 {
+                                return Err(format!(
+                                    "octal escape value \\{digits} outside of range 0-0o377"
+                                ));
+                            }
+                            literal.push(char::from_u32(value).unwrap());
+                            continue;
+                        }
+                    }
+                }
+                group(&mut literal, &mut pieces, digits.parse().unwrap())?;
+            }
+            'a' => literal.push('\x07'),
+            'b' => literal.push('\x08'),
+            'f' => literal.push('\x0c'),
+            'n' => literal.push('\n'),
+            'r' => literal.push('\r'),
+            't' => literal.push('\t'),
+            'v' => literal.push('\x0b'),
+            '\\' => literal.push('\\'),
+            c if c.is_ascii_alphabetic() => return Err(format!("bad escape \\{c}")),
+            c => {
+                literal.push('\\');
+                literal.push(c);
+            }
+        }
+    }
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+    Ok(pieces)
+}
+
+/// Append `pieces` to `out` with the groups of `caps` filled in; groups that
+/// did not take part in the match are empty.
+pub fn expand(pieces: &[Piece], caps: &Captures, out: &mut String) {
+    for piece in pieces {
+        match piece {
+            Piece::Literal(text) => out.push_str(text),
+            Piece::Group(index) => out.push_str(caps.get(*index).map_or("", |m| m.as_str())),
+        }
+    }
+}
+
+/// Byte offset of character `index` of `s`, clamped to `s`, as Python clamps
+/// `pos` and `endpos`.
+pub fn byte_offset(s: &str, index: i64, ascii: bool) -> usize {
+    if index <= 0 {
+        return 0;
+    }
+    if ascii {
+        return (index as usize).min(s.len());
+    }
+    s.char_indices()
+        .nth(index as usize)
+        .map_or(s.len(), |(offset, _)| offset)
+}
+
+/// Character index of byte offset `offset` of `s`.
+pub fn char_index(s: &str, offset: usize, ascii: bool) -> usize {
+    if ascii {
+        offset
+    } else {
+        s[..offset].chars().count()
+    }
+}
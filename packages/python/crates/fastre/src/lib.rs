@@ -0,0 +1,760 @@
+//! Native `re`-compatible module for RustPython, backed by the regex crate.
+//!
+//! `import fastre as re` gives `compile`, `match`, `search`, `fullmatch`,
+//! `findall`, `finditer`, `sub`, `subn` and `split`, with named groups and
+//! the `I`, `M`, `S` and `A` flags, running in linear time on large inputs
+//! where the pure-Python engine crawls.
+//!
+//! Patterns the regex crate cannot run with Python's meaning —
+//! backreferences, lookaround, `$` outside MULTILINE, verbose mode, bytes
+//! patterns and the like — are handed to the stdlib `re` instead, so every
+//! pattern works and only the common ones get faster. Errors in templates
+//! raise the stdlib's `re.error`.
+
+mod engine;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use rustpython_vm as vm;
+
+use engine::{byte_offset, char_index, expand, for_each_match, parse_template, Compiled, Piece};
+use regex::Captures;
+use vm::builtins::{PyBaseExceptionRef, PyInt, PyStr, PyStrRef, PyTypeRef};
+use vm::convert::ToPyObject;
+use vm::function::{FuncArgs, OptionalArg};
+use vm::{PyObjectRef, PyResult, VirtualMachine};
+
+/// Compiled patterns by pattern and flags, `None` for those left to the
+/// stdlib. Like `re`'s own cache it is dropped whole when full.
+type Cache = HashMap<(String, u32), Option<Arc<Compiled>>>;
+
+const MAX_CACHE: usize = 512;
+
+fn cache() -> &'static Mutex<Cache> {
+    static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached(pattern: &str, flags: u32) -> Option<Arc<Compiled>> {
+    let mut cache = cache().lock().unwrap();
+    let key = (pattern.to_owned(), flags);
+    if let Some(compiled) = cache.get(&key) {
+        return compiled.clone();
+    }
+    if cache.len() >= MAX_CACHE {
+        cache.clear();
+    }
+    let compiled = Compiled::new(pattern, flags).map(Arc::new);
+    cache.insert(key, compiled.clone());
+    compiled
+}
+
+// ---------------------------------------------------------------------------
+// Argument helpers
+// ---------------------------------------------------------------------------
+
+/// Bind `args` to the parameters `names` as a Python function would, the
+/// first `required` of them mandatory. Lets the `re` signatures take
+/// `count=`, `flags=` and friends by keyword.
+fn bind<const N: usize>(
+    func: &str,
+    args: FuncArgs,
+    names: [&str; N],
+    required: usize,
+    py_vm: &VirtualMachine,
+) -> PyResult<[Option<PyObjectRef>; N]> {
+    let FuncArgs { args, mut kwargs } = args;
+    if args.len() > N {
+        return Err(py_vm.new_type_error(format!(
+            "{func}() takes at most {N} arguments ({} given)",
+            args.len()
+        )));
+    }
+    let mut bound: [Option<PyObjectRef>; N] = std::array::from_fn(|_| None);
+    for (slot, arg) in bound.iter_mut().zip(args) {
+        *slot = Some(arg);
+    }
+    for (slot, name) in bound.iter_mut().zip(names) {
+        if let Some(value) = kwargs.swap_remove(name) {
+            if slot.is_some() {
+                return Err(py_vm.new_type_error(format!(
+                    "{func}() got multiple values for argument '{name}'"
+                )));
+            }
+            *slot = Some(value);
+        }
+    }
+    if let Some(name) = kwargs.keys().next() {
+        return Err(py_vm.new_type_error(format!(
+            "{func}() got an unexpected keyword argument '{name}'"
+        )));
+    }
+    if let Some(missing) = bound[..required].iter().position(Option::is_none) {
+        return Err(py_vm.new_type_error(format!(
+            "{func}() missing required argument '{}'",
+            names[missing]
+        )));
+    }
+    Ok(bound)
+}
+
+/// An integer argument, or `default` when it was not given or is None.
+#[allow(deprecated)] // payload() usage
+fn int_arg(value: &Option<PyObjectRef>, default: i64, py_vm: &VirtualMachine) -> PyResult<i64> {
+    match value {
+        Some(value) if !py_vm.is_none(value) => match value.payload::<PyInt>() {
+            Some(int) => int.try_to_primitive::<i64>(py_vm),
+            None => Err(py_vm.new_type_error("an integer is required".to_owned())),
+        },
+        _ => Ok(default),
+    }
+}
+
+fn str_arg(value: PyObjectRef, py_vm: &VirtualMachine) -> PyResult<PyStrRef> {
+    value.downcast::<PyStr>().map_err(|_| {
+        py_vm.new_type_error("cannot use a string pattern on a bytes-like object".to_owned())
+    })
+}
+
+/// An instance of the stdlib's `re.error`.
+fn re_error(message: String, py_vm: &VirtualMachine) -> PyBaseExceptionRef {
+    let message: PyObjectRef = py_vm.ctx.new_str(message).into();
+    let exc = py_vm
+        .import("re", 0)
+        .and_then(|re| re.get_attr("error", py_vm))
+        .and_then(|error| error.call((message,), py_vm));
+    match exc {
+        Ok(exc) => exc
+            .downcast()
+            .unwrap_or_else(|_| py_vm.new_type_error("re.error is not an exception".to_owned())),
+        Err(err) => err,
+    }
+}
+
+/// `pattern` compiled with `flags`: a native `Pattern` when the regex crate
+/// can run it, otherwise whatever `re.compile` returns.
+#[allow(deprecated)] // payload() usage
+fn compile_object(
+    pattern: PyObjectRef,
+    flags: Option<PyObjectRef>,
+    py_vm: &VirtualMachine,
+) -> PyResult<PyObjectRef> {
+    let bits = int_arg(&flags, 0, py_vm)?;
+    if pattern.payload::<PyPattern>().is_some() {
+        if bits != 0 {
+            return Err(py_vm.new_value_error(
+                "cannot process flags argument with a compiled pattern".to_owned(),
+            ));
+        }
+        return Ok(pattern);
+    }
+    if let (Some(source), Ok(bits)) = (pattern.payload::<PyStr>(), u32::try_from(bits)) {
+        if let Some(inner) = cached(source.as_str(), bits) {
+            return Ok(PyPattern { inner }.to_pyobject(py_vm));
+        }
+    }
+    let flags = flags.unwrap_or_else(|| py_vm.ctx.new_int(0).into());
+    py_vm
+        .import("re", 0)?
+        .get_attr("compile", py_vm)?
+        .call((pattern, flags), py_vm)
+}
+
+/// The module-level form of a `Pattern` method: compile the pattern, then
+/// call `method` on it with the other arguments. `names` starts with
+/// `pattern` and ends with `flags`.
+fn call_compiled<const N: usize>(
+    method: &'static str,
+    args: FuncArgs,
+    names: [&str; N],
+    required: usize,
+    py_vm: &VirtualMachine,
+) -> PyResult<PyObjectRef> {
+    let mut bound = bind(method, args, names, required, py_vm)?;
+    let flags = bound[N - 1].take();
+    let pattern = bound[0].take().unwrap();
+    let rest: Vec<PyObjectRef> = bound[1..N - 1].iter_mut().flat_map(Option::take).collect();
+    compile_object(pattern, flags, py_vm)?
+        .get_attr(method, py_vm)?
+        .call(FuncArgs::from(rest), py_vm)
+}
+
+// ---------------------------------------------------------------------------
+// Pattern
+// ---------------------------------------------------------------------------
+
+/// The part of a string a `Pattern` method looks at, from `pos` and
+/// `endpos`.
+struct Subject {
+    string: PyStrRef,
+    ascii: bool,
+    /// Byte range searched.
+    start: usize,
+    end: usize,
+    /// `pos` and `endpos` clamped to the string, in characters.
+    pos: usize,
+    endpos: usize,
+}
+
+impl Subject {
+    fn new(
+        string: PyObjectRef,
+        pos: &Option<PyObjectRef>,
+        endpos: &Option<PyObjectRef>,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<Self> {
+        let string = str_arg(string, py_vm)?;
+        let s = string.as_str();
+        let ascii = s.is_ascii();
+        let start = byte_offset(s, int_arg(pos, 0, py_vm)?, ascii);
+        let end = byte_offset(s, int_arg(endpos, i64::MAX, py_vm)?, ascii);
+        Ok(Self {
+            pos: char_index(s, start, ascii),
+            endpos: char_index(s, end, ascii),
+            string,
+            ascii,
+            start,
+            end,
+        })
+    }
+
+    /// The string up to `endpos`, which then counts as its end.
+    fn hay(&self) -> &str {
+        &self.string.as_str()[..self.end]
+    }
+
+    /// Whether there is anything to search: Python finds nothing when
+    /// `endpos` is before `pos`.
+    fn searchable(&self) -> bool {
+        self.start <= self.end
+    }
+
+    fn matched(&self, re: &Arc<Compiled>, caps: &Captures) -> PyMatch {
+        PyMatch {
+            re: re.clone(),
+            string: self.string.clone(),
+            ascii: self.ascii,
+            spans: caps
+                .iter()
+                .map(|m| m.map(|m| (m.start(), m.end())))
+                .collect(),
+            pos: self.pos,
+            endpos: self.endpos,
+        }
+    }
+}
+
+#[vm::pyclass(module = "fastre", name = "Pattern")]
+#[derive(Debug, vm::PyPayload)]
+struct PyPattern {
+    inner: Arc<Compiled>,
+}
+
+impl PyPattern {
+    fn subject(func: &str, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<Subject> {
+        let [string, pos, endpos] = bind(func, args, ["string", "pos", "endpos"], 1, py_vm)?;
+        Subject::new(string.unwrap(), &pos, &endpos, py_vm)
+    }
+
+    /// `sub` and `subn`: the new string and the number of replacements.
+    #[allow(deprecated)] // payload() usage
+    fn substitute(
+        &self,
+        func: &str,
+        args: FuncArgs,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(String, usize)> {
+        let [repl, string, count] = bind(func, args, ["repl", "string", "count"], 2, py_vm)?;
+        let repl = repl.unwrap();
+        let string = str_arg(string.unwrap(), py_vm)?;
+        let count = int_arg(&count, 0, py_vm)?;
+        let template: Option<Vec<Piece>> = match repl.payload::<PyStr>() {
+            Some(template) => Some(
+                parse_template(template.as_str(), &self.inner)
+                    .map_err(|message| re_error(message, py_vm))?,
+            ),
+            None if repl.is_callable() => None,
+            None => return Err(py_vm.new_type_error("expected str instance".to_owned())),
+        };
+
+        let subject = Subject::new(string.into(), &None, &None, py_vm)?;
+        let hay = subject.hay();
+        let mut out = String::with_capacity(hay.len());
+        let mut last = 0;
+        let mut replaced = 0;
+        let mut error = None;
+        for_each_match(&self.inner.regex, hay, 0, |caps| {
+            let m = caps.get(0).unwrap();
+            out.push_str(&hay[last..m.start()]);
+            match &template {
+                Some(template) => expand(template, caps, &mut out),
+                None => {
+                    let matched = subject.matched(&self.inner, caps).to_pyobject(py_vm);
+                    let replacement = repl.call((matched,), py_vm).and_then(|r| str_arg(r, py_vm));
+                    match replacement {
+                        Ok(replacement) => out.push_str(replacement.as_str()),
+                        Err(err) => {
+                            error = Some(err);
+                            return false;
+                        }
+                    }
+                }
+            }
+            last = m.end();
+            replaced += 1;
+            count <= 0 || replaced < count
+        });
+        if let Some(err) = error {
+            return Err(err);
+        }
+        out.push_str(&hay[last..]);
+        Ok((out, replaced as usize))
+    }
+}
+
+#[vm::pyclass]
+impl PyPattern {
+    #[pygetset]
+    fn pattern(&self) -> String {
+        self.inner.pattern.clone()
+    }
+
+    #[pygetset]
+    fn flags(&self) -> u32 {
+        // As in `re`, str patterns are always UNICODE unless ASCII.
+        if self.inner.flags & engine::ASCII != 0 {
+            self.inner.flags
+        } else {
+            self.inner.flags | engine::UNICODE
+        }
+    }
+
+    #[pygetset]
+    fn groups(&self) -> usize {
+        self.inner.groups()
+    }
+
+    /// Named groups and their numbers, as a dict.
+    #[pygetset]
+    fn groupindex(&self, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let dict = py_vm.ctx.new_dict();
+        for (index, name) in self.inner.regex.capture_names().enumerate() {
+            if let Some(name) = name {
+                dict.set_item(name, py_vm.ctx.new_int(index).into(), py_vm)?;
+            }
+        }
+        Ok(dict.into())
+    }
+
+    #[pymethod(name = "match")]
+    fn match_(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<Option<PyMatch>> {
+        let subject = Self::subject("match", args, py_vm)?;
+        if !subject.searchable() {
+            return Ok(None);
+        }
+        let caps = self.inner.match_at(subject.hay(), subject.start);
+        Ok(caps.map(|caps| subject.matched(&self.inner, &caps)))
+    }
+
+    #[pymethod]
+    fn search(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<Option<PyMatch>> {
+        let subject = Self::subject("search", args, py_vm)?;
+        if !subject.searchable() {
+            return Ok(None);
+        }
+        let caps = self.inner.regex.captures_at(subject.hay(), subject.start);
+        Ok(caps.map(|caps| subject.matched(&self.inner, &caps)))
+    }
+
+    #[pymethod]
+    fn fullmatch(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<Option<PyMatch>> {
+        let subject = Self::subject("fullmatch", args, py_vm)?;
+        if !subject.searchable() {
+            return Ok(None);
+        }
+        let caps = self.inner.fullmatch_at(subject.hay(), subject.start);
+        Ok(caps.map(|caps| subject.matched(&self.inner, &caps)))
+    }
+
+    /// All matches as strings, or as the group's string with one group, or
+    /// as tuples of group strings with several; unmatched groups are "".
+    #[pymethod]
+    fn findall(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let subject = Self::subject("findall", args, py_vm)?;
+        let mut found = Vec::new();
+        if subject.searchable() {
+            let text = |caps: &Captures, index: usize| -> PyObjectRef {
+                let text = caps.get(index).map_or("", |m| m.as_str());
+                py_vm.ctx.new_str(text).into()
+            };
+            for_each_match(&self.inner.regex, subject.hay(), subject.start, |caps| {
+                found.push(match self.inner.groups() {
+                    0 => text(caps, 0),
+                    1 => text(caps, 1),
+                    groups => {
+                        let items = (1..=groups).map(|index| text(caps, index)).collect();
+                        py_vm.ctx.new_tuple(items).into()
+                    }
+                });
+                true
+            });
+        }
+        Ok(py_vm.ctx.new_list(found).into())
+    }
+
+    /// An iterator over the matches. They are all found up front.
+    #[pymethod]
+    fn finditer(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let subject = Self::subject("finditer", args, py_vm)?;
+        let mut found = Vec::new();
+        if subject.searchable() {
+            for_each_match(&self.inner.regex, subject.hay(), subject.start, |caps| {
+                found.push(subject.matched(&self.inner, caps).to_pyobject(py_vm));
+                true
+            });
+        }
+        let list: PyObjectRef = py_vm.ctx.new_list(found).into();
+        py_vm.builtins.get_attr("iter", py_vm)?.call((list,), py_vm)
+    }
+
+    #[pymethod]
+    fn sub(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<String> {
+        Ok(self.substitute("sub", args, py_vm)?.0)
+    }
+
+    #[pymethod]
+    fn subn(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let (out, replaced) = self.substitute("subn", args, py_vm)?;
+        let items = vec![
+            py_vm.ctx.new_str(out).into(),
+            py_vm.ctx.new_int(replaced).into(),
+        ];
+        Ok(py_vm.ctx.new_tuple(items).into())
+    }
+
+    /// Split at each match, with the groups' strings (None when unmatched)
+    /// between the pieces, at most `maxsplit` times if it is positive.
+    #[pymethod]
+    fn split(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let [string, maxsplit] = bind("split", args, ["string", "maxsplit"], 1, py_vm)?;
+        let string = str_arg(string.unwrap(), py_vm)?;
+        let maxsplit = int_arg(&maxsplit, 0, py_vm)?;
+        let hay = string.as_str();
+        let mut parts: Vec<PyObjectRef> = Vec::new();
+        let mut last = 0;
+        let mut splits = 0;
+        if maxsplit >= 0 {
+            for_each_match(&self.inner.regex, hay, 0, |caps| {
+                let m = caps.get(0).unwrap();
+                parts.push(py_vm.ctx.new_str(&hay[last..m.start()]).into());
+                for group in caps.iter().skip(1) {
+                    parts.push(match group {
+                        Some(group) => py_vm.ctx.new_str(group.as_str()).into(),
+                        None => py_vm.ctx.none(),
+                    });
+                }
+                last = m.end();
+                splits += 1;
+                maxsplit == 0 || splits < maxsplit
+            });
+        }
+        parts.push(py_vm.ctx.new_str(&hay[last..]).into());
+        Ok(py_vm.ctx.new_list(parts).into())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Match
+// ---------------------------------------------------------------------------
+
+#[vm::pyclass(module = "fastre", name = "Match")]
+#[derive(Debug, vm::PyPayload)]
+struct PyMatch {
+    re: Arc<Compiled>,
+    string: PyStrRef,
+    ascii: bool,
+    /// Byte spans of the groups, group 0 first; None if a group did not
+    /// take part in the match.
+    spans: Vec<Option<(usize, usize)>>,
+    pos: usize,
+    endpos: usize,
+}
+
+impl PyMatch {
+    /// The index of a group given by number or name.
+    #[allow(deprecated)] // payload() usage
+    fn index(&self, group: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<usize> {
+        let index = if let Some(name) = group.payload::<PyStr>() {
+            self.re.group_named(name.as_str())
+        } else if let Some(index) = group.payload::<PyInt>() {
+            index
+                .try_to_primitive::<usize>(py_vm)
+                .ok()
+                .filter(|&index| index < self.spans.len())
+        } else {
+            None
+        };
+        index.ok_or_else(|| py_vm.new_index_error("no such group".to_owned()))
+    }
+
+    fn optional_index(
+        &self,
+        group: OptionalArg<PyObjectRef>,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<usize> {
+        match group {
+            OptionalArg::Present(ref group) => self.index(group, py_vm),
+            OptionalArg::Missing => Ok(0),
+        }
+    }
+
+    fn text(&self, index: usize) -> Option<&str> {
+        self.spans[index].map(|(start, end)| &self.string.as_str()[start..end])
+    }
+
+    fn text_or(&self, index: usize, default: &PyObjectRef, py_vm: &VirtualMachine) -> PyObjectRef {
+        match self.text(index) {
+            Some(text) => py_vm.ctx.new_str(text).into(),
+            None => default.clone(),
+        }
+    }
+
+    /// The character span of a group, (-1, -1) if it did not match.
+    fn char_span(&self, index: usize) -> (isize, isize) {
+        match self.spans[index] {
+            Some((start, end)) => {
+                let s = self.string.as_str();
+                let start_char = char_index(s, start, self.ascii);
+                let end_char = start_char + char_index(&s[start..], end - start, self.ascii);
+                (start_char as isize, end_char as isize)
+            }
+            None => (-1, -1),
+        }
+    }
+}
+
+#[vm::pyclass]
+impl PyMatch {
+    /// `group()` is the whole match, `group(g)` one group, and several
+    /// arguments give a tuple.
+    #[pymethod]
+    fn group(&self, args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let none = py_vm.ctx.none();
+        match args.args.as_slice() {
+            [] => Ok(self.text_or(0, &none, py_vm)),
+            [group] => Ok(self.text_or(self.index(group, py_vm)?, &none, py_vm)),
+            groups => {
+                let items = groups
+                    .iter()
+                    .map(|group| Ok(self.text_or(self.index(group, py_vm)?, &none, py_vm)))
+                    .collect::<PyResult<Vec<_>>>()?;
+                Ok(py_vm.ctx.new_tuple(items).into())
+            }
+        }
+    }
+
+    #[pymethod]
+    fn groups(&self, default: OptionalArg<PyObjectRef>, py_vm: &VirtualMachine) -> PyObjectRef {
+        let default = default.unwrap_or_none(py_vm);
+        let items = (1..self.spans.len())
+            .map(|index| self.text_or(index, &default, py_vm))
+            .collect();
+        py_vm.ctx.new_tuple(items).into()
+    }
+
+    #[pymethod]
+    fn groupdict(
+        &self,
+        default: OptionalArg<PyObjectRef>,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        let default = default.unwrap_or_none(py_vm);
+        let dict = py_vm.ctx.new_dict();
+        for (index, name) in self.re.regex.capture_names().enumerate() {
+            if let Some(name) = name {
+                dict.set_item(name, self.text_or(index, &default, py_vm), py_vm)?;
+            }
+        }
+        Ok(dict.into())
+    }
+
+    #[pymethod]
+    fn start(&self, group: OptionalArg<PyObjectRef>, py_vm: &VirtualMachine) -> PyResult<isize> {
+        Ok(self.char_span(self.optional_index(group, py_vm)?).0)
+    }
+
+    #[pymethod]
+    fn end(&self, group: OptionalArg<PyObjectRef>, py_vm: &VirtualMachine) -> PyResult<isize> {
+        Ok(self.char_span(self.optional_index(group, py_vm)?).1)
+    }
+
+    #[pymethod]
+    fn span(
+        &self,
+        group: OptionalArg<PyObjectRef>,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(isize, isize)> {
+        Ok(self.char_span(self.optional_index(group, py_vm)?))
+    }
+
+    /// The template filled in as `sub` would fill it for this match.
+    #[pymethod]
+    fn expand(&self, template: PyStrRef, py_vm: &VirtualMachine) -> PyResult<String> {
+        let pieces = parse_template(template.as_str(), &self.re)
+            .map_err(|message| re_error(message, py_vm))?;
+        let mut out = String::new();
+        for piece in &pieces {
+            match piece {
+                Piece::Literal(text) => out.push_str(text),
+                Piece::Group(index) => out.push_str(self.text(*index).unwrap_or("")),
+            }
+        }
+        Ok(out)
+    }
+
+    #[pygetset]
+    fn string(&self) -> PyStrRef {
+        self.string.clone()
+    }
+
+    #[pygetset]
+    fn re(&self) -> PyPattern {
+        PyPattern {
+            inner: self.re.clone(),
+        }
+    }
+
+    #[pygetset]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[pygetset]
+    fn endpos(&self) -> usize {
+        self.endpos
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Python module: fastre
+// ---------------------------------------------------------------------------
+
+#[allow(non_snake_case)]
+#[vm::pymodule]
+pub mod fastre {
+    use super::*;
+    use vm::class::PyClassImpl;
+
+    #[pyattr]
+    const I: u32 = engine::IGNORECASE;
+    #[pyattr]
+    const IGNORECASE: u32 = engine::IGNORECASE;
+    #[pyattr]
+    const M: u32 = engine::MULTILINE;
+    #[pyattr]
+    const MULTILINE: u32 = engine::MULTILINE;
+    #[pyattr]
+    const S: u32 = engine::DOTALL;
+    #[pyattr]
+    const DOTALL: u32 = engine::DOTALL;
+    #[pyattr]
+    const U: u32 = engine::UNICODE;
+    #[pyattr]
+    const UNICODE: u32 = engine::UNICODE;
+    #[pyattr]
+    const X: u32 = engine::VERBOSE;
+    #[pyattr]
+    const VERBOSE: u32 = engine::VERBOSE;
+    #[pyattr]
+    const A: u32 = engine::ASCII;
+    #[pyattr]
+    const ASCII: u32 = engine::ASCII;
+
+    #[pyattr]
+    fn Pattern(vm: &VirtualMachine) -> PyTypeRef {
+        PyPattern::make_class(&vm.ctx)
+    }
+
+    #[pyattr]
+    fn Match(vm: &VirtualMachine) -> PyTypeRef {
+        PyMatch::make_class(&vm.ctx)
+    }
+
+    /// The stdlib's `re.error`, which template errors and the patterns
+    /// handed to the stdlib raise.
+    #[pyattr]
+    fn error(vm: &VirtualMachine) -> PyObjectRef {
+        vm.import("re", 0)
+            .and_then(|re| re.get_attr("error", vm))
+            .unwrap_or_else(|_| vm.ctx.exceptions.value_error.to_owned().into())
+    }
+
+    #[pyfunction]
+    fn compile(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let [pattern, flags] = bind("compile", args, ["pattern", "flags"], 1, py_vm)?;
+        compile_object(pattern.unwrap(), flags, py_vm)
+    }
+
+    #[pyfunction(name = "match")]
+    fn match_(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        call_compiled("match", args, ["pattern", "string", "flags"], 2, py_vm)
+    }
+
+    #[pyfunction]
+    fn search(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        call_compiled("search", args, ["pattern", "string", "flags"], 2, py_vm)
+    }
+
+    #[pyfunction]
+    fn fullmatch(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        call_compiled("fullmatch", args, ["pattern", "string", "flags"], 2, py_vm)
+    }
+
+    #[pyfunction]
+    fn findall(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        call_compiled("findall", args, ["pattern", "string", "flags"], 2, py_vm)
+    }
+
+    #[pyfunction]
+    fn finditer(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        call_compiled("finditer", args, ["pattern", "string", "flags"], 2, py_vm)
+    }
+
+    #[pyfunction]
+    fn sub(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let names = ["pattern", "repl", "string", "count", "flags"];
+        call_compiled("sub", args, names, 3, py_vm)
+    }
+
+    #[pyfunction]
+    fn subn(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let names = ["pattern", "repl", "string", "count", "flags"];
+        call_compiled("subn", args, names, 3, py_vm)
+    }
+
+    #[pyfunction]
+    fn split(args: FuncArgs, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let names = ["pattern", "string", "maxsplit", "flags"];
+        call_compiled("split", args, names, 2, py_vm)
+    }
+
+    #[pyfunction]
+    fn escape(pattern: PyObjectRef, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        py_vm
+            .import("re", 0)?
+            .get_attr("escape", py_vm)?
+            .call((pattern,), py_vm)
+    }
+
+    /// Clear the compiled-pattern cache.
+    #[pyfunction]
+    fn purge() {
+        cache().lock().unwrap().clear();
+    }
+}
+
+/// Public entry point for module registration.
+pub fn module_def(ctx: &vm::Context) -> &'static vm::builtins::PyModuleDef {
+    fastre::module_def(ctx)
+}
@@ -4,6 +4,8 @@
 //!   invoke(method_ptr, method_len, args_ptr, args_len, out_ptr, out_cap) -> i32
 //!   __alloc(size) -> ptr
 //!   __dealloc(ptr, size)
+//!   __abi_version() -> u32
+//!   __abi_capabilities() -> u32
 //!
 //! Each method receives JSON args and returns a JSON result.
 //! This POC implements a few basic operations to prove the bridge works.
@@ -33,6 +35,27 @@ pub extern "C" fn __dealloc(ptr: *mut u8, size: usize) {
     unsafe { dealloc(ptr, layout) }
 }
 
+// ---------------------------------------------------------------------------
+// ABI handshake
+// ---------------------------------------------------------------------------
+
+/// Version of the `invoke` calling convention. Bump it whenever the
+/// signature or the output-buffer protocol changes incompatibly.
+const ABI_VERSION: u32 = 1;
+
+/// `invoke` is exported.
+const CAP_INVOKE: u32 = 1 << 0;
+
+#[no_mangle]
+pub extern "C" fn __abi_version() -> u32 {
+    ABI_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn __abi_capabilities() -> u32 {
+    CAP_INVOKE
+}
+
 // ---------------------------------------------------------------------------
 // invoke() — main dispatch entry point
 // ---------------------------------------------------------------------------
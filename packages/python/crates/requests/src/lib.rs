@@ -0,0 +1,437 @@
+//! Native `requests` module for RustPython.
+//!
+//! Implements the part of the `requests` API that data-fetching snippets
+//! use: `get`/`post` and friends with `params`, `headers`, `data` and
+//! `json`, and a `Response` with `status_code`, `headers`, `text`,
+//! `json()` and `raise_for_status()`. Requests go through
+//! `_codepod.fetch`, so the host's network policy applies to them as to
+//! any other fetch from the sandbox.
+//!
+//! The host hands response bodies back as text; `content` is that text
+//! encoded as UTF-8.
+
+use rustpython_vm as vm;
+
+use vm::builtins::{PyBytes, PyDict, PyInt, PyStr, PyStrRef, PyTypeRef};
+use vm::function::KwArgs;
+use vm::{AsObject, PyObjectRef, PyRef, PyResult, VirtualMachine};
+
+/// Keyword arguments of `requests` that are accepted and have no effect:
+/// the host applies its own timeouts, certificate checks and redirects.
+const IGNORED_KWARGS: &[&str] = &["timeout", "verify", "allow_redirects", "stream"];
+
+// ---------------------------------------------------------------------------
+// Exceptions
+// ---------------------------------------------------------------------------
+
+vm::common::static_cell! {
+    static REQUEST_EXCEPTION: PyTypeRef;
+    static HTTP_ERROR: PyTypeRef;
+    static CONNECTION_ERROR: PyTypeRef;
+}
+
+/// `requests.RequestException`, the base of the others. Like the real one it
+/// is an `OSError`.
+fn request_exception(py_vm: &VirtualMachine) -> &'static PyTypeRef {
+    REQUEST_EXCEPTION.get_or_init(|| {
+        py_vm.ctx.new_exception_type(
+            "requests",
+            "RequestException",
+            Some(vec![py_vm.ctx.exceptions.os_error.to_owned()]),
+        )
+    })
+}
+
+/// Raised by `Response.raise_for_status` for 4xx and 5xx responses.
+fn http_error(py_vm: &VirtualMachine) -> &'static PyTypeRef {
+    HTTP_ERROR.get_or_init(|| {
+        py_vm.ctx.new_exception_type(
+            "requests",
+            "HTTPError",
+            Some(vec![request_exception(py_vm).clone()]),
+        )
+    })
+}
+
+/// Raised when no response arrived, including requests the host refused.
+fn connection_error(py_vm: &VirtualMachine) -> &'static PyTypeRef {
+    CONNECTION_ERROR.get_or_init(|| {
+        py_vm.ctx.new_exception_type(
+            "requests",
+            "ConnectionError",
+            Some(vec![request_exception(py_vm).clone()]),
+        )
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Conversion helpers
+// ---------------------------------------------------------------------------
+
+/// The entries of a dict, with string keys.
+#[allow(deprecated)] // payload() usage
+fn dict_entries(obj: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<Vec<(String, PyObjectRef)>> {
+    let Some(dict) = obj.payload::<PyDict>() else {
+        return Err(py_vm.new_type_error("expected a dict".to_owned()));
+    };
+    let mut entries = Vec::new();
+    for (key, value) in dict.into_iter() {
+        let key = match key.payload::<PyStr>() {
+            Some(s) => s.as_str().to_owned(),
+            None => key.str(py_vm)?.as_str().to_owned(),
+        };
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+/// The entries of a dict with both keys and values as strings, as for
+/// headers.
+fn string_pairs(obj: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<Vec<(String, String)>> {
+    dict_entries(obj, py_vm)?
+        .into_iter()
+        .map(|(key, value)| Ok((key, value.str(py_vm)?.as_str().to_owned())))
+        .collect()
+}
+
+/// Call `module.function(arg)` and return the result as a string.
+fn call_to_string(
+    module: &str,
+    function: &str,
+    arg: PyObjectRef,
+    py_vm: &VirtualMachine,
+) -> PyResult<String> {
+    let result = py_vm
+        .import(module, 0)?
+        .get_attr(function, py_vm)?
+        .call((arg,), py_vm)?;
+    Ok(result.str(py_vm)?.as_str().to_owned())
+}
+
+/// Turn a `data=` argument into a request body, with the content type it
+/// implies if any.
+#[allow(deprecated)] // payload() usage
+fn data_body(
+    data: PyObjectRef,
+    py_vm: &VirtualMachine,
+) -> PyResult<(String, Option<&'static str>)> {
+    if data.payload::<PyDict>().is_some() {
+        let form = call_to_string("urllib.parse", "urlencode", data, py_vm)?;
+        return Ok((form, Some("application/x-www-form-urlencoded")));
+    }
+    if let Some(bytes) = data.payload::<PyBytes>() {
+        return Ok((String::from_utf8_lossy(bytes.as_bytes()).into_owned(), None));
+    }
+    Ok((data.str(py_vm)?.as_str().to_owned(), None))
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sending a request
+// ---------------------------------------------------------------------------
+
+/// Make a request from `requests`-style keyword arguments through
+/// `_codepod.fetch` and wrap the reply.
+#[allow(deprecated)] // payload() usage
+fn send(method: &str, url: &str, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+    let mut url = url.to_owned();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut data = None;
+    let mut json = None;
+    for (name, value) in kwargs.into_iter() {
+        if py_vm.is_none(&value) {
+            continue;
+        }
+        match name.as_str() {
+            "params" => {
+                let query = call_to_string("urllib.parse", "urlencode", value, py_vm)?;
+                if !query.is_empty() {
+                    url.push(if url.contains('?') { '&' } else { '?' });
+                    url.push_str(&query);
+                }
+            }
+            "headers" => headers = string_pairs(&value, py_vm)?,
+            "data" => data = Some(value),
+            "json" => json = Some(value),
+            name if IGNORED_KWARGS.contains(&name) => {}
+            other => {
+                return Err(py_vm.new_type_error(format!(
+                    "request() got an unexpected keyword argument '{other}'"
+                )))
+            }
+        }
+    }
+
+    // As in requests, `data` wins when both bodies are given.
+    let (body, content_type) = match (data, json) {
+        (Some(data), _) => {
+            let (body, content_type) = data_body(data, py_vm)?;
+            (Some(body), content_type)
+        }
+        (None, Some(json)) => (
+            Some(call_to_string("json", "dumps", json, py_vm)?),
+            Some("application/json"),
+        ),
+        (None, None) => (None, None),
+    };
+    if let Some(content_type) = content_type {
+        if !headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        {
+            headers.push(("Content-Type".to_owned(), content_type.to_owned()));
+        }
+    }
+
+    let headers_dict = py_vm.ctx.new_dict();
+    for (name, value) in &headers {
+        headers_dict.set_item(
+            name.as_str(),
+            py_vm.ctx.new_str(value.as_str()).into(),
+            py_vm,
+        )?;
+    }
+    let body: PyObjectRef = match body {
+        Some(body) => py_vm.ctx.new_str(body).into(),
+        None => py_vm.ctx.none(),
+    };
+    let fetch = py_vm.import("_codepod", 0)?.get_attr("fetch", py_vm)?;
+    let method_arg: PyObjectRef = py_vm.ctx.new_str(method.to_ascii_uppercase()).into();
+    let url_arg: PyObjectRef = py_vm.ctx.new_str(url.as_str()).into();
+    let reply = fetch.call((method_arg, url_arg, headers_dict.into(), body), py_vm)?;
+
+    let mut response = PyResponse {
+        status_code: 0,
+        url,
+        headers: Vec::new(),
+        text: String::new(),
+    };
+    let mut error = None;
+    for (key, value) in dict_entries(&reply, py_vm)? {
+        if py_vm.is_none(&value) {
+            continue;
+        }
+        match key.as_str() {
+            "status" => {
+                response.status_code = value
+                    .payload::<PyInt>()
+                    .and_then(|status| status.try_to_primitive::<u16>(py_vm).ok())
+                    .unwrap_or(0)
+            }
+            "headers" => response.headers = string_pairs(&value, py_vm)?,
+            "body" => response.text = value.str(py_vm)?.as_str().to_owned(),
+            "error" => error = Some(value.str(py_vm)?.as_str().to_owned()),
+            _ => {}
+        }
+    }
+    // HTTP error statuses are still responses; only a missing one fails.
+    if response.status_code == 0 {
+        return Err(py_vm.new_exception_msg(
+            connection_error(py_vm).clone(),
+            format!(
+                "{} {} failed: {}",
+                method.to_ascii_uppercase(),
+                response.url,
+                error.as_deref().unwrap_or("no response")
+            ),
+        ));
+    }
+    Ok(response)
+}
+
+// ---------------------------------------------------------------------------
+// Response
+// ---------------------------------------------------------------------------
+
+#[vm::pyclass(module = "requests", name = "Response")]
+#[derive(Debug, vm::PyPayload)]
+struct PyResponse {
+    status_code: u16,
+    url: String,
+    headers: Vec<(String, String)>,
+    text: String,
+}
+
+#[vm::pyclass]
+impl PyResponse {
+    #[pygetset]
+    fn status_code(&self) -> u16 {
+        self.status_code
+    }
+
+    #[pygetset]
+    fn reason(&self) -> String {
+        reason_phrase(self.status_code).to_owned()
+    }
+
+    #[pygetset]
+    fn ok(&self) -> bool {
+        self.status_code < 400
+    }
+
+    #[pygetset]
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// The response headers as a dict, with names as the host reported
+    /// them (lower-case for most hosts).
+    #[pygetset]
+    fn headers(&self, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let dict = py_vm.ctx.new_dict();
+        for (name, value) in &self.headers {
+            dict.set_item(
+                name.as_str(),
+                py_vm.ctx.new_str(value.as_str()).into(),
+                py_vm,
+            )?;
+        }
+        Ok(dict.into())
+    }
+
+    #[pygetset]
+    fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    #[pygetset]
+    fn content(&self, py_vm: &VirtualMachine) -> PyObjectRef {
+        py_vm.ctx.new_bytes(self.text.as_bytes().to_vec()).into()
+    }
+
+    #[pymethod]
+    fn json(&self, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let loads = py_vm.import("json", 0)?.get_attr("loads", py_vm)?;
+        let text: PyObjectRef = py_vm.ctx.new_str(self.text.as_str()).into();
+        loads.call((text,), py_vm)
+    }
+
+    /// Raise `HTTPError`, with this response as its `response`, for a 4xx
+    /// or 5xx status.
+    #[pymethod]
+    fn raise_for_status(zelf: PyRef<Self>, py_vm: &VirtualMachine) -> PyResult<()> {
+        let side = match zelf.status_code {
+            400..=499 => "Client",
+            500..=599 => "Server",
+            _ => return Ok(()),
+        };
+        let err = py_vm.new_exception_msg(
+            http_error(py_vm).clone(),
+            format!(
+                "{} {side} Error: {} for url: {}",
+                zelf.status_code,
+                reason_phrase(zelf.status_code),
+                zelf.url
+            ),
+        );
+        let response: PyObjectRef = zelf.into();
+        err.as_object().set_attr("response", response, py_vm)?;
+        Err(err)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Python module: requests
+// ---------------------------------------------------------------------------
+
+#[allow(non_snake_case)]
+#[vm::pymodule]
+pub mod requests {
+    use super::*;
+    use vm::class::PyClassImpl;
+
+    #[pyattr]
+    fn __version__(_vm: &VirtualMachine) -> String {
+        "2.32.0".to_owned()
+    }
+
+    #[pyattr]
+    fn Response(vm: &VirtualMachine) -> PyTypeRef {
+        PyResponse::make_class(&vm.ctx)
+    }
+
+    #[pyattr]
+    fn RequestException(vm: &VirtualMachine) -> PyTypeRef {
+        request_exception(vm).clone()
+    }
+
+    #[pyattr]
+    fn HTTPError(vm: &VirtualMachine) -> PyTypeRef {
+        http_error(vm).clone()
+    }
+
+    #[pyattr]
+    fn ConnectionError(vm: &VirtualMachine) -> PyTypeRef {
+        connection_error(vm).clone()
+    }
+
+    /// `requests.request(method, url, **kwargs)`.
+    #[pyfunction]
+    fn request(
+        method: PyStrRef,
+        url: PyStrRef,
+        kwargs: KwArgs,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<PyResponse> {
+        send(method.as_str(), url.as_str(), kwargs, py_vm)
+    }
+
+    #[pyfunction]
+    fn get(url: PyStrRef, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+        send("GET", url.as_str(), kwargs, py_vm)
+    }
+
+    #[pyfunction]
+    fn post(url: PyStrRef, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+        send("POST", url.as_str(), kwargs, py_vm)
+    }
+
+    #[pyfunction]
+    fn put(url: PyStrRef, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+        send("PUT", url.as_str(), kwargs, py_vm)
+    }
+
+    #[pyfunction]
+    fn patch(url: PyStrRef, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+        send("PATCH", url.as_str(), kwargs, py_vm)
+    }
+
+    #[pyfunction]
+    fn delete(url: PyStrRef, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+        send("DELETE", url.as_str(), kwargs, py_vm)
+    }
+
+    #[pyfunction]
+    fn head(url: PyStrRef, kwargs: KwArgs, py_vm: &VirtualMachine) -> PyResult<PyResponse> {
+        send("HEAD", url.as_str(), kwargs, py_vm)
+    }
+}
+
+/// Public entry point for module registration.
+pub fn module_def(ctx: &vm::Context) -> &'static vm::builtins::PyModuleDef {
+    requests::module_def(ctx)
+}
@@ -0,0 +1,9 @@
+"""
+sklearn - machine learning for the sandbox
+
+A subset of scikit-learn's API backed by the native _sklearn_native
+module: linear and logistic regression, decision trees, random forests,
+k-means, train_test_split and the common metrics.
+"""
+
+__version__ = "1.3.0"
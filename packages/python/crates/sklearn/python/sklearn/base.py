@@ -0,0 +1,124 @@
+"""Shared pieces of the estimators: parameters, input conversion, scoring."""
+
+import inspect
+import random
+
+from sklearn.exceptions import NotFittedError
+
+try:
+    import numpy as _np
+except ImportError:
+    _np = None
+
+
+def _rows(X):
+    """X as a list of rows. numpy arrays and DataFrames are converted."""
+    if hasattr(X, "to_numpy"):
+        X = X.to_numpy()
+    if hasattr(X, "tolist"):
+        X = X.tolist()
+    rows = list(X)
+    for row in rows:
+        if not isinstance(row, (list, tuple)):
+            raise ValueError(
+                "Expected 2D array, got 1D array instead. Reshape your data "
+                "using array.reshape(-1, 1) for a single feature."
+            )
+    return rows
+
+
+def _column(y):
+    """y as a flat list."""
+    if hasattr(y, "to_numpy"):
+        y = y.to_numpy()
+    if hasattr(y, "tolist"):
+        y = y.tolist()
+    return list(y)
+
+
+def _array(values):
+    """A numpy array of values when numpy is available, else the list."""
+    return _np.array(values) if _np is not None else values
+
+
+def _seed(random_state):
+    """A 64-bit seed for the native code from a random_state."""
+    if random_state is None:
+        return random.getrandbits(64)
+    return int(random_state) % (1 << 64)
+
+
+def _encode(y):
+    """Sorted class labels and y as indices into them."""
+    classes = sorted(set(y))
+    index = {label: i for i, label in enumerate(classes)}
+    return classes, [index[label] for label in y]
+
+
+def _argmax(row):
+    return max(range(len(row)), key=row.__getitem__)
+
+
+class BaseEstimator:
+    """Parameter handling shared by all estimators. Parameters are the
+    arguments of __init__, stored under the same names."""
+
+    @classmethod
+    def _param_names(cls):
+        params = inspect.signature(cls.__init__).parameters
+        return [name for name in params if name != "self"]
+
+    def get_params(self, deep=True):
+        return {name: getattr(self, name) for name in self._param_names()}
+
+    def set_params(self, **params):
+        valid = self._param_names()
+        for name, value in params.items():
+            if name not in valid:
+                raise ValueError(
+                    f"Invalid parameter {name!r} for estimator {type(self).__name__}."
+                )
+            setattr(self, name, value)
+        return self
+
+    def __repr__(self):
+        params = inspect.signature(type(self).__init__).parameters
+        changed = [
+            f"{name}={getattr(self, name)!r}"
+            for name, param in params.items()
+            if name != "self" and getattr(self, name) != param.default
+        ]
+        return f"{type(self).__name__}({', '.join(changed)})"
+
+    def _check_fitted(self, attribute):
+        if not hasattr(self, attribute):
+            raise NotFittedError(
+                f"This {type(self).__name__} instance is not fitted yet. Call "
+                "'fit' with appropriate arguments before using this estimator."
+            )
+
+
+class ClassifierMixin:
+    _estimator_type = "classifier"
+
+    def score(self, X, y):
+        from sklearn.metrics import accuracy_score
+
+        return accuracy_score(y, self.predict(X))
+
+
+class RegressorMixin:
+    _estimator_type = "regressor"
+
+    def score(self, X, y):
+        from sklearn.metrics import r2_score
+
+        return r2_score(y, self.predict(X))
+
+
+class ClusterMixin:
+    _estimator_type = "clusterer"
+
+    def fit_predict(self, X, y=None):
+        self.fit(X)
+        return self.labels_
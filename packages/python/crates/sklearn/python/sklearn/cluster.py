@@ -0,0 +1,74 @@
+"""Clustering: KMeans."""
+
+import math
+
+import _sklearn_native as _native
+
+from sklearn.base import BaseEstimator, ClusterMixin, _array, _rows, _seed
+
+
+class KMeans(ClusterMixin, BaseEstimator):
+    def __init__(
+        self,
+        n_clusters=8,
+        init="k-means++",
+        n_init="auto",
+        max_iter=300,
+        tol=1e-4,
+        random_state=None,
+    ):
+        self.n_clusters = n_clusters
+        self.init = init
+        self.n_init = n_init
+        self.max_iter = max_iter
+        self.tol = tol
+        self.random_state = random_state
+
+    def fit(self, X, y=None):
+        if self.init not in ("k-means++", "random"):
+            raise ValueError(f"init={self.init!r} is not supported; use 'k-means++' or 'random'")
+        random_init = self.init == "random"
+        n_init = self.n_init
+        if n_init == "auto":
+            n_init = 10 if random_init else 1
+        rows = _rows(X)
+        centers, labels, inertia, n_iter = _native.kmeans_fit(
+            rows,
+            int(self.n_clusters),
+            int(n_init),
+            int(self.max_iter),
+            float(self.tol),
+            random_init,
+            _seed(self.random_state),
+        )
+        self._centers = centers
+        self.cluster_centers_ = _array(centers)
+        self.labels_ = _array(labels)
+        self.inertia_ = inertia
+        self.n_iter_ = n_iter
+        self.n_features_in_ = len(rows[0])
+        return self
+
+    def predict(self, X):
+        self._check_fitted("cluster_centers_")
+        labels, _ = _native.kmeans_predict(_rows(X), self._centers)
+        return _array(labels)
+
+    def transform(self, X):
+        """Distances from each row to every cluster center."""
+        self._check_fitted("cluster_centers_")
+        return _array(
+            [
+                [math.dist(row, center) for center in self._centers]
+                for row in _rows(X)
+            ]
+        )
+
+    def fit_transform(self, X, y=None):
+        return self.fit(X).transform(X)
+
+    def score(self, X, y=None):
+        """Opposite of the inertia of X against the fitted centers."""
+        self._check_fitted("cluster_centers_")
+        _, distances = _native.kmeans_predict(_rows(X), self._centers)
+        return -sum(distances)
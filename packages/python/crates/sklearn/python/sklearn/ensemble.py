@@ -0,0 +1,157 @@
+"""Random forests: RandomForestClassifier and RandomForestRegressor."""
+
+import _sklearn_native as _native
+
+from sklearn.base import (
+    BaseEstimator,
+    ClassifierMixin,
+    RegressorMixin,
+    _argmax,
+    _array,
+    _column,
+    _encode,
+    _rows,
+    _seed,
+)
+from sklearn.tree import DecisionTreeClassifier, DecisionTreeRegressor, _native_params
+
+
+class BaseForest(BaseEstimator):
+    _tree_class = None
+
+    def __init__(
+        self,
+        n_estimators=100,
+        criterion=None,
+        max_depth=None,
+        min_samples_split=2,
+        min_samples_leaf=1,
+        max_features=None,
+        bootstrap=True,
+        random_state=None,
+    ):
+        self.n_estimators = n_estimators
+        self.criterion = criterion
+        self.max_depth = max_depth
+        self.min_samples_split = min_samples_split
+        self.min_samples_leaf = min_samples_leaf
+        self.max_features = max_features
+        self.bootstrap = bootstrap
+        self.random_state = random_state
+
+    def _fit(self, X, target, n_classes):
+        rows = _rows(X)
+        n_features = len(rows[0]) if rows else 0
+        self._trees = _native.fit_forest(
+            rows,
+            target,
+            n_classes,
+            *_native_params(self, len(rows), n_features),
+            int(self.n_estimators),
+            bool(self.bootstrap),
+            _seed(self.random_state),
+        )
+        self.n_features_in_ = n_features
+        self.estimators_ = [self._wrap(tree) for tree in self._trees]
+        return self
+
+    def _wrap(self, tree):
+        """A fitted tree estimator around one of the forest's trees."""
+        estimator = self._tree_class(
+            criterion=self.criterion,
+            max_depth=self.max_depth,
+            min_samples_split=self.min_samples_split,
+            min_samples_leaf=self.min_samples_leaf,
+            max_features=self.max_features,
+        )
+        estimator.tree_ = tree
+        estimator.n_features_in_ = self.n_features_in_
+        if hasattr(self, "_classes"):
+            estimator._classes = self._classes
+            estimator.classes_ = self.classes_
+            estimator.n_classes_ = self.n_classes_
+        return estimator
+
+    @property
+    def feature_importances_(self):
+        self._check_fitted("_trees")
+        total = [0.0] * self.n_features_in_
+        for tree in self._trees:
+            for i, value in enumerate(tree.feature_importances):
+                total[i] += value / len(self._trees)
+        return _array(total)
+
+
+class RandomForestClassifier(ClassifierMixin, BaseForest):
+    _tree_class = DecisionTreeClassifier
+
+    def __init__(
+        self,
+        n_estimators=100,
+        criterion="gini",
+        max_depth=None,
+        min_samples_split=2,
+        min_samples_leaf=1,
+        max_features="sqrt",
+        bootstrap=True,
+        random_state=None,
+    ):
+        super().__init__(
+            n_estimators=n_estimators,
+            criterion=criterion,
+            max_depth=max_depth,
+            min_samples_split=min_samples_split,
+            min_samples_leaf=min_samples_leaf,
+            max_features=max_features,
+            bootstrap=bootstrap,
+            random_state=random_state,
+        )
+
+    def fit(self, X, y):
+        classes, codes = _encode(_column(y))
+        self._classes = classes
+        self.classes_ = _array(classes)
+        self.n_classes_ = len(classes)
+        return self._fit(X, codes, len(classes))
+
+    def predict_proba(self, X):
+        self._check_fitted("_trees")
+        return _array(_native.forest_predict(self._trees, _rows(X)))
+
+    def predict(self, X):
+        self._check_fitted("_trees")
+        proba = _native.forest_predict(self._trees, _rows(X))
+        return _array([self._classes[_argmax(row)] for row in proba])
+
+
+class RandomForestRegressor(RegressorMixin, BaseForest):
+    _tree_class = DecisionTreeRegressor
+
+    def __init__(
+        self,
+        n_estimators=100,
+        criterion="squared_error",
+        max_depth=None,
+        min_samples_split=2,
+        min_samples_leaf=1,
+        max_features=1.0,
+        bootstrap=True,
+        random_state=None,
+    ):
+        super().__init__(
+            n_estimators=n_estimators,
+            criterion=criterion,
+            max_depth=max_depth,
+            min_samples_split=min_samples_split,
+            min_samples_leaf=min_samples_leaf,
+            max_features=max_features,
+            bootstrap=bootstrap,
+            random_state=random_state,
+        )
+
+    def fit(self, X, y):
+        return self._fit(X, _column(y), 0)
+
+    def predict(self, X):
+        self._check_fitted("_trees")
+        return _array([value[0] for value in _native.forest_predict(self._trees, _rows(X))])
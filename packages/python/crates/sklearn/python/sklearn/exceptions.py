@@ -0,0 +1,9 @@
+"""Exceptions and warnings raised by the estimators."""
+
+
+class NotFittedError(ValueError, AttributeError):
+    """An estimator was used before fit() was called."""
+
+
+class ConvergenceWarning(UserWarning):
+    """An iterative solver stopped at max_iter before converging."""
@@ -0,0 +1,123 @@
+"""Linear models: LinearRegression and LogisticRegression."""
+
+import math
+import warnings
+
+import _sklearn_native as _native
+
+from sklearn.base import (
+    BaseEstimator,
+    ClassifierMixin,
+    RegressorMixin,
+    _argmax,
+    _array,
+    _column,
+    _encode,
+    _rows,
+)
+from sklearn.exceptions import ConvergenceWarning
+
+
+class LinearRegression(RegressorMixin, BaseEstimator):
+    """Ordinary least squares."""
+
+    def __init__(self, fit_intercept=True):
+        self.fit_intercept = fit_intercept
+
+    def fit(self, X, y):
+        rows = _rows(X)
+        coef, intercept = _native.least_squares(rows, _column(y), bool(self.fit_intercept))
+        self._coef = coef
+        self.coef_ = _array(coef)
+        self.intercept_ = intercept
+        self.n_features_in_ = len(coef)
+        return self
+
+    def predict(self, X):
+        self._check_fitted("coef_")
+        coef, intercept = self._coef, self.intercept_
+        return _array(
+            [sum(c * v for c, v in zip(coef, row)) + intercept for row in _rows(X)]
+        )
+
+
+class LogisticRegression(ClassifierMixin, BaseEstimator):
+    """L2-regularised logistic regression: binary for two classes,
+    multinomial otherwise, fitted with L-BFGS."""
+
+    def __init__(
+        self,
+        penalty="l2",
+        C=1.0,
+        fit_intercept=True,
+        max_iter=100,
+        tol=1e-4,
+        random_state=None,
+        solver="lbfgs",
+    ):
+        self.penalty = penalty
+        self.C = C
+        self.fit_intercept = fit_intercept
+        self.max_iter = max_iter
+        self.tol = tol
+        self.random_state = random_state
+        self.solver = solver
+
+    def fit(self, X, y):
+        if self.penalty not in ("l2", None):
+            raise ValueError(f"penalty={self.penalty!r} is not supported; use 'l2' or None")
+        classes, codes = _encode(_column(y))
+        if len(classes) < 2:
+            raise ValueError(
+                "This solver needs samples of at least 2 classes in the data, "
+                f"but the data contains only one class: {classes[0]!r}"
+            )
+        C = math.inf if self.penalty is None else float(self.C)
+        coef, intercept, n_iter = _native.logistic_fit(
+            _rows(X),
+            codes,
+            len(classes),
+            C,
+            bool(self.fit_intercept),
+            int(self.max_iter),
+            float(self.tol),
+        )
+        if n_iter >= self.max_iter:
+            warnings.warn(
+                "lbfgs failed to converge (status=1): increase the number of "
+                "iterations (max_iter) or scale the data.",
+                ConvergenceWarning,
+            )
+        self._classes = classes
+        self._coef = coef
+        self._intercept = intercept
+        self.classes_ = _array(classes)
+        self.coef_ = _array(coef)
+        self.intercept_ = _array(intercept)
+        self.n_iter_ = _array([n_iter])
+        self.n_features_in_ = len(coef[0])
+        return self
+
+    def decision_function(self, X):
+        self._check_fitted("coef_")
+        scores = [
+            [sum(c * v for c, v in zip(w, row)) + b for w, b in zip(self._coef, self._intercept)]
+            for row in _rows(X)
+        ]
+        if len(self._coef) == 1:
+            scores = [s[0] for s in scores]
+        return _array(scores)
+
+    def predict_proba(self, X):
+        self._check_fitted("coef_")
+        return _array(_native.logistic_proba(_rows(X), self._coef, self._intercept))
+
+    def predict_log_proba(self, X):
+        self._check_fitted("coef_")
+        proba = _native.logistic_proba(_rows(X), self._coef, self._intercept)
+        return _array([[math.log(p) if p > 0 else -math.inf for p in row] for row in proba])
+
+    def predict(self, X):
+        self._check_fitted("coef_")
+        proba = _native.logistic_proba(_rows(X), self._coef, self._intercept)
+        return _array([self._classes[_argmax(row)] for row in proba])
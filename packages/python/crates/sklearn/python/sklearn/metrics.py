@@ -0,0 +1,217 @@
+"""Classification and regression metrics."""
+
+import warnings
+
+from sklearn.base import _array, _column
+
+
+def _pair(y_true, y_pred):
+    y_true, y_pred = _column(y_true), _column(y_pred)
+    if len(y_true) != len(y_pred):
+        raise ValueError(
+            "Found input variables with inconsistent numbers of samples: "
+            f"[{len(y_true)}, {len(y_pred)}]"
+        )
+    return y_true, y_pred
+
+
+# ---------------------------------------------------------------------------
+# Classification
+# ---------------------------------------------------------------------------
+
+
+def accuracy_score(y_true, y_pred, normalize=True):
+    y_true, y_pred = _pair(y_true, y_pred)
+    correct = sum(1 for t, p in zip(y_true, y_pred) if t == p)
+    if not normalize:
+        return correct
+    return correct / len(y_true) if y_true else 0.0
+
+
+def confusion_matrix(y_true, y_pred, labels=None):
+    """Counts of true label (rows) against predicted label (columns)."""
+    y_true, y_pred = _pair(y_true, y_pred)
+    if labels is None:
+        labels = sorted(set(y_true) | set(y_pred))
+    index = {label: i for i, label in enumerate(_column(labels))}
+    matrix = [[0] * len(index) for _ in index]
+    for t, p in zip(y_true, y_pred):
+        if t in index and p in index:
+            matrix[index[t]][index[p]] += 1
+    return _array(matrix)
+
+
+def _divide(numerator, denominator, zero_division, metric):
+    if denominator:
+        return numerator / denominator
+    if zero_division == "warn":
+        warnings.warn(
+            f"{metric} is ill-defined and being set to 0.0 due to no samples. "
+            "Use `zero_division` parameter to control this behavior."
+        )
+        return 0.0
+    return float(zero_division)
+
+
+def _per_label(y_true, y_pred, labels):
+    """(true positives, false positives, false negatives, support) per label."""
+    counts = []
+    for label in labels:
+        tp = fp = fn = 0
+        for t, p in zip(y_true, y_pred):
+            if p == label:
+                if t == label:
+                    tp += 1
+                else:
+                    fp += 1
+            elif t == label:
+                fn += 1
+        counts.append((tp, fp, fn, tp + fn))
+    return counts
+
+
+def precision_recall_fscore_support(
+    y_true, y_pred, labels=None, pos_label=1, average=None, zero_division="warn"
+):
+    y_true, y_pred = _pair(y_true, y_pred)
+    present = sorted(set(y_true) | set(y_pred))
+    if average == "binary":
+        if len(present) > 2:
+            raise ValueError(
+                "Target is multiclass but average='binary'. Please choose another "
+                "average setting, one of [None, 'micro', 'macro', 'weighted']."
+            )
+        labels = [pos_label]
+    elif labels is None:
+        labels = present
+    else:
+        labels = _column(labels)
+    counts = _per_label(y_true, y_pred, labels)
+
+    if average == "micro":
+        tp = sum(c[0] for c in counts)
+        fp = sum(c[1] for c in counts)
+        fn = sum(c[2] for c in counts)
+        counts = [(tp, fp, fn, tp + fn)]
+
+    precision, recall, f1 = [], [], []
+    for tp, fp, fn, _ in counts:
+        p = _divide(tp, tp + fp, zero_division, "Precision")
+        r = _divide(tp, tp + fn, zero_division, "Recall")
+        precision.append(p)
+        recall.append(r)
+        f1.append(2 * p * r / (p + r) if p + r else 0.0)
+    support = [c[3] for c in counts]
+
+    if average is None:
+        return _array(precision), _array(recall), _array(f1), _array(support)
+    if average in ("binary", "micro"):
+        return precision[0], recall[0], f1[0], None
+    if average == "macro":
+        weights = [1] * len(counts)
+    elif average == "weighted":
+        weights = support
+    else:
+        raise ValueError(f"Unsupported average {average!r}")
+    total = sum(weights)
+
+    def mean(values):
+        return sum(v * w for v, w in zip(values, weights)) / total if total else 0.0
+
+    return mean(precision), mean(recall), mean(f1), None
+
+
+def precision_score(
+    y_true, y_pred, labels=None, pos_label=1, average="binary", zero_division="warn"
+):
+    return precision_recall_fscore_support(
+        y_true, y_pred, labels, pos_label, average, zero_division
+    )[0]
+
+
+def recall_score(
+    y_true, y_pred, labels=None, pos_label=1, average="binary", zero_division="warn"
+):
+    return precision_recall_fscore_support(
+        y_true, y_pred, labels, pos_label, average, zero_division
+    )[1]
+
+
+def f1_score(
+    y_true, y_pred, labels=None, pos_label=1, average="binary", zero_division="warn"
+):
+    return precision_recall_fscore_support(
+        y_true, y_pred, labels, pos_label, average, zero_division
+    )[2]
+
+
+def classification_report(
+    y_true, y_pred, labels=None, target_names=None, digits=2, zero_division="warn"
+):
+    """A text table of precision, recall and F1 per class, laid out as
+    scikit-learn does."""
+    y_true, y_pred = _pair(y_true, y_pred)
+    labels = sorted(set(y_true) | set(y_pred)) if labels is None else _column(labels)
+    if target_names is None:
+        target_names = [str(label) for label in labels]
+    counts = _per_label(y_true, y_pred, labels)
+    rows = []
+    for name, (tp, fp, fn, support) in zip(target_names, counts):
+        p = _divide(tp, tp + fp, zero_division, "Precision")
+        r = _divide(tp, tp + fn, zero_division, "Recall")
+        rows.append((name, p, r, 2 * p * r / (p + r) if p + r else 0.0, support))
+    total = sum(row[4] for row in rows)
+
+    last_heading = "weighted avg"
+    width = max(max(len(name) for name in target_names), len(last_heading), digits)
+    headers = ["precision", "recall", "f1-score", "support"]
+    report = ("{:>{width}s} " + " {:>9}" * len(headers)).format(
+        "", *headers, width=width
+    )
+    report += "\n\n"
+    row_fmt = "{:>{width}s} " + " {:>9.{digits}f}" * 3 + " {:>9}\n"
+    for row in rows:
+        report += row_fmt.format(*row, width=width, digits=digits)
+    report += "\n"
+
+    accuracy = accuracy_score(y_true, y_pred)
+    report += (
+        "{:>{width}s} " + " {:>9.{digits}}" * 2 + " {:>9.{digits}f}" + " {:>9}\n"
+    ).format("accuracy", "", "", accuracy, total, width=width, digits=digits)
+    for heading, weights in (
+        ("macro avg", [1] * len(rows)),
+        ("weighted avg", [row[4] for row in rows]),
+    ):
+        weight = sum(weights)
+        means = [
+            sum(row[i] * w for row, w in zip(rows, weights)) / weight if weight else 0.0
+            for i in (1, 2, 3)
+        ]
+        report += row_fmt.format(heading, *means, total, width=width, digits=digits)
+    return report
+
+
+# ---------------------------------------------------------------------------
+# Regression
+# ---------------------------------------------------------------------------
+
+
+def mean_squared_error(y_true, y_pred, squared=True):
+    y_true, y_pred = _pair(y_true, y_pred)
+    mse = sum((t - p) ** 2 for t, p in zip(y_true, y_pred)) / len(y_true)
+    return mse if squared else mse**0.5
+
+
+def mean_absolute_error(y_true, y_pred):
+    y_true, y_pred = _pair(y_true, y_pred)
+    return sum(abs(t - p) for t, p in zip(y_true, y_pred)) / len(y_true)
+
+
+def r2_score(y_true, y_pred):
+    y_true, y_pred = _pair(y_true, y_pred)
+    mean = sum(y_true) / len(y_true)
+    residual = sum((t - p) ** 2 for t, p in zip(y_true, y_pred))
+    total = sum((t - mean) ** 2 for t in y_true)
+    if total == 0:
+        return 1.0 if residual == 0 else 0.0
+    return 1 - residual / total
@@ -0,0 +1,116 @@
+"""Splitting data: train_test_split."""
+
+import math
+import random
+
+from sklearn.base import _array, _column
+
+
+def _length(array):
+    if hasattr(array, "shape"):
+        return array.shape[0]
+    return len(array)
+
+
+def _take(array, indices):
+    """The rows of array at indices, in the same kind of container."""
+    if hasattr(array, "iloc"):
+        return array.iloc[indices]
+    if hasattr(array, "tolist"):
+        rows = array.tolist()
+        return _array([rows[i] for i in indices])
+    return [array[i] for i in indices]
+
+
+def _split_sizes(n_samples, test_size, train_size):
+    """The number of test and train samples."""
+    if test_size is None and train_size is None:
+        test_size = 0.25
+    n_test = n_train = None
+    if isinstance(test_size, float):
+        n_test = math.ceil(test_size * n_samples)
+    elif test_size is not None:
+        n_test = int(test_size)
+    if isinstance(train_size, float):
+        n_train = math.floor(train_size * n_samples)
+    elif train_size is not None:
+        n_train = int(train_size)
+    if n_test is None:
+        n_test = n_samples - n_train
+    if n_train is None:
+        n_train = n_samples - n_test
+    if n_train + n_test > n_samples or n_train <= 0 or n_test < 0:
+        raise ValueError(
+            f"With n_samples={n_samples}, test_size={test_size} and "
+            f"train_size={train_size}, the resulting train set would be empty "
+            "or the sets would not fit. Adjust any of the aforementioned parameters."
+        )
+    return n_test, n_train
+
+
+def _stratified_test(labels, n_test, rng):
+    """Test indices drawn from each class in proportion to its size."""
+    groups = {}
+    for i, label in enumerate(labels):
+        groups.setdefault(label, []).append(i)
+    n_samples = len(labels)
+    wanted = {label: n_test * len(rows) / n_samples for label, rows in groups.items()}
+    counts = {label: int(want) for label, want in wanted.items()}
+    # Hand out what rounding down left over to the largest remainders.
+    by_remainder = sorted(groups, key=lambda label: wanted[label] - counts[label], reverse=True)
+    for label in by_remainder[: n_test - sum(counts.values())]:
+        counts[label] += 1
+    test = []
+    for label, rows in groups.items():
+        rng.shuffle(rows)
+        test.extend(rows[: counts[label]])
+    rng.shuffle(test)
+    return test
+
+
+def train_test_split(
+    *arrays,
+    test_size=None,
+    train_size=None,
+    random_state=None,
+    shuffle=True,
+    stratify=None,
+):
+    """Split arrays into random train and test subsets; returns train and
+    test parts of each array in turn."""
+    if not arrays:
+        raise ValueError("At least one array required as input")
+    lengths = [_length(array) for array in arrays]
+    if len(set(lengths)) > 1:
+        raise ValueError(
+            f"Found input variables with inconsistent numbers of samples: {lengths}"
+        )
+    n_samples = lengths[0]
+    n_test, n_train = _split_sizes(n_samples, test_size, train_size)
+
+    if not shuffle:
+        if stratify is not None:
+            raise ValueError(
+                "Stratified train/test split is not implemented for shuffle=False"
+            )
+        train = list(range(n_train))
+        test = list(range(n_train, n_train + n_test))
+    else:
+        rng = random.Random(random_state)
+        if stratify is not None:
+            test = _stratified_test(_column(stratify), n_test, rng)
+            chosen = set(test)
+            rest = [i for i in range(n_samples) if i not in chosen]
+            rng.shuffle(rest)
+            train = rest[:n_train]
+        else:
+            order = list(range(n_samples))
+            rng.shuffle(order)
+            test = order[:n_test]
+            train = order[n_test : n_test + n_train]
+
+    result = []
+    for array in arrays:
+        result.append(_take(array, train))
+        result.append(_take(array, test))
+    return result
@@ -0,0 +1,154 @@
+"""Decision trees: DecisionTreeClassifier and DecisionTreeRegressor."""
+
+import math
+
+import _sklearn_native as _native
+
+from sklearn.base import (
+    BaseEstimator,
+    ClassifierMixin,
+    RegressorMixin,
+    _argmax,
+    _array,
+    _column,
+    _encode,
+    _rows,
+    _seed,
+)
+
+
+def _max_features(max_features, n_features):
+    """The number of features tried per split."""
+    if max_features is None:
+        return n_features
+    if max_features == "sqrt":
+        return max(1, int(math.sqrt(n_features)))
+    if max_features == "log2":
+        return max(1, int(math.log2(n_features)))
+    if isinstance(max_features, float):
+        return max(1, int(max_features * n_features))
+    return int(max_features)
+
+
+def _min_samples(value, n_samples):
+    """min_samples_split / min_samples_leaf, which may be a fraction."""
+    if isinstance(value, float):
+        return max(1, math.ceil(value * n_samples))
+    return int(value)
+
+
+def _native_params(estimator, n_samples, n_features):
+    """The tree arguments shared by fit_tree and fit_forest."""
+    return (
+        estimator.criterion,
+        -1 if estimator.max_depth is None else int(estimator.max_depth),
+        _min_samples(estimator.min_samples_split, n_samples),
+        _min_samples(estimator.min_samples_leaf, n_samples),
+        _max_features(estimator.max_features, n_features),
+    )
+
+
+class BaseDecisionTree(BaseEstimator):
+    def __init__(
+        self,
+        criterion,
+        max_depth=None,
+        min_samples_split=2,
+        min_samples_leaf=1,
+        max_features=None,
+        random_state=None,
+    ):
+        self.criterion = criterion
+        self.max_depth = max_depth
+        self.min_samples_split = min_samples_split
+        self.min_samples_leaf = min_samples_leaf
+        self.max_features = max_features
+        self.random_state = random_state
+
+    def _fit(self, X, target, n_classes):
+        rows = _rows(X)
+        n_features = len(rows[0]) if rows else 0
+        self.tree_ = _native.fit_tree(
+            rows,
+            target,
+            n_classes,
+            *_native_params(self, len(rows), n_features),
+            _seed(self.random_state),
+        )
+        self.n_features_in_ = n_features
+        return self
+
+    @property
+    def feature_importances_(self):
+        self._check_fitted("tree_")
+        return _array(self.tree_.feature_importances)
+
+    def get_depth(self):
+        self._check_fitted("tree_")
+        return self.tree_.max_depth
+
+    def get_n_leaves(self):
+        self._check_fitted("tree_")
+        return self.tree_.n_leaves
+
+
+class DecisionTreeClassifier(ClassifierMixin, BaseDecisionTree):
+    def __init__(
+        self,
+        criterion="gini",
+        max_depth=None,
+        min_samples_split=2,
+        min_samples_leaf=1,
+        max_features=None,
+        random_state=None,
+    ):
+        super().__init__(
+            criterion=criterion,
+            max_depth=max_depth,
+            min_samples_split=min_samples_split,
+            min_samples_leaf=min_samples_leaf,
+            max_features=max_features,
+            random_state=random_state,
+        )
+
+    def fit(self, X, y):
+        classes, codes = _encode(_column(y))
+        self._classes = classes
+        self.classes_ = _array(classes)
+        self.n_classes_ = len(classes)
+        return self._fit(X, codes, len(classes))
+
+    def predict_proba(self, X):
+        self._check_fitted("tree_")
+        return _array(self.tree_.predict(_rows(X)))
+
+    def predict(self, X):
+        self._check_fitted("tree_")
+        return _array([self._classes[_argmax(row)] for row in self.tree_.predict(_rows(X))])
+
+
+class DecisionTreeRegressor(RegressorMixin, BaseDecisionTree):
+    def __init__(
+        self,
+        criterion="squared_error",
+        max_depth=None,
+        min_samples_split=2,
+        min_samples_leaf=1,
+        max_features=None,
+        random_state=None,
+    ):
+        super().__init__(
+            criterion=criterion,
+            max_depth=max_depth,
+            min_samples_split=min_samples_split,
+            min_samples_leaf=min_samples_leaf,
+            max_features=max_features,
+            random_state=random_state,
+        )
+
+    def fit(self, X, y):
+        return self._fit(X, _column(y), 0)
+
+    def predict(self, X):
+        self._check_fitted("tree_")
+        return _array([value[0] for value in self.tree_.predict(_rows(X))])
@@ -0,0 +1,164 @@
+//! K-means clustering with k-means++ seeding.
+
+use crate::matrix::{squared_distance, Matrix, Rng};
+
+#[derive(Debug)]
+pub struct KMeans {
+    pub centers: Matrix,
+    pub labels: Vec<usize>,
+    pub inertia: f64,
+    pub n_iter: usize,
+}
+
+/// The closest of `centers` to `row` and the squared distance to it.
+pub fn nearest(centers: &Matrix, row: &[f64]) -> (usize, f64) {
+    (0..centers.rows)
+        .map(|c| (c, squared_distance(centers.row(c), row)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+/// Run Lloyd's algorithm `n_init` times and keep the run with the lowest
+/// inertia. As in scikit-learn, `tol` is relative to the mean variance of
+/// the features, and a run also stops once no label changes.
+pub fn kmeans(
+    x: &Matrix,
+    k: usize,
+    n_init: usize,
+    max_iter: usize,
+    tol: f64,
+    random_init: bool,
+    rng: &mut Rng,
+) -> KMeans {
+    let tol = tol * mean_variance(x);
+    (0..n_init.max(1))
+        .map(|_| {
+            let centers = if random_init {
+                random_centers(x, k, rng)
+            } else {
+                plus_plus(x, k, rng)
+            };
+            lloyd(x, centers, max_iter, tol)
+        })
+        .min_by(|a, b| a.inertia.total_cmp(&b.inertia))
+        .unwrap()
+}
+
+fn mean_variance(x: &Matrix) -> f64 {
+    if x.rows == 0 || x.cols == 0 {
+        return 0.0;
+    }
+    let n = x.rows as f64;
+    let total: f64 = (0..x.cols)
+        .map(|j| {
+            let mean = (0..x.rows).map(|i| x.get(i, j)).sum::<f64>() / n;
+            (0..x.rows)
+                .map(|i| (x.get(i, j) - mean).powi(2))
+                .sum::<f64>()
+                / n
+        })
+        .sum();
+    total / x.cols as f64
+}
+
+fn random_centers(x: &Matrix, k: usize, rng: &mut Rng) -> Matrix {
+    let mut rows: Vec<usize> = (0..x.rows).collect();
+    rng.shuffle(&mut rows);
+    let mut centers = Matrix::zeros(k, x.cols);
+    for (c, &row) in rows.iter().cycle().take(k).enumerate() {
+        centers.row_mut(c).copy_from_slice(x.row(row));
+    }
+    centers
+}
+
+/// k-means++: each new center is a row drawn with probability proportional
+/// to its squared distance from the centers chosen so far.
+fn plus_plus(x: &Matrix, k: usize, rng: &mut Rng) -> Matrix {
+    let mut centers = Matrix::zeros(k, x.cols);
+    centers.row_mut(0).copy_from_slice(x.row(rng.below(x.rows)));
+    let mut closest: Vec<f64> = (0..x.rows)
+        .map(|i| squared_distance(x.row(i), centers.row(0)))
+        .collect();
+    for c in 1..k {
+        let total: f64 = closest.iter().sum();
+        let chosen = if total > 0.0 {
+            let mut target = rng.unit() * total;
+            closest
+                .iter()
+                .position(|&d| {
+                    target -= d;
+                    target < 0.0
+                })
+                .unwrap_or(x.rows - 1)
+        } else {
+            rng.below(x.rows)
+        };
+        centers.row_mut(c).copy_from_slice(x.row(chosen));
+        for (i, best) in closest.iter_mut().enumerate() {
+            *best = best.min(squared_distance(x.row(i), centers.row(c)));
+        }
+    }
+    centers
+}
+
+fn lloyd(x: &Matrix, mut centers: Matrix, max_iter: usize, tol: f64) -> KMeans {
+    let k = centers.rows;
+    let mut labels = vec![usize::MAX; x.rows];
+    let mut n_iter = 0;
+    while n_iter < max_iter {
+        n_iter += 1;
+        let mut changed = false;
+        let mut distances = vec![0.0; x.rows];
+        for i in 0..x.rows {
+            let (label, distance) = nearest(&centers, x.row(i));
+            changed |= labels[i] != label;
+            labels[i] = label;
+            distances[i] = distance;
+        }
+        if !changed {
+            break;
+        }
+
+        let mut sums = Matrix::zeros(k, x.cols);
+        let mut counts = vec![0usize; k];
+        for i in 0..x.rows {
+            counts[labels[i]] += 1;
+            for (sum, v) in sums.row_mut(labels[i]).iter_mut().zip(x.row(i)) {
+                *sum += v;
+            }
+        }
+        let mut shift = 0.0;
+        for c in 0..k {
+            if counts[c] == 0 {
+                // An empty cluster takes the row furthest from its center.
+                let far = (0..x.rows)
+                    .max_by(|&a, &b| distances[a].total_cmp(&distances[b]))
+                    .unwrap();
+                distances[far] = 0.0;
+                sums.row_mut(c).copy_from_slice(x.row(far));
+                counts[c] = 1;
+            }
+            let count = counts[c] as f64;
+            sums.row_mut(c).iter_mut().for_each(|v| *v /= count);
+            shift += squared_distance(sums.row(c), centers.row(c));
+        }
+        centers = sums;
+        if shift <= tol {
+            break;
+        }
+    }
+
+    // Labels and inertia for the final centers.
+    let mut inertia = 0.0;
+    for i in 0..x.rows {
+        let (label, distance) = nearest(&centers, x.row(i));
+        labels[i] = label;
+        inertia += distance;
+    }
+    KMeans {
+        centers,
+        labels,
+        inertia,
+        n_iter,
+    }
+}
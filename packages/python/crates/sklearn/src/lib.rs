@@ -1,14 +1,467 @@
-// Stub: sklearn native module (to be implemented)
+//! Native core of the sandbox's `sklearn` package.
+//!
+//! The Python package (`python/sklearn`) holds the estimator classes and
+//! their scikit-learn API; this module does the numeric work on plain
+//! lists: least squares, logistic regression, decision trees and forests,
+//! and k-means. Inputs are lists of rows (numpy arrays are turned into
+//! lists on the Python side) and results come back as lists.
+
+mod cluster;
+mod linear;
+mod matrix;
+mod tree;
+
 use rustpython_vm as vm;
 
+use matrix::{Matrix, Rng};
+use vm::builtins::{PyFloat, PyInt, PyList, PyTuple};
+use vm::{PyObjectRef, PyResult, VirtualMachine};
+
+// ---------------------------------------------------------------------------
+// Conversion helpers
+// ---------------------------------------------------------------------------
+
+/// The items of a list or tuple.
+#[allow(deprecated)] // payload() usage
+fn items(obj: &PyObjectRef, what: &str, py_vm: &VirtualMachine) -> PyResult<Vec<PyObjectRef>> {
+    if let Some(list) = obj.payload::<PyList>() {
+        Ok(list.borrow_vec().to_vec())
+    } else if let Some(tuple) = obj.payload::<PyTuple>() {
+        Ok(tuple.as_slice().to_vec())
+    } else {
+        Err(py_vm.new_type_error(format!("{what} must be a list")))
+    }
+}
+
+#[allow(deprecated)] // payload() usage
+fn float(obj: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<f64> {
+    if let Some(f) = obj.payload::<PyFloat>() {
+        Ok(f.to_f64())
+    } else if let Some(i) = obj.payload::<PyInt>() {
+        i.try_to_primitive::<i64>(py_vm).map(|i| i as f64)
+    } else {
+        Err(py_vm.new_type_error("expected a number".to_owned()))
+    }
+}
+
+fn floats(obj: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<Vec<f64>> {
+    items(obj, "y", py_vm)?
+        .iter()
+        .map(|item| float(item, py_vm))
+        .collect()
+}
+
+#[allow(deprecated)] // payload() usage
+fn indices(obj: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<Vec<usize>> {
+    items(obj, "y", py_vm)?
+        .iter()
+        .map(|item| match item.payload::<PyInt>() {
+            Some(i) => i.try_to_primitive::<usize>(py_vm),
+            None => Err(py_vm.new_type_error("class labels must be encoded as ints".to_owned())),
+        })
+        .collect()
+}
+
+/// A list of equally long rows of numbers as a matrix.
+fn matrix(obj: &PyObjectRef, py_vm: &VirtualMachine) -> PyResult<Matrix> {
+    let rows = items(obj, "X", py_vm)?;
+    let mut data = Vec::new();
+    let mut cols = None;
+    for row in &rows {
+        let row = floats(row, py_vm)?;
+        if *cols.get_or_insert(row.len()) != row.len() {
+            return Err(py_vm.new_value_error("all rows of X must have the same length".to_owned()));
+        }
+        data.extend(row);
+    }
+    Ok(Matrix {
+        data,
+        rows: rows.len(),
+        cols: cols.unwrap_or(0),
+    })
+}
+
+fn float_list(values: &[f64], py_vm: &VirtualMachine) -> PyObjectRef {
+    let items = values
+        .iter()
+        .map(|&v| py_vm.ctx.new_float(v).into())
+        .collect();
+    py_vm.ctx.new_list(items).into()
+}
+
+fn rows_list<'a>(rows: impl Iterator<Item = &'a [f64]>, py_vm: &VirtualMachine) -> PyObjectRef {
+    let items = rows.map(|row| float_list(row, py_vm)).collect();
+    py_vm.ctx.new_list(items).into()
+}
+
+fn index_list(values: &[usize], py_vm: &VirtualMachine) -> PyObjectRef {
+    let items = values
+        .iter()
+        .map(|&v| py_vm.ctx.new_int(v).into())
+        .collect();
+    py_vm.ctx.new_list(items).into()
+}
+
+/// The tree target for `y`: class indices when `n_classes` is positive,
+/// values otherwise.
+enum OwnedTarget {
+    Classes(Vec<usize>, usize),
+    Values(Vec<f64>),
+}
+
+impl OwnedTarget {
+    fn new(y: &PyObjectRef, n_classes: usize, py_vm: &VirtualMachine) -> PyResult<Self> {
+        if n_classes == 0 {
+            return Ok(OwnedTarget::Values(floats(y, py_vm)?));
+        }
+        let classes = indices(y, py_vm)?;
+        if classes.iter().any(|&c| c >= n_classes) {
+            return Err(py_vm.new_value_error("class index out of range".to_owned()));
+        }
+        Ok(OwnedTarget::Classes(classes, n_classes))
+    }
+
+    fn as_target(&self) -> tree::Target<'_> {
+        match self {
+            OwnedTarget::Classes(classes, n) => tree::Target::Classes(classes, *n),
+            OwnedTarget::Values(values) => tree::Target::Values(values),
+        }
+    }
+}
+
+/// Tree parameters from the estimator's; `max_depth` is negative for no
+/// limit and `criterion` one of scikit-learn's names.
+fn tree_params(
+    criterion: &str,
+    max_depth: i64,
+    min_samples_split: usize,
+    min_samples_leaf: usize,
+    max_features: usize,
+    n_features: usize,
+    py_vm: &VirtualMachine,
+) -> PyResult<tree::Params> {
+    let criterion = match criterion {
+        "gini" => tree::Criterion::Gini,
+        "entropy" | "log_loss" => tree::Criterion::Entropy,
+        "squared_error" => tree::Criterion::SquaredError,
+        other => return Err(py_vm.new_value_error(format!("unsupported criterion '{other}'"))),
+    };
+    Ok(tree::Params {
+        criterion,
+        max_depth: usize::try_from(max_depth).unwrap_or(usize::MAX),
+        min_samples_split: min_samples_split.max(2),
+        min_samples_leaf: min_samples_leaf.max(1),
+        max_features: max_features.clamp(1, n_features.max(1)),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tree
+// ---------------------------------------------------------------------------
+
+/// A fitted decision tree, kept native so prediction does not walk Python
+/// objects.
+#[vm::pyclass(module = "_sklearn_native", name = "Tree")]
+#[derive(Debug, vm::PyPayload)]
+struct PyTree {
+    tree: tree::Tree,
+}
+
+#[vm::pyclass]
+impl PyTree {
+    /// For each row, the class proportions or the value (as a one-item
+    /// list) of the leaf it lands in.
+    #[pymethod]
+    fn predict(&self, x: PyObjectRef, py_vm: &VirtualMachine) -> PyResult<PyObjectRef> {
+        let x = matrix(&x, py_vm)?;
+        Ok(rows_list(
+            (0..x.rows).map(|i| self.tree.predict(x.row(i))),
+            py_vm,
+        ))
+    }
+
+    #[pygetset]
+    fn node_count(&self) -> usize {
+        self.tree.nodes.len()
+    }
+
+    #[pygetset]
+    fn max_depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    #[pygetset]
+    fn n_leaves(&self) -> usize {
+        self.tree.n_leaves()
+    }
+
+    #[pygetset]
+    fn feature_importances(&self, py_vm: &VirtualMachine) -> PyObjectRef {
+        float_list(&self.tree.importances, py_vm)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Python module: _sklearn_native
+// ---------------------------------------------------------------------------
+
+#[allow(non_snake_case)]
 #[vm::pymodule]
 mod _sklearn_native {
-    use rustpython_vm as vm;
-    use vm::VirtualMachine;
+    use super::*;
+    use vm::builtins::PyTypeRef;
+    use vm::class::PyClassImpl;
+
+    #[pyattr]
+    fn Tree(vm: &VirtualMachine) -> PyTypeRef {
+        PyTree::make_class(&vm.ctx)
+    }
+
+    /// `least_squares(X, y, fit_intercept) -> (coef, intercept)`
+    #[pyfunction]
+    fn least_squares(
+        x: PyObjectRef,
+        y: PyObjectRef,
+        fit_intercept: bool,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(PyObjectRef, f64)> {
+        let x = matrix(&x, py_vm)?;
+        let y = floats(&y, py_vm)?;
+        if y.len() != x.rows {
+            return Err(py_vm.new_value_error("X and y have different lengths".to_owned()));
+        }
+        let (coef, intercept) = linear::least_squares(&x, &y, fit_intercept);
+        Ok((float_list(&coef, py_vm), intercept))
+    }
+
+    /// `logistic_fit(X, y, n_classes, C, fit_intercept, max_iter, tol)
+    /// -> (coef rows, intercepts, n_iter)`, with `y` as class indices.
+    #[allow(clippy::too_many_arguments)]
+    #[pyfunction]
+    fn logistic_fit(
+        x: PyObjectRef,
+        y: PyObjectRef,
+        n_classes: usize,
+        c: f64,
+        fit_intercept: bool,
+        max_iter: usize,
+        tol: f64,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(PyObjectRef, PyObjectRef, usize)> {
+        let x = matrix(&x, py_vm)?;
+        let y = indices(&y, py_vm)?;
+        if y.len() != x.rows {
+            return Err(py_vm.new_value_error("X and y have different lengths".to_owned()));
+        }
+        if n_classes < 2 || y.iter().any(|&c| c >= n_classes) {
+            return Err(py_vm.new_value_error("need at least two classes".to_owned()));
+        }
+        let fit = linear::logistic(&x, &y, n_classes, c, fit_intercept, max_iter, tol);
+        Ok((
+            rows_list(fit.coef.iter().map(Vec::as_slice), py_vm),
+            float_list(&fit.intercept, py_vm),
+            fit.n_iter,
+        ))
+    }
+
+    /// `logistic_proba(X, coef, intercept) -> probability rows`
+    #[pyfunction]
+    fn logistic_proba(
+        x: PyObjectRef,
+        coef: PyObjectRef,
+        intercept: PyObjectRef,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        let x = matrix(&x, py_vm)?;
+        let coef = matrix(&coef, py_vm)?;
+        let intercept = floats(&intercept, py_vm)?;
+        if coef.cols != x.cols || intercept.len() != coef.rows {
+            return Err(py_vm.new_value_error("X does not match the fitted model".to_owned()));
+        }
+        let coef: Vec<Vec<f64>> = (0..coef.rows).map(|c| coef.row(c).to_vec()).collect();
+        let proba = linear::logistic_proba(&x, &coef, &intercept);
+        Ok(rows_list(proba.iter().map(Vec::as_slice), py_vm))
+    }
+
+    /// `fit_tree(X, y, n_classes, criterion, max_depth, min_samples_split,
+    /// min_samples_leaf, max_features, seed) -> Tree`; `n_classes` is 0
+    /// for regression.
+    #[allow(clippy::too_many_arguments)]
+    #[pyfunction]
+    fn fit_tree(
+        x: PyObjectRef,
+        y: PyObjectRef,
+        n_classes: usize,
+        criterion: vm::builtins::PyStrRef,
+        max_depth: i64,
+        min_samples_split: usize,
+        min_samples_leaf: usize,
+        max_features: usize,
+        seed: u64,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<PyTree> {
+        let x = matrix(&x, py_vm)?;
+        let target = OwnedTarget::new(&y, n_classes, py_vm)?;
+        let params = tree_params(
+            criterion.as_str(),
+            max_depth,
+            min_samples_split,
+            min_samples_leaf,
+            max_features,
+            x.cols,
+            py_vm,
+        )?;
+        if x.rows == 0 {
+            return Err(py_vm.new_value_error("X is empty".to_owned()));
+        }
+        let samples = (0..x.rows).collect();
+        let tree = tree::fit(
+            &x,
+            &target.as_target(),
+            samples,
+            &params,
+            &mut Rng::new(seed),
+        );
+        Ok(PyTree { tree })
+    }
+
+    /// `fit_forest(X, y, n_classes, criterion, max_depth, min_samples_split,
+    /// min_samples_leaf, max_features, n_estimators, bootstrap, seed)
+    /// -> [Tree]`
+    #[allow(clippy::too_many_arguments)]
+    #[pyfunction]
+    fn fit_forest(
+        x: PyObjectRef,
+        y: PyObjectRef,
+        n_classes: usize,
+        criterion: vm::builtins::PyStrRef,
+        max_depth: i64,
+        min_samples_split: usize,
+        min_samples_leaf: usize,
+        max_features: usize,
+        n_estimators: usize,
+        bootstrap: bool,
+        seed: u64,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        let x = matrix(&x, py_vm)?;
+        let target = OwnedTarget::new(&y, n_classes, py_vm)?;
+        let params = tree_params(
+            criterion.as_str(),
+            max_depth,
+            min_samples_split,
+            min_samples_leaf,
+            max_features,
+            x.cols,
+            py_vm,
+        )?;
+        if x.rows == 0 {
+            return Err(py_vm.new_value_error("X is empty".to_owned()));
+        }
+        let trees = tree::fit_forest(
+            &x,
+            &target.as_target(),
+            &params,
+            n_estimators,
+            bootstrap,
+            &mut Rng::new(seed),
+        );
+        let trees = trees
+            .into_iter()
+            .map(|tree| vm::PyPayload::into_pyobject(PyTree { tree }, py_vm))
+            .collect();
+        Ok(py_vm.ctx.new_list(trees).into())
+    }
+
+    /// `forest_predict(trees, X) -> rows`: the mean of the trees'
+    /// predictions for each row.
+    #[allow(deprecated)] // payload() usage
+    #[pyfunction]
+    fn forest_predict(
+        trees: PyObjectRef,
+        x: PyObjectRef,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<PyObjectRef> {
+        let trees = items(&trees, "trees", py_vm)?;
+        let trees = trees
+            .iter()
+            .map(|tree| {
+                tree.payload::<PyTree>()
+                    .map(|tree| &tree.tree)
+                    .ok_or_else(|| py_vm.new_type_error("expected Tree objects".to_owned()))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let x = matrix(&x, py_vm)?;
+        let rows: Vec<Vec<f64>> = (0..x.rows)
+            .map(|i| {
+                let mut mean: Vec<f64> = Vec::new();
+                for tree in &trees {
+                    let value = tree.predict(x.row(i));
+                    mean.resize(value.len(), 0.0);
+                    mean.iter_mut().zip(value).for_each(|(m, v)| *m += v);
+                }
+                mean.iter_mut().for_each(|m| *m /= trees.len() as f64);
+                mean
+            })
+            .collect();
+        Ok(rows_list(rows.iter().map(Vec::as_slice), py_vm))
+    }
+
+    /// `kmeans_fit(X, n_clusters, n_init, max_iter, tol, random_init, seed)
+    /// -> (centers, labels, inertia, n_iter)`
+    #[allow(clippy::too_many_arguments)]
+    #[pyfunction]
+    fn kmeans_fit(
+        x: PyObjectRef,
+        n_clusters: usize,
+        n_init: usize,
+        max_iter: usize,
+        tol: f64,
+        random_init: bool,
+        seed: u64,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(PyObjectRef, PyObjectRef, f64, usize)> {
+        let x = matrix(&x, py_vm)?;
+        if n_clusters == 0 || x.rows < n_clusters {
+            return Err(py_vm.new_value_error(format!(
+                "n_samples={} should be >= n_clusters={n_clusters}",
+                x.rows
+            )));
+        }
+        let fit = cluster::kmeans(
+            &x,
+            n_clusters,
+            n_init,
+            max_iter,
+            tol,
+            random_init,
+            &mut Rng::new(seed),
+        );
+        let centers = (0..fit.centers.rows).map(|c| fit.centers.row(c));
+        Ok((
+            rows_list(centers, py_vm),
+            index_list(&fit.labels, py_vm),
+            fit.inertia,
+            fit.n_iter,
+        ))
+    }
 
+    /// `kmeans_predict(X, centers) -> (labels, squared distances)`
     #[pyfunction]
-    fn _stub(_vm: &VirtualMachine) -> vm::PyResult<()> {
-        Ok(())
+    fn kmeans_predict(
+        x: PyObjectRef,
+        centers: PyObjectRef,
+        py_vm: &VirtualMachine,
+    ) -> PyResult<(PyObjectRef, PyObjectRef)> {
+        let x = matrix(&x, py_vm)?;
+        let centers = matrix(&centers, py_vm)?;
+        if centers.cols != x.cols {
+            return Err(py_vm.new_value_error("X does not match the fitted centers".to_owned()));
+        }
+        let (labels, distances): (Vec<usize>, Vec<f64>) = (0..x.rows)
+            .map(|i| cluster::nearest(&centers, x.row(i)))
+            .unzip();
+        Ok((index_list(&labels, py_vm), float_list(&distances, py_vm)))
     }
 }
 
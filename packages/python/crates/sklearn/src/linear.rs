@@ -0,0 +1,281 @@
+//! Linear models: ordinary least squares and L2-regularised logistic
+//! regression.
+
+use std::collections::VecDeque;
+
+use crate::matrix::{dot, Matrix};
+
+/// Least-squares coefficients and intercept for `y ≈ X·w + b`.
+///
+/// Solves the normal equations on centred data. A singular system (a
+/// constant or duplicated feature) gets a tiny ridge term, so the answer
+/// is close to the minimum-norm one rather than an error.
+pub fn least_squares(x: &Matrix, y: &[f64], fit_intercept: bool) -> (Vec<f64>, f64) {
+    let (n, d) = (x.rows, x.cols);
+    let (x_mean, y_mean) = if fit_intercept && n > 0 {
+        let mut x_mean = vec![0.0; d];
+        for i in 0..n {
+            for (mean, v) in x_mean.iter_mut().zip(x.row(i)) {
+                *mean += v / n as f64;
+            }
+        }
+        (x_mean, y.iter().sum::<f64>() / n as f64)
+    } else {
+        (vec![0.0; d], 0.0)
+    };
+
+    let mut gram = vec![vec![0.0; d]; d];
+    let mut rhs = vec![0.0; d];
+    let mut centred = vec![0.0; d];
+    for i in 0..n {
+        for (c, (v, mean)) in centred.iter_mut().zip(x.row(i).iter().zip(&x_mean)) {
+            *c = v - mean;
+        }
+        let target = y[i] - y_mean;
+        for j in 0..d {
+            rhs[j] += centred[j] * target;
+            for k in j..d {
+                gram[j][k] += centred[j] * centred[k];
+            }
+        }
+    }
+    for j in 0..d {
+        for k in 0..j {
+            gram[j][k] = gram[k][j];
+        }
+    }
+
+    let coef = solve(gram.clone(), rhs.clone()).unwrap_or_else(|| {
+        let trace: f64 = (0..d).map(|j| gram[j][j]).sum();
+        let ridge = 1e-10 * trace.max(1.0);
+        let mut gram = gram;
+        for (j, row) in gram.iter_mut().enumerate() {
+            row[j] += ridge;
+        }
+        solve(gram, rhs).unwrap_or_else(|| vec![0.0; d])
+    });
+    let intercept = y_mean - dot(&coef, &x_mean);
+    (coef, intercept)
+}
+
+/// Solve `a·x = b` by Gaussian elimination with partial pivoting, or None
+/// if `a` is singular.
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    let scale = a
+        .iter()
+        .flatten()
+        .fold(0.0f64, |max, v| max.max(v.abs()))
+        .max(f64::MIN_POSITIVE);
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() <= 1e-12 * scale {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..n {
+            let factor = a[row][col] / a[col][col];
+            if factor == 0.0 {
+                continue;
+            }
+            for k in col..n {
+                a[row][k] -= factor * a[col][k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let rest: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - rest) / a[row][row];
+    }
+    Some(x)
+}
+
+/// A fitted logistic regression: one row of coefficients per class, or a
+/// single row for the positive class when there are two.
+#[derive(Debug)]
+pub struct Logistic {
+    pub coef: Vec<Vec<f64>>,
+    pub intercept: Vec<f64>,
+    pub n_iter: usize,
+}
+
+/// Fit multinomial logistic regression (binary logistic for two classes)
+/// with an L2 penalty of strength `1 / c`, as scikit-learn's `lbfgs`
+/// solver does: minimise the mean log loss plus `‖W‖² / (2·c·n)`, stopping
+/// when no gradient component exceeds `tol`.
+pub fn logistic(
+    x: &Matrix,
+    y: &[usize],
+    n_classes: usize,
+    c: f64,
+    fit_intercept: bool,
+    max_iter: usize,
+    tol: f64,
+) -> Logistic {
+    let (n, d) = (x.rows, x.cols);
+    let k = if n_classes == 2 { 1 } else { n_classes };
+    // Each class row holds d weights then the intercept.
+    let width = d + 1;
+    let penalty = 1.0 / (c * n.max(1) as f64);
+    let mut scores = vec![0.0; k];
+
+    let objective = |theta: &[f64], grad: &mut [f64]| -> f64 {
+        grad.iter_mut().for_each(|g| *g = 0.0);
+        let mut loss = 0.0;
+        for i in 0..n {
+            let row = x.row(i);
+            for (class, score) in scores.iter_mut().enumerate() {
+                let params = &theta[class * width..(class + 1) * width];
+                *score = dot(&params[..d], row) + params[d];
+            }
+            if k == 1 {
+                // Binary: log(1 + e^s) - y·s, with derivative σ(s) - y.
+                let s = scores[0];
+                let target = (y[i] == 1) as u8 as f64;
+                loss += s.max(0.0) + (-s.abs()).exp().ln_1p() - target * s;
+                scores[0] = 1.0 / (1.0 + (-s).exp()) - target;
+            } else {
+                let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let sum: f64 = scores.iter().map(|s| (s - max).exp()).sum();
+                loss += max + sum.ln() - scores[y[i]];
+                for (class, score) in scores.iter_mut().enumerate() {
+                    *score = (*score - max).exp() / sum - (class == y[i]) as u8 as f64;
+                }
+            }
+            for (class, residual) in scores.iter().enumerate() {
+                let g = &mut grad[class * width..(class + 1) * width];
+                for (g, v) in g[..d].iter_mut().zip(row) {
+                    *g += residual * v;
+                }
+                g[d] += residual;
+            }
+        }
+        let scale = 1.0 / n.max(1) as f64;
+        loss *= scale;
+        for class in 0..k {
+            for j in 0..width {
+                let at = class * width + j;
+                grad[at] *= scale;
+                if j < d {
+                    loss += 0.5 * penalty * theta[at] * theta[at];
+                    grad[at] += penalty * theta[at];
+                } else if !fit_intercept {
+                    grad[at] = 0.0;
+                }
+            }
+        }
+        loss
+    };
+
+    let mut theta = vec![0.0; k * width];
+    let n_iter = lbfgs(objective, &mut theta, max_iter, tol);
+    Logistic {
+        coef: (0..k)
+            .map(|class| theta[class * width..class * width + d].to_vec())
+            .collect(),
+        intercept: (0..k).map(|class| theta[class * width + d]).collect(),
+        n_iter,
+    }
+}
+
+/// Minimise `f` from `x` with L-BFGS and a backtracking line search.
+/// `f` returns the value and writes the gradient. Returns the number of
+/// iterations taken.
+fn lbfgs(
+    mut f: impl FnMut(&[f64], &mut [f64]) -> f64,
+    x: &mut Vec<f64>,
+    max_iter: usize,
+    tol: f64,
+) -> usize {
+    const HISTORY: usize = 10;
+    let mut grad = vec![0.0; x.len()];
+    let mut value = f(x, &mut grad);
+    let mut history: VecDeque<(Vec<f64>, Vec<f64>, f64)> = VecDeque::new();
+    let mut next = vec![0.0; x.len()];
+    let mut next_grad = vec![0.0; x.len()];
+
+    for iter in 0..max_iter {
+        if grad.iter().all(|g| g.abs() <= tol) {
+            return iter;
+        }
+        // Two-loop recursion for the quasi-Newton direction.
+        let mut q = grad.clone();
+        let mut alphas = Vec::with_capacity(history.len());
+        for (s, y, rho) in history.iter().rev() {
+            let alpha = rho * dot(s, &q);
+            q.iter_mut().zip(y).for_each(|(q, y)| *q -= alpha * y);
+            alphas.push(alpha);
+        }
+        let gamma = match history.back() {
+            Some((s, y, _)) => dot(s, y) / dot(y, y),
+            None => 1.0 / dot(&grad, &grad).sqrt().max(1.0),
+        };
+        q.iter_mut().for_each(|q| *q *= gamma);
+        for ((s, y, rho), alpha) in history.iter().zip(alphas.iter().rev()) {
+            let beta = rho * dot(y, &q);
+            q.iter_mut()
+                .zip(s)
+                .for_each(|(q, s)| *q += s * (alpha - beta));
+        }
+        let mut direction: Vec<f64> = q.iter().map(|q| -q).collect();
+        let mut slope = dot(&grad, &direction);
+        if slope >= 0.0 {
+            history.clear();
+            direction = grad.iter().map(|g| -g).collect();
+            slope = dot(&grad, &direction);
+        }
+
+        let mut step = 1.0;
+        let next_value = loop {
+            for ((next, x), d) in next.iter_mut().zip(x.iter()).zip(&direction) {
+                *next = x + step * d;
+            }
+            let next_value = f(&next, &mut next_grad);
+            if next_value <= value + 1e-4 * step * slope {
+                break next_value;
+            }
+            step *= 0.5;
+            if step < 1e-20 {
+                return iter;
+            }
+        };
+
+        let s: Vec<f64> = next.iter().zip(x.iter()).map(|(a, b)| a - b).collect();
+        let y: Vec<f64> = next_grad.iter().zip(&grad).map(|(a, b)| a - b).collect();
+        let sy = dot(&s, &y);
+        if sy > 1e-12 {
+            history.push_back((s, y, 1.0 / sy));
+            if history.len() > HISTORY {
+                history.pop_front();
+            }
+        }
+        std::mem::swap(x, &mut next);
+        std::mem::swap(&mut grad, &mut next_grad);
+        value = next_value;
+    }
+    max_iter
+}
+
+/// Class probabilities for each row of `x` under a fitted model.
+pub fn logistic_proba(x: &Matrix, coef: &[Vec<f64>], intercept: &[f64]) -> Vec<Vec<f64>> {
+    (0..x.rows)
+        .map(|i| {
+            let scores: Vec<f64> = coef
+                .iter()
+                .zip(intercept)
+                .map(|(w, b)| dot(w, x.row(i)) + b)
+                .collect();
+            if let [s] = scores[..] {
+                let p = 1.0 / (1.0 + (-s).exp());
+                return vec![1.0 - p, p];
+            }
+            let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let exps: Vec<f64> = scores.iter().map(|s| (s - max).exp()).collect();
+            let sum: f64 = exps.iter().sum();
+            exps.iter().map(|e| e / sum).collect()
+        })
+        .collect()
+}
@@ -0,0 +1,283 @@
+//! CART decision trees, alone or bagged into random forests.
+
+use crate::matrix::{Matrix, Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Criterion {
+    Gini,
+    Entropy,
+    SquaredError,
+}
+
+/// What a tree predicts: class indices below `n_classes`, or values.
+pub enum Target<'a> {
+    Classes(&'a [usize], usize),
+    Values(&'a [f64]),
+}
+
+#[derive(Debug, Clone)]
+pub struct Params {
+    pub criterion: Criterion,
+    pub max_depth: usize,
+    pub min_samples_split: usize,
+    pub min_samples_leaf: usize,
+    /// Features considered per split; all of them when it is the number of
+    /// columns.
+    pub max_features: usize,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    /// `(feature, threshold, left, right)`; samples with the feature at or
+    /// below the threshold go left. None for a leaf.
+    pub split: Option<(usize, f64, usize, usize)>,
+    /// Class proportions, or the mean value, of the samples that reach the
+    /// node.
+    pub value: Vec<f64>,
+}
+
+#[derive(Debug)]
+pub struct Tree {
+    pub nodes: Vec<Node>,
+    /// Total impurity decrease per feature, normalised to sum to 1.
+    pub importances: Vec<f64>,
+}
+
+impl Tree {
+    pub fn predict(&self, row: &[f64]) -> &[f64] {
+        let mut at = 0;
+        while let Some((feature, threshold, left, right)) = self.nodes[at].split {
+            at = if row[feature] <= threshold {
+                left
+            } else {
+                right
+            };
+        }
+        &self.nodes[at].value
+    }
+
+    pub fn depth(&self) -> usize {
+        let mut deepest = 0;
+        let mut stack = vec![(0, 0)];
+        while let Some((at, depth)) = stack.pop() {
+            deepest = deepest.max(depth);
+            if let Some((_, _, left, right)) = self.nodes[at].split {
+                stack.push((left, depth + 1));
+                stack.push((right, depth + 1));
+            }
+        }
+        deepest
+    }
+
+    pub fn n_leaves(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| node.split.is_none())
+            .count()
+    }
+}
+
+/// Running statistics of a set of samples, enough for the impurity.
+#[derive(Clone)]
+enum Stats {
+    Counts(Vec<f64>),
+    Moments { sum: f64, sum_sq: f64 },
+}
+
+impl Stats {
+    fn new(target: &Target) -> Self {
+        match target {
+            Target::Classes(_, n_classes) => Stats::Counts(vec![0.0; *n_classes]),
+            Target::Values(_) => Stats::Moments {
+                sum: 0.0,
+                sum_sq: 0.0,
+            },
+        }
+    }
+
+    fn add(&mut self, target: &Target, sample: usize, sign: f64) {
+        match (self, target) {
+            (Stats::Counts(counts), Target::Classes(classes, _)) => counts[classes[sample]] += sign,
+            (Stats::Moments { sum, sum_sq }, Target::Values(values)) => {
+                *sum += sign * values[sample];
+                *sum_sq += sign * values[sample] * values[sample];
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn impurity(&self, criterion: Criterion, n: f64) -> f64 {
+        match self {
+            Stats::Counts(counts) => match criterion {
+                Criterion::Entropy => -counts
+                    .iter()
+                    .filter(|&&c| c > 0.0)
+                    .map(|c| c / n * (c / n).log2())
+                    .sum::<f64>(),
+                _ => 1.0 - counts.iter().map(|c| (c / n) * (c / n)).sum::<f64>(),
+            },
+            Stats::Moments { sum, sum_sq } => (sum_sq / n - (sum / n) * (sum / n)).max(0.0),
+        }
+    }
+
+    fn value(&self, n: f64) -> Vec<f64> {
+        match self {
+            Stats::Counts(counts) => counts.iter().map(|c| c / n).collect(),
+            Stats::Moments { sum, .. } => vec![sum / n],
+        }
+    }
+}
+
+/// Features closer than this count as equal, as in scikit-learn.
+const FEATURE_THRESHOLD: f64 = 1e-7;
+
+struct Split {
+    feature: usize,
+    threshold: f64,
+    /// Samples going left once `samples` is sorted by the feature.
+    left: usize,
+    /// Weighted child impurity, lower is better.
+    score: f64,
+}
+
+/// Grow a tree on `samples` (row indices, repeats allowed for bootstrap
+/// samples) of `x`.
+pub fn fit(
+    x: &Matrix,
+    target: &Target,
+    samples: Vec<usize>,
+    params: &Params,
+    rng: &mut Rng,
+) -> Tree {
+    let mut nodes = Vec::new();
+    let mut importances = vec![0.0; x.cols];
+    let mut features: Vec<usize> = (0..x.cols).collect();
+    // (node index, samples, depth); nodes are created before they are split.
+    let mut pending = vec![(0, samples, 0)];
+    nodes.push(Node {
+        split: None,
+        value: Vec::new(),
+    });
+
+    while let Some((at, mut samples, depth)) = pending.pop() {
+        let n = samples.len();
+        let mut stats = Stats::new(target);
+        for &sample in &samples {
+            stats.add(target, sample, 1.0);
+        }
+        let impurity = stats.impurity(params.criterion, n as f64);
+        nodes[at].value = stats.value(n as f64);
+
+        if depth >= params.max_depth
+            || n < params.min_samples_split
+            || n < 2 * params.min_samples_leaf
+            || impurity <= 1e-12
+        {
+            continue;
+        }
+        rng.shuffle(&mut features);
+        let Some(best) = best_split(x, target, &mut samples, &features, &stats, params) else {
+            continue;
+        };
+
+        samples.sort_by(|&a, &b| x.get(a, best.feature).total_cmp(&x.get(b, best.feature)));
+        let right_samples = samples.split_off(best.left);
+        importances[best.feature] += n as f64 * impurity - best.score;
+        let (left, right) = (nodes.len(), nodes.len() + 1);
+        for _ in 0..2 {
+            nodes.push(Node {
+                split: None,
+                value: Vec::new(),
+            });
+        }
+        nodes[at].split = Some((best.feature, best.threshold, left, right));
+        pending.push((right, right_samples, depth + 1));
+        pending.push((left, samples, depth + 1));
+    }
+
+    let total: f64 = importances.iter().sum();
+    if total > 0.0 {
+        importances.iter_mut().for_each(|v| *v /= total);
+    }
+    Tree { nodes, importances }
+}
+
+/// The best split of `samples` over up to `max_features` of `features`
+/// (already shuffled). Like scikit-learn, features that are constant here
+/// do not count towards the limit.
+fn best_split(
+    x: &Matrix,
+    target: &Target,
+    samples: &mut [usize],
+    features: &[usize],
+    total: &Stats,
+    params: &Params,
+) -> Option<Split> {
+    let n = samples.len();
+    let mut best: Option<Split> = None;
+    let mut tried = 0;
+    for &feature in features {
+        if tried == params.max_features {
+            break;
+        }
+        samples.sort_by(|&a, &b| x.get(a, feature).total_cmp(&x.get(b, feature)));
+        let first = x.get(samples[0], feature);
+        let last = x.get(samples[n - 1], feature);
+        if last <= first + FEATURE_THRESHOLD {
+            continue;
+        }
+        tried += 1;
+
+        let mut left = Stats::new(target);
+        let mut right = total.clone();
+        for i in 0..n - 1 {
+            left.add(target, samples[i], 1.0);
+            right.add(target, samples[i], -1.0);
+            let (n_left, n_right) = (i + 1, n - i - 1);
+            let (here, next) = (x.get(samples[i], feature), x.get(samples[i + 1], feature));
+            if next <= here + FEATURE_THRESHOLD
+                || n_left < params.min_samples_leaf
+                || n_right < params.min_samples_leaf
+            {
+                continue;
+            }
+            let score = n_left as f64 * left.impurity(params.criterion, n_left as f64)
+                + n_right as f64 * right.impurity(params.criterion, n_right as f64);
+            if best.as_ref().map_or(true, |best| score < best.score) {
+                let mut threshold = (here + next) / 2.0;
+                if threshold >= next {
+                    threshold = here;
+                }
+                best = Some(Split {
+                    feature,
+                    threshold,
+                    left: n_left,
+                    score,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// Grow `n_trees` trees, each on a bootstrap sample of the rows when
+/// `bootstrap` is set.
+pub fn fit_forest(
+    x: &Matrix,
+    target: &Target,
+    params: &Params,
+    n_trees: usize,
+    bootstrap: bool,
+    rng: &mut Rng,
+) -> Vec<Tree> {
+    (0..n_trees)
+        .map(|_| {
+            let samples = if bootstrap {
+                (0..x.rows).map(|_| rng.below(x.rows)).collect()
+            } else {
+                (0..x.rows).collect()
+            };
+            fit(x, target, samples, params, rng)
+        })
+        .collect()
+}
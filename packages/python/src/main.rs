@@ -1,8 +1,28 @@
+use std::alloc::System;
 use std::process::ExitCode;
 
+use codepod_host_native::limits::{self, Limits};
+use codepod_rpc::heap::Tracking;
 use rustpython::InterpreterBuilderExt;
 
+mod repl;
+
 fn main() -> ExitCode {
+    // Keep the message and location of a panic for __panic_info, since
+    // panic=abort turns it into a bare trap.
+    codepod_rpc::abi::record_panics();
+
+    // WASI has no working directory of its own; the host passes the
+    // shell's in PWD. Without this, relative paths in os, open() and
+    // pathlib would resolve against / instead of where the shell is.
+    if let Ok(pwd) = std::env::var("PWD") {
+        let _ = std::env::set_current_dir(pwd);
+    }
+
+    // Budgets for runaway scripts; sitecustomize arms the profile hook
+    // that enforces them.
+    limits::install(Limits::from_env(), || HEAP.used());
+
     let config = rustpython::InterpreterBuilder::new().init_stdlib();
 
     // Extract module defs while config is still borrowed, then move config.
@@ -35,9 +55,198 @@ fn main() -> ExitCode {
     #[cfg(feature = "sqlite3")]
     let config = config.add_native_module(sqlite3_def);
 
+    // requests goes through _codepod.fetch, registered below.
+    #[cfg(feature = "requests")]
+    let requests_def = requests_native::module_def(&config.ctx);
+    #[cfg(feature = "requests")]
+    let config = config.add_native_module(requests_def);
+
+    // fastre hands the patterns it cannot run to the stdlib re.
+    #[cfg(feature = "fastre")]
+    let fastre_def = fastre_native::module_def(&config.ctx);
+    #[cfg(feature = "fastre")]
+    let config = config.add_native_module(fastre_def);
+
+    // fastjson likewise hands what it cannot encode or parse to the stdlib.
+    #[cfg(feature = "fastjson")]
+    let fastjson_def = fastjson_native::module_def(&config.ctx);
+    #[cfg(feature = "fastjson")]
+    let config = config.add_native_module(fastjson_def);
+
+    // fasthash hands keyed BLAKE2 and other algorithms to the stdlib hashlib.
+    #[cfg(feature = "fasthash")]
+    let fasthash_def = fasthash_native::module_def(&config.ctx);
+    #[cfg(feature = "fasthash")]
+    let config = config.add_native_module(fasthash_def);
+
     // _codepod host bridge module — always available (not feature-gated)
     let codepod_def = codepod_host_native::module_def(&config.ctx);
     let config = config.add_native_module(codepod_def);
 
+    // `python3 -i` with nothing to run is the interactive console, which
+    // the stock runner only offers on a terminal.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if repl::requested(&args) {
+        return repl::run(config, args.iter().any(|a| a == "-q"));
+    }
+
     rustpython::run(config)
 }
+
+/// Interrupt the running script: its next `_codepod` call raises
+/// KeyboardInterrupt. Only sets a flag, so the host may call it while the
+/// interpreter is suspended in a host import. Returns 0.
+#[no_mangle]
+pub extern "C" fn __request_cancel() -> i32 {
+    codepod_host_native::request_cancel();
+    0
+}
+
+/// Drop what a previous run left behind in the instance: a pending
+/// interrupt and the heap peak. The interpreter itself only lives for one
+/// `_start`, which builds a fresh one, so there is no Python state to clear
+/// here. Returns 0.
+#[no_mangle]
+pub extern "C" fn __reset() -> i32 {
+    codepod_rpc::cancel::clear();
+    codepod_host_native::clear_exception_report();
+    HEAP.reset_peak();
+    0
+}
+
+/// Allocate `size` bytes of guest memory for the host to pass to
+/// `__panic_info` or `__exception_info`.
+#[no_mangle]
+pub extern "C" fn __alloc(size: u32) -> *mut u8 {
+    let layout = std::alloc::Layout::from_size_align(size.max(1) as usize, 1).unwrap();
+    unsafe { std::alloc::alloc(layout) }
+}
+
+/// Free a buffer from `__alloc`.
+///
+/// # Safety
+///
+/// `ptr` must have been allocated by `__alloc` with the same `size`.
+#[no_mangle]
+pub unsafe extern "C" fn __dealloc(ptr: *mut u8, size: u32) {
+    let layout = std::alloc::Layout::from_size_align(size.max(1) as usize, 1).unwrap();
+    std::alloc::dealloc(ptr, layout);
+}
+
+/// Write the interpreter's first panic as JSON, `{"message", "file",
+/// "line", "column"}`, into the output buffer. Returns the length, or the
+/// required size without writing if the buffer is too small, or 0 if the
+/// interpreter has not panicked.
+#[no_mangle]
+pub extern "C" fn __panic_info(out_ptr: *mut u8, out_cap: u32) -> i32 {
+    let Some(report) = codepod_rpc::abi::panic_report() else {
+        return 0;
+    };
+    let json = serde_json::to_vec(&report).unwrap();
+    if json.len() <= out_cap as usize {
+        unsafe { std::ptr::copy_nonoverlapping(json.as_ptr(), out_ptr, json.len()) };
+    }
+    json.len() as i32
+}
+
+/// Write the script's uncaught exception as JSON, `{"type", "message",
+/// "traceback", "frames"}`, into the output buffer. Returns the length, or
+/// the required size without writing if the buffer is too small, or 0 if
+/// the script did not die of an exception. `sitecustomize` builds the
+/// report; see `buildSiteCustomizeSource` on the host.
+#[no_mangle]
+pub extern "C" fn __exception_info(out_ptr: *mut u8, out_cap: u32) -> i32 {
+    let Some(json) = codepod_host_native::exception_report() else {
+        return 0;
+    };
+    if json.len() <= out_cap as usize {
+        unsafe { std::ptr::copy_nonoverlapping(json.as_ptr(), out_ptr, json.len()) };
+    }
+    json.len() as i32
+}
+
+// ---------------------------------------------------------------------------
+// Heap statistics -- let the host show memory use and enforce soft limits
+// ---------------------------------------------------------------------------
+
+#[global_allocator]
+static HEAP: Tracking<System> = Tracking::new(System);
+
+/// Bytes currently allocated on the interpreter's heap.
+#[no_mangle]
+pub extern "C" fn __heap_used() -> u32 {
+    HEAP.used() as u32
+}
+
+/// The most bytes the heap has held since start-up or the last
+/// `__heap_reset_peak`.
+#[no_mangle]
+pub extern "C" fn __heap_peak() -> u32 {
+    HEAP.peak() as u32
+}
+
+/// Restart peak tracking from the current usage.
+#[no_mangle]
+pub extern "C" fn __heap_reset_peak() {
+    HEAP.reset_peak();
+}
+
+// ---------------------------------------------------------------------------
+// ABI handshake -- lets the host reject a guest built for another ABI
+// ---------------------------------------------------------------------------
+
+/// Version of the host-import conventions this interpreter was built
+/// against. Bump it whenever those change incompatibly.
+const ABI_VERSION: u32 = 1;
+
+/// Native modules compiled into this build, one bit each.
+const CAP_NUMPY: u32 = 1 << 0;
+const CAP_PANDAS: u32 = 1 << 1;
+const CAP_PIL: u32 = 1 << 2;
+const CAP_SKLEARN: u32 = 1 << 3;
+const CAP_SQLITE3: u32 = 1 << 4;
+const CAP_REQUESTS: u32 = 1 << 5;
+const CAP_FASTRE: u32 = 1 << 6;
+const CAP_FASTJSON: u32 = 1 << 7;
+const CAP_FASTHASH: u32 = 1 << 8;
+
+/// Return the guest ABI version. Hosts call this right after instantiation
+/// and refuse versions they don't speak.
+#[no_mangle]
+pub extern "C" fn __abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Return a bitmask of the native modules compiled into this build.
+#[no_mangle]
+pub extern "C" fn __abi_capabilities() -> u32 {
+    let mut caps = 0;
+    if cfg!(feature = "numpy") {
+        caps |= CAP_NUMPY;
+    }
+    if cfg!(feature = "pandas") {
+        caps |= CAP_PANDAS;
+    }
+    if cfg!(feature = "pil") {
+        caps |= CAP_PIL;
+    }
+    if cfg!(feature = "sklearn") {
+        caps |= CAP_SKLEARN;
+    }
+    if cfg!(feature = "sqlite3") {
+        caps |= CAP_SQLITE3;
+    }
+    if cfg!(feature = "requests") {
+        caps |= CAP_REQUESTS;
+    }
+    if cfg!(feature = "fastre") {
+        caps |= CAP_FASTRE;
+    }
+    if cfg!(feature = "fastjson") {
+        caps |= CAP_FASTJSON;
+    }
+    if cfg!(feature = "fasthash") {
+        caps |= CAP_FASTHASH;
+    }
+    caps
+}
@@ -0,0 +1,209 @@
+//! Interactive console: `python3 -i` with no script or command.
+//!
+//! Statements are read from `sys.stdin` one line at a time, so a host can
+//! drive a console by writing lines as the user enters them. Prompts
+//! (`sys.ps1`/`sys.ps2`) go to stderr as in CPython, and expression
+//! results are shown by `sys.displayhook`, which also binds `_`.
+
+use std::process::ExitCode;
+
+use rustpython::vm::{self, compiler::Mode, PyResult, VirtualMachine};
+
+/// Whether the arguments ask for the console: `-i` (and optionally `-q`)
+/// with nothing to run. Anything else is left to `rustpython::run`.
+pub fn requested(args: &[String]) -> bool {
+    args.iter().any(|a| a == "-i") && args.iter().all(|a| a == "-i" || a == "-q")
+}
+
+pub fn run(config: rustpython::InterpreterBuilder, quiet: bool) -> ExitCode {
+    let code = config.build().run(|vm| console(vm, quiet));
+    ExitCode::from(u8::try_from(code).unwrap_or(1))
+}
+
+/// Setup the script runner would do: module search path from
+/// PYTHONPATH, prompts and the banner.
+const PRELUDE: &str = r#"
+import os, sys
+sys.path[:0] = [p for p in os.environ.get("PYTHONPATH", "").split(":") if p]
+sys.argv = [""]
+if not hasattr(sys, "ps1"):
+    sys.ps1 = ">>> "
+if not hasattr(sys, "ps2"):
+    sys.ps2 = "... "
+"#;
+
+const BANNER: &str = r#"
+import sys
+print(f"Python {sys.version} on {sys.platform}", file=sys.stderr)
+print('Type "help", "copyright", "credits" or "license" for more information.', file=sys.stderr)
+"#;
+
+fn console(vm: &VirtualMachine, quiet: bool) -> PyResult<()> {
+    let scope = vm.new_scope_with_builtins();
+    scope
+        .globals
+        .set_item("__name__", vm.ctx.new_str("__main__").into(), vm)?;
+    vm.run_code_string(scope.clone(), PRELUDE, "<console>".to_owned())?;
+    if !quiet {
+        vm.run_code_string(scope.clone(), BANNER, "<console>".to_owned())?;
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    loop {
+        let prompt = if lines.is_empty() { "ps1" } else { "ps2" };
+        let line = match read_line(vm, prompt) {
+            Ok(Some(line)) => line,
+            Ok(None) => {
+                write_stderr(vm, "\n");
+                break;
+            }
+            Err(exc) if exc.fast_isinstance(vm.ctx.exceptions.keyboard_interrupt) => {
+                write_stderr(vm, "\nKeyboardInterrupt\n");
+                lines.clear();
+                continue;
+            }
+            Err(exc) => return Err(exc),
+        };
+        if lines.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        lines.push(line);
+        let source = lines.join("\n");
+        if is_incomplete(&source) {
+            continue;
+        }
+        lines.clear();
+
+        if let Err(exc) = execute(vm, &scope, &source) {
+            if exc.fast_isinstance(vm.ctx.exceptions.system_exit) {
+                return Err(exc);
+            }
+            vm.print_exception(exc);
+        }
+    }
+    Ok(())
+}
+
+/// Compile `source` as one interactive statement and run it in `scope`.
+fn execute(vm: &VirtualMachine, scope: &vm::scope::Scope, source: &str) -> PyResult<()> {
+    let code = vm
+        .compile(&format!("{source}\n"), Mode::Single, "<stdin>".to_owned())
+        .map_err(|err| vm.new_syntax_error(&err, Some(source)))?;
+    vm.run_code_obj(code, scope.clone())?;
+    Ok(())
+}
+
+/// Show `sys.<prompt>` and read one line from `sys.stdin`, without its
+/// newline. None at end of input.
+fn read_line(vm: &VirtualMachine, prompt: &str) -> PyResult<Option<String>> {
+    let sys = &vm.sys_module;
+    let prompt = sys.get_attr(prompt, vm)?.str(vm)?;
+    write_stderr(vm, prompt.as_str());
+    let stdin = sys.get_attr("stdin", vm)?;
+    let line = vm.call_method(&stdin, "readline", ())?.str(vm)?;
+    let line = line.as_str();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(line.strip_suffix('\n').unwrap_or(line).to_owned()))
+}
+
+fn write_stderr(vm: &VirtualMachine, text: &str) {
+    if let Ok(stderr) = vm.sys_module.get_attr("stderr", vm) {
+        let _ = vm.call_method(&stderr, "write", (text.to_owned(),));
+        let _ = vm.call_method(&stderr, "flush", ());
+    }
+}
+
+/// Whether the console should prompt for another line before running
+/// `source`, following CPython: an open bracket or string, a trailing
+/// backslash, or a compound statement not yet ended by a blank line.
+fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0usize;
+    // The quote character and whether it is tripled.
+    let mut string: Option<(char, bool)> = None;
+    let mut chars = source.chars().peekable();
+    let mut line = String::new();
+    let mut opens_block = false;
+    let mut continued = false;
+
+    while let Some(c) = chars.next() {
+        if let Some((quote, triple)) = string {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                // An unterminated single-quoted string; the compiler
+                // reports it, so treat the line as ended.
+                '\n' if !triple => string = None,
+                c if c == quote => {
+                    if !triple {
+                        string = None;
+                    } else if chars.peek() == Some(&quote) {
+                        chars.next();
+                        if chars.peek() == Some(&quote) {
+                            chars.next();
+                            string = None;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            if string.is_some() || c != '\n' {
+                continue;
+            }
+        }
+        match c {
+            '#' => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '\'' | '"' => {
+                let triple = chars.peek() == Some(&c) && {
+                    let mut ahead = chars.clone();
+                    ahead.next();
+                    ahead.peek() == Some(&c)
+                };
+                if triple {
+                    chars.next();
+                    chars.next();
+                } else if chars.peek() == Some(&c) {
+                    // An empty string.
+                    chars.next();
+                    line.push_str("''");
+                    continue;
+                }
+                string = Some((c, triple));
+                line.push('\'');
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '\\' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                continue;
+            }
+            '\\' if chars.peek().is_none() => continued = true,
+            '\n' => {
+                if depth == 0 {
+                    let code = line.trim();
+                    opens_block |= code.ends_with(':') || code.starts_with('@');
+                }
+                line.clear();
+                continue;
+            }
+            _ => {}
+        }
+        line.push(c);
+    }
+
+    if continued || depth > 0 || string.is_some_and(|(_, triple)| triple) {
+        return true;
+    }
+    let last = line.trim();
+    if last.ends_with(':') || last.starts_with('@') {
+        return true;
+    }
+    // A compound statement runs once a blank line ends it.
+    opens_block && !last.is_empty()
+}
@@ -6,6 +6,7 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::{Duration, Instant};
 use tokio::sync::Notify;
 
 use anyhow::{bail, Result};
@@ -14,6 +15,10 @@ use serde_json::Value;
 use crate::vfs::MemVfs;
 use crate::wasm::{ShellInstance, WasmEngine};
 
+/// How long past its deadline a command may take to unwind before it is
+/// abandoned.
+const TIMEOUT_GRACE_MS: u64 = 250;
+
 // ── SandboxState ─────────────────────────────────────────────────────────────
 
 /// One live sandbox: a WASM shell instance plus its environment state.
@@ -27,7 +32,7 @@ pub struct SandboxState {
     pub nice: u8,
     /// Per-command wall-clock kill timeout in ms. None = no limit.
     pub timeout_ms: Option<u64>,
-    /// Set to true after a command times out. Subsequent run() calls error immediately.
+    /// Set to true after a command is abandoned at its timeout. Subsequent run() calls error immediately.
     pub poisoned: bool,
     /// When true, run() waits before executing the next command.
     pub paused: Arc<AtomicBool>,
@@ -72,10 +77,17 @@ impl SandboxState {
             self.resume_notify.notified().await;
         }
 
+        // The guest polls the deadline and stops itself with exit 124, keeping
+        // its output. The hard timeout a grace period later only catches a
+        // guest blocked where it cannot poll, e.g. in `sleep`; the instance is
+        // abandoned mid-call then, so the sandbox is poisoned.
+        let deadline = self.timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+        self.shell.set_deadline(deadline);
         let run_fut = self.shell.run_command(cmd);
         let raw = match self.timeout_ms {
             Some(ms) => {
-                match tokio::time::timeout(std::time::Duration::from_millis(ms), run_fut).await {
+                let limit = Duration::from_millis(ms + TIMEOUT_GRACE_MS);
+                match tokio::time::timeout(limit, run_fut).await {
                     Ok(Ok(v)) => v,
                     Ok(Err(e)) => return Err(e),
                     Err(_elapsed) => {
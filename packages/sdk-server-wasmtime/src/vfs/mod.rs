@@ -23,7 +23,7 @@ use serde::{Deserialize, Serialize};
 pub use error::{VfsError, VfsResult};
 pub use inode::{DirEntry, Inode, StatResult, S_TOOL};
 
-use inode::now_ms;
+use inode::{now_ms, TimeMs};
 use path::{join_path, parse_path, split_path};
 
 const MAX_SYMLINK_DEPTH: usize = 40;
@@ -205,6 +205,11 @@ impl MemVfs {
         chmod_in(&mut self.root, path, mode)
     }
 
+    /// Set the modification time of `path`, in ms since the Unix epoch.
+    pub fn set_mtime(&mut self, path: &str, mtime: TimeMs) -> VfsResult<()> {
+        set_mtime_in(&mut self.root, path, mtime)
+    }
+
     pub fn readlink(&self, path: &str) -> VfsResult<String> {
         match resolve(&self.root, path, false, 0)? {
             Inode::Symlink { target, .. } => Ok(target.clone()),
@@ -513,6 +518,19 @@ fn chmod_in(root: &mut Inode, path: &str, mode: u32) -> VfsResult<()> {
     }
 }
 
+fn set_mtime_in(root: &mut Inode, path: &str, mtime: TimeMs) -> VfsResult<()> {
+    let (parent_parts, name) =
+        split_path(path).ok_or_else(|| VfsError::NotFound(path.to_owned()))?;
+    let parent = navigate_dir_mut(root, &parent_parts)?;
+    match parent.get_mut(name) {
+        None => Err(VfsError::NotFound(path.to_owned())),
+        Some(node) => {
+            node.meta_mut().mtime = mtime;
+            Ok(())
+        }
+    }
+}
+
 // ── Navigation ────────────────────────────────────────────────────────────────
 
 /// Walk `root` following `parts` and return the children map of the terminal
@@ -799,6 +817,15 @@ mod tests {
         assert_eq!(v.stat("/usr/bin/mytool").unwrap().permissions & S_TOOL, S_TOOL);
     }
 
+    #[test]
+    fn set_mtime() {
+        let mut v = vfs();
+        v.write_file("/tmp/f", b"x", false).unwrap();
+        v.set_mtime("/tmp/f", 1_000_000).unwrap();
+        assert_eq!(v.stat("/tmp/f").unwrap().mtime, 1_000_000);
+        assert!(matches!(v.set_mtime("/tmp/missing", 0), Err(VfsError::NotFound(_))));
+    }
+
     #[test]
     fn glob_star() {
         let mut v = vfs();
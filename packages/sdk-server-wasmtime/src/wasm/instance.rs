@@ -2,11 +2,13 @@
 //! Per-sandbox WASM instance: loads a module, wires host imports, drives execution.
 
 use anyhow::{bail, Context};
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use wasmtime::{Memory, Module, Store, TypedFunc};
 
 use super::spawn::SpawnContext;
-use super::{StoreData, WasmEngine};
+use super::{check_guest_abi, StoreData, WasmEngine};
 use crate::vfs::MemVfs;
 
 /// A live WASM instance for one sandbox.
@@ -19,6 +21,8 @@ pub struct ShellInstance {
     run_command: TypedFunc<(u32, u32, u32, u32), i32>,
     alloc: TypedFunc<u32, u32>,
     dealloc: TypedFunc<(u32, u32), ()>,
+    /// `__panic_info`, for explaining traps; older guests lack it.
+    panic_info: Option<TypedFunc<(u32, u32), i32>>,
 }
 
 impl ShellInstance {
@@ -53,6 +57,7 @@ impl ShellInstance {
             .instantiate_async(&mut store, &*module)
             .await
             .context("instantiating WASM module")?;
+        check_guest_abi(&mut store, &instance).await?;
 
         // Resolve exported functions and memory.
         let memory = instance
@@ -71,12 +76,15 @@ impl ShellInstance {
             .get_typed_func(&mut store, "__dealloc")
             .context("WASM module missing '__dealloc' export")?;
 
+        let panic_info = instance.get_typed_func(&mut store, "__panic_info").ok();
+
         Ok(Self {
             store,
             memory,
             run_command,
             alloc,
             dealloc,
+            panic_info,
         })
     }
 
@@ -85,6 +93,8 @@ impl ShellInstance {
     /// Allocates a guest buffer, calls `__run_command`, and decodes the output.
     /// If the guest signals the buffer is too small, retries with the requested size.
     pub async fn run_command(&mut self, cmd: &str) -> anyhow::Result<serde_json::Value> {
+        // A cancel request applies to the run it was made during.
+        self.store.data().cancel.store(0, Ordering::Relaxed);
         let cmd_bytes = cmd.as_bytes();
 
         // Allocate guest memory for the command string.
@@ -108,11 +118,14 @@ impl ShellInstance {
             .context("__alloc for output buffer")?;
 
         // Call __run_command.
-        let n = self
+        let n = match self
             .run_command
             .call_async(&mut self.store, (cmd_ptr, cmd_bytes.len() as u32, out_ptr, out_cap))
             .await
-            .context("__run_command")?;
+        {
+            Ok(n) => n,
+            Err(e) => return Err(self.explain_trap(e, "__run_command").await),
+        };
 
         // Check if the output buffer was too small.
         let (out_ptr, out_cap, n) = if n as usize > out_cap as usize {
@@ -130,14 +143,17 @@ impl ShellInstance {
                 .await
                 .context("__alloc for large output buffer")?;
 
-            let n2 = self
+            let n2 = match self
                 .run_command
                 .call_async(
                     &mut self.store,
                     (cmd_ptr, cmd_bytes.len() as u32, big_ptr, needed),
                 )
                 .await
-                .context("__run_command (retry)")?;
+            {
+                Ok(n) => n,
+                Err(e) => return Err(self.explain_trap(e, "__run_command (retry)").await),
+            };
 
             if n2 < 0 || n2 as usize > needed as usize {
                 bail!("__run_command retry failed: n={n2}");
@@ -166,6 +182,47 @@ impl ShellInstance {
         serde_json::from_slice(&result_bytes).context("parsing run_command JSON result")
     }
 
+    /// Attach the panic the guest recorded before trapping, if any, to an
+    /// error from calling `export`.
+    async fn explain_trap(&mut self, err: anyhow::Error, export: &str) -> anyhow::Error {
+        match self.panic_report().await {
+            Some(panic) => err.context(format!("{export}: guest {panic}")),
+            None => err.context(export.to_string()),
+        }
+    }
+
+    /// The guest's first panic from `__panic_info`, as "panicked at
+    /// FILE:LINE:COL: MESSAGE". `None` if the guest has not panicked, lacks the export, or
+    /// cannot be asked any more.
+    async fn panic_report(&mut self) -> Option<String> {
+        let panic_info = self.panic_info?;
+        let mut cap: u32 = 1024;
+        let mut ptr = self.alloc.call_async(&mut self.store, cap).await.ok()?;
+        let mut n = panic_info.call_async(&mut self.store, (ptr, cap)).await.ok()?;
+        if n as u32 > cap {
+            let _ = self.dealloc.call_async(&mut self.store, (ptr, cap)).await;
+            cap = n as u32;
+            ptr = self.alloc.call_async(&mut self.store, cap).await.ok()?;
+            n = panic_info.call_async(&mut self.store, (ptr, cap)).await.ok()?;
+        }
+        let mut json = vec![0u8; n.clamp(0, cap as i32) as usize];
+        let read = self.memory.read(&self.store, ptr as usize, &mut json);
+        let _ = self.dealloc.call_async(&mut self.store, (ptr, cap)).await;
+        if n <= 0 || read.is_err() {
+            return None;
+        }
+        let report: serde_json::Value = serde_json::from_slice(&json).ok()?;
+        let message = report["message"].as_str().unwrap_or("unknown panic");
+        Some(match report["file"].as_str() {
+            Some(file) => format!(
+                "panicked at {file}:{}:{}: {message}",
+                report["line"].as_u64().unwrap_or(0),
+                report["column"].as_u64().unwrap_or(0),
+            ),
+            None => format!("panicked: {message}"),
+        })
+    }
+
     /// Access the sandbox's VFS (for reading files, checking state, etc.).
     pub fn vfs(&self) -> &MemVfs {
         &self.store.data().vfs
@@ -176,6 +233,18 @@ impl ShellInstance {
         &mut self.store.data_mut().vfs
     }
 
+    /// A handle for stopping the running command from another task: store
+    /// [`CANCEL_REQUESTED`](super::CANCEL_REQUESTED) and the guest unwinds at
+    /// its next check with exit 125, keeping the output written so far.
+    pub fn cancel_handle(&self) -> Arc<AtomicI32> {
+        self.store.data().cancel.clone()
+    }
+
+    /// Set when commands must stop; past it the guest unwinds with exit 124.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.store.data_mut().deadline = deadline;
+    }
+
     /// Take the captured stdout bytes (drains the pipe).
     pub fn take_stdout(&mut self) -> bytes::Bytes {
         self.store.data().stdout_pipe.take()
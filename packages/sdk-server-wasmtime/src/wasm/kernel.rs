@@ -4,7 +4,7 @@
 //! Provides the host-side state for:
 //! - `host_pipe` / `host_close_fd` / `host_dup` / `host_dup2`
 //! - `host_read_fd` / `host_write_fd`
-//! - `host_spawn_async` / `host_waitpid` / `host_waitpid_nohang`
+//! - `host_spawn_async` / `host_waitpid` / `host_waitpid_nohang` / `host_kill`
 //! - `host_list_processes`
 
 use std::collections::HashMap;
@@ -12,6 +12,7 @@ use std::sync::{Arc, Mutex};
 
 use serde::Serialize;
 use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
 
 // ── Pipe buffer ───────────────────────────────────────────────────────────────
 
@@ -78,6 +79,8 @@ pub struct ProcessKernel {
     fds: HashMap<i32, FdEntry>,
     /// pid → state.
     procs: HashMap<i32, ChildState>,
+    /// pid → handle for aborting a still-running child's task.
+    aborts: HashMap<i32, AbortHandle>,
     next_fd: i32,
     next_pid: i32,
 }
@@ -87,6 +90,7 @@ impl Default for ProcessKernel {
         Self {
             fds: HashMap::new(),
             procs: HashMap::new(),
+            aborts: HashMap::new(),
             next_fd: 3,
             next_pid: 1,
         }
@@ -162,19 +166,41 @@ impl ProcessKernel {
     // ── process management ─────────────────────────────────────────────────
 
     /// Register a new child process.  Returns its PID.
-    pub fn add_process(&mut self, rx: oneshot::Receiver<i32>) -> i32 {
+    pub fn add_process(&mut self, rx: oneshot::Receiver<i32>, abort: AbortHandle) -> i32 {
         let pid = self.next_pid;
         self.next_pid += 1;
         self.procs.insert(pid, ChildState::Running(rx));
+        self.aborts.insert(pid, abort);
         pid
     }
 
+    /// Terminate a running child. Its exit code becomes `128 + signal`.
+    /// Returns `false` if `pid` is unknown; killing an exited child is a no-op.
+    pub fn kill(&mut self, pid: i32, signal: i32) -> bool {
+        if !self.procs.contains_key(&pid) {
+            return false;
+        }
+        if self.poll_exit(pid).is_none() {
+            if let Some(abort) = self.aborts.remove(&pid) {
+                abort.abort();
+            }
+            self.procs.insert(pid, ChildState::Done(128 + signal));
+        }
+        true
+    }
+
     /// Take the wait-state for `pid`, replacing it with `Done(-1)` as a
     /// placeholder (the caller is responsible for updating it once awaited).
     pub fn take_state(&mut self, pid: i32) -> Option<ChildState> {
         self.procs.insert(pid, ChildState::Done(-1))
     }
 
+    /// Put back the wait-state of a child that is still running after a
+    /// timed wait gave up.
+    pub fn restore_running(&mut self, pid: i32, rx: oneshot::Receiver<i32>) {
+        self.procs.insert(pid, ChildState::Running(rx));
+    }
+
     /// Record the exit code after `wait` completes.
     pub fn set_exit_code(&mut self, pid: i32, code: i32) {
         self.procs.insert(pid, ChildState::Done(code));
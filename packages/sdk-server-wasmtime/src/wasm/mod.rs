@@ -20,18 +20,21 @@ pub mod spawn;
 #[allow(unused_imports)]
 pub use instance::ShellInstance;
 
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use anyhow::Context;
 use bytes::Bytes;
-use serde_json::json;
-use wasmtime::{Caller, Config, Engine, Linker};
+use codepod_rpc::{method, Outbox, Request, RpcError};
+use serde_json::{json, Value};
+use wasmtime::{Caller, Config, Engine, Instance, Linker, Store};
 use wasmtime_wasi::{async_trait, ResourceTable, WasiCtx, WasiCtxBuilder, WasiView};
 use wasmtime_wasi::{HostOutputStream, StreamError, StdoutStream, Subscribe};
 use wasmtime_wasi::preview1::WasiP1Ctx;
 
 use kernel::{ChildState, ProcessKernel};
-use spawn::{SpawnContext, SpawnRequest};
+use spawn::{ShellRunParams, SpawnContext, SpawnRequest};
 
 use crate::vfs::{MemVfs, VfsError};
 
@@ -121,8 +124,23 @@ pub struct StoreData {
     pub env: Vec<(String, String)>,
     /// Scheduling priority for this store's spawned children.
     pub nice: u8,
+    /// Cancellation request for the running command, polled by the guest
+    /// through `host_should_cancel`: 0, [`CANCEL_REQUESTED`] or
+    /// [`CANCEL_TIMED_OUT`]. Shared so another task can stop a run.
+    pub cancel: Arc<AtomicI32>,
+    /// When the running command must stop; once it passes the guest is told
+    /// it timed out.
+    pub deadline: Option<Instant>,
+    /// Holds a `host_rpc` response that did not fit the guest's buffer
+    /// until the guest retries.
+    pub rpc_outbox: Outbox,
 }
 
+/// `cancel` value asking the guest to stop (exit 125).
+pub const CANCEL_REQUESTED: i32 = 1;
+/// `cancel` value telling the guest it ran out of time (exit 124).
+pub const CANCEL_TIMED_OUT: i32 = 2;
+
 impl WasiView for StoreData {
     fn table(&mut self) -> &mut ResourceTable {
         WasiView::table(&mut self.p1_ctx)
@@ -167,6 +185,9 @@ impl StoreData {
             spawn_ctx,
             env: env.to_vec(),
             nice,
+            cancel: Arc::new(AtomicI32::new(0)),
+            deadline: None,
+            rpc_outbox: Outbox::new(),
         })
     }
 }
@@ -232,6 +253,39 @@ impl Drop for WasmEngine {
     }
 }
 
+// ── Guest ABI handshake ───────────────────────────────────────────────────────
+
+/// The guest ABI version this host speaks (see `__abi_version` in the guests).
+pub const GUEST_ABI_VERSION: u32 = 1;
+
+/// Refuse a guest built for a different ABI before any buffers are exchanged
+/// with it, and return its capability bitmask. Guests that predate the
+/// handshake export neither function and are accepted with no capabilities.
+pub async fn check_guest_abi(
+    store: &mut Store<StoreData>,
+    instance: &Instance,
+) -> anyhow::Result<u32> {
+    let Ok(abi_version) = instance.get_typed_func::<(), u32>(&mut *store, "__abi_version") else {
+        return Ok(0);
+    };
+    let version = abi_version
+        .call_async(&mut *store, ())
+        .await
+        .context("__abi_version")?;
+    if version != GUEST_ABI_VERSION {
+        anyhow::bail!(
+            "guest speaks ABI version {version}, but this host speaks version {GUEST_ABI_VERSION}; rebuild the guest or the host"
+        );
+    }
+    match instance.get_typed_func::<(), u32>(&mut *store, "__abi_capabilities") {
+        Ok(capabilities) => capabilities
+            .call_async(&mut *store, ())
+            .await
+            .context("__abi_capabilities"),
+        Err(_) => Ok(0),
+    }
+}
+
 // ── WASM memory helpers ───────────────────────────────────────────────────────
 
 /// Read `len` bytes from guest linear memory at `ptr`. Returns empty Vec on error.
@@ -285,6 +339,7 @@ fn vfs_rc(e: &VfsError) -> i32 {
     match e {
         VfsError::NotFound(_) => -1,
         VfsError::PermissionDenied | VfsError::ReadOnly => -2,
+        VfsError::NoSpace => -7,
         _ => -3,
     }
 }
@@ -446,6 +501,19 @@ fn add_fs_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
         },
     )?;
 
+    // host_set_mtime(path_ptr, path_len, mtime_ms) -> i32
+    linker.func_wrap(
+        "codepod",
+        "host_set_mtime",
+        |mut c: Caller<'_, StoreData>, path_ptr: u32, path_len: u32, mtime_ms: u64| -> i32 {
+            let path = read_str(&mut c, path_ptr, path_len);
+            match c.data_mut().vfs.set_mtime(&path, mtime_ms) {
+                Ok(()) => 0,
+                Err(e) => vfs_rc(&e),
+            }
+        },
+    )?;
+
     // host_glob(pattern_ptr, pattern_len, out_ptr, out_cap) -> i32
     linker.func_wrap(
         "codepod",
@@ -670,12 +738,12 @@ fn add_process_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
             let parent_env = c.data().env.clone();
             let parent_nice = c.data().nice;
 
-            // Spawn background task; get oneshot receiver.
-            let (_, rx) =
+            // Spawn background task; get abort handle and oneshot receiver.
+            let (abort, rx) =
                 spawn::spawn_child(spawn_ctx, parent_vfs, parent_env, stdin_data, stdout_pipe, stderr_pipe, &req, parent_nice);
 
             // Register the child in the kernel's process table.
-            c.data_mut().kernel.add_process(rx)
+            c.data_mut().kernel.add_process(rx, abort)
         },
     )?;
 
@@ -702,6 +770,40 @@ fn add_process_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
         },
     )?;
 
+    // host_waitpid_timeout(pid, timeout_ms, out_ptr, out_cap) -> i32
+    // Like host_waitpid, but writes {"timed_out": true} if the child is still
+    // running after timeout_ms.
+    linker.func_wrap_async(
+        "codepod",
+        "host_waitpid_timeout",
+        |mut caller: Caller<'_, StoreData>,
+         (pid, timeout_ms, out_ptr, out_cap): (i32, u32, u32, u32)| {
+            Box::new(async move {
+                let state = caller.data_mut().kernel.take_state(pid);
+                let j = match state {
+                    Some(ChildState::Running(mut rx)) => {
+                        let limit = std::time::Duration::from_millis(timeout_ms as u64);
+                        match tokio::time::timeout(limit, &mut rx).await {
+                            Ok(res) => {
+                                let code = res.unwrap_or(-1);
+                                caller.data_mut().kernel.set_exit_code(pid, code);
+                                json!({"exit_code": code})
+                            }
+                            Err(_) => {
+                                // Still running: put the receiver back.
+                                caller.data_mut().kernel.restore_running(pid, rx);
+                                json!({"timed_out": true})
+                            }
+                        }
+                    }
+                    Some(ChildState::Done(code)) => json!({"exit_code": code}),
+                    None => json!({"exit_code": -1}),
+                };
+                write_out(&mut caller, out_ptr, out_cap, j.to_string().as_bytes())
+            })
+        },
+    )?;
+
     // host_waitpid_nohang(pid) -> i32  — non-blocking: exit code or -1 if still running.
     linker.func_wrap(
         "codepod",
@@ -711,11 +813,57 @@ fn add_process_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
         },
     )?;
 
+    // host_kill(pid, signal) -> i32  — 0 on success, -1 if no such process.
+    linker.func_wrap(
+        "codepod",
+        "host_kill",
+        |mut c: Caller<'_, StoreData>, pid: i32, signal: i32| -> i32 {
+            if c.data_mut().kernel.kill(pid, signal) {
+                0
+            } else {
+                -1
+            }
+        },
+    )?;
+
     // host_yield() — yield to the async executor (cooperative scheduling).
     linker.func_wrap_async("codepod", "host_yield", |_: Caller<'_, StoreData>, ()| {
         Box::new(async move { tokio::task::yield_now().await })
     })?;
 
+    // host_rpc(req_ptr, req_len, out_ptr, out_cap) -> i32  — async: answer a
+    // codepod-rpc request frame. A retry after a too-small buffer gets the
+    // held response without running the request again.
+    linker.func_wrap_async(
+        "codepod",
+        "host_rpc",
+        |mut caller: Caller<'_, StoreData>, (req_ptr, req_len, out_ptr, out_cap): (u32, u32, u32, u32)| {
+            Box::new(async move {
+                let request = read_mem(&mut caller, req_ptr, req_len);
+                // Run the request before borrowing the outbox: the handler
+                // awaits, and the outbox only takes a synchronous one.
+                let mut outcome = None;
+                if !caller.data().rpc_outbox.holds(&request) {
+                    if let Ok(req) = codepod_rpc::decode::<Request>(&request) {
+                        outcome = Some(serve_rpc(&mut caller, &req).await);
+                    }
+                }
+                let Some(mem) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+                    return -3;
+                };
+                let (bytes, data) = mem.data_and_store_mut(&mut caller);
+                let start = out_ptr as usize;
+                let Some(out) = bytes.get_mut(start..start.saturating_add(out_cap as usize)) else {
+                    return -3;
+                };
+                let outcome = outcome.unwrap_or_else(|| {
+                    Err(RpcError::new(RpcError::INTERNAL_ERROR, "request was not run"))
+                });
+                data.rpc_outbox.answer(&request, out, |_| outcome) as i32
+            })
+        },
+    )?;
+
     // host_list_processes(out_ptr, out_cap) -> i32
     linker.func_wrap(
         "codepod",
@@ -748,6 +896,53 @@ fn add_process_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Serve a guest's `host_rpc` request. Commands run in fresh shell
+/// instances over a copy of the caller's VFS, like `host_spawn_async`
+/// children; `shell.run_all` runs its commands concurrently.
+async fn serve_rpc(caller: &mut Caller<'_, StoreData>, req: &Request) -> Result<Value, RpcError> {
+    let commands: Vec<ShellRunParams> = match req.method.as_str() {
+        method::SHELL_RUN => vec![req.params()?],
+        method::SHELL_RUN_ALL => {
+            #[derive(serde::Deserialize)]
+            struct RunAll {
+                commands: Vec<ShellRunParams>,
+            }
+            req.params::<RunAll>()?.commands
+        }
+        other => return Err(RpcError::method_not_found(other)),
+    };
+    let Some(spawn_ctx) = caller.data().spawn_ctx.clone() else {
+        let unavailable =
+            json!({"exit_code": 1, "stdout": "", "stderr": "subprocess not available\n"});
+        return Ok(match req.method.as_str() {
+            method::SHELL_RUN => unavailable,
+            _ => Value::Array(vec![unavailable; commands.len()]),
+        });
+    };
+
+    let tasks: Vec<_> = commands
+        .into_iter()
+        .map(|params| {
+            let run = spawn::run_shell(
+                spawn_ctx.clone(),
+                caller.data().vfs.cow_clone(),
+                caller.data().env.clone(),
+                params,
+                caller.data().nice,
+            );
+            tokio::spawn(run)
+        })
+        .collect();
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(task.await.map_err(RpcError::failed)?);
+    }
+    Ok(match req.method.as_str() {
+        method::SHELL_RUN => results.pop().unwrap_or(Value::Null),
+        _ => Value::Array(results),
+    })
+}
+
 // ── Network host imports ──────────────────────────────────────────────────────
 
 fn add_network_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
@@ -812,6 +1007,32 @@ fn add_misc_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
         },
     )?;
 
+    // host_should_cancel() -> i32  (0 = keep running, 1 = cancelled, 2 = timed out)
+    linker.func_wrap(
+        "codepod",
+        "host_should_cancel",
+        |c: Caller<'_, StoreData>| -> i32 {
+            let data = c.data();
+            match data.cancel.load(Ordering::Relaxed) {
+                0 if data.deadline.is_some_and(|d| Instant::now() >= d) => CANCEL_TIMED_OUT,
+                state => state,
+            }
+        },
+    )?;
+
+    // host_isatty(fd) -> i32 / host_terminal_size(fd) -> u32
+    // Output is always captured into pipes here, never shown on a terminal.
+    linker.func_wrap(
+        "codepod",
+        "host_isatty",
+        |_: Caller<'_, StoreData>, _fd: i32| -> i32 { 0 },
+    )?;
+    linker.func_wrap(
+        "codepod",
+        "host_terminal_size",
+        |_: Caller<'_, StoreData>, _fd: i32| -> u32 { 0 },
+    )?;
+
     // host_time() -> f64  (seconds since Unix epoch)
     linker.func_wrap("codepod", "host_time", |_: Caller<'_, StoreData>| -> f64 {
         std::time::SystemTime::now()
@@ -820,5 +1041,11 @@ fn add_misc_imports(linker: &mut Linker<StoreData>) -> anyhow::Result<()> {
             .as_secs_f64()
     })?;
 
+    // host_monotonic_ns() -> u64  (nanoseconds on a monotonic clock)
+    linker.func_wrap("codepod", "host_monotonic_ns", |_: Caller<'_, StoreData>| -> u64 {
+        static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        START.get_or_init(std::time::Instant::now).elapsed().as_nanos() as u64
+    })?;
+
     Ok(())
 }
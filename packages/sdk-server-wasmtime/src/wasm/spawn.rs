@@ -5,15 +5,18 @@
 //! program as a shell command via `__run_command` in a background tokio task.
 //! The child's stdout/stderr are captured and fed back into the parent's pipe fds.
 
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 
 use anyhow::Context;
 use serde::Deserialize;
+use serde_json::json;
 use tokio::sync::oneshot;
+use tokio::task::AbortHandle;
 use wasmtime::{Module, Store, TypedFunc};
 
 use super::kernel::PipeBuf;
-use super::{StoreData, WasmEngine};
+use super::{check_guest_abi, StoreData, WasmEngine};
 use crate::vfs::MemVfs;
 
 // ── SpawnContext ──────────────────────────────────────────────────────────────
@@ -90,11 +93,72 @@ fn shell_quote(s: &str) -> String {
     format!("'{}'", s.replace('\'', r"'\''"))
 }
 
+/// Set `key` in `env`, replacing an existing value.
+fn set_env(env: &mut Vec<(String, String)>, key: &str, value: &str) {
+    if let Some(existing) = env.iter_mut().find(|(k, _)| k == key) {
+        existing.1 = value.to_owned();
+    } else {
+        env.push((key.to_owned(), value.to_owned()));
+    }
+}
+
+// ── Guest-requested commands ──────────────────────────────────────────────────
+
+/// Params of a `shell.run` request made through `host_rpc`.
+#[derive(Deserialize, Debug)]
+pub struct ShellRunParams {
+    pub command: String,
+    #[serde(default)]
+    pub stdin: String,
+    /// Directory to run in; the caller's own when absent.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Variables exported before the command runs.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Run a guest's `shell.run` request in a fresh shell instance, the way
+/// `host_spawn_async` runs children, and return its
+/// `{exit_code, stdout, stderr}`.
+pub async fn run_shell(
+    spawn_ctx: Arc<SpawnContext>,
+    parent_vfs: MemVfs,
+    parent_env: Vec<(String, String)>,
+    params: ShellRunParams,
+    nice: u8,
+) -> serde_json::Value {
+    let mut env = parent_env;
+    for (k, v) in &params.env {
+        set_env(&mut env, k, v);
+    }
+    if let Some(cwd) = &params.cwd {
+        set_env(&mut env, "PWD", cwd);
+    }
+    let stdout: PipeBuf = Arc::new(Mutex::new(Vec::new()));
+    let stderr: PipeBuf = Arc::new(Mutex::new(Vec::new()));
+    let exit_code = run_child(
+        spawn_ctx,
+        parent_vfs,
+        params.stdin.into_bytes(),
+        env,
+        params.command,
+        Some(stdout.clone()),
+        Some(stderr.clone()),
+        nice,
+    )
+    .await
+    .unwrap_or(1);
+    let stdout = String::from_utf8_lossy(&stdout.lock().unwrap()).into_owned();
+    let stderr = String::from_utf8_lossy(&stderr.lock().unwrap()).into_owned();
+    json!({"exit_code": exit_code, "stdout": stdout, "stderr": stderr})
+}
+
 // ── Child runner ──────────────────────────────────────────────────────────────
 
 /// Spawn a child WASM instance in a background task.
 ///
-/// Returns the PID assigned to this child.
+/// Returns an abort handle for the task and a receiver for its exit code.
 pub fn spawn_child(
     spawn_ctx: Arc<SpawnContext>,
     parent_vfs: MemVfs,
@@ -104,31 +168,23 @@ pub fn spawn_child(
     stderr_pipe: Option<PipeBuf>,
     req: &SpawnRequest,
     parent_nice: u8,
-) -> (i32, oneshot::Receiver<i32>) {
+) -> (AbortHandle, oneshot::Receiver<i32>) {
     let (tx, rx) = oneshot::channel::<i32>();
 
     // Build the child's environment: parent env overridden by spawn request env.
     let mut child_env = parent_env;
     for [k, v] in &req.env {
-        if let Some(existing) = child_env.iter_mut().find(|(ek, _)| ek == k) {
-            existing.1 = v.clone();
-        } else {
-            child_env.push((k.clone(), v.clone()));
-        }
+        set_env(&mut child_env, k, v);
     }
     // Override CWD via PWD env var.
-    if let Some(pwd) = child_env.iter_mut().find(|(k, _)| k == "PWD") {
-        pwd.1 = req.cwd.clone();
-    } else {
-        child_env.push(("PWD".to_owned(), req.cwd.clone()));
-    }
+    set_env(&mut child_env, "PWD", &req.cwd);
 
     // Child inherits parent nice unless the spawn request overrides it.
     let child_nice = if req.nice > 0 { req.nice } else { parent_nice };
 
     let cmd_str = req.to_shell_cmd();
 
-    tokio::spawn(async move {
+    let task = tokio::spawn(async move {
         let exit_code =
             run_child(spawn_ctx, parent_vfs, stdin_data, child_env, cmd_str, stdout_pipe, stderr_pipe, child_nice)
                 .await
@@ -137,8 +193,8 @@ pub fn spawn_child(
     });
 
     // The PID assignment happens in the kernel (caller's responsibility).
-    // We return rx so the caller can register it.
-    (0 /* placeholder, caller uses real pid */, rx)
+    // We return the handle and rx so the caller can register them.
+    (task.abort_handle(), rx)
 }
 
 /// Create and run a child WASM instance to completion.
@@ -171,6 +227,7 @@ async fn run_child(
         .instantiate_async(&mut store, &ctx.module)
         .await
         .context("instantiating child WASM")?;
+    check_guest_abi(&mut store, &instance).await?;
 
     // Resolve exports.
     let alloc: TypedFunc<u32, u32> = instance.get_typed_func(&mut store, "__alloc")?;
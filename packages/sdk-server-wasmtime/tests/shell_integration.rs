@@ -1,8 +1,19 @@
 //! Integration tests for WasmEngine + ShellInstance against the real
 //! codepod-shell-exec.wasm binary.
 
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+use codepod_rpc::{method, Request, Response, RpcError};
 use sdk_server_wasmtime::vfs::MemVfs;
-use sdk_server_wasmtime::wasm::{ShellInstance, WasmEngine};
+use sdk_server_wasmtime::wasm::spawn::SpawnContext;
+use sdk_server_wasmtime::wasm::{
+    ShellInstance, StoreData, WasmEngine, CANCEL_REQUESTED, CANCEL_TIMED_OUT,
+};
+use serde_json::{json, Value};
+use wasmtime::{Module, Store};
 
 static WASM_BYTES: &[u8] = include_bytes!(concat!(
     env!("CARGO_MANIFEST_DIR"),
@@ -94,3 +105,144 @@ async fn stderr_captured() {
         "expected non-empty output from echo err >&2, stdout={stdout:?} stderr={stderr:?}"
     );
 }
+
+/// The `codepod` functions the shell guest imports, read from its source so a
+/// new import is checked here even before the fixture binary is rebuilt.
+fn shell_guest_imports() -> Vec<&'static str> {
+    let source = include_str!("../../shell-exec/src/host.rs");
+    let block = source
+        .split("#[link(wasm_import_module = \"codepod\")]")
+        .nth(1)
+        .and_then(|rest| rest.split("\n}\n").next())
+        .expect("codepod extern block in shell-exec/src/host.rs");
+    block
+        .lines()
+        .map(str::trim_start)
+        .filter_map(|line| line.strip_prefix("pub fn ").or_else(|| line.strip_prefix("fn ")))
+        .filter_map(|rest| rest.split('(').next())
+        .collect()
+}
+
+#[tokio::test]
+async fn linker_defines_every_shell_import() {
+    let engine = WasmEngine::new().expect("WasmEngine::new");
+    let data = StoreData::new(MemVfs::new(None, None), &[], &[]).expect("StoreData::new");
+    let mut store = Store::new(&engine.engine, data);
+    let defined: HashSet<String> = engine
+        .linker
+        .iter(&mut store)
+        .filter(|(module, _, _)| *module == "codepod")
+        .map(|(_, name, _)| name.to_owned())
+        .collect();
+
+    let imports = shell_guest_imports();
+    assert!(imports.contains(&"host_spawn_async"), "parsed imports: {imports:?}");
+    let missing: Vec<_> = imports.iter().filter(|name| !defined.contains(**name)).collect();
+    assert!(missing.is_empty(), "codepod imports with no wasmtime definition: {missing:?}");
+
+    // And the shell module itself links and instantiates.
+    let module = Module::new(&engine.engine, WASM_BYTES).expect("compile shell module");
+    for import in module.imports() {
+        assert!(
+            engine.linker.get_by_import(&mut store, &import).is_some(),
+            "unresolved import {}::{}",
+            import.module(),
+            import.name()
+        );
+    }
+    make_instance().await.expect("ShellInstance::new");
+}
+
+#[tokio::test]
+async fn should_cancel_reports_cancel_flag_and_deadline() {
+    let engine = WasmEngine::new().expect("WasmEngine::new");
+    let data = StoreData::new(MemVfs::new(None, None), &[], &[]).expect("StoreData::new");
+    let mut store = Store::new(&engine.engine, data);
+    let should_cancel = engine
+        .linker
+        .get(&mut store, "codepod", "host_should_cancel")
+        .and_then(|e| e.into_func())
+        .expect("host_should_cancel is defined")
+        .typed::<(), i32>(&store)
+        .expect("host_should_cancel signature");
+
+    assert_eq!(should_cancel.call_async(&mut store, ()).await.unwrap(), 0);
+
+    store.data_mut().deadline = Some(Instant::now());
+    assert_eq!(should_cancel.call_async(&mut store, ()).await.unwrap(), CANCEL_TIMED_OUT);
+
+    store.data_mut().deadline = None;
+    store.data().cancel.store(CANCEL_REQUESTED, Ordering::Relaxed);
+    assert_eq!(should_cancel.call_async(&mut store, ()).await.unwrap(), CANCEL_REQUESTED);
+}
+
+/// A guest that hands the request frame at offset 0 to `host_rpc` and takes
+/// the response at offset 4096, the way the python guest's subprocess
+/// support does.
+const RPC_GUEST: &str = r#"
+(module
+  (import "codepod" "host_rpc" (func $rpc (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "call") (param $len i32) (param $cap i32) (result i32)
+    (call $rpc (i32.const 0) (local.get $len) (i32.const 4096) (local.get $cap))))
+"#;
+
+#[tokio::test]
+async fn host_rpc_runs_shell_commands() {
+    let engine = WasmEngine::new().expect("WasmEngine::new");
+    let shell = Module::new(&engine.engine, WASM_BYTES).expect("compile shell module");
+    let spawn_ctx = SpawnContext::new(&engine, Arc::new(shell));
+    let data = StoreData::new_with_ctx(MemVfs::new(None, None), &[], &[], Some(spawn_ctx), 0)
+        .expect("StoreData::new_with_ctx");
+    let mut store = Store::new(&engine.engine, data);
+    store.set_fuel(u64::MAX / 2).unwrap();
+    store.epoch_deadline_async_yield_and_update(10);
+
+    let guest = Module::new(&engine.engine, RPC_GUEST).expect("compile rpc guest");
+    let instance = engine
+        .linker
+        .instantiate_async(&mut store, &guest)
+        .await
+        .expect("instantiate rpc guest");
+    let call = instance
+        .get_typed_func::<(u32, u32), i32>(&mut store, "call")
+        .unwrap();
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+
+    let request = codepod_rpc::encode(&Request::new(
+        1,
+        method::SHELL_RUN,
+        json!({"command": "echo $GREETING; pwd", "cwd": "/tmp", "env": {"GREETING": "hi"}}),
+    ));
+    memory.write(&mut store, 0, &request).unwrap();
+    let len = request.len() as u32;
+
+    // Too small a buffer: the host says how much it needs and keeps the
+    // response for the retry.
+    let needed = call.call_async(&mut store, (len, 8)).await.unwrap();
+    assert!(needed > 8, "needed {needed}");
+    let n = call.call_async(&mut store, (len, 4096)).await.unwrap();
+    assert_eq!(n, needed);
+    let mut out = vec![0u8; n as usize];
+    memory.read(&store, 4096, &mut out).unwrap();
+    let result = codepod_rpc::decode::<Response>(&out)
+        .unwrap()
+        .into_result()
+        .unwrap();
+    assert_eq!(result["exit_code"], 0, "{result}");
+    assert_eq!(result["stdout"], "hi\n/tmp\n", "{result}");
+
+    let request = codepod_rpc::encode(&Request::new(2, "nope", Value::Null));
+    memory.write(&mut store, 0, &request).unwrap();
+    let n = call
+        .call_async(&mut store, (request.len() as u32, 4096))
+        .await
+        .unwrap();
+    let mut out = vec![0u8; n as usize];
+    memory.read(&store, 4096, &mut out).unwrap();
+    let err = codepod_rpc::decode::<Response>(&out)
+        .unwrap()
+        .into_result()
+        .unwrap_err();
+    assert_eq!(err.code, RpcError::METHOD_NOT_FOUND);
+}
@@ -446,10 +446,30 @@ describe('Dispatcher', () => {
     });
   });
 
+  it('passes through the exception a Python script died of', async () => {
+    const exception = {
+      type: 'KeyError',
+      message: "'b'",
+      traceback: "Traceback (most recent call last):\n  ...\nKeyError: 'b'\n",
+      frames: [{ file: '/tmp/a.py', line: 2, column: 12, endColumn: 22, name: 'f', source: '    return d["b"]' }],
+    };
+    (sandbox.run as ReturnType<typeof mock>).mockImplementation(async () => ({
+      exitCode: 1,
+      stdout: '',
+      stderr: exception.traceback,
+      executionTimeMs: 5,
+      exception,
+    }));
+
+    const result = await dispatcher.dispatch('run', { command: 'python3 /tmp/a.py' });
+    expect(result).toMatchObject({ exitCode: 1, exception });
+  });
+
   it('omits truncated when not present', async () => {
     const result = await dispatcher.dispatch('run', { command: 'echo hello' });
     expect(result).not.toHaveProperty('truncated');
     expect(result).not.toHaveProperty('errorClass');
+    expect(result).not.toHaveProperty('exception');
   });
 
   describe('multi-sandbox (sandbox.create / sandbox.list / sandbox.remove)', () => {
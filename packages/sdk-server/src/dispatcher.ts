@@ -20,6 +20,7 @@ export interface SandboxLike {
     executionTimeMs: number;
     truncated?: { stdout: boolean; stderr: boolean };
     errorClass?: 'TIMEOUT' | 'CANCELLED' | 'CAPABILITY_DENIED' | 'LIMIT_EXCEEDED';
+    exception?: unknown;
   }>;
   readFile(path: string): Uint8Array;
   writeFile(path: string, data: Uint8Array): void;
@@ -224,6 +225,7 @@ export class Dispatcher {
     };
     if (result.truncated) response.truncated = result.truncated;
     if (result.errorClass) response.errorClass = result.errorClass;
+    if (result.exception) response.exception = result.exception;
     return response;
   }
 
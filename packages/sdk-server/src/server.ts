@@ -125,6 +125,7 @@ async function main(): Promise<void> {
           extensions: extensionSpecs,
           pool: poolConfig,
           storage: storageRequested,
+          randomSeed,
         } = params as {
           wasmDir?: string;
           timeoutMs?: number;
@@ -154,6 +155,7 @@ async function main(): Promise<void> {
             replenishIntervalMs?: number;
           };
           storage?: boolean;
+          randomSeed?: number;
         };
 
         // Validate wasmDir — if not provided, derive from shellWasmPath's directory
@@ -226,6 +228,7 @@ async function main(): Promise<void> {
           pythonPath,
           extensions: extensionConfigs,
           storage: storageCallbacks,
+          randomSeed,
         };
 
         if (limits?.rpcBytes !== undefined) {
@@ -0,0 +1,214 @@
+//! Coreutils run in-process.
+//!
+//! The utilities in [`codepod_coreutils::APPLETS`] (cat, grep, sed, ...)
+//! are the same code as the binaries the host would spawn for them, written
+//! against [`codepod_coreutils::Io`]. A simple command naming one of them
+//! runs it here instead, against the shell's files and cwd, which skips the
+//! round trip of spawning and waiting for a child. Setting
+//! [`SPAWN_COREUTILS_VAR`] spawns them like any other command, so the two
+//! can be compared.
+
+use std::io::{self, BufRead, Cursor, Write};
+
+use codepod_coreutils::{Applet, Io, Metadata};
+
+use crate::host::{HostError, HostInterface, WriteMode};
+use crate::state::ShellState;
+
+/// Shell variable that, when set to anything but the empty string, turns
+/// in-process coreutils off.
+pub const SPAWN_COREUTILS_VAR: &str = "CODEPOD_SPAWN_COREUTILS";
+
+/// Where an applet's standard input comes from.
+pub enum AppletStdin {
+    /// Data from a redirect or an earlier pipeline stage.
+    Data(Vec<u8>),
+    /// The shell's own stdin fd, read only if the applet asks for it.
+    Fd(i32),
+}
+
+/// An applet's exit status and everything it wrote.
+pub struct AppletOutput {
+    pub exit_code: i32,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// The applet to run in-process for `cmd_name`, if any: only a bare name
+/// the host would resolve to one of its tools, and only when
+/// [`SPAWN_COREUTILS_VAR`] is not set.
+pub fn find_applet(state: &ShellState, host: &dyn HostInterface, cmd_name: &str) -> Option<Applet> {
+    if cmd_name.contains('/') {
+        return None;
+    }
+    if state
+        .env
+        .get(SPAWN_COREUTILS_VAR)
+        .is_some_and(|v| !v.is_empty())
+    {
+        return None;
+    }
+    let applet = codepod_coreutils::lookup(cmd_name)?;
+    host.has_tool(cmd_name).then_some(applet)
+}
+
+/// Run `applet` with `args` in the shell's cwd, collecting its output.
+pub fn run_applet(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    applet: Applet,
+    args: &[String],
+    stdin: AppletStdin,
+) -> AppletOutput {
+    let mut io = ShellIo {
+        state,
+        host,
+        stdin: Some(stdin),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    let exit_code = applet(args, &mut io);
+    AppletOutput {
+        exit_code,
+        stdout: io.stdout,
+        stderr: io.stderr,
+    }
+}
+
+/// [`Io`] over the host's filesystem, with output kept in memory.
+struct ShellIo<'a> {
+    state: &'a ShellState,
+    host: &'a dyn HostInterface,
+    stdin: Option<AppletStdin>,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+impl Io for ShellIo<'_> {
+    fn stdin(&mut self) -> io::Result<Box<dyn BufRead>> {
+        let data = match self.stdin.take() {
+            Some(AppletStdin::Data(data)) => data,
+            Some(AppletStdin::Fd(fd)) => read_to_eof(self.host, fd).map_err(to_io_error)?,
+            None => Vec::new(),
+        };
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn open(&self, path: &str) -> io::Result<Box<dyn BufRead>> {
+        let path = self.state.resolve_path(path);
+        let data = self.host.read_file(&path).map_err(to_io_error)?;
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        let path = self.state.resolve_path(path);
+        self.host
+            .stat(&path)
+            .is_ok_and(|info| info.exists && info.is_dir)
+    }
+
+    fn read_dir(&self, path: &str) -> io::Result<Vec<String>> {
+        let path = self.state.resolve_path(path);
+        self.host.readdir(&path).map_err(to_io_error)
+    }
+
+    fn write_atomic(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.state.resolve_path(path);
+        self.host
+            .write_file_atomic(&path, data)
+            .map_err(to_io_error)
+    }
+
+    fn append(&self, path: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.state.resolve_path(path);
+        self.host
+            .write_file(&path, data, WriteMode::Append)
+            .map_err(to_io_error)
+    }
+
+    fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let path = self.state.resolve_path(path);
+        let info = self.host.stat(&path).map_err(to_io_error)?;
+        if !info.exists {
+            return Err(to_io_error(HostError::NotFound(path)));
+        }
+        Ok(Metadata {
+            is_dir: info.is_dir,
+            mode: info.mode & 0o7777,
+            mtime_ms: info.mtime_ms,
+        })
+    }
+
+    fn create_dir(&self, path: &str) -> io::Result<()> {
+        let path = self.state.resolve_path(path);
+        self.host.mkdir(&path).map_err(to_io_error)
+    }
+
+    fn copy_file(&self, from: &str, to: &str) -> io::Result<()> {
+        let from = self.state.resolve_path(from);
+        let to = self.state.resolve_path(to);
+        self.host.copy(&from, &to).map_err(to_io_error)
+    }
+
+    fn set_mode(&self, path: &str, mode: u32) -> io::Result<()> {
+        let path = self.state.resolve_path(path);
+        self.host.chmod(&path, mode).map_err(to_io_error)
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> io::Result<()> {
+        let path = self.state.resolve_path(path);
+        self.host.set_mtime(&path, mtime_ms).map_err(to_io_error)
+    }
+
+    fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.stdout
+    }
+
+    fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.stderr
+    }
+}
+
+/// Everything left on `fd`, up to EOF. `host.read_fd` only drains what is
+/// buffered, so a stage whose writer is still running would come up short;
+/// on wasm the read goes through WASI `fd_read`, which waits for the writer
+/// the way a spawned tool's read would.
+fn read_to_eof(host: &dyn HostInterface, fd: i32) -> Result<Vec<u8>, HostError> {
+    let mut data = Vec::new();
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = host;
+        let mut chunk = vec![0u8; 64 * 1024];
+        loop {
+            let n = crate::host::read_from_fd(fd, &mut chunk)?;
+            if n == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..n]);
+        }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    loop {
+        let chunk = host.read_fd(fd)?;
+        if chunk.is_empty() {
+            break;
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+/// `e` as an `io::Error` carrying the usual strerror text, so applets print
+/// the messages a spawned tool would, less the errno.
+fn to_io_error(e: HostError) -> io::Error {
+    let (kind, message) = match e {
+        HostError::NotFound(_) => (io::ErrorKind::NotFound, "No such file or directory"),
+        HostError::PermissionDenied(_) => (io::ErrorKind::PermissionDenied, "Permission denied"),
+        HostError::IsADirectory(_) => (io::ErrorKind::IsADirectory, "Is a directory"),
+        HostError::Unsupported(_) => (io::ErrorKind::Unsupported, "Operation not supported"),
+        HostError::Interrupted(_) => (io::ErrorKind::Interrupted, "Interrupted system call"),
+        HostError::QuotaExceeded(_) => (io::ErrorKind::QuotaExceeded, "Disk quota exceeded"),
+        HostError::IoError(msg) | HostError::Other(msg) => return io::Error::other(msg),
+    };
+    io::Error::new(kind, message)
+}
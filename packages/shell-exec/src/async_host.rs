@@ -0,0 +1,397 @@
+//! Embedding the shell in hosts whose I/O is future-based.
+//!
+//! An async host implements [`AsyncHostInterface`] on top of its
+//! `HostInterface`, overriding whichever of the process and file calls it
+//! can service asynchronously, and runs scripts with [`exec_command_async`].
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use codepod_shell::ast::Command;
+
+use crate::control::{CancelReason, ControlFlow, ShellError};
+use crate::executor::exec_command;
+use crate::host::{
+    DuplexChild, FetchRequest, FetchResult, FileChange, FileWatch, HostError, HostInterface,
+    SpawnResult, StatInfo, StorageQuota, StreamingChild, TerminalSize, WriteMode,
+};
+use crate::state::ShellState;
+
+/// Asynchronous versions of the host calls that can take a long time:
+/// starting and waiting for processes, and reading and writing files.
+///
+/// Each method defaults to the blocking `HostInterface` call, except
+/// `waitpid_async`, which polls `waitpid_nohang` and yields between polls.
+/// The futures are driven on the calling thread, so they need not be `Send`.
+#[allow(async_fn_in_trait)]
+pub trait AsyncHostInterface: HostInterface {
+    #[allow(clippy::too_many_arguments)]
+    async fn spawn_async(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        self.spawn(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+        )
+    }
+
+    async fn waitpid_async(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        while self.waitpid_nohang(pid)? < 0 {
+            YieldNow(false).await;
+        }
+        self.waitpid(pid)
+    }
+
+    async fn read_file_async(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        self.read_file(path)
+    }
+
+    async fn write_file_async(
+        &self,
+        path: &str,
+        data: &[u8],
+        mode: WriteMode,
+    ) -> Result<(), HostError> {
+        self.write_file(path, data, mode)
+    }
+}
+
+/// Execute `cmd` against an async host.
+///
+/// The executor itself is synchronous: each host future is polled to
+/// completion on this thread before execution moves on. Futures that wait
+/// on work completed elsewhere (another thread, the host's own reactor)
+/// park the thread until woken.
+pub async fn exec_command_async<H: AsyncHostInterface>(
+    state: &mut ShellState,
+    host: &H,
+    cmd: &Command,
+) -> Result<ControlFlow, ShellError> {
+    exec_command(state, &Driven(host), cmd)
+}
+
+/// A future that is pending once, waking itself so it is polled again.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Wakes the thread that is blocked in `block_on`.
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Poll `future` on the current thread until it completes.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::park();
+    }
+}
+
+/// Presents an `AsyncHostInterface` to the executor, routing the async
+/// calls through `block_on` and everything else straight to the host.
+struct Driven<'a, H>(&'a H);
+
+impl<H: AsyncHostInterface> HostInterface for Driven<'_, H> {
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        block_on(self.0.spawn_async(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+        ))
+    }
+
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        self.0.spawn_duplex(program, args, env, cwd)
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        self.0.spawn_streaming(program, args, env, cwd)
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        self.0.has_tool(name)
+    }
+
+    fn time(&self) -> f64 {
+        self.0.time()
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        self.0.monotonic_ms()
+    }
+
+    fn now_unix_ms(&self) -> u64 {
+        self.0.now_unix_ms()
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        self.0.monotonic_ns()
+    }
+
+    fn should_cancel(&self) -> Option<CancelReason> {
+        self.0.should_cancel()
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        self.0.random_bytes(n)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        self.0.isatty(fd)
+    }
+
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        self.0.terminal_size(fd)
+    }
+
+    fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+        self.0.stat(path)
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        block_on(self.0.read_file_async(path))
+    }
+
+    fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
+        block_on(self.0.write_file_async(path, data, mode))
+    }
+
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        self.0.storage_quota()
+    }
+
+    fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
+        self.0.readdir(path)
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), HostError> {
+        self.0.mkdir(path)
+    }
+
+    fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+        self.0.remove(path, recursive)
+    }
+
+    fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        self.0.chmod(path, mode)
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+        self.0.set_mtime(path, mtime_ms)
+    }
+
+    fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
+        self.0.glob(pattern)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+        self.0.rename(from, to)
+    }
+
+    fn write_file_atomic(&self, path: &str, data: &[u8]) -> Result<(), HostError> {
+        self.0.write_file_atomic(path, data)
+    }
+
+    fn create_temp_file(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.0.create_temp_file(dir, prefix)
+    }
+
+    fn create_temp_dir(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.0.create_temp_dir(dir, prefix)
+    }
+
+    fn watch(&self, path: &str) -> Result<FileWatch, HostError> {
+        self.0.watch(path)
+    }
+
+    fn wait_for_change(
+        &self,
+        watch: &mut FileWatch,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        self.0.wait_for_change(watch, timeout_ms)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+        self.0.symlink(target, link_path)
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, HostError> {
+        self.0.readlink(path)
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        self.0.fetch(request)
+    }
+
+    fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError> {
+        self.0.register_tool(name, wasm_path)
+    }
+
+    fn pipe(&self) -> Result<(i32, i32), HostError> {
+        self.0.pipe()
+    }
+
+    fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        block_on(self.0.waitpid_async(pid))
+    }
+
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        self.0.waitpid_timeout(pid, timeout_ms)
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        self.0.kill(pid, signal)
+    }
+
+    fn close_fd(&self, fd: i32) -> Result<(), HostError> {
+        self.0.close_fd(fd)
+    }
+
+    fn dup(&self, fd: i32) -> Result<i32, HostError> {
+        self.0.dup(fd)
+    }
+
+    fn dup2(&self, src_fd: i32, dst_fd: i32) -> Result<(), HostError> {
+        self.0.dup2(src_fd, dst_fd)
+    }
+
+    fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
+        self.0.read_fd(fd)
+    }
+
+    fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
+        self.0.write_fd(fd, data)
+    }
+
+    fn yield_now(&self) -> Result<(), HostError> {
+        self.0.yield_now()
+    }
+
+    fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
+        self.0.waitpid_nohang(pid)
+    }
+
+    fn list_processes(&self) -> Result<String, HostError> {
+        self.0.list_processes()
+    }
+
+    fn socket_connect(&self, host: &str, port: u16, tls: bool) -> Result<u32, HostError> {
+        self.0.socket_connect(host, port, tls)
+    }
+
+    fn socket_send(&self, socket_id: u32, data: &[u8]) -> Result<usize, HostError> {
+        self.0.socket_send(socket_id, data)
+    }
+
+    fn socket_recv(&self, socket_id: u32, max_bytes: usize) -> Result<Vec<u8>, HostError> {
+        self.0.socket_recv(socket_id, max_bytes)
+    }
+
+    fn socket_close(&self, socket_id: u32) -> Result<(), HostError> {
+        self.0.socket_close(socket_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::parse_script;
+    use crate::test_support::mock::{MockHost, MockSpawnOutput};
+    use std::cell::Cell;
+
+    thread_local! {
+        static READ_POLLS: Cell<u32> = const { Cell::new(0) };
+    }
+
+    /// File reads complete only after being polled a few times.
+    impl AsyncHostInterface for MockHost {
+        async fn read_file_async(&self, path: &str) -> Result<Vec<u8>, HostError> {
+            for _ in 0..3 {
+                READ_POLLS.with(|n| n.set(n.get() + 1));
+                YieldNow(false).await;
+            }
+            self.read_file(path)
+        }
+    }
+
+    #[test]
+    fn runs_scripts_against_an_async_host() {
+        let host = MockHost::new()
+            .with_file("/in.txt", b"from file\n")
+            .with_spawn_result(
+                "tool",
+                MockSpawnOutput {
+                    exit_code: 5,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+        let ast = parse_script("read line < /in.txt; echo \"$line\" > /out.txt; tool").unwrap();
+        let flow = block_on(exec_command_async(&mut state, &host, &ast)).unwrap();
+
+        match flow {
+            ControlFlow::Normal(run) => assert_eq!(run.exit_code, 5),
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(host.get_file("/out.txt").as_deref(), Some("from file\n"));
+        assert_eq!(READ_POLLS.with(Cell::get), 3);
+    }
+}
@@ -0,0 +1,672 @@
+//! Recording what a session touched.
+//!
+//! Wrapping a host in [`AuditHost`] logs every file read and write and
+//! every spawn the executor, builtins and virtual commands make through
+//! it: what was touched, how many bytes moved, when it started, how long
+//! it took and how it ended. The [`AuditLog`] can be queried in place or
+//! exported as JSON, so an embedder can show exactly what a script did.
+//!
+//! Lookups that only read metadata (`stat`, `glob`, `readlink`) are not
+//! logged; they are frequent, and a script learns nothing from them that
+//! a listed read would not show. Neither is fd I/O, which moves data the
+//! logged calls have already accounted for.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::control::CancelReason;
+use crate::host::{
+    DuplexChild, FetchRequest, FetchResult, FileChange, FileWatch, HostError, HostInterface,
+    SpawnLimits, SpawnResult, StatInfo, StorageQuota, StreamingChild, TerminalSize, WriteMode,
+};
+
+/// What a logged call did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOp {
+    /// Read a whole file.
+    Read,
+    /// Listed a directory.
+    List,
+    /// Replaced a file's contents.
+    Write,
+    /// Appended to a file.
+    Append,
+    Mkdir,
+    Remove,
+    Rename,
+    Chmod,
+    /// Set a file's modification time.
+    SetMtime,
+    Symlink,
+    /// Created a temporary file or directory.
+    CreateTemp,
+    /// Started a child process.
+    Spawn,
+}
+
+impl AuditOp {
+    /// Whether the op changes the filesystem.
+    pub fn is_write(self) -> bool {
+        !matches!(self, AuditOp::Read | AuditOp::List | AuditOp::Spawn)
+    }
+}
+
+/// How a logged call ended.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Ok,
+    /// The call failed with `message`.
+    Error {
+        message: String,
+    },
+    /// A spawned child that has not been waited for yet.
+    Running,
+    /// A spawned child that exited with `code`.
+    Exited {
+        code: i32,
+    },
+}
+
+/// One logged call.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEvent {
+    pub op: AuditOp,
+    /// The path acted on, or the program spawned. A created temporary
+    /// file or directory is logged under its new path.
+    pub path: String,
+    /// The program's arguments, for a spawn.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// The new path, for a rename or a symlink's target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Bytes read or written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    /// When the call was made, by the host's monotonic clock (ms).
+    pub start_ms: f64,
+    /// How long it took; for a spawn, until the child was reaped. `None`
+    /// while the child runs.
+    pub duration_ms: Option<f64>,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+impl AuditEvent {
+    fn new(op: AuditOp, path: &str) -> Self {
+        Self {
+            op,
+            path: path.to_string(),
+            args: Vec::new(),
+            to: None,
+            bytes: None,
+            start_ms: 0.0,
+            duration_ms: None,
+            outcome: Outcome::Ok,
+        }
+    }
+}
+
+/// The calls an [`AuditHost`] has logged, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct AuditLog {
+    events: Vec<AuditEvent>,
+}
+
+impl AuditLog {
+    pub fn events(&self) -> &[AuditEvent] {
+        &self.events
+    }
+
+    /// The events for calls of kind `op`.
+    pub fn of(&self, op: AuditOp) -> impl Iterator<Item = &AuditEvent> {
+        self.events.iter().filter(move |e| e.op == op)
+    }
+
+    /// The events that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &AuditEvent> {
+        self.events
+            .iter()
+            .filter(|e| matches!(e.outcome, Outcome::Error { .. }))
+    }
+
+    /// The paths read and the paths changed, each in the order first
+    /// touched and without repeats. Failed calls are left out.
+    pub fn touched(&self) -> (Vec<&str>, Vec<&str>) {
+        let mut read = Vec::new();
+        let mut written = Vec::new();
+        for event in &self.events {
+            if event.op == AuditOp::Spawn || matches!(event.outcome, Outcome::Error { .. }) {
+                continue;
+            }
+            let paths = if event.op.is_write() {
+                &mut written
+            } else {
+                &mut read
+            };
+            let renamed_to = event.to.as_ref().filter(|_| event.op == AuditOp::Rename);
+            for path in std::iter::once(&event.path).chain(renamed_to) {
+                if !paths.contains(&path.as_str()) {
+                    paths.push(path.as_str());
+                }
+            }
+        }
+        (read, written)
+    }
+
+    /// Total bytes moved by calls of kind `op`.
+    pub fn bytes(&self, op: AuditOp) -> u64 {
+        self.of(op).filter_map(|e| e.bytes).sum()
+    }
+
+    /// The log as a JSON array of events.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// A host that logs file reads and writes and spawns into an
+/// [`AuditLog`] on their way to the wrapped host. Everything else passes
+/// straight through.
+pub struct AuditHost<'a> {
+    host: &'a dyn HostInterface,
+    log: RefCell<AuditLog>,
+    /// The log entries of spawned children not yet reaped, by pid.
+    running: RefCell<HashMap<i32, usize>>,
+}
+
+impl<'a> AuditHost<'a> {
+    pub fn new(host: &'a dyn HostInterface) -> Self {
+        Self {
+            host,
+            log: RefCell::new(AuditLog::default()),
+            running: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// A copy of the log so far.
+    pub fn log(&self) -> AuditLog {
+        self.log.borrow().clone()
+    }
+
+    /// Return the log so far and start a new one. Children still running
+    /// stay in the old log as running.
+    pub fn take_log(&self) -> AuditLog {
+        let log = self.log.take();
+        self.running.borrow_mut().clear();
+        log
+    }
+
+    /// Make `call`, logging it as `event` with its timing and outcome, and
+    /// the byte count `bytes` gives for a success.
+    fn audit<T>(
+        &self,
+        mut event: AuditEvent,
+        call: impl FnOnce() -> Result<T, HostError>,
+        bytes: impl FnOnce(&T) -> Option<u64>,
+    ) -> Result<T, HostError> {
+        event.start_ms = self.host.monotonic_ms();
+        let result = call();
+        event.duration_ms = Some(self.host.monotonic_ms() - event.start_ms);
+        match &result {
+            Ok(value) => event.bytes = bytes(value),
+            Err(e) => {
+                event.outcome = Outcome::Error {
+                    message: e.to_string(),
+                }
+            }
+        }
+        self.log.borrow_mut().events.push(event);
+        result
+    }
+
+    /// Make the spawn `call`, logging it as running until the child is
+    /// reaped.
+    fn spawned<T>(
+        &self,
+        program: &str,
+        args: &[&str],
+        call: impl FnOnce() -> Result<T, HostError>,
+        pid: impl FnOnce(&T) -> i32,
+    ) -> Result<T, HostError> {
+        let mut event = AuditEvent::new(AuditOp::Spawn, program);
+        event.args = args.iter().map(|a| a.to_string()).collect();
+        event.start_ms = self.host.monotonic_ms();
+        let result = call();
+        match &result {
+            Ok(child) => {
+                event.outcome = Outcome::Running;
+                let mut log = self.log.borrow_mut();
+                self.running
+                    .borrow_mut()
+                    .insert(pid(child), log.events.len());
+                log.events.push(event);
+            }
+            Err(e) => {
+                event.duration_ms = Some(self.host.monotonic_ms() - event.start_ms);
+                event.outcome = Outcome::Error {
+                    message: e.to_string(),
+                };
+                self.log.borrow_mut().events.push(event);
+            }
+        }
+        result
+    }
+
+    /// Make the `call` creating a temporary file or directory in `dir`,
+    /// logging it under the path created.
+    fn created_temp(
+        &self,
+        dir: &str,
+        call: impl FnOnce() -> Result<String, HostError>,
+    ) -> Result<String, HostError> {
+        let result = self.audit(AuditEvent::new(AuditOp::CreateTemp, dir), call, |_| None);
+        if let Ok(path) = &result {
+            if let Some(event) = self.log.borrow_mut().events.last_mut() {
+                event.path = path.clone();
+            }
+        }
+        result
+    }
+
+    /// Note that child `pid` has been reaped with `exit_code`.
+    fn reaped(&self, pid: i32, exit_code: i32) {
+        let Some(index) = self.running.borrow_mut().remove(&pid) else {
+            return;
+        };
+        let now = self.host.monotonic_ms();
+        if let Some(event) = self.log.borrow_mut().events.get_mut(index) {
+            event.duration_ms = Some(now - event.start_ms);
+            event.outcome = Outcome::Exited { code: exit_code };
+        }
+    }
+}
+
+impl HostInterface for AuditHost<'_> {
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        self.spawned(
+            program,
+            args,
+            || {
+                self.host.spawn(
+                    program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+                )
+            },
+            |&pid| pid,
+        )
+    }
+
+    fn spawn_with_limits(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        self.spawned(
+            program,
+            args,
+            || {
+                self.host.spawn_with_limits(
+                    program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+                    limits,
+                )
+            },
+            |&pid| pid,
+        )
+    }
+
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        self.spawned(
+            program,
+            args,
+            || self.host.spawn_duplex(program, args, env, cwd),
+            |child| child.pid,
+        )
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        self.spawned(
+            program,
+            args,
+            || self.host.spawn_streaming(program, args, env, cwd),
+            |child| child.pid,
+        )
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        self.host.has_tool(name)
+    }
+
+    fn time(&self) -> f64 {
+        self.host.time()
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        self.host.monotonic_ms()
+    }
+
+    fn now_unix_ms(&self) -> u64 {
+        self.host.now_unix_ms()
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        self.host.monotonic_ns()
+    }
+
+    fn should_cancel(&self) -> Option<CancelReason> {
+        self.host.should_cancel()
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        self.host.random_bytes(n)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        self.host.isatty(fd)
+    }
+
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        self.host.terminal_size(fd)
+    }
+
+    fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+        self.host.stat(path)
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::Read, path),
+            || self.host.read_file(path),
+            |data| Some(data.len() as u64),
+        )
+    }
+
+    fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
+        let op = match mode {
+            WriteMode::Truncate => AuditOp::Write,
+            WriteMode::Append => AuditOp::Append,
+        };
+        self.audit(
+            AuditEvent::new(op, path),
+            || self.host.write_file(path, data, mode),
+            |_| Some(data.len() as u64),
+        )
+    }
+
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        self.host.storage_quota()
+    }
+
+    fn check_writable(&self, path: &str) -> Result<(), HostError> {
+        self.host.check_writable(path)
+    }
+
+    fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::List, path),
+            || self.host.readdir(path),
+            |_| None,
+        )
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::Mkdir, path),
+            || self.host.mkdir(path),
+            |_| None,
+        )
+    }
+
+    fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::Remove, path),
+            || self.host.remove(path, recursive),
+            |_| None,
+        )
+    }
+
+    fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::Chmod, path),
+            || self.host.chmod(path, mode),
+            |_| None,
+        )
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::SetMtime, path),
+            || self.host.set_mtime(path, mtime_ms),
+            |_| None,
+        )
+    }
+
+    fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
+        self.host.glob(pattern)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+        let mut event = AuditEvent::new(AuditOp::Rename, from);
+        event.to = Some(to.to_string());
+        self.audit(event, || self.host.rename(from, to), |_| None)
+    }
+
+    fn write_file_atomic(&self, path: &str, data: &[u8]) -> Result<(), HostError> {
+        self.audit(
+            AuditEvent::new(AuditOp::Write, path),
+            || self.host.write_file_atomic(path, data),
+            |_| Some(data.len() as u64),
+        )
+    }
+
+    fn create_temp_file(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.created_temp(dir, || self.host.create_temp_file(dir, prefix))
+    }
+
+    fn create_temp_dir(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.created_temp(dir, || self.host.create_temp_dir(dir, prefix))
+    }
+
+    fn watch(&self, path: &str) -> Result<FileWatch, HostError> {
+        self.host.watch(path)
+    }
+
+    fn wait_for_change(
+        &self,
+        watch: &mut FileWatch,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        self.host.wait_for_change(watch, timeout_ms)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+        let mut event = AuditEvent::new(AuditOp::Symlink, link_path);
+        event.to = Some(target.to_string());
+        self.audit(event, || self.host.symlink(target, link_path), |_| None)
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, HostError> {
+        self.host.readlink(path)
+    }
+
+    fn canonicalize(&self, path: &str) -> String {
+        self.host.canonicalize(path)
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        self.host.fetch(request)
+    }
+
+    fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError> {
+        self.host.register_tool(name, wasm_path)
+    }
+
+    fn pipe(&self) -> Result<(i32, i32), HostError> {
+        self.host.pipe()
+    }
+
+    fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        let result = self.host.waitpid(pid)?;
+        self.reaped(pid, result.exit_code);
+        Ok(result)
+    }
+
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        let result = self.host.waitpid_timeout(pid, timeout_ms)?;
+        if let Some(result) = &result {
+            self.reaped(pid, result.exit_code);
+        }
+        Ok(result)
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        self.host.kill(pid, signal)
+    }
+
+    fn close_fd(&self, fd: i32) -> Result<(), HostError> {
+        self.host.close_fd(fd)
+    }
+
+    fn dup(&self, fd: i32) -> Result<i32, HostError> {
+        self.host.dup(fd)
+    }
+
+    fn dup2(&self, src_fd: i32, dst_fd: i32) -> Result<(), HostError> {
+        self.host.dup2(src_fd, dst_fd)
+    }
+
+    fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
+        self.host.read_fd(fd)
+    }
+
+    fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
+        self.host.write_fd(fd, data)
+    }
+
+    fn yield_now(&self) -> Result<(), HostError> {
+        self.host.yield_now()
+    }
+
+    fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
+        let exit_code = self.host.waitpid_nohang(pid)?;
+        if exit_code >= 0 {
+            self.reaped(pid, exit_code);
+        }
+        Ok(exit_code)
+    }
+
+    fn list_processes(&self) -> Result<String, HostError> {
+        self.host.list_processes()
+    }
+
+    fn socket_connect(&self, host: &str, port: u16, tls: bool) -> Result<u32, HostError> {
+        self.host.socket_connect(host, port, tls)
+    }
+
+    fn socket_send(&self, socket_id: u32, data: &[u8]) -> Result<usize, HostError> {
+        self.host.socket_send(socket_id, data)
+    }
+
+    fn socket_recv(&self, socket_id: u32, max_bytes: usize) -> Result<Vec<u8>, HostError> {
+        self.host.socket_recv(socket_id, max_bytes)
+    }
+
+    fn socket_close(&self, socket_id: u32) -> Result<(), HostError> {
+        self.host.socket_close(socket_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::exec_command;
+    use crate::state::ShellState;
+    use crate::test_support::mock::{MockHost, MockSpawnOutput};
+
+    #[test]
+    fn audit_logs_file_access_and_spawns() {
+        let inner = MockHost::new()
+            .with_dir("/tmp")
+            .with_file("/tmp/in.txt", b"hello\n")
+            .with_spawn_result(
+                "tool",
+                MockSpawnOutput {
+                    exit_code: 3,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            );
+        let host = AuditHost::new(&inner);
+        let mut state = ShellState::new_default();
+        state.cwd = "/tmp".into();
+        let src = "read l < in.txt; echo \"$l\" > out.txt; echo more >> out.txt; \
+                   tool -v; read m < missing.txt";
+        let _ = exec_command(&mut state, &host, &codepod_shell::parser::parse(src));
+
+        let log = host.take_log();
+        let read = log.of(AuditOp::Read).next().unwrap();
+        assert_eq!(read.path, "/tmp/in.txt");
+        assert_eq!(read.bytes, Some(6));
+        assert_eq!(log.bytes(AuditOp::Write), 6);
+        assert_eq!(log.bytes(AuditOp::Append), 5);
+
+        let spawn = log.of(AuditOp::Spawn).next().unwrap();
+        assert_eq!(spawn.path, "tool");
+        assert_eq!(spawn.args, ["-v"]);
+        assert_eq!(spawn.outcome, Outcome::Exited { code: 3 });
+        assert!(spawn.duration_ms.is_some());
+
+        let failures: Vec<_> = log.failures().map(|e| e.path.as_str()).collect();
+        assert_eq!(failures, ["/tmp/missing.txt"]);
+        assert_eq!(log.touched(), (vec!["/tmp/in.txt"], vec!["/tmp/out.txt"]));
+
+        let json: serde_json::Value = serde_json::from_str(&log.to_json()).unwrap();
+        assert_eq!(json[0]["op"], "read");
+        assert_eq!(json[0]["status"], "ok");
+        assert!(log.to_json().contains(r#""status":"exited","code":3"#));
+        assert!(host.log().is_empty());
+    }
+}
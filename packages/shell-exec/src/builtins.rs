@@ -62,7 +62,7 @@ pub fn try_builtin(
         "printf" => builtin_printf(state, args),
         "true" | ":" => Some(BuiltinResult::Result(0)),
         "false" => Some(BuiltinResult::Result(1)),
-        "pwd" => Some(builtin_pwd(state)),
+        "pwd" => Some(builtin_pwd(state, host, args)),
         "cd" => Some(builtin_cd(state, host, args)),
         "exit" => Some(builtin_exit(state, args)),
         "export" => Some(builtin_export(state, args)),
@@ -112,56 +112,58 @@ pub fn try_builtin(
     result
 }
 
+/// Names of all builtin commands.
+pub const BUILTIN_NAMES: &[&str] = &[
+    "echo",
+    "true",
+    ":",
+    "false",
+    "pwd",
+    "cd",
+    "exit",
+    "export",
+    "unset",
+    "set",
+    "local",
+    "declare",
+    "typeset",
+    "test",
+    "[",
+    "read",
+    "shift",
+    "type",
+    "command",
+    "let",
+    "which",
+    "source",
+    ".",
+    "eval",
+    "return",
+    "history",
+    "trap",
+    "getopts",
+    "mapfile",
+    "readarray",
+    "chmod",
+    "date",
+    "exec",
+    "readonly",
+    "pushd",
+    "popd",
+    "dirs",
+    "sleep",
+    "wait",
+    "jobs",
+    "ps",
+    "kill",
+    "alias",
+    "unalias",
+    "nice",
+];
+
 /// Returns true if `cmd_name` is the name of a builtin command.
 pub fn is_builtin(cmd_name: &str) -> bool {
-    matches!(
-        cmd_name,
-        "echo"
-            | "true"
-            | ":"
-            | "false"
-            | "pwd"
-            | "cd"
-            | "exit"
-            | "export"
-            | "unset"
-            | "set"
-            | "local"
-            | "declare"
-            | "typeset"
-            | "test"
-            | "["
-            | "read"
-            | "shift"
-            | "type"
-            | "command"
-            | "let"
-            | "which"
-            | "source"
-            | "."
-            | "eval"
-            | "return"
-            | "history"
-            | "trap"
-            | "getopts"
-            | "mapfile"
-            | "readarray"
-            | "chmod"
-            | "date"
-            | "exec"
-            | "readonly"
-            | "pushd"
-            | "popd"
-            | "dirs"
-            | "sleep"
-            | "wait"
-            | "jobs"
-            | "ps"
-            | "kill"
-            | "alias"
-            | "unalias"
-            | "nice"
-    )
+    BUILTIN_NAMES.contains(&cmd_name)
 }
 
 // ---------------------------------------------------------------------------
@@ -464,7 +466,13 @@ fn format_printf(format: &str, args: &[String]) -> String {
 
 // -- pwd ------------------------------------------------------------------
 
-fn builtin_pwd(state: &ShellState) -> BuiltinResult {
+fn builtin_pwd(state: &ShellState, host: &dyn HostInterface, args: &[String]) -> BuiltinResult {
+    // -P prints the directory with symlinks resolved; -L (the default)
+    // prints the logical path the shell cd'd through.
+    if args.last().is_some_and(|a| a == "-P") {
+        shell_println!("{}", state.resolve_path_physical(host, &state.cwd));
+        return BuiltinResult::Result(0);
+    }
     let cwd = state
         .env
         .get("PWD")
@@ -477,6 +485,15 @@ fn builtin_pwd(state: &ShellState) -> BuiltinResult {
 // -- cd -------------------------------------------------------------------
 
 fn builtin_cd(state: &mut ShellState, host: &dyn HostInterface, args: &[String]) -> BuiltinResult {
+    // -P resolves symlinks in the new directory; -L keeps the logical path.
+    // The last one given wins, and `set -P` makes -P the default.
+    let mut physical = state.flags.contains(&ShellFlag::Physical);
+    let mut args = args;
+    while let Some(opt) = args.first().filter(|a| *a == "-P" || *a == "-L") {
+        physical = opt == "-P";
+        args = &args[1..];
+    }
+
     let target = if args.is_empty() {
         state
             .env
@@ -501,7 +518,11 @@ fn builtin_cd(state: &mut ShellState, host: &dyn HostInterface, args: &[String])
     } else {
         format!("{}/{}", state.cwd, target)
     };
-    let normalized = normalize_path(&resolved);
+    let normalized = if physical {
+        state.resolve_path_physical(host, &resolved)
+    } else {
+        normalize_path(&resolved)
+    };
 
     // Check that the target is a directory
     match host.stat(&normalized) {
@@ -647,6 +668,9 @@ fn builtin_set(state: &mut ShellState, args: &[String]) -> BuiltinResult {
             if flag_chars == "o" {
                 // -o / +o option
                 i += 1;
+                if i == args.len() {
+                    print_set_options(state, add);
+                }
                 if i < args.len() {
                     let opt = &args[i];
                     match opt.as_str() {
@@ -671,6 +695,20 @@ fn builtin_set(state: &mut ShellState, args: &[String]) -> BuiltinResult {
                                 state.flags.remove(&ShellFlag::Nounset);
                             }
                         }
+                        "noclobber" => {
+                            if add {
+                                state.flags.insert(ShellFlag::Noclobber);
+                            } else {
+                                state.flags.remove(&ShellFlag::Noclobber);
+                            }
+                        }
+                        "physical" => {
+                            if add {
+                                state.flags.insert(ShellFlag::Physical);
+                            } else {
+                                state.flags.remove(&ShellFlag::Physical);
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -691,6 +729,20 @@ fn builtin_set(state: &mut ShellState, args: &[String]) -> BuiltinResult {
                                 state.flags.remove(&ShellFlag::Nounset);
                             }
                         }
+                        'C' => {
+                            if add {
+                                state.flags.insert(ShellFlag::Noclobber);
+                            } else {
+                                state.flags.remove(&ShellFlag::Noclobber);
+                            }
+                        }
+                        'P' => {
+                            if add {
+                                state.flags.insert(ShellFlag::Physical);
+                            } else {
+                                state.flags.remove(&ShellFlag::Physical);
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -702,6 +754,30 @@ fn builtin_set(state: &mut ShellState, args: &[String]) -> BuiltinResult {
     BuiltinResult::Result(0)
 }
 
+/// `set -o` lists each option as on or off; `set +o` prints the `set`
+/// commands that would restore them.
+fn print_set_options(state: &ShellState, listing: bool) {
+    let options = [
+        ("errexit", ShellFlag::Errexit),
+        ("noclobber", ShellFlag::Noclobber),
+        ("nounset", ShellFlag::Nounset),
+        ("physical", ShellFlag::Physical),
+        ("pipefail", ShellFlag::Pipefail),
+    ];
+    let mut output = String::new();
+    for (name, flag) in options {
+        let on = state.flags.contains(&flag);
+        if listing {
+            let value = if on { "on" } else { "off" };
+            output.push_str(&format!("{name:<15}\t{value}\n"));
+        } else {
+            let sign = if on { '-' } else { '+' };
+            output.push_str(&format!("set {sign}o {name}\n"));
+        }
+    }
+    shell_print!("{}", output);
+}
+
 // -- local ----------------------------------------------------------------
 
 fn builtin_local(state: &mut ShellState, args: &[String]) -> BuiltinResult {
@@ -1006,11 +1082,21 @@ fn eval_test_expr(state: &ShellState, host: &dyn HostInterface, args: &[String])
                     .map(|s| s.exists && s.size > 0)
                     .unwrap_or(false)
             }
+            "-L" | "-h" => {
+                let path = state.resolve_path(val);
+                host.lstat(&path).is_ok_and(|s| s.is_symlink)
+            }
             "-r" | "-w" | "-x" => {
-                // In our sandbox, just check existence
+                let bits = match op.as_str() {
+                    "-r" => 0o444,
+                    "-w" => 0o222,
+                    _ => 0o111,
+                };
                 let path = state.resolve_path(val);
-                host.stat(&path).map(|s| s.exists).unwrap_or(false)
+                host.stat(&path)
+                    .is_ok_and(|s| s.exists && (s.mode & bits) != 0)
             }
+            "-t" => val.parse().is_ok_and(|fd| state.isatty(host, fd)),
             _ => !val.is_empty(), // single arg: true if non-empty
         };
     }
@@ -1294,6 +1380,10 @@ fn builtin_command(host: &dyn HostInterface, args: &[String]) -> Option<BuiltinR
 // -- let ------------------------------------------------------------------
 
 fn builtin_let(state: &mut ShellState, args: &[String]) -> BuiltinResult {
+    if args.is_empty() {
+        shell_eprintln!("let: expression expected");
+        return BuiltinResult::Result(1);
+    }
     let mut last_val = 0i64;
     for arg in args {
         last_val = eval_arithmetic(state, arg);
@@ -1602,63 +1692,69 @@ fn builtin_getopts(state: &mut ShellState, args: &[String]) -> BuiltinResult {
 
 // -- mapfile / readarray --------------------------------------------------
 
+/// `mapfile [-t] [-n count] [-s count] [array]`: read stdin lines into an
+/// indexed array (default `MAPFILE`). `-s` skips leading lines, `-n` caps
+/// the number stored (0 means no limit), and `-t` drops the newlines.
 fn builtin_mapfile(
     state: &mut ShellState,
     host: &dyn HostInterface,
     args: &[String],
 ) -> BuiltinResult {
     let mut strip_newline = false;
-    let mut max_lines: Option<usize> = None;
+    let mut max_lines = 0usize;
+    let mut skip_lines = 0usize;
     let mut array_name = "MAPFILE".to_string();
 
     let mut i = 0;
     while i < args.len() {
         match args[i].as_str() {
             "-t" => strip_newline = true,
-            "-n" => {
+            opt @ ("-n" | "-s") => {
                 i += 1;
-                if i < args.len() {
-                    max_lines = args[i].parse().ok();
+                let Some(count) = args.get(i).and_then(|a| a.parse::<usize>().ok()) else {
+                    let value = args.get(i).map_or("", String::as_str);
+                    shell_eprint!("mapfile: {}: invalid line count\n", value);
+                    return BuiltinResult::Result(1);
+                };
+                if opt == "-n" {
+                    max_lines = count;
+                } else {
+                    skip_lines = count;
                 }
             }
-            other => {
-                if !other.starts_with('-') {
-                    array_name = other.to_string();
-                }
+            other if other.starts_with('-') && other.len() > 1 => {
+                shell_eprint!("mapfile: {}: invalid option\n", other);
+                return BuiltinResult::Result(2);
             }
+            other => array_name = other.to_string(),
         }
         i += 1;
     }
 
-    // Read stdin from fd 0.
-    let stdin_data = match host.read_fd(0) {
-        Ok(data) if !data.is_empty() => String::from_utf8_lossy(&data).to_string(),
-        _ => String::new(),
+    // Stdin comes from fd 0, after anything a previous `read` in the same
+    // compound command left buffered.
+    let mut input = state.pipeline_stdin.take().unwrap_or_default();
+    if let Ok(data) = host.read_fd(0) {
+        input.push_str(&String::from_utf8_lossy(&data));
+    }
+    let limit = if max_lines == 0 {
+        usize::MAX
+    } else {
+        max_lines
     };
-    let lines: Vec<String> = stdin_data
-        .split('\n')
-        .enumerate()
-        .take_while(|(idx, _)| max_lines.is_none_or(|max| *idx < max))
-        .map(|(_, line)| {
+    let lines: Vec<String> = input
+        .split_inclusive('\n')
+        .skip(skip_lines)
+        .take(limit)
+        .map(|line| {
             if strip_newline {
-                line.to_string()
+                line.strip_suffix('\n').unwrap_or(line).to_string()
             } else {
-                format!("{}\n", line)
+                line.to_string()
             }
         })
         .collect();
 
-    // Remove trailing empty entry that comes from final newline
-    let lines = if strip_newline {
-        let mut l = lines;
-        if l.last().map(|s| s.is_empty()).unwrap_or(false) {
-            l.pop();
-        }
-        l
-    } else {
-        lines
-    };
-
     state.arrays.insert(array_name, lines);
 
     BuiltinResult::Result(0)
@@ -1793,7 +1889,7 @@ fn parse_symbolic_mode(s: &str, current: u32) -> Option<u32> {
 // -- date -----------------------------------------------------------------
 
 fn builtin_date(host: &dyn HostInterface, args: &[String]) -> BuiltinResult {
-    let ts_secs = host.time() as u64;
+    let ts_secs = host.now_unix_ms() / 1000;
 
     let output = if !args.is_empty() && args[0].starts_with('+') {
         let format = &args[0][1..];
@@ -2067,62 +2163,127 @@ fn builtin_sleep(_host: &dyn HostInterface, args: &[String]) -> BuiltinResult {
     BuiltinResult::Result(0)
 }
 
+/// `wait [-n] [id ...]`: wait for background jobs, where each id is a pid
+/// or a job spec (`%N`, `%%`, `%+`, `%-`, `%prefix`).
+///
+/// With no ids every job is waited for and the status is 0; otherwise it is
+/// the status of the last id (127 for an unknown one). `-n` waits for the
+/// first of the given jobs (default: all) to finish and returns its status.
+/// Waited-for jobs are removed from the job table, and job numbering starts
+/// again at 1 once it is empty.
 fn builtin_wait(
     state: &mut ShellState,
     host: &dyn HostInterface,
     args: &[String],
 ) -> BuiltinResult {
-    if args.is_empty() {
-        // Wait for all background jobs
-        for job in &mut state.jobs {
-            if job.done.is_none() {
-                match host.waitpid(job.pid) {
-                    Ok(result) => {
-                        job.done = Some(result.exit_code);
-                    }
-                    Err(_) => {
-                        job.done = Some(-1);
-                    }
+    let any = args.first().is_some_and(|a| a == "-n");
+    let ids = if any { &args[1..] } else { args };
+
+    let mut indices = Vec::new();
+    let mut last_code = 0;
+    for id in ids {
+        if let Some(idx) = find_job(state, id) {
+            indices.push(idx);
+        } else if let Some(spec) = id.strip_prefix('%') {
+            shell_eprintln!("wait: %{}: no such job", spec);
+            last_code = 127;
+        } else if let Ok(pid) = id.parse::<i32>() {
+            // Not a job of ours, but possibly another host-spawned process.
+            last_code = match crate::executor::wait_child(state, host, pid) {
+                Ok(result) => result.exit_code,
+                Err(_) => {
+                    shell_eprintln!("wait: pid {} is not a child of this shell", pid);
+                    127
                 }
+            };
+        } else {
+            shell_eprintln!("wait: `{}': not a pid or valid job spec", id);
+            return BuiltinResult::Result(2);
+        }
+    }
+
+    if any {
+        if ids.is_empty() {
+            indices = (0..state.jobs.len()).collect();
+        }
+        if indices.is_empty() {
+            return BuiltinResult::Result(127);
+        }
+        let code = wait_any_job(state, host, &indices);
+        return BuiltinResult::Result(code);
+    }
+
+    if ids.is_empty() {
+        indices = (0..state.jobs.len()).collect();
+    }
+    for &idx in &indices {
+        last_code = wait_job(state, host, idx);
+    }
+    if ids.is_empty() {
+        last_code = 0;
+    }
+    let waited: Vec<usize> = indices.iter().map(|&idx| state.jobs[idx].id).collect();
+    state.jobs.retain(|j| !waited.contains(&j.id));
+    if state.jobs.is_empty() {
+        state.next_job_id = 1;
+    }
+    BuiltinResult::Result(last_code)
+}
+
+/// Index into the job table for a pid or job spec.
+fn find_job(state: &ShellState, id: &str) -> Option<usize> {
+    let Some(spec) = id.strip_prefix('%') else {
+        let pid: i32 = id.parse().ok()?;
+        return state.jobs.iter().position(|j| j.pid == pid && pid != 0);
+    };
+    let last = state.jobs.len().checked_sub(1);
+    match spec {
+        "" | "%" | "+" => last,
+        "-" => last?.checked_sub(1),
+        _ => match spec.parse::<usize>() {
+            Ok(n) => state.jobs.iter().position(|j| j.id == n),
+            Err(_) => state.jobs.iter().position(|j| j.command.starts_with(spec)),
+        },
+    }
+}
+
+/// Block until the job at `idx` exits, recording and returning its status.
+fn wait_job(state: &mut ShellState, host: &dyn HostInterface, idx: usize) -> i32 {
+    if let Some(code) = state.jobs[idx].done {
+        return code;
+    }
+    let code =
+        crate::executor::wait_child(state, host, state.jobs[idx].pid).map_or(127, |r| r.exit_code);
+    state.jobs[idx].done = Some(code);
+    code
+}
+
+/// `wait -n`: return the status of the first of `indices` to finish and
+/// drop it from the job table. Already-finished jobs are reported first.
+fn wait_any_job(state: &mut ShellState, host: &dyn HostInterface, indices: &[usize]) -> i32 {
+    loop {
+        if let Some(&idx) = indices.iter().find(|&&i| state.jobs[i].done.is_some()) {
+            let code = state.jobs.remove(idx).done.unwrap_or(0);
+            if state.jobs.is_empty() {
+                state.next_job_id = 1;
             }
+            return code;
         }
-        let last_code = state.jobs.last().and_then(|j| j.done).unwrap_or(0);
-        state.last_exit_code = last_code;
-    } else {
-        // Wait for specific PIDs
-        let mut last_code = 0;
-        for arg in args {
-            if let Ok(pid) = arg.parse::<i32>() {
-                if let Some(job) = state.jobs.iter_mut().find(|j| j.pid == pid) {
-                    if job.done.is_none() {
-                        match host.waitpid(pid) {
-                            Ok(result) => {
-                                job.done = Some(result.exit_code);
-                                last_code = result.exit_code;
-                            }
-                            Err(_) => {
-                                job.done = Some(-1);
-                                last_code = -1;
-                            }
-                        }
-                    } else {
-                        last_code = job.done.unwrap_or(0);
-                    }
-                } else {
-                    match host.waitpid(pid) {
-                        Ok(result) => {
-                            last_code = result.exit_code;
-                        }
-                        Err(_) => {
-                            last_code = 127;
-                        }
-                    }
-                }
+        if host.should_cancel().is_some()
+            || state.deadline_ms.is_some_and(|d| host.monotonic_ms() >= d)
+        {
+            return 124;
+        }
+        // No "wait for any" host call exists, so poll each child in turn.
+        for &idx in indices {
+            match host.waitpid_timeout(state.jobs[idx].pid, 10) {
+                Ok(None) => continue,
+                Ok(Some(result)) => state.jobs[idx].done = Some(result.exit_code),
+                Err(_) => state.jobs[idx].done = Some(127),
             }
+            break;
         }
-        state.last_exit_code = last_code;
     }
-    BuiltinResult::Result(state.last_exit_code)
 }
 
 fn builtin_jobs(state: &mut ShellState, host: &dyn HostInterface) -> BuiltinResult {
@@ -2182,15 +2343,15 @@ fn signal_number(name: &str) -> Option<i32> {
         "USR1" => Some(10),
         "USR2" => Some(12),
         "TERM" => Some(15),
-        "STOP" => Some(17),
-        "CONT" => Some(19),
+        "CONT" => Some(18),
+        "STOP" => Some(19),
         _ => None,
     }
 }
 
 /// All signal names we advertise, in numeric order.
 const SIGNAL_NAMES: &[&str] = &[
-    "HUP", "INT", "QUIT", "KILL", "USR1", "USR2", "TERM", "STOP", "CONT",
+    "HUP", "INT", "QUIT", "KILL", "USR1", "USR2", "TERM", "CONT", "STOP",
 ];
 
 fn builtin_kill(
@@ -2198,14 +2359,32 @@ fn builtin_kill(
     host: &dyn HostInterface,
     args: &[String],
 ) -> BuiltinResult {
-    // Parse options: kill [-s SIGNAL | -SIGNAL] PID... | kill -l
+    // Parse options: kill [-s SIGNAL | -n NUM | -SIGNAL] ID... | kill -l [NUM]
     let mut signal = 15_i32; // default: TERM
     let mut pids: Vec<String> = Vec::new();
     let mut i = 0;
 
     while i < args.len() {
         let arg = &args[i];
-        if arg == "-l" {
+        if arg == "-l" && i + 1 < args.len() {
+            // Name the signal for a number (or exit status 128+N)
+            let n = args[i + 1]
+                .parse::<i32>()
+                .map(|n| if n > 128 { n - 128 } else { n });
+            match n
+                .ok()
+                .and_then(|n| SIGNAL_NAMES.iter().find(|s| signal_number(s) == Some(n)))
+            {
+                Some(name) => {
+                    shell_println!("{}", name);
+                    return BuiltinResult::Result(0);
+                }
+                None => {
+                    shell_eprintln!("kill: {}: invalid signal specification", args[i + 1]);
+                    return BuiltinResult::Result(1);
+                }
+            }
+        } else if arg == "-l" {
             // List signals
             let mut out = String::new();
             for (idx, name) in SIGNAL_NAMES.iter().enumerate() {
@@ -2217,7 +2396,7 @@ fn builtin_kill(
             out.push('\n');
             shell_print!("{}", out);
             return BuiltinResult::Result(0);
-        } else if arg == "-s" {
+        } else if arg == "-s" || arg == "-n" {
             i += 1;
             if i >= args.len() {
                 shell_eprintln!("kill: -s requires a signal name");
@@ -2264,61 +2443,38 @@ fn builtin_kill(
         return BuiltinResult::Result(1);
     }
 
-    let exit_code = 128 + signal;
     let mut errors = 0;
-
-    for pid_str in &pids {
-        if let Some(job_spec) = pid_str.strip_prefix('%') {
-            // Job reference: %N
-            if let Ok(job_id) = job_spec.parse::<usize>() {
-                if let Some(job) = state.jobs.iter_mut().find(|j| j.id == job_id) {
-                    if job.done.is_some() {
-                        shell_eprintln!("kill: %{}: job has already terminated", job_id);
-                        errors += 1;
-                    } else {
-                        job.done = Some(exit_code);
-                        // Also reap via waitpid_nohang so the kernel knows
-                        let _ = host.waitpid_nohang(job.pid);
-                    }
-                } else {
-                    shell_eprintln!("kill: %{}: no such job", job_id);
+    for target in &pids {
+        let pid = if target.starts_with('%') {
+            match find_job(state, target) {
+                Some(idx) if state.jobs[idx].done.is_none() => state.jobs[idx].pid,
+                Some(_) => {
+                    shell_eprintln!("kill: {}: job has already terminated", target);
                     errors += 1;
+                    continue;
                 }
-            } else {
-                shell_eprintln!("kill: %{}: invalid job spec", job_spec);
-                errors += 1;
-            }
-        } else if let Ok(pid) = pid_str.parse::<i32>() {
-            // Direct PID
-            // Check if it's in our job table first
-            if let Some(job) = state.jobs.iter_mut().find(|j| j.pid == pid) {
-                if job.done.is_some() {
-                    shell_eprintln!("kill: ({}): process already terminated", pid);
+                None => {
+                    shell_eprintln!("kill: {}: no such job", target);
                     errors += 1;
-                } else {
-                    job.done = Some(exit_code);
-                    let _ = host.waitpid_nohang(pid);
-                }
-            } else {
-                // Not in job table — try waitpid_nohang to see if it exists
-                match host.waitpid_nohang(pid) {
-                    Ok(code) if code >= 0 => {
-                        // Already exited
-                        shell_eprintln!("kill: ({}): process already terminated", pid);
-                        errors += 1;
-                    }
-                    Ok(_) => {
-                        // Still running but not in our job table; nothing more
-                        // we can do without a host kill syscall.
-                    }
-                    Err(_) => {
-                        shell_eprintln!("kill: ({}): no such process", pid);
-                        errors += 1;
-                    }
+                    continue;
                 }
             }
+        } else if let Ok(pid) = target.parse::<i32>() {
+            pid
         } else {
-            shell_eprintln!("kill: {}: invalid pid", pid_str);
+            shell_eprintln!("kill: {}: arguments must be process or job IDs", target);
+            errors += 1;
+            continue;
+        };
+
+        let delivered = if signal == 0 {
+            // Signal 0 only checks that the process is still running.
+            matches!(host.waitpid_nohang(pid), Ok(code) if code < 0)
+        } else {
+            host.kill(pid, signal).is_ok()
+        };
+        if !delivered {
+            shell_eprintln!("kill: ({}) - No such process", pid);
             errors += 1;
         }
     }
@@ -2418,12 +2574,16 @@ fn builtin_nice(state: &mut ShellState, host: &dyn HostInterface, args: &[String
     }
 
     let prog = args[i];
+    let argv: Vec<String> = args[i..].iter().map(|a| a.to_string()).collect();
+    if !crate::executor::command_allowed(state, host, &argv) {
+        return BuiltinResult::Result(126);
+    }
     let spawn_args: Vec<&str> = args[i + 1..].to_vec();
     let env_pairs: Vec<(&str, &str)> =
         state.env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
 
     match host.spawn(prog, &spawn_args, &env_pairs, &state.cwd, "", state.stdin_fd, state.stdout_fd, 2, nice) {
-        Ok(pid) => match host.waitpid(pid) {
+        Ok(pid) => match crate::executor::wait_child(state, host, pid) {
             Ok(result) => BuiltinResult::Result(result.exit_code),
             Err(_) => BuiltinResult::Result(1),
         },
@@ -2721,6 +2881,37 @@ mod tests {
         assert_eq!(code, 1);
     }
 
+    #[test]
+    fn cd_follows_symlinks_physically_with_p() {
+        let host = MockHost::new()
+            .with_dir("/data/real")
+            .with_symlink("/home/link", "../data/real");
+        let mut state = ShellState::new_default();
+        state.cwd = "/home".to_string();
+
+        assert_eq!(run_builtin(&mut state, &host, "cd", &["link"]), 0);
+        assert_eq!(state.cwd, "/home/link");
+        let (_, stdout, _) = run_capture(&mut state, &host, "pwd", &["-P"]);
+        assert_eq!(stdout, "/data/real\n");
+
+        state.cwd = "/home".to_string();
+        assert_eq!(run_builtin(&mut state, &host, "cd", &["-P", "link"]), 0);
+        assert_eq!(state.cwd, "/data/real");
+
+        state.cwd = "/home".to_string();
+        state.flags.insert(ShellFlag::Physical);
+        assert_eq!(run_builtin(&mut state, &host, "cd", &["link/../real"]), 0);
+        assert_eq!(state.cwd, "/data/real");
+        assert_eq!(
+            run_builtin(&mut state, &host, "test", &["-L", "/home/link"]),
+            0
+        );
+        assert_eq!(
+            run_builtin(&mut state, &host, "test", &["-h", "/data/real"]),
+            1
+        );
+    }
+
     // -- exit tests -------------------------------------------------------
 
     #[test]
@@ -3155,6 +3346,23 @@ mod tests {
         assert_eq!(code, 1); // 0 -> exit code 1
     }
 
+    #[test]
+    fn let_multiple_expressions() {
+        let mut state = ShellState::new_default();
+        let host = MockHost::new();
+        let code = run_builtin(&mut state, &host, "let", &["a=2", "b=a*3"]);
+        assert_eq!(code, 0);
+        assert_eq!(state.env.get("b").unwrap(), "6");
+    }
+
+    #[test]
+    fn let_without_arguments_fails() {
+        let mut state = ShellState::new_default();
+        let host = MockHost::new();
+        let code = run_builtin(&mut state, &host, "let", &[]);
+        assert_eq!(code, 1);
+    }
+
     // -- eval tests -------------------------------------------------------
 
     #[test]
@@ -3330,6 +3538,35 @@ mod tests {
         assert_eq!(state.arrays.get("lines").unwrap().len(), 2);
     }
 
+    #[test]
+    fn mapfile_skip_and_keep_newlines() {
+        let mut state = ShellState::new_default();
+        let host = MockHost::new();
+        let code = run_builtin_stdin(
+            &mut state,
+            &host,
+            "readarray",
+            &["-s", "1", "-n", "2"],
+            "head\na\nb\nc",
+        );
+        assert_eq!(code, 0);
+        assert_eq!(
+            state.arrays.get("MAPFILE").unwrap(),
+            &vec!["a\n".to_string(), "b\n".to_string()]
+        );
+
+        run_builtin_stdin(&mut state, &host, "mapfile", &["-t", "-n", "0"], "x\ny");
+        assert_eq!(
+            state.arrays.get("MAPFILE").unwrap(),
+            &vec!["x".to_string(), "y".to_string()]
+        );
+
+        assert_eq!(
+            run_builtin(&mut state, &host, "mapfile", &["-n", "lots"]),
+            1
+        );
+    }
+
     // -- which tests ------------------------------------------------------
 
     #[test]
@@ -3449,14 +3686,26 @@ mod tests {
         assert_eq!(code, 0);
     }
 
+    #[test]
+    fn date_reads_the_host_clock() {
+        let mut state = ShellState::new_default();
+        let host = MockHost::new().with_unix_ms(86_400_000 + 1_999);
+        let (_, stdout, _) = run_capture(&mut state, &host, "date", &["+%Y-%m-%d %s"]);
+        assert_eq!(stdout, "1970-01-02 86401\n");
+        assert_eq!(host.monotonic_ns(), 0);
+    }
+
     // -- chmod tests ------------------------------------------------------
 
     #[test]
     fn chmod_basic() {
         let mut state = ShellState::new_default();
-        let host = MockHost::new();
+        let host = MockHost::new().with_file("/tmp/script.sh", b"echo hi\n");
         let code = run_builtin(&mut state, &host, "chmod", &["755", "/tmp/script.sh"]);
         assert_eq!(code, 0);
+        assert_eq!(host.get_mode("/tmp/script.sh").unwrap(), 0o755);
+        let code = run_builtin(&mut state, &host, "chmod", &["755", "/tmp/missing"]);
+        assert_eq!(code, 1);
     }
 
     // -- exec tests -------------------------------------------------------
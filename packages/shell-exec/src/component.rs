@@ -0,0 +1,391 @@
+//! The shell as a component: bindings for the `shell-guest` world in
+//! `codepod-rpc/wit/sandbox.wit`, generated with `wit-bindgen`.
+//!
+//! [`ComponentHost`] implements `HostInterface` over the world's
+//! `filesystem`, `process`, `clock` and `fetch` imports, and the binary
+//! exports `shell` with [`export_shell_guest!`]. Built with the `component`
+//! feature, [`GuestHost`](crate::host::GuestHost) is this host, so the
+//! module needs no `codepod` core imports.
+//!
+//! Calls the world leaves out (sockets, tool registration, the process
+//! list, `set_mtime`) fail with `Unsupported`; `glob` walks `list-dir`.
+
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::control::CancelReason;
+use crate::host::{
+    fill_random, spawn_duplex_over_pipes, spawn_streaming_over_pipes, DirEntry, DuplexChild,
+    FetchRequest, FetchResult, HostError, HostInterface, SpawnResult, StatInfo, StreamingChild,
+    WriteMode,
+};
+
+wit_bindgen::generate!({
+    path: "../codepod-rpc/wit",
+    world: "shell-guest",
+    pub_export_macro: true,
+    export_macro_name: "export_shell_guest",
+    default_bindings_module: "codepod_shell_exec::component",
+});
+
+use codepod::sandbox::{clock, fetch, filesystem, process, types};
+
+/// How often `waitpid_timeout` polls a child that has not exited.
+const WAIT_POLL_MS: u64 = 10;
+
+impl From<types::HostError> for HostError {
+    fn from(e: types::HostError) -> Self {
+        match e {
+            types::HostError::NotFound(msg) => Self::NotFound(msg),
+            types::HostError::PermissionDenied(msg) => Self::PermissionDenied(msg),
+            types::HostError::IsADirectory(msg) => Self::IsADirectory(msg),
+            types::HostError::Io(msg) => Self::IoError(msg),
+            types::HostError::Unsupported(msg) => Self::Unsupported(msg),
+            types::HostError::Interrupted(msg) => Self::Interrupted(msg),
+            types::HostError::QuotaExceeded(msg) => Self::QuotaExceeded(msg),
+            types::HostError::Other(msg) => Self::Other(msg),
+        }
+    }
+}
+
+impl From<filesystem::StatInfo> for StatInfo {
+    fn from(s: filesystem::StatInfo) -> Self {
+        Self {
+            exists: true,
+            is_file: s.is_file,
+            is_dir: s.is_dir,
+            is_symlink: s.is_symlink,
+            size: s.size,
+            mode: s.mode,
+            mtime_ms: s.mtime_ms,
+        }
+    }
+}
+
+fn unsupported<T>(call: &str) -> Result<T, HostError> {
+    Err(HostError::Unsupported(call.into()))
+}
+
+/// Append to `out` the paths under `dir` that match the remaining
+/// `pattern` components. Like the shell, `*` and `?` skip dotfiles unless
+/// the component itself starts with a dot.
+fn glob_walk(dir: &str, pattern: &[&str], out: &mut Vec<String>) {
+    let Some((first, rest)) = pattern.split_first() else {
+        out.push(dir.to_string());
+        return;
+    };
+    let Ok(entries) = filesystem::list_dir(dir) else {
+        return;
+    };
+    let base = dir.trim_end_matches('/');
+    for entry in entries {
+        if entry.name.starts_with('.') && !first.starts_with('.') {
+            continue;
+        }
+        if !crate::expand::glob_matches(first, &entry.name) {
+            continue;
+        }
+        let path = format!("{base}/{}", entry.name);
+        if rest.is_empty() || entry.is_dir {
+            glob_walk(&path, rest, out);
+        }
+    }
+}
+
+/// `HostInterface` over the `shell-guest` world's imports.
+pub struct ComponentHost;
+
+impl HostInterface for ComponentHost {
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        // `spawn-request` has no inline stdin; hand the data over through a
+        // pipe the child reads to EOF.
+        let stdin_pipe = if stdin_data.is_empty() {
+            None
+        } else {
+            let (read_fd, write_fd) = self.pipe()?;
+            let written = self.write_fd(write_fd, stdin_data.as_bytes());
+            let _ = self.close_fd(write_fd);
+            if let Err(e) = written {
+                let _ = self.close_fd(read_fd);
+                return Err(e);
+            }
+            Some(read_fd)
+        };
+        let request = process::SpawnRequest {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            env: env
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            cwd: cwd.to_string(),
+            stdin_fd: stdin_pipe.unwrap_or(stdin_fd),
+            stdout_fd,
+            stderr_fd,
+            nice,
+        };
+        let spawned = process::spawn(&request);
+        if let Some(fd) = stdin_pipe {
+            let _ = self.close_fd(fd);
+        }
+        Ok(spawned?)
+    }
+
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        spawn_duplex_over_pipes(self, program, args, env, cwd)
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        spawn_streaming_over_pipes(self, program, args, env, cwd)
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        process::has_tool(name)
+    }
+
+    fn time(&self) -> f64 {
+        clock::now_unix_ms() as f64 / 1000.0
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        self.monotonic_ns() as f64 / 1_000_000.0
+    }
+
+    fn now_unix_ms(&self) -> u64 {
+        clock::now_unix_ms()
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        clock::monotonic_ns()
+    }
+
+    fn should_cancel(&self) -> Option<CancelReason> {
+        if codepod_rpc::cancel::requested() {
+            return Some(CancelReason::Cancelled);
+        }
+        match process::should_cancel() {
+            0 => None,
+            2 => Some(CancelReason::Timeout),
+            _ => Some(CancelReason::Cancelled),
+        }
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        let mut buf = vec![0; n];
+        fill_random(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        process::isatty(fd)
+    }
+
+    fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+        Ok(filesystem::stat(path)?.into())
+    }
+
+    fn lstat(&self, path: &str) -> Result<StatInfo, HostError> {
+        Ok(filesystem::lstat(path)?.into())
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        Ok(filesystem::read_file(path)?)
+    }
+
+    fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
+        let mode = match mode {
+            WriteMode::Truncate => filesystem::WriteMode::Truncate,
+            WriteMode::Append => filesystem::WriteMode::Append,
+        };
+        Ok(filesystem::write_file(path, data, mode)?)
+    }
+
+    fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
+        Ok(filesystem::list_dir(path)?
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect())
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>, HostError> {
+        let mut entries: Vec<DirEntry> = filesystem::list_dir(path)?
+            .into_iter()
+            .map(|entry| DirEntry {
+                name: entry.name,
+                is_dir: entry.is_dir,
+                is_symlink: entry.is_symlink,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), HostError> {
+        Ok(filesystem::mkdir(path)?)
+    }
+
+    fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+        Ok(filesystem::remove(path, recursive)?)
+    }
+
+    fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        Ok(filesystem::chmod(path, mode)?)
+    }
+
+    fn set_mtime(&self, _path: &str, _mtime_ms: u64) -> Result<(), HostError> {
+        unsupported("set_mtime")
+    }
+
+    fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
+        let pattern = crate::builtins::normalize_path(pattern);
+        let components: Vec<&str> = pattern
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .collect();
+        let mut matches = Vec::new();
+        glob_walk("/", &components, &mut matches);
+        Ok(matches)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+        Ok(filesystem::rename(from, to)?)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+        Ok(filesystem::symlink(target, link_path)?)
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, HostError> {
+        Ok(filesystem::readlink(path)?)
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        let request = fetch::Request {
+            method: request.method.to_string(),
+            url: request.url.to_string(),
+            headers: request
+                .headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: request.body.map(|b| b.as_bytes().to_vec()),
+        };
+        match fetch::fetch(&request) {
+            Ok(response) => FetchResult {
+                ok: (200..400).contains(&response.status),
+                status: response.status,
+                headers: response.headers.into_iter().collect(),
+                body: String::from_utf8_lossy(&response.body).into_owned(),
+                body_base64: Some(BASE64.encode(&response.body)),
+                error: None,
+            },
+            Err(reason) => FetchResult::failed(reason),
+        }
+    }
+
+    fn register_tool(&self, _name: &str, _wasm_path: &str) -> Result<(), HostError> {
+        unsupported("register_tool")
+    }
+
+    fn pipe(&self) -> Result<(i32, i32), HostError> {
+        Ok(process::pipe()?)
+    }
+
+    fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        Ok(SpawnResult {
+            exit_code: process::waitpid(pid)?,
+        })
+    }
+
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        let deadline = self.monotonic_ns() + u64::from(timeout_ms) * 1_000_000;
+        loop {
+            if let Some(exit_code) = process::waitpid_nohang(pid)? {
+                return Ok(Some(SpawnResult { exit_code }));
+            }
+            if self.monotonic_ns() >= deadline {
+                return Ok(None);
+            }
+            std::thread::sleep(Duration::from_millis(WAIT_POLL_MS));
+        }
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        Ok(process::kill(pid, signal)?)
+    }
+
+    fn close_fd(&self, fd: i32) -> Result<(), HostError> {
+        Ok(process::close_fd(fd)?)
+    }
+
+    fn dup(&self, fd: i32) -> Result<i32, HostError> {
+        Ok(process::dup(fd)?)
+    }
+
+    fn dup2(&self, src_fd: i32, dst_fd: i32) -> Result<(), HostError> {
+        Ok(process::dup2(src_fd, dst_fd)?)
+    }
+
+    fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
+        Ok(process::read_fd(fd)?)
+    }
+
+    fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
+        Ok(process::write_fd(fd, data)?)
+    }
+
+    fn yield_now(&self) -> Result<(), HostError> {
+        // Component calls block until they complete, so there is nothing
+        // to hand the turn to.
+        Ok(())
+    }
+
+    fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
+        Ok(process::waitpid_nohang(pid)?.unwrap_or(-1))
+    }
+
+    fn list_processes(&self) -> Result<String, HostError> {
+        unsupported("list_processes")
+    }
+
+    fn socket_connect(&self, _host: &str, _port: u16, _tls: bool) -> Result<u32, HostError> {
+        unsupported("socket_connect")
+    }
+
+    fn socket_send(&self, _socket_id: u32, _data: &[u8]) -> Result<usize, HostError> {
+        unsupported("socket_send")
+    }
+
+    fn socket_recv(&self, _socket_id: u32, _max_bytes: usize) -> Result<Vec<u8>, HostError> {
+        unsupported("socket_recv")
+    }
+
+    fn socket_close(&self, _socket_id: u32) -> Result<(), HostError> {
+        unsupported("socket_close")
+    }
+}
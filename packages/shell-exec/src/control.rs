@@ -1,5 +1,9 @@
+use codepod_shell::parser::ParseError;
+use codepod_shell::token::Span;
 use serde::{Deserialize, Serialize};
 
+use crate::host::HostError;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RunResult {
     pub exit_code: i32,
@@ -38,21 +42,129 @@ pub enum CancelReason {
     Cancelled,
 }
 
+/// Broad classification of a [`ShellError`], so embedders can react to a
+/// kind of failure without matching on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The command text is malformed.
+    Syntax,
+    /// A host call (file access, process spawn, ...) failed.
+    Host,
+    /// A recursion limit was exceeded.
+    Limit,
+}
+
 #[derive(Debug)]
 pub enum ShellError {
-    ParseError(String),
-    HostError(String),
-    SubstitutionTooDeep,
-    FunctionTooDeep,
+    /// `source` failed to parse; `error` locates the offending token in it.
+    Syntax {
+        source: String,
+        error: ParseError,
+    },
+    HostError(HostError),
+    /// A resource limit in [`Limits`](crate::state::Limits) was exceeded.
+    LimitExceeded(LimitKind),
+}
+
+/// Which of the [`Limits`](crate::state::Limits) was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    FunctionDepth,
+    SubstitutionDepth,
+    LoopIterations,
+    CapturedBytes,
+    Substitutions,
+}
+
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::FunctionDepth => "maximum function call depth exceeded",
+            Self::SubstitutionDepth => "maximum command substitution depth exceeded",
+            Self::LoopIterations => "maximum loop iterations exceeded",
+            Self::CapturedBytes => "maximum captured output size exceeded",
+            Self::Substitutions => "maximum number of command substitutions exceeded",
+        })
+    }
+}
+
+impl ShellError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Syntax { .. } => ErrorCategory::Syntax,
+            Self::HostError(_) => ErrorCategory::Host,
+            Self::LimitExceeded(_) => ErrorCategory::Limit,
+        }
+    }
+
+    /// The exit status a shell reports for this error: 2 for syntax errors,
+    /// as in bash, and 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self.category() {
+            ErrorCategory::Syntax => 2,
+            _ => 1,
+        }
+    }
+
+    /// A serializable description of the error, located in its source text
+    /// where that is known.
+    pub fn diagnostic(&self) -> Diagnostic {
+        let (source, span, position) = match self {
+            Self::Syntax { source, error } => (
+                Some(source.clone()),
+                Some(error.span),
+                Some(error.span.line_col(source)),
+            ),
+            _ => (None, None, None),
+        };
+        Diagnostic {
+            category: self.category(),
+            message: match self {
+                Self::Syntax { error, .. } => error.to_string(),
+                other => other.to_string(),
+            },
+            source,
+            span,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+        }
+    }
 }
 
 impl std::fmt::Display for ShellError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::ParseError(msg) => write!(f, "parse error: {msg}"),
-            Self::HostError(msg) => write!(f, "host error: {msg}"),
-            Self::SubstitutionTooDeep => write!(f, "maximum command substitution depth exceeded"),
-            Self::FunctionTooDeep => write!(f, "maximum function call depth exceeded"),
+            Self::Syntax { source, error } => {
+                let (line, column) = error.span.line_col(source);
+                write!(f, "line {line}, col {column}: {error}")
+            }
+            Self::HostError(e) => write!(f, "host error: {e}"),
+            Self::LimitExceeded(kind) => write!(f, "{kind}"),
         }
     }
 }
+
+impl std::error::Error for ShellError {}
+
+/// A [`ShellError`] in a form embedders can serialize and render, e.g. as
+/// "line 3, col 7: syntax error near unexpected token `fi'".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub category: ErrorCategory,
+    /// The error message, without position.
+    pub message: String,
+    /// The command text `span` refers to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Byte range of the offending text within `source`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// 1-based line of the start of `span`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based column (in characters) of the start of `span`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
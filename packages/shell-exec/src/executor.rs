@@ -2,14 +2,14 @@ use codepod_shell::ast::{Command, ListOp, Word, WordPart};
 use codepod_shell::lexer::parse_string_expansion;
 use codepod_shell::token::RedirectType;
 
-use crate::control::{ControlFlow, RunResult, ShellError};
+use crate::control::{CancelReason, ControlFlow, LimitKind, RunResult, ShellError};
 use crate::expand::{
     expand_braces, expand_globs, expand_word, expand_words_with_splitting, glob_matches,
-    restore_brace_sentinels, restore_glob_sentinels, ExecFn,
+    restore_brace_sentinels, restore_glob_sentinels, run_substitution, ExecFn,
 };
-use crate::host::{HostInterface, WriteMode};
+use crate::host::{ChildHandle, HostError, HostInterface, SpawnLimitKind, SpawnResult, WriteMode};
 use crate::state::ShellState;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 // ---------------------------------------------------------------------------
 // Alias expansion
@@ -54,6 +54,28 @@ fn expand_alias(state: &ShellState, words: Vec<String>) -> Vec<String> {
     result
 }
 
+/// Whether the session's command policy lets `argv` run. If not, say why
+/// on stderr, in the command's name. Shell functions are not checked.
+pub(crate) fn command_allowed(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    argv: &[String],
+) -> bool {
+    let Some(name) = argv.first() else {
+        return true;
+    };
+    if state.policy.is_empty() || state.functions.contains_key(name) {
+        return true;
+    }
+    match state.policy.check(state, host, argv) {
+        Ok(()) => true,
+        Err(violation) => {
+            crate::shell_eprintln!("{name}: {violation}");
+            false
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Heredoc / herestring expansion helper
 // ---------------------------------------------------------------------------
@@ -98,7 +120,9 @@ fn needs_default_dir(cmd: &str, args: &[&str]) -> bool {
 
 /// Resolve a single argument to an absolute path if it looks like a relative
 /// file path. Flags (starting with `-`) and absolute paths (starting with `/`)
-/// pass through. Commands in PASSTHROUGH_ARGS never resolve their args.
+/// pass through. Commands in PASSTHROUGH_ARGS never resolve their args, and
+/// neither does Python: it starts in the shell's cwd, and its args are the
+/// script's `sys.argv`, which should read as typed.
 ///
 /// Uses `host.stat()` to disambiguate: only resolves if the resolved path
 /// exists in VFS. Also resolves args that look like filenames (have a file
@@ -109,7 +133,7 @@ fn resolve_arg_if_path(
     cmd_name: &str,
     arg: &str,
 ) -> String {
-    if PASSTHROUGH_ARGS.contains(&cmd_name) {
+    if PASSTHROUGH_ARGS.contains(&cmd_name) || is_python_interpreter(cmd_name) {
         return arg.to_string();
     }
     if arg.starts_with('-') || arg.starts_with('/') {
@@ -264,10 +288,23 @@ fn exec_path(
 ) -> Result<ControlFlow, ShellError> {
     let resolved = normalize_path(&state.resolve_path(cmd_path));
 
-    // Read the file
+    // Read the file, reporting problems against the path as typed.
+    match host.stat(&resolved) {
+        Ok(info) if info.is_dir => {
+            return Err(ShellError::HostError(HostError::IsADirectory(
+                cmd_path.to_string(),
+            )));
+        }
+        Ok(info) if info.exists => {}
+        _ => {
+            return Err(ShellError::HostError(HostError::NotFound(
+                cmd_path.to_string(),
+            )));
+        }
+    }
     let text = host
         .read_file_str(&resolved)
-        .map_err(|e| ShellError::HostError(format!("{}: {}", cmd_path, e)))?;
+        .map_err(ShellError::HostError)?;
 
     let first_line = text.lines().next().unwrap_or("");
     let interpreter = parse_shebang(first_line);
@@ -278,13 +315,21 @@ fn exec_path(
             // Python script: spawn python with the resolved script path + args
             let mut python_args: Vec<&str> = vec![resolved.as_str()];
             python_args.extend(args);
+            let python_argv: Vec<String> = std::iter::once("python3")
+                .chain(python_args.iter().copied())
+                .map(String::from)
+                .collect();
+            if !command_allowed(state, host, &python_argv) {
+                state.last_exit_code = 126;
+                return Ok(ControlFlow::Normal(RunResult::exit(126)));
+            }
             let env_pairs: Vec<(&str, &str)> = state
                 .env
                 .iter()
                 .map(|(k, v)| (k.as_str(), v.as_str()))
                 .collect();
             let pid = host
-                .spawn(
+                .spawn_with_limits(
                     "python3",
                     &python_args,
                     &env_pairs,
@@ -294,11 +339,10 @@ fn exec_path(
                     state.stdout_fd,
                     2,
                     0,
+                    &state.limits.spawn,
                 )
-                .map_err(|e| ShellError::HostError(e.to_string()))?;
-            let spawn_result = host
-                .waitpid(pid)
-                .map_err(|e| ShellError::HostError(e.to_string()))?;
+                .map_err(ShellError::HostError)?;
+            let spawn_result = wait_child(state, host, pid).map_err(ShellError::HostError)?;
             state.last_exit_code = spawn_result.exit_code;
             return Ok(ControlFlow::Normal(RunResult::exit(spawn_result.exit_code)));
         }
@@ -308,6 +352,74 @@ fn exec_path(
     exec_shell_script(state, host, &text, args)
 }
 
+/// Run a script file as the whole program (`codepod-shell-exec SCRIPT
+/// [ARGS...]`): `$0` is the script path, `$1`... are `args`, and the EXIT
+/// trap runs at the end. Returns the script's exit status, or 127 if the
+/// file cannot be found.
+pub fn run_script_file(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    path: &str,
+    args: &[String],
+) -> i32 {
+    if !host
+        .stat(&state.resolve_path(path))
+        .is_ok_and(|info| info.exists)
+    {
+        crate::shell_eprintln!("codepod-shell: {path}: No such file or directory");
+        return 127;
+    }
+    state.script_name = path.to_string();
+    state.begin_run();
+    let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    let status = match exec_path(state, host, path, &arg_refs, "") {
+        Ok(ControlFlow::Normal(r)) => r.exit_code,
+        Ok(ControlFlow::Exit(code)) | Ok(ControlFlow::Return(code)) => code,
+        Ok(ControlFlow::Cancelled(CancelReason::Timeout)) => 124,
+        Ok(ControlFlow::Cancelled(CancelReason::Cancelled)) => 125,
+        Ok(_) => state.last_exit_code,
+        Err(e) => {
+            crate::shell_eprintln!("codepod-shell: {e}");
+            e.exit_code()
+        }
+    };
+    run_exit_trap(state, host);
+    status
+}
+
+/// Run the EXIT trap, if one is set, clearing it so it fires only once.
+pub fn run_exit_trap(state: &mut ShellState, host: &dyn HostInterface) {
+    if let Some(trap_cmd) = state.traps.remove("EXIT") {
+        run_text(state, host, &trap_cmd);
+    }
+}
+
+/// Parse shell source text, turning a syntax error into
+/// [`ShellError::Syntax`] so it can be reported with its position.
+pub fn parse_script(source: &str) -> Result<Command, ShellError> {
+    codepod_shell::parser::try_parse(source).map_err(|error| ShellError::Syntax {
+        source: source.to_string(),
+        error,
+    })
+}
+
+/// Run shell text on behalf of a builtin (`eval`, `source`, traps). A
+/// syntax error is reported on stderr and gives status 2.
+fn run_text(state: &mut ShellState, host: &dyn HostInterface, cmd_str: &str) -> RunResult {
+    let inner_cmd = match parse_script(cmd_str) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            crate::shell_eprintln!("codepod-shell: {e}");
+            return RunResult::exit(e.exit_code());
+        }
+    };
+    match exec_command(state, host, &inner_cmd) {
+        Ok(ControlFlow::Normal(r)) => r,
+        Ok(ControlFlow::Exit(code)) => RunResult::exit(code),
+        _ => RunResult::empty(),
+    }
+}
+
 /// Execute a string as a shell script. Strips shebang if present,
 /// sets positional parameters, and runs via the parser.
 fn exec_shell_script(
@@ -326,12 +438,13 @@ fn exec_shell_script(
         script_text
     };
 
+    // Parse the entire script as a single command
+    let parsed = parse_script(script)?;
+
     // Set positional parameters
     let saved_positionals = state.positional_args.clone();
     state.positional_args = args.iter().map(|s| s.to_string()).collect();
 
-    // Run the entire script as a single parsed command
-    let parsed = codepod_shell::parser::parse(script);
     let result = exec_command(state, host, &parsed);
 
     // Restore positional parameters
@@ -353,7 +466,7 @@ fn exec_shell_command(
     // sh -c 'command string'
     if args.len() >= 2 && args[0] == "-c" {
         let cmd_str = args[1];
-        let parsed = codepod_shell::parser::parse(cmd_str);
+        let parsed = parse_script(cmd_str)?;
         return exec_command(state, host, &parsed);
     }
     // sh script.sh — read and execute as shell script
@@ -379,6 +492,10 @@ fn dispatch_external_command(
     if cmd_name.contains('/') {
         match exec_path(state, host, cmd_name, args, stdin_data) {
             Ok(flow) => return Err(flow),
+            Err(ShellError::HostError(e)) => {
+                crate::shell_eprintln!("{e}");
+                return Err(ControlFlow::Normal(RunResult::exit(e.command_exit_code())));
+            }
             Err(e) => {
                 crate::shell_eprintln!("{}", e);
                 return Err(ControlFlow::Normal(RunResult::exit(127)));
@@ -390,6 +507,10 @@ fn dispatch_external_command(
     if cmd_name == "sh" || cmd_name == "bash" {
         match exec_shell_command(state, host, args, stdin_data) {
             Ok(flow) => return Err(flow),
+            Err(ShellError::HostError(e)) => {
+                crate::shell_eprintln!("{cmd_name}: {e}");
+                return Err(ControlFlow::Normal(RunResult::exit(e.command_exit_code())));
+            }
             Err(e) => {
                 crate::shell_eprintln!("{}", e);
                 return Err(ControlFlow::Normal(RunResult::exit(1)));
@@ -423,86 +544,513 @@ fn dispatch_external_command(
 /// to the given stdout/stderr buffers. This is called after a command
 /// finishes execution to process any `>`, `>>`, `2>`, `2>>`, `2>&1`, `&>`
 /// redirections attached to the command.
+///
+/// Numbered fds (`3>file`, `2>&3`, `>&3`) are resolved against a copy of
+/// `state.fd_table`, so fds opened here only live for this command.
 fn apply_output_redirects(
     state: &ShellState,
     host: &dyn HostInterface,
     redirects: &[codepod_shell::ast::Redirect],
-    stdout: &mut String,
-    stderr: &mut String,
+    stdout: &mut Vec<u8>,
+    stderr: &mut Vec<u8>,
 ) -> Result<(), ShellError> {
     let mut last_stdout_redirect_path: Option<String> = None;
+    let mut fds = state.fd_table.clone();
     for redir in redirects {
         match &redir.redirect_type {
             RedirectType::StdoutOverwrite(path) => {
-                if path.starts_with('&') {
+                if let Some(fd_word) = path.strip_prefix('&') {
                     // >&N fd redirect
                     match path.as_str() {
                         "&2" => {
                             // >&2: merge stdout into stderr
-                            stderr.push_str(stdout);
-                            *stdout = String::new();
+                            stderr.append(stdout);
+                            stdout.clear();
                         }
                         "&1" => {
                             // >&1: no-op
                         }
                         _ => {
-                            // >&N for unsupported N — discard output (like /dev/null)
-                            *stdout = String::new();
+                            // >&N: append to the file behind fd N, or discard
+                            // the output (like /dev/null) if N is not open.
+                            if let Some(file) =
+                                resolve_fd_word(state, fd_word).and_then(|fd| fds.get(&fd))
+                            {
+                                write_redirect_target(
+                                    state,
+                                    host,
+                                    file,
+                                    stdout,
+                                    WriteMode::Append,
+                                )?;
+                            }
+                            stdout.clear();
                         }
                     }
                 } else {
-                    let resolved = state.resolve_path(path);
-                    host.write_file(&resolved, stdout.as_bytes(), WriteMode::Truncate)
-                        .map_err(|e| ShellError::HostError(e.to_string()))?;
-                    *stdout = String::new();
-                    last_stdout_redirect_path = Some(resolved);
+                    let target = redirect_output(state, host, &mut fds, 1, path, stdout, false)?;
+                    last_stdout_redirect_path = Some(target);
                 }
             }
+            RedirectType::StdoutClobber(path) => {
+                let target = redirect_output(state, host, &mut fds, 1, path, stdout, false)?;
+                last_stdout_redirect_path = Some(target);
+            }
             RedirectType::StdoutAppend(path) => {
-                let resolved = state.resolve_path(path);
-                host.write_file(&resolved, stdout.as_bytes(), WriteMode::Append)
-                    .map_err(|e| ShellError::HostError(e.to_string()))?;
-                *stdout = String::new();
-                last_stdout_redirect_path = Some(resolved);
+                let target = redirect_output(state, host, &mut fds, 1, path, stdout, true)?;
+                last_stdout_redirect_path = Some(target);
             }
             RedirectType::StderrOverwrite(path) => {
-                let resolved = state.resolve_path(path);
-                host.write_file(&resolved, stderr.as_bytes(), WriteMode::Truncate)
-                    .map_err(|e| ShellError::HostError(e.to_string()))?;
-                *stderr = String::new();
+                redirect_output(state, host, &mut fds, 2, path, stderr, false)?;
             }
             RedirectType::StderrAppend(path) => {
-                let resolved = state.resolve_path(path);
-                host.write_file(&resolved, stderr.as_bytes(), WriteMode::Append)
-                    .map_err(|e| ShellError::HostError(e.to_string()))?;
-                *stderr = String::new();
+                redirect_output(state, host, &mut fds, 2, path, stderr, true)?;
             }
             RedirectType::StderrToStdout => {
                 if let Some(ref file_path) = last_stdout_redirect_path {
                     if !stderr.is_empty() {
-                        host.write_file(file_path, stderr.as_bytes(), WriteMode::Append)
-                            .map_err(|e| ShellError::HostError(e.to_string()))?;
+                        write_redirect_target(state, host, file_path, stderr, WriteMode::Append)?;
                     }
                 } else {
-                    stdout.push_str(stderr);
+                    stdout.append(stderr);
                 }
-                *stderr = String::new();
+                stderr.clear();
             }
             RedirectType::BothOverwrite(path) => {
                 let resolved = state.resolve_path(path);
-                let combined = format!("{stdout}{stderr}");
-                host.write_file(&resolved, combined.as_bytes(), WriteMode::Truncate)
-                    .map_err(|e| ShellError::HostError(e.to_string()))?;
-                *stdout = String::new();
-                *stderr = String::new();
+                let combined = [stdout.as_slice(), stderr.as_slice()].concat();
+                write_redirect_target(state, host, &resolved, &combined, WriteMode::Truncate)?;
+                stdout.clear();
+                stderr.clear();
+            }
+            RedirectType::FdOverwrite(fd, path) | RedirectType::FdAppend(fd, path) => {
+                let resolved = state.resolve_path(path);
+                let mode = if matches!(redir.redirect_type, RedirectType::FdOverwrite(..)) {
+                    WriteMode::Truncate
+                } else {
+                    WriteMode::Append
+                };
+                write_redirect_target(state, host, &resolved, b"", mode)?;
+                fds.insert(*fd, resolved);
+            }
+            RedirectType::FdDup(fd, src) => match (*fd, resolve_fd_word(state, src)) {
+                (2, Some(1)) => {
+                    if let Some(ref file_path) = last_stdout_redirect_path {
+                        write_redirect_target(state, host, file_path, stderr, WriteMode::Append)?;
+                    } else {
+                        stdout.append(stderr);
+                    }
+                    stderr.clear();
+                }
+                (2, Some(2)) => {}
+                (2, src_fd) => {
+                    if let Some(file) = src_fd.and_then(|n| fds.get(&n)) {
+                        write_redirect_target(state, host, file, stderr, WriteMode::Append)?;
+                    }
+                    stderr.clear();
+                }
+                (n, Some(src_fd)) => {
+                    if let Some(file) = fds.get(&src_fd).cloned() {
+                        fds.insert(n, file);
+                    }
+                }
+                _ => {}
+            },
+            RedirectType::FdClose(1) => stdout.clear(),
+            RedirectType::FdClose(2) => stderr.clear(),
+            RedirectType::FdClose(fd) => {
+                fds.remove(fd);
             }
             // Input redirects are handled separately; skip them here.
+            // `{var}` redirects were already opened by `open_fd_redirects`.
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Deliver one of a command's output streams (`fd` 1 or 2) to the
+/// redirect target `path` and record it in `fds`, so a later
+/// `/dev/stdout` or `/dev/stderr` in the same command finds it. Returns
+/// the path the output went to.
+fn redirect_output(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    fds: &mut HashMap<i32, String>,
+    fd: i32,
+    path: &str,
+    data: &mut Vec<u8>,
+    append: bool,
+) -> Result<String, ShellError> {
+    let resolved = state.resolve_path(path);
+    let (target, mode) = match fd_table_target(fds, &resolved) {
+        Some(file) => (file.to_string(), WriteMode::Append),
+        None if append => (resolved, WriteMode::Append),
+        None => (resolved, WriteMode::Truncate),
+    };
+    write_redirect_target(state, host, &target, data, mode)?;
+    data.clear();
+    fds.insert(fd, target.clone());
+    Ok(target)
+}
+
+/// Collect the bytes that input redirects (`<`, `<>`, heredocs, `<<<`) feed
+/// to a command's stdin; the last one wins.
+///
+/// Returns `None` when no redirect supplies stdin (or only `< /dev/stdin`),
+/// so the command keeps the stdin it inherited.
+fn stdin_from_redirects(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    redirects: &[codepod_shell::ast::Redirect],
+    exec_fn: ExecFn,
+) -> Result<Option<Vec<u8>>, ShellError> {
+    let mut stdin_data = None;
+    for redir in redirects {
+        match &redir.redirect_type {
+            RedirectType::StdinFrom(path) if path.starts_with('&') => {
+                // <&N: read from the file or pipe behind fd N.
+                let fd_word = &path[1..];
+                match resolve_fd_word(state, fd_word) {
+                    Some(0) => {}
+                    Some(fd) if state.fd_table.contains_key(&fd) => {
+                        let target = state.fd_table[&fd].clone();
+                        if let Some(data) = read_redirect_source(state, host, &target, false)? {
+                            stdin_data = Some(data);
+                        }
+                    }
+                    _ => {
+                        return Err(ShellError::HostError(HostError::Other(format!(
+                            "{fd_word}: Bad file descriptor"
+                        ))));
+                    }
+                }
+            }
+            RedirectType::StdinFrom(path) | RedirectType::ReadWrite(path) => {
+                let create = matches!(redir.redirect_type, RedirectType::ReadWrite(_));
+                if let Some(data) = read_redirect_source(state, host, path, create)? {
+                    stdin_data = Some(data);
+                }
+            }
+            RedirectType::Heredoc(content) => {
+                stdin_data = Some(expand_raw_string(state, content, Some(exec_fn)).into_bytes());
+            }
+            RedirectType::HeredocQuoted(content) => {
+                stdin_data = Some(content.clone().into_bytes());
+            }
+            RedirectType::HeredocStrip(content) => {
+                // Strip before expanding so tabs in substituted values stay.
+                let stripped = strip_heredoc_tabs(content);
+                stdin_data = Some(expand_raw_string(state, &stripped, Some(exec_fn)).into_bytes());
+            }
+            RedirectType::HeredocStripQuoted(content) => {
+                stdin_data = Some(strip_heredoc_tabs(content).into_bytes());
+            }
+            RedirectType::HereString(word) => {
+                let expanded = expand_raw_string(state, word, Some(exec_fn));
+                stdin_data = Some(format!("{expanded}\n").into_bytes());
+            }
+            _ => {}
+        }
+    }
+    Ok(stdin_data)
+}
+
+/// Strip leading tabs from every line of a `<<-` heredoc body.
+fn strip_heredoc_tabs(content: &str) -> String {
+    let mut stripped = content
+        .lines()
+        .map(|l| l.trim_start_matches('\t'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.ends_with('\n') {
+        stripped.push('\n');
+    }
+    stripped
+}
+
+/// Whether stdout must be captured so output redirects can deliver it to
+/// its target after the command finishes.
+fn has_stdout_file_redirect(redirects: &[codepod_shell::ast::Redirect]) -> bool {
+    redirects.iter().any(|r| {
+        matches!(
+            &r.redirect_type,
+            RedirectType::StdoutOverwrite(_)
+                | RedirectType::StdoutAppend(_)
+                | RedirectType::StdoutClobber(_)
+                | RedirectType::BothOverwrite(_)
+                | RedirectType::FdClose(1)
+        )
+    })
+}
+
+/// Whether stderr must be captured for output redirects (`2>file`, `2>&3`).
+fn has_stderr_file_redirect(redirects: &[codepod_shell::ast::Redirect]) -> bool {
+    redirects.iter().any(|r| {
+        matches!(
+            &r.redirect_type,
+            RedirectType::StderrOverwrite(_)
+                | RedirectType::StderrAppend(_)
+                | RedirectType::BothOverwrite(_)
+                | RedirectType::FdDup(2, _)
+                | RedirectType::FdClose(2)
+        )
+    })
+}
+
+/// Close the write end of a capture pipe and read everything written to it.
+fn drain_pipe_sink(host: &dyn HostInterface, sink: Option<(i32, i32)>) -> Vec<u8> {
+    let Some((r, w)) = sink else {
+        return Vec::new();
+    };
+    let _ = host.close_fd(w);
+    let data = host.read_fd(r).unwrap_or_default();
+    let _ = host.close_fd(r);
+    data
+}
+
+/// Write redirected output to `path`.
+///
+/// `/dev/null` discards the data, and `/dev/stdout` / `/dev/stderr` forward
+/// it to the shell's current stdout and stderr rather than creating files.
+/// `/dev/fd/N` writes to the file fd N was opened on, or else straight to
+/// host fd N (a coprocess pipe).
+fn write_redirect_target(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    path: &str,
+    data: &[u8],
+    mode: WriteMode,
+) -> Result<(), ShellError> {
+    if let Some(file) = fd_table_target(&state.fd_table, path) {
+        return write_redirect_target(state, host, file, data, WriteMode::Append);
+    }
+    let fd = match path {
+        "/dev/null" => return Ok(()),
+        "/dev/stdout" => state.stdout_fd,
+        "/dev/stderr" => 2,
+        _ if dev_fd(path).is_some() => dev_fd(path).unwrap_or_default(),
+        _ => {
+            return host
+                .check_writable(path)
+                .and_then(|()| host.write_file(path, data, mode))
+                .map_err(ShellError::HostError);
+        }
+    };
+    if !data.is_empty() {
+        let _ = host.write_fd(fd, data);
+    }
+    Ok(())
+}
+
+/// Read the data an input redirect (`< file` or `<> file`) feeds to stdin.
+///
+/// Returns `None` for `/dev/stdin`, meaning the command keeps its current
+/// standard input. `/dev/null` reads as empty and `/dev/fd/N` reads host
+/// fd N to EOF. With `create` (for `<>`), a missing file is created empty
+/// instead of being an error.
+fn read_redirect_source(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    path: &str,
+    create: bool,
+) -> Result<Option<Vec<u8>>, ShellError> {
+    let resolved = state.resolve_path(path);
+    match resolved.as_str() {
+        "/dev/stdin" => return Ok(None),
+        "/dev/null" => return Ok(Some(Vec::new())),
+        _ => {}
+    }
+    if let Some(fd) = dev_fd(&resolved) {
+        return host.read_fd(fd).map(Some).map_err(ShellError::HostError);
+    }
+    if create {
+        // Appending nothing creates the file without touching existing data.
+        host.write_file(&resolved, b"", WriteMode::Append)
+            .map_err(ShellError::HostError)?;
+    }
+    host.read_file(&resolved)
+        .map(Some)
+        .map_err(ShellError::HostError)
+}
+
+/// The host fd behind a `/dev/fd/N` path, as recorded for coprocess pipes.
+fn dev_fd(path: &str) -> Option<i32> {
+    path.strip_prefix("/dev/fd/")?.parse().ok()
+}
+
+/// What `fds` has open behind the device path `path` (`/dev/stdout`,
+/// `/dev/stderr` or `/dev/fd/N`), if anything. Output sent there goes to
+/// that target instead, appended to what is already written.
+fn fd_table_target<'a>(fds: &'a HashMap<i32, String>, path: &str) -> Option<&'a str> {
+    let fd = match path {
+        "/dev/stdout" => 1,
+        "/dev/stderr" => 2,
+        _ => dev_fd(path)?,
+    };
+    fds.get(&fd)
+        .map(String::as_str)
+        .filter(|target| *target != path)
+}
+
+/// Resolve the fd operand of `>&N` / `N>&M`: a number, or a `$name`,
+/// `${name}` or `${name[i]}` reference.
+fn resolve_fd_word(state: &ShellState, word: &str) -> Option<i32> {
+    let Some(reference) = word.strip_prefix('$') else {
+        return word.parse().ok();
+    };
+    let name = reference
+        .strip_prefix('{')
+        .and_then(|r| r.strip_suffix('}'))
+        .unwrap_or(reference);
+    let value = match name.split_once('[') {
+        Some((array, index)) => {
+            let index: usize = index.strip_suffix(']')?.parse().ok()?;
+            state.arrays.get(array)?.get(index)?
+        }
+        None => state.env.get(name)?,
+    };
+    value.parse().ok()
+}
+
+/// Drop `fd` from the shell's fd table. A coprocess pipe is closed on the
+/// host once no other fd refers to it.
+fn close_table_fd(state: &mut ShellState, host: &dyn HostInterface, fd: i32) {
+    if let Some(path) = state.fd_table.remove(&fd) {
+        if let Some(host_fd) = dev_fd(&path) {
+            if !state.fd_table.values().any(|p| *p == path) {
+                let _ = host.close_fd(host_fd);
+            }
+        }
+    }
+}
+
+/// Open fds that outlive the command they are attached to.
+///
+/// `{var}>file` always allocates a persistent fd (>= 10) and stores its number
+/// in `var`, matching bash. When `persistent` is set (a bare `exec`), plain
+/// `N>file`, `N>>file`, `N>&M` and `N>&-` also update `state.fd_table`.
+/// Only fds >= 3 are tracked; stdout and stderr stay with the host fd table.
+fn open_fd_redirects(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    redirects: &[codepod_shell::ast::Redirect],
+    persistent: bool,
+) -> Result<(), ShellError> {
+    for redir in redirects {
+        match &redir.redirect_type {
+            RedirectType::FdVarOverwrite(var, path) | RedirectType::FdVarAppend(var, path) => {
+                let resolved = state.resolve_path(path);
+                let mode = if matches!(redir.redirect_type, RedirectType::FdVarOverwrite(..)) {
+                    WriteMode::Truncate
+                } else {
+                    WriteMode::Append
+                };
+                write_redirect_target(state, host, &resolved, b"", mode)?;
+                let fd = state.next_free_fd();
+                state.fd_table.insert(fd, resolved);
+                state.env.insert(var.clone(), fd.to_string());
+            }
+            RedirectType::FdVarClose(var) => {
+                if let Some(fd) = state.env.get(var).and_then(|v| v.parse::<i32>().ok()) {
+                    close_table_fd(state, host, fd);
+                }
+            }
+            RedirectType::FdOverwrite(fd, path) | RedirectType::FdAppend(fd, path)
+                if persistent =>
+            {
+                let resolved = state.resolve_path(path);
+                let mode = if matches!(redir.redirect_type, RedirectType::FdOverwrite(..)) {
+                    WriteMode::Truncate
+                } else {
+                    WriteMode::Append
+                };
+                write_redirect_target(state, host, &resolved, b"", mode)?;
+                state.fd_table.insert(*fd, resolved);
+            }
+            RedirectType::FdDup(fd, src) if persistent && *fd >= 3 => {
+                if let Some(file) = resolve_fd_word(state, src)
+                    .and_then(|n| state.fd_table.get(&n))
+                    .cloned()
+                {
+                    state.fd_table.insert(*fd, file);
+                }
+            }
+            RedirectType::FdClose(fd) if persistent => {
+                close_table_fd(state, host, *fd);
+            }
             _ => {}
         }
     }
     Ok(())
 }
 
+/// Enforce `set -C` (noclobber) for the truncating redirects `>`, `2>`, `N>`
+/// and `&>`.
+///
+/// Returns an error message naming the first target that already exists as a
+/// regular file. `>|` is never checked, and neither are fd duplications such
+/// as `>&2` or non-regular targets like `/dev/null`.
+fn check_noclobber(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    redirects: &[codepod_shell::ast::Redirect],
+) -> Option<String> {
+    if !state.flags.contains(&crate::state::ShellFlag::Noclobber) {
+        return None;
+    }
+    for redir in redirects {
+        let path = match &redir.redirect_type {
+            RedirectType::StdoutOverwrite(p) if !p.starts_with('&') => p,
+            RedirectType::StderrOverwrite(p)
+            | RedirectType::BothOverwrite(p)
+            | RedirectType::FdOverwrite(_, p)
+            | RedirectType::FdVarOverwrite(_, p) => p,
+            _ => continue,
+        };
+        let resolved = state.resolve_path(path);
+        if host.stat(&resolved).map(|s| s.is_file).unwrap_or(false) {
+            return Some(format!("{path}: cannot overwrite existing file"));
+        }
+    }
+    None
+}
+
+/// Refuse file redirects whose target exists but has no write permission.
+///
+/// Returns the host's error message for the first such target, so the
+/// command fails before it runs instead of producing output that is then
+/// lost.
+fn check_writable_targets(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    redirects: &[codepod_shell::ast::Redirect],
+) -> Option<String> {
+    for redir in redirects {
+        let path = match &redir.redirect_type {
+            RedirectType::StdoutOverwrite(p)
+            | RedirectType::StdoutAppend(p)
+            | RedirectType::StdoutClobber(p)
+                if !p.starts_with('&') =>
+            {
+                p
+            }
+            RedirectType::StderrOverwrite(p)
+            | RedirectType::StderrAppend(p)
+            | RedirectType::BothOverwrite(p)
+            | RedirectType::FdOverwrite(_, p)
+            | RedirectType::FdAppend(_, p)
+            | RedirectType::FdVarOverwrite(_, p)
+            | RedirectType::FdVarAppend(_, p) => p,
+            _ => continue,
+        };
+        if let Err(e) = host.check_writable(&state.resolve_path(path)) {
+            return Some(e.to_string());
+        }
+    }
+    None
+}
+
 /// Result of resolving process substitutions in words.
 struct ProcessSubResult {
     /// Words with process substitution parts replaced by temp file paths.
@@ -541,17 +1089,13 @@ fn resolve_process_subs(
                 .iter()
                 .map(|part| match part {
                     WordPart::ProcessSub(cmd_str) => {
-                        state.substitution_depth += 1;
-                        let stdout = exec_fn(state, cmd_str);
-                        state.substitution_depth -= 1;
-                        let path = format!("/tmp/.proc_sub_{}", state.proc_sub_counter);
-                        state.proc_sub_counter += 1;
+                        let stdout = run_substitution(state, exec_fn, cmd_str);
+                        let path = proc_sub_path(state, host);
                         let _ = host.write_file(&path, stdout.as_bytes(), WriteMode::Truncate);
                         WordPart::Literal(path)
                     }
                     WordPart::OutputProcessSub(cmd_str) => {
-                        let path = format!("/tmp/.proc_sub_{}", state.proc_sub_counter);
-                        state.proc_sub_counter += 1;
+                        let path = proc_sub_path(state, host);
                         deferred_output_subs.push((path.clone(), cmd_str.clone()));
                         WordPart::Literal(path)
                     }
@@ -571,59 +1115,181 @@ fn resolve_process_subs(
 ///
 /// For each `>(cmd)`, the main command has already written to the temp file.
 /// We read the file content, feed it as stdin to `cmd`, then remove the temp file.
+/// The content goes through a pipe on fd 0 so binary data arrives intact.
+/// A fresh temp file for a process substitution. Falls back to a numbered
+/// name when the host cannot create one (no `/tmp`, say).
+fn proc_sub_path(state: &mut ShellState, host: &dyn HostInterface) -> String {
+    host.create_temp_file("/tmp", ".proc_sub_")
+        .unwrap_or_else(|_| {
+            let path = format!("/tmp/.proc_sub_{}", state.proc_sub_counter);
+            state.proc_sub_counter += 1;
+            path
+        })
+}
+
 fn run_deferred_output_subs(
     state: &mut ShellState,
     host: &dyn HostInterface,
     deferred: &[(String, String)],
 ) {
     for (path, cmd_str) in deferred {
-        if let Ok(content) = host.read_file_str(path) {
-            state.pipeline_stdin = Some(content);
-            let inner_cmd = codepod_shell::parser::parse(cmd_str);
-            let _ = exec_command(state, host, &inner_cmd);
-            state.pipeline_stdin = None;
+        if let Ok(content) = host.read_file(path) {
+            match host.pipe() {
+                Ok((r, w)) => {
+                    let _ = host.write_fd(w, &content);
+                    let _ = host.close_fd(w);
+                    let saved_stdin_fd = state.stdin_fd;
+                    let saved_fd0 = host.dup(0).ok();
+                    state.stdin_fd = r;
+                    let _ = host.dup2(r, 0);
+                    run_text(state, host, cmd_str);
+                    if let Some(fd) = saved_fd0 {
+                        let _ = host.dup2(fd, 0);
+                        let _ = host.close_fd(fd);
+                    }
+                    state.stdin_fd = saved_stdin_fd;
+                    let _ = host.close_fd(r);
+                }
+                Err(_) => {
+                    state.pipeline_stdin = Some(String::from_utf8_lossy(&content).into_owned());
+                    run_text(state, host, cmd_str);
+                    state.pipeline_stdin = None;
+                }
+            }
         }
         let _ = host.remove(path, false);
     }
 }
 
+/// Fd state replaced by `apply_compound_redirects`, put back by
+/// `restore_compound_redirects`.
+struct CompoundRedirects {
+    /// Host fds redirected with dup2, each with a dup of its original target.
+    saved: Vec<(i32, Option<i32>)>,
+    /// `state.stdout_fd` before the redirects.
+    stdout_fd: i32,
+    /// Capture pipes (read end, write end) and the file each one fills.
+    sinks: Vec<(i32, i32, String)>,
+}
+
 /// Apply redirects on compound commands (Subshell, BraceGroup).
+///
+/// `>file`, `>>file`, `2>file`, `2>>file` and `&>file` send the body's
+/// output through capture pipes that are written to the file when the body
+/// finishes. Device targets (`/dev/stdout`, `/dev/stderr`, `/dev/fd/N`)
+/// point the stream at the fd they name as it stands at that point.
 /// Returns saved fd state for restoration.
 fn apply_compound_redirects(
     state: &mut ShellState,
     host: &dyn HostInterface,
     redirects: &[codepod_shell::ast::Redirect],
-) -> Vec<(i32, Option<i32>)> {
+) -> CompoundRedirects {
     use codepod_shell::token::RedirectType;
-    let mut saved = Vec::new();
+    let mut out = CompoundRedirects {
+        saved: Vec::new(),
+        stdout_fd: state.stdout_fd,
+        sinks: Vec::new(),
+    };
     for redir in redirects {
         match &redir.redirect_type {
             RedirectType::StderrToStdout => {
                 // 2>&1: dup stderr to wherever stdout currently points
-                let saved_fd2 = host.dup(2).ok();
-                saved.push((2, saved_fd2));
-                let _ = host.dup2(state.stdout_fd, 2);
+                redirect_compound_stderr(host, &mut out, state.stdout_fd);
+            }
+            RedirectType::StdoutOverwrite(path)
+            | RedirectType::StdoutClobber(path)
+            | RedirectType::StdoutAppend(path)
+                if !path.starts_with('&') =>
+            {
+                let append = matches!(redir.redirect_type, RedirectType::StdoutAppend(_));
+                if let Some(fd) = compound_output_fd(state, host, &mut out, path, append) {
+                    state.stdout_fd = fd;
+                }
+            }
+            RedirectType::StderrOverwrite(path) | RedirectType::StderrAppend(path) => {
+                let append = matches!(redir.redirect_type, RedirectType::StderrAppend(_));
+                if let Some(fd) = compound_output_fd(state, host, &mut out, path, append) {
+                    redirect_compound_stderr(host, &mut out, fd);
+                }
             }
-            RedirectType::BothOverwrite(_) => {
-                // &>file: redirect both stdout and stderr — dup stderr to stdout
-                let saved_fd2 = host.dup(2).ok();
-                saved.push((2, saved_fd2));
-                let _ = host.dup2(state.stdout_fd, 2);
+            RedirectType::BothOverwrite(path) => {
+                // &>file: stdout to the file, stderr to stdout
+                if let Some(fd) = compound_output_fd(state, host, &mut out, path, false) {
+                    state.stdout_fd = fd;
+                }
+                redirect_compound_stderr(host, &mut out, state.stdout_fd);
             }
             _ => {} // Other redirect types not yet handled on compound commands
         }
     }
-    saved
+    // Numbered fds (`{ ...; } 3>log`) are visible to every command in the
+    // body; the caller restores `state.fd_table` afterwards.
+    let _ = open_fd_redirects(state, host, redirects, true);
+    out
+}
+
+/// Point host fd 2 at `fd` for the body of a compound command, saving the
+/// first original target.
+fn redirect_compound_stderr(host: &dyn HostInterface, out: &mut CompoundRedirects, fd: i32) {
+    if fd == 2 {
+        return;
+    }
+    let saved_fd2 = host.dup(2).ok();
+    out.saved.push((2, saved_fd2));
+    let _ = host.dup2(fd, 2);
+}
+
+/// The host fd a compound command's output redirect to `path` writes to:
+/// the fd a device path names, or the write end of a new capture pipe for
+/// the file, which is created (or truncated) now.
+fn compound_output_fd(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    out: &mut CompoundRedirects,
+    path: &str,
+    append: bool,
+) -> Option<i32> {
+    let resolved = state.resolve_path(path);
+    let resolved = fd_table_target(&state.fd_table, &resolved)
+        .map(str::to_string)
+        .unwrap_or(resolved);
+    match resolved.as_str() {
+        "/dev/stdout" => return Some(state.stdout_fd),
+        "/dev/stderr" => return Some(2),
+        _ => {}
+    }
+    if let Some(fd) = dev_fd(&resolved) {
+        return Some(fd);
+    }
+    let mode = if append {
+        WriteMode::Append
+    } else {
+        WriteMode::Truncate
+    };
+    write_redirect_target(state, host, &resolved, b"", mode).ok()?;
+    let (r, w) = host.pipe().ok()?;
+    out.sinks.push((r, w, resolved));
+    Some(w)
 }
 
-/// Restore fds saved by apply_compound_redirects.
-fn restore_compound_redirects(host: &dyn HostInterface, saved: &[(i32, Option<i32>)]) {
-    for (fd, saved_fd) in saved.iter().rev() {
+/// Restore fds saved by apply_compound_redirects, then write what the
+/// capture pipes collected to their files.
+fn restore_compound_redirects(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    saved: CompoundRedirects,
+) {
+    for (fd, saved_fd) in saved.saved.iter().rev() {
         if let Some(orig) = saved_fd {
             let _ = host.dup2(*orig, *fd);
             let _ = host.close_fd(*orig);
         }
     }
+    state.stdout_fd = saved.stdout_fd;
+    for (r, w, path) in saved.sinks {
+        let data = drain_pipe_sink(host, Some((r, w)));
+        let _ = write_redirect_target(state, host, &path, &data, WriteMode::Append);
+    }
 }
 
 /// Execute a parsed `Command` AST node.
@@ -650,15 +1316,164 @@ fn format_command(cmd: &Command) -> String {
     }
 }
 
+/// Execute a parsed `Command` AST node, recording its wall time in
+/// `RunResult::execution_time_ms`. Returns `ControlFlow::Cancelled` without
+/// running anything if the host has requested cancellation.
 pub fn exec_command(
     state: &mut ShellState,
     host: &dyn HostInterface,
     cmd: &Command,
+) -> Result<ControlFlow, ShellError> {
+    if let Some(reason) = host.should_cancel() {
+        return Ok(ControlFlow::Cancelled(reason));
+    }
+    if deadline_passed(state, host) {
+        return Ok(ControlFlow::Cancelled(CancelReason::Timeout));
+    }
+    if let Some(kind) = state.limit_exceeded {
+        return Err(ShellError::LimitExceeded(kind));
+    }
+    let start = host.monotonic_ms();
+    let mut result = exec_command_untimed(state, host, cmd);
+    // A limit hit during expansion is reported here, whatever the command
+    // made of its truncated words.
+    if let Some(kind) = state.limit_exceeded {
+        return Err(ShellError::LimitExceeded(kind));
+    }
+    if let Ok(ControlFlow::Normal(run)) = &mut result {
+        run.execution_time_ms = elapsed_ms(host, start);
+        // A simple command is a one-stage pipeline.
+        if matches!(cmd, Command::Simple { .. }) {
+            set_pipestatus(state, &[run.exit_code]);
+        }
+    }
+    result
+}
+
+/// Record that `kind` was exceeded, so that the rest of the run unwinds
+/// even through callers that discard errors, and return the error.
+fn limit_exceeded(state: &mut ShellState, kind: LimitKind) -> ShellError {
+    state.limit_exceeded.get_or_insert(kind);
+    ShellError::LimitExceeded(kind)
+}
+
+/// Count one more iteration of a loop, failing once it has run more than
+/// the configured maximum.
+fn count_iteration(state: &mut ShellState, iterations: &mut u64) -> Result<(), ShellError> {
+    *iterations += 1;
+    if *iterations > state.limits.max_loop_iterations {
+        return Err(limit_exceeded(state, LimitKind::LoopIterations));
+    }
+    Ok(())
+}
+
+/// Record the exit code of each pipeline stage in `$PIPESTATUS`.
+fn set_pipestatus(state: &mut ShellState, codes: &[i32]) {
+    state.arrays.insert(
+        "PIPESTATUS".to_string(),
+        codes.iter().map(|c| c.to_string()).collect(),
+    );
+}
+
+/// Whether the shell's deadline (`ShellState::deadline_ms`) has passed.
+fn deadline_passed(state: &ShellState, host: &dyn HostInterface) -> bool {
+    state
+        .deadline_ms
+        .is_some_and(|deadline| host.monotonic_ms() >= deadline)
+}
+
+/// Wait for a spawned child. If the shell's deadline or the per-command
+/// `max_wall_ms` budget passes first the child is sent SIGTERM and reported
+/// with exit code 124, like GNU `timeout`.
+pub(crate) fn wait_child(
+    state: &ShellState,
+    host: &dyn HostInterface,
+    pid: i32,
+) -> Result<SpawnResult, HostError> {
+    let child = ChildHandle { pid };
+    let wall_deadline = state
+        .limits
+        .spawn
+        .max_wall_ms
+        .map(|ms| host.monotonic_ms() + f64::from(ms));
+    let deadline = [state.deadline_ms, wall_deadline]
+        .into_iter()
+        .flatten()
+        .reduce(f64::min);
+    let Some(deadline) = deadline else {
+        return child.wait(host);
+    };
+    let remaining = deadline - host.monotonic_ms();
+    if remaining > 0.0 {
+        let timeout_ms = remaining.ceil().min(u32::MAX as f64) as u32;
+        if let Some(result) = child.wait_with_timeout(host, timeout_ms)? {
+            return Ok(result);
+        }
+    }
+    let _ = child.kill(host, 15);
+    let _ = child.wait(host);
+    Ok(SpawnResult { exit_code: 124 })
+}
+
+/// Report a command the host could not start and return its exit status
+/// (see [`HostError::command_exit_code`]).
+fn report_spawn_error(cmd_name: &str, err: &HostError) -> i32 {
+    match err {
+        HostError::NotFound(_) => crate::shell_eprintln!("{cmd_name}: command not found"),
+        other => crate::shell_eprintln!("{other}"),
+    }
+    err.command_exit_code()
+}
+
+/// Parse a `timeout` duration: a non-negative number with an optional
+/// `s`, `m`, `h` or `d` suffix. Returns milliseconds.
+fn parse_duration_ms(text: &str) -> Option<f64> {
+    let (num, scale) = match text.char_indices().last()? {
+        (i, 's') => (&text[..i], 1_000.0),
+        (i, 'm') => (&text[..i], 60_000.0),
+        (i, 'h') => (&text[..i], 3_600_000.0),
+        (i, 'd') => (&text[..i], 86_400_000.0),
+        _ => (text, 1_000.0),
+    };
+    let value: f64 = num.parse().ok()?;
+    (value.is_finite() && value >= 0.0).then_some(value * scale)
+}
+
+/// Milliseconds elapsed on the host's monotonic clock since `start`.
+fn elapsed_ms(host: &dyn HostInterface, start: f64) -> u64 {
+    (host.monotonic_ms() - start).max(0.0) as u64
+}
+
+/// Format the report printed by `time`. Only wall time is measured, so user
+/// and sys are always zero.
+fn format_time_report(elapsed_ms: u64, posix: bool) -> String {
+    if posix {
+        let secs = elapsed_ms as f64 / 1000.0;
+        format!("real {secs:.2}\nuser 0.00\nsys 0.00\n")
+    } else {
+        let mins = elapsed_ms / 60_000;
+        let secs = (elapsed_ms % 60_000) as f64 / 1000.0;
+        format!("\nreal\t{mins}m{secs:.3}s\nuser\t0m0.000s\nsys\t0m0.000s\n")
+    }
+}
+
+fn exec_command_untimed(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    cmd: &Command,
 ) -> Result<ControlFlow, ShellError> {
     // Create executor callback for command substitution.
     // When word expansion encounters `$(...)`, it calls this closure to
     // parse and execute the inner command, capturing its stdout.
     let exec_fn = |state: &mut ShellState, cmd_str: &str| -> String {
+        let inner_cmd = match parse_script(cmd_str) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                crate::shell_eprintln!("codepod-shell: {e}");
+                state.last_exit_code = e.exit_code();
+                return String::new();
+            }
+        };
         // Capture stdout via a pipe so command substitution works even
         // though RunResult no longer carries stdout/stderr strings.
         let (read_fd, write_fd) = match host.pipe() {
@@ -667,7 +1482,6 @@ pub fn exec_command(
         };
         let saved_stdout_fd = state.stdout_fd;
         state.stdout_fd = write_fd;
-        let inner_cmd = codepod_shell::parser::parse(cmd_str);
         match exec_command(state, host, &inner_cmd) {
             Ok(ControlFlow::Normal(r)) => {
                 state.last_exit_code = r.exit_code;
@@ -690,6 +1504,8 @@ pub fn exec_command(
             redirects,
             assignments,
         } => {
+            // Taken before expansion so command substitutions run normally.
+            let background = std::mem::take(&mut state.spawn_in_background);
             // Process assignments before word expansion
             let assign_err = process_assignments(state, assignments, Some(&exec_fn));
 
@@ -715,7 +1531,13 @@ pub fn exec_command(
             let expanded =
                 expand_words_with_splitting(state, &proc_sub_result.words, Some(&exec_fn));
 
-            // Check for ${var:?msg} error during expansion
+            // A command substitution may have been cut short by cancellation;
+            // don't run the command with its partial output.
+            if let Some(reason) = host.should_cancel() {
+                return Ok(ControlFlow::Cancelled(reason));
+            }
+
+            // Check for ${var:?msg} error during expansion
             if let Some(err_msg) = state.param_error.take() {
                 state.last_exit_code = 1;
                 crate::shell_eprintln!("{}", err_msg);
@@ -736,14 +1558,34 @@ pub fn exec_command(
                 return Ok(ControlFlow::Normal(RunResult::empty()));
             }
             let globbed = expand_alias(state, globbed);
+            crate::transcript::record_command(state, host, &globbed);
+            if !command_allowed(state, host, &globbed) {
+                state.last_exit_code = 126;
+                return Ok(ControlFlow::Normal(RunResult::exit(126)));
+            }
             let cmd_name = &globbed[0];
             let args: Vec<&str> = globbed[1..].iter().map(|s| s.as_str()).collect();
 
+            // Redirect failures abort the command before it runs.
+            if let Some(err) = check_noclobber(state, host, redirects)
+                .or_else(|| check_writable_targets(state, host, redirects))
+            {
+                crate::shell_eprintln!("{err}");
+                state.last_exit_code = 1;
+                return Ok(ControlFlow::Normal(RunResult::exit(1)));
+            }
+            // A bare `exec 3>file` keeps its fds open for later commands.
+            open_fd_redirects(
+                state,
+                host,
+                redirects,
+                cmd_name == "exec" && args.is_empty(),
+            )?;
+
             // ── Check for function invocation ────────────────────────────
             if let Some(func_body) = state.functions.get(cmd_name).cloned() {
-                if state.function_depth >= crate::state::MAX_FUNCTION_DEPTH {
-                    crate::shell_eprintln!("{cmd_name}: maximum function nesting depth exceeded");
-                    return Ok(ControlFlow::Normal(RunResult::exit(1)));
+                if state.function_depth >= state.limits.max_function_depth {
+                    return Err(limit_exceeded(state, LimitKind::FunctionDepth));
                 }
                 let func_args: Vec<String> = globbed[1..].iter().map(|s| s.to_string()).collect();
                 let saved_positionals = state.positional_args.clone();
@@ -784,56 +1626,21 @@ pub fn exec_command(
             // Collect stdin data from redirects, then write to a pipe and
             // dup2 onto fd 0 so all consumers (builtins, spawned commands)
             // read from standard input.
-            let mut stdin_data = String::new();
-            for redir in redirects {
-                match &redir.redirect_type {
-                    RedirectType::StdinFrom(path) => {
-                        let resolved = state.resolve_path(path);
-                        stdin_data = host
-                            .read_file_str(&resolved)
-                            .map_err(|e| ShellError::HostError(e.to_string()))?;
-                    }
-                    RedirectType::Heredoc(content) => {
-                        stdin_data = expand_raw_string(state, content, Some(&exec_fn));
-                    }
-                    RedirectType::HeredocQuoted(content) => {
-                        stdin_data = content.clone();
-                    }
-                    RedirectType::HeredocStrip(content) => {
-                        let expanded = expand_raw_string(state, content, Some(&exec_fn));
-                        stdin_data = expanded
-                            .lines()
-                            .map(|l| l.trim_start_matches('\t'))
-                            .collect::<Vec<_>>()
-                            .join("\n");
-                        if expanded.ends_with('\n') {
-                            stdin_data.push('\n');
-                        }
-                    }
-                    RedirectType::HeredocStripQuoted(content) => {
-                        stdin_data = content
-                            .lines()
-                            .map(|l| l.trim_start_matches('\t'))
-                            .collect::<Vec<_>>()
-                            .join("\n");
-                        if content.ends_with('\n') {
-                            stdin_data.push('\n');
-                        }
-                    }
-                    RedirectType::HereString(word) => {
-                        stdin_data =
-                            format!("{}\n", expand_raw_string(state, word, Some(&exec_fn)));
-                    }
-                    _ => {}
-                }
-            }
+            let stdin_redirect = stdin_from_redirects(state, host, redirects, &exec_fn)?;
+            // Set even for empty data (`< /dev/null`) so the command does not
+            // fall back to its inherited stdin.
+            let has_stdin_redirect = stdin_redirect.is_some();
+            let stdin_bytes = stdin_redirect.unwrap_or_default();
+            // Text view for builtins and virtual commands that take stdin as a
+            // string; spawned commands and fd 0 get the raw bytes.
+            let stdin_data = String::from_utf8_lossy(&stdin_bytes).into_owned();
 
             // If we have stdin data from redirects, write it to a pipe and
             // dup2 onto fd 0 so builtins can read from standard input.
-            let stdin_pipe = if !stdin_data.is_empty() {
+            let stdin_pipe = if has_stdin_redirect {
                 if let Ok((r, w)) = host.pipe() {
                     // Write data to pipe, close write end so readers see EOF.
-                    let _ = host.write_fd(w, stdin_data.as_bytes());
+                    let _ = host.write_fd(w, &stdin_bytes);
                     let _ = host.close_fd(w);
                     let saved = host.dup(0).ok();
                     let _ = host.dup2(r, 0);
@@ -848,14 +1655,7 @@ pub fn exec_command(
 
             // ── Check for builtin commands ────────────────────────────
             let func_args: Vec<String> = globbed[1..].iter().map(|s| s.to_string()).collect();
-            let run_fn = |state: &mut ShellState, cmd_str: &str| -> RunResult {
-                let inner_cmd = codepod_shell::parser::parse(cmd_str);
-                match exec_command(state, host, &inner_cmd) {
-                    Ok(ControlFlow::Normal(r)) => r,
-                    Ok(ControlFlow::Exit(code)) => RunResult::exit(code),
-                    _ => RunResult::empty(),
-                }
-            };
+            let run_fn = |state: &mut ShellState, cmd_str: &str| run_text(state, host, cmd_str);
 
             // If there are stdout redirects, pipe-sink stdout_fd so shell_print!()
             // output is captured for writing to the redirect target file.
@@ -877,14 +1677,17 @@ pub fn exec_command(
             let has_stdout_redir = redirects.iter().any(|r| {
                 matches!(
                     &r.redirect_type,
-                    RedirectType::StdoutOverwrite(p) if p != "&2" && p != "&1"
+                    RedirectType::StdoutOverwrite(p) if p != "&1"
                 ) || matches!(
                     &r.redirect_type,
-                    RedirectType::StdoutAppend(_) | RedirectType::BothOverwrite(_)
+                    RedirectType::StdoutAppend(_)
+                        | RedirectType::StdoutClobber(_)
+                        | RedirectType::BothOverwrite(_)
+                        | RedirectType::FdClose(1)
                 )
             });
             let saved_redir_stdout = state.stdout_fd;
-            let redir_sink = if has_stdout_redir && state.stdout_fd == 1 {
+            let redir_sink = if has_stdout_redir {
                 if let Ok((r, w)) = host.pipe() {
                     state.stdout_fd = w;
                     Some((r, w))
@@ -919,14 +1722,7 @@ pub fn exec_command(
 
                 // Capture output from redirect pipe sink.
                 state.stdout_fd = saved_redir_stdout;
-                let captured_stdout = if let Some((r, w)) = redir_sink {
-                    let _ = host.close_fd(w);
-                    let data = host.read_fd(r).unwrap_or_default();
-                    let _ = host.close_fd(r);
-                    String::from_utf8_lossy(&data).to_string()
-                } else {
-                    String::new()
-                };
+                let captured_stdout = drain_pipe_sink(host, redir_sink);
 
                 let exit_code = match builtin_result {
                     crate::builtins::BuiltinResult::Result(code) => {
@@ -942,12 +1738,12 @@ pub fn exec_command(
                 };
 
                 let mut stdout = captured_stdout;
-                let mut stderr = String::new();
+                let mut stderr = Vec::new();
                 apply_output_redirects(state, host, redirects, &mut stdout, &mut stderr)?;
 
                 // Write remaining stderr to fd 2 (handles >&2 redirect)
                 if !stderr.is_empty() {
-                    let _ = host.write_fd(2, stderr.as_bytes());
+                    let _ = host.write_fd(2, &stderr);
                 }
 
                 // Restore fd 0 if we redirected stdin.
@@ -976,6 +1772,16 @@ pub fn exec_command(
             }
 
             // ── Virtual commands (curl, wget, pkg, pip) ──────────────────
+            // They print to stdout_fd like builtins, so sink it the same way
+            // when stdout is redirected.
+            let virtual_sink = if has_stdout_redir
+                && state.stdout_fd == 1
+                && crate::virtual_commands::is_virtual_command(cmd_name)
+            {
+                host.pipe().ok().inspect(|&(_, w)| state.stdout_fd = w)
+            } else {
+                None
+            };
             if let Some(result) = crate::virtual_commands::try_virtual_command(
                 state,
                 host,
@@ -983,14 +1789,51 @@ pub fn exec_command(
                 &func_args,
                 &stdin_data,
             ) {
+                state.stdout_fd = saved_redir_stdout;
                 state.last_exit_code = result.exit_code;
-                let mut stdout = String::new();
-                let mut stderr = String::new();
+                let mut stdout = drain_pipe_sink(host, virtual_sink);
+                let mut stderr = Vec::new();
                 apply_output_redirects(state, host, redirects, &mut stdout, &mut stderr)?;
                 run_deferred_output_subs(state, host, &proc_sub_result.deferred_output_subs);
                 return Ok(ControlFlow::Normal(RunResult::exit(result.exit_code)));
             }
 
+            // ── In-process coreutils (cat, grep, sed, ...) ───────────────
+            // Run like a builtin: output is collected, redirected, and the
+            // rest written to stdout_fd and fd 2.
+            if let Some(applet) =
+                crate::applets::find_applet(state, host, cmd_name).filter(|_| !background)
+            {
+                let stdin = if has_stdin_redirect {
+                    crate::applets::AppletStdin::Data(stdin_bytes)
+                } else {
+                    match state.pipeline_stdin.take().filter(|s| !s.is_empty()) {
+                        Some(data) => crate::applets::AppletStdin::Data(data.into_bytes()),
+                        None => crate::applets::AppletStdin::Fd(state.stdin_fd),
+                    }
+                };
+                // Arguments stay as typed: ShellIo resolves paths against
+                // the cwd itself, and messages should name the path given.
+                let applet_args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                let output = crate::applets::run_applet(state, host, applet, &applet_args, stdin);
+                state.last_exit_code = output.exit_code;
+                let mut stdout = output.stdout;
+                let mut stderr = output.stderr;
+                apply_output_redirects(state, host, redirects, &mut stdout, &mut stderr)?;
+                if !stdout.is_empty() {
+                    if state.stdout_fd == 1 {
+                        crate::io::write_stdout(&stdout);
+                    } else {
+                        let _ = host.write_fd(state.stdout_fd, &stdout);
+                    }
+                }
+                if !stderr.is_empty() {
+                    crate::io::write_stderr(&stderr);
+                }
+                run_deferred_output_subs(state, host, &proc_sub_result.deferred_output_subs);
+                return Ok(ControlFlow::Normal(RunResult::exit(output.exit_code)));
+            }
+
             // ── Path resolution and command dispatch ─────────────────────
             // Host commands (extensions) are now routed through host_spawn
             // by the host ProcessManager — no separate extension_invoke needed.
@@ -1006,8 +1849,8 @@ pub fn exec_command(
                         state.last_exit_code = run.exit_code;
 
                         // Process output redirects for dispatched commands too
-                        let mut stdout = String::new();
-                        let mut stderr = String::new();
+                        let mut stdout = Vec::new();
+                        let mut stderr = Vec::new();
                         apply_output_redirects(state, host, redirects, &mut stdout, &mut stderr)?;
 
                         run_deferred_output_subs(
@@ -1027,23 +1870,29 @@ pub fn exec_command(
                 .collect();
 
             let spawn_args_refs: Vec<&str> = spawn_args.iter().map(|s| s.as_str()).collect();
-            // Use pipeline stdin if no explicit stdin redirect
-            let effective_stdin = if stdin_data.is_empty() {
+            // Use pipeline stdin if no explicit stdin redirect. Redirected
+            // stdin reaches the child through a pipe fd so binary data is
+            // passed through unchanged.
+            let stdin_redirect_fd = if has_stdin_redirect {
+                host.pipe().ok().map(|(r, w)| {
+                    let _ = host.write_fd(w, &stdin_bytes);
+                    let _ = host.close_fd(w);
+                    r
+                })
+            } else {
+                None
+            };
+            let effective_stdin = if !has_stdin_redirect {
                 state.pipeline_stdin.take().unwrap_or_default()
+            } else if stdin_redirect_fd.is_some() {
+                String::new()
             } else {
                 stdin_data.clone()
             };
             // If there are stderr redirects that require capture, pipe-sink stderr.
             // Note: StderrToStdout is handled by routing stderr_fd directly to
             // spawn_stdout_fd (computed below), so it doesn't need a pipe.
-            let has_stderr_redir = redirects.iter().any(|r| {
-                matches!(
-                    &r.redirect_type,
-                    RedirectType::StderrOverwrite(_)
-                        | RedirectType::StderrAppend(_)
-                        | RedirectType::BothOverwrite(_)
-                )
-            });
+            let has_stderr_redir = has_stderr_file_redirect(redirects);
             let has_stderr_to_stdout = redirects
                 .iter()
                 .any(|r| matches!(&r.redirect_type, RedirectType::StderrToStdout));
@@ -1057,17 +1906,10 @@ pub fn exec_command(
                 None
             };
 
-            // If there are stdout redirects and stdout_fd is 1 (not already a pipe),
-            // pipe-sink stdout so we can capture it for redirect.
-            let has_stdout_redir = redirects.iter().any(|r| {
-                matches!(
-                    &r.redirect_type,
-                    RedirectType::StdoutOverwrite(_)
-                        | RedirectType::StdoutAppend(_)
-                        | RedirectType::BothOverwrite(_)
-                )
-            });
-            let stdout_sink = if has_stdout_redir && state.stdout_fd == 1 {
+            // If there are stdout redirects, pipe-sink stdout so we can capture it
+            // for the redirect, whether or not stdout is already a pipe.
+            let has_stdout_redir = has_stdout_file_redirect(redirects);
+            let stdout_sink = if has_stdout_redir {
                 if let Ok((r, w)) = host.pipe() {
                     Some((r, w))
                 } else {
@@ -1090,44 +1932,69 @@ pub fn exec_command(
                 2
             };
 
-            let pid = host
-                .spawn(
+            let spawn = || {
+                host.spawn_with_limits(
                     &spawn_program,
                     &spawn_args_refs,
                     &env_pairs,
                     &state.cwd,
                     &effective_stdin,
-                    state.stdin_fd,
+                    stdin_redirect_fd.unwrap_or(state.stdin_fd),
                     spawn_stdout_fd,
                     stderr_fd,
                     0,
+                    &state.limits.spawn,
                 )
-                .map_err(|e| ShellError::HostError(e.to_string()))?;
-            let spawn_result = host
-                .waitpid(pid)
-                .map_err(|e| ShellError::HostError(e.to_string()))?;
+            };
+            // A background job outlives this command, so its output is not
+            // the transcript's to capture.
+            let spawned = if background {
+                crate::transcript::untracked(spawn)
+            } else {
+                spawn()
+            };
+            let pid = match spawned {
+                Ok(pid) => pid,
+                Err(e) => {
+                    let sinks = [stdout_sink, stderr_sink].into_iter().flatten();
+                    let fds = sinks.flat_map(|(r, w)| [r, w]).chain(stdin_redirect_fd);
+                    for fd in fds {
+                        let _ = host.close_fd(fd);
+                    }
+                    let code = report_spawn_error(cmd_name, &e);
+                    state.last_exit_code = code;
+                    return Ok(ControlFlow::Normal(RunResult::exit(code)));
+                }
+            };
+            // `cmd &`: leave the child running unless its output has to be
+            // collected for a redirect once it exits.
+            if background
+                && stdout_sink.is_none()
+                && stderr_sink.is_none()
+                && stdin_redirect_fd.is_none()
+                && proc_sub_result.deferred_output_subs.is_empty()
+            {
+                state.last_bg_pid = pid;
+                state.last_exit_code = 0;
+                return Ok(ControlFlow::Normal(RunResult::exit(0)));
+            }
+            let spawn_result = wait_child(state, host, pid).map_err(ShellError::HostError)?;
+            if let Some(fd) = stdin_redirect_fd {
+                let _ = host.close_fd(fd);
+            }
+            if let Some(kind) =
+                SpawnLimitKind::from_exit(&state.limits.spawn, spawn_result.exit_code)
+            {
+                crate::shell_eprintln!("{cmd_name}: {kind}");
+            }
 
             state.last_exit_code = spawn_result.exit_code;
 
             // ── Phase 2: Process output redirects ────────────────────────
             // Capture stdout from pipe sink if used.
-            let mut stdout = if let Some((r, w)) = stdout_sink {
-                let _ = host.close_fd(w);
-                let data = host.read_fd(r).unwrap_or_default();
-                let _ = host.close_fd(r);
-                String::from_utf8_lossy(&data).to_string()
-            } else {
-                String::new()
-            };
+            let mut stdout = drain_pipe_sink(host, stdout_sink);
             // Capture stderr from pipe sink if used.
-            let mut stderr = if let Some((r, w)) = stderr_sink {
-                let _ = host.close_fd(w);
-                let data = host.read_fd(r).unwrap_or_default();
-                let _ = host.close_fd(r);
-                String::from_utf8_lossy(&data).to_string()
-            } else {
-                String::new()
-            };
+            let mut stderr = drain_pipe_sink(host, stderr_sink);
             apply_output_redirects(state, host, redirects, &mut stdout, &mut stderr)?;
 
             run_deferred_output_subs(state, host, &proc_sub_result.deferred_output_subs);
@@ -1183,8 +2050,20 @@ pub fn exec_command(
                 // then passes it as stdin to the next stage.
                 let mut last_result = RunResult::empty();
                 let mut stdin_data = String::new();
+                let mut pipe_status = Vec::with_capacity(stage_count);
 
-                for cmd in commands {
+                for (i, cmd) in commands.iter().enumerate() {
+                    if i > 0 {
+                        pipe_status.push(last_result.exit_code);
+                    }
+                    if let Some(reason) = host.should_cancel() {
+                        state.env = saved_env;
+                        state.arrays = saved_arrays;
+                        state.assoc_arrays = saved_assoc;
+                        state.stdout_fd = saved_stdout_fd;
+                        state.stdin_fd = saved_stdin_fd;
+                        return Ok(ControlFlow::Cancelled(reason));
+                    }
                     match cmd {
                         Command::Simple {
                             words,
@@ -1229,70 +2108,31 @@ pub fn exec_command(
                             }
 
                             let globbed = expand_alias(state, globbed);
+                            crate::transcript::record_command(state, host, &globbed);
+                            if !command_allowed(state, host, &globbed) {
+                                last_result = RunResult::exit(126);
+                                stdin_data = String::new();
+                                if pipefail {
+                                    pipefail_code = 126;
+                                }
+                                continue;
+                            }
                             let cmd_name = &globbed[0];
                             let args: Vec<&str> = globbed[1..].iter().map(|s| s.as_str()).collect();
 
                             // Process input redirects — they override pipeline stdin
-                            let mut effective_stdin = stdin_data.clone();
-                            for redir in redirects {
-                                match &redir.redirect_type {
-                                    RedirectType::StdinFrom(path) => {
-                                        let resolved = state.resolve_path(path);
-                                        effective_stdin = host
-                                            .read_file_str(&resolved)
-                                            .map_err(|e| ShellError::HostError(e.to_string()))?;
-                                    }
-                                    RedirectType::Heredoc(content) => {
-                                        effective_stdin =
-                                            expand_raw_string(state, content, Some(&exec_fn));
-                                    }
-                                    RedirectType::HeredocQuoted(content) => {
-                                        effective_stdin = content.clone();
-                                    }
-                                    RedirectType::HeredocStrip(content) => {
-                                        let expanded =
-                                            expand_raw_string(state, content, Some(&exec_fn));
-                                        effective_stdin = expanded
-                                            .lines()
-                                            .map(|l| l.trim_start_matches('\t'))
-                                            .collect::<Vec<_>>()
-                                            .join("\n");
-                                        if expanded.ends_with('\n') {
-                                            effective_stdin.push('\n');
-                                        }
-                                    }
-                                    RedirectType::HeredocStripQuoted(content) => {
-                                        effective_stdin = content
-                                            .lines()
-                                            .map(|l| l.trim_start_matches('\t'))
-                                            .collect::<Vec<_>>()
-                                            .join("\n");
-                                        if content.ends_with('\n') {
-                                            effective_stdin.push('\n');
-                                        }
-                                    }
-                                    RedirectType::HereString(word) => {
-                                        effective_stdin = format!(
-                                            "{}\n",
-                                            expand_raw_string(state, word, Some(&exec_fn))
-                                        );
-                                    }
-                                    _ => {}
-                                }
-                            }
+                            let effective_stdin =
+                                match stdin_from_redirects(state, host, redirects, &exec_fn)? {
+                                    Some(data) => String::from_utf8_lossy(&data).into_owned(),
+                                    None => stdin_data.clone(),
+                                };
 
                             // Check for builtin in pipeline
                             let pipe_func_args: Vec<String> =
                                 globbed[1..].iter().map(|s| s.to_string()).collect();
-                            let pipe_run_fn =
-                                |state: &mut ShellState, cmd_str: &str| -> RunResult {
-                                    let inner_cmd = codepod_shell::parser::parse(cmd_str);
-                                    match exec_command(state, host, &inner_cmd) {
-                                        Ok(ControlFlow::Normal(r)) => r,
-                                        Ok(ControlFlow::Exit(code)) => RunResult::exit(code),
-                                        _ => RunResult::empty(),
-                                    }
-                                };
+                            let pipe_run_fn = |state: &mut ShellState, cmd_str: &str| {
+                                run_text(state, host, cmd_str)
+                            };
                             if let Some(builtin_result) = crate::builtins::try_builtin(
                                 state,
                                 host,
@@ -1303,8 +2143,8 @@ pub fn exec_command(
                             ) {
                                 match builtin_result {
                                     crate::builtins::BuiltinResult::Result(code) => {
-                                        let mut bstdout = String::new();
-                                        let mut bstderr = String::new();
+                                        let mut bstdout = Vec::new();
+                                        let mut bstderr = Vec::new();
 
                                         // Handle output redirects
                                         apply_output_redirects(
@@ -1342,8 +2182,8 @@ pub fn exec_command(
                                 &effective_stdin,
                             ) {
                                 state.last_exit_code = result.exit_code;
-                                let mut bstdout = String::new();
-                                let mut bstderr = String::new();
+                                let mut bstdout = Vec::new();
+                                let mut bstderr = Vec::new();
                                 apply_output_redirects(
                                     state,
                                     host,
@@ -1399,7 +2239,7 @@ pub fn exec_command(
                                         resolved_args.iter().map(|s| s.as_str()).collect();
 
                                     match host
-                                        .spawn(
+                                        .spawn_with_limits(
                                             &prog,
                                             &spawn_args_refs,
                                             &env_pairs,
@@ -1409,12 +2249,13 @@ pub fn exec_command(
                                             state.stdout_fd,
                                             2,
                                             0,
+                                            &state.limits.spawn,
                                         )
-                                        .and_then(|pid| host.waitpid(pid))
+                                        .and_then(|pid| wait_child(state, host, pid))
                                     {
                                         Ok(spawn_result) => {
-                                            let mut stdout = String::new();
-                                            let mut stderr = String::new();
+                                            let mut stdout = Vec::new();
+                                            let mut stderr = Vec::new();
 
                                             // Handle output redirects in pipeline stages
                                             apply_output_redirects(
@@ -1480,6 +2321,8 @@ pub fn exec_command(
                 state.assoc_arrays = saved_assoc;
                 state.stdout_fd = saved_stdout_fd;
                 state.stdin_fd = saved_stdin_fd;
+                pipe_status.push(last_result.exit_code);
+                set_pipestatus(state, &pipe_status);
 
                 // Apply pipefail: use last non-zero exit code
                 if pipefail && pipefail_code != 0 && last_result.exit_code == 0 {
@@ -1499,8 +2342,26 @@ pub fn exec_command(
             let mut pids: Vec<(i32, usize)> = Vec::new(); // (pid, stage_index)
             let mut last_result = RunResult::empty();
             let mut last_stage_was_spawned = false;
+            let mut cancelled = None;
+            // Exit code of every stage, for $PIPESTATUS. Spawned stages are
+            // filled in once they have been waited on.
+            let mut pipe_status = vec![0; stage_count];
 
             for (i, cmd) in commands.iter().enumerate() {
+                // Stop launching stages once the host asks to cancel. Close
+                // the pipe ends no stage will use so running stages see EOF.
+                if let Some(reason) = host.should_cancel() {
+                    if i > 0 {
+                        let _ = host.close_fd(pipes[i - 1].0);
+                    }
+                    for (read_fd, write_fd) in &pipes[i..] {
+                        let _ = host.close_fd(*read_fd);
+                        let _ = host.close_fd(*write_fd);
+                    }
+                    cancelled = Some(reason);
+                    break;
+                }
+
                 // Set up fds for this pipeline stage:
                 // - stdin_fd:  read end of pipe from previous stage (or saved stdin for first)
                 // - stdout_fd: write end of pipe to next stage (or saved stdout for last)
@@ -1515,6 +2376,34 @@ pub fn exec_command(
                     pipes[i].1
                 };
 
+                // Input redirects on a stage (`grep x < big.log | head`)
+                // replace the pipe from the previous stage; their data is
+                // fed through a pipe of its own.
+                let redirect_stdin = match cmd {
+                    Command::Simple { redirects, .. } => {
+                        match stdin_from_redirects(state, host, redirects, &exec_fn) {
+                            Ok(data) => data,
+                            Err(e) => {
+                                for (read_fd, write_fd) in &pipes {
+                                    let _ = host.close_fd(*read_fd);
+                                    let _ = host.close_fd(*write_fd);
+                                }
+                                state.stdout_fd = saved_stdout_fd;
+                                state.stdin_fd = saved_stdin_fd;
+                                return Err(e);
+                            }
+                        }
+                    }
+                    _ => None,
+                };
+                let redirect_stdin_fd = redirect_stdin.and_then(|data| {
+                    let (r, w) = host.pipe().ok()?;
+                    let _ = host.write_fd(w, &data);
+                    let _ = host.close_fd(w);
+                    Some(r)
+                });
+                let stage_stdin_fd = redirect_stdin_fd.unwrap_or(stage_stdin_fd);
+
                 state.stdin_fd = stage_stdin_fd;
                 state.stdout_fd = stage_stdout_fd;
 
@@ -1570,6 +2459,7 @@ pub fn exec_command(
                                 last_stage_was_spawned = false;
                             } else {
                                 let globbed = expand_alias(state, globbed);
+                                crate::transcript::record_command(state, host, &globbed);
                                 let cmd_name = &globbed[0];
                                 let pipe_func_args: Vec<String> =
                                     globbed[1..].iter().map(|s| s.to_string()).collect();
@@ -1578,27 +2468,42 @@ pub fn exec_command(
                                 // Builtins run inline. Task 6 ensures they also
                                 // write to stdout_fd via write_to_fd, so the pipe
                                 // gets data even in streaming mode.
-                                let pipe_run_fn =
-                                    |state: &mut ShellState, cmd_str: &str| -> RunResult {
-                                        let inner_cmd = codepod_shell::parser::parse(cmd_str);
-                                        match exec_command(state, host, &inner_cmd) {
-                                            Ok(ControlFlow::Normal(r)) => r,
-                                            Ok(ControlFlow::Exit(code)) => RunResult::exit(code),
-                                            _ => RunResult::empty(),
-                                        }
-                                    };
-                                if let Some(builtin_result) = crate::builtins::try_builtin(
-                                    state,
-                                    host,
-                                    cmd_name,
-                                    &pipe_func_args,
-                                    "", // no string stdin in streaming mode
-                                    Some(&pipe_run_fn),
-                                ) {
+                                let pipe_run_fn = |state: &mut ShellState, cmd_str: &str| {
+                                    run_text(state, host, cmd_str)
+                                };
+                                // A builtin with `> file` writes into a capture
+                                // pipe instead of the next stage.
+                                let builtin_sink = if crate::builtins::is_builtin(cmd_name)
+                                    && has_stdout_file_redirect(redirects)
+                                {
+                                    host.pipe().ok()
+                                } else {
+                                    None
+                                };
+                                if let Some((_, w)) = builtin_sink {
+                                    state.stdout_fd = w;
+                                }
+                                // A command the policy turns down fails like a
+                                // builtin, without running.
+                                let builtin = if command_allowed(state, host, &globbed) {
+                                    crate::builtins::try_builtin(
+                                        state,
+                                        host,
+                                        cmd_name,
+                                        &pipe_func_args,
+                                        "", // no string stdin in streaming mode
+                                        Some(&pipe_run_fn),
+                                    )
+                                } else {
+                                    Some(crate::builtins::BuiltinResult::Result(126))
+                                };
+                                state.stdout_fd = stage_stdout_fd;
+                                let builtin_stdout = drain_pipe_sink(host, builtin_sink);
+                                if let Some(builtin_result) = builtin {
                                     match builtin_result {
                                         crate::builtins::BuiltinResult::Result(code) => {
-                                            let mut bstdout = String::new();
-                                            let mut bstderr = String::new();
+                                            let mut bstdout = builtin_stdout;
+                                            let mut bstderr = Vec::new();
                                             apply_output_redirects(
                                                 state,
                                                 host,
@@ -1646,8 +2551,8 @@ pub fn exec_command(
                                     )
                                 {
                                     state.last_exit_code = result.exit_code;
-                                    let mut bstdout = String::new();
-                                    let mut bstderr = String::new();
+                                    let mut bstdout = Vec::new();
+                                    let mut bstderr = Vec::new();
                                     apply_output_redirects(
                                         state,
                                         host,
@@ -1712,6 +2617,30 @@ pub fn exec_command(
                                             let spawn_args_refs: Vec<&str> =
                                                 resolved_args.iter().map(|s| s.as_str()).collect();
 
+                                            // Stages with output redirects write into
+                                            // capture pipes that are flushed to their
+                                            // targets once the stage exits.
+                                            let stdout_sink = if has_stdout_file_redirect(redirects)
+                                            {
+                                                host.pipe().ok()
+                                            } else {
+                                                None
+                                            };
+                                            let stderr_sink = if has_stderr_file_redirect(redirects)
+                                            {
+                                                host.pipe().ok()
+                                            } else {
+                                                None
+                                            };
+                                            let spawn_stdout_fd =
+                                                stdout_sink.map_or(stage_stdout_fd, |(_, w)| w);
+                                            // stderr_fd — dup2'd to stdout by stage setup if 2>&1
+                                            let spawn_stderr_fd = match stderr_sink {
+                                                Some((_, w)) => w,
+                                                None if has_stderr_to_stdout => spawn_stdout_fd,
+                                                None => 2,
+                                            };
+
                                             match host.spawn(
                                                 &prog,
                                                 &spawn_args_refs,
@@ -1719,15 +2648,39 @@ pub fn exec_command(
                                                 &state.cwd,
                                                 "", // stdin comes from pipe fd, not string
                                                 stage_stdin_fd,
-                                                stage_stdout_fd,
-                                                2, // stderr_fd — dup2'd to stdout by stage setup if 2>&1
+                                                spawn_stdout_fd,
+                                                spawn_stderr_fd,
                                                 0,
                                             ) {
+                                                Ok(pid)
+                                                    if stdout_sink.is_some()
+                                                        || stderr_sink.is_some() =>
+                                                {
+                                                    let code = wait_child(state, host, pid)
+                                                        .map(|r| r.exit_code)
+                                                        .unwrap_or(1);
+                                                    let mut sout =
+                                                        drain_pipe_sink(host, stdout_sink);
+                                                    let mut serr =
+                                                        drain_pipe_sink(host, stderr_sink);
+                                                    apply_output_redirects(
+                                                        state, host, redirects, &mut sout,
+                                                        &mut serr,
+                                                    )?;
+                                                    state.last_exit_code = code;
+                                                    last_result = RunResult::exit(code);
+                                                    if pipefail && code != 0 {
+                                                        pipefail_code = code;
+                                                    }
+                                                    last_stage_was_spawned = false;
+                                                }
                                                 Ok(pid) => {
                                                     pids.push((pid, i));
                                                     last_stage_was_spawned = true;
                                                 }
                                                 Err(e) => {
+                                                    let _ = drain_pipe_sink(host, stdout_sink);
+                                                    let _ = drain_pipe_sink(host, stderr_sink);
                                                     state.last_exit_code = 127;
                                                     crate::shell_eprintln!("{}: {}", cmd_name, e);
                                                     last_result = RunResult::exit(127);
@@ -1827,13 +2780,20 @@ pub fn exec_command(
                     // Close the write end we passed to this stage's stdout
                     let _ = host.close_fd(pipes[i].1);
                 }
+                if let Some(fd) = redirect_stdin_fd {
+                    let _ = host.close_fd(fd);
+                }
+                if !last_stage_was_spawned {
+                    pipe_status[i] = last_result.exit_code;
+                }
             }
 
             // ── Wait for all spawned processes ──
             let mut last_spawned_exit_code = 0;
-            for (idx, (pid, _stage_idx)) in pids.iter().enumerate() {
-                match host.waitpid(*pid) {
+            for (idx, (pid, stage_idx)) in pids.iter().enumerate() {
+                match wait_child(state, host, *pid) {
                     Ok(result) => {
+                        pipe_status[*stage_idx] = result.exit_code;
                         if idx == pids.len() - 1 {
                             last_spawned_exit_code = result.exit_code;
                         }
@@ -1843,6 +2803,7 @@ pub fn exec_command(
                     }
                     Err(_) => {
                         // waitpid failed — treat as error
+                        pipe_status[*stage_idx] = 1;
                         if pipefail {
                             pipefail_code = 1;
                         }
@@ -1857,6 +2818,11 @@ pub fn exec_command(
             state.arrays = saved_arrays;
             state.assoc_arrays = saved_assoc;
 
+            if let Some(reason) = cancelled {
+                return Ok(ControlFlow::Cancelled(reason));
+            }
+            set_pipestatus(state, &pipe_status);
+
             // Determine final exit code:
             // - If the last stage was a spawned process, use its exit code
             // - Otherwise use the last inline result's exit code
@@ -1880,6 +2846,28 @@ pub fn exec_command(
 
         // ── List: ;, &&, || ────────────────────────────────────────────
         Command::List { left, op, right } => {
+            // `a; b &` and `a & b &` parse as `(a; b) &`, but only the last
+            // command is backgrounded, so regroup as `a; (b &)`.
+            if *op == ListOp::Background {
+                if let Command::List {
+                    left: first,
+                    op: inner_op @ (ListOp::Seq | ListOp::Background),
+                    right: last,
+                } = left.as_ref()
+                {
+                    let regrouped = Command::List {
+                        left: first.clone(),
+                        op: inner_op.clone(),
+                        right: Box::new(Command::List {
+                            left: last.clone(),
+                            op: ListOp::Background,
+                            right: right.clone(),
+                        }),
+                    };
+                    return exec_command(state, host, &regrouped);
+                }
+            }
+
             // For && and ||, suppress errexit during evaluation (bash spec)
             let suppress_errexit = matches!(op, ListOp::And | ListOp::Or);
             let had_errexit = suppress_errexit
@@ -1888,8 +2876,14 @@ pub fn exec_command(
                 state.flags.remove(&crate::state::ShellFlag::Errexit);
             }
 
-            let left_result = exec_command(state, host, left)?;
-            let left_run = match left_result {
+            // `cmd &` runs a simple external command asynchronously; other
+            // background commands still run to completion first.
+            let background_pid = state.last_bg_pid;
+            state.spawn_in_background =
+                *op == ListOp::Background && matches!(left.as_ref(), Command::Simple { .. });
+            let left_result = exec_command(state, host, left);
+            state.spawn_in_background = false;
+            let left_run = match left_result? {
                 ControlFlow::Normal(r) => r,
                 other => {
                     if had_errexit {
@@ -1948,18 +2942,29 @@ pub fn exec_command(
                     // Record background job
                     let job_id = state.next_job_id;
                     state.next_job_id += 1;
+                    let spawned = state.last_bg_pid != background_pid;
+                    let pid = if spawned { state.last_bg_pid } else { 0 };
                     state.jobs.push(crate::state::Job {
                         id: job_id,
-                        pid: 0,
+                        pid,
                         command: format_command(left),
-                        done: Some(left_run.exit_code),
+                        done: if spawned {
+                            None
+                        } else {
+                            Some(left_run.exit_code)
+                        },
                     });
-                    state.last_bg_pid = 0;
+                    state.last_bg_pid = pid;
                     state.last_exit_code = 0; // & always returns 0
 
                     // If right side is empty (trailing &), return
-                    if let Command::Simple { words, .. } = right.as_ref() {
-                        if words.is_empty() {
+                    if let Command::Simple {
+                        words,
+                        redirects,
+                        assignments,
+                    } = right.as_ref()
+                    {
+                        if words.is_empty() && redirects.is_empty() && assignments.is_empty() {
                             return Ok(ControlFlow::Normal(RunResult::exit(0)));
                         }
                     }
@@ -2003,8 +3008,10 @@ pub fn exec_command(
             let final_words = restore_glob_sentinels(&final_words);
 
             let mut last_exit_code = 0;
+            let mut iterations = 0;
 
             for word in &final_words {
+                count_iteration(state, &mut iterations)?;
                 state.env.insert(var.clone(), word.clone());
                 match exec_command(state, host, body)? {
                     ControlFlow::Normal(r) => {
@@ -2019,12 +3026,156 @@ pub fn exec_command(
             Ok(ControlFlow::Normal(RunResult::exit(last_exit_code)))
         }
 
+        // ── Select loop ─────────────────────────────────────────────────
+        Command::Select { var, words, body } => {
+            let expanded = expand_words_with_splitting(state, words, Some(&exec_fn));
+            let braced = expand_braces(&expanded);
+            let restored = restore_brace_sentinels(&braced);
+            let final_words = expand_globs(host, &restored, &state.cwd);
+            let final_words = restore_glob_sentinels(&final_words);
+            if final_words.is_empty() {
+                return Ok(ControlFlow::Normal(RunResult::empty()));
+            }
+
+            let mut menu = String::new();
+            for (i, word) in final_words.iter().enumerate() {
+                menu.push_str(&format!("{}) {}\n", i + 1, word));
+            }
+
+            let mut last_exit_code = 0;
+            let mut iterations = 0;
+            let mut show_menu = true;
+            loop {
+                count_iteration(state, &mut iterations)?;
+                if show_menu {
+                    crate::shell_eprint!("{}", menu);
+                }
+                let prompt = state.env.get("PS3").map_or("#? ", String::as_str);
+                crate::shell_eprint!("{}", prompt);
+
+                // REPLY gets the raw line, exactly as `read -r` with no names.
+                let read_args = ["-r".to_string()];
+                let eof = !matches!(
+                    crate::builtins::try_builtin(state, host, "read", &read_args, "", None),
+                    Some(crate::builtins::BuiltinResult::Result(0))
+                );
+                if eof {
+                    crate::shell_eprint!("\n");
+                    break;
+                }
+                let reply = state.env.get("REPLY").cloned().unwrap_or_default();
+                // An empty line redisplays the menu without running the body.
+                show_menu = reply.trim().is_empty();
+                if show_menu {
+                    continue;
+                }
+                let choice = reply
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1))
+                    .and_then(|i| final_words.get(i))
+                    .cloned()
+                    .unwrap_or_default();
+                state.env.insert(var.clone(), choice);
+
+                match exec_command(state, host, body)? {
+                    ControlFlow::Normal(r) => {
+                        last_exit_code = r.exit_code;
+                    }
+                    ControlFlow::Break(_) => break,
+                    ControlFlow::Continue(_) => continue,
+                    other => return Ok(other),
+                }
+            }
+            state.last_exit_code = last_exit_code;
+            Ok(ControlFlow::Normal(RunResult::exit(last_exit_code)))
+        }
+
+        // ── Coprocess ───────────────────────────────────────────────────
+        Command::Coproc { name, body } => {
+            // Only a simple external command (optionally in braces) can be
+            // spawned with its stdin and stdout wired back to the shell.
+            let simple = match body.as_ref() {
+                Command::BraceGroup { body, redirects } if redirects.is_empty() => body.as_ref(),
+                other => other,
+            };
+            let words = match simple {
+                Command::Simple {
+                    words,
+                    redirects,
+                    assignments,
+                } if !words.is_empty() && redirects.is_empty() && assignments.is_empty() => words,
+                _ => {
+                    crate::shell_eprint!(
+                        "coproc: only simple external commands can run as coprocesses\n"
+                    );
+                    return Ok(ControlFlow::Normal(RunResult::exit(1)));
+                }
+            };
+            let expanded = expand_words_with_splitting(state, words, Some(&exec_fn));
+            let braced = expand_braces(&expanded);
+            let restored = restore_brace_sentinels(&braced);
+            let argv = expand_globs(host, &restored, &state.cwd);
+            let argv = restore_glob_sentinels(&argv);
+            let Some(program) = argv.first() else {
+                return Ok(ControlFlow::Normal(RunResult::empty()));
+            };
+            if state.functions.contains_key(program) || crate::builtins::is_builtin(program) {
+                crate::shell_eprint!(
+                    "coproc: {program}: only external commands can run as coprocesses\n"
+                );
+                return Ok(ControlFlow::Normal(RunResult::exit(1)));
+            }
+            if !command_allowed(state, host, &argv) {
+                state.last_exit_code = 126;
+                return Ok(ControlFlow::Normal(RunResult::exit(126)));
+            }
+
+            let args: Vec<&str> = argv[1..].iter().map(String::as_str).collect();
+            let env_pairs: Vec<(&str, &str)> = state
+                .env
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect();
+            let child = match host.spawn_duplex(program, &args, &env_pairs, &state.cwd) {
+                Ok(child) => child,
+                Err(e) => {
+                    crate::shell_eprint!("coproc: {program}: {e}\n");
+                    return Ok(ControlFlow::Normal(RunResult::exit(127)));
+                }
+            };
+
+            state.arrays.insert(
+                name.clone(),
+                vec![child.read_fd.to_string(), child.write_fd.to_string()],
+            );
+            state
+                .env
+                .insert(format!("{name}_PID"), child.pid.to_string());
+            for fd in [child.read_fd, child.write_fd] {
+                state.fd_table.insert(fd, format!("/dev/fd/{fd}"));
+            }
+            let job_id = state.next_job_id;
+            state.next_job_id += 1;
+            state.jobs.push(crate::state::Job {
+                id: job_id,
+                pid: child.pid,
+                command: format_command(cmd),
+                done: None,
+            });
+            state.last_bg_pid = child.pid;
+            state.last_exit_code = 0;
+            Ok(ControlFlow::Normal(RunResult::exit(0)))
+        }
+
         // ── While loop ──────────────────────────────────────────────────
         Command::While { condition, body } => {
             let mut last_exit_code = 0;
-            let max_iterations = 100_000;
+            let mut iterations = 0;
 
-            for _ in 0..max_iterations {
+            loop {
+                count_iteration(state, &mut iterations)?;
                 let cond_result = exec_command(state, host, condition)?;
                 let cond_run = match cond_result {
                     ControlFlow::Normal(r) => r,
@@ -2060,9 +3211,10 @@ pub fn exec_command(
             }
 
             let mut last_exit_code = 0;
-            let max_iterations = 100_000;
+            let mut iterations = 0;
 
-            for _ in 0..max_iterations {
+            loop {
+                count_iteration(state, &mut iterations)?;
                 if !cond.is_empty() {
                     let val = eval_arithmetic(state, cond);
                     if val == 0 {
@@ -2117,9 +3269,11 @@ pub fn exec_command(
             let saved_last_exit_code = state.last_exit_code;
 
             // Apply redirects (e.g. 2>&1) via fd dup
+            let saved_fd_table = state.fd_table.clone();
             let saved_fds = apply_compound_redirects(state, host, redirects);
             let result = exec_command(state, host, body);
-            restore_compound_redirects(host, &saved_fds);
+            restore_compound_redirects(state, host, saved_fds);
+            state.fd_table = saved_fd_table;
 
             state.env = saved_env;
             state.cwd = saved_cwd;
@@ -2134,9 +3288,11 @@ pub fn exec_command(
 
         // ── Brace group ─────────────────────────────────────────────────
         Command::BraceGroup { body, redirects } => {
+            let saved_fd_table = state.fd_table.clone();
             let saved_fds = apply_compound_redirects(state, host, redirects);
             let result = exec_command(state, host, body);
-            restore_compound_redirects(host, &saved_fds);
+            restore_compound_redirects(state, host, saved_fds);
+            state.fd_table = saved_fd_table;
             result
         }
 
@@ -2150,6 +3306,54 @@ pub fn exec_command(
             other => Ok(other),
         },
 
+        // ── time [-p] pipeline ──────────────────────────────────────────
+        Command::Timed { body, posix } => {
+            // A bare `time` reports the shell's cumulative time instead.
+            let bare = matches!(
+                body.as_ref(),
+                Command::Simple { words, redirects, assignments }
+                    if words.is_empty() && redirects.is_empty() && assignments.is_empty()
+            );
+            let start = host.monotonic_ms();
+            let result = exec_command(state, host, body)?;
+            let elapsed = if bare {
+                state.total_time_ms
+            } else {
+                elapsed_ms(host, start)
+            };
+            crate::shell_eprint!("{}", format_time_report(elapsed, *posix));
+            Ok(result)
+        }
+
+        // ── timeout DURATION command ────────────────────────────────────
+        Command::Timeout { duration, body } => {
+            let text = expand_word(state, duration, Some(&exec_fn));
+            let Some(ms) = parse_duration_ms(&text) else {
+                crate::shell_eprintln!("timeout: invalid time interval '{}'", text);
+                state.last_exit_code = 125;
+                return Ok(ControlFlow::Normal(RunResult::exit(125)));
+            };
+            // A zero duration disables the timeout. An enclosing deadline
+            // still applies if it is earlier.
+            let saved_deadline = state.deadline_ms;
+            if ms > 0.0 {
+                let deadline = host.monotonic_ms() + ms;
+                state.deadline_ms = Some(saved_deadline.map_or(deadline, |d| d.min(deadline)));
+            }
+            let result = exec_command(state, host, body);
+            let own_deadline_hit = deadline_passed(state, host);
+            state.deadline_ms = saved_deadline;
+            match result? {
+                ControlFlow::Cancelled(CancelReason::Timeout)
+                    if own_deadline_hit && !deadline_passed(state, host) =>
+                {
+                    state.last_exit_code = 124;
+                    Ok(ControlFlow::Normal(RunResult::exit(124)))
+                }
+                other => Ok(other),
+            }
+        }
+
         // ── Break / Continue ────────────────────────────────────────────
         Command::Break => Ok(ControlFlow::Break(1)),
         Command::Continue => Ok(ControlFlow::Continue(1)),
@@ -2171,7 +3375,10 @@ pub fn exec_command(
         // ── Arithmetic command (( ... )) ────────────────────────────────
         Command::ArithmeticCommand { expr } => {
             use crate::arithmetic::eval_arithmetic;
-            let val = eval_arithmetic(state, expr);
+            // Same pre-expansion as `$(( ))`: `(( n = $(wc -l < f) ))` works.
+            let expanded =
+                crate::expand::expand_command_subs_in_string(state, expr, Some(&exec_fn));
+            let val = eval_arithmetic(state, &expanded);
             let exit_code = if val != 0 { 0 } else { 1 };
             state.last_exit_code = exit_code;
             Ok(ControlFlow::Normal(RunResult::exit(exit_code)))
@@ -2617,7 +3824,7 @@ fn expand_bracket_word(state: &mut ShellState, word: &str, exec: Option<ExecFn>)
 fn is_unary_test(op: &str) -> bool {
     matches!(
         op,
-        "-z" | "-n" | "-f" | "-d" | "-e" | "-s" | "-r" | "-w" | "-x"
+        "-z" | "-n" | "-f" | "-d" | "-e" | "-s" | "-r" | "-w" | "-x" | "-L" | "-h" | "-t"
     )
 }
 
@@ -2657,6 +3864,10 @@ fn eval_unary_test(
                 Err(_) => false,
             }
         }
+        "-L" | "-h" => host
+            .lstat(&state.resolve_path(operand))
+            .is_ok_and(|info| info.is_symlink),
+        "-t" => operand.parse().is_ok_and(|fd| state.isatty(host, fd)),
         _ => false,
     }
 }
@@ -2694,7 +3905,9 @@ fn eval_binary_test(left: &str, op: &str, right: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::control::ErrorCategory;
     use crate::test_support::mock::{MockHost, MockSpawnOutput};
+    use codepod_shell::token::Span;
 
     /// Helper: execute a shell command string, capturing stdout via a pipe.
     /// Returns (exit_code, captured_stdout).
@@ -3057,6 +4270,24 @@ mod tests {
         assert_eq!(result, "");
     }
 
+    #[test]
+    fn backtick_substitution_basic() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (exit_code, stdout) = exec_capture(&mut state, &host, "echo `echo hello`!");
+        assert_eq!(exit_code, 0);
+        assert_eq!(stdout, "hello!\n");
+    }
+
+    #[test]
+    fn backtick_substitution_nested_in_assignment() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, stdout) =
+            exec_capture(&mut state, &host, r"x=`echo \`echo inner\` outer`; echo $x");
+        assert_eq!(stdout, "inner outer\n");
+    }
+
     // ---- Redirect tests ----
 
     /// Helper: build a `Command::Simple` with the given command name and redirects.
@@ -3264,9 +4495,9 @@ mod tests {
         let cmd =
             simple_cmd_with_redirects("cmd", &[], vec![redirect(RedirectType::StderrToStdout)]);
         let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
-        // In pipe-based model, stderr merging happens at fd level;
-        // here we just verify stdout was captured.
-        assert_eq!(stdout, "out\n");
+        // In pipe-based model, stderr merging happens at fd level:
+        // the spawn's stderr fd is the same pipe as stdout.
+        assert_eq!(stdout, "out\nerr\n");
     }
 
     #[test]
@@ -3343,7 +4574,7 @@ mod tests {
 
     #[test]
     fn redirect_heredoc_strip() {
-        // HeredocStrip content becomes stdin (tab stripping is done by the parser)
+        // HeredocStrip content becomes stdin with its leading tabs stripped
         let host = MockHost::new().with_spawn_result(
             "cat",
             MockSpawnOutput {
@@ -3364,6 +4595,18 @@ mod tests {
         assert_eq!(stdout, "stripped content\n");
     }
 
+    #[test]
+    fn heredoc_strips_tabs_before_expanding() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "x=$(printf '\\tkeep')\nmapfile -t a <<-EOF\n\t\tone $x \\$HOME \\\ncontinued\n\tEOF\necho \"[${a[0]}]\"",
+        );
+        assert_eq!(out, "[one \tkeep $HOME continued]\n");
+    }
+
     #[test]
     fn redirect_here_string() {
         // `cat <<< "hello"` — stdin becomes "hello\n"
@@ -3407,13 +4650,318 @@ mod tests {
     }
 
     #[test]
-    fn redirect_multiple_output_redirects() {
-        // `cmd > /tmp/out.txt 2> /tmp/err.txt` — stdout and stderr to separate files
-        let host = MockHost::new().with_spawn_result(
-            "cmd",
-            MockSpawnOutput {
-                exit_code: 0,
-                stdout: "output\n".into(),
+    fn noclobber_refuses_existing_file() {
+        use crate::state::ShellFlag;
+        let host = MockHost::new().with_file("/tmp/out.txt", b"old\n");
+        let mut state = ShellState::new_default();
+        state.flags.insert(ShellFlag::Noclobber);
+        let cmd = codepod_shell::parser::parse("echo new > /tmp/out.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 1);
+        assert_eq!(host.get_file("/tmp/out.txt").unwrap(), "old\n");
+    }
+
+    #[test]
+    fn noclobber_allows_new_file_and_append() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd =
+            codepod_shell::parser::parse("set -C; echo a > /tmp/out.txt; echo b >> /tmp/out.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 0);
+        assert_eq!(host.get_file("/tmp/out.txt").unwrap(), "a\nb\n");
+    }
+
+    #[test]
+    fn noclobber_overridden_by_pipe_redirect() {
+        let host = MockHost::new().with_file("/tmp/out.txt", b"old\n");
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("set -o noclobber; echo new >| /tmp/out.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 0);
+        assert_eq!(host.get_file("/tmp/out.txt").unwrap(), "new\n");
+    }
+
+    #[test]
+    fn noclobber_shows_in_set_o_and_dollar_dash() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, stdout) = exec_capture(&mut state, &host, "echo \"[$-]\"; set -eC; echo \"[$-]\"");
+        assert_eq!(stdout, "[]\n[eC]\n");
+        let (_, stdout) = exec_capture(&mut state, &host, "set -o; set +o");
+        assert!(stdout.contains("\nnoclobber      \ton\nnounset        \toff\n"));
+        assert!(stdout.contains("set -o noclobber\nset +o nounset\n"));
+    }
+
+    const FIXTURE_SCRIPT: &str = "read greeting < /in.txt
+upper \"$greeting\" > /tmp/out.txt
+n=$(wc -l < /tmp/out.txt)
+echo \"lines: $n\" >> /tmp/out.txt";
+
+    fn fixture_host() -> MockHost {
+        MockHost::new()
+            .with_file("/in.txt", b"hello\n")
+            .with_dir("/tmp")
+    }
+
+    #[test]
+    fn recorded_fixture_replays_the_same_run() {
+        let host = fixture_host()
+            .with_recording()
+            .with_spawn_handler(|program, args, stdin| MockSpawnOutput {
+                exit_code: 0,
+                stdout: match program {
+                    "upper" => format!("{}\n", args.join(" ").to_uppercase()),
+                    _ => format!("{}\n", stdin.lines().count()),
+                },
+                stderr: String::new(),
+            });
+        let mut state = ShellState::new_default();
+        let _ = exec_command(
+            &mut state,
+            &host,
+            &codepod_shell::parser::parse(FIXTURE_SCRIPT),
+        );
+        let recorded = host.get_file("/tmp/out.txt");
+        assert_eq!(recorded.as_deref(), Some("HELLO\nlines: 1\n"));
+
+        // Without the spawn handler, the replay gets its output from the
+        // fixture alone.
+        let fixture = crate::test_support::mock::Fixture::from_json(&host.fixture().to_json());
+        let replay = fixture_host().with_replay(&fixture);
+        let mut state = ShellState::new_default();
+        let _ = exec_command(
+            &mut state,
+            &replay,
+            &codepod_shell::parser::parse(FIXTURE_SCRIPT),
+        );
+        assert_eq!(replay.get_file("/tmp/out.txt"), recorded);
+        replay.assert_replayed();
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture expected")]
+    fn replay_panics_when_the_run_diverges() {
+        let host = fixture_host().with_recording();
+        let mut state = ShellState::new_default();
+        let _ = exec_command(
+            &mut state,
+            &host,
+            &codepod_shell::parser::parse(FIXTURE_SCRIPT),
+        );
+
+        let replay = fixture_host().with_replay(&host.fixture());
+        let script = FIXTURE_SCRIPT.replace("upper", "lower");
+        let _ = exec_command(&mut state, &replay, &codepod_shell::parser::parse(&script));
+    }
+
+    #[test]
+    fn redirect_past_the_storage_quota_fails() {
+        let host = MockHost::new()
+            .with_file("/tmp/out.txt", b"old\n")
+            .with_quota(8);
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("echo 0123456789 > /tmp/out.txt");
+        let err = exec_command(&mut state, &host, &cmd).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "host error: /tmp/out.txt: Disk quota exceeded"
+        );
+        assert_eq!(host.get_file("/tmp/out.txt").unwrap(), "old\n");
+    }
+
+    #[test]
+    fn fd_redirect_dup_stdout_to_numbered_fd() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("echo hello 3>/tmp/log >&3");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(host.get_file("/tmp/log").unwrap(), "hello\n");
+        assert!(state.fd_table.is_empty());
+    }
+
+    #[test]
+    fn fd_redirect_stderr_to_numbered_fd() {
+        let host = MockHost::new().with_spawn_result(
+            "cmd",
+            MockSpawnOutput {
+                exit_code: 0,
+                stdout: String::new(),
+                stderr: "oops\n".into(),
+            },
+        );
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("cmd 3>/tmp/err.log 2>&3");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(host.get_file("/tmp/err.log").unwrap(), "oops\n");
+    }
+
+    #[test]
+    fn fd_redirect_exec_keeps_fd_open_until_closed() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse(
+            "exec 3>/tmp/log; echo one >&3; echo two >&3; exec 3>&-; echo three >&3",
+        );
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(host.get_file("/tmp/log").unwrap(), "one\ntwo\n");
+        assert!(state.fd_table.is_empty());
+    }
+
+    #[test]
+    fn fd_redirect_on_brace_group() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("{ echo a >&3; echo b >&3; } 3>/tmp/log");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(host.get_file("/tmp/log").unwrap(), "a\nb\n");
+        assert!(state.fd_table.is_empty());
+    }
+
+    #[test]
+    fn fd_redirect_variable_allocation() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("exec {log}>/tmp/log; echo hi >&$log");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.env.get("log").unwrap(), "10");
+        assert_eq!(host.get_file("/tmp/log").unwrap(), "hi\n");
+    }
+
+    #[test]
+    fn output_redirect_preserves_binary_data() {
+        let host = MockHost::new();
+        let state = ShellState::new_default();
+        let data = vec![0x89, b'P', b'N', b'G', 0x00, 0xff, 0xfe];
+        let mut stdout = data.clone();
+        let mut stderr = Vec::new();
+        let redirects = vec![redirect(RedirectType::StdoutOverwrite(
+            "/tmp/img.png".into(),
+        ))];
+        apply_output_redirects(&state, &host, &redirects, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(host.read_file("/tmp/img.png").unwrap(), data);
+        assert!(stdout.is_empty());
+    }
+
+    #[test]
+    fn stdin_redirect_accepts_binary_file() {
+        let host = MockHost::new()
+            .with_file("/tmp/img.png", &[0x89, b'P', b'N', b'G', 0x00, 0xff])
+            .with_spawn_result(
+                "wc",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: "6\n".into(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("wc -c < /tmp/img.png");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 0);
+        assert_eq!(host.get_spawn_calls().len(), 1);
+    }
+
+    #[test]
+    fn dev_null_redirect_discards_output() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("echo hi > /dev/null; echo err 2>> /dev/null");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 0);
+        assert!(host.get_file("/dev/null").is_none());
+    }
+
+    #[test]
+    fn dev_null_as_stdin_reads_empty() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("read line < /dev/null");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 1);
+        assert!(host.get_file("/dev/null").is_none());
+    }
+
+    #[test]
+    fn dev_stdout_redirect_writes_to_stdout() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (code, out) = exec_capture(&mut state, &host, "echo hi > /dev/stdout");
+        assert_eq!(code, 0);
+        assert_eq!(out, "hi\n");
+        assert!(host.get_file("/dev/stdout").is_none());
+    }
+
+    #[test]
+    fn dev_stderr_follows_redirected_stderr() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        exec_capture(&mut state, &host, "{ echo x > /dev/stderr; } 2>/tmp/e1");
+        assert_eq!(host.get_file("/tmp/e1").unwrap(), "x\n");
+        // Within one command, /dev/stderr is wherever fd 2 points so far.
+        let (_, out) = exec_capture(&mut state, &host, "echo y 2>/tmp/e2 > /dev/stderr");
+        assert_eq!(out, "");
+        assert_eq!(host.get_file("/tmp/e2").unwrap(), "y\n");
+    }
+
+    #[test]
+    fn dev_stdout_and_dev_fd_follow_redirected_fds() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        exec_capture(&mut state, &host, "{ echo a > /dev/stdout; } > /tmp/out");
+        assert_eq!(host.get_file("/tmp/out").unwrap(), "a\n");
+        exec_capture(&mut state, &host, "exec 3>/tmp/log; echo b > /dev/fd/3");
+        assert_eq!(host.get_file("/tmp/log").unwrap(), "b\n");
+    }
+
+    #[test]
+    fn compound_output_redirects_to_files() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "{ echo a; echo b >&2; } > /tmp/o 2>/tmp/e; ( echo c; echo d >&2 ) &>/tmp/both",
+        );
+        assert_eq!(out, "");
+        assert_eq!(host.get_file("/tmp/o").unwrap(), "a\n");
+        assert_eq!(host.get_file("/tmp/e").unwrap(), "b\n");
+        assert_eq!(host.get_file("/tmp/both").unwrap(), "c\nd\n");
+
+        exec_capture(&mut state, &host, "{ echo e; echo f >&2; } >> /tmp/o 2>&1");
+        assert_eq!(host.get_file("/tmp/o").unwrap(), "a\ne\nf\n");
+        // A redirect inside the group still wins over the group's own.
+        exec_capture(&mut state, &host, "{ echo g > /tmp/inner; } > /tmp/outer");
+        assert_eq!(host.get_file("/tmp/inner").unwrap(), "g\n");
+        assert_eq!(host.get_file("/tmp/outer").unwrap(), "");
+    }
+
+    #[test]
+    fn read_write_redirect_reads_file() {
+        let host = MockHost::new().with_file("/tmp/data.txt", b"first\nsecond\n");
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("read line <> /tmp/data.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.env.get("line").unwrap(), "first");
+    }
+
+    #[test]
+    fn read_write_redirect_creates_missing_file() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("read line <> /tmp/new.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(host.get_file("/tmp/new.txt").unwrap(), "");
+    }
+
+    #[test]
+    fn redirect_multiple_output_redirects() {
+        // `cmd > /tmp/out.txt 2> /tmp/err.txt` — stdout and stderr to separate files
+        let host = MockHost::new().with_spawn_result(
+            "cmd",
+            MockSpawnOutput {
+                exit_code: 0,
+                stdout: "output\n".into(),
                 stderr: "error\n".into(),
             },
         );
@@ -3535,93 +5083,485 @@ mod tests {
     }
 
     #[test]
-    fn redirect_last_stdin_redirect_wins() {
-        // Multiple input redirects: the last one should win
-        let host = MockHost::new()
-            .with_file("/tmp/a.txt", b"content a\n")
-            .with_file("/tmp/b.txt", b"content b\n")
-            .with_spawn_result(
-                "cat",
-                MockSpawnOutput {
-                    exit_code: 0,
-                    stdout: "content b\n".into(),
-                    stderr: String::new(),
-                },
-            );
-        let mut state = ShellState::new_default();
-        let cmd = simple_cmd_with_redirects(
-            "cat",
-            &[],
-            vec![
-                redirect(RedirectType::StdinFrom("/tmp/a.txt".into())),
-                redirect(RedirectType::StdinFrom("/tmp/b.txt".into())),
-            ],
+    fn redirect_last_stdin_redirect_wins() {
+        // Multiple input redirects: the last one should win
+        let host = MockHost::new()
+            .with_file("/tmp/a.txt", b"content a\n")
+            .with_file("/tmp/b.txt", b"content b\n")
+            .with_spawn_result(
+                "cat",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: "content b\n".into(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+        let cmd = simple_cmd_with_redirects(
+            "cat",
+            &[],
+            vec![
+                redirect(RedirectType::StdinFrom("/tmp/a.txt".into())),
+                redirect(RedirectType::StdinFrom("/tmp/b.txt".into())),
+            ],
+        );
+        let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
+        // Last input redirect wins, so cat sees content of b.txt
+        assert_eq!(stdout, "content b\n");
+    }
+
+    // ---- Pipeline tests ----
+
+    #[test]
+    fn pipeline_two_stage_stdin_threading() {
+        // `echo hello | cat` — cat receives "hello\n" as stdin
+        // Note: `echo` is now a builtin, so only `cat` generates a spawn call.
+        let host = MockHost::new().with_spawn_handler(|program, _args, stdin| match program {
+            "cat" => MockSpawnOutput {
+                exit_code: 0,
+                stdout: stdin.to_string(),
+                stderr: String::new(),
+            },
+            _ => MockSpawnOutput {
+                exit_code: 127,
+                stdout: String::new(),
+                stderr: format!("{program}: command not found"),
+            },
+        });
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("echo hello | cat");
+        let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
+        assert_eq!(exit_code, 0);
+        assert_eq!(stdout, "hello\n");
+
+        // echo is a builtin; only cat is spawned, receiving echo's stdout as stdin
+        let calls = host.get_spawn_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].program, "cat");
+        assert_eq!(calls[0].stdin, "hello\n"); // cat gets echo's stdout
+    }
+
+    #[test]
+    fn pipeline_three_stage() {
+        // `echo hello | cat | cat` — chaining works through 3 stages
+        // Note: `echo` is a builtin, so only two `cat` spawns.
+        let host = MockHost::new().with_spawn_handler(|program, _args, stdin| match program {
+            "cat" => MockSpawnOutput {
+                exit_code: 0,
+                stdout: stdin.to_string(),
+                stderr: String::new(),
+            },
+            _ => MockSpawnOutput {
+                exit_code: 127,
+                stdout: String::new(),
+                stderr: format!("{program}: command not found"),
+            },
+        });
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("echo hello | cat | cat");
+        let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
+        assert_eq!(exit_code, 0);
+        assert_eq!(stdout, "hello\n");
+
+        // echo is a builtin; two cat spawns
+        let calls = host.get_spawn_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].program, "cat");
+        assert_eq!(calls[0].stdin, "hello\n");
+        assert_eq!(calls[1].program, "cat");
+        assert_eq!(calls[1].stdin, "hello\n");
+    }
+
+    #[test]
+    fn execution_time_measured_for_commands_and_pipelines() {
+        let host = MockHost::new()
+            .with_spawn_duration_ms(250.0)
+            .with_spawn_result(
+                "cmd",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("cmd");
+        let Ok(ControlFlow::Normal(run)) = exec_command(&mut state, &host, &cmd) else {
+            panic!("expected Normal")
+        };
+        assert_eq!(run.execution_time_ms, 250);
+
+        let cmd = codepod_shell::parser::parse("cmd | cmd");
+        let Ok(ControlFlow::Normal(run)) = exec_command(&mut state, &host, &cmd) else {
+            panic!("expected Normal")
+        };
+        assert_eq!(run.execution_time_ms, 500);
+    }
+
+    #[test]
+    fn time_keyword_keeps_exit_status() {
+        let host = MockHost::new().with_spawn_duration_ms(1500.0);
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("time -p missing-cmd");
+        let Ok(ControlFlow::Normal(run)) = exec_command(&mut state, &host, &cmd) else {
+            panic!("expected Normal")
+        };
+        assert_eq!(run.exit_code, 127);
+        assert_eq!(run.execution_time_ms, 1500);
+    }
+
+    #[test]
+    fn cancellation_stops_loop_between_iterations() {
+        let host = MockHost::new()
+            .with_spawn_result(
+                "cmd",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            )
+            .with_cancel_after_spawns(2);
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("for i in 1 2 3 4 5; do cmd; done");
+        let result = exec_command(&mut state, &host, &cmd);
+        assert!(matches!(
+            result,
+            Ok(ControlFlow::Cancelled(CancelReason::Cancelled))
+        ));
+        assert_eq!(host.get_spawn_calls().len(), 2);
+        assert_eq!(state.env.get("i").map(String::as_str), Some("3"));
+    }
+
+    #[test]
+    fn cancellation_stops_pipeline_between_stages() {
+        let host = MockHost::new()
+            .with_spawn_result(
+                "cmd",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            )
+            .with_cancel_after_spawns(1);
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("cmd | cmd | cmd; cmd");
+        let result = exec_command(&mut state, &host, &cmd);
+        assert!(matches!(
+            result,
+            Ok(ControlFlow::Cancelled(CancelReason::Cancelled))
+        ));
+        assert_eq!(host.get_spawn_calls().len(), 1);
+    }
+
+    #[test]
+    fn select_sets_var_and_reply() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "printf '2\\n' | select x in a b c; do echo \"$x $REPLY\"; break; done",
+        );
+        assert_eq!(out, "b 2\n");
+
+        // Invalid picks leave the variable empty; empty lines just reprompt;
+        // EOF ends the loop.
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "printf 'zz\\n\\n3\\n' | select x in a b c; do echo \"[$x]\"; done",
+        );
+        assert_eq!(out, "[]\n[c]\n");
+    }
+
+    #[test]
+    fn pipestatus_records_every_stage() {
+        let host = MockHost::new().with_spawn_result(
+            "fail3",
+            MockSpawnOutput {
+                exit_code: 3,
+                stdout: String::new(),
+                stderr: String::new(),
+            },
+        );
+        let mut state = ShellState::new_default();
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "fail3 | true | false; echo ${PIPESTATUS[@]}",
+        );
+        assert_eq!(out, "3 0 1\n");
+
+        let (_, out) = exec_capture(&mut state, &host, "false; echo ${PIPESTATUS[@]}");
+        assert_eq!(out, "1\n");
+    }
+
+    #[test]
+    fn coproc_exposes_pipes_through_array() {
+        let host = MockHost::new().with_spawn_result(
+            "upper",
+            MockSpawnOutput {
+                exit_code: 0,
+                stdout: "HELLO\n".into(),
+                stderr: String::new(),
+            },
+        );
+        let mut state = ShellState::new_default();
+        // Output redirects are captured only while stdout is the real fd 1.
+        let cmd = codepod_shell::parser::parse("coproc upper; echo ping >&${COPROC[1]}");
+        exec_command(&mut state, &host, &cmd).unwrap();
+        let (code, out) =
+            exec_capture(&mut state, &host, "read -r line <&${COPROC[0]}; echo $line");
+        assert_eq!(code, 0);
+        assert_eq!(out, "HELLO\n");
+        let pid: i32 = state.env["COPROC_PID"].parse().unwrap();
+        assert_eq!(state.last_bg_pid, pid);
+        assert_eq!(host.read_duplex_stdin(pid), b"ping\n");
+
+        let (code, _) = exec_capture(&mut state, &host, "coproc { echo hi; }");
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn wait_reports_background_job_status() {
+        let host = MockHost::new().with_spawn_result(
+            "fail3",
+            MockSpawnOutput {
+                exit_code: 3,
+                stdout: String::new(),
+                stderr: String::new(),
+            },
+        );
+        let mut state = ShellState::new_default();
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "fail3 & pid=$!; wait $pid; echo $? $((pid > 0))",
+        );
+        assert_eq!(out, "3 1\n");
+        assert!(state.jobs.is_empty());
+
+        // The builtin job finishes synchronously, so it is reported first;
+        // an empty table gives 127.
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "fail3 & false & wait -n; echo $?; wait -n; echo $?; wait -n; echo $?",
+        );
+        assert_eq!(out, "1\n3\n127\n");
+
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "fail3 & fail3 & wait %1; echo $?; wait; echo $?; wait %1; echo $?",
+        );
+        assert_eq!(out, "3\n0\n127\n");
+    }
+
+    #[test]
+    fn kill_signals_background_job_through_host() {
+        let host = MockHost::new().with_tool("server");
+        let mut state = ShellState::new_default();
+        let (_, out) = exec_capture(
+            &mut state,
+            &host,
+            "server & kill %1; wait %1; echo $?; server & kill -s KILL $!; wait $!; echo $?",
+        );
+        assert_eq!(out, "143\n137\n");
+        let signals: Vec<i32> = host.get_kills().iter().map(|&(_, sig)| sig).collect();
+        assert_eq!(signals, vec![15, 9]);
+
+        let (code, _) = exec_capture(&mut state, &host, "kill %4");
+        assert_eq!(code, 1);
+        let (_, out) = exec_capture(&mut state, &host, "kill -l 137");
+        assert_eq!(out, "KILL\n");
+    }
+
+    fn slow_cmd_host(ms: f64) -> MockHost {
+        MockHost::new()
+            .with_spawn_duration_ms(ms)
+            .with_spawn_result(
+                "cmd",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            )
+    }
+
+    #[test]
+    fn timeout_keyword_kills_slow_command() {
+        let host = slow_cmd_host(2000.0);
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("timeout 1 cmd");
+        let Ok(ControlFlow::Normal(run)) = exec_command(&mut state, &host, &cmd) else {
+            panic!("expected Normal")
+        };
+        assert_eq!(run.exit_code, 124);
+        assert_eq!(host.get_kills(), vec![(100, 15)]);
+        assert_eq!(state.deadline_ms, None);
+
+        let host = slow_cmd_host(1000.0);
+        let cmd = codepod_shell::parser::parse("timeout 1.5s cmd");
+        let Ok(ControlFlow::Normal(run)) = exec_command(&mut state, &host, &cmd) else {
+            panic!("expected Normal")
+        };
+        assert_eq!(run.exit_code, 0);
+        assert!(host.get_kills().is_empty());
+    }
+
+    #[test]
+    fn timeout_keyword_stops_waiting_on_a_running_command() {
+        let host = MockHost::new()
+            .with_spawn_latency(2000.0)
+            .with_spawn_result(
+                "cmd",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+        let (code, _) = exec_capture(&mut state, &host, "timeout 1 cmd");
+        assert_eq!(code, 124);
+        assert_eq!(host.get_kills(), vec![(100, 15)]);
+        assert_eq!(host.monotonic_ms(), 1000.0);
+
+        let (code, _) = exec_capture(&mut state, &host, "timeout 5 cmd");
+        assert_eq!(code, 0);
+        assert_eq!(host.monotonic_ms(), 3000.0);
+    }
+
+    #[test]
+    fn injected_faults_reach_redirects_and_spawns() {
+        let host = MockHost::new()
+            .with_failing_write("/tmp/out", HostError::PermissionDenied)
+            .with_failing_read("/tmp/in", HostError::IoError)
+            .with_failing_spawn("tool", HostError::PermissionDenied);
+        let mut state = ShellState::new_default();
+        let run = |state: &mut ShellState, src: &str| {
+            exec_command(state, &host, &codepod_shell::parser::parse(src))
+                .map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            run(&mut state, "echo hi > /tmp/out").unwrap_err(),
+            "host error: /tmp/out: Permission denied"
+        );
+        assert_eq!(
+            run(&mut state, "cat < /tmp/in").unwrap_err(),
+            "host error: I/O error: /tmp/in"
+        );
+        run(&mut state, "tool").unwrap();
+        assert_eq!(state.last_exit_code, 126);
+        run(&mut state, "echo hi | tool").unwrap();
+        assert_eq!(state.last_exit_code, 127);
+    }
+
+    #[test]
+    fn timeout_keyword_rejects_bad_interval() {
+        let host = slow_cmd_host(0.0);
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("timeout soon cmd");
+        let Ok(ControlFlow::Normal(run)) = exec_command(&mut state, &host, &cmd) else {
+            panic!("expected Normal")
+        };
+        assert_eq!(run.exit_code, 125);
+        assert!(host.get_spawn_calls().is_empty());
+    }
+
+    #[test]
+    fn script_deadline_kills_child_and_stops_script() {
+        let host = slow_cmd_host(1000.0);
+        let mut state = ShellState::new_default();
+        state.deadline_ms = Some(1500.0);
+        let cmd = codepod_shell::parser::parse("cmd; cmd; cmd");
+        let result = exec_command(&mut state, &host, &cmd);
+        assert!(matches!(
+            result,
+            Ok(ControlFlow::Cancelled(CancelReason::Timeout))
+        ));
+        assert_eq!(host.get_spawn_calls().len(), 2);
+        assert_eq!(host.get_kills(), vec![(101, 15)]);
+        assert_eq!(state.last_exit_code, 124);
+    }
+
+    #[test]
+    fn duration_parsing() {
+        assert_eq!(parse_duration_ms("2"), Some(2_000.0));
+        assert_eq!(parse_duration_ms("0.5s"), Some(500.0));
+        assert_eq!(parse_duration_ms("1m"), Some(60_000.0));
+        assert_eq!(parse_duration_ms("1d"), Some(86_400_000.0));
+        assert_eq!(parse_duration_ms("-1"), None);
+        assert_eq!(parse_duration_ms(""), None);
+    }
+
+    #[test]
+    fn time_report_format() {
+        assert_eq!(
+            format_time_report(61_250, false),
+            "\nreal\t1m1.250s\nuser\t0m0.000s\nsys\t0m0.000s\n"
+        );
+        assert_eq!(
+            format_time_report(1_500, true),
+            "real 1.50\nuser 0.00\nsys 0.00\n"
         );
-        let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
-        // Last input redirect wins, so cat sees content of b.txt
-        assert_eq!(stdout, "content b\n");
     }
 
-    // ---- Pipeline tests ----
-
-    #[test]
-    fn pipeline_two_stage_stdin_threading() {
-        // `echo hello | cat` — cat receives "hello\n" as stdin
-        // Note: `echo` is now a builtin, so only `cat` generates a spawn call.
-        let host = MockHost::new().with_spawn_handler(|program, _args, stdin| match program {
+    fn cat_grep_host() -> MockHost {
+        MockHost::new().with_spawn_handler(|program, args, stdin| match program {
             "cat" => MockSpawnOutput {
                 exit_code: 0,
                 stdout: stdin.to_string(),
                 stderr: String::new(),
             },
+            "grep" => MockSpawnOutput {
+                exit_code: 0,
+                stdout: stdin
+                    .lines()
+                    .filter(|l| l.contains(args[0]))
+                    .map(|l| format!("{l}\n"))
+                    .collect(),
+                stderr: String::new(),
+            },
             _ => MockSpawnOutput {
                 exit_code: 127,
                 stdout: String::new(),
                 stderr: format!("{program}: command not found"),
             },
-        });
+        })
+    }
+
+    #[test]
+    fn pipeline_stage_reads_stdin_redirect() {
+        let host = cat_grep_host().with_file("/tmp/big.log", b"a1\nb2\na3\n");
         let mut state = ShellState::new_default();
-        let cmd = codepod_shell::parser::parse("echo hello | cat");
-        let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
+        let (exit_code, stdout) = exec_capture(&mut state, &host, "grep a < /tmp/big.log | cat");
         assert_eq!(exit_code, 0);
-        assert_eq!(stdout, "hello\n");
-
-        // echo is a builtin; only cat is spawned, receiving echo's stdout as stdin
-        let calls = host.get_spawn_calls();
-        assert_eq!(calls.len(), 1);
-        assert_eq!(calls[0].program, "cat");
-        assert_eq!(calls[0].stdin, "hello\n"); // cat gets echo's stdout
+        assert_eq!(stdout, "a1\na3\n");
     }
 
     #[test]
-    fn pipeline_three_stage() {
-        // `echo hello | cat | cat` — chaining works through 3 stages
-        // Note: `echo` is a builtin, so only two `cat` spawns.
-        let host = MockHost::new().with_spawn_handler(|program, _args, stdin| match program {
-            "cat" => MockSpawnOutput {
-                exit_code: 0,
-                stdout: stdin.to_string(),
-                stderr: String::new(),
-            },
-            _ => MockSpawnOutput {
-                exit_code: 127,
-                stdout: String::new(),
-                stderr: format!("{program}: command not found"),
-            },
-        });
+    fn pipeline_last_stage_writes_stdout_redirect() {
+        let host = cat_grep_host();
         let mut state = ShellState::new_default();
-        let cmd = codepod_shell::parser::parse("echo hello | cat | cat");
-        let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
+        let (exit_code, stdout) = exec_capture(&mut state, &host, "echo hi | cat > /tmp/out.txt");
         assert_eq!(exit_code, 0);
-        assert_eq!(stdout, "hello\n");
+        assert_eq!(stdout, "");
+        assert_eq!(host.get_file("/tmp/out.txt").unwrap(), "hi\n");
+    }
 
-        // echo is a builtin; two cat spawns
-        let calls = host.get_spawn_calls();
-        assert_eq!(calls.len(), 2);
-        assert_eq!(calls[0].program, "cat");
-        assert_eq!(calls[0].stdin, "hello\n");
-        assert_eq!(calls[1].program, "cat");
-        assert_eq!(calls[1].stdin, "hello\n");
+    #[test]
+    fn pipeline_builtin_stage_writes_stdout_redirect() {
+        let host = cat_grep_host();
+        let mut state = ShellState::new_default();
+        let (_, stdout) = exec_capture(&mut state, &host, "echo hi > /tmp/out.txt | cat");
+        assert_eq!(stdout, "");
+        assert_eq!(host.get_file("/tmp/out.txt").unwrap(), "hi\n");
     }
 
     #[test]
@@ -3776,12 +5716,11 @@ mod tests {
         let mut state = ShellState::new_default();
         let cmd = codepod_shell::parser::parse("cmd 2>&1 | cat");
         let (exit_code, stdout) = exec_capture_cmd(&mut state, &host, &cmd);
-        // In pipe-based model, stderr merge happens at fd level;
-        // cat only receives what was written to stdout_fd.
-        assert_eq!(stdout, "out\n");
+        // The spawn's stderr fd is the stage's stdout pipe.
+        assert_eq!(stdout, "out\nerr\n");
 
         let calls = host.get_spawn_calls();
-        assert_eq!(calls[1].stdin, "out\n");
+        assert_eq!(calls[1].stdin, "out\nerr\n");
     }
 
     #[test]
@@ -4843,10 +6782,10 @@ mod tests {
             assignments: vec![],
         };
         let result = exec_command(&mut state, &host, &cmd);
-        let ControlFlow::Normal(run) = result.unwrap() else {
-            panic!("expected Normal")
-        };
-        assert_eq!(run.exit_code, 1);
+        assert!(matches!(
+            result,
+            Err(ShellError::LimitExceeded(LimitKind::FunctionDepth))
+        ));
     }
 
     #[test]
@@ -5299,6 +7238,22 @@ mod tests {
         assert_eq!(run.exit_code, 1);
     }
 
+    #[test]
+    fn arithmetic_cmd_assigns_variable() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, stdout) = exec_capture(&mut state, &host, "(( x = 6 * 7 )); echo $? $x");
+        assert_eq!(stdout, "0 42\n");
+    }
+
+    #[test]
+    fn arithmetic_cmd_expands_command_substitution() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, stdout) = exec_capture(&mut state, &host, "(( y = $(echo 4) * 2 )); echo $y");
+        assert_eq!(stdout, "8\n");
+    }
+
     // ====================================================================
     // Assignment handling tests
     // ====================================================================
@@ -5870,6 +7825,17 @@ mod tests {
         assert_eq!(resolved[1], "/home/user/file.txt"); // file resolved
     }
 
+    #[test]
+    fn resolve_command_args_python_keeps_argv_as_typed() {
+        let state = ShellState::new_default();
+        let host = MockHost::new()
+            .with_file("/home/user/script.py", b"print(1)")
+            .with_file("/home/user/data.csv", b"a,b");
+        let args = vec!["script.py", "data.csv", "out.txt"];
+        let resolved = resolve_command_args(&state, &host, "python3", &args);
+        assert_eq!(resolved, vec!["script.py", "data.csv", "out.txt"]);
+    }
+
     // ---- implicit cwd commands (integration) ----
 
     #[test]
@@ -5964,6 +7930,25 @@ mod tests {
         assert_eq!(stdout, "hello\n");
     }
 
+    #[test]
+    fn run_script_file_binds_args_and_returns_status() {
+        let host = MockHost::new().with_file(
+            "/home/user/job.sh",
+            b"#!/bin/sh\ntrap 'echo bye' EXIT\necho \"$0 $# $2\"\nexit 3\n",
+        );
+        let mut state = ShellState::new_default();
+        let (read_fd, write_fd) = host.pipe().unwrap();
+        state.stdout_fd = write_fd;
+        let args = vec!["a".to_string(), "b".to_string()];
+        let code = run_script_file(&mut state, &host, "job.sh", &args);
+        host.close_fd(write_fd).unwrap();
+        let out = String::from_utf8(host.read_fd(read_fd).unwrap()).unwrap();
+        assert_eq!(code, 3);
+        assert_eq!(out, "job.sh 2 b\nbye\n");
+
+        assert_eq!(run_script_file(&mut state, &host, "missing.sh", &[]), 127);
+    }
+
     #[test]
     fn exec_path_script_no_shebang() {
         let host = MockHost::new().with_file("/home/user/run.sh", b"echo no_shebang\n");
@@ -6499,6 +8484,94 @@ mod tests {
         assert!(stdout.contains("hello /tmp/.proc_sub_"));
     }
 
+    #[test]
+    fn binary_data_survives_redirects_and_output_process_subs() {
+        use crate::host::FetchResult;
+
+        let png = [0xff, 0x00, 0x89, b'P', b'N', b'G'];
+        let host = MockHost::new()
+            .with_fetch_result(
+                "https://example.com/img",
+                FetchResult {
+                    ok: true,
+                    status: 200,
+                    headers: Default::default(),
+                    body: String::new(),
+                    body_base64: Some("/wCJUE5H".to_string()),
+                    error: None,
+                },
+            )
+            .with_spawn_result(
+                "tool",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+
+        let cmd = codepod_shell::parser::parse(
+            "curl -s https://example.com/img > /img; tool < /img; \
+             curl -s -o >(tool) https://example.com/img",
+        );
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 0);
+        assert_eq!(host.get_file_bytes("/img").unwrap(), png);
+        let calls = host.get_spawn_calls();
+        assert_eq!(calls.len(), 2);
+        let expected = String::from_utf8_lossy(&png);
+        assert_eq!(calls[0].stdin, expected);
+        assert_eq!(calls[1].stdin, expected);
+    }
+
+    #[test]
+    fn unrunnable_paths_map_to_shell_exit_codes() {
+        let host = MockHost::new()
+            .with_dir("/bin")
+            .with_file("/bin/ok.sh", b"exit 3\n");
+        let mut state = ShellState::new_default();
+        let (code, _) = exec_capture(&mut state, &host, "/bin/ok.sh");
+        assert_eq!(code, 3);
+        let (code, _) = exec_capture(&mut state, &host, "./missing.sh");
+        assert_eq!(code, 127);
+        let (code, _) = exec_capture(&mut state, &host, "/bin");
+        assert_eq!(code, 126);
+        let (code, _) = exec_capture(&mut state, &host, "sh /missing.sh");
+        assert_eq!(code, 127);
+
+        assert_eq!(HostError::NotFound("x".into()).command_exit_code(), 127);
+        assert_eq!(
+            HostError::PermissionDenied("x".into()).command_exit_code(),
+            126
+        );
+        assert_eq!(HostError::Interrupted("x".into()).command_exit_code(), 1);
+        assert!(matches!(
+            host.read_file("/bin"),
+            Err(HostError::IsADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn read_only_files_refuse_redirected_writes() {
+        let host = MockHost::new().with_file("/ro.txt", b"keep\n");
+        let mut state = ShellState::new_default();
+        let cmd = codepod_shell::parser::parse("chmod 444 /ro.txt; echo new > /ro.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 1);
+        assert_eq!(host.get_mode("/ro.txt").unwrap(), 0o444);
+        assert_eq!(host.get_file("/ro.txt").as_deref(), Some("keep\n"));
+
+        let (code, _) = exec_capture(&mut state, &host, "test -w /ro.txt");
+        assert_eq!(code, 1);
+
+        let cmd = codepod_shell::parser::parse("chmod u+w /ro.txt && echo ok >> /ro.txt");
+        let _ = exec_command(&mut state, &host, &cmd);
+        assert_eq!(state.last_exit_code, 0);
+        assert_eq!(host.get_file("/ro.txt").as_deref(), Some("keep\nok\n"));
+        assert_eq!(host.get_mode("/ro.txt").unwrap(), 0o644);
+    }
+
     #[test]
     fn alias_does_not_expand_in_second_position() {
         let host = MockHost::new();
@@ -6511,10 +8584,266 @@ mod tests {
         // "world" in second position should NOT be expanded
         assert_eq!(stdout, "world\n");
     }
-}
 
+    #[test]
+    fn syntax_errors_report_position_and_status_2() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let err = parse_script("echo ok\nfi").unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::Syntax);
+        assert_eq!(err.exit_code(), 2);
+        assert_eq!(
+            err.to_string(),
+            "line 2, col 1: syntax error near unexpected token `fi'"
+        );
+        let diag = err.diagnostic();
+        assert_eq!((diag.line, diag.column), (Some(2), Some(1)));
+        assert_eq!(diag.span, Some(Span { start: 8, end: 10 }));
+
+        // eval reports the error and the shell carries on.
+        let (code, stdout) = exec_capture(&mut state, &host, "eval 'echo a |'; echo $?");
+        assert_eq!(code, 0);
+        assert_eq!(stdout, "2\n");
+    }
+
+    #[test]
+    fn configured_limits_stop_the_run() {
+        let host = MockHost::new();
+        let run = |state: &mut ShellState, src: &str| {
+            state.begin_run();
+            let cmd = codepod_shell::parser::parse(src);
+            match exec_command(state, &host, &cmd) {
+                Err(ShellError::LimitExceeded(kind)) => Some(kind),
+                _ => None,
+            }
+        };
+
+        let mut state = ShellState::new_default();
+        state.limits.max_loop_iterations = 3;
+        assert_eq!(run(&mut state, "for i in 1 2 3; do :; done"), None);
+        assert_eq!(
+            run(&mut state, "i=0; while true; do i=$((i+1)); done"),
+            Some(LimitKind::LoopIterations)
+        );
+        assert_eq!(state.env["i"], "3");
+
+        let mut state = ShellState::new_default();
+        state.limits.max_substitutions = 2;
+        assert_eq!(
+            run(
+                &mut state,
+                "a=$(echo 1); b=$(echo 2); c=$(echo 3); touched=yes"
+            ),
+            Some(LimitKind::Substitutions)
+        );
+        assert!(!state.env.contains_key("touched"));
+
+        let mut state = ShellState::new_default();
+        state.limits.max_captured_bytes = 8;
+        assert_eq!(run(&mut state, "x=$(echo 12345)"), None);
+        assert_eq!(
+            run(&mut state, "x=$(echo 1234567890)"),
+            Some(LimitKind::CapturedBytes)
+        );
+
+        // Recursion is caught even when it happens inside a substitution.
+        let mut state = ShellState::new_default();
+        state.limits.max_function_depth = 5;
+        assert_eq!(
+            run(&mut state, "f() { echo $(f); }; f"),
+            Some(LimitKind::FunctionDepth)
+        );
+    }
+
+    #[test]
+    fn spawn_limit_violations_keep_their_exit_codes() {
+        use crate::host::SpawnLimits;
+        let host = MockHost::new().with_spawn_result(
+            "chatty",
+            MockSpawnOutput {
+                exit_code: 153,
+                stdout: String::new(),
+                stderr: String::new(),
+            },
+        );
+        let mut state = ShellState::new_default();
+        state.limits.spawn = SpawnLimits {
+            max_output_bytes: Some(1024),
+            ..SpawnLimits::default()
+        };
+        let (code, _) = exec_capture(&mut state, &host, "chatty");
+        assert_eq!(code, 153);
+        assert_eq!(
+            SpawnLimitKind::from_exit(&state.limits.spawn, code),
+            Some(SpawnLimitKind::Output)
+        );
+        // An unset budget never claims an exit status.
+        assert_eq!(SpawnLimitKind::from_exit(&state.limits.spawn, 124), None);
+        assert_eq!(
+            serde_json::to_string(&state.limits.spawn).unwrap(),
+            r#"{"max_output_bytes":1024}"#
+        );
+    }
+
+    #[test]
+    fn session_snapshot_resumes_in_a_new_state() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        exec_capture(
+            &mut state,
+            &host,
+            "greet() { echo \"hi $1\"; }; alias ll='ls -l'; arr=(a b); set -u; X=1",
+        );
+        exec_capture(&mut state, &host, "exec 3>/tmp/log; exec 4>/tmp/log2");
+        state.fd_table.insert(5, "/dev/fd/5".to_string());
+        state.history.push("greet you".to_string());
+        state.cwd = "/tmp".to_string();
+
+        let json = serde_json::to_string(&state.snapshot()).unwrap();
+        let mut resumed = ShellState::new_default();
+        resumed.restore(serde_json::from_str(&json).unwrap());
+
+        assert_eq!(resumed.cwd, "/tmp");
+        assert_eq!(resumed.env["X"], "1");
+        assert_eq!(resumed.aliases["ll"], "ls -l");
+        assert_eq!(resumed.arrays["arr"], vec!["a", "b"]);
+        assert!(resumed.flags.contains(&crate::state::ShellFlag::Nounset));
+        assert_eq!(resumed.history, vec!["greet you"]);
+        let (_, stdout) = exec_capture(&mut resumed, &host, "greet there");
+        assert_eq!(stdout, "hi there\n");
+        // File-backed fds carry over; the pipe does not.
+        assert_eq!(resumed.fd_table.len(), 2);
+        let cmd = codepod_shell::parser::parse("echo logged >&3");
+        let _ = exec_command(&mut resumed, &host, &cmd);
+        assert_eq!(host.get_file("/tmp/log").unwrap(), "logged\n");
+
+        // Snapshots missing newer fields still load.
+        let mut partial = ShellState::new_default();
+        partial.restore(serde_json::from_str(r#"{"env":{"Y":"2"}}"#).unwrap());
+        assert_eq!(partial.env["Y"], "2");
+        assert_eq!(partial.cwd, "/home/user");
+    }
+
+    #[test]
+    fn reset_starts_a_fresh_session_and_stops_jobs() {
+        let host = slow_cmd_host(5000.0);
+        let mut state = ShellState::new_default();
+        state.limits.max_loop_iterations = 7;
+        exec_capture(
+            &mut state,
+            &host,
+            "f() { :; }; alias ll='ls -l'; X=1; set -u; cmd &",
+        );
+        state.cwd = "/tmp".to_string();
+        assert_eq!(state.jobs.len(), 1);
+
+        state.reset(&host);
+
+        assert_eq!(host.get_kills(), vec![(100, 9)]);
+        assert!(state.jobs.is_empty());
+        assert!(state.functions.is_empty() && state.aliases.is_empty());
+        assert!(!state.env.contains_key("X"));
+        assert!(state.flags.is_empty());
+        assert_eq!(state.cwd, "/home/user");
+        assert_eq!(state.limits.max_loop_iterations, 7);
+    }
+
+    fn coreutils_host() -> MockHost {
+        ["cat", "grep", "sed", "tac", "wc"]
+            .into_iter()
+            .fold(MockHost::new(), |host, tool| host.with_tool(tool))
+            .with_file("/home/user/notes.txt", b"apple\nbanana\ncherry\n")
+    }
+
+    #[test]
+    fn coreutils_run_in_process() {
+        let host = coreutils_host();
+        let mut state = ShellState::new_default();
+
+        assert_eq!(
+            exec_capture(&mut state, &host, "grep an notes.txt"),
+            (0, "banana\n".into())
+        );
+        assert_eq!(
+            exec_capture(&mut state, &host, "tac < notes.txt").1,
+            "cherry\nbanana\napple\n"
+        );
+        assert_eq!(
+            exec_capture(&mut state, &host, "wc -l <<< 'a b'").1,
+            "       1\n"
+        );
+        exec_capture(&mut state, &host, "sed -n 2p notes.txt > second.txt");
+        assert_eq!(host.get_file("/home/user/second.txt").unwrap(), "banana\n");
+        exec_capture(&mut state, &host, "sed -i s/apple/apricot/ notes.txt");
+        assert_eq!(
+            host.get_file("/home/user/notes.txt").unwrap(),
+            "apricot\nbanana\ncherry\n"
+        );
+        assert!(host.get_spawn_calls().is_empty());
+    }
+
+    #[test]
+    fn coreutils_in_process_errors_and_status() {
+        let host = coreutils_host();
+        let mut state = ShellState::new_default();
+
+        assert_eq!(
+            exec_capture(&mut state, &host, "grep -q kiwi notes.txt").0,
+            1
+        );
+        let (code, _) = exec_capture(&mut state, &host, "cat missing.txt 2> err.txt");
+        assert_eq!(code, 1);
+        assert_eq!(state.last_exit_code, 1);
+        assert_eq!(
+            host.get_file("/home/user/err.txt").unwrap(),
+            "cat: missing.txt: No such file or directory\n"
+        );
+    }
+
+    #[test]
+    fn coreutils_in_process_binary_files() {
+        let png = [0x89, b'P', b'N', b'G', 0x00, 0xff, 0xfe];
+        let host = coreutils_host().with_file("/home/user/img.png", &png);
+        let mut state = ShellState::new_default();
 
+        exec_capture(&mut state, &host, "cat img.png > copy.png");
+        assert_eq!(host.get_file_bytes("/home/user/copy.png").unwrap(), png);
+        assert_eq!(
+            exec_capture(&mut state, &host, "wc -c img.png"),
+            (0, "       7 img.png\n".into())
+        );
+    }
 
+    #[test]
+    fn coreutils_in_compound_stage_read_the_whole_pipe() {
+        let host = coreutils_host();
+        let mut state = ShellState::new_default();
 
+        assert_eq!(
+            exec_capture(&mut state, &host, "printf 'a\\nb\\n' | { wc -l; }").1,
+            "       2\n"
+        );
+    }
 
+    #[test]
+    fn spawn_coreutils_var_spawns_them_instead() {
+        let host = coreutils_host().with_spawn_result(
+            "grep",
+            MockSpawnOutput {
+                exit_code: 0,
+                stdout: "spawned\n".into(),
+                stderr: String::new(),
+            },
+        );
+        let mut state = ShellState::new_default();
+        state
+            .env
+            .insert(crate::applets::SPAWN_COREUTILS_VAR.into(), "1".into());
 
+        assert_eq!(
+            exec_capture(&mut state, &host, "grep an notes.txt").1,
+            "spawned\n"
+        );
+        assert_eq!(host.get_spawn_calls().len(), 1);
+    }
+}
@@ -1,5 +1,6 @@
 use codepod_shell::ast::{Word, WordPart};
 
+use crate::control::LimitKind;
 use crate::host::HostInterface;
 use crate::state::{ShellFlag, ShellState};
 
@@ -14,6 +15,36 @@ use crate::state::{ShellFlag, ShellState};
 /// command string, and returns the captured stdout.
 pub type ExecFn<'a> = &'a dyn Fn(&mut ShellState, &str) -> String;
 
+/// Run a command or process substitution through `exec_fn`, enforcing the
+/// substitution depth, count and captured-output [`Limits`]. Past a limit
+/// the substitution produces no output and the limit is recorded in
+/// `state.limit_exceeded` for the executor to report.
+///
+/// [`Limits`]: crate::state::Limits
+pub fn run_substitution(state: &mut ShellState, exec_fn: ExecFn, cmd: &str) -> String {
+    let exceeded = if state.substitution_depth >= state.limits.max_substitution_depth {
+        Some(LimitKind::SubstitutionDepth)
+    } else if state.substitution_count >= state.limits.max_substitutions {
+        Some(LimitKind::Substitutions)
+    } else {
+        None
+    };
+    if let Some(kind) = exceeded {
+        state.limit_exceeded.get_or_insert(kind);
+        return String::new();
+    }
+    state.substitution_count += 1;
+    state.substitution_depth += 1;
+    let output = exec_fn(state, cmd);
+    state.substitution_depth -= 1;
+    state.captured_bytes = state.captured_bytes.saturating_add(output.len());
+    if state.captured_bytes > state.limits.max_captured_bytes {
+        state.limit_exceeded.get_or_insert(LimitKind::CapturedBytes);
+        return String::new();
+    }
+    output
+}
+
 /// Expand all parts of a `Word` into a single string.
 ///
 /// `exec` is an optional callback used to evaluate `$(...)` command
@@ -99,9 +130,9 @@ pub fn expand_words_with_splitting(
 /// Parse a raw assignment value string into a `Word` with proper parts.
 ///
 /// The lexer stores assignment values as raw strings (e.g. `$(echo hi)` is
-/// kept literally). This function scans for `$VAR`, `${...}`, `$(cmd)`, and
-/// `$((expr))` patterns, producing `WordPart` entries so that `expand_word`
-/// properly evaluates substitutions.
+/// kept literally). This function scans for `$VAR`, `${...}`, `$(cmd)`,
+/// `` `cmd` ``, and `$((expr))` patterns, producing `WordPart` entries so that
+/// `expand_word` properly evaluates substitutions.
 pub fn parse_assignment_value(raw: &str) -> Word {
     let chars: Vec<char> = raw.chars().collect();
     let len = chars.len();
@@ -189,10 +220,11 @@ pub fn parse_assignment_value(raw: &str) -> Word {
                 // $VAR -- simple variable
                 let start = i + 1;
                 let mut j = start;
-                // Special single-char variables: $?, $#, $@, $*, $0-$9, $$, $!
+                // Special single-char variables: $?, $#, $@, $*, $0-$9, $$, $!, $-
                 if j < len
                     && (chars[j] == '?'
                         || chars[j] == '#'
+                        || chars[j] == '-'
                         || chars[j] == '@'
                         || chars[j] == '*'
                         || chars[j] == '$'
@@ -214,6 +246,22 @@ pub fn parse_assignment_value(raw: &str) -> Word {
                 }
                 i = j;
             }
+        } else if chars[i] == '`' {
+            // `cmd` -- legacy command substitution; \$, \` and \\ are unescaped
+            if !literal.is_empty() {
+                parts.push(WordPart::Literal(std::mem::take(&mut literal)));
+            }
+            let mut cmd = String::new();
+            let mut j = i + 1;
+            while j < len && chars[j] != '`' {
+                if chars[j] == '\\' && j + 1 < len && matches!(chars[j + 1], '$' | '`' | '\\') {
+                    j += 1;
+                }
+                cmd.push(chars[j]);
+                j += 1;
+            }
+            parts.push(WordPart::CommandSub(cmd));
+            i = j + 1;
         } else {
             literal.push(chars[i]);
             i += 1;
@@ -547,13 +595,8 @@ pub fn expand_word_part(state: &mut ShellState, part: &WordPart, exec: Option<Ex
         WordPart::Variable(name) => expand_variable(state, name),
 
         WordPart::CommandSub(cmd_str) => {
-            if state.substitution_depth >= crate::state::MAX_SUBSTITUTION_DEPTH {
-                return String::new(); // prevent infinite recursion
-            }
             if let Some(exec_fn) = exec {
-                state.substitution_depth += 1;
-                let result = exec_fn(state, cmd_str);
-                state.substitution_depth -= 1;
+                let result = run_substitution(state, exec_fn, cmd_str);
                 // Strip trailing newline (standard shell behavior)
                 result.trim_end_matches('\n').to_string()
             } else {
@@ -615,6 +658,7 @@ fn expand_variable(state: &mut ShellState, name: &str) -> String {
         "!" => return state.last_bg_pid.to_string(),
         "@" | "*" => return state.positional_args.join(" "),
         "#" => return state.positional_args.len().to_string(),
+        "-" => return state.flag_letters(),
         "RANDOM" => return random_u15(state).to_string(),
         "SECONDS" => return "0".to_string(), // placeholder — no start_time yet
         "LINENO" => return "1".to_string(),  // minimum value for shell conformance
@@ -624,7 +668,7 @@ fn expand_variable(state: &mut ShellState, name: &str) -> String {
     // Positional parameters ($0–$9 and beyond)
     if let Ok(idx) = name.parse::<usize>() {
         if idx == 0 {
-            return "codepod-shell".to_string();
+            return state.script_name.clone();
         }
         return state
             .positional_args
@@ -936,10 +980,10 @@ fn expand_param(state: &mut ShellState, var: &str, op: &str, operand: &str) -> S
 
 /// Collect char boundary positions for safe slicing.
 /// Expand `$(cmd)` command substitutions in a raw string (e.g. inside arithmetic).
-fn expand_command_subs_in_string(
+pub fn expand_command_subs_in_string(
     state: &mut ShellState,
     s: &str,
-    exec: Option<&dyn Fn(&mut ShellState, &str) -> String>,
+    exec: Option<ExecFn>,
 ) -> String {
     let exec_fn = match exec {
         Some(f) => f,
@@ -974,12 +1018,8 @@ fn expand_command_subs_in_string(
             if i < bytes.len() {
                 i += 1; // skip )
             }
-            if state.substitution_depth < crate::state::MAX_SUBSTITUTION_DEPTH {
-                state.substitution_depth += 1;
-                let output = exec_fn(state, cmd);
-                state.substitution_depth -= 1;
-                result.push_str(output.trim_end_matches('\n'));
-            }
+            let output = run_substitution(state, exec_fn, cmd);
+            result.push_str(output.trim_end_matches('\n'));
         } else {
             result.push(bytes[i] as char);
             i += 1;
@@ -1449,6 +1489,26 @@ mod tests {
         assert!(val < 32768);
     }
 
+    #[test]
+    fn random_is_seeded_from_the_host() {
+        let host = crate::test_support::mock::MockHost::new();
+        let part = WordPart::Variable("RANDOM".into());
+        let draw = |state: &mut ShellState| {
+            (0..4)
+                .map(|_| expand_word_part(state, &part, None))
+                .collect::<Vec<_>>()
+        };
+        let mut a = test_state();
+        let mut b = test_state();
+        let unseeded = draw(&mut test_state());
+        a.seed_random(&host);
+        b.seed_random(&crate::test_support::mock::MockHost::new());
+        assert_ne!(a.rng_seed, 12345);
+        let seeded = draw(&mut a);
+        assert_eq!(seeded, draw(&mut b));
+        assert_ne!(seeded, unseeded);
+    }
+
     #[test]
     fn positional_param_1() {
         let mut state = test_state();
@@ -2385,4 +2445,22 @@ mod tests {
         let result = expand_globs(&host, &input, "/home/user");
         assert_eq!(result, vec!["echo", "lib.rs", "main.rs", "done"]);
     }
+
+    #[test]
+    fn glob_expansion_walks_the_mock_filesystem() {
+        use crate::test_support::mock::MockHost;
+
+        let host = MockHost::new()
+            .with_file("/src/main.rs", b"")
+            .with_file("/src/.hidden.rs", b"")
+            .with_file("/src/util/io.rs", b"")
+            .with_file("/src/util/io.txt", b"")
+            .with_dir("/src/empty.rs");
+        let input = vec!["*.rs".to_string(), "*/*.rs".to_string(), ".*".to_string()];
+        let result = expand_globs(&host, &input, "/src");
+        assert_eq!(
+            result,
+            vec!["empty.rs", "main.rs", "util/io.rs", ".hidden.rs"]
+        );
+    }
 }
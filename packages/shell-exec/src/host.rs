@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::control::CancelReason;
+
 // ---------------------------------------------------------------------------
 // Types shared between trait and WASM host
 // ---------------------------------------------------------------------------
@@ -9,6 +11,280 @@ pub struct SpawnResult {
     pub exit_code: i32,
 }
 
+/// Per-command resource budget passed to `spawn_with_limits`. `None`
+/// leaves that resource unbounded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpawnLimits {
+    /// Most bytes the command may write to stdout and stderr together.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_bytes: Option<u64>,
+    /// Longest the command may run, in milliseconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_wall_ms: Option<u32>,
+    /// Largest linear memory the command may grow to. Hosts treat this as a
+    /// hint and may round it up to their allocation granularity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl SpawnLimits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Which `SpawnLimits` budget a command ran out of. Each has its own exit
+/// status so callers can tell them apart from ordinary failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnLimitKind {
+    /// Killed for running too long: 124, as with GNU `timeout`.
+    WallTime,
+    /// Killed for writing too much: 153 (`128 + SIGXFSZ`).
+    Output,
+    /// Killed for using too much memory: 137 (`128 + SIGKILL`), as an
+    /// out-of-memory kill reports.
+    Memory,
+}
+
+impl SpawnLimitKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::WallTime => 124,
+            Self::Output => 153,
+            Self::Memory => 137,
+        }
+    }
+
+    /// The budget in `limits` that a child exiting with `exit_code` most
+    /// likely exceeded, if that budget was set.
+    pub fn from_exit(limits: &SpawnLimits, exit_code: i32) -> Option<Self> {
+        [
+            (Self::WallTime, limits.max_wall_ms.is_some()),
+            (Self::Output, limits.max_output_bytes.is_some()),
+            (Self::Memory, limits.max_memory_bytes.is_some()),
+        ]
+        .into_iter()
+        .find(|&(kind, set)| set && kind.exit_code() == exit_code)
+        .map(|(kind, _)| kind)
+    }
+}
+
+impl std::fmt::Display for SpawnLimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::WallTime => "time limit exceeded",
+            Self::Output => "output limit exceeded",
+            Self::Memory => "memory limit exceeded",
+        })
+    }
+}
+
+/// A running child started by `spawn_handle`, for callers that need to
+/// bound how long they wait for it or stop it early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChildHandle {
+    pub pid: i32,
+}
+
+impl ChildHandle {
+    /// Send `signal` to the child. It exits with `128 + signal`, which the
+    /// next wait reports.
+    pub fn kill(&self, host: &dyn HostInterface, signal: i32) -> Result<(), HostError> {
+        host.kill(self.pid, signal)
+    }
+
+    /// Block until the child exits.
+    pub fn wait(&self, host: &dyn HostInterface) -> Result<SpawnResult, HostError> {
+        host.waitpid(self.pid)
+    }
+
+    /// Wait up to `timeout_ms` for the child to exit. Returns `None` if it is
+    /// still running; the handle stays valid for another wait or a kill.
+    pub fn wait_with_timeout(
+        &self,
+        host: &dyn HostInterface,
+        timeout_ms: u32,
+    ) -> Result<Option<SpawnResult>, HostError> {
+        host.waitpid_timeout(self.pid, timeout_ms)
+    }
+}
+
+/// How a watched path changed between two polls of a [`FileWatch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    Created,
+    /// Written to, or replaced, without getting smaller.
+    Modified,
+    /// Got smaller, as when truncated or rewritten with less data.
+    Truncated,
+    Removed,
+}
+
+/// A path being watched for changes, from `HostInterface::watch`. Each poll
+/// compares the path's size and modification time with the previous one,
+/// so it sees writes made by any command in the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileWatch {
+    pub path: String,
+    /// `(size, mtime_ms)` at the last poll; `None` while the path is absent.
+    last: Option<(u64, u64)>,
+}
+
+impl FileWatch {
+    /// Start watching `path`, which need not exist yet.
+    pub fn new<H: HostInterface + ?Sized>(host: &H, path: &str) -> Result<Self, HostError> {
+        Ok(Self {
+            path: path.to_string(),
+            last: Self::observe(host, path)?,
+        })
+    }
+
+    /// Check once, without waiting, for a change since the last poll.
+    pub fn poll<H: HostInterface + ?Sized>(
+        &mut self,
+        host: &H,
+    ) -> Result<Option<FileChange>, HostError> {
+        let now = Self::observe(host, &self.path)?;
+        let change = match (self.last, now) {
+            (None, None) => None,
+            (None, Some(_)) => Some(FileChange::Created),
+            (Some(_), None) => Some(FileChange::Removed),
+            (Some((old_size, _)), Some((size, _))) if size < old_size => {
+                Some(FileChange::Truncated)
+            }
+            (Some(old), Some(new)) if old != new => Some(FileChange::Modified),
+            _ => None,
+        };
+        self.last = now;
+        Ok(change)
+    }
+
+    /// Wait up to `timeout_ms` for the next change; see
+    /// `HostInterface::wait_for_change`.
+    pub fn wait(
+        &mut self,
+        host: &dyn HostInterface,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        host.wait_for_change(self, timeout_ms)
+    }
+
+    fn observe<H: HostInterface + ?Sized>(
+        host: &H,
+        path: &str,
+    ) -> Result<Option<(u64, u64)>, HostError> {
+        match host.stat(path) {
+            Ok(info) if info.exists => Ok(Some((info.size, info.mtime_ms))),
+            Ok(_) | Err(HostError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// A child started by `spawn_duplex`, together with the shell's ends of the
+/// pipes connected to it.
+#[derive(Debug, Clone, Copy)]
+pub struct DuplexChild {
+    pub pid: i32,
+    /// Read end of the pipe carrying the child's stdout.
+    pub read_fd: i32,
+    /// Write end of the pipe feeding the child's stdin.
+    pub write_fd: i32,
+}
+
+/// Which output stream a chunk from a [`StreamingChild`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A child started by `spawn_streaming`. Its stdin, stdout and stderr are
+/// all pipes held by the shell, so input can be fed to it and output
+/// collected while it is still running.
+#[derive(Debug)]
+pub struct StreamingChild {
+    pub pid: i32,
+    /// Write end of the child's stdin, until `close_stdin` is called.
+    pub stdin_fd: Option<i32>,
+    /// Read end of the pipe carrying the child's stdout.
+    pub stdout_fd: i32,
+    /// Read end of the pipe carrying the child's stderr.
+    pub stderr_fd: i32,
+}
+
+impl StreamingChild {
+    /// Send `data` to the child's stdin.
+    pub fn write_stdin(&self, host: &dyn HostInterface, data: &[u8]) -> Result<(), HostError> {
+        match self.stdin_fd {
+            Some(fd) => host.write_fd(fd, data),
+            None => Err(HostError::IoError("stdin already closed".into())),
+        }
+    }
+
+    /// Close the child's stdin so it sees end of input.
+    pub fn close_stdin(&mut self, host: &dyn HostInterface) {
+        if let Some(fd) = self.stdin_fd.take() {
+            let _ = host.close_fd(fd);
+        }
+    }
+
+    /// Pass whatever output the child has produced since the last call to
+    /// `on_output`. Returns the result once the child has exited, after
+    /// which its pipes are closed and the handle should not be used again.
+    pub fn poll(
+        &mut self,
+        host: &dyn HostInterface,
+        on_output: &mut dyn FnMut(OutputStream, &[u8]),
+    ) -> Result<Option<SpawnResult>, HostError> {
+        self.drain(host, on_output)?;
+        let code = host.waitpid_nohang(self.pid)?;
+        if code < 0 {
+            return Ok(None);
+        }
+        // Pick up anything written between the drain and the exit.
+        self.drain(host, on_output)?;
+        self.close_stdin(host);
+        let _ = host.close_fd(self.stdout_fd);
+        let _ = host.close_fd(self.stderr_fd);
+        host.waitpid(self.pid).map(Some)
+    }
+
+    /// Close stdin, then stream output to `on_output` until the child
+    /// exits, yielding to the scheduler between polls.
+    pub fn wait(
+        mut self,
+        host: &dyn HostInterface,
+        on_output: &mut dyn FnMut(OutputStream, &[u8]),
+    ) -> Result<SpawnResult, HostError> {
+        self.close_stdin(host);
+        loop {
+            if let Some(result) = self.poll(host, on_output)? {
+                return Ok(result);
+            }
+            host.yield_now()?;
+        }
+    }
+
+    fn drain(
+        &self,
+        host: &dyn HostInterface,
+        on_output: &mut dyn FnMut(OutputStream, &[u8]),
+    ) -> Result<(), HostError> {
+        for (fd, stream) in [
+            (self.stdout_fd, OutputStream::Stdout),
+            (self.stderr_fd, OutputStream::Stderr),
+        ] {
+            let chunk = host.read_fd(fd)?;
+            if !chunk.is_empty() {
+                on_output(stream, &chunk);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FetchResult {
     pub ok: bool,
@@ -33,16 +309,105 @@ impl FetchResult {
             self.body.as_bytes().to_vec()
         }
     }
+
+    /// A failed fetch carrying `error` and no response.
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            status: 0,
+            headers: Default::default(),
+            body: String::new(),
+            body_base64: None,
+            error: Some(error.into()),
+        }
+    }
+
+    /// The result of a fetch the host does not allow.
+    pub fn denied() -> Self {
+        Self::failed("network access is not enabled")
+    }
 }
 
-/// JSON-encoded fetch request sent to the host via `host_network_fetch`.
-#[cfg(target_arch = "wasm32")]
-#[derive(Serialize)]
-struct FetchRequest<'a> {
-    url: &'a str,
-    method: &'a str,
-    headers: std::collections::HashMap<&'a str, &'a str>,
-    body: Option<&'a str>,
+/// An HTTP request for `HostInterface::fetch`. Sent to the WASM host as
+/// JSON via `host_network_fetch`, with `headers` as an object.
+#[derive(Debug, Clone, Serialize)]
+pub struct FetchRequest<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    #[serde(serialize_with = "headers_as_object")]
+    pub headers: Vec<(&'a str, &'a str)>,
+    pub body: Option<&'a str>,
+}
+
+impl<'a> FetchRequest<'a> {
+    /// A bodyless `GET` of `url`.
+    pub fn get(url: &'a str) -> Self {
+        Self {
+            method: "GET",
+            url,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+}
+
+/// How often the default `wait_for_change` polls a watched path.
+pub const WATCH_POLL_MS: u32 = 50;
+
+/// How many names `create_temp_file` and `create_temp_dir` try.
+const TEMP_ATTEMPTS: u32 = 100;
+
+/// A candidate temporary path: `prefix` in `dir` followed by ten letters
+/// and digits from the host's random source, or from its clock when it
+/// has none.
+fn temp_path<H: HostInterface + ?Sized>(host: &H, dir: &str, prefix: &str, attempt: u32) -> String {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let bytes = host.random_bytes(10).unwrap_or_else(|_| {
+        let mut x = (host.monotonic_ns() ^ ((attempt as u64 + 1) << 32)) | 1;
+        (0..10)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    });
+    let suffix: String = bytes
+        .iter()
+        .map(|&b| CHARS[b as usize % CHARS.len()] as char)
+        .collect();
+    format!("{}/{prefix}{suffix}", dir.trim_end_matches('/'))
+}
+
+fn headers_as_object<S: serde::Serializer>(
+    headers: &[(&str, &str)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(headers.iter().copied())
+}
+
+/// Size of the terminal a stream is attached to, in character cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// How much of the session's storage is in use, from `storage_quota`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageQuota {
+    pub used_bytes: u64,
+    /// The most the session may store, or `None` when it is unbounded.
+    pub limit_bytes: Option<u64>,
+}
+
+impl StorageQuota {
+    /// Bytes that can still be written, or `None` when unbounded.
+    pub fn remaining(&self) -> Option<u64> {
+        self.limit_bytes
+            .map(|limit| limit.saturating_sub(self.used_bytes))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,20 +421,53 @@ pub struct StatInfo {
     pub mtime_ms: u64,
 }
 
+/// One entry of a directory listing from `list_dir`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+}
+
 #[derive(Debug, Clone)]
 pub enum HostError {
     NotFound(String),
     PermissionDenied(String),
+    /// A file operation was applied to a directory.
+    IsADirectory(String),
     IoError(String),
+    /// The host does not implement this call.
+    Unsupported(String),
+    /// The call was cut short, e.g. by cancellation, before it completed.
+    Interrupted(String),
+    /// A write would take the session past its storage quota.
+    QuotaExceeded(String),
     Other(String),
 }
 
+impl HostError {
+    /// The exit status for a command that could not be run because of this
+    /// error: 127 if it does not exist, 126 if it exists but cannot be
+    /// executed, and 1 otherwise.
+    pub fn command_exit_code(&self) -> i32 {
+        match self {
+            Self::NotFound(_) => 127,
+            Self::PermissionDenied(_) | Self::IsADirectory(_) => 126,
+            _ => 1,
+        }
+    }
+}
+
 impl std::fmt::Display for HostError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::NotFound(msg) => write!(f, "{msg}: No such file or directory"),
-            Self::PermissionDenied(msg) => write!(f, "permission denied: {msg}"),
+            Self::PermissionDenied(msg) => write!(f, "{msg}: Permission denied"),
+            Self::IsADirectory(msg) => write!(f, "{msg}: Is a directory"),
             Self::IoError(msg) => write!(f, "I/O error: {msg}"),
+            Self::Unsupported(msg) => write!(f, "{msg}: Operation not supported"),
+            Self::Interrupted(msg) => write!(f, "{msg}: Interrupted"),
+            Self::QuotaExceeded(msg) => write!(f, "{msg}: Disk quota exceeded"),
             Self::Other(msg) => write!(f, "{msg}"),
         }
     }
@@ -107,16 +505,156 @@ pub trait HostInterface {
         nice: u8,
     ) -> Result<i32, HostError>;
 
+    /// `spawn` under a resource budget. Hosts that can enforce the limits
+    /// kill the child when one is exceeded and report the matching
+    /// `SpawnLimitKind` exit status. The default ignores them; the shell
+    /// still applies `max_wall_ms` itself while waiting.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_with_limits(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        let _ = limits;
+        self.spawn(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+        )
+    }
+
+    /// `spawn`, returning a handle that can kill the child or wait for it
+    /// with a timeout.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_handle(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<ChildHandle, HostError> {
+        let pid = self.spawn(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+        )?;
+        Ok(ChildHandle { pid })
+    }
+
+    /// Spawn a command asynchronously with both its stdin and stdout
+    /// connected to the shell through new pipes (used by `coproc`).
+    /// Stderr is inherited.
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError>;
+
+    /// Spawn a command asynchronously with its stdin, stdout and stderr all
+    /// connected to the shell through new pipes, so a long-running command's
+    /// output can be consumed as it is produced rather than after it exits.
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError>;
+
     fn has_tool(&self, name: &str) -> bool;
 
     fn time(&self) -> f64;
 
+    /// Monotonic clock in milliseconds. Only differences between readings are
+    /// meaningful; used to measure how long commands take.
+    fn monotonic_ms(&self) -> f64;
+
+    /// Wall-clock time in whole milliseconds since the Unix epoch. Everything
+    /// that reports the date reads this (by default derived from `time`), so
+    /// a host can pin or simulate it for reproducible runs.
+    fn now_unix_ms(&self) -> u64 {
+        (self.time() * 1000.0) as u64
+    }
+
+    /// The monotonic clock in nanoseconds; see `monotonic_ms`.
+    fn monotonic_ns(&self) -> u64 {
+        (self.monotonic_ms() * 1_000_000.0) as u64
+    }
+
+    /// Poll whether the embedder wants the running script interrupted.
+    /// Checked between pipeline stages, loop iterations and expansions;
+    /// returning `Some` unwinds execution with `ControlFlow::Cancelled`.
+    fn should_cancel(&self) -> Option<CancelReason>;
+
+    /// `n` random bytes from the host. Everything in the shell that needs
+    /// entropy (seeding `$RANDOM`, temporary names) draws it from here, so a
+    /// host with a seeded generator makes runs reproducible. The default
+    /// reports `Unsupported`.
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        let _ = n;
+        Err(HostError::Unsupported("random_bytes".into()))
+    }
+
+    /// Whether `fd` is attached to a terminal rather than a pipe or file.
+    /// Hosts without terminals keep the default, `false`, so output stays
+    /// plain and pipe-friendly.
+    fn isatty(&self, fd: i32) -> bool {
+        let _ = fd;
+        false
+    }
+
+    /// Size of the terminal `fd` is attached to, or `None` when it is not
+    /// a terminal.
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        let _ = fd;
+        None
+    }
+
     fn stat(&self, path: &str) -> Result<StatInfo, HostError>;
 
+    /// Like `stat`, but a symlink is described itself (`is_symlink`, with
+    /// the target's length as its size) rather than followed. The default
+    /// asks `readlink` whether `path` is a link, so it needs no host support
+    /// beyond `stat` and `readlink`.
+    fn lstat(&self, path: &str) -> Result<StatInfo, HostError> {
+        match self.readlink(path) {
+            Ok(target) => Ok(StatInfo {
+                exists: true,
+                is_file: false,
+                is_dir: false,
+                is_symlink: true,
+                size: target.len() as u64,
+                mode: 0o777,
+                mtime_ms: 0,
+            }),
+            Err(_) => self.stat(path),
+        }
+    }
+
     fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError>;
 
+    /// Write `data` to `path`. A write that would take the session past its
+    /// storage quota fails with `QuotaExceeded` and leaves the file as it
+    /// was.
     fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError>;
 
+    /// The session's storage use and limit, or `None` when the host does
+    /// not account for it. The default reports `None`.
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        None
+    }
+
     /// Convenience: read a file as a UTF-8 string.
     fn read_file_str(&self, path: &str) -> Result<String, HostError> {
         let bytes = self.read_file(path)?;
@@ -130,29 +668,265 @@ pub trait HostInterface {
 
     fn readdir(&self, path: &str) -> Result<Vec<String>, HostError>;
 
+    /// List a directory's entries sorted by name, with each entry's kind
+    /// looked up through `stat` (so a link to a directory counts as one)
+    /// and `lstat`.
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>, HostError> {
+        let mut names = self.readdir(path)?;
+        names.sort();
+        let dir = path.trim_end_matches('/');
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let full = format!("{dir}/{name}");
+                DirEntry {
+                    is_dir: self.stat(&full).is_ok_and(|s| s.is_dir),
+                    is_symlink: self.lstat(&full).is_ok_and(|s| s.is_symlink),
+                    name,
+                }
+            })
+            .collect())
+    }
+
     fn mkdir(&self, path: &str) -> Result<(), HostError>;
 
     fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError>;
 
+    /// Create `path` and any missing parent directories, like `mkdir -p`.
+    /// Existing directories along the way are fine; a file in the way is
+    /// an error.
+    fn mkdir_p(&self, path: &str) -> Result<(), HostError> {
+        let mut prefix = String::new();
+        for component in path.split('/').filter(|c| !c.is_empty()) {
+            if !prefix.is_empty() || path.starts_with('/') {
+                prefix.push('/');
+            }
+            prefix.push_str(component);
+            let info = self.stat(&prefix)?;
+            if info.is_dir {
+                continue;
+            }
+            if info.exists {
+                return Err(HostError::IoError(format!("{prefix}: Not a directory")));
+            }
+            self.mkdir(&prefix)?;
+        }
+        Ok(())
+    }
+
+    /// Remove a single file (or empty directory).
+    fn remove_file(&self, path: &str) -> Result<(), HostError> {
+        self.remove(path, false)
+    }
+
+    /// Remove a directory and everything beneath it.
+    fn remove_dir_all(&self, path: &str) -> Result<(), HostError> {
+        self.remove(path, true)
+    }
+
+    /// Copy a file, keeping its mode, or a directory tree to `to`.
+    /// An existing file at the destination is overwritten.
+    fn copy(&self, from: &str, to: &str) -> Result<(), HostError> {
+        let info = self.stat(from)?;
+        if !info.exists {
+            return Err(HostError::NotFound(from.to_string()));
+        }
+        if info.is_dir {
+            self.mkdir_p(to)?;
+            let (from, to) = (from.trim_end_matches('/'), to.trim_end_matches('/'));
+            for entry in self.list_dir(from)? {
+                self.copy(
+                    &format!("{from}/{}", entry.name),
+                    &format!("{to}/{}", entry.name),
+                )?;
+            }
+            return Ok(());
+        }
+        let data = self.read_file(from)?;
+        self.write_file(to, &data, WriteMode::Truncate)?;
+        self.chmod(to, info.mode)
+    }
+
     fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError>;
 
+    /// Set the modification time of `path`, in ms since the Unix epoch.
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError>;
+
+    /// The permission bits of `path` (`0o644` and so on).
+    fn get_mode(&self, path: &str) -> Result<u32, HostError> {
+        let info = self.stat(path)?;
+        if !info.exists {
+            return Err(HostError::NotFound(path.to_string()));
+        }
+        Ok(info.mode & 0o7777)
+    }
+
+    /// Set the permission bits of `path`.
+    fn set_mode(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        self.chmod(path, mode)
+    }
+
+    /// Fail with `PermissionDenied` if `path` exists but its owner write
+    /// bit is clear. Missing files pass, since writing creates them.
+    fn check_writable(&self, path: &str) -> Result<(), HostError> {
+        match self.stat(path) {
+            Ok(info) if info.exists && !info.is_dir && info.mode & 0o200 == 0 => {
+                Err(HostError::PermissionDenied(path.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError>;
 
     fn rename(&self, from: &str, to: &str) -> Result<(), HostError>;
 
+    /// Start watching `path` (which need not exist yet) for changes made by
+    /// any command in the session.
+    fn watch(&self, path: &str) -> Result<FileWatch, HostError> {
+        FileWatch::new(self, path)
+    }
+
+    /// Wait up to `timeout_ms` for `watch` to change, returning how it
+    /// changed, or `None` on timeout or cancellation. The default polls
+    /// `stat` every `WATCH_POLL_MS`, yielding in between; hosts with native
+    /// change notification can block on that instead.
+    fn wait_for_change(
+        &self,
+        watch: &mut FileWatch,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        let mut waited = 0;
+        loop {
+            if let Some(change) = watch.poll(self)? {
+                return Ok(Some(change));
+            }
+            if waited >= timeout_ms || self.should_cancel().is_some() {
+                return Ok(None);
+            }
+            self.yield_now()?;
+            let step = WATCH_POLL_MS.min(timeout_ms - waited);
+            std::thread::sleep(std::time::Duration::from_millis(step.into()));
+            waited += step;
+        }
+    }
+
+    /// Replace the contents of `path` with `data` in one step, so readers
+    /// (and a crash part-way through) see the old file or the new one and
+    /// never a partial write. The default writes a temporary file beside
+    /// `path`, gives it the old file's mode, and renames it over `path`.
+    fn write_file_atomic(&self, path: &str, data: &[u8]) -> Result<(), HostError> {
+        let (dir, name) = match path.rsplit_once('/') {
+            Some(("", name)) => ("/", name),
+            Some((dir, name)) => (dir, name),
+            None => (".", path),
+        };
+        let tmp = self.create_temp_file(dir, &format!(".{name}."))?;
+        let result = self
+            .write_file(&tmp, data, WriteMode::Truncate)
+            .and_then(|_| match self.get_mode(path) {
+                Ok(mode) => self.chmod(&tmp, mode),
+                Err(_) => Ok(()),
+            })
+            .and_then(|_| self.rename(&tmp, path));
+        if result.is_err() {
+            let _ = self.remove(&tmp, false);
+        }
+        result
+    }
+
+    /// Create an empty file in `dir` named `prefix` plus a random suffix,
+    /// returning its path. The default picks names with `random_bytes`
+    /// and skips any that exist; hosts that can create a file exclusively
+    /// should override it to close the gap between the check and the
+    /// create.
+    fn create_temp_file(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        for attempt in 0..TEMP_ATTEMPTS {
+            let path = temp_path(self, dir, prefix, attempt);
+            if !self.lstat(&path).is_ok_and(|s| s.exists) {
+                self.write_file(&path, b"", WriteMode::Truncate)?;
+                return Ok(path);
+            }
+        }
+        Err(HostError::IoError(format!(
+            "{dir}/{prefix}: no unused temporary name"
+        )))
+    }
+
+    /// Create an empty directory in `dir` named `prefix` plus a random
+    /// suffix, returning its path. See `create_temp_file`.
+    fn create_temp_dir(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        for attempt in 0..TEMP_ATTEMPTS {
+            let path = temp_path(self, dir, prefix, attempt);
+            if !self.lstat(&path).is_ok_and(|s| s.exists) {
+                self.mkdir(&path)?;
+                return Ok(path);
+            }
+        }
+        Err(HostError::IoError(format!(
+            "{dir}/{prefix}: no unused temporary name"
+        )))
+    }
+
     fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError>;
 
     fn readlink(&self, path: &str) -> Result<String, HostError>;
 
+    /// The physical form of the absolute path `path`: symlinks are followed
+    /// through `readlink` and `.` and `..` applied to the result. Components
+    /// that do not exist are kept as written. Links are followed at most 40
+    /// deep; a path that loops is returned as resolved so far.
+    fn canonicalize(&self, path: &str) -> String {
+        let mut pending: Vec<String> = path
+            .split('/')
+            .rev()
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+        let mut resolved: Vec<String> = Vec::new();
+        let mut links = 0;
+        while let Some(component) = pending.pop() {
+            match component.as_str() {
+                "." => continue,
+                ".." => {
+                    resolved.pop();
+                    continue;
+                }
+                _ => {}
+            }
+            resolved.push(component);
+            let current = format!("/{}", resolved.join("/"));
+            let Ok(target) = self.readlink(&current) else {
+                continue;
+            };
+            links += 1;
+            if links > 40 {
+                break;
+            }
+            resolved.pop();
+            if target.starts_with('/') {
+                resolved.clear();
+            }
+            pending.extend(
+                target
+                    .split('/')
+                    .rev()
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        format!("/{}", resolved.join("/"))
+    }
+
     /// Perform an HTTP fetch via the host. All arg parsing and response
     /// formatting happens in Rust; only the actual I/O crosses to the host.
-    fn fetch(
-        &self,
-        url: &str,
-        method: &str,
-        headers: &[(&str, &str)],
-        body: Option<&str>,
-    ) -> FetchResult;
+    ///
+    /// Network access is opt-in: hosts that do not override this, or that
+    /// refuse a request, return `FetchResult::denied()`.
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        let _ = request;
+        FetchResult::denied()
+    }
 
     /// Register a pkg-installed tool with the host process manager.
     fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError>;
@@ -168,6 +942,13 @@ pub trait HostInterface {
     /// can verify output without a real fd system.
     fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError>;
 
+    /// Wait up to `timeout_ms` for a child process to exit.
+    /// Returns `None` if it is still running when the time is up.
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError>;
+
+    /// Terminate a child process with `signal`; it exits with `128 + signal`.
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError>;
+
     /// Close a host-side file descriptor.
     fn close_fd(&self, fd: i32) -> Result<(), HostError>;
 
@@ -228,6 +1009,20 @@ extern "C" {
     /// Get current wall-clock time in seconds (f64).
     pub fn host_time() -> f64;
 
+    /// Read the host's monotonic clock, in nanoseconds.
+    pub fn host_monotonic_ns() -> u64;
+
+    /// Poll for a pending cancellation request.
+    /// Returns 0 to keep running, 1 if cancelled, 2 if the deadline passed.
+    pub fn host_should_cancel() -> i32;
+
+    /// Whether `fd` is attached to a terminal. Returns 1 for true, 0 for false.
+    pub fn host_isatty(fd: i32) -> i32;
+
+    /// Size of the terminal `fd` is attached to, packed as
+    /// `cols << 16 | rows`, or 0 when it is not a terminal.
+    pub fn host_terminal_size(fd: i32) -> u32;
+
     /// Stat a path.
     pub fn host_stat(path_ptr: *const u8, path_len: u32, out_ptr: *mut u8, out_cap: u32) -> i32;
 
@@ -261,6 +1056,9 @@ extern "C" {
     /// Set file mode bits.
     pub fn host_chmod(path_ptr: *const u8, path_len: u32, mode: u32) -> i32;
 
+    /// Set a file's modification time, in ms since the Unix epoch.
+    pub fn host_set_mtime(path_ptr: *const u8, path_len: u32, mtime_ms: u64) -> i32;
+
     /// Glob pattern match (JSON array of matching paths).
     pub fn host_glob(
         pattern_ptr: *const u8,
@@ -325,6 +1123,13 @@ extern "C" {
     /// Returns bytes written, or negative error code.
     fn host_waitpid(pid: i32, out_ptr: *mut u8, out_cap: u32) -> i32;
 
+    /// Like `host_waitpid`, but gives up after `timeout_ms` and writes
+    /// `{"timed_out": true}` instead.
+    fn host_waitpid_timeout(pid: i32, timeout_ms: u32, out_ptr: *mut u8, out_cap: u32) -> i32;
+
+    /// Terminate a child process. Returns 0 on success, negative if no such process.
+    fn host_kill(pid: i32, signal: i32) -> i32;
+
     /// Close a host-side file descriptor. Returns 0 on success, negative on error.
     fn host_close_fd(fd: i32) -> i32;
 
@@ -375,13 +1180,18 @@ extern "C" {
 
 /// Convert a negative host return code to a typed `HostError`.
 ///
-/// Convention: -1 = NotFound, -2 = PermissionDenied, -3 = IoError.
-#[cfg(target_arch = "wasm32")]
+/// Convention: -1 = NotFound, -2 = PermissionDenied, -3 = IoError,
+/// -4 = IsADirectory, -5 = Unsupported, -6 = Interrupted.
+#[cfg(any(target_arch = "wasm32", test))]
 fn rc_to_error(rc: i32, context: &str) -> HostError {
     match rc {
         -1 => HostError::NotFound(context.into()),
         -2 => HostError::PermissionDenied(context.into()),
         -3 => HostError::IoError(context.into()),
+        -4 => HostError::IsADirectory(context.into()),
+        -5 => HostError::Unsupported(context.into()),
+        -6 => HostError::Interrupted(context.into()),
+        -7 => HostError::QuotaExceeded(context.into()),
         other => HostError::Other(format!("{context}: host error code {other}")),
     }
 }
@@ -391,18 +1201,19 @@ fn rc_to_error(rc: i32, context: &str) -> HostError {
 // ---------------------------------------------------------------------------
 
 /// Default starting capacity for output buffers.
-#[cfg(target_arch = "wasm32")]
+#[cfg(any(target_arch = "wasm32", test))]
 const DEFAULT_OUTBUF_CAP: usize = 4096;
 
 /// Call a host FFI function that follows the pattern:
 ///   fn(args..., out_ptr, out_cap) -> i32
 /// where a negative return is an error code and a positive return is the
-/// number of bytes written.  Returns the output as a `String`.
+/// number of bytes written.  Returns the output as raw bytes, so binary
+/// file contents and pipe data come through unchanged.
 ///
 /// `context` is used to produce meaningful error messages (typically the
 /// path or operation name).
-#[cfg(target_arch = "wasm32")]
-fn call_with_outbuf<F>(context: &str, f: F) -> Result<String, HostError>
+#[cfg(any(target_arch = "wasm32", test))]
+fn call_with_outbuf_bytes<F>(context: &str, f: F) -> Result<Vec<u8>, HostError>
 where
     F: Fn(*mut u8, u32) -> i32,
 {
@@ -423,9 +1234,96 @@ where
     } else {
         buf.truncate(n);
     }
+    Ok(buf)
+}
+
+/// Like [`call_with_outbuf_bytes`], for host functions whose output is text
+/// (JSON, paths, command lines).  Returns the output as a `String`.
+#[cfg(target_arch = "wasm32")]
+fn call_with_outbuf<F>(context: &str, f: F) -> Result<String, HostError>
+where
+    F: Fn(*mut u8, u32) -> i32,
+{
+    let buf = call_with_outbuf_bytes(context, f)?;
     String::from_utf8(buf).map_err(|e| HostError::Other(format!("invalid UTF-8 from host: {e}")))
 }
 
+// ---------------------------------------------------------------------------
+// Pipe-backed duplex and streaming spawns (wasm32 only)
+// ---------------------------------------------------------------------------
+
+/// `spawn_duplex` for hosts whose pipes stream: an ordinary async spawn
+/// wired to two pipes whose child-side ends the shell then drops.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn_duplex_over_pipes<H: HostInterface + ?Sized>(
+    host: &H,
+    program: &str,
+    args: &[&str],
+    env: &[(&str, &str)],
+    cwd: &str,
+) -> Result<DuplexChild, HostError> {
+    let (child_stdin, write_fd) = host.pipe()?;
+    let (read_fd, child_stdout) = host.pipe()?;
+    let spawned = host.spawn(program, args, env, cwd, "", child_stdin, child_stdout, 2, 0);
+    let _ = host.close_fd(child_stdin);
+    let _ = host.close_fd(child_stdout);
+    match spawned {
+        Ok(pid) => Ok(DuplexChild {
+            pid,
+            read_fd,
+            write_fd,
+        }),
+        Err(e) => {
+            let _ = host.close_fd(read_fd);
+            let _ = host.close_fd(write_fd);
+            Err(e)
+        }
+    }
+}
+
+/// `spawn_streaming` for hosts whose pipes stream, like
+/// [`spawn_duplex_over_pipes`] with a third pipe for stderr.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn_streaming_over_pipes<H: HostInterface + ?Sized>(
+    host: &H,
+    program: &str,
+    args: &[&str],
+    env: &[(&str, &str)],
+    cwd: &str,
+) -> Result<StreamingChild, HostError> {
+    let (child_stdin, stdin_fd) = host.pipe()?;
+    let (stdout_fd, child_stdout) = host.pipe()?;
+    let (stderr_fd, child_stderr) = host.pipe()?;
+    let spawned = host.spawn(
+        program,
+        args,
+        env,
+        cwd,
+        "",
+        child_stdin,
+        child_stdout,
+        child_stderr,
+        0,
+    );
+    for fd in [child_stdin, child_stdout, child_stderr] {
+        let _ = host.close_fd(fd);
+    }
+    match spawned {
+        Ok(pid) => Ok(StreamingChild {
+            pid,
+            stdin_fd: Some(stdin_fd),
+            stdout_fd,
+            stderr_fd,
+        }),
+        Err(e) => {
+            for fd in [stdin_fd, stdout_fd, stderr_fd] {
+                let _ = host.close_fd(fd);
+            }
+            Err(e)
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // WasmHost — production HostInterface bridge (wasm32 only)
 // ---------------------------------------------------------------------------
@@ -433,6 +1331,15 @@ where
 #[cfg(target_arch = "wasm32")]
 pub struct WasmHost;
 
+/// The host this guest binary talks to: [`WasmHost`] over the `codepod`
+/// core imports, or, built with the `component` feature, the
+/// `shell-guest` world's imports.
+#[cfg(all(target_arch = "wasm32", not(feature = "component")))]
+pub use WasmHost as GuestHost;
+
+#[cfg(all(target_arch = "wasm32", feature = "component"))]
+pub use crate::component::ComponentHost as GuestHost;
+
 #[cfg(target_arch = "wasm32")]
 impl HostInterface for WasmHost {
     fn spawn(
@@ -446,6 +1353,33 @@ impl HostInterface for WasmHost {
         stdout_fd: i32,
         stderr_fd: i32,
         nice: u8,
+    ) -> Result<i32, HostError> {
+        self.spawn_with_limits(
+            program,
+            args,
+            env,
+            cwd,
+            stdin_data,
+            stdin_fd,
+            stdout_fd,
+            stderr_fd,
+            nice,
+            &SpawnLimits::default(),
+        )
+    }
+
+    fn spawn_with_limits(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
     ) -> Result<i32, HostError> {
         let mut req = serde_json::json!({
             "prog": program,
@@ -460,17 +1394,37 @@ impl HostInterface for WasmHost {
         if !stdin_data.is_empty() {
             req["stdin_data"] = serde_json::Value::String(stdin_data.to_string());
         }
+        if !limits.is_unlimited() {
+            req["limits"] = serde_json::json!(limits);
+        }
         let req_bytes = req.to_string();
         let pid = unsafe { host_spawn_async(req_bytes.as_ptr(), req_bytes.len() as u32) };
         if pid < 0 {
-            return Err(HostError::IoError(format!(
-                "spawn({}): host error code {}",
-                program, pid
-            )));
+            return Err(rc_to_error(pid, program));
         }
         Ok(pid)
     }
 
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        spawn_duplex_over_pipes(self, program, args, env, cwd)
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        spawn_streaming_over_pipes(self, program, args, env, cwd)
+    }
+
     fn has_tool(&self, name: &str) -> bool {
         unsafe { host_has_tool(name.as_ptr(), name.len() as u32) != 0 }
     }
@@ -479,6 +1433,55 @@ impl HostInterface for WasmHost {
         unsafe { host_time() }
     }
 
+    fn should_cancel(&self) -> Option<CancelReason> {
+        // An interrupt through `__request_cancel` needs no host round trip.
+        if codepod_rpc::cancel::requested() {
+            return Some(CancelReason::Cancelled);
+        }
+        match unsafe { host_should_cancel() } {
+            0 => None,
+            2 => Some(CancelReason::Timeout),
+            _ => Some(CancelReason::Cancelled),
+        }
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        self.monotonic_ns() as f64 / 1_000_000.0
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        unsafe { host_monotonic_ns() }
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        let mut buf = vec![0; n];
+        fill_random(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        unsafe { host_isatty(fd) == 1 }
+    }
+
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        let packed = unsafe { host_terminal_size(fd) };
+        (packed != 0).then_some(TerminalSize {
+            cols: (packed >> 16) as u16,
+            rows: packed as u16,
+        })
+    }
+
+    /// Read from `/proc/diskstats`, which both runtimes serve as JSON with
+    /// `totalBytes` and `limitBytes` (0 or null when there is no limit).
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        let stats: serde_json::Value =
+            serde_json::from_slice(&self.read_file("/proc/diskstats").ok()?).ok()?;
+        Some(StorageQuota {
+            used_bytes: stats["totalBytes"].as_u64()?,
+            limit_bytes: stats["limitBytes"].as_u64().filter(|&limit| limit > 0),
+        })
+    }
+
     fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
         let output = call_with_outbuf(path, |out_ptr, out_cap| unsafe {
             host_stat(path.as_ptr(), path.len() as u32, out_ptr, out_cap)
@@ -487,10 +1490,9 @@ impl HostInterface for WasmHost {
     }
 
     fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
-        let s = call_with_outbuf(path, |out_ptr, out_cap| unsafe {
+        call_with_outbuf_bytes(path, |out_ptr, out_cap| unsafe {
             host_read_file(path.as_ptr(), path.len() as u32, out_ptr, out_cap)
-        })?;
-        Ok(s.into_bytes())
+        })
     }
 
     fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
@@ -555,6 +1557,15 @@ impl HostInterface for WasmHost {
         }
     }
 
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+        let rc = unsafe { host_set_mtime(path.as_ptr(), path.len() as u32, mtime_ms) };
+        if rc < 0 {
+            Err(rc_to_error(rc, path))
+        } else {
+            Ok(())
+        }
+    }
+
     fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
         let output = call_with_outbuf(pattern, |out_ptr, out_cap| unsafe {
             host_glob(pattern.as_ptr(), pattern.len() as u32, out_ptr, out_cap)
@@ -601,52 +1612,21 @@ impl HostInterface for WasmHost {
         })
     }
 
-    fn fetch(
-        &self,
-        url: &str,
-        method: &str,
-        headers: &[(&str, &str)],
-        body: Option<&str>,
-    ) -> FetchResult {
-        let req = FetchRequest {
-            url,
-            method,
-            headers: headers.iter().copied().collect(),
-            body,
-        };
-        let req_json = match serde_json::to_vec(&req) {
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        let req_json = match serde_json::to_vec(request) {
             Ok(j) => j,
             Err(e) => {
-                return FetchResult {
-                    ok: false,
-                    status: 0,
-                    headers: Default::default(),
-                    body: String::new(),
-                    body_base64: None,
-                    error: Some(format!("fetch: failed to serialize request: {e}")),
-                }
+                return FetchResult::failed(format!("fetch: failed to serialize request: {e}"))
             }
         };
         let output = call_with_outbuf("fetch", |out_ptr, out_cap| unsafe {
             host_network_fetch(req_json.as_ptr(), req_json.len() as u32, out_ptr, out_cap)
         });
         match output {
-            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| FetchResult {
-                ok: false,
-                status: 0,
-                headers: Default::default(),
-                body: String::new(),
-                body_base64: None,
-                error: Some(format!("fetch: failed to deserialize response: {e}")),
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                FetchResult::failed(format!("fetch: failed to deserialize response: {e}"))
             }),
-            Err(e) => FetchResult {
-                ok: false,
-                status: 0,
-                headers: Default::default(),
-                body: String::new(),
-                body_base64: None,
-                error: Some(format!("fetch: host error: {e}")),
-            },
+            Err(e) => FetchResult::failed(format!("fetch: host error: {e}")),
         }
     }
 
@@ -690,6 +1670,28 @@ impl HostInterface for WasmHost {
         })
     }
 
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        let result_json = call_with_outbuf("waitpid_timeout", |out_ptr, out_cap| unsafe {
+            host_waitpid_timeout(pid, timeout_ms, out_ptr, out_cap)
+        })?;
+        let parsed: serde_json::Value = serde_json::from_str(&result_json)
+            .map_err(|e| HostError::IoError(format!("waitpid_timeout: {e}")))?;
+        if parsed["timed_out"].as_bool().unwrap_or(false) {
+            return Ok(None);
+        }
+        Ok(Some(SpawnResult {
+            exit_code: parsed["exit_code"].as_i64().unwrap_or(-1) as i32,
+        }))
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        let rc = unsafe { host_kill(pid, signal) };
+        if rc < 0 {
+            return Err(HostError::NotFound(format!("kill: ({pid})")));
+        }
+        Ok(())
+    }
+
     fn close_fd(&self, fd: i32) -> Result<(), HostError> {
         let rc = unsafe { host_close_fd(fd) };
         if rc < 0 {
@@ -726,10 +1728,9 @@ impl HostInterface for WasmHost {
     }
 
     fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
-        let result_str = call_with_outbuf("read_fd", |out_ptr, out_cap| unsafe {
+        call_with_outbuf_bytes("read_fd", |out_ptr, out_cap| unsafe {
             host_read_fd(fd, out_ptr, out_cap)
-        })?;
-        Ok(result_str.into_bytes())
+        })
     }
 
     fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
@@ -850,6 +1851,7 @@ pub fn write_result(result: &crate::control::RunResult) {
 extern "C" {
     fn fd_write(fd: i32, iovs: *const WasiIovec, iovs_len: u32, nwritten: *mut u32) -> u32;
     fn fd_read(fd: i32, iovs: *const WasiIovec, iovs_len: u32, nread: *mut u32) -> u32;
+    fn random_get(buf: *mut u8, buf_len: u32) -> u32;
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -874,6 +1876,17 @@ pub fn write_to_fd(fd: i32, data: &[u8]) -> Result<usize, HostError> {
     Ok(nwritten as usize)
 }
 
+/// Fill `buf` from the host's WASI `random_get`, the same source spawned
+/// tools draw on.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn fill_random(buf: &mut [u8]) -> Result<(), HostError> {
+    let errno = unsafe { random_get(buf.as_mut_ptr(), buf.len() as u32) };
+    if errno != 0 {
+        return Err(HostError::IoError(format!("random_get errno {errno}")));
+    }
+    Ok(())
+}
+
 /// Read from a host file descriptor via WASI `fd_read`.
 #[cfg(target_arch = "wasm32")]
 pub fn read_from_fd(fd: i32, buf: &mut [u8]) -> Result<usize, HostError> {
@@ -889,3 +1902,253 @@ pub fn read_from_fd(fd: i32, buf: &mut [u8]) -> Result<usize, HostError> {
     Ok(nread as usize)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock::{MockHost, MockSpawnOutput};
+
+    #[test]
+    fn streaming_child_delivers_output_by_stream() {
+        let host = MockHost::new().with_spawn_result(
+            "build",
+            MockSpawnOutput {
+                exit_code: 3,
+                stdout: "step 1\nstep 2\n".into(),
+                stderr: "warning\n".into(),
+            },
+        );
+        let child = host.spawn_streaming("build", &[], &[], "/").unwrap();
+        child.write_stdin(&host, b"input\n").unwrap();
+        let pid = child.pid;
+
+        let mut chunks = Vec::new();
+        let result = child
+            .wait(&host, &mut |stream, data| {
+                chunks.push((stream, String::from_utf8_lossy(data).into_owned()))
+            })
+            .unwrap();
+
+        assert_eq!(result.exit_code, 3);
+        assert_eq!(
+            chunks,
+            vec![
+                (OutputStream::Stdout, "step 1\nstep 2\n".to_string()),
+                (OutputStream::Stderr, "warning\n".to_string()),
+            ]
+        );
+        assert_eq!(host.read_duplex_stdin(pid), b"input\n");
+    }
+
+    #[test]
+    fn directory_helpers_build_on_the_primitive_calls() {
+        let host = MockHost::new()
+            .with_file("/src/a.txt", b"a")
+            .with_file("/src/sub/b.txt", b"b")
+            .with_dir("/src")
+            .with_dir("/src/sub");
+
+        host.mkdir_p("/dst/deep/er").unwrap();
+        assert!(host.stat("/dst/deep").unwrap().is_dir);
+        assert!(host.mkdir_p("/src/a.txt/x").is_err());
+
+        host.copy("/src", "/dst/copy").unwrap();
+        assert_eq!(host.get_file("/dst/copy/a.txt").as_deref(), Some("a"));
+        assert_eq!(host.get_file("/dst/copy/sub/b.txt").as_deref(), Some("b"));
+
+        assert!(host.remove_file("/src").is_err());
+        host.remove_dir_all("/src").unwrap();
+        assert!(!host.stat("/src/sub/b.txt").unwrap().exists);
+        assert_eq!(host.get_file("/dst/copy/sub/b.txt").as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn child_handle_waits_with_timeout_and_kills() {
+        let host = MockHost::new().with_spawn_result(
+            "sleep",
+            MockSpawnOutput {
+                exit_code: 0,
+                stdout: String::new(),
+                stderr: String::new(),
+            },
+        );
+        let child = host
+            .spawn_handle("sleep", &["1"], &[], "/", "", 0, 1, 2, 0)
+            .unwrap();
+        let result = child.wait_with_timeout(&host, 10).unwrap();
+        assert_eq!(result.map(|r| r.exit_code), Some(0));
+
+        let child = host
+            .spawn_handle("sleep", &["100"], &[], "/", "", 0, 1, 2, 0)
+            .unwrap();
+        child.kill(&host, 9).unwrap();
+        assert_eq!(child.wait(&host).unwrap().exit_code, 137);
+        assert!(ChildHandle { pid: 999 }.kill(&host, 15).is_err());
+    }
+
+    #[test]
+    fn fetch_is_denied_unless_the_host_serves_it() {
+        let request = FetchRequest {
+            headers: vec![("Accept", "text/plain")],
+            ..FetchRequest::get("https://example.com/a")
+        };
+        let json: serde_json::Value = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["headers"]["Accept"], "text/plain");
+        assert_eq!(json["method"], "GET");
+
+        let host = MockHost::new().with_fetch_result(
+            "https://example.com/a",
+            FetchResult {
+                body: "hi".into(),
+                error: None,
+                ok: true,
+                status: 200,
+                ..FetchResult::denied()
+            },
+        );
+        assert_eq!(host.fetch(&request).body, "hi");
+        let denied = host.fetch(&FetchRequest::get("https://example.com/b"));
+        assert!(!denied.ok);
+        assert_eq!(
+            denied.error.as_deref(),
+            Some("network access is not enabled")
+        );
+    }
+
+    #[test]
+    fn atomic_writes_and_temp_files() {
+        let host = MockHost::new()
+            .with_dir("/tmp")
+            .with_file("/tmp/data", b"old\n");
+        host.chmod("/tmp/data", 0o600).unwrap();
+        host.write_file_atomic("/tmp/data", b"new\n").unwrap();
+        assert_eq!(host.get_file("/tmp/data").as_deref(), Some("new\n"));
+        assert_eq!(host.get_mode("/tmp/data").unwrap(), 0o600);
+        assert_eq!(host.readdir("/tmp").unwrap(), vec!["data".to_string()]);
+
+        let a = host.create_temp_file("/tmp", "tmp.").unwrap();
+        let b = host.create_temp_file("/tmp/", "tmp.").unwrap();
+        assert_ne!(a, b);
+        assert!(a.starts_with("/tmp/tmp.") && a.len() == "/tmp/tmp.".len() + 10);
+        assert_eq!(host.get_file(&a).as_deref(), Some(""));
+        let dir = host.create_temp_dir("/tmp", "d.").unwrap();
+        assert!(host.stat(&dir).unwrap().is_dir);
+    }
+
+    #[test]
+    fn watches_report_each_kind_of_change() {
+        let host = MockHost::new().with_dir("/tmp");
+        let mut watch = host.watch("/tmp/log").unwrap();
+        assert_eq!(watch.poll(&host).unwrap(), None);
+
+        host.write_file("/tmp/log", b"one\n", WriteMode::Truncate)
+            .unwrap();
+        assert_eq!(watch.wait(&host, 0).unwrap(), Some(FileChange::Created));
+        host.write_file("/tmp/log", b"two\n", WriteMode::Truncate)
+            .unwrap();
+        assert_eq!(watch.poll(&host).unwrap(), Some(FileChange::Modified));
+        host.write_file("/tmp/log", b"x", WriteMode::Truncate)
+            .unwrap();
+        assert_eq!(watch.poll(&host).unwrap(), Some(FileChange::Truncated));
+        host.remove("/tmp/log", false).unwrap();
+        assert_eq!(watch.poll(&host).unwrap(), Some(FileChange::Removed));
+
+        // Nothing further happens, so the wait runs out.
+        assert_eq!(watch.wait(&host, 5).unwrap(), None);
+    }
+
+    #[test]
+    fn writes_are_held_to_the_storage_quota() {
+        let host = MockHost::new().with_quota(10);
+        assert_eq!(host.storage_quota().unwrap().remaining(), Some(10));
+
+        host.write_file("/a", b"123456", WriteMode::Truncate)
+            .unwrap();
+        assert_eq!(host.storage_quota().unwrap().remaining(), Some(4));
+        let err = host
+            .write_file("/a", b"12345", WriteMode::Append)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "/a: Disk quota exceeded");
+        assert_eq!(host.get_file("/a").as_deref(), Some("123456"));
+
+        // Replacing a file only counts the difference in size.
+        host.write_file("/a", b"1234567890", WriteMode::Truncate)
+            .unwrap();
+        assert_eq!(host.storage_quota().unwrap().remaining(), Some(0));
+        assert!(host.write_file("/b", b"x", WriteMode::Truncate).is_err());
+
+        let unlimited = StorageQuota {
+            used_bytes: 5,
+            limit_bytes: None,
+        };
+        assert_eq!(unlimited.remaining(), None);
+    }
+
+    #[test]
+    fn mock_filesystem_is_a_tree() {
+        let host = MockHost::new()
+            .with_file("/proj/README", b"hi\n")
+            .with_symlink("/proj/docs", "notes")
+            .with_file("/proj/notes/a.md", b"");
+
+        let names: Vec<_> = host
+            .list_dir("/proj")
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.name, e.is_dir, e.is_symlink))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("README".to_string(), false, false),
+                ("docs".to_string(), true, true),
+                ("notes".to_string(), true, false),
+            ]
+        );
+        assert_eq!(host.readdir("/proj/docs").unwrap(), ["a.md"]);
+
+        host.chmod("/proj/README", 0o600).unwrap();
+        let info = host.stat("/proj/README").unwrap();
+        assert_eq!((info.size, info.mode), (3, 0o600));
+        assert!(host.stat("/proj").unwrap().is_dir);
+
+        assert!(host.mkdir("/proj/notes").is_err());
+        assert!(host.remove("/proj/notes", false).is_err());
+        host.rename("/proj/notes", "/proj/old").unwrap();
+        assert_eq!(host.get_file("/proj/old/a.md").as_deref(), Some(""));
+        host.remove("/proj/old", true).unwrap();
+        assert!(!host.stat("/proj/docs").unwrap().exists);
+        assert!(host.readdir("/proj/README").is_err());
+    }
+
+    /// Stand-in for a host import such as `host_read_file`: copies `data`
+    /// into the guest buffer, or returns the required size when it does not
+    /// fit — the same contract as `writeBytes` on the TypeScript side.
+    fn fake_host_write(data: &[u8], out_ptr: *mut u8, out_cap: u32) -> i32 {
+        if data.len() > out_cap as usize {
+            return data.len() as i32;
+        }
+        unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), out_ptr, data.len()) };
+        data.len() as i32
+    }
+
+    #[test]
+    fn outbuf_bytes_passes_binary_data_through() {
+        let png = [
+            0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0x00, 0xff, 0xfe,
+        ];
+        let got =
+            call_with_outbuf_bytes("img.png", |p, cap| fake_host_write(&png, p, cap)).unwrap();
+        assert_eq!(got, png);
+
+        // Larger than the default buffer: the retry path must keep every byte.
+        let big: Vec<u8> = (0..DEFAULT_OUTBUF_CAP * 3)
+            .map(|i| (i % 256) as u8)
+            .collect();
+        let got =
+            call_with_outbuf_bytes("big.bin", |p, cap| fake_host_write(&big, p, cap)).unwrap();
+        assert_eq!(got, big);
+
+        let err = call_with_outbuf_bytes("missing", |_, _| -1).unwrap_err();
+        assert!(matches!(err, HostError::NotFound(_)));
+    }
+}
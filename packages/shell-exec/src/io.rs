@@ -1,13 +1,24 @@
+use std::sync::OnceLock;
+
+use codepod_rpc::Ring;
+
 /// Write bytes to fd 1.
 ///
-/// On wasm32: uses `print!()` which goes through WASI `fd_write(1)` → kernel.
+/// On wasm32: writes the raw bytes through WASI `fd_write(1)` → kernel, so
+/// binary output is not mangled by UTF-8 conversion.
 /// On native: writes directly to OS fd 1 via `libc::write`, bypassing Rust's
 /// stdout wrapper (which intercepts output during `cargo test`).
 pub fn write_stdout(data: &[u8]) {
+    crate::transcript::record_output(1, data);
     #[cfg(target_arch = "wasm32")]
     {
         // WASI fd_write(1) routes through kernel fd table → correct target.
-        print!("{}", String::from_utf8_lossy(data));
+        // Flush straight away so output interleaves correctly with stderr.
+        use std::io::Write;
+        tee_to_terminal(1, data);
+        let mut stdout = std::io::stdout().lock();
+        let _ = stdout.write_all(data);
+        let _ = stdout.flush();
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -31,9 +42,12 @@ pub fn write_stdout(data: &[u8]) {
 
 /// Write bytes to fd 2.
 pub fn write_stderr(data: &[u8]) {
+    crate::transcript::record_output(2, data);
     #[cfg(target_arch = "wasm32")]
     {
-        eprint!("{}", String::from_utf8_lossy(data));
+        use std::io::Write;
+        tee_to_terminal(2, data);
+        let _ = std::io::stderr().lock().write_all(data);
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -55,6 +69,62 @@ pub fn write_stderr(data: &[u8]) {
     }
 }
 
+/// Write bytes to any of the shell's fds, through the same route as
+/// [`write_stdout`] but without copying them to the output rings.
+pub fn write_to(fd: i32, data: &[u8]) {
+    let mut offset = 0;
+    while offset < data.len() {
+        #[cfg(target_arch = "wasm32")]
+        let n = crate::host::write_to_fd(fd, &data[offset..]).map_or(0, |n| n as isize);
+        #[cfg(not(target_arch = "wasm32"))]
+        let n = unsafe {
+            libc::write(
+                fd,
+                data[offset..].as_ptr() as *const libc::c_void,
+                data[offset..].len(),
+            )
+        };
+        if n <= 0 {
+            break;
+        }
+        offset += n as usize;
+    }
+}
+
+/// Rings the host polls for stdout (index 0) and stderr (index 1) while a
+/// command runs, once it has asked for them with [`enable_output_rings`].
+static OUTPUT_RINGS: OnceLock<[Ring; 2]> = OnceLock::new();
+
+/// Start copying terminal output into rings of `capacity` bytes (rounded
+/// up to a power of two) and return the capacity used. The rings are
+/// created once; later calls return their existing capacity.
+pub fn enable_output_rings(capacity: u32) -> u32 {
+    OUTPUT_RINGS.get_or_init(|| [Ring::new(capacity), Ring::new(capacity)])[0].capacity()
+}
+
+/// The ring for `fd` (1 or 2), if the host has enabled them.
+pub fn output_ring(fd: i32) -> Option<&'static Ring> {
+    let rings = OUTPUT_RINGS.get()?;
+    match fd {
+        1 => Some(&rings[0]),
+        2 => Some(&rings[1]),
+        _ => None,
+    }
+}
+
+/// Copy `data` written to `fd` into its output ring when `fd` is the
+/// terminal. Output that is redirected, piped or captured by a command
+/// substitution is not the terminal's, and stays out of the ring.
+#[cfg(target_arch = "wasm32")]
+fn tee_to_terminal(fd: i32, data: &[u8]) {
+    use crate::host::{GuestHost, HostInterface};
+    if let Some(ring) = output_ring(fd) {
+        if GuestHost.isatty(fd) {
+            ring.push(data);
+        }
+    }
+}
+
 /// Convenience macros for writing formatted output to stdout/stderr via fd.
 #[macro_export]
 macro_rules! shell_print {
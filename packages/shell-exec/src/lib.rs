@@ -1,11 +1,22 @@
+pub mod applets;
 pub mod arithmetic;
+pub mod async_host;
+pub mod audit;
 pub mod builtins;
+#[cfg(all(target_arch = "wasm32", feature = "component"))]
+pub mod component;
 pub mod control;
 pub mod executor;
 pub mod expand;
 pub mod host;
 pub mod io;
+pub mod pip_lite;
+pub mod policy;
+pub mod repl;
+pub mod rpc;
+pub mod spawn_cache;
 pub mod state;
+pub mod transcript;
 pub mod virtual_commands;
 pub mod wheel;
 
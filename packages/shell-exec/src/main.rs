@@ -1,7 +1,20 @@
+use std::alloc::System;
+use std::sync::Mutex;
+
+use codepod_rpc::arena::Arena;
+use codepod_rpc::heap::Tracking;
+
 fn main() {
-    // For wasm32-wasip1: main() is a no-op. The host calls __run_command directly.
-    // main() must exist because the binary target requires it, and wasm32-wasip1
-    // entry point is _start which calls main().
+    // For wasm32-wasip1 the entry point is _start, which calls main(). With no
+    // arguments main() is a no-op: the host initializes the module this way
+    // and then calls __run_command directly. Given a script path, as in
+    // `codepod-shell-exec script.sh ARGS...`, it runs the script and exits
+    // with its status; `codepod-shell-exec -i` starts an interactive session.
+    #[cfg(target_arch = "wasm32")]
+    {
+        codepod_rpc::abi::record_panics();
+        wasm_entry::run_from_args();
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -13,16 +26,60 @@ mod wasm_entry {
     use std::sync::Mutex;
     use std::sync::OnceLock;
 
-    use codepod_shell_exec::control::{ControlFlow, RunResult};
-    use codepod_shell_exec::executor::exec_command;
-    use codepod_shell_exec::host::WasmHost;
+    use codepod_rpc::abi::{self, GuestResult};
+    use codepod_rpc::{decode, encode, Outbox, Request, Response, Streams};
+    use codepod_shell_exec::executor::run_script_file;
+    use codepod_shell_exec::host::GuestHost;
+    use codepod_shell_exec::io;
+    use codepod_shell_exec::repl::{complete, run_repl};
+    use codepod_shell_exec::rpc::{dispatch, run_command};
     use codepod_shell_exec::shell_eprintln;
-    use codepod_shell_exec::state::ShellState;
+    use codepod_shell_exec::state::{SessionSnapshot, ShellState};
 
     static STATE: OnceLock<Mutex<ShellState>> = OnceLock::new();
+    static OUTBOX: Mutex<Outbox> = Mutex::new(Outbox::new());
+    static STREAMS: Mutex<Streams> = Mutex::new(Streams::new());
 
     fn get_state() -> &'static Mutex<ShellState> {
-        STATE.get_or_init(|| Mutex::new(ShellState::new_default()))
+        STATE.get_or_init(|| {
+            let mut state = ShellState::new_default();
+            state.seed_random(&GuestHost);
+            Mutex::new(state)
+        })
+    }
+
+    /// Run the script named by the first command-line argument, passing the
+    /// rest as positional parameters, or an interactive session for `-i`,
+    /// then exit with its status. Returns without doing anything when there
+    /// are no arguments.
+    pub fn run_from_args() {
+        let mut args = std::env::args().skip(1);
+        let Some(path) = args.next() else {
+            return;
+        };
+        let script_args: Vec<String> = args.collect();
+
+        let mut state = get_state().lock().unwrap();
+        // A spawned script sees the environment and directory it was given.
+        state.env.extend(std::env::vars());
+        if let Some(pwd) = state.env.get("PWD").cloned() {
+            state.cwd = pwd;
+        }
+        let code = if path == "-i" {
+            // Read through the shared stdin buffer, line by line, so that
+            // builtins like `read` see whatever the prompt has not consumed.
+            let mut read_line = || {
+                let mut line = String::new();
+                match std::io::stdin().read_line(&mut line) {
+                    Ok(0) | Err(_) => None,
+                    Ok(_) => Some(line),
+                }
+            };
+            run_repl(&mut state, &GuestHost, &mut read_line)
+        } else {
+            run_script_file(&mut state, &GuestHost, &path, &script_args)
+        };
+        std::process::exit(code);
     }
 
     /// Execute a shell command and write the JSON result into the output buffer.
@@ -47,40 +104,297 @@ mod wasm_entry {
         };
 
         let mut state = get_state().lock().unwrap();
-        let host = WasmHost;
+        let output = run_command(&mut state, &GuestHost, cmd_str);
+        match &output.error {
+            Some(diagnostic) => abi::set_last_error(abi::STATUS_ERROR, &diagnostic.message),
+            None => abi::clear_last_error(),
+        }
+        write_output(&serde_json::to_vec(&output).unwrap(), out_ptr, out_cap)
+    }
 
-        // Track command in history
-        state.history.push(cmd_str.to_string());
+    /// Answer the `codepod_rpc` request frame at `req_ptr`, writing the
+    /// response frame into the output buffer. Returns the response's length;
+    /// if that exceeds `out_cap` nothing is written, and a retry of the same
+    /// request with a large enough buffer gets the response without running
+    /// the request again.
+    #[no_mangle]
+    pub extern "C" fn __rpc(
+        req_ptr: *const u8,
+        req_len: u32,
+        out_ptr: *mut u8,
+        out_cap: u32,
+    ) -> i32 {
+        let request = unsafe { std::slice::from_raw_parts(req_ptr, req_len as usize) };
+        let out = unsafe { std::slice::from_raw_parts_mut(out_ptr, out_cap as usize) };
+        let mut outbox = OUTBOX.lock().unwrap();
+        outbox.answer(request, out, |req| {
+            let mut state = get_state().lock().unwrap();
+            dispatch(&mut state, &GuestHost, &mut STREAMS.lock().unwrap(), req)
+        }) as i32
+    }
 
-        let ast = codepod_shell::parser::parse(cmd_str);
-        let result = match exec_command(&mut state, &host, &ast) {
-            Ok(ControlFlow::Normal(r)) => r,
-            Ok(ControlFlow::Exit(code)) => RunResult::exit(code),
-            Ok(_) => RunResult::empty(),
-            Err(e) => {
-                shell_eprintln!("{e}");
-                RunResult::exit(1)
+    /// Answer the `codepod_rpc` request frame at `req_ptr` like `__rpc`, but
+    /// hand the response frame back through a `GuestResult` written to
+    /// `result`, so no buffer needs sizing. The status is `STATUS_ERROR`
+    /// when the response carries an error. Returns the status.
+    #[no_mangle]
+    pub extern "C" fn __rpc_call(
+        req_ptr: *const u8,
+        req_len: u32,
+        result: *mut GuestResult,
+    ) -> i32 {
+        let request = unsafe { std::slice::from_raw_parts(req_ptr, req_len as usize) };
+        let outcome = abi::run_entry(|| {
+            let response = match decode::<Request>(request) {
+                Ok(req) => {
+                    let mut state = get_state().lock().unwrap();
+                    let mut streams = STREAMS.lock().unwrap();
+                    Response::new(req.id, dispatch(&mut state, &GuestHost, &mut streams, &req))
+                }
+                Err(e) => Response::new(0, Err(e)),
+            };
+            match &response.error {
+                Some(e) => Err((encode(&response), e.message.clone())),
+                None => Ok(encode(&response)),
             }
-        };
+        });
+        unsafe { result.write(outcome) };
+        outcome.status
+    }
+
+    /// Copy the message recorded by the last failed call (a shell error from
+    /// `__run_command`, an error from `__rpc_call`, or a panic) into the
+    /// output buffer, with the same sizing protocol as `__run_command`.
+    /// Returns 0 when the last call succeeded.
+    #[no_mangle]
+    pub extern "C" fn __last_error(out_ptr: *mut u8, out_cap: u32) -> i32 {
+        match abi::last_error() {
+            Some((_, message)) => write_output(message.as_bytes(), out_ptr, out_cap),
+            None => 0,
+        }
+    }
 
-        // Fire EXIT trap if one is registered
-        if let Some(trap_cmd) = state.traps.remove("EXIT") {
-            let trap_ast = codepod_shell::parser::parse(&trap_cmd);
-            let _ = exec_command(&mut state, &host, &trap_ast);
+    /// Write the guest's first panic as JSON, `{"message", "file", "line",
+    /// "column"}`, into the output buffer with the same sizing protocol as
+    /// `__run_command`. Returns 0 if the guest has not panicked. A host that
+    /// catches a trap calls this to report why the guest died.
+    #[no_mangle]
+    pub extern "C" fn __panic_info(out_ptr: *mut u8, out_cap: u32) -> i32 {
+        match abi::panic_report() {
+            Some(report) => write_output(&serde_json::to_vec(&report).unwrap(), out_ptr, out_cap),
+            None => 0,
         }
+    }
+
+    /// Append `len` bytes at `ptr` to incoming stream `stream`, which a later
+    /// `__rpc` request names in place of inline data. The host sends a large
+    /// payload by reusing one small buffer for every chunk. Returns 0.
+    #[no_mangle]
+    pub extern "C" fn __write_chunk(stream: u32, ptr: *const u8, len: u32) -> i32 {
+        let chunk = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
+        STREAMS.lock().unwrap().write_chunk(stream, chunk);
+        0
+    }
 
-        // Include env state in result for host sync
-        #[derive(serde::Serialize)]
-        struct WasmOutput {
-            #[serde(flatten)]
-            result: RunResult,
-            env: std::collections::HashMap<String, String>,
+    /// Copy the next piece of outgoing stream `stream` into the output
+    /// buffer and return its length: 0 once the payload has been read (the
+    /// stream is then gone), or -1 for a stream that does not exist.
+    #[no_mangle]
+    pub extern "C" fn __read_chunk(stream: u32, out_ptr: *mut u8, out_cap: u32) -> i32 {
+        let out = unsafe { std::slice::from_raw_parts_mut(out_ptr, out_cap as usize) };
+        match STREAMS.lock().unwrap().read_chunk(stream, out) {
+            Some(n) => n as i32,
+            None => -1,
         }
-        let output = WasmOutput {
-            result,
-            env: state.env.clone(),
+    }
+
+    /// Interrupt the running command at its next cancellation point (between
+    /// pipeline stages, loop iterations and expansions), as if the host had
+    /// cancelled it. Only sets a flag, so the host may call it while another
+    /// export is suspended. Returns 0.
+    #[no_mangle]
+    pub extern "C" fn __request_cancel() -> i32 {
+        codepod_rpc::cancel::request();
+        0
+    }
+
+    /// Return the shell to a fresh session so the host can reuse this
+    /// instance for another user instead of instantiating a new one: the
+    /// session state, held rpc responses and streams are dropped, running
+    /// jobs are killed, and arena buffers are freed. Output rings stay
+    /// enabled. Only valid between calls. Returns 0.
+    #[no_mangle]
+    pub extern "C" fn __reset() -> i32 {
+        get_state().lock().unwrap().reset(&GuestHost);
+        *OUTBOX.lock().unwrap() = Outbox::new();
+        STREAMS.lock().unwrap().clear();
+        codepod_rpc::cancel::clear();
+        abi::clear_last_error();
+        super::__arena_reset();
+        super::HEAP.reset_peak();
+        0
+    }
+
+    /// Start copying terminal output into a ring per stream (1 = stdout,
+    /// 2 = stderr) that the host can poll while a command runs, and return
+    /// the ring capacity: `capacity` rounded up to a power of two, or the
+    /// existing capacity if the rings were already enabled.
+    #[no_mangle]
+    pub extern "C" fn __output_ring_enable(capacity: u32) -> u32 {
+        io::enable_output_rings(capacity)
+    }
+
+    /// Where the data of `fd`'s ring starts, or null before
+    /// `__output_ring_enable`. The byte at position `p` is at offset
+    /// `p % capacity`.
+    #[no_mangle]
+    pub extern "C" fn __output_ring_data(fd: i32) -> *const u8 {
+        io::output_ring(fd).map_or(std::ptr::null(), |ring| ring.data_ptr())
+    }
+
+    /// Position just past the last byte written to `fd`'s ring.
+    #[no_mangle]
+    pub extern "C" fn __output_head(fd: i32) -> u32 {
+        io::output_ring(fd).map_or(0, |ring| ring.head())
+    }
+
+    /// Position of the first byte of `fd`'s ring the host has not taken.
+    #[no_mangle]
+    pub extern "C" fn __output_tail(fd: i32) -> u32 {
+        io::output_ring(fd).map_or(0, |ring| ring.tail())
+    }
+
+    /// Record that the host has taken every byte of `fd`'s ring before
+    /// `tail`, freeing the space. Returns 0, or -1 if `tail` is not between
+    /// the tail and the head.
+    #[no_mangle]
+    pub extern "C" fn __output_set_tail(fd: i32, tail: u32) -> i32 {
+        match io::output_ring(fd) {
+            Some(ring) if ring.set_tail(tail) => 0,
+            _ => -1,
+        }
+    }
+
+    /// Bytes dropped from `fd`'s ring because the host did not drain it in
+    /// time. They still reach the command's output as usual.
+    #[no_mangle]
+    pub extern "C" fn __output_dropped(fd: i32) -> u32 {
+        io::output_ring(fd).map_or(0, |ring| ring.dropped())
+    }
+
+    /// Write the session state (see `SessionSnapshot`) as JSON into the
+    /// output buffer, with the same sizing protocol as `__run_command`.
+    #[no_mangle]
+    pub extern "C" fn __snapshot_state(out_ptr: *mut u8, out_cap: u32) -> i32 {
+        let state = get_state().lock().unwrap();
+        let json = serde_json::to_vec(&state.snapshot()).unwrap();
+        write_output(&json, out_ptr, out_cap)
+    }
+
+    /// Replace the session state with a JSON snapshot produced by
+    /// `__snapshot_state`. Returns 0 on success, or -1 (leaving the state
+    /// untouched) if the snapshot cannot be decoded.
+    #[no_mangle]
+    pub extern "C" fn __restore_state(ptr: *const u8, len: u32) -> i32 {
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, len as usize) };
+        match serde_json::from_slice::<SessionSnapshot>(bytes) {
+            Ok(snapshot) => {
+                get_state().lock().unwrap().restore(snapshot);
+                0
+            }
+            Err(e) => {
+                shell_eprintln!("codepod-shell: invalid session snapshot: {e}");
+                -1
+            }
+        }
+    }
+
+    /// Tab-complete the word at the end of the UTF-8 line at `line_ptr`,
+    /// writing `{"start": <byte offset>, "candidates": [...]}` into the
+    /// output buffer with the same sizing protocol as `__run_command`.
+    #[no_mangle]
+    pub extern "C" fn __complete(
+        line_ptr: *const u8,
+        line_len: u32,
+        out_ptr: *mut u8,
+        out_cap: u32,
+    ) -> i32 {
+        let bytes = unsafe { std::slice::from_raw_parts(line_ptr, line_len as usize) };
+        let line = String::from_utf8_lossy(bytes);
+        let state = get_state().lock().unwrap();
+        let (start, candidates) = complete(&state, &GuestHost, &line);
+        let json = serde_json::json!({ "start": start, "candidates": candidates });
+        write_output(&serde_json::to_vec(&json).unwrap(), out_ptr, out_cap)
+    }
+
+    /// The `shell` export of the `shell-guest` world, over the same
+    /// session as the core exports above.
+    #[cfg(feature = "component")]
+    mod component {
+        use codepod_shell_exec::component::exports::codepod::sandbox::shell::{
+            CommandOutput, Guest,
         };
-        let json = serde_json::to_vec(&output).unwrap();
+
+        use super::*;
+
+        struct Shell;
+
+        impl Guest for Shell {
+            fn run_command(command: String) -> CommandOutput {
+                let mut state = get_state().lock().unwrap();
+                let output = run_command(&mut state, &GuestHost, &command);
+                CommandOutput {
+                    exit_code: output.result.exit_code,
+                    env: output.env.into_iter().collect(),
+                    error: output.error.map(|diagnostic| diagnostic.message),
+                }
+            }
+
+            fn rpc(request: Vec<u8>) -> Vec<u8> {
+                let response = match decode::<Request>(&request) {
+                    Ok(req) => {
+                        let mut state = get_state().lock().unwrap();
+                        let mut streams = STREAMS.lock().unwrap();
+                        Response::new(req.id, dispatch(&mut state, &GuestHost, &mut streams, &req))
+                    }
+                    Err(e) => Response::new(0, Err(e)),
+                };
+                encode(&response)
+            }
+
+            fn complete(line: String) -> (u32, Vec<String>) {
+                let state = get_state().lock().unwrap();
+                let (start, candidates) = complete(&state, &GuestHost, &line);
+                (start as u32, candidates)
+            }
+
+            fn snapshot_state() -> Vec<u8> {
+                let state = get_state().lock().unwrap();
+                serde_json::to_vec(&state.snapshot()).unwrap()
+            }
+
+            fn restore_state(snapshot: Vec<u8>) -> Result<(), String> {
+                let snapshot = serde_json::from_slice::<SessionSnapshot>(&snapshot)
+                    .map_err(|e| format!("invalid session snapshot: {e}"))?;
+                get_state().lock().unwrap().restore(snapshot);
+                Ok(())
+            }
+
+            fn request_cancel() {
+                super::__request_cancel();
+            }
+
+            fn reset() {
+                super::__reset();
+            }
+        }
+
+        codepod_shell_exec::component::export_shell_guest!(Shell);
+    }
+
+    /// Copy `json` into the caller's buffer and return its length, or return
+    /// the required size without writing if the buffer is too small.
+    fn write_output(json: &[u8], out_ptr: *mut u8, out_cap: u32) -> i32 {
         if json.len() > out_cap as usize {
             return json.len() as i32; // signal: need bigger buffer
         }
@@ -95,21 +409,141 @@ mod wasm_entry {
 // WASM allocator exports -- allow the host to allocate/free guest memory
 // ---------------------------------------------------------------------------
 
+/// Buffers for the host, once it has enabled the arena with
+/// `__arena_enable`.
+static ARENA: Mutex<Option<Arena>> = Mutex::new(None);
+
 /// Allocate `size` bytes of guest memory and return the pointer.
 /// Used by the host to prepare buffers before calling into the guest.
 #[no_mangle]
 pub extern "C" fn __alloc(size: u32) -> *mut u8 {
     let layout = std::alloc::Layout::from_size_align(size as usize, 1).unwrap();
+    if let Some(ptr) = ARENA.lock().unwrap().as_mut().and_then(|a| a.alloc(layout)) {
+        return ptr;
+    }
     unsafe { std::alloc::alloc(layout) }
 }
 
-/// Free `size` bytes of guest memory starting at `ptr`.
+/// Free `size` bytes of guest memory starting at `ptr`. Arena buffers are
+/// only reclaimed by `__arena_reset`.
 ///
 /// # Safety
 ///
 /// `ptr` must have been allocated by `__alloc` with the same `size`.
 #[no_mangle]
 pub unsafe extern "C" fn __dealloc(ptr: *mut u8, size: u32) {
+    if ARENA
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|a| a.contains(ptr))
+    {
+        return;
+    }
     let layout = std::alloc::Layout::from_size_align(size as usize, 1).unwrap();
     std::alloc::dealloc(ptr, layout);
 }
+
+/// Serve `__alloc` from a bump arena of `capacity` bytes, falling back to
+/// the heap for requests that do not fit. Returns the arena's capacity,
+/// which is the existing one if the arena was already enabled.
+#[no_mangle]
+pub extern "C" fn __arena_enable(capacity: u32) -> u32 {
+    ARENA
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| Arena::new(capacity as usize))
+        .capacity() as u32
+}
+
+/// Free every arena buffer at once. The host calls this between requests,
+/// when it holds none of them any more.
+#[no_mangle]
+pub extern "C" fn __arena_reset() {
+    if let Some(arena) = ARENA.lock().unwrap().as_mut() {
+        arena.reset();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Heap statistics -- let the host show memory use and enforce soft limits
+// ---------------------------------------------------------------------------
+
+#[global_allocator]
+static HEAP: Tracking<System> = Tracking::new(System);
+
+/// Bytes currently allocated on the guest heap.
+#[no_mangle]
+pub extern "C" fn __heap_used() -> u32 {
+    HEAP.used() as u32
+}
+
+/// The most bytes the guest heap has held since start-up or the last
+/// `__heap_reset_peak`.
+#[no_mangle]
+pub extern "C" fn __heap_peak() -> u32 {
+    HEAP.peak() as u32
+}
+
+/// Restart peak tracking from the current usage, e.g. before a command.
+#[no_mangle]
+pub extern "C" fn __heap_reset_peak() {
+    HEAP.reset_peak();
+}
+
+// ---------------------------------------------------------------------------
+// ABI handshake -- lets the host reject a guest built for another ABI
+// ---------------------------------------------------------------------------
+
+/// Version of the calling convention for the exports in this file. Bump it
+/// whenever an export's signature or buffer layout changes incompatibly.
+const ABI_VERSION: u32 = 1;
+
+/// `__run_command` is exported.
+const CAP_RUN_COMMAND: u32 = 1 << 0;
+/// `__snapshot_state` and `__restore_state` are exported.
+const CAP_SNAPSHOT: u32 = 1 << 1;
+/// `__complete` is exported.
+const CAP_COMPLETE: u32 = 1 << 2;
+/// `__rpc` is exported.
+const CAP_RPC: u32 = 1 << 3;
+/// `__write_chunk` and `__read_chunk` are exported.
+const CAP_STREAMS: u32 = 1 << 4;
+/// `__rpc_call` and `__last_error` are exported.
+const CAP_RESULTS: u32 = 1 << 5;
+/// `__output_ring_enable` and the `__output_*` ring accessors are exported.
+const CAP_OUTPUT_RING: u32 = 1 << 6;
+/// `__request_cancel` is exported.
+const CAP_CANCEL: u32 = 1 << 7;
+/// `__heap_used`, `__heap_peak` and `__heap_reset_peak` are exported.
+const CAP_HEAP_STATS: u32 = 1 << 8;
+/// `__panic_info` is exported.
+const CAP_PANIC_INFO: u32 = 1 << 9;
+/// `__arena_enable` and `__arena_reset` are exported.
+const CAP_ARENA: u32 = 1 << 10;
+/// `__reset` is exported.
+const CAP_RESET: u32 = 1 << 11;
+
+/// Return the guest ABI version. Hosts call this right after instantiation,
+/// before exchanging any buffers, and refuse versions they don't speak.
+#[no_mangle]
+pub extern "C" fn __abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Return a bitmask of the optional exports this build provides.
+#[no_mangle]
+pub extern "C" fn __abi_capabilities() -> u32 {
+    CAP_RUN_COMMAND
+        | CAP_SNAPSHOT
+        | CAP_COMPLETE
+        | CAP_RPC
+        | CAP_STREAMS
+        | CAP_RESULTS
+        | CAP_OUTPUT_RING
+        | CAP_CANCEL
+        | CAP_HEAP_STATS
+        | CAP_PANIC_INFO
+        | CAP_ARENA
+        | CAP_RESET
+}
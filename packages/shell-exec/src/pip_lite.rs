@@ -0,0 +1,1633 @@
+//! pip_lite — pure-Python packages from a simple package index.
+//!
+//! The codepod registry serves the packages that need native code. Any
+//! other package that is pure Python can come straight from an index the
+//! host provides (a PEP 503 "simple" index such as a PyPI mirror): the
+//! newest compatible wheel, or failing that an sdist, is fetched and
+//! unpacked in memory, its `Requires-Dist` dependencies are resolved the
+//! same way, and only then is anything written to site-packages.
+//!
+//! There is no build step. An sdist is installed by copying its Python
+//! packages, which is all a pure-Python build backend would do.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::io::{Cursor, Read};
+
+use sha2::{Digest, Sha256};
+
+use crate::host::{FetchRequest, HostInterface, WriteMode};
+
+/// Where distributions are installed: the PYTHONPATH directory that the
+/// codepod registry's wheels are unpacked into as well.
+pub const SITE_PACKAGES: &str = "/usr/lib/python";
+
+/// The language version RustPython implements, for `Requires-Python`,
+/// wheel tags and environment markers.
+const PYTHON: (u64, u64, u64) = (3, 13, 0);
+
+/// PEP 503 normalised project name: lowercase, with runs of `-`, `_` and
+/// `.` collapsed to a single `-`.
+pub fn normalize(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut separator = false;
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            separator = true;
+            continue;
+        }
+        if separator && !out.is_empty() {
+            out.push('-');
+        }
+        separator = false;
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// The project name at the front of a requirement like `six>=1.16`.
+pub fn project_name(spec: &str) -> &str {
+    let spec = spec.trim_start();
+    let end = spec
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(spec.len());
+    &spec[..end]
+}
+
+// ---------------------------------------------------------------------------
+// Versions and specifiers (PEP 440)
+// ---------------------------------------------------------------------------
+
+/// A PEP 440 version. A local label (`+...`) is ignored.
+#[derive(Debug, Clone)]
+pub struct Version {
+    epoch: u64,
+    release: Vec<u64>,
+    /// `(0 | 1 | 2, n)` for `a`, `b` and `rc` releases.
+    pre: Option<(u8, u64)>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    text: String,
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Version> {
+        let lower = text.trim().to_ascii_lowercase();
+        let s = lower.strip_prefix('v').unwrap_or(&lower);
+        let s = s.split('+').next().unwrap_or_default();
+        let (epoch, mut rest) = match s.split_once('!') {
+            Some((epoch, rest)) => (epoch.parse().ok()?, rest),
+            None => (0, s),
+        };
+
+        let mut release = Vec::new();
+        loop {
+            let digits = leading_digits(rest);
+            if digits == 0 {
+                return None;
+            }
+            release.push(rest[..digits].parse().ok()?);
+            rest = &rest[digits..];
+            match rest.strip_prefix('.') {
+                Some(next) if leading_digits(next) > 0 => rest = next,
+                _ => break,
+            }
+        }
+
+        let pre = take_label(
+            &mut rest,
+            &[
+                ("alpha", 0),
+                ("a", 0),
+                ("beta", 1),
+                ("b", 1),
+                ("rc", 2),
+                ("c", 2),
+                ("preview", 2),
+                ("pre", 2),
+            ],
+        );
+        let mut post = take_label(&mut rest, &[("post", 0), ("rev", 0), ("r", 0)]).map(|(_, n)| n);
+        if post.is_none() {
+            // The implicit form, `1.0-1`.
+            if let Some(next) = rest.strip_prefix('-') {
+                let digits = leading_digits(next);
+                if digits > 0 {
+                    post = Some(next[..digits].parse().ok()?);
+                    rest = &next[digits..];
+                }
+            }
+        }
+        let dev = take_label(&mut rest, &[("dev", 0)]).map(|(_, n)| n);
+        if !rest.is_empty() {
+            return None;
+        }
+        Some(Version {
+            epoch,
+            release,
+            pre,
+            post,
+            dev,
+            text: text.trim().to_string(),
+        })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// The release segments padded with zeros to at least `len`.
+    fn release_padded(&self, len: usize) -> Vec<u64> {
+        let mut release = self.release.clone();
+        release.resize(release.len().max(len), 0);
+        release
+    }
+
+    /// PEP 440 ordering: dev releases before pre-releases before the
+    /// final release before post releases.
+    #[allow(clippy::type_complexity)]
+    fn key(&self) -> (u64, Vec<u64>, (i8, u8, u64), (i8, u64), (i8, u64)) {
+        let mut release = self.release.clone();
+        while release.len() > 1 && release.last() == Some(&0) {
+            release.pop();
+        }
+        let pre = match (self.pre, self.post, self.dev) {
+            (Some((kind, n)), _, _) => (0, kind, n),
+            (None, None, Some(_)) => (-1, 0, 0),
+            _ => (1, 0, 0),
+        };
+        let post = self.post.map_or((-1, 0), |n| (0, n));
+        let dev = self.dev.map_or((1, 0), |n| (0, n));
+        (self.epoch, release, pre, post, dev)
+    }
+}
+
+fn leading_digits(s: &str) -> usize {
+    s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len()
+}
+
+/// Take an optional separator, one of `labels`, and an optional
+/// (optionally separated) number from the front of `rest`.
+fn take_label(rest: &mut &str, labels: &[(&str, u8)]) -> Option<(u8, u64)> {
+    let s = rest.strip_prefix(['-', '_', '.']).unwrap_or(rest);
+    let (label, kind) = labels.iter().find(|(label, _)| s.starts_with(label))?;
+    let s = &s[label.len()..];
+    let number = s.strip_prefix(['-', '_', '.']).unwrap_or(s);
+    let digits = leading_digits(number);
+    let (n, s) = if digits > 0 {
+        (number[..digits].parse().ok()?, &number[digits..])
+    } else {
+        (0, s)
+    };
+    *rest = s;
+    Some((*kind, n))
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// One clause of a version specifier, like `>=1.2` or `==2.*`.
+#[derive(Debug, Clone)]
+pub struct Specifier {
+    op: &'static str,
+    version: String,
+}
+
+impl Specifier {
+    pub fn contains(&self, version: &Version) -> bool {
+        if self.op == "===" {
+            return version.text == self.version;
+        }
+        if let Some(prefix) = self.version.strip_suffix(".*") {
+            let matches = Version::parse(prefix).is_some_and(|prefix| {
+                prefix.epoch == version.epoch
+                    && version.release_padded(prefix.release.len())[..prefix.release.len()]
+                        == prefix.release[..]
+            });
+            return match self.op {
+                "==" => matches,
+                "!=" => !matches,
+                _ => false,
+            };
+        }
+        let Some(spec) = Version::parse(&self.version) else {
+            return false;
+        };
+        let same_release = || {
+            let len = spec.release.len().max(version.release.len());
+            spec.release_padded(len) == version.release_padded(len)
+        };
+        match self.op {
+            "==" => *version == spec,
+            "!=" => *version != spec,
+            "<=" => *version <= spec,
+            ">=" => *version >= spec,
+            // `<1.0` does not admit 1.0's pre-releases, nor `>1.0` its
+            // post releases, unless the specifier is one itself.
+            "<" => {
+                *version < spec
+                    && !(version.is_prerelease() && !spec.is_prerelease() && same_release())
+            }
+            ">" => {
+                *version > spec
+                    && !(version.post.is_some() && spec.post.is_none() && same_release())
+            }
+            "~=" => {
+                let len = spec.release.len();
+                len >= 2
+                    && *version >= spec
+                    && version.epoch == spec.epoch
+                    && version.release_padded(len)[..len - 1] == spec.release[..len - 1]
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Specifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.op, self.version)
+    }
+}
+
+/// Parse a comma-separated specifier set. An empty string allows any
+/// version.
+pub fn parse_specifiers(text: &str) -> Result<Vec<Specifier>, String> {
+    const OPS: [&str; 8] = ["===", "~=", "==", "!=", "<=", ">=", "<", ">"];
+    text.split(',')
+        .map(str::trim)
+        .filter(|clause| !clause.is_empty())
+        .map(|clause| {
+            let op = OPS
+                .iter()
+                .find(|op| clause.starts_with(*op))
+                .ok_or_else(|| format!("invalid version specifier '{clause}'"))?;
+            Ok(Specifier {
+                op,
+                version: clause[op.len()..].trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Requirements and markers (PEP 508)
+// ---------------------------------------------------------------------------
+
+/// A dependency: project name, extras, version specifiers and an
+/// optional environment marker.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    pub name: String,
+    pub extras: Vec<String>,
+    pub specifiers: Vec<Specifier>,
+    pub marker: Option<String>,
+}
+
+impl Requirement {
+    pub fn parse(text: &str) -> Result<Requirement, String> {
+        let (spec, marker) = match text.split_once(';') {
+            Some((spec, marker)) => (spec.trim(), Some(marker.trim().to_string())),
+            None => (text.trim(), None),
+        };
+        if spec.contains('@') {
+            return Err(format!("direct URL requirements are not supported: {text}"));
+        }
+        let name = project_name(spec);
+        if name.is_empty() {
+            return Err(format!("invalid requirement '{text}'"));
+        }
+        let mut rest = spec[name.len()..].trim_start();
+        let mut extras = Vec::new();
+        if let Some(inner) = rest.strip_prefix('[') {
+            let (list, after) = inner
+                .split_once(']')
+                .ok_or_else(|| format!("invalid requirement '{text}'"))?;
+            extras = list
+                .split(',')
+                .map(str::trim)
+                .filter(|extra| !extra.is_empty())
+                .map(normalize)
+                .collect();
+            rest = after;
+        }
+        // Older metadata writes `name (>=1.0)`.
+        let rest = rest.trim().trim_start_matches('(').trim_end_matches(')');
+        Ok(Requirement {
+            name: name.to_string(),
+            extras,
+            specifiers: parse_specifiers(rest)?,
+            marker,
+        })
+    }
+
+    /// Whether `version` satisfies every specifier.
+    pub fn allows(&self, version: &Version) -> bool {
+        self.specifiers.iter().all(|spec| spec.contains(version))
+    }
+
+    /// Whether the marker holds in the sandbox, for a dependent
+    /// installed with `extras`.
+    pub fn applies(&self, extras: &[String]) -> bool {
+        self.marker
+            .as_deref()
+            .is_none_or(|marker| evaluate_marker(marker, extras))
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if !self.extras.is_empty() {
+            write!(f, "[{}]", self.extras.join(","))?;
+        }
+        let specifiers: Vec<String> = self.specifiers.iter().map(|s| s.to_string()).collect();
+        f.write_str(&specifiers.join(","))
+    }
+}
+
+/// The value of a marker variable in the sandbox.
+fn marker_variable(name: &str) -> Option<String> {
+    let (major, minor, micro) = PYTHON;
+    Some(match name {
+        "python_version" => format!("{major}.{minor}"),
+        "python_full_version" | "implementation_version" => format!("{major}.{minor}.{micro}"),
+        "os_name" => "posix".into(),
+        "sys_platform" => "wasi".into(),
+        "platform_machine" => "wasm32".into(),
+        "platform_python_implementation" => "RustPython".into(),
+        "implementation_name" => "rustpython".into(),
+        "platform_system" | "platform_release" | "platform_version" => String::new(),
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Str(String),
+    Word(String),
+    Op(String),
+}
+
+fn tokenize(marker: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = marker.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let s: String = chars.by_ref().take_while(|&ch| ch != c).collect();
+                tokens.push(Token::Str(s));
+            }
+            '<' | '>' | '=' | '!' | '~' => {
+                let mut op = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !matches!(ch, '<' | '>' | '=' | '!' | '~') {
+                        break;
+                    }
+                    op.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '.') {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+/// Recursive-descent evaluation of a tokenized marker.
+struct MarkerParser<'a> {
+    tokens: Vec<Token>,
+    at: usize,
+    extras: &'a [String],
+}
+
+impl MarkerParser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        token
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.tokens.get(self.at) == Some(&Token::Word(word.to_string())) {
+            self.at += 1;
+            return true;
+        }
+        false
+    }
+
+    fn or_expr(&mut self) -> Option<bool> {
+        let mut value = self.and_expr()?;
+        while self.eat_word("or") {
+            value |= self.and_expr()?;
+        }
+        Some(value)
+    }
+
+    fn and_expr(&mut self) -> Option<bool> {
+        let mut value = self.atom()?;
+        while self.eat_word("and") {
+            value &= self.atom()?;
+        }
+        Some(value)
+    }
+
+    fn atom(&mut self) -> Option<bool> {
+        if self.tokens.get(self.at) == Some(&Token::Open) {
+            self.at += 1;
+            let value = self.or_expr()?;
+            return (self.next()? == Token::Close).then_some(value);
+        }
+        let left = self.next()?;
+        let op = match self.next()? {
+            Token::Op(op) => op,
+            Token::Word(word) if word == "in" => word,
+            Token::Word(word) if word == "not" && self.eat_word("in") => "not in".into(),
+            _ => return None,
+        };
+        let right = self.next()?;
+        Some(self.compare(left, &op, right))
+    }
+
+    fn compare(&self, left: Token, op: &str, right: Token) -> bool {
+        // `extra` matches against the extras the dependent asked for.
+        match (&left, &right) {
+            (Token::Word(var), Token::Str(value)) | (Token::Str(value), Token::Word(var))
+                if var == "extra" =>
+            {
+                let wanted = self.extras.contains(&normalize(value));
+                return match op {
+                    "==" => wanted,
+                    "!=" => !wanted,
+                    _ => false,
+                };
+            }
+            _ => {}
+        }
+        let value = |token: Token| match token {
+            Token::Str(s) => Some(s),
+            Token::Word(var) => marker_variable(&var),
+            _ => None,
+        };
+        let (Some(left), Some(right)) = (value(left), value(right)) else {
+            return false;
+        };
+        match op {
+            "in" => right.contains(&left),
+            "not in" => !right.contains(&left),
+            _ => match (
+                Version::parse(&left),
+                parse_specifiers(&format!("{op}{right}")),
+            ) {
+                (Some(version), Ok(specifiers)) if Version::parse(&right).is_some() => {
+                    specifiers.iter().all(|spec| spec.contains(&version))
+                }
+                _ => match op {
+                    "==" | "===" => left == right,
+                    "!=" => left != right,
+                    _ => false,
+                },
+            },
+        }
+    }
+}
+
+/// Evaluate a PEP 508 environment marker for the sandbox's interpreter,
+/// with `extra` matching any of `extras`. A marker that does not parse
+/// is false.
+pub fn evaluate_marker(marker: &str, extras: &[String]) -> bool {
+    let Some(tokens) = tokenize(marker) else {
+        return false;
+    };
+    let len = tokens.len();
+    let mut parser = MarkerParser {
+        tokens,
+        at: 0,
+        extras,
+    };
+    parser
+        .or_expr()
+        .filter(|_| parser.at == len)
+        .unwrap_or(false)
+}
+
+// ---------------------------------------------------------------------------
+// Index pages (PEP 503)
+// ---------------------------------------------------------------------------
+
+/// A file listed on a project's index page.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub filename: String,
+    pub url: String,
+    pub sha256: Option<String>,
+    pub requires_python: Option<String>,
+    pub yanked: bool,
+}
+
+/// The `<a>` links of a simple index page fetched from `page_url`.
+pub fn parse_index_page(html: &str, page_url: &str) -> Vec<Link> {
+    let lower = html.to_ascii_lowercase();
+    let mut links = Vec::new();
+    let mut from = 0;
+    while let Some(start) = lower[from..].find("<a ").map(|i| i + from) {
+        let Some(tag_end) = lower[start..].find('>').map(|i| i + start) else {
+            break;
+        };
+        let text_end = lower[tag_end..]
+            .find("</a>")
+            .map_or(html.len(), |i| i + tag_end);
+        let tag = &html[start..tag_end];
+        let text = unescape(html[tag_end + 1..text_end].trim());
+        from = text_end;
+
+        let Some(href) = attribute(tag, "href") else {
+            continue;
+        };
+        let href = unescape(&href);
+        let (href, fragment) = href.split_once('#').unwrap_or((&href, ""));
+        let url = join_url(page_url, href);
+        let filename = if text.is_empty() {
+            url.rsplit('/').next().unwrap_or_default().to_string()
+        } else {
+            text
+        };
+        links.push(Link {
+            filename,
+            url,
+            sha256: fragment.strip_prefix("sha256=").map(str::to_string),
+            requires_python: attribute(tag, "data-requires-python").map(|v| unescape(&v)),
+            yanked: attribute(tag, "data-yanked").is_some(),
+        });
+    }
+    links
+}
+
+/// The value of attribute `name` in an HTML start tag; empty for a bare
+/// attribute.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(at) = lower[from..].find(name).map(|i| i + from) {
+        from = at + name.len();
+        if !lower[..at].ends_with(|c: char| c.is_ascii_whitespace()) {
+            continue;
+        }
+        let after = &tag[at + name.len()..];
+        if let Some(value) = after.trim_start().strip_prefix('=') {
+            let value = value.trim_start();
+            return Some(match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => value[1..]
+                    .split(quote)
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                _ => value
+                    .split(|c: char| c.is_ascii_whitespace())
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+        if after.is_empty() || after.starts_with(|c: char| c.is_ascii_whitespace() || c == '/') {
+            return Some(String::new());
+        }
+    }
+    None
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Resolve `href` against the page it appears on.
+fn join_url(base: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+    let scheme_end = base.find("://").map_or(0, |i| i + 3);
+    if let Some(rest) = href.strip_prefix("//") {
+        return format!("{}{rest}", &base[..scheme_end]);
+    }
+    let origin_end = base[scheme_end..]
+        .find('/')
+        .map_or(base.len(), |i| i + scheme_end);
+    let path = if href.starts_with('/') {
+        href.to_string()
+    } else {
+        let base_path = &base[origin_end..];
+        let dir = &base_path[..base_path.rfind('/').map_or(0, |i| i + 1)];
+        format!("{dir}{href}")
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let mut url = format!("{}/{}", &base[..origin_end], segments.join("/"));
+    if path.ends_with('/') && !segments.is_empty() {
+        url.push('/');
+    }
+    url
+}
+
+// ---------------------------------------------------------------------------
+// Choosing a file
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Wheel,
+    Sdist,
+}
+
+#[derive(Debug)]
+struct Candidate<'a> {
+    link: &'a Link,
+    version: Version,
+    kind: Kind,
+}
+
+/// The version of `project` in `link`, if the file is a wheel that runs
+/// on any platform under Python 3, or an sdist.
+fn candidate<'a>(link: &'a Link, project: &str) -> Option<Candidate<'a>> {
+    let filename = link.filename.as_str();
+    if let Some(stem) = filename.strip_suffix(".whl") {
+        // name-version(-build)?-python-abi-platform
+        let parts: Vec<&str> = stem.split('-').collect();
+        if !(5..=6).contains(&parts.len()) || normalize(parts[0]) != project {
+            return None;
+        }
+        let n = parts.len();
+        let (python, abi, platform) = (parts[n - 3], parts[n - 2], parts[n - 1]);
+        let runs = python.split('.').any(|tag| {
+            tag == "py3"
+                || tag
+                    .strip_prefix("py3")
+                    .and_then(|minor| minor.parse::<u64>().ok())
+                    .is_some_and(|minor| minor <= PYTHON.1)
+        });
+        if abi != "none" || platform != "any" || !runs {
+            return None;
+        }
+        return Some(Candidate {
+            link,
+            version: Version::parse(parts[1])?,
+            kind: Kind::Wheel,
+        });
+    }
+    let stem = filename
+        .strip_suffix(".tar.gz")
+        .or_else(|| filename.strip_suffix(".zip"))?;
+    // The name itself may contain dashes; the version follows the dash
+    // at which the prefix normalises to the project name.
+    let split = stem
+        .match_indices('-')
+        .map(|(i, _)| i)
+        .find(|&i| normalize(&stem[..i]) == project)?;
+    Some(Candidate {
+        link,
+        version: Version::parse(&stem[split + 1..])?,
+        kind: Kind::Sdist,
+    })
+}
+
+/// The newest file in `links` that satisfies `requirement` and supports
+/// the sandbox's Python, preferring a wheel to an sdist of the same
+/// version. Pre-releases are only chosen when nothing else matches.
+fn select<'a>(links: &'a [Link], requirement: &Requirement) -> Option<Candidate<'a>> {
+    let (major, minor, micro) = PYTHON;
+    let python = Version::parse(&format!("{major}.{minor}.{micro}"))?;
+    let project = normalize(&requirement.name);
+    let candidates: Vec<Candidate> = links
+        .iter()
+        .filter(|link| !link.yanked)
+        .filter(|link| {
+            link.requires_python.as_deref().is_none_or(|spec| {
+                parse_specifiers(spec).is_ok_and(|specs| specs.iter().all(|s| s.contains(&python)))
+            })
+        })
+        .filter_map(|link| candidate(link, &project))
+        .filter(|c| requirement.allows(&c.version))
+        .collect();
+    let finals = candidates.iter().any(|c| !c.version.is_prerelease());
+    candidates
+        .into_iter()
+        .filter(|c| !finals || !c.version.is_prerelease())
+        .max_by(|a, b| {
+            a.version
+                .cmp(&b.version)
+                .then((a.kind == Kind::Wheel).cmp(&(b.kind == Kind::Wheel)))
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Distributions
+// ---------------------------------------------------------------------------
+
+/// A downloaded distribution, unpacked in memory and ready to install.
+#[derive(Debug)]
+pub struct Dist {
+    pub name: String,
+    pub version: String,
+    /// The file it came from.
+    pub filename: String,
+    /// `name-version.dist-info`, relative to site-packages.
+    pub dist_info: String,
+    /// Paths relative to site-packages, with their contents.
+    pub files: Vec<(String, Vec<u8>)>,
+    pub requires: Vec<Requirement>,
+    /// Files of an sdist that were left out, such as C sources.
+    pub skipped: Vec<String>,
+}
+
+/// The headers of a METADATA or PKG-INFO file, in order.
+fn parse_metadata(text: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.as_str())
+}
+
+fn requires_dist(headers: &[(String, String)]) -> Result<Vec<Requirement>, String> {
+    headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("Requires-Dist"))
+        .map(|(_, v)| Requirement::parse(v))
+        .collect()
+}
+
+/// Reject archive paths that would land outside site-packages.
+fn check_path(path: &str) -> Result<(), String> {
+    if path.starts_with('/') || path.contains('\\') || path.split('/').any(|s| s == "..") {
+        return Err(format!("unsafe path in archive: {path}"));
+    }
+    Ok(())
+}
+
+fn read_zip(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).map_err(|e| format!("invalid zip: {e}"))?;
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("zip entry {i}: {e}"))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| format!("reading {name}: {e}"))?;
+        files.push((name, content));
+    }
+    Ok(files)
+}
+
+fn read_tar_gz(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data));
+    let mut files = Vec::new();
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("invalid tar.gz: {e}"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("invalid tar.gz: {e}"))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .map_err(|e| format!("invalid tar.gz: {e}"))?
+            .to_string_lossy()
+            .into_owned();
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| format!("reading {name}: {e}"))?;
+        files.push((name, content));
+    }
+    Ok(files)
+}
+
+/// Unpack a wheel. `.data/purelib` and `.data/platlib` contents install
+/// alongside the packages; scripts, headers and data have nowhere to go.
+fn dist_from_wheel(filename: &str, data: &[u8]) -> Result<Dist, String> {
+    let mut files = Vec::new();
+    for (name, content) in read_zip(data)? {
+        if name.contains("__pycache__/") {
+            continue;
+        }
+        let (top, rest) = name.split_once('/').unwrap_or(("", &name));
+        let path = if top.ends_with(".data") {
+            match rest.split_once('/') {
+                Some(("purelib" | "platlib", inner)) => inner.to_string(),
+                _ => continue,
+            }
+        } else {
+            name.clone()
+        };
+        check_path(&path)?;
+        files.push((path, content));
+    }
+
+    let (dist_info, metadata) = files
+        .iter()
+        .find_map(|(path, content)| {
+            let dir = path.strip_suffix("/METADATA")?;
+            (dir.ends_with(".dist-info") && !dir.contains('/')).then(|| {
+                (
+                    dir.to_string(),
+                    String::from_utf8_lossy(content).into_owned(),
+                )
+            })
+        })
+        .ok_or_else(|| format!("{filename} has no .dist-info/METADATA"))?;
+    // The wheel's RECORD is replaced by one of what was installed.
+    let record = format!("{dist_info}/RECORD");
+    files.retain(|(path, _)| *path != record);
+
+    let headers = parse_metadata(&metadata);
+    Ok(Dist {
+        name: header(&headers, "Name").unwrap_or_default().to_string(),
+        version: header(&headers, "Version").unwrap_or_default().to_string(),
+        filename: filename.to_string(),
+        dist_info,
+        files,
+        requires: requires_dist(&headers)?,
+        skipped: Vec::new(),
+    })
+}
+
+/// Directories and modules at the top of a source tree that are never
+/// part of the installed package.
+const SOURCE_TREE_EXTRAS: &[&str] = &[
+    "tests",
+    "test",
+    "testing",
+    "docs",
+    "doc",
+    "examples",
+    "benchmarks",
+    "scripts",
+    "setup.py",
+    "conftest.py",
+    "noxfile.py",
+];
+
+/// "Build" an sdist by copying its Python packages, the way a
+/// pure-Python build backend would. The packages are the ones named in
+/// `*.egg-info/top_level.txt` when there is one, otherwise every package
+/// and module at the top of `src/` or of the project directory.
+fn dist_from_sdist(filename: &str, data: &[u8]) -> Result<Dist, String> {
+    let entries = if filename.ends_with(".zip") {
+        read_zip(data)?
+    } else {
+        read_tar_gz(data)?
+    };
+    // Everything sits in one `name-version/` directory.
+    let entries: Vec<(String, Vec<u8>)> = entries
+        .into_iter()
+        .filter_map(|(path, content)| {
+            let (_, inner) = path.split_once('/')?;
+            Some((inner.to_string(), content))
+        })
+        .collect();
+    let file = |path: &str| {
+        entries
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, content)| String::from_utf8_lossy(content).into_owned())
+    };
+
+    let pkg_info = file("PKG-INFO").ok_or_else(|| format!("{filename} has no PKG-INFO"))?;
+    let headers = parse_metadata(&pkg_info);
+    let name = header(&headers, "Name").unwrap_or_default().to_string();
+    let version = header(&headers, "Version").unwrap_or_default().to_string();
+
+    let root = if entries
+        .iter()
+        .any(|(p, _)| p.starts_with("src/") && p.ends_with(".py"))
+    {
+        "src/"
+    } else {
+        ""
+    };
+    let egg_info = |suffix: &str| {
+        entries.iter().find_map(|(p, content)| {
+            let (dir, file) = p.strip_prefix(root).unwrap_or(p).rsplit_once('/')?;
+            (dir.ends_with(".egg-info") && !dir.contains('/') && file == suffix)
+                .then(|| String::from_utf8_lossy(content).into_owned())
+        })
+    };
+
+    let top_level: Option<HashSet<String>> = egg_info("top_level.txt").map(|text| {
+        text.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect()
+    });
+    let wanted = |first: &str, is_module: bool| match &top_level {
+        Some(names) => {
+            let stem = first.strip_suffix(".py").unwrap_or(first);
+            names.contains(stem) && (is_module == first.ends_with(".py"))
+        }
+        None => !SOURCE_TREE_EXTRAS.contains(&first),
+    };
+
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for (path, content) in &entries {
+        let Some(inner) = path.strip_prefix(root) else {
+            continue;
+        };
+        let (first, is_module) = match inner.split_once('/') {
+            // A package needs its __init__.py at the top.
+            Some((dir, _)) => {
+                let init = format!("{root}{dir}/__init__.py");
+                if !entries.iter().any(|(p, _)| *p == init) {
+                    continue;
+                }
+                (dir, false)
+            }
+            None if inner.ends_with(".py") => (inner, true),
+            None => continue,
+        };
+        if !wanted(first, is_module) || inner.contains("__pycache__/") || inner.ends_with(".pyc") {
+            continue;
+        }
+        if [".c", ".h", ".cpp", ".pyx", ".pxd", ".rs"]
+            .iter()
+            .any(|ext| inner.ends_with(ext))
+        {
+            skipped.push(inner.to_string());
+            continue;
+        }
+        check_path(inner)?;
+        files.push((inner.to_string(), content.clone()));
+    }
+    if files.is_empty() {
+        return Err(format!("no Python packages found in {filename}"));
+    }
+
+    // Metadata 2.2+ puts dependencies in PKG-INFO; older sdists only have
+    // setuptools' requires.txt.
+    let mut requires = requires_dist(&headers)?;
+    if requires.is_empty() {
+        if let Some(text) = egg_info("requires.txt") {
+            requires = parse_requires_txt(&text)?;
+        }
+    }
+
+    let dist_info = format!("{}-{version}.dist-info", name.replace('-', "_"));
+    files.push((format!("{dist_info}/METADATA"), pkg_info.into_bytes()));
+    Ok(Dist {
+        name,
+        version,
+        filename: filename.to_string(),
+        dist_info,
+        files,
+        requires,
+        skipped,
+    })
+}
+
+/// setuptools' `requires.txt`: plain requirements, then `[extra]`,
+/// `[:marker]` or `[extra:marker]` sections.
+fn parse_requires_txt(text: &str) -> Result<Vec<Requirement>, String> {
+    let mut requires = Vec::new();
+    let mut section: Option<String> = None;
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(inner) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let (extra, marker) = inner.split_once(':').unwrap_or((inner, ""));
+            let mut clauses = Vec::new();
+            if !extra.is_empty() {
+                clauses.push(format!("extra == \"{extra}\""));
+            }
+            if !marker.is_empty() {
+                clauses.push(format!("({marker})"));
+            }
+            section = (!clauses.is_empty()).then(|| clauses.join(" and "));
+            continue;
+        }
+        let mut requirement = Requirement::parse(line)?;
+        requirement.marker = section.clone();
+        requires.push(requirement);
+    }
+    Ok(requires)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn fetch_failure(url: &str, result: &crate::host::FetchResult) -> String {
+    match &result.error {
+        Some(err) => format!("failed to fetch {url}: {err}"),
+        None => format!("failed to fetch {url}: status {}", result.status),
+    }
+}
+
+/// Find, download and unpack the distribution that best satisfies
+/// `requirement` from the index at `index_url`.
+pub fn fetch_dist(
+    host: &dyn HostInterface,
+    index_url: &str,
+    requirement: &Requirement,
+) -> Result<Dist, String> {
+    let not_found =
+        || format!("Could not find a version that satisfies the requirement {requirement}");
+    let page_url = format!(
+        "{}/{}/",
+        index_url.trim_end_matches('/'),
+        normalize(&requirement.name)
+    );
+    let page = host.fetch(&FetchRequest {
+        headers: vec![("Accept", "text/html")],
+        ..FetchRequest::get(&page_url)
+    });
+    if page.status == 404 {
+        return Err(not_found());
+    }
+    if page.error.is_some() || !page.ok {
+        return Err(fetch_failure(&page_url, &page));
+    }
+
+    let links = parse_index_page(&page.body, &page_url);
+    let chosen = select(&links, requirement).ok_or_else(not_found)?;
+    let link = chosen.link;
+    let result = host.fetch(&FetchRequest::get(&link.url));
+    if result.error.is_some() || !result.ok {
+        return Err(fetch_failure(&link.url, &result));
+    }
+    let data = result.body_bytes();
+    if let Some(expected) = &link.sha256 {
+        let actual = hex(&Sha256::digest(&data));
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!(
+                "hash mismatch for {}: expected sha256 {expected}, got {actual}",
+                link.filename
+            ));
+        }
+    }
+    match chosen.kind {
+        Kind::Wheel => dist_from_wheel(&link.filename, &data),
+        Kind::Sdist => dist_from_sdist(&link.filename, &data),
+    }
+}
+
+/// How a project outside the index is already provided.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Provided {
+    /// Built in or installed.
+    Installed,
+    /// Served by the codepod registry under this name.
+    Registry(String),
+}
+
+/// The distributions `resolve` chose.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    /// From the index, in the order they were collected.
+    pub dists: Vec<Dist>,
+    /// Dependencies the codepod registry serves, for the caller to
+    /// install from there.
+    pub registry: Vec<String>,
+}
+
+/// Collect `requirements` and everything they depend on from the index.
+/// Projects `provided` knows about are not fetched. The first
+/// requirement seen for a project decides its version.
+pub fn resolve(
+    host: &dyn HostInterface,
+    index_url: &str,
+    requirements: &[Requirement],
+    provided: &dyn Fn(&str) -> Option<Provided>,
+) -> Result<Resolution, String> {
+    let mut resolution = Resolution::default();
+    let mut seen = HashSet::new();
+    let mut pending: Vec<Requirement> = requirements.iter().rev().cloned().collect();
+    while let Some(requirement) = pending.pop() {
+        if !seen.insert(normalize(&requirement.name)) {
+            continue;
+        }
+        match provided(&requirement.name) {
+            Some(Provided::Installed) => continue,
+            Some(Provided::Registry(name)) => {
+                resolution.registry.push(name);
+                continue;
+            }
+            None => {}
+        }
+        let dist = fetch_dist(host, index_url, &requirement)?;
+        pending.extend(
+            dist.requires
+                .iter()
+                .rev()
+                .filter(|dep| dep.applies(&requirement.extras))
+                .cloned(),
+        );
+        resolution.dists.push(dist);
+    }
+    Ok(resolution)
+}
+
+// ---------------------------------------------------------------------------
+// Installed distributions
+// ---------------------------------------------------------------------------
+
+fn write(host: &dyn HostInterface, path: &str, content: &[u8]) -> Result<(), String> {
+    let full = format!("{SITE_PACKAGES}/{path}");
+    if let Some((parent, _)) = full.rsplit_once('/') {
+        let _ = host.mkdir_p(parent);
+    }
+    host.write_file(&full, content, WriteMode::Truncate)
+        .map_err(|e| format!("writing {full}: {e}"))
+}
+
+/// Write `dist` into site-packages, with an INSTALLER and a RECORD of
+/// what was written for `uninstall`. Returns the number of files.
+pub fn install(host: &dyn HostInterface, dist: &Dist) -> Result<usize, String> {
+    let mut record = String::new();
+    for (path, content) in &dist.files {
+        write(host, path, content)?;
+        record.push_str(&format!("{path},,\n"));
+    }
+    let installer = format!("{}/INSTALLER", dist.dist_info);
+    write(host, &installer, b"pip_lite\n")?;
+    let record_path = format!("{}/RECORD", dist.dist_info);
+    record.push_str(&format!("{installer},,\n{record_path},,\n"));
+    write(host, &record_path, record.as_bytes())?;
+    Ok(dist.files.len())
+}
+
+/// The `.dist-info` directory of `name` in site-packages.
+fn find_dist_info(host: &dyn HostInterface, name: &str) -> Option<String> {
+    let project = normalize(name);
+    host.readdir(SITE_PACKAGES).ok()?.into_iter().find(|entry| {
+        entry
+            .strip_suffix(".dist-info")
+            .and_then(|stem| stem.rsplit_once('-'))
+            .is_some_and(|(dist, _)| normalize(dist) == project)
+    })
+}
+
+/// The METADATA headers of `name` if it is installed in site-packages.
+pub fn installed_metadata(host: &dyn HostInterface, name: &str) -> Option<Vec<(String, String)>> {
+    let dist_info = find_dist_info(host, name)?;
+    let text = host
+        .read_file_str(&format!("{SITE_PACKAGES}/{dist_info}/METADATA"))
+        .ok()?;
+    Some(parse_metadata(&text))
+}
+
+/// Remove `name` from site-packages using its RECORD, along with the
+/// directories that leaves empty. False if it has no `.dist-info`.
+pub fn uninstall(host: &dyn HostInterface, name: &str) -> bool {
+    let Some(dist_info) = find_dist_info(host, name) else {
+        return false;
+    };
+    let record = host
+        .read_file_str(&format!("{SITE_PACKAGES}/{dist_info}/RECORD"))
+        .unwrap_or_default();
+    let mut dirs = BTreeSet::new();
+    for line in record.lines() {
+        // path,hash,size — the path itself may contain commas.
+        let path = line.rsplitn(3, ',').last().unwrap_or_default();
+        if path.is_empty() || check_path(path).is_err() {
+            continue;
+        }
+        let _ = host.remove(&format!("{SITE_PACKAGES}/{path}"), false);
+        let mut parent = path;
+        while let Some((dir, _)) = parent.rsplit_once('/') {
+            dirs.insert(dir.to_string());
+            parent = dir;
+        }
+    }
+    let mut dirs: Vec<String> = dirs.into_iter().collect();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.matches('/').count()));
+    for dir in dirs {
+        let full = format!("{SITE_PACKAGES}/{dir}");
+        if host.readdir(&full).is_ok_and(|entries| entries.is_empty()) {
+            let _ = host.remove(&full, false);
+        }
+    }
+    let _ = host.remove(&format!("{SITE_PACKAGES}/{dist_info}"), true);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::FetchResult;
+    use crate::test_support::mock::MockHost;
+    use std::io::Write;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    fn wheel(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        for (path, content) in files {
+            zip.start_file(*path, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn sdist(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn served(body: &[u8]) -> FetchResult {
+        FetchResult {
+            ok: true,
+            status: 200,
+            headers: Default::default(),
+            body: String::new(),
+            body_base64: Some(base64::Engine::encode(
+                &base64::engine::general_purpose::STANDARD,
+                body,
+            )),
+            error: None,
+        }
+    }
+
+    fn page(html: &str) -> FetchResult {
+        FetchResult {
+            body: html.to_string(),
+            body_base64: None,
+            ..served(b"")
+        }
+    }
+
+    fn link(filename: &str) -> Link {
+        Link {
+            filename: filename.to_string(),
+            url: format!("https://files.example/{filename}"),
+            sha256: None,
+            requires_python: None,
+            yanked: false,
+        }
+    }
+
+    #[test]
+    fn names_normalize() {
+        assert_eq!(normalize("Typing_Extensions"), "typing-extensions");
+        assert_eq!(normalize("zope..interface"), "zope-interface");
+        assert_eq!(project_name("six>=1.16"), "six");
+        assert_eq!(project_name("attrs[tests]"), "attrs");
+    }
+
+    #[test]
+    fn versions_order_like_pep_440() {
+        let ordered = [
+            "1.0.dev1",
+            "1.0a1",
+            "1.0b2",
+            "1.0rc1",
+            "1.0",
+            "1.0.post1",
+            "1.0.1",
+            "1.1",
+            "1!0.5",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert_eq!(v("1.0-1"), v("1.0.post1"));
+        assert_eq!(v("2.0+local"), v("2.0"));
+        assert!(Version::parse("not-a-version").is_none());
+    }
+
+    #[test]
+    fn specifiers_match() {
+        let allows = |spec: &str, version: &str| {
+            parse_specifiers(spec)
+                .unwrap()
+                .iter()
+                .all(|s| s.contains(&v(version)))
+        };
+        assert!(allows(">=1.0,<2", "1.5"));
+        assert!(!allows(">=1.0,<2", "2.0"));
+        assert!(!allows("<2.0", "2.0a1"));
+        assert!(allows("~=1.4.2", "1.4.9"));
+        assert!(!allows("~=1.4.2", "1.5.0"));
+        assert!(allows("==1.2.*", "1.2.7"));
+        assert!(!allows("==1.2.*", "1.3"));
+        assert!(allows("!=1.2.*", "1.3"));
+        assert!(allows("", "0.1"));
+        assert!(parse_specifiers("1.0").is_err());
+    }
+
+    #[test]
+    fn requirements_and_markers() {
+        let req = Requirement::parse("requests[socks] >= 2.0 ; python_version >= \"3.8\"").unwrap();
+        assert_eq!(req.name, "requests");
+        assert_eq!(req.extras, vec!["socks"]);
+        assert_eq!(req.to_string(), "requests[socks]>=2.0");
+        assert!(req.applies(&[]));
+
+        let old = Requirement::parse("six (>=1.10)").unwrap();
+        assert!(old.allows(&v("1.16")) && !old.allows(&v("1.9")));
+
+        let extra = Requirement::parse("pytest; extra == 'Test'").unwrap();
+        assert!(!extra.applies(&[]));
+        assert!(extra.applies(&["test".to_string()]));
+
+        assert!(!evaluate_marker("sys_platform == \"win32\"", &[]));
+        assert!(evaluate_marker(
+            "(python_version < \"3.8\" or os_name == \"posix\") and implementation_name == 'rustpython'",
+            &[]
+        ));
+        assert!(evaluate_marker("'wasm' in platform_machine", &[]));
+        assert!(!evaluate_marker("python_version <", &[]));
+        assert!(Requirement::parse("pkg @ https://example.com/pkg.whl").is_err());
+    }
+
+    #[test]
+    fn index_page_links_resolve_against_the_page() {
+        let html = r#"<!DOCTYPE html><html><body>
+            <a href="../../files/six-1.16.0-py2.py3-none-any.whl#sha256=abc">six-1.16.0-py2.py3-none-any.whl</a><br/>
+            <A HREF='https://cdn.example/six-1.15.0.tar.gz' data-requires-python="&gt;=3.6">six-1.15.0.tar.gz</A>
+            <a href="/x/six-1.0.tar.gz" data-yanked>six-1.0.tar.gz</a>
+        </body></html>"#;
+        let links = parse_index_page(html, "https://pypi.example/simple/six/");
+        assert_eq!(links.len(), 3);
+        assert_eq!(
+            links[0].url,
+            "https://pypi.example/files/six-1.16.0-py2.py3-none-any.whl"
+        );
+        assert_eq!(links[0].sha256.as_deref(), Some("abc"));
+        assert_eq!(links[1].url, "https://cdn.example/six-1.15.0.tar.gz");
+        assert_eq!(links[1].requires_python.as_deref(), Some(">=3.6"));
+        assert!(!links[1].yanked);
+        assert_eq!(links[2].url, "https://pypi.example/x/six-1.0.tar.gz");
+        assert!(links[2].yanked);
+    }
+
+    #[test]
+    fn select_prefers_the_newest_compatible_wheel() {
+        let mut too_new = link("attrs-24.0.0-py3-none-any.whl");
+        too_new.requires_python = Some(">=3.14".into());
+        let mut yanked = link("attrs-23.9.0-py3-none-any.whl");
+        yanked.yanked = true;
+        let links = vec![
+            link("attrs-23.1.0.tar.gz"),
+            link("attrs-23.1.0-py3-none-any.whl"),
+            link("attrs-23.2.0-cp311-cp311-manylinux_2_17_x86_64.whl"),
+            link("attrs-23.2.0.tar.gz"),
+            link("attrs-25.0.0a1-py3-none-any.whl"),
+            too_new,
+            yanked,
+        ];
+        let pick = |spec: &str| {
+            let chosen = select(&links, &Requirement::parse(spec).unwrap()).unwrap();
+            chosen.link.filename.clone()
+        };
+        // The cp311 wheel does not run here, so 23.2.0 comes from the sdist.
+        assert_eq!(pick("attrs"), "attrs-23.2.0.tar.gz");
+        assert_eq!(pick("attrs<23.2"), "attrs-23.1.0-py3-none-any.whl");
+        // Only the pre-release satisfies this.
+        assert_eq!(pick("attrs>24"), "attrs-25.0.0a1-py3-none-any.whl");
+        assert!(select(&links, &Requirement::parse("attrs>=26").unwrap()).is_none());
+    }
+
+    #[test]
+    fn sdist_installs_its_packages_and_requirements() {
+        let data = sdist(&[
+            (
+                "tiny-1.0/PKG-INFO",
+                "Metadata-Version: 2.1\nName: tiny\nVersion: 1.0\n",
+            ),
+            ("tiny-1.0/setup.py", "from setuptools import setup\n"),
+            ("tiny-1.0/src/tiny/__init__.py", "X = 1\n"),
+            ("tiny-1.0/src/tiny/_speedups.c", "/* C */\n"),
+            ("tiny-1.0/src/tiny/data.json", "{}\n"),
+            (
+                "tiny-1.0/src/tiny.egg-info/requires.txt",
+                "six\n\n[:python_version < \"3\"]\nenum34\n\n[test]\npytest\n",
+            ),
+            ("tiny-1.0/tests/test_tiny.py", "def test(): pass\n"),
+        ]);
+        let dist = dist_from_sdist("tiny-1.0.tar.gz", &data).unwrap();
+        let paths: Vec<&str> = dist.files.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "tiny/__init__.py",
+                "tiny/data.json",
+                "tiny-1.0.dist-info/METADATA"
+            ]
+        );
+        assert_eq!(dist.skipped, vec!["tiny/_speedups.c"]);
+        let applied: Vec<&str> = dist
+            .requires
+            .iter()
+            .filter(|r| r.applies(&[]))
+            .map(|r| r.name.as_str())
+            .collect();
+        assert_eq!(applied, vec!["six"]);
+    }
+
+    #[test]
+    fn resolve_install_and_uninstall() {
+        let whl = wheel(&[
+            ("greet/__init__.py", "from shout import shout\n"),
+            (
+                "greet-2.0.dist-info/METADATA",
+                "Metadata-Version: 2.1\nName: greet\nVersion: 2.0\nRequires-Dist: shout>=1\n\
+                 Requires-Dist: numpy\nRequires-Dist: colorama; sys_platform == \"win32\"\n",
+            ),
+            ("greet-2.0.dist-info/RECORD", "greet/__init__.py,,\n"),
+            ("greet-2.0.data/scripts/greet", "#!python\n"),
+        ]);
+        let tarball = sdist(&[
+            (
+                "shout-1.1/PKG-INFO",
+                "Metadata-Version: 2.1\nName: shout\nVersion: 1.1\n",
+            ),
+            ("shout-1.1/shout.py", "def shout(s): return s.upper()\n"),
+            ("shout-1.1/setup.py", "\n"),
+        ]);
+        let whl_hash = hex(&Sha256::digest(&whl));
+        let host = MockHost::new()
+            .with_fetch_result(
+                "https://index.example/simple/greet/",
+                page(&format!(
+                    "<a href=\"greet-2.0-py3-none-any.whl#sha256={whl_hash}\">greet-2.0-py3-none-any.whl</a>"
+                )),
+            )
+            .with_fetch_result(
+                "https://index.example/simple/greet/greet-2.0-py3-none-any.whl",
+                served(&whl),
+            )
+            .with_fetch_result(
+                "https://index.example/simple/shout/",
+                page("<a href=\"https://files.example/shout-1.1.tar.gz\">shout-1.1.tar.gz</a>"),
+            )
+            .with_fetch_result("https://files.example/shout-1.1.tar.gz", served(&tarball));
+
+        let provided = |name: &str| (name == "numpy").then(|| Provided::Registry("numpy".into()));
+        let resolution = resolve(
+            &host,
+            "https://index.example/simple/",
+            &[Requirement::parse("greet").unwrap()],
+            &provided,
+        )
+        .unwrap();
+        let names: Vec<&str> = resolution.dists.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["greet", "shout"]);
+        assert_eq!(resolution.registry, vec!["numpy"]);
+
+        for dist in &resolution.dists {
+            install(&host, dist).unwrap();
+        }
+        let read = |path: &str| host.read_file_str(&format!("{SITE_PACKAGES}/{path}"));
+        assert!(read("greet/__init__.py").unwrap().contains("shout"));
+        assert!(read("shout.py").is_ok());
+        assert!(read("greet-2.0.dist-info/RECORD")
+            .unwrap()
+            .contains("greet-2.0.dist-info/INSTALLER"));
+        assert!(read("greet-2.0.data/scripts/greet").is_err());
+        let metadata = installed_metadata(&host, "Greet").unwrap();
+        assert_eq!(header(&metadata, "version"), Some("2.0"));
+
+        assert!(uninstall(&host, "greet"));
+        assert_eq!(
+            host.readdir(SITE_PACKAGES).unwrap(),
+            vec!["shout-1.1.dist-info", "shout.py"]
+        );
+        assert!(!uninstall(&host, "greet"));
+    }
+
+    #[test]
+    fn missing_project_and_bad_hash_are_errors() {
+        let host = MockHost::new()
+            .with_fetch_result(
+                "https://index.example/nope/",
+                FetchResult {
+                    ok: false,
+                    status: 404,
+                    ..page("")
+                },
+            )
+            .with_fetch_result(
+                "https://index.example/tiny/",
+                page(
+                    "<a href=\"tiny-1.0-py3-none-any.whl#sha256=00\">tiny-1.0-py3-none-any.whl</a>",
+                ),
+            )
+            .with_fetch_result(
+                "https://index.example/tiny/tiny-1.0-py3-none-any.whl",
+                served(b"PK"),
+            );
+        let err = fetch_dist(
+            &host,
+            "https://index.example",
+            &Requirement::parse("nope").unwrap(),
+        )
+        .unwrap_err();
+        assert!(err.contains("Could not find a version that satisfies the requirement nope"));
+        let err = fetch_dist(
+            &host,
+            "https://index.example",
+            &Requirement::parse("tiny").unwrap(),
+        )
+        .unwrap_err();
+        assert!(err.contains("hash mismatch"), "{err}");
+    }
+}
@@ -0,0 +1,593 @@
+//! Confining the shell to parts of the filesystem and to chosen commands.
+//!
+//! A [`PathPolicy`] lists the path prefixes that may be read and written.
+//! Wrapping a host in [`PolicyHost`] applies it to every file call the
+//! executor, builtins and virtual commands make, after resolving `..` and
+//! symlinks so a path cannot name its way out of an allowed tree.
+//!
+//! A [`CommandPolicy`], set as the session's `ShellState::policy`, lists
+//! the commands that may run and the arguments they may not be given. The
+//! executor checks it before every builtin and spawn; a command it turns
+//! down fails with status 126 without running.
+
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::control::CancelReason;
+use crate::host::{
+    DuplexChild, FetchRequest, FetchResult, FileChange, FileWatch, HostError, HostInterface,
+    SpawnLimits, SpawnResult, StatInfo, StorageQuota, StreamingChild, TerminalSize, WriteMode,
+};
+use crate::state::ShellState;
+
+/// Which path prefixes may be read and written. Paths are compared by
+/// whole components, so `/tmp` covers `/tmp/x` but not `/tmpfoo`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathPolicy {
+    /// Prefixes that may be read (`stat`, `readdir`, reading files). Empty
+    /// allows reading anywhere not denied.
+    pub read: Vec<String>,
+    /// Prefixes that may be written (creating, changing and removing
+    /// files). Empty allows writing anywhere not denied.
+    pub write: Vec<String>,
+    /// Prefixes that may be neither read nor written, even inside an
+    /// allowed one.
+    pub deny: Vec<String>,
+}
+
+/// The kind of access a host call makes to a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+impl PathPolicy {
+    /// A policy allowing reads and writes only under `prefixes`.
+    pub fn confined_to(prefixes: &[&str]) -> Self {
+        let prefixes: Vec<String> = prefixes.iter().map(|p| p.to_string()).collect();
+        Self {
+            read: prefixes.clone(),
+            write: prefixes,
+            deny: Vec::new(),
+        }
+    }
+
+    /// Whether `access` to the already-canonical path `path` is allowed.
+    pub fn allows(&self, path: &str, access: Access) -> bool {
+        let allowed = match access {
+            Access::Read => &self.read,
+            Access::Write => &self.write,
+        };
+        !self.deny.iter().any(|p| under(path, p))
+            && (allowed.is_empty() || allowed.iter().any(|p| under(path, p)))
+    }
+
+    /// Fail with `PermissionDenied` unless `access` to `path` is allowed
+    /// once symlinks and `..` in it are resolved through `host`.
+    pub fn check(
+        &self,
+        host: &dyn HostInterface,
+        path: &str,
+        access: Access,
+    ) -> Result<(), HostError> {
+        if self.allows(&host.canonicalize(path), access) {
+            Ok(())
+        } else {
+            Err(HostError::PermissionDenied(path.to_string()))
+        }
+    }
+}
+
+/// Whether `path` is `prefix` or lies beneath it.
+fn under(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Which commands may run, and with what arguments. Deserializes from
+/// JSON such as `{"deny": ["curl"], "paths": ["/tmp"]}`, with missing
+/// fields allowing everything.
+///
+/// Commands are matched by their last path component, so denying `rm`
+/// denies `/bin/rm` too. Shell functions are not checked themselves, only
+/// the commands they run. Files reached other than through an argument,
+/// such as by a redirect, are a [`PathPolicy`]'s to guard.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CommandPolicy {
+    /// Names of the commands that may run. Empty allows any command not
+    /// denied.
+    pub allow: Vec<String>,
+    /// Names of commands that may not run.
+    pub deny: Vec<String>,
+    /// Arguments that may not be passed.
+    pub deny_args: Vec<ArgRule>,
+    /// Prefixes that paths given as arguments must lie under, compared
+    /// like a [`PathPolicy`]'s. Empty allows any path.
+    pub paths: Vec<String>,
+}
+
+/// Turns a command down when any of its arguments matches `pattern`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArgRule {
+    /// The command the rule applies to, or `*` for every command.
+    pub command: String,
+    #[serde(deserialize_with = "regex_from_str")]
+    pub pattern: Regex,
+}
+
+fn regex_from_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+/// Why a [`CommandPolicy`] turned a command down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    Command,
+    Argument(String),
+    Path(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Command => write!(f, "command not allowed by policy"),
+            Violation::Argument(arg) => write!(f, "argument not allowed by policy: {arg}"),
+            Violation::Path(path) => write!(f, "path not allowed by policy: {path}"),
+        }
+    }
+}
+
+impl CommandPolicy {
+    /// Whether the policy lets every command run.
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty()
+            && self.deny.is_empty()
+            && self.deny_args.is_empty()
+            && self.paths.is_empty()
+    }
+
+    /// Check that `argv` may run in `state`'s cwd. An argument names a
+    /// path when it contains a `/` and is not a URL; in `--option=value`,
+    /// only the value counts. Paths are resolved through `host`, like a
+    /// [`PathPolicy`]'s, before they are compared.
+    pub fn check(
+        &self,
+        state: &ShellState,
+        host: &dyn HostInterface,
+        argv: &[String],
+    ) -> Result<(), Violation> {
+        let Some((program, args)) = argv.split_first() else {
+            return Ok(());
+        };
+        let name = program.rsplit('/').next().unwrap_or(program);
+        if self.deny.iter().any(|d| d == name)
+            || (!self.allow.is_empty() && !self.allow.iter().any(|a| a == name))
+        {
+            return Err(Violation::Command);
+        }
+        let rules: Vec<&ArgRule> = self
+            .deny_args
+            .iter()
+            .filter(|rule| rule.command == "*" || rule.command == name)
+            .collect();
+        for arg in args {
+            if rules.iter().any(|rule| rule.pattern.is_match(arg)) {
+                return Err(Violation::Argument(arg.clone()));
+            }
+            if self.paths.is_empty() {
+                continue;
+            }
+            let value = match arg.split_once('=') {
+                Some((option, value)) if option.starts_with("--") => value,
+                _ => arg.as_str(),
+            };
+            if !value.contains('/') || value.contains("://") {
+                continue;
+            }
+            let path = state.resolve_path_physical(host, value);
+            if !self.paths.iter().any(|prefix| under(&path, prefix)) {
+                return Err(Violation::Path(value.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A host whose file calls are checked against a [`PathPolicy`] before
+/// they reach the wrapped host. Process, network and fd calls pass
+/// straight through; spawned tools are confined by the host itself.
+pub struct PolicyHost<'a> {
+    host: &'a dyn HostInterface,
+    policy: &'a PathPolicy,
+}
+
+impl<'a> PolicyHost<'a> {
+    pub fn new(host: &'a dyn HostInterface, policy: &'a PathPolicy) -> Self {
+        Self { host, policy }
+    }
+
+    fn check(&self, path: &str, access: Access) -> Result<(), HostError> {
+        self.policy.check(self.host, path, access)
+    }
+}
+
+impl HostInterface for PolicyHost<'_> {
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        self.host.spawn(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+        )
+    }
+
+    fn spawn_with_limits(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        self.host.spawn_with_limits(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice, limits,
+        )
+    }
+
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        self.host.spawn_duplex(program, args, env, cwd)
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        self.host.spawn_streaming(program, args, env, cwd)
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        self.host.has_tool(name)
+    }
+
+    fn time(&self) -> f64 {
+        self.host.time()
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        self.host.monotonic_ms()
+    }
+
+    fn now_unix_ms(&self) -> u64 {
+        self.host.now_unix_ms()
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        self.host.monotonic_ns()
+    }
+
+    fn should_cancel(&self) -> Option<CancelReason> {
+        self.host.should_cancel()
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        self.host.random_bytes(n)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        self.host.isatty(fd)
+    }
+
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        self.host.terminal_size(fd)
+    }
+
+    fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+        self.check(path, Access::Read)?;
+        self.host.stat(path)
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        self.check(path, Access::Read)?;
+        self.host.read_file(path)
+    }
+
+    fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.write_file(path, data, mode)
+    }
+
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        self.host.storage_quota()
+    }
+
+    fn check_writable(&self, path: &str) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.check_writable(path)
+    }
+
+    fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
+        self.check(path, Access::Read)?;
+        self.host.readdir(path)
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.mkdir(path)
+    }
+
+    fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.remove(path, recursive)
+    }
+
+    fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.chmod(path, mode)
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.set_mtime(path, mtime_ms)
+    }
+
+    fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
+        let matches = self.host.glob(pattern)?;
+        Ok(matches
+            .into_iter()
+            .filter(|m| self.check(m, Access::Read).is_ok())
+            .collect())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+        self.check(from, Access::Write)?;
+        self.check(to, Access::Write)?;
+        self.host.rename(from, to)
+    }
+
+    fn write_file_atomic(&self, path: &str, data: &[u8]) -> Result<(), HostError> {
+        self.check(path, Access::Write)?;
+        self.host.write_file_atomic(path, data)
+    }
+
+    fn create_temp_file(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.check(dir, Access::Write)?;
+        self.host.create_temp_file(dir, prefix)
+    }
+
+    fn create_temp_dir(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.check(dir, Access::Write)?;
+        self.host.create_temp_dir(dir, prefix)
+    }
+
+    fn watch(&self, path: &str) -> Result<FileWatch, HostError> {
+        self.check(path, Access::Read)?;
+        self.host.watch(path)
+    }
+
+    fn wait_for_change(
+        &self,
+        watch: &mut FileWatch,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        self.host.wait_for_change(watch, timeout_ms)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+        self.check(link_path, Access::Write)?;
+        self.host.symlink(target, link_path)
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, HostError> {
+        self.host.readlink(path)
+    }
+
+    fn canonicalize(&self, path: &str) -> String {
+        self.host.canonicalize(path)
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        self.host.fetch(request)
+    }
+
+    fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError> {
+        self.check(wasm_path, Access::Read)?;
+        self.host.register_tool(name, wasm_path)
+    }
+
+    fn pipe(&self) -> Result<(i32, i32), HostError> {
+        self.host.pipe()
+    }
+
+    fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        self.host.waitpid(pid)
+    }
+
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        self.host.waitpid_timeout(pid, timeout_ms)
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        self.host.kill(pid, signal)
+    }
+
+    fn close_fd(&self, fd: i32) -> Result<(), HostError> {
+        self.host.close_fd(fd)
+    }
+
+    fn dup(&self, fd: i32) -> Result<i32, HostError> {
+        self.host.dup(fd)
+    }
+
+    fn dup2(&self, src_fd: i32, dst_fd: i32) -> Result<(), HostError> {
+        self.host.dup2(src_fd, dst_fd)
+    }
+
+    fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
+        self.host.read_fd(fd)
+    }
+
+    fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
+        self.host.write_fd(fd, data)
+    }
+
+    fn yield_now(&self) -> Result<(), HostError> {
+        self.host.yield_now()
+    }
+
+    fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
+        self.host.waitpid_nohang(pid)
+    }
+
+    fn list_processes(&self) -> Result<String, HostError> {
+        self.host.list_processes()
+    }
+
+    fn socket_connect(&self, host: &str, port: u16, tls: bool) -> Result<u32, HostError> {
+        self.host.socket_connect(host, port, tls)
+    }
+
+    fn socket_send(&self, socket_id: u32, data: &[u8]) -> Result<usize, HostError> {
+        self.host.socket_send(socket_id, data)
+    }
+
+    fn socket_recv(&self, socket_id: u32, max_bytes: usize) -> Result<Vec<u8>, HostError> {
+        self.host.socket_recv(socket_id, max_bytes)
+    }
+
+    fn socket_close(&self, socket_id: u32) -> Result<(), HostError> {
+        self.host.socket_close(socket_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::ShellError;
+    use crate::executor::exec_command;
+    use crate::state::ShellState;
+    use crate::test_support::mock::{MockHost, MockSpawnOutput};
+    use serde_json::json;
+
+    #[test]
+    fn policy_confines_reads_and_writes() {
+        let inner = MockHost::new()
+            .with_file("/etc/secret", b"hunter2\n")
+            .with_file("/tmp/in.txt", b"hello\n")
+            .with_dir("/tmp")
+            .with_dir("/etc")
+            .with_symlink("/tmp/escape", "/etc/secret");
+        let policy = PathPolicy {
+            deny: vec!["/tmp/private".into()],
+            ..PathPolicy::confined_to(&["/tmp", "/home/user"])
+        };
+        let host = PolicyHost::new(&inner, &policy);
+        let mut state = ShellState::new_default();
+        state.cwd = "/tmp".into();
+
+        let run = |state: &mut ShellState, src: &str| {
+            let _ = exec_command(state, &host, &codepod_shell::parser::parse(src));
+            state.last_exit_code
+        };
+        assert_eq!(run(&mut state, "read l < in.txt; echo \"$l\" > out.txt"), 0);
+        assert_eq!(inner.get_file("/tmp/out.txt").as_deref(), Some("hello\n"));
+
+        // Escapes by `..` and by symlink are both caught.
+        assert_eq!(run(&mut state, "echo x > ../etc/new"), 1);
+        let read = codepod_shell::parser::parse("read line < escape");
+        assert!(matches!(
+            exec_command(&mut state, &host, &read),
+            Err(ShellError::HostError(HostError::PermissionDenied(_)))
+        ));
+        assert_eq!(run(&mut state, "echo x > /tmp/private/f"), 1);
+        assert!(inner.get_file("/etc/new").is_none());
+
+        assert!(matches!(
+            host.read_file("/tmp/../etc/secret"),
+            Err(HostError::PermissionDenied(_))
+        ));
+        assert!(!policy.allows("/tmpfoo", Access::Read));
+        assert!(policy.allows("/home/user", Access::Write));
+    }
+
+    #[test]
+    fn command_policy_turns_commands_down_before_they_run() {
+        let host = MockHost::new()
+            .with_dir("/tmp")
+            .with_file("/tmp/a.txt", b"a\n")
+            .with_file("/etc/passwd", b"root\n")
+            .with_spawn_result(
+                "tool",
+                MockSpawnOutput {
+                    exit_code: 0,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                },
+            );
+        let mut state = ShellState::new_default();
+        state.cwd = "/tmp".into();
+        state.policy = serde_json::from_value(json!({
+            "deny": ["curl"],
+            "deny_args": [{ "command": "rm", "pattern": "^-[a-zA-Z]*r" }],
+            "paths": ["/tmp"],
+        }))
+        .unwrap();
+        let run = |state: &mut ShellState, src: &str| {
+            let _ = exec_command(state, &host, &codepod_shell::parser::parse(src));
+            state.last_exit_code
+        };
+
+        assert_eq!(run(&mut state, "curl https://example.com"), 126);
+        assert_eq!(run(&mut state, "/usr/bin/curl x"), 126);
+        assert_eq!(run(&mut state, "nice -n 5 curl x"), 126);
+        assert_eq!(run(&mut state, "echo x | curl"), 126);
+        assert_eq!(run(&mut state, "rm -fr a.txt"), 126);
+        assert!(host.get_file("/tmp/a.txt").is_some());
+        assert_eq!(run(&mut state, "cat ../etc/passwd"), 126);
+        assert_eq!(run(&mut state, "tool --out=/etc/x"), 126);
+        assert!(host.get_spawn_calls().is_empty());
+
+        assert_eq!(run(&mut state, "tool --out=./x https://example.com"), 0);
+        assert_eq!(run(&mut state, "curl() { echo fake; }; curl"), 0);
+
+        state.policy = serde_json::from_value(json!({ "allow": ["echo", "tool"] })).unwrap();
+        assert_eq!(run(&mut state, "echo hi; tool"), 0);
+        assert_eq!(run(&mut state, "cat a.txt"), 126);
+        assert_eq!(host.get_spawn_calls().len(), 2);
+
+        let bad = serde_json::from_value::<CommandPolicy>(json!({
+            "deny_args": [{ "command": "*", "pattern": "(" }],
+        }));
+        assert!(bad.is_err());
+    }
+}
@@ -0,0 +1,437 @@
+//! Interactive mode (`codepod-shell-exec -i`): prompt, read, execute, repeat.
+
+use codepod_shell::ast::Word;
+use codepod_shell::lexer::{is_complete, parse_string_expansion};
+
+use crate::control::{CancelReason, ControlFlow};
+use crate::executor::{exec_command, parse_script, run_exit_trap};
+use crate::expand::{expand_word, restore_brace_sentinels, restore_glob_sentinels};
+use crate::host::HostInterface;
+use crate::state::ShellState;
+
+/// Run an interactive session until end of input or `exit`, returning the
+/// final exit status.
+///
+/// `read_line` supplies one line of input at a time (with its newline), or
+/// `None` at end of input. Prompts go to stderr: `PS1` before each command
+/// and `PS2` while an unterminated construct continues onto further lines.
+/// History references (`!!`, `!N`, `!-N`, `!prefix`) are expanded before a
+/// command is recorded in the history and run. A command the host cancels
+/// (Ctrl-C) is abandoned with status 130 and the session carries on.
+/// Like bash's `checkwinsize`, `COLUMNS` and `LINES` follow the terminal
+/// size before each prompt when stderr is a terminal.
+pub fn run_repl(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    read_line: &mut dyn FnMut() -> Option<String>,
+) -> i32 {
+    loop {
+        update_window_size(state, host);
+        let mut source = String::new();
+        loop {
+            let prompt_var = if source.is_empty() { "PS1" } else { "PS2" };
+            let prompt = expand_prompt(state, prompt_var);
+            crate::shell_eprint!("{}", prompt);
+            let Some(line) = read_line() else {
+                if source.is_empty() {
+                    crate::shell_eprint!("exit\n");
+                } else {
+                    crate::shell_eprintln!("codepod-shell: syntax error: unexpected end of file");
+                    state.last_exit_code = 2;
+                }
+                let code = state.last_exit_code;
+                return finish(state, host, code);
+            };
+            source.push_str(&line);
+            if is_complete(&source) {
+                break;
+            }
+        }
+        if source.trim().is_empty() {
+            continue;
+        }
+
+        match expand_history(&state.history, &source) {
+            Ok(Some(expanded)) => {
+                // Like bash, show the command that is actually run.
+                crate::shell_eprint!("{}", expanded);
+                source = expanded;
+            }
+            Ok(None) => {}
+            Err(msg) => {
+                crate::shell_eprintln!("codepod-shell: {msg}");
+                state.last_exit_code = 1;
+                continue;
+            }
+        }
+        state
+            .history
+            .push(source.trim_end_matches('\n').to_string());
+        state.begin_run();
+
+        match parse_script(&source).and_then(|ast| exec_command(state, host, &ast)) {
+            Ok(ControlFlow::Normal(run)) => state.last_exit_code = run.exit_code,
+            Ok(ControlFlow::Exit(code)) => return finish(state, host, code),
+            Ok(ControlFlow::Cancelled(CancelReason::Cancelled)) => {
+                crate::shell_eprint!("\n");
+                state.last_exit_code = 130;
+            }
+            Ok(ControlFlow::Cancelled(CancelReason::Timeout)) => state.last_exit_code = 124,
+            // A stray break, continue or return at the prompt does nothing.
+            Ok(_) => {}
+            Err(e) => {
+                crate::shell_eprintln!("codepod-shell: {e}");
+                state.last_exit_code = e.exit_code();
+            }
+        }
+    }
+}
+
+/// Set `COLUMNS` and `LINES` from the terminal the prompt is written to.
+fn update_window_size(state: &mut ShellState, host: &dyn HostInterface) {
+    if let Some(size) = host.terminal_size(2) {
+        state.env.insert("COLUMNS".into(), size.cols.to_string());
+        state.env.insert("LINES".into(), size.rows.to_string());
+    }
+}
+
+/// Run the EXIT trap, if any, and pass `code` through.
+fn finish(state: &mut ShellState, host: &dyn HostInterface, code: i32) -> i32 {
+    run_exit_trap(state, host);
+    code
+}
+
+/// Expand the prompt in `var` (`PS1` defaults to `$ `, `PS2` to `> `).
+///
+/// Bash's backslash escapes are replaced first: `\u` user, `\h` host, `\w`
+/// and `\W` working directory (with `~` for `$HOME`), `\s` shell name, `\$`,
+/// `\n`, `\e`, `\\`, and the non-printing markers `\[` `\]`. Parameter and
+/// arithmetic expansion then apply to the result.
+fn expand_prompt(state: &mut ShellState, var: &str) -> String {
+    let default = if var == "PS1" { "$ " } else { "> " };
+    let template = state
+        .env
+        .get(var)
+        .cloned()
+        .unwrap_or_else(|| default.to_string());
+
+    let home = state.env.get("HOME").cloned().unwrap_or_default();
+    let cwd = state.cwd.clone();
+    let tilde_cwd = match cwd.strip_prefix(home.as_str()) {
+        Some(rest) if !home.is_empty() && (rest.is_empty() || rest.starts_with('/')) => {
+            format!("~{rest}")
+        }
+        _ => cwd.clone(),
+    };
+
+    let mut escaped = String::new();
+    let mut chars = template.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            escaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('u') => escaped.push_str(state.env.get("USER").map_or("user", String::as_str)),
+            Some('h') | Some('H') => {
+                escaped.push_str(state.env.get("HOSTNAME").map_or("codepod", String::as_str))
+            }
+            Some('w') => escaped.push_str(&tilde_cwd),
+            Some('W') => {
+                let base = if tilde_cwd == "~" {
+                    "~"
+                } else {
+                    cwd.rsplit('/').find(|s| !s.is_empty()).unwrap_or("/")
+                };
+                escaped.push_str(base);
+            }
+            Some('s') => escaped.push_str("codepod-shell"),
+            Some('$') => escaped.push('$'),
+            Some('n') => escaped.push('\n'),
+            Some('e') => escaped.push('\x1b'),
+            Some('\\') => escaped.push('\\'),
+            Some('[') | Some(']') => {}
+            Some(other) => {
+                escaped.push('\\');
+                escaped.push(other);
+            }
+            None => escaped.push('\\'),
+        }
+    }
+
+    let word = Word {
+        parts: parse_string_expansion(&escaped),
+    };
+    let expanded = vec![expand_word(state, &word, None)];
+    restore_glob_sentinels(&restore_brace_sentinels(&expanded)).remove(0)
+}
+
+/// Tab-completion candidates for the word at the end of `line`.
+///
+/// Returns the byte offset where that word starts and the sorted,
+/// deduplicated replacements for it. A word starting with `$` completes
+/// variable names; a word in command position completes builtins,
+/// functions and aliases (or paths, if it contains `/`); anything else
+/// completes paths, with `/` appended to directories. Hidden entries are
+/// offered only when the word's last component starts with `.`.
+pub fn complete(state: &ShellState, host: &dyn HostInterface, line: &str) -> (usize, Vec<String>) {
+    let start = line
+        .rfind(|c: char| c.is_whitespace() || matches!(c, ';' | '|' | '&' | '<' | '>' | '(' | '`'))
+        .map_or(0, |i| i + 1);
+    let word = &line[start..];
+    let before = line[..start].trim_end();
+    let command_position = before.is_empty()
+        || before.ends_with([';', '|', '&', '(', '`'])
+        || matches!(
+            before.rsplit(char::is_whitespace).next(),
+            Some("then" | "do" | "else" | "elif" | "if" | "while" | "until" | "!" | "time")
+        );
+
+    let mut candidates: Vec<String> = if let Some(prefix) = word.strip_prefix('$') {
+        let prefix = prefix.strip_prefix('{').unwrap_or(prefix);
+        state
+            .env
+            .keys()
+            .chain(state.arrays.keys())
+            .chain(state.assoc_arrays.keys())
+            .filter(|name| name.starts_with(prefix))
+            .map(|name| format!("${name}"))
+            .collect()
+    } else if command_position && !word.contains('/') {
+        crate::builtins::BUILTIN_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .chain(state.functions.keys().cloned())
+            .chain(state.aliases.keys().cloned())
+            .filter(|name| name.starts_with(word))
+            .collect()
+    } else {
+        complete_path(state, host, word)
+    };
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+/// Complete `word` as a path relative to the working directory.
+fn complete_path(state: &ShellState, host: &dyn HostInterface, word: &str) -> Vec<String> {
+    let (dir_part, prefix) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i + 1..]),
+        None => ("", word),
+    };
+    let home = state.env.get("HOME").map_or("", String::as_str);
+    let lookup = match dir_part.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{home}{rest}"),
+        _ if dir_part.is_empty() => state.cwd.clone(),
+        _ => state.resolve_path(dir_part),
+    };
+    let Ok(entries) = host.list_dir(&lookup) else {
+        return Vec::new();
+    };
+    entries
+        .into_iter()
+        .filter(|e| {
+            e.name.starts_with(prefix) && (prefix.starts_with('.') || !e.name.starts_with('.'))
+        })
+        .map(|e| {
+            let slash = if e.is_dir { "/" } else { "" };
+            format!("{dir_part}{}{slash}", e.name)
+        })
+        .collect()
+}
+
+/// Expand history references in a command line: `!!` (the previous
+/// command), `!N` (entry N as numbered by `history`), `!-N` (N commands
+/// back) and `!prefix` (the latest command starting with `prefix`).
+///
+/// Returns `Ok(None)` if the line has no references. A `!` inside single
+/// quotes, after `$`, or before whitespace, `=` or `(` is left alone.
+fn expand_history(history: &[String], line: &str) -> Result<Option<String>, String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    let mut changed = false;
+    let mut in_single = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '\'' {
+            in_single = !in_single;
+        }
+        let literal = ch != '!'
+            || in_single
+            || (i > 0 && matches!(chars[i - 1], '$' | '\\'))
+            || chars
+                .get(i + 1)
+                .is_none_or(|c| c.is_whitespace() || matches!(c, '=' | '(' | '"'));
+        if literal {
+            out.push(ch);
+            i += 1;
+            continue;
+        }
+
+        let start = i + 1;
+        let mut end = start;
+        let entry = if chars[start] == '!' {
+            end += 1;
+            history.last()
+        } else {
+            while end < chars.len()
+                && !chars[end].is_whitespace()
+                && !matches!(
+                    chars[end],
+                    ';' | '|' | '&' | '<' | '>' | '(' | ')' | '"' | '\''
+                )
+            {
+                end += 1;
+            }
+            let designator: String = chars[start..end].iter().collect();
+            match designator.parse::<i64>() {
+                Ok(n) if n > 0 => history.get(n as usize - 1),
+                Ok(n) if n < 0 => history
+                    .len()
+                    .checked_sub(n.unsigned_abs() as usize)
+                    .and_then(|idx| history.get(idx)),
+                Ok(_) => None,
+                Err(_) => history.iter().rev().find(|h| h.starts_with(&designator)),
+            }
+        };
+        let Some(entry) = entry else {
+            let designator: String = chars[i..end].iter().collect();
+            return Err(format!("{designator}: event not found"));
+        };
+        out.push_str(entry);
+        changed = true;
+        i = end;
+    }
+    Ok(changed.then_some(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock::MockHost;
+
+    /// Run a session over `lines`, capturing stdout. Returns (status, stdout).
+    fn run_session(state: &mut ShellState, host: &MockHost, lines: &[&str]) -> (i32, String) {
+        let mut input = lines.iter().map(|l| format!("{l}\n"));
+        let (read_fd, write_fd) = host.pipe().unwrap();
+        state.stdout_fd = write_fd;
+        let code = run_repl(state, host, &mut || input.next());
+        host.close_fd(write_fd).unwrap();
+        let out = String::from_utf8(host.read_fd(read_fd).unwrap()).unwrap();
+        (code, out)
+    }
+
+    #[test]
+    fn continues_unterminated_commands_and_exits_with_status() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (code, out) = run_session(
+            &mut state,
+            &host,
+            &[
+                "for i in 1 2; do",
+                "echo $i",
+                "done",
+                "echo 'a",
+                "b'",
+                "exit 4",
+                "echo unreachable",
+            ],
+        );
+        assert_eq!(code, 4);
+        assert_eq!(out, "1\n2\na\nb\n");
+        assert_eq!(state.history[0], "for i in 1 2; do\necho $i\ndone");
+    }
+
+    #[test]
+    fn history_references_rerun_earlier_commands() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (code, out) = run_session(
+            &mut state,
+            &host,
+            &[
+                "echo one",
+                "echo two",
+                "!!",
+                "!1",
+                "!-3 again",
+                "!nope",
+                "false",
+            ],
+        );
+        assert_eq!(code, 1);
+        assert_eq!(out, "one\ntwo\ntwo\none\ntwo again\n");
+        assert_eq!(state.history.len(), 6);
+        assert_eq!(expand_history(&state.history, "echo $! != 1"), Ok(None));
+    }
+
+    #[test]
+    fn completes_commands_paths_and_variables() {
+        let host = MockHost::new()
+            .with_dir("/work/src")
+            .with_file("/work/setup.sh", b"")
+            .with_file("/work/.hidden", b"")
+            .with_file("/work/src/main.rs", b"");
+        let mut state = ShellState::new_default();
+        state.cwd = "/work".to_string();
+        state.env.insert("HOME".into(), "/work".into());
+        state.env.insert("SRC_DIR".into(), "src".into());
+        state.aliases.insert("ech".into(), "echo".into());
+
+        assert_eq!(
+            complete(&state, &host, "ec"),
+            (0, vec!["ech".to_string(), "echo".to_string()])
+        );
+        assert_eq!(
+            complete(&state, &host, "cat s"),
+            (4, vec!["setup.sh".to_string(), "src/".to_string()])
+        );
+        assert_eq!(
+            complete(&state, &host, "ls; cat ~/src/m"),
+            (8, vec!["~/src/main.rs".to_string()])
+        );
+        assert_eq!(
+            complete(&state, &host, "echo x>."),
+            (7, vec![".hidden".to_string()])
+        );
+        assert_eq!(
+            complete(&state, &host, "echo $SRC"),
+            (5, vec!["$SRC_DIR".to_string()])
+        );
+    }
+
+    #[test]
+    fn prompt_escapes_and_expansion() {
+        let mut state = ShellState::new_default();
+        state.cwd = "/home/user/proj".to_string();
+        state
+            .env
+            .insert("PS1".into(), "\\u@\\h:\\w [$X]\\$ ".into());
+        state.env.insert("X".into(), "x".into());
+        assert_eq!(
+            expand_prompt(&mut state, "PS1"),
+            "user@codepod:~/proj [x]$ "
+        );
+        assert_eq!(expand_prompt(&mut state, "PS2"), "> ");
+    }
+
+    #[test]
+    fn tracks_the_terminal_size_and_answers_test_t() {
+        let script = [
+            "echo \"$COLUMNS x $LINES\"",
+            "[ -t 1 ] && echo tty1 || echo pipe1",
+            "[[ -t 2 ]] && echo tty2 || echo pipe2",
+        ];
+        let host = MockHost::new().with_terminal(120, 40);
+        let mut state = ShellState::new_default();
+        let (_, out) = run_session(&mut state, &host, &script);
+        assert_eq!(out, "120 x 40\npipe1\ntty2\n");
+
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let (_, out) = run_session(&mut state, &host, &script);
+        assert_eq!(out, " x \npipe1\npipe2\n");
+    }
+}
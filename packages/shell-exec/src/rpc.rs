@@ -0,0 +1,405 @@
+//! Serving host calls made through the `__rpc` export.
+//!
+//! The host sends [`codepod_rpc`] requests to run commands, read and write
+//! files through the shell's host, and ask after the session. [`dispatch`]
+//! answers them against the session state.
+
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use codepod_rpc::{method, Request, RpcError, Streams};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::control::{CancelReason, ControlFlow, Diagnostic, RunResult};
+use crate::executor::{exec_command, parse_script, run_exit_trap};
+use crate::host::{HostInterface, WriteMode};
+use crate::shell_eprintln;
+use crate::state::ShellState;
+use crate::transcript::{self, TranscriptHost};
+
+/// What running a command reports to the host, through `__run_command` or
+/// `shell.run`.
+#[derive(Debug, Serialize)]
+pub struct CommandOutput {
+    #[serde(flatten)]
+    pub result: RunResult,
+    /// The environment after the run, for the host to sync.
+    pub env: HashMap<String, String>,
+    /// Set when the command failed with a shell error (e.g. a syntax
+    /// error), locating it in the command text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Diagnostic>,
+}
+
+/// Run `cmd` as one host-initiated command: record it in the history,
+/// execute it and fire the EXIT trap. While the session is being recorded,
+/// the run also goes into its transcript.
+pub fn run_command(state: &mut ShellState, host: &dyn HostInterface, cmd: &str) -> CommandOutput {
+    if state.transcript.is_none() {
+        return execute(state, host, cmd);
+    }
+    let recorder = TranscriptHost::start(state, host, cmd);
+    let output = execute(state, &recorder, cmd);
+    recorder.finish(state, output.result.exit_code);
+    output
+}
+
+fn execute(state: &mut ShellState, host: &dyn HostInterface, cmd: &str) -> CommandOutput {
+    state.history.push(cmd.to_string());
+    state.begin_run();
+
+    let mut error = None;
+    let result = match parse_script(cmd).and_then(|ast| exec_command(state, host, &ast)) {
+        Ok(ControlFlow::Normal(r)) => r,
+        Ok(ControlFlow::Exit(code)) => RunResult::exit(code),
+        // Output written before the cancellation point is kept.
+        Ok(ControlFlow::Cancelled(CancelReason::Timeout)) => RunResult::exit(124),
+        Ok(ControlFlow::Cancelled(CancelReason::Cancelled)) => RunResult::exit(125),
+        Ok(_) => RunResult::empty(),
+        Err(e) => {
+            shell_eprintln!("{e}");
+            error = Some(e.diagnostic());
+            RunResult::exit(e.exit_code())
+        }
+    };
+
+    state.total_time_ms += result.execution_time_ms;
+    run_exit_trap(state, host);
+
+    CommandOutput {
+        result,
+        env: state.env.clone(),
+        error,
+    }
+}
+
+#[derive(Deserialize)]
+struct RunParams {
+    command: String,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum TranscriptFormat {
+    #[default]
+    Json,
+    Asciicast,
+}
+
+#[derive(Deserialize, Default)]
+struct TranscriptParams {
+    #[serde(default)]
+    format: TranscriptFormat,
+}
+
+/// The transcript being recorded, in the format `request` asks for.
+fn transcript_reply(state: &ShellState, request: &Request) -> Result<Value, RpcError> {
+    let params: TranscriptParams = if request.params.is_null() {
+        TranscriptParams::default()
+    } else {
+        request.params()?
+    };
+    let Some(transcript) = state.transcript.as_ref() else {
+        return Err(RpcError::new(
+            RpcError::FAILED,
+            format!("{}: the session is not being recorded", request.method),
+        ));
+    };
+    let transcript = match params.format {
+        TranscriptFormat::Json => serde_json::to_value(transcript)
+            .map_err(|e| RpcError::new(RpcError::INTERNAL_ERROR, e.to_string()))?,
+        TranscriptFormat::Asciicast => Value::String(transcript.to_asciicast()),
+    };
+    Ok(json!({ "transcript": transcript }))
+}
+
+#[derive(Deserialize)]
+struct PathParams {
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct ReadParams {
+    path: String,
+    /// Answer with an outgoing stream instead of inline data.
+    #[serde(default)]
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct WriteParams {
+    path: String,
+    /// The contents, base64-encoded.
+    data: Option<String>,
+    /// An incoming stream holding the contents, in place of `data`.
+    stream: Option<u32>,
+    #[serde(default)]
+    append: bool,
+}
+
+/// Answer `request`. Relative paths are taken from the shell's cwd, and
+/// payloads too large to pass inline move through `streams`.
+pub fn dispatch(
+    state: &mut ShellState,
+    host: &dyn HostInterface,
+    streams: &mut Streams,
+    request: &Request,
+) -> Result<Value, RpcError> {
+    match request.method.as_str() {
+        method::SHELL_RUN => {
+            let params: RunParams = request.params()?;
+            let output = run_command(state, host, &params.command);
+            serde_json::to_value(output)
+                .map_err(|e| RpcError::new(RpcError::INTERNAL_ERROR, e.to_string()))
+        }
+        method::FS_READ => {
+            let params: ReadParams = request.params()?;
+            let data = host
+                .read_file(&state.resolve_path(&params.path))
+                .map_err(RpcError::failed)?;
+            if params.stream {
+                let size = data.len();
+                return Ok(json!({ "stream": streams.offer(data), "size": size }));
+            }
+            Ok(json!({ "data": BASE64.encode(data) }))
+        }
+        method::FS_WRITE => {
+            let params: WriteParams = request.params()?;
+            let data = match (params.data, params.stream) {
+                (Some(data), None) => BASE64.decode(data).map_err(|e| {
+                    RpcError::new(RpcError::INVALID_PARAMS, format!("fs.write: data: {e}"))
+                })?,
+                // A stream nothing was written to is an empty payload.
+                (None, Some(id)) => streams.take_incoming(id).unwrap_or_default(),
+                _ => {
+                    return Err(RpcError::new(
+                        RpcError::INVALID_PARAMS,
+                        "fs.write: give exactly one of data and stream",
+                    ))
+                }
+            };
+            let mode = if params.append {
+                WriteMode::Append
+            } else {
+                WriteMode::Truncate
+            };
+            host.write_file(&state.resolve_path(&params.path), &data, mode)
+                .map_err(RpcError::failed)?;
+            Ok(Value::Null)
+        }
+        method::FS_STAT => {
+            let params: PathParams = request.params()?;
+            let info = host
+                .stat(&state.resolve_path(&params.path))
+                .map_err(RpcError::failed)?;
+            serde_json::to_value(info)
+                .map_err(|e| RpcError::new(RpcError::INTERNAL_ERROR, e.to_string()))
+        }
+        method::TRANSCRIPT_START => {
+            transcript::start(state, host);
+            Ok(Value::Null)
+        }
+        method::TRANSCRIPT_GET => transcript_reply(state, request),
+        method::TRANSCRIPT_STOP => {
+            let reply = transcript_reply(state, request)?;
+            transcript::stop(state);
+            Ok(reply)
+        }
+        method::POLICY_SET => {
+            state.policy = request.params()?;
+            Ok(Value::Null)
+        }
+        method::STATUS => Ok(json!({
+            "cwd": state.cwd,
+            "last_exit_code": state.last_exit_code,
+            "commands_run": state.history.len(),
+            "total_time_ms": state.total_time_ms,
+        })),
+        other => Err(RpcError::method_not_found(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock::MockHost;
+    use codepod_rpc::{decode, encode, Outbox, Response};
+
+    #[test]
+    fn dispatches_requests_against_the_session() {
+        let host = MockHost::new().with_dir("/home/user");
+        let mut state = ShellState::new_default();
+        state.cwd = "/home/user".into();
+        let mut outbox = Outbox::new();
+        let mut streams = Streams::new();
+        let mut next_id = 0;
+        let mut call = |state: &mut ShellState, name: &str, params: Value| {
+            next_id += 1;
+            let request = encode(&Request::new(next_id, name, params));
+            let mut out = vec![0u8; 4096];
+            let n = outbox.answer(&request, &mut out, |req| {
+                dispatch(state, &host, &mut streams, req)
+            });
+            let response: Response = decode(&out[..n]).unwrap();
+            assert_eq!(response.id, next_id);
+            response.into_result()
+        };
+
+        let written = BASE64.encode("hi\n");
+        call(
+            &mut state,
+            method::FS_WRITE,
+            json!({ "path": "a.txt", "data": written }),
+        )
+        .unwrap();
+        let run = call(
+            &mut state,
+            method::SHELL_RUN,
+            json!({ "command": "read l < a.txt; echo \"$l!\" > b.txt; X=1" }),
+        )
+        .unwrap();
+        assert_eq!(run["exit_code"], 0);
+        assert_eq!(host.get_file("/home/user/b.txt").as_deref(), Some("hi!\n"));
+        assert_eq!(run["env"]["X"], "1");
+
+        let read = call(
+            &mut state,
+            method::FS_READ,
+            json!({ "path": "/home/user/a.txt" }),
+        )
+        .unwrap();
+        assert_eq!(read["data"], written);
+        let stat = call(&mut state, method::FS_STAT, json!({ "path": "a.txt" })).unwrap();
+        assert_eq!(stat["size"], 3);
+        let status = call(&mut state, method::STATUS, Value::Null).unwrap();
+        assert_eq!(status["cwd"], "/home/user");
+        assert_eq!(status["commands_run"], 1);
+
+        let err = call(&mut state, method::FS_READ, json!({ "path": "missing" })).unwrap_err();
+        assert_eq!(err.code, RpcError::FAILED);
+        let err = call(&mut state, method::SHELL_RUN, json!({ "cmd": "true" })).unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_PARAMS);
+        let err = call(&mut state, "shell.eval", Value::Null).unwrap_err();
+        assert_eq!(err.code, RpcError::METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn large_files_move_through_streams() {
+        let host = MockHost::new().with_dir("/tmp");
+        let mut state = ShellState::new_default();
+        let mut streams = Streams::new();
+        let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+
+        for chunk in payload.chunks(64 * 1024) {
+            streams.write_chunk(1, chunk);
+        }
+        let write = Request::new(
+            1,
+            method::FS_WRITE,
+            json!({ "path": "/tmp/big", "stream": 1 }),
+        );
+        dispatch(&mut state, &host, &mut streams, &write).unwrap();
+
+        let read = Request::new(
+            2,
+            method::FS_READ,
+            json!({ "path": "/tmp/big", "stream": true }),
+        );
+        let reply = dispatch(&mut state, &host, &mut streams, &read).unwrap();
+        assert_eq!(reply["size"], 200_000);
+        let id = reply["stream"].as_u64().unwrap() as u32;
+        let mut received = Vec::new();
+        let mut chunk = vec![0u8; 64 * 1024];
+        while let Some(n @ 1..) = streams.read_chunk(id, &mut chunk) {
+            received.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(received, payload);
+
+        let both = Request::new(
+            3,
+            method::FS_WRITE,
+            json!({ "path": "/tmp/x", "data": "", "stream": 2 }),
+        );
+        let err = dispatch(&mut state, &host, &mut streams, &both).unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn transcripts_are_started_read_and_stopped() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let mut streams = Streams::new();
+        let mut call = |state: &mut ShellState, name: &str, params: Value| {
+            dispatch(state, &host, &mut streams, &Request::new(1, name, params))
+        };
+
+        let err = call(&mut state, method::TRANSCRIPT_GET, Value::Null).unwrap_err();
+        assert_eq!(err.code, RpcError::FAILED);
+        call(&mut state, method::TRANSCRIPT_START, Value::Null).unwrap();
+        call(
+            &mut state,
+            method::SHELL_RUN,
+            json!({ "command": "echo $((6 * 7))" }),
+        )
+        .unwrap();
+
+        let json = call(&mut state, method::TRANSCRIPT_GET, Value::Null).unwrap();
+        let entry = &json["transcript"]["entries"][0];
+        assert_eq!(entry["command"], "echo $((6 * 7))");
+        assert_eq!(entry["steps"][0]["argv"], json!(["echo", "42"]));
+        assert_eq!(entry["output"][0]["stream"], "stdout");
+        assert_eq!(entry["output"][0]["data"], "42\n");
+
+        let cast = call(
+            &mut state,
+            method::TRANSCRIPT_STOP,
+            json!({ "format": "asciicast" }),
+        )
+        .unwrap();
+        let cast = cast["transcript"].as_str().unwrap();
+        assert!(cast.ends_with("[0.0,\"o\",\"42\\r\\n\"]\n"), "{cast}");
+        let err = call(&mut state, method::TRANSCRIPT_STOP, Value::Null).unwrap_err();
+        assert_eq!(err.code, RpcError::FAILED);
+
+        call(&mut state, method::TRANSCRIPT_START, Value::Null).unwrap();
+        let err = call(
+            &mut state,
+            method::TRANSCRIPT_GET,
+            json!({ "format": "yaml" }),
+        )
+        .unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn policy_set_restricts_later_runs() {
+        let host = MockHost::new();
+        let mut state = ShellState::new_default();
+        let mut streams = Streams::new();
+        let mut call = |state: &mut ShellState, name: &str, params: Value| {
+            dispatch(state, &host, &mut streams, &Request::new(1, name, params))
+        };
+
+        let policy = json!({ "deny_args": [{ "command": "echo", "pattern": "secret" }] });
+        call(&mut state, method::POLICY_SET, policy).unwrap();
+        let run = call(
+            &mut state,
+            method::SHELL_RUN,
+            json!({ "command": "echo secret" }),
+        );
+        assert_eq!(run.unwrap()["exit_code"], 126);
+
+        let bad = json!({ "deny_args": [{ "command": "echo", "pattern": "[" }] });
+        let err = call(&mut state, method::POLICY_SET, bad).unwrap_err();
+        assert_eq!(err.code, RpcError::INVALID_PARAMS);
+        call(&mut state, method::POLICY_SET, json!({})).unwrap();
+        let run = call(
+            &mut state,
+            method::SHELL_RUN,
+            json!({ "command": "echo secret" }),
+        );
+        assert_eq!(run.unwrap()["exit_code"], 0);
+    }
+}
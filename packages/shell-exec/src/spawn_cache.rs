@@ -0,0 +1,716 @@
+//! Replaying repeated runs of side-effect-free commands.
+//!
+//! Generated scripts often run the same command on the same input many
+//! times over. Wrapping a host in [`CachingHost`] remembers the output and
+//! exit status of each run of a command the embedder has declared pure, and
+//! replays them for the next identical run instead of spawning it again.
+//!
+//! A run is identified by its program, arguments, `stdin_data`, working
+//! directory and a hash of its environment. Since a pure command's output
+//! can still depend on the files it reads, any write made through the
+//! wrapper empties the cache, as does spawning a command not declared pure.
+//! Writes made around the wrapper are not seen; call
+//! [`CachingHost::clear`] after them.
+
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::control::CancelReason;
+use crate::host::{
+    DuplexChild, FetchRequest, FetchResult, FileChange, FileWatch, HostError, HostInterface,
+    SpawnLimitKind, SpawnLimits, SpawnResult, StatInfo, StorageQuota, StreamingChild, TerminalSize,
+    WriteMode,
+};
+
+/// Most output, stdout and stderr together, a run may produce and still be
+/// cached. A replay writes it all before anyone reads it, so it has to fit
+/// in a pipe's buffer.
+pub const MAX_CACHED_OUTPUT: usize = 64 * 1024;
+
+/// Most runs kept at once. Further runs are not cached until the next
+/// invalidation empties the cache.
+pub const MAX_CACHED_RUNS: usize = 256;
+
+/// Pids handed out for replayed runs start here, well clear of the host's.
+const REPLAY_PID_BASE: i32 = 1 << 24;
+
+/// Everything a pure command's output may depend on, apart from files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SpawnKey {
+    program: String,
+    args: Vec<String>,
+    stdin: String,
+    cwd: String,
+    env: u64,
+    /// Whether stderr went to the same place as stdout (`2>&1`).
+    merged: bool,
+}
+
+impl SpawnKey {
+    fn new(
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin: &str,
+        merged: bool,
+    ) -> Self {
+        let mut sorted = env.to_vec();
+        sorted.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        sorted.hash(&mut hasher);
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            stdin: stdin.to_string(),
+            cwd: cwd.to_string(),
+            env: hasher.finish(),
+            merged,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedRun {
+    stdout: Vec<u8>,
+    /// Empty when stderr was merged into stdout.
+    stderr: Vec<u8>,
+    exit_code: i32,
+}
+
+/// One output stream of a run being recorded: the pipe the child writes
+/// to, and the shell's own copy of the fd it was asked to write to.
+#[derive(Debug)]
+struct Capture {
+    from: i32,
+    to: i32,
+    data: Vec<u8>,
+}
+
+/// A cached command that is running for the first time, with its output
+/// passing through the wrapper on the way to its real destination.
+#[derive(Debug)]
+struct Recording {
+    key: SpawnKey,
+    /// Stdout, then stderr unless merged.
+    captures: Vec<Capture>,
+    limits: SpawnLimits,
+    /// The cache generation the run started in.
+    generation: u64,
+    killed: bool,
+}
+
+impl Recording {
+    /// Pass on whatever the child has written since the last call.
+    fn forward(&mut self, host: &dyn HostInterface) -> Result<(), HostError> {
+        for capture in &mut self.captures {
+            let chunk = host.read_fd(capture.from)?;
+            if !chunk.is_empty() {
+                host.write_fd(capture.to, &chunk)?;
+                if capture.data.len() <= MAX_CACHED_OUTPUT {
+                    capture.data.extend_from_slice(&chunk);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn close(&self, host: &dyn HostInterface) {
+        for capture in &self.captures {
+            let _ = host.close_fd(capture.from);
+            let _ = host.close_fd(capture.to);
+        }
+    }
+
+    /// The run to remember, if this one may be replayed.
+    fn into_cached(mut self, exit_code: i32) -> Option<CachedRun> {
+        let size: usize = self.captures.iter().map(|c| c.data.len()).sum();
+        if self.killed
+            || size > MAX_CACHED_OUTPUT
+            || SpawnLimitKind::from_exit(&self.limits, exit_code).is_some()
+        {
+            return None;
+        }
+        let stderr = match self.captures.len() {
+            2 => self.captures.pop().map(|c| c.data).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let stdout = self.captures.pop().map(|c| c.data).unwrap_or_default();
+        Some(CachedRun {
+            stdout,
+            stderr,
+            exit_code,
+        })
+    }
+}
+
+/// A host that replays earlier runs of the commands listed as pure rather
+/// than spawning them again. Everything else passes straight through.
+///
+/// Only runs whose whole input is in `stdin_data` are cached; a command
+/// reading a pipe, a redirected file or a terminal is always run.
+pub struct CachingHost<'a> {
+    host: &'a dyn HostInterface,
+    pure: HashSet<String>,
+    runs: RefCell<HashMap<SpawnKey, CachedRun>>,
+    recording: RefCell<HashMap<i32, Recording>>,
+    /// Exit statuses of replayed runs not yet waited for.
+    replayed: RefCell<HashMap<i32, i32>>,
+    next_replay_pid: Cell<i32>,
+    /// Bumped by every invalidation, so a run that overlapped one is not
+    /// cached.
+    generation: Cell<u64>,
+}
+
+impl<'a> CachingHost<'a> {
+    /// Cache runs of the programs named in `pure`, which must produce the
+    /// same output for the same arguments, input, directory, environment
+    /// and files, and must not change any files themselves.
+    pub fn new(host: &'a dyn HostInterface, pure: &[&str]) -> Self {
+        Self {
+            host,
+            pure: pure.iter().map(|p| p.to_string()).collect(),
+            runs: RefCell::new(HashMap::new()),
+            recording: RefCell::new(HashMap::new()),
+            replayed: RefCell::new(HashMap::new()),
+            next_replay_pid: Cell::new(REPLAY_PID_BASE),
+            generation: Cell::new(0),
+        }
+    }
+
+    /// Forget every cached run.
+    pub fn clear(&self) {
+        self.runs.borrow_mut().clear();
+        self.generation.set(self.generation.get() + 1);
+    }
+
+    /// How many runs are cached.
+    pub fn len(&self) -> usize {
+        self.runs.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn is_pure(&self, program: &str) -> bool {
+        self.pure.contains(program)
+    }
+
+    /// Empty the cache after a call that may have changed files.
+    fn writing<T>(&self, result: T) -> T {
+        self.clear();
+        result
+    }
+
+    /// Write a cached run's output and return the pid to wait on.
+    fn replay(&self, run: &CachedRun, stdout_fd: i32, stderr_fd: i32) -> i32 {
+        if !run.stdout.is_empty() {
+            let _ = self.host.write_fd(stdout_fd, &run.stdout);
+        }
+        if !run.stderr.is_empty() {
+            let _ = self.host.write_fd(stderr_fd, &run.stderr);
+        }
+        let pid = self.next_replay_pid.get();
+        self.next_replay_pid.set(pid + 1);
+        self.replayed.borrow_mut().insert(pid, run.exit_code);
+        pid
+    }
+
+    /// Spawn a cacheable command with its output going through capture
+    /// pipes, so it can be recorded on the way past.
+    #[allow(clippy::too_many_arguments)]
+    fn record(
+        &self,
+        key: SpawnKey,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        let targets: &[i32] = if key.merged {
+            &[stdout_fd]
+        } else {
+            &[stdout_fd, stderr_fd]
+        };
+        let mut captures = Vec::new();
+        let mut child_fds = Vec::new();
+        let mut opened = Ok(());
+        for &target in targets {
+            let pair = self
+                .host
+                .pipe()
+                .and_then(|(r, w)| match self.host.dup(target) {
+                    Ok(to) => Ok((r, w, to)),
+                    Err(e) => {
+                        let _ = self.host.close_fd(r);
+                        let _ = self.host.close_fd(w);
+                        Err(e)
+                    }
+                });
+            match pair {
+                Ok((from, w, to)) => {
+                    child_fds.push(w);
+                    captures.push(Capture {
+                        from,
+                        to,
+                        data: Vec::new(),
+                    });
+                }
+                Err(e) => {
+                    opened = Err(e);
+                    break;
+                }
+            }
+        }
+        let spawned = opened.and_then(|()| {
+            let child_stdout = child_fds[0];
+            let child_stderr = *child_fds.last().unwrap_or(&child_stdout);
+            self.host.spawn_with_limits(
+                program,
+                args,
+                env,
+                cwd,
+                stdin_data,
+                stdin_fd,
+                child_stdout,
+                child_stderr,
+                nice,
+                limits,
+            )
+        });
+        for fd in child_fds {
+            let _ = self.host.close_fd(fd);
+        }
+        let recording = Recording {
+            key,
+            captures,
+            limits: limits.clone(),
+            generation: self.generation.get(),
+            killed: false,
+        };
+        match spawned {
+            Ok(pid) => {
+                self.recording.borrow_mut().insert(pid, recording);
+                Ok(pid)
+            }
+            Err(e) => {
+                recording.close(self.host);
+                Err(e)
+            }
+        }
+    }
+
+    /// Forward a recording child's output and, once it has exited, finish
+    /// the recording and return its result.
+    fn poll_recording(&self, pid: i32) -> Result<Option<SpawnResult>, HostError> {
+        let mut recordings = self.recording.borrow_mut();
+        let Some(recording) = recordings.get_mut(&pid) else {
+            return Ok(None);
+        };
+        recording.forward(self.host)?;
+        if self.host.waitpid_nohang(pid)? < 0 {
+            return Ok(None);
+        }
+        // Pick up anything written between the forward and the exit.
+        recording.forward(self.host)?;
+        let Some(recording) = recordings.remove(&pid) else {
+            return Ok(None);
+        };
+        drop(recordings);
+        recording.close(self.host);
+        let result = self.host.waitpid(pid)?;
+        let current = recording.generation == self.generation.get();
+        let key = recording.key.clone();
+        if let Some(run) = recording.into_cached(result.exit_code) {
+            let mut runs = self.runs.borrow_mut();
+            if current && runs.len() < MAX_CACHED_RUNS {
+                runs.insert(key, run);
+            }
+        }
+        Ok(Some(result))
+    }
+
+    fn is_recording(&self, pid: i32) -> bool {
+        self.recording.borrow().contains_key(&pid)
+    }
+}
+
+impl HostInterface for CachingHost<'_> {
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        self.spawn_with_limits(
+            program,
+            args,
+            env,
+            cwd,
+            stdin_data,
+            stdin_fd,
+            stdout_fd,
+            stderr_fd,
+            nice,
+            &SpawnLimits::default(),
+        )
+    }
+
+    fn spawn_with_limits(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        if !self.is_pure(program) {
+            self.clear();
+        } else if stdin_fd == 0 && !self.host.isatty(0) {
+            let merged = stdout_fd == stderr_fd;
+            let key = SpawnKey::new(program, args, env, cwd, stdin_data, merged);
+            if let Some(run) = self.runs.borrow().get(&key) {
+                return Ok(self.replay(run, stdout_fd, stderr_fd));
+            }
+            return self.record(
+                key, program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice,
+                limits,
+            );
+        }
+        self.host.spawn_with_limits(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice, limits,
+        )
+    }
+
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        if !self.is_pure(program) {
+            self.clear();
+        }
+        self.host.spawn_duplex(program, args, env, cwd)
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        if !self.is_pure(program) {
+            self.clear();
+        }
+        self.host.spawn_streaming(program, args, env, cwd)
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        self.host.has_tool(name)
+    }
+
+    fn time(&self) -> f64 {
+        self.host.time()
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        self.host.monotonic_ms()
+    }
+
+    fn now_unix_ms(&self) -> u64 {
+        self.host.now_unix_ms()
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        self.host.monotonic_ns()
+    }
+
+    fn should_cancel(&self) -> Option<CancelReason> {
+        self.host.should_cancel()
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        self.host.random_bytes(n)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        self.host.isatty(fd)
+    }
+
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        self.host.terminal_size(fd)
+    }
+
+    fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+        self.host.stat(path)
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        self.host.read_file(path)
+    }
+
+    fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
+        self.writing(self.host.write_file(path, data, mode))
+    }
+
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        self.host.storage_quota()
+    }
+
+    fn check_writable(&self, path: &str) -> Result<(), HostError> {
+        self.host.check_writable(path)
+    }
+
+    fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
+        self.host.readdir(path)
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), HostError> {
+        self.writing(self.host.mkdir(path))
+    }
+
+    fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+        self.writing(self.host.remove(path, recursive))
+    }
+
+    fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        self.writing(self.host.chmod(path, mode))
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+        self.writing(self.host.set_mtime(path, mtime_ms))
+    }
+
+    fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
+        self.host.glob(pattern)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+        self.writing(self.host.rename(from, to))
+    }
+
+    fn write_file_atomic(&self, path: &str, data: &[u8]) -> Result<(), HostError> {
+        self.writing(self.host.write_file_atomic(path, data))
+    }
+
+    fn create_temp_file(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.writing(self.host.create_temp_file(dir, prefix))
+    }
+
+    fn create_temp_dir(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.writing(self.host.create_temp_dir(dir, prefix))
+    }
+
+    fn watch(&self, path: &str) -> Result<FileWatch, HostError> {
+        self.host.watch(path)
+    }
+
+    fn wait_for_change(
+        &self,
+        watch: &mut FileWatch,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        self.host.wait_for_change(watch, timeout_ms)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+        self.writing(self.host.symlink(target, link_path))
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, HostError> {
+        self.host.readlink(path)
+    }
+
+    fn canonicalize(&self, path: &str) -> String {
+        self.host.canonicalize(path)
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        self.host.fetch(request)
+    }
+
+    /// Registering a tool can change what a program name runs, so it
+    /// invalidates the cache like a write.
+    fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError> {
+        self.writing(self.host.register_tool(name, wasm_path))
+    }
+
+    fn pipe(&self) -> Result<(i32, i32), HostError> {
+        self.host.pipe()
+    }
+
+    fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        if let Some(exit_code) = self.replayed.borrow_mut().remove(&pid) {
+            return Ok(SpawnResult { exit_code });
+        }
+        if !self.is_recording(pid) {
+            return self.host.waitpid(pid);
+        }
+        loop {
+            if let Some(result) = self.poll_recording(pid)? {
+                return Ok(result);
+            }
+            self.host.yield_now()?;
+        }
+    }
+
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        if let Some(exit_code) = self.replayed.borrow_mut().remove(&pid) {
+            return Ok(Some(SpawnResult { exit_code }));
+        }
+        if !self.is_recording(pid) {
+            return self.host.waitpid_timeout(pid, timeout_ms);
+        }
+        let deadline = self.host.monotonic_ms() + f64::from(timeout_ms);
+        loop {
+            if let Some(result) = self.poll_recording(pid)? {
+                return Ok(Some(result));
+            }
+            if self.host.monotonic_ms() >= deadline {
+                return Ok(None);
+            }
+            self.host.yield_now()?;
+        }
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        if self.replayed.borrow().contains_key(&pid) {
+            // A replayed run has already finished.
+            return Ok(());
+        }
+        if let Some(recording) = self.recording.borrow_mut().get_mut(&pid) {
+            recording.killed = true;
+        }
+        self.host.kill(pid, signal)
+    }
+
+    fn close_fd(&self, fd: i32) -> Result<(), HostError> {
+        self.host.close_fd(fd)
+    }
+
+    fn dup(&self, fd: i32) -> Result<i32, HostError> {
+        self.host.dup(fd)
+    }
+
+    fn dup2(&self, src_fd: i32, dst_fd: i32) -> Result<(), HostError> {
+        self.host.dup2(src_fd, dst_fd)
+    }
+
+    fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
+        self.host.read_fd(fd)
+    }
+
+    fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
+        self.host.write_fd(fd, data)
+    }
+
+    fn yield_now(&self) -> Result<(), HostError> {
+        self.host.yield_now()
+    }
+
+    fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
+        if let Some(&exit_code) = self.replayed.borrow().get(&pid) {
+            return Ok(exit_code);
+        }
+        if !self.is_recording(pid) {
+            return self.host.waitpid_nohang(pid);
+        }
+        // Finishing the recording reaps the child, so keep its status for
+        // the waitpid that follows.
+        match self.poll_recording(pid)? {
+            Some(result) => {
+                self.replayed.borrow_mut().insert(pid, result.exit_code);
+                Ok(result.exit_code)
+            }
+            None => Ok(-1),
+        }
+    }
+
+    fn list_processes(&self) -> Result<String, HostError> {
+        self.host.list_processes()
+    }
+
+    fn socket_connect(&self, host: &str, port: u16, tls: bool) -> Result<u32, HostError> {
+        self.host.socket_connect(host, port, tls)
+    }
+
+    fn socket_send(&self, socket_id: u32, data: &[u8]) -> Result<usize, HostError> {
+        self.host.socket_send(socket_id, data)
+    }
+
+    fn socket_recv(&self, socket_id: u32, max_bytes: usize) -> Result<Vec<u8>, HostError> {
+        self.host.socket_recv(socket_id, max_bytes)
+    }
+
+    fn socket_close(&self, socket_id: u32) -> Result<(), HostError> {
+        self.host.socket_close(socket_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::exec_command;
+    use crate::state::ShellState;
+    use crate::test_support::mock::{MockHost, MockSpawnOutput};
+
+    #[test]
+    fn pure_commands_are_replayed_until_a_write() {
+        let inner = MockHost::new()
+            .with_dir("/tmp")
+            .with_spawn_handler(|program, args, _stdin| MockSpawnOutput {
+                exit_code: 0,
+                stdout: format!("{program} {}", args.join(" ")),
+                stderr: String::new(),
+            });
+        let host = CachingHost::new(&inner, &["digest"]);
+        let mut state = ShellState::new_default();
+        let mut run = |src: &str| {
+            let _ = exec_command(&mut state, &host, &codepod_shell::parser::parse(src));
+        };
+
+        run("echo \"$(digest x)|$(digest x)|$(digest y)\" > /tmp/out");
+        assert_eq!(inner.get_spawn_calls().len(), 2);
+        assert_eq!(
+            inner.get_file("/tmp/out").as_deref(),
+            Some("digest x|digest x|digest y\n")
+        );
+
+        // The redirect wrote a file, so the next run is real.
+        assert!(host.is_empty());
+        run("echo $(digest x) $(digest x)");
+        assert_eq!(inner.get_spawn_calls().len(), 3);
+        assert_eq!(host.len(), 1);
+
+        // So is one with a different environment.
+        run("X=1 digest x");
+        assert_eq!(inner.get_spawn_calls().len(), 4);
+
+        // Commands not declared pure always run, and empty the cache.
+        run("other; other; digest x");
+        assert_eq!(inner.get_spawn_calls().len(), 7);
+    }
+}
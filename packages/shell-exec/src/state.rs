@@ -1,15 +1,61 @@
 use std::collections::{HashMap, HashSet};
 
 use codepod_shell::ast::Command;
+use serde::{Deserialize, Serialize};
 
+use crate::control::LimitKind;
+use crate::host::{HostInterface, SpawnLimits};
+use crate::policy::CommandPolicy;
+use crate::transcript::Transcript;
+
+/// Default for [`Limits::max_substitution_depth`].
 pub const MAX_SUBSTITUTION_DEPTH: u32 = 50;
+/// Default for [`Limits::max_function_depth`].
 pub const MAX_FUNCTION_DEPTH: u32 = 100;
+/// Default for [`Limits::max_loop_iterations`].
+pub const MAX_LOOP_ITERATIONS: u64 = 100_000;
+
+/// Resource limits enforced while commands run. Exceeding one stops the run
+/// with [`ShellError::LimitExceeded`](crate::control::ShellError::LimitExceeded).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Deepest nesting of shell function calls.
+    pub max_function_depth: u32,
+    /// Deepest nesting of command and process substitutions.
+    pub max_substitution_depth: u32,
+    /// Most iterations a single `for`, `while`, `until` or `select` loop may run.
+    pub max_loop_iterations: u64,
+    /// Most bytes of output that command and process substitutions may
+    /// capture in one run, in total.
+    pub max_captured_bytes: usize,
+    /// Most command and process substitutions one run may perform.
+    pub max_substitutions: u64,
+    /// Budget applied to each external command the shell spawns.
+    pub spawn: SpawnLimits,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_function_depth: MAX_FUNCTION_DEPTH,
+            max_substitution_depth: MAX_SUBSTITUTION_DEPTH,
+            max_loop_iterations: MAX_LOOP_ITERATIONS,
+            max_captured_bytes: usize::MAX,
+            max_substitutions: u64::MAX,
+            spawn: SpawnLimits::default(),
+        }
+    }
+}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ShellFlag {
     Errexit,
     Nounset,
     Pipefail,
+    Noclobber,
+    /// `set -P`: `cd` resolves symlinks instead of keeping the logical path.
+    Physical,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +75,8 @@ pub struct ShellState {
     pub aliases: HashMap<String, String>,
     pub flags: HashSet<ShellFlag>,
     pub positional_args: Vec<String>,
+    /// Value of `$0`: the script path when running a script file.
+    pub script_name: String,
     pub last_exit_code: i32,
     pub function_depth: u32,
     pub substitution_depth: u32,
@@ -48,7 +96,8 @@ pub struct ShellState {
     pub dir_stack: Vec<String>,
     /// Captured groups from last `[[ ... =~ ... ]]` regex match.
     pub bash_rematch: Vec<String>,
-    /// Counter for generating unique process substitution temp file paths.
+    /// Numbers process substitution files when the host cannot create
+    /// temporary files itself.
     pub proc_sub_counter: u32,
     /// Current stdout fd for the executing context (default: 1).
     /// Pipeline stages override this to write to pipe fds.
@@ -61,9 +110,140 @@ pub struct ShellState {
     pub next_job_id: usize,
     /// PID of most recently backgrounded process ($!).
     pub last_bg_pid: i32,
+    /// Set by `cmd &` for the next simple command: an external command is
+    /// spawned without waiting and its pid stored in `last_bg_pid`.
+    pub spawn_in_background: bool,
+    /// Open numbered fds (>= 3), mapped to the file each one writes to.
+    /// Populated by `exec N>file`, `{var}>file`, and compound-command redirects.
+    pub fd_table: HashMap<i32, String>,
+    /// Cumulative wall time (ms) of completed top-level commands. Reported by
+    /// a bare `time`.
+    pub total_time_ms: u64,
+    /// Monotonic time (ms, see `HostInterface::monotonic_ms`) after which
+    /// running children are killed and the script stops with a timeout.
+    /// Set by embedders for a per-script limit and narrowed by `timeout`.
+    pub deadline_ms: Option<f64>,
+    /// Resource limits; see [`Limits`].
+    pub limits: Limits,
+    /// The commands this session may run; see [`CommandPolicy`].
+    pub policy: CommandPolicy,
+    /// Substitutions performed so far in the current run.
+    pub substitution_count: u64,
+    /// Bytes captured by substitutions so far in the current run.
+    pub captured_bytes: usize,
+    /// Set when a limit is exceeded somewhere that cannot return an error,
+    /// such as word expansion. The executor then unwinds the whole run with
+    /// `ShellError::LimitExceeded`.
+    pub limit_exceeded: Option<LimitKind>,
+    /// The session being recorded, between `transcript::start` and
+    /// `transcript::stop`.
+    pub transcript: Option<Transcript>,
+}
+
+/// The part of a [`ShellState`] that outlives a single command: variables,
+/// functions, aliases, options, history and the like. A host can take a
+/// snapshot with [`ShellState::snapshot`], persist it as JSON, and resume the
+/// session later with [`ShellState::restore`], possibly in another
+/// executor instance.
+///
+/// Numbered fds that write to files are included, so `exec 3>log` keeps
+/// working in the resumed session. Pipe-backed fds (coprocesses, process
+/// substitutions), running jobs and per-run counters belong to the instance
+/// that created them and are not. Fields missing from older snapshots take
+/// their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSnapshot {
+    pub env: HashMap<String, String>,
+    pub cwd: String,
+    pub functions: HashMap<String, Command>,
+    pub aliases: HashMap<String, String>,
+    pub arrays: HashMap<String, Vec<String>>,
+    pub assoc_arrays: HashMap<String, HashMap<String, String>>,
+    pub options: HashSet<ShellFlag>,
+    pub readonly_vars: HashSet<String>,
+    pub positional_args: Vec<String>,
+    pub traps: HashMap<String, String>,
+    pub history: Vec<String>,
+    pub dir_stack: Vec<String>,
+    pub last_exit_code: i32,
+    pub fds: HashMap<i32, String>,
 }
 
 impl ShellState {
+    /// Capture the session state; see [`SessionSnapshot`].
+    pub fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            env: self.env.clone(),
+            cwd: self.cwd.clone(),
+            functions: self.functions.clone(),
+            aliases: self.aliases.clone(),
+            arrays: self.arrays.clone(),
+            assoc_arrays: self.assoc_arrays.clone(),
+            options: self.flags.clone(),
+            readonly_vars: self.readonly_vars.clone(),
+            positional_args: self.positional_args.clone(),
+            traps: self.traps.clone(),
+            history: self.history.clone(),
+            dir_stack: self.dir_stack.clone(),
+            last_exit_code: self.last_exit_code,
+            fds: self
+                .fd_table
+                .iter()
+                .filter(|(_, path)| !path.starts_with("/dev/fd/"))
+                .map(|(fd, path)| (*fd, path.clone()))
+                .collect(),
+        }
+    }
+
+    /// Replace the session state with `snapshot`, leaving jobs, limits and
+    /// the command policy as they are. File-backed fds are replaced by the snapshot's; this
+    /// instance's own pipe fds stay open. An empty `cwd` keeps the current
+    /// directory.
+    pub fn restore(&mut self, snapshot: SessionSnapshot) {
+        self.env = snapshot.env;
+        if !snapshot.cwd.is_empty() {
+            self.cwd = snapshot.cwd;
+        }
+        self.functions = snapshot.functions;
+        self.aliases = snapshot.aliases;
+        self.arrays = snapshot.arrays;
+        self.assoc_arrays = snapshot.assoc_arrays;
+        self.flags = snapshot.options;
+        self.readonly_vars = snapshot.readonly_vars;
+        self.positional_args = snapshot.positional_args;
+        self.traps = snapshot.traps;
+        self.history = snapshot.history;
+        self.dir_stack = snapshot.dir_stack;
+        self.last_exit_code = snapshot.last_exit_code;
+        self.fd_table.retain(|_, path| path.starts_with("/dev/fd/"));
+        self.fd_table.extend(snapshot.fds);
+    }
+
+    /// Start over with a fresh session, as a newly created instance would,
+    /// so a host can hand a warm instance to its next user. Running jobs are
+    /// killed and pipe fds closed on the host; the limits and the command
+    /// policy are kept.
+    pub fn reset(&mut self, host: &dyn HostInterface) {
+        for job in self.jobs.iter().filter(|job| job.done.is_none()) {
+            let _ = host.kill(job.pid, 9);
+        }
+        let pipes: HashSet<i32> = self
+            .fd_table
+            .values()
+            .filter_map(|path| path.strip_prefix("/dev/fd/")?.parse().ok())
+            .collect();
+        for fd in pipes {
+            let _ = host.close_fd(fd);
+        }
+        let limits = std::mem::take(&mut self.limits);
+        let policy = std::mem::take(&mut self.policy);
+        *self = Self::new_default();
+        self.limits = limits;
+        self.policy = policy;
+        self.seed_random(host);
+    }
+
     pub fn new_default() -> Self {
         let mut env = HashMap::new();
         env.insert("HOME".into(), "/home/user".into());
@@ -81,6 +261,7 @@ impl ShellState {
             aliases: HashMap::new(),
             flags: HashSet::new(),
             positional_args: Vec::new(),
+            script_name: "codepod-shell".to_string(),
             last_exit_code: 0,
             function_depth: 0,
             substitution_depth: 0,
@@ -88,7 +269,7 @@ impl ShellState {
             local_var_stack: Vec::new(),
             history: Vec::new(),
             cwd: "/home/user".into(),
-            rng_seed: 12345, // deterministic default; host can override
+            rng_seed: 12345, // deterministic default; see seed_random
             param_error: None,
             pipeline_stdin: None,
             readonly_vars: HashSet::new(),
@@ -100,9 +281,78 @@ impl ShellState {
             jobs: Vec::new(),
             next_job_id: 1,
             last_bg_pid: 0,
+            spawn_in_background: false,
+            fd_table: HashMap::new(),
+            total_time_ms: 0,
+            deadline_ms: None,
+            limits: Limits::default(),
+            policy: CommandPolicy::default(),
+            substitution_count: 0,
+            captured_bytes: 0,
+            limit_exceeded: None,
+            transcript: None,
+        }
+    }
+
+    /// Start a new run (a top-level command, script or prompt line): clear
+    /// the per-run substitution totals, any exceeded limit, and an interrupt
+    /// requested after the previous run had finished.
+    pub fn begin_run(&mut self) {
+        codepod_rpc::cancel::clear();
+        self.substitution_count = 0;
+        self.captured_bytes = 0;
+        self.limit_exceeded = None;
+    }
+
+    /// The single-letter options in effect, as `$-` reports them.
+    pub fn flag_letters(&self) -> String {
+        [
+            (ShellFlag::Errexit, 'e'),
+            (ShellFlag::Nounset, 'u'),
+            (ShellFlag::Noclobber, 'C'),
+            (ShellFlag::Physical, 'P'),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| self.flags.contains(flag))
+        .map(|(_, letter)| letter)
+        .collect()
+    }
+
+    /// Lowest fd >= 10 not yet in `fd_table`, as used by `{var}>file`.
+    pub fn next_free_fd(&self) -> i32 {
+        (10..)
+            .find(|fd| !self.fd_table.contains_key(fd))
+            .unwrap_or(10)
+    }
+
+    /// Seed `$RANDOM` from the host's random source. Hosts without one, or
+    /// that return a zero seed (a fixed point of the generator), keep the
+    /// current seed.
+    pub fn seed_random(&mut self, host: &dyn HostInterface) {
+        if let Ok(bytes) = host.random_bytes(8) {
+            if let Ok(bytes) = <[u8; 8]>::try_from(bytes.as_slice()) {
+                let seed = u64::from_le_bytes(bytes);
+                if seed != 0 {
+                    self.rng_seed = seed;
+                }
+            }
         }
     }
 
+    /// Whether the shell's `fd` reaches a terminal, as `test -t` asks.
+    /// Stdin and stdout follow any pipe or capture the current command is
+    /// plumbed into, and fds redirected onto files never do.
+    pub fn isatty(&self, host: &dyn HostInterface, fd: i32) -> bool {
+        if self.fd_table.contains_key(&fd) {
+            return false;
+        }
+        host.isatty(match fd {
+            0 => self.stdin_fd,
+            1 => self.stdout_fd,
+            fd => fd,
+        })
+    }
+
     pub fn resolve_path(&self, path: &str) -> String {
         if path.starts_with('/') {
             return path.to_string();
@@ -113,4 +363,10 @@ impl ShellState {
             format!("{}/{path}", self.cwd)
         }
     }
+    /// Resolve `path` like `resolve_path`, then make it physical with
+    /// `HostInterface::canonicalize`, giving the path `cd -P` and `pwd -P`
+    /// report.
+    pub fn resolve_path_physical(&self, host: &dyn HostInterface, path: &str) -> String {
+        host.canonicalize(&self.resolve_path(path))
+    }
 }
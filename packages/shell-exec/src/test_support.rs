@@ -1,10 +1,16 @@
 #[cfg(test)]
 pub mod mock {
     use std::cell::RefCell;
-    use std::collections::{HashMap, HashSet};
+    use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
     use std::sync::Mutex;
 
-    use crate::host::{FetchResult, HostError, HostInterface, SpawnResult, StatInfo, WriteMode};
+    use serde::{Deserialize, Serialize};
+
+    use crate::control::CancelReason;
+    use crate::host::{
+        DuplexChild, FetchRequest, FetchResult, HostError, HostInterface, SpawnResult, StatInfo,
+        StorageQuota, StreamingChild, TerminalSize, WriteMode,
+    };
 
     /// Mutex to serialize dup2 operations on fd 1 across test threads.
     pub static FD_MUTEX: Mutex<()> = Mutex::new(());
@@ -26,13 +32,250 @@ pub mod mock {
         pub stderr: String,
     }
 
+    /// One host call captured by a recording `MockHost`. File contents are
+    /// kept as text, lossily converted from UTF-8, so fixtures stay readable.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(tag = "call", rename_all = "snake_case")]
+    pub enum FixtureEvent {
+        Spawn {
+            program: String,
+            args: Vec<String>,
+            stdin: String,
+            exit_code: i32,
+            stdout: String,
+            stderr: String,
+        },
+        /// A `read_file`; `data` is `None` when the file was not there.
+        Read { path: String, data: Option<String> },
+        Write {
+            path: String,
+            data: String,
+            append: bool,
+        },
+    }
+
+    /// The spawns, reads and writes a script made, in order, as recorded
+    /// by [`MockHost::with_recording`]. Replaying it with
+    /// [`MockHost::with_replay`] checks a later run makes the same calls.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct Fixture {
+        pub events: Vec<FixtureEvent>,
+    }
+
+    impl Fixture {
+        pub fn to_json(&self) -> String {
+            serde_json::to_string_pretty(self).expect("fixture serializes")
+        }
+
+        pub fn from_json(json: &str) -> Self {
+            serde_json::from_str(json).expect("invalid fixture")
+        }
+    }
+
+    /// How many symlinks path resolution follows before giving up.
+    const MAX_LINK_DEPTH: usize = 40;
+
+    /// A node of the mock filesystem.
+    #[derive(Debug, Clone)]
+    enum Node {
+        File {
+            data: Vec<u8>,
+            mode: u32,
+            mtime_ms: u64,
+        },
+        Dir {
+            entries: BTreeMap<String, Node>,
+            mode: u32,
+            mtime_ms: u64,
+        },
+        Symlink(String),
+    }
+
+    impl Node {
+        fn file(data: Vec<u8>) -> Self {
+            Self::File {
+                data,
+                mode: 0o644,
+                mtime_ms: 0,
+            }
+        }
+
+        fn dir() -> Self {
+            Self::Dir {
+                entries: BTreeMap::new(),
+                mode: 0o755,
+                mtime_ms: 0,
+            }
+        }
+
+        /// Total size of the files at and below this node.
+        fn used_bytes(&self) -> u64 {
+            match self {
+                Self::File { data, .. } => data.len() as u64,
+                Self::Dir { entries, .. } => entries.values().map(Node::used_bytes).sum(),
+                Self::Symlink(_) => 0,
+            }
+        }
+    }
+
+    /// The components of `path`, with `.` and `..` resolved. Relative
+    /// paths are taken from `/`.
+    fn components(path: &str) -> Vec<String> {
+        crate::builtins::normalize_path(path)
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn join(components: &[String]) -> String {
+        format!("/{}", components.join("/"))
+    }
+
+    /// The mock filesystem: a tree of directories, files and symlinks
+    /// rooted at `/`. Missing parent directories are created as needed,
+    /// so tests can write `/a/b/c.txt` without making `/a` and `/a/b`.
+    #[derive(Debug)]
+    struct MockFs {
+        root: Node,
+        /// The most recent modification time handed out.
+        last_mtime: Option<u64>,
+    }
+
+    impl MockFs {
+        fn new() -> Self {
+            Self {
+                root: Node::dir(),
+                last_mtime: None,
+            }
+        }
+
+        /// The node at `path`, without following symlinks.
+        fn node(&self, path: &str) -> Option<&Node> {
+            let mut node = &self.root;
+            for name in components(path) {
+                let Node::Dir { entries, .. } = node else {
+                    return None;
+                };
+                node = entries.get(&name)?;
+            }
+            Some(node)
+        }
+
+        fn node_mut(&mut self, path: &str) -> Option<&mut Node> {
+            let mut node = &mut self.root;
+            for name in components(path) {
+                let Node::Dir { entries, .. } = node else {
+                    return None;
+                };
+                node = entries.get_mut(&name)?;
+            }
+            Some(node)
+        }
+
+        /// `path` with symlinks in its directories replaced by their
+        /// targets, and in its last component too when `follow_last`.
+        fn resolve(&self, path: &str, follow_last: bool) -> String {
+            let mut parts = components(path);
+            let mut i = 0;
+            let mut links = 0;
+            while i < parts.len() {
+                let prefix = join(&parts[..=i]);
+                match self.node(&prefix) {
+                    Some(Node::Symlink(target))
+                        if (follow_last || i + 1 < parts.len()) && links < MAX_LINK_DEPTH =>
+                    {
+                        links += 1;
+                        let base = if target.starts_with('/') {
+                            target.clone()
+                        } else {
+                            format!("{}/{target}", join(&parts[..i]))
+                        };
+                        let mut resolved = components(&base);
+                        resolved.extend_from_slice(&parts[i + 1..]);
+                        parts = resolved;
+                        i = 0;
+                    }
+                    _ => i += 1,
+                }
+            }
+            join(&parts)
+        }
+
+        /// The entries of the directory `path`, creating it and any
+        /// missing parents first.
+        fn dir_entries(&mut self, path: &str) -> Result<&mut BTreeMap<String, Node>, HostError> {
+            let mut node = &mut self.root;
+            for name in components(path) {
+                let Node::Dir { entries, .. } = node else {
+                    return Err(HostError::IoError(format!("{path}: Not a directory")));
+                };
+                node = entries.entry(name).or_insert_with(Node::dir);
+            }
+            match node {
+                Node::Dir { entries, .. } => Ok(entries),
+                _ => Err(HostError::IoError(format!("{path}: Not a directory"))),
+            }
+        }
+
+        /// Put `node` at `path`, replacing whatever was there.
+        fn insert(&mut self, path: &str, node: Node) -> Result<(), HostError> {
+            let mut parts = components(path);
+            let Some(name) = parts.pop() else {
+                return Err(HostError::IoError(format!("{path}: File exists")));
+            };
+            self.dir_entries(&join(&parts))?.insert(name, node);
+            Ok(())
+        }
+
+        /// Detach and return the node at `path`, without following a
+        /// symlink there.
+        fn take(&mut self, path: &str) -> Option<Node> {
+            let mut parts = components(path);
+            let name = parts.pop()?;
+            match self.node_mut(&join(&parts))? {
+                Node::Dir { entries, .. } => entries.remove(&name),
+                _ => None,
+            }
+        }
+
+        /// A modification time later than any handed out before.
+        fn stamp(&mut self, now_ms: u64) -> u64 {
+            let stamp = self.last_mtime.unwrap_or(now_ms) + 1;
+            self.last_mtime = Some(stamp);
+            stamp
+        }
+
+        /// Absolute paths under `dir` matching the glob components in
+        /// `pattern`, in order. Hidden names only match a component that
+        /// starts with `.`.
+        fn glob(&self, dir: &str, pattern: &[String], out: &mut Vec<String>) {
+            let Some((first, rest)) = pattern.split_first() else {
+                out.push(dir.to_string());
+                return;
+            };
+            let dir_path = self.resolve(dir, true);
+            let Some(Node::Dir { entries, .. }) = self.node(&dir_path) else {
+                return;
+            };
+            let base = dir.trim_end_matches('/');
+            for name in entries.keys() {
+                if name.starts_with('.') && !first.starts_with('.') {
+                    continue;
+                }
+                if crate::expand::glob_matches(first, name) {
+                    self.glob(&format!("{base}/{name}"), rest, out);
+                }
+            }
+        }
+    }
+
     /// An in-memory mock implementation of `HostInterface` for testing.
     ///
     /// Uses `RefCell` for the files map so that `write_file` can mutate state
     /// through a `&self` reference (as required by the `HostInterface` trait).
     pub struct MockHost {
-        files: RefCell<HashMap<String, Vec<u8>>>,
-        dirs: HashSet<String>,
+        fs: RefCell<MockFs>,
         tools: HashSet<String>,
         spawn_results: HashMap<String, MockSpawnOutput>,
         glob_results: HashMap<String, Vec<String>>,
@@ -50,6 +293,39 @@ pub mod mock {
         next_pid: RefCell<i32>,
         /// Stored spawn results keyed by PID, for waitpid to return exit codes.
         pid_results: RefCell<HashMap<i32, SpawnResult>>,
+        /// Wall-clock time reported by `now_unix_ms`.
+        unix_ms: u64,
+        /// State of the xorshift generator behind `random_bytes`.
+        random_state: RefCell<u64>,
+        /// Current reading of the mock monotonic clock, in milliseconds.
+        clock_ms: RefCell<f64>,
+        /// How far each spawn advances the mock clock.
+        spawn_duration_ms: f64,
+        /// How long each spawned command runs before it can be reaped.
+        spawn_latency_ms: f64,
+        /// Mock clock reading at which each running child finishes.
+        finish_ms: RefCell<HashMap<i32, f64>>,
+        /// Errors injected into writes, reads and spawns, keyed by path or
+        /// program name.
+        failing_writes: HashMap<String, fn(String) -> HostError>,
+        failing_reads: HashMap<String, fn(String) -> HostError>,
+        failing_spawns: HashMap<String, fn(String) -> HostError>,
+        /// Report cancellation once this many commands have been spawned.
+        cancel_after_spawns: Option<usize>,
+        /// `(pid, signal)` for every `kill` call, in order.
+        kills: RefCell<Vec<(i32, i32)>>,
+        /// pid → non-blocking read end of a duplex child's stdin pipe.
+        duplex_stdin: RefCell<HashMap<i32, i32>>,
+        /// Terminal that fds 0-2 are attached to, unless replaced by a pipe.
+        terminal: Option<TerminalSize>,
+        /// fds that currently refer to a pipe made by `pipe`.
+        pipe_fds: RefCell<HashSet<i32>>,
+        /// Most bytes the files may hold in total, if limited.
+        quota_bytes: Option<u64>,
+        /// Calls captured so far, when recording.
+        recorded: RefCell<Option<Vec<FixtureEvent>>>,
+        /// Calls still expected, when replaying a fixture.
+        replay: RefCell<Option<VecDeque<FixtureEvent>>>,
     }
 
     impl Default for MockHost {
@@ -61,8 +337,7 @@ pub mod mock {
     impl MockHost {
         pub fn new() -> Self {
             Self {
-                files: RefCell::new(HashMap::new()),
-                dirs: HashSet::new(),
+                fs: RefCell::new(MockFs::new()),
                 tools: HashSet::new(),
                 spawn_results: HashMap::new(),
                 glob_results: HashMap::new(),
@@ -72,9 +347,157 @@ pub mod mock {
                 registered_tools: RefCell::new(Vec::new()),
                 next_pid: RefCell::new(100),
                 pid_results: RefCell::new(HashMap::new()),
+                unix_ms: 1_700_000_000_000,
+                random_state: RefCell::new(0x9e37_79b9_7f4a_7c15),
+                clock_ms: RefCell::new(0.0),
+                spawn_duration_ms: 0.0,
+                spawn_latency_ms: 0.0,
+                finish_ms: RefCell::new(HashMap::new()),
+                failing_writes: HashMap::new(),
+                failing_reads: HashMap::new(),
+                failing_spawns: HashMap::new(),
+                cancel_after_spawns: None,
+                kills: RefCell::new(Vec::new()),
+                duplex_stdin: RefCell::new(HashMap::new()),
+                terminal: None,
+                pipe_fds: RefCell::new(HashSet::new()),
+                quota_bytes: None,
+                recorded: RefCell::new(None),
+                replay: RefCell::new(None),
+            }
+        }
+
+        /// Capture every spawn, file read and file write, for `fixture`.
+        pub fn with_recording(self) -> Self {
+            *self.recorded.borrow_mut() = Some(Vec::new());
+            self
+        }
+
+        /// The calls captured since `with_recording`.
+        pub fn fixture(&self) -> Fixture {
+            Fixture {
+                events: self.recorded.borrow().clone().unwrap_or_default(),
+            }
+        }
+
+        /// Answer spawns and file reads from `fixture`, panicking as soon
+        /// as a call differs from the recorded one. Writes are checked and
+        /// then applied, so results can be inspected with `get_file` as
+        /// usual. Other calls (`stat`, `readdir`, ...) still see the mock
+        /// filesystem, so set it up as it was when recording.
+        pub fn with_replay(self, fixture: &Fixture) -> Self {
+            *self.replay.borrow_mut() = Some(fixture.events.iter().cloned().collect());
+            self
+        }
+
+        /// Panic unless every call in the replayed fixture has been made.
+        pub fn assert_replayed(&self) {
+            if let Some(rest) = self.replay.borrow().as_ref() {
+                assert!(rest.is_empty(), "fixture calls not made: {rest:?}");
+            }
+        }
+
+        fn record(&self, event: FixtureEvent) {
+            if let Some(events) = self.recorded.borrow_mut().as_mut() {
+                events.push(event);
+            }
+        }
+
+        /// The next call of a replayed fixture, checked against `expect`,
+        /// or `None` when not replaying.
+        fn next_replayed(
+            &self,
+            expect: impl FnOnce(&FixtureEvent) -> bool,
+            call: &str,
+        ) -> Option<FixtureEvent> {
+            let mut replay = self.replay.borrow_mut();
+            let events = replay.as_mut()?;
+            match events.pop_front() {
+                Some(event) if expect(&event) => Some(event),
+                Some(event) => panic!("fixture expected {event:?}, got {call}"),
+                None => panic!("fixture has no more calls, got {call}"),
             }
         }
 
+        /// Refuse writes that would take the files past `bytes` in total.
+        pub fn with_quota(mut self, bytes: u64) -> Self {
+            self.quota_bytes = Some(bytes);
+            self
+        }
+
+        /// Total size of all files.
+        fn used_bytes(&self) -> u64 {
+            self.fs.borrow().root.used_bytes()
+        }
+
+        /// Make every spawned command advance the monotonic clock by `ms`.
+        pub fn with_spawn_duration_ms(mut self, ms: f64) -> Self {
+            self.spawn_duration_ms = ms;
+            self
+        }
+
+        /// Keep every spawned command running for `ms` of mock time. A
+        /// wait shorter than that times out, and `waitpid` and `yield_now`
+        /// move the clock on, so deadlines and timeouts can be exercised.
+        pub fn with_spawn_latency(mut self, ms: f64) -> Self {
+            self.spawn_latency_ms = ms;
+            self
+        }
+
+        /// Make every `write_file` to `path` fail with `error(path)`, e.g.
+        /// `with_failing_write("/out", HostError::PermissionDenied)`.
+        pub fn with_failing_write(mut self, path: &str, error: fn(String) -> HostError) -> Self {
+            self.failing_writes.insert(path.to_string(), error);
+            self
+        }
+
+        /// Make every `read_file` of `path` fail with `error(path)`.
+        pub fn with_failing_read(mut self, path: &str, error: fn(String) -> HostError) -> Self {
+            self.failing_reads.insert(path.to_string(), error);
+            self
+        }
+
+        /// Make every spawn of `program` fail with `error(program)`.
+        pub fn with_failing_spawn(mut self, program: &str, error: fn(String) -> HostError) -> Self {
+            self.failing_spawns.insert(program.to_string(), error);
+            self
+        }
+
+        /// The error injected for `key` in `faults`, if any.
+        fn fault(
+            faults: &HashMap<String, fn(String) -> HostError>,
+            key: &str,
+        ) -> Result<(), HostError> {
+            match faults.get(key) {
+                Some(error) => Err(error(key.to_string())),
+                None => Ok(()),
+            }
+        }
+
+        /// Move the mock clock on to `ms` if it is not there yet.
+        fn advance_clock_to(&self, ms: f64) {
+            let mut clock = self.clock_ms.borrow_mut();
+            *clock = clock.max(ms);
+        }
+
+        /// Attach fds 0-2 to a `cols` x `rows` terminal.
+        pub fn with_terminal(mut self, cols: u16, rows: u16) -> Self {
+            self.terminal = Some(TerminalSize { cols, rows });
+            self
+        }
+
+        /// Set the wall-clock time, in milliseconds since the Unix epoch.
+        pub fn with_unix_ms(mut self, ms: u64) -> Self {
+            self.unix_ms = ms;
+            self
+        }
+
+        /// Request cancellation after `n` commands have been spawned.
+        pub fn with_cancel_after_spawns(mut self, n: usize) -> Self {
+            self.cancel_after_spawns = Some(n);
+            self
+        }
+
         /// Register a tool name as available.
         pub fn with_tool(mut self, name: &str) -> Self {
             self.tools.insert(name.to_string());
@@ -87,17 +510,31 @@ pub mod mock {
             self
         }
 
-        /// Add a file with the given content.
+        /// Add a file with the given content, and any missing parent
+        /// directories.
         pub fn with_file(self, path: &str, content: &[u8]) -> Self {
-            self.files
+            self.fs
+                .borrow_mut()
+                .insert(path, Node::file(content.to_vec()))
+                .expect("with_file: parent is not a directory");
+            self
+        }
+
+        /// Add a directory, and any missing parents.
+        pub fn with_dir(self, path: &str) -> Self {
+            self.fs
                 .borrow_mut()
-                .insert(path.to_string(), content.to_vec());
+                .dir_entries(path)
+                .expect("with_dir: parent is not a directory");
             self
         }
 
-        /// Add a directory.
-        pub fn with_dir(mut self, path: &str) -> Self {
-            self.dirs.insert(path.to_string());
+        /// Add a symlink at `path` pointing to `target`.
+        pub fn with_symlink(self, path: &str, target: &str) -> Self {
+            self.fs
+                .borrow_mut()
+                .insert(path, Node::Symlink(target.to_string()))
+                .expect("with_symlink: parent is not a directory");
             self
         }
 
@@ -119,10 +556,17 @@ pub mod mock {
 
         /// Read a file's content from the mock filesystem (for test assertions).
         pub fn get_file(&self, path: &str) -> Option<String> {
-            self.files
-                .borrow()
-                .get(path)
-                .and_then(|data| String::from_utf8(data.clone()).ok())
+            self.get_file_bytes(path)
+                .and_then(|data| String::from_utf8(data).ok())
+        }
+
+        /// Read a file's raw bytes from the mock filesystem.
+        pub fn get_file_bytes(&self, path: &str) -> Option<Vec<u8>> {
+            let fs = self.fs.borrow();
+            match fs.node(&fs.resolve(path, true)) {
+                Some(Node::File { data, .. }) => Some(data.clone()),
+                _ => None,
+            }
         }
 
         /// Retrieve all recorded spawn calls for test assertions.
@@ -130,6 +574,20 @@ pub mod mock {
             self.spawn_calls.borrow().clone()
         }
 
+        /// Read what the shell has written so far to a duplex or streaming
+        /// child's stdin.
+        pub fn read_duplex_stdin(&self, pid: i32) -> Vec<u8> {
+            match self.duplex_stdin.borrow().get(&pid) {
+                Some(&fd) => self.read_fd(fd).unwrap_or_default(),
+                None => Vec::new(),
+            }
+        }
+
+        /// Get the `(pid, signal)` pairs passed to `kill`.
+        pub fn get_kills(&self) -> Vec<(i32, i32)> {
+            self.kills.borrow().clone()
+        }
+
         /// Register a pre-configured fetch result for a URL.
         pub fn with_fetch_result(mut self, url: &str, result: FetchResult) -> Self {
             self.fetch_results.insert(url.to_string(), result);
@@ -152,9 +610,11 @@ pub mod mock {
             stdin_data: &str,
             stdin_fd: i32,
             stdout_fd: i32,
-            _stderr_fd: i32,
+            stderr_fd: i32,
             _nice: u8,
         ) -> Result<i32, HostError> {
+            Self::fault(&self.failing_spawns, program)?;
+
             // In streaming pipeline mode, stdin comes from a pipe fd, not the
             // stdin_data string. Read from stdin_fd if stdin_data is empty.
             let effective_stdin = if stdin_data.is_empty() && stdin_fd > 2 {
@@ -172,8 +632,29 @@ pub mod mock {
                 stdin: effective_stdin.clone(),
             });
 
-            // Resolve the mock spawn output from handler or static map.
-            let output = if let Some(ref handler) = self.spawn_handler {
+            let replayed = self.next_replayed(
+                |event| {
+                    matches!(event, FixtureEvent::Spawn { program: p, args: a, stdin: i, .. }
+                        if p == program && a == args && *i == effective_stdin)
+                },
+                &format!("spawn {program} {args:?}"),
+            );
+
+            // Resolve the mock spawn output from a replayed fixture, the
+            // handler or the static map.
+            let output = if let Some(FixtureEvent::Spawn {
+                exit_code,
+                stdout,
+                stderr,
+                ..
+            }) = replayed
+            {
+                MockSpawnOutput {
+                    exit_code,
+                    stdout,
+                    stderr,
+                }
+            } else if let Some(ref handler) = self.spawn_handler {
                 handler(program, args, &effective_stdin)
             } else if let Some(r) = self.spawn_results.get(program) {
                 r.clone()
@@ -184,6 +665,14 @@ pub mod mock {
                     stderr: format!("{program}: command not found"),
                 }
             };
+            self.record(FixtureEvent::Spawn {
+                program: program.to_string(),
+                args: args.iter().map(|s| s.to_string()).collect(),
+                stdin: effective_stdin.clone(),
+                exit_code: output.exit_code,
+                stdout: output.stdout.clone(),
+                stderr: output.stderr.clone(),
+            });
 
             // Write mock stdout to the pipe fd so streaming pipelines work.
             if !output.stdout.is_empty() && stdout_fd > 2 {
@@ -197,6 +686,20 @@ pub mod mock {
                 }
             }
 
+            // Likewise for stderr when it is routed to a pipe sink.
+            if !output.stderr.is_empty() && stderr_fd > 2 {
+                let data = output.stderr.as_bytes();
+                unsafe {
+                    libc::write(
+                        stderr_fd as libc::c_int,
+                        data.as_ptr() as *const libc::c_void,
+                        data.len(),
+                    );
+                }
+            }
+
+            *self.clock_ms.borrow_mut() += self.spawn_duration_ms;
+
             // Allocate a PID and store the exit code for waitpid.
             let mut pid_ref = self.next_pid.borrow_mut();
             let pid = *pid_ref;
@@ -207,150 +710,334 @@ pub mod mock {
                     exit_code: output.exit_code,
                 },
             );
+            if self.spawn_latency_ms > 0.0 {
+                let finish = *self.clock_ms.borrow() + self.spawn_latency_ms;
+                self.finish_ms.borrow_mut().insert(pid, finish);
+            }
             Ok(pid)
         }
 
+        fn spawn_duplex(
+            &self,
+            program: &str,
+            args: &[&str],
+            env: &[(&str, &str)],
+            cwd: &str,
+        ) -> Result<DuplexChild, HostError> {
+            // Mock processes run to completion at spawn, so the child sees
+            // no input. Its canned stdout is left in the output pipe, and
+            // input written by the shell can be inspected afterwards.
+            let (child_stdin, write_fd) = self.pipe()?;
+            let (read_fd, child_stdout) = self.pipe()?;
+            let pid = self.spawn(program, args, env, cwd, "", 0, child_stdout, 2, 0)?;
+            self.close_fd(child_stdout)?;
+            unsafe {
+                libc::fcntl(child_stdin, libc::F_SETFL, libc::O_NONBLOCK);
+            }
+            self.duplex_stdin.borrow_mut().insert(pid, child_stdin);
+            Ok(DuplexChild {
+                pid,
+                read_fd,
+                write_fd,
+            })
+        }
+
+        fn spawn_streaming(
+            &self,
+            program: &str,
+            args: &[&str],
+            env: &[(&str, &str)],
+            cwd: &str,
+        ) -> Result<StreamingChild, HostError> {
+            // As with `spawn_duplex`, the mock runs to completion at spawn:
+            // its canned output is waiting in the pipes, and stdin written
+            // afterwards can be read back with `read_duplex_stdin`.
+            let (child_stdin, stdin_fd) = self.pipe()?;
+            let (stdout_fd, child_stdout) = self.pipe()?;
+            let (stderr_fd, child_stderr) = self.pipe()?;
+            let pid = self.spawn(
+                program,
+                args,
+                env,
+                cwd,
+                "",
+                0,
+                child_stdout,
+                child_stderr,
+                0,
+            )?;
+            self.close_fd(child_stdout)?;
+            self.close_fd(child_stderr)?;
+            unsafe {
+                libc::fcntl(child_stdin, libc::F_SETFL, libc::O_NONBLOCK);
+            }
+            self.duplex_stdin.borrow_mut().insert(pid, child_stdin);
+            Ok(StreamingChild {
+                pid,
+                stdin_fd: Some(stdin_fd),
+                stdout_fd,
+                stderr_fd,
+            })
+        }
+
         fn has_tool(&self, name: &str) -> bool {
             self.tools.contains(name)
         }
 
         fn time(&self) -> f64 {
-            1700000000.0
+            self.unix_ms as f64 / 1000.0
         }
 
-        fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
-            let files = self.files.borrow();
-            if let Some(data) = files.get(path) {
-                Ok(StatInfo {
-                    exists: true,
-                    is_file: true,
-                    is_dir: false,
-                    is_symlink: false,
-                    size: data.len() as u64,
-                    mode: 0o644,
-                    mtime_ms: 0,
-                })
-            } else if self.dirs.contains(path) {
-                Ok(StatInfo {
-                    exists: true,
-                    is_file: false,
-                    is_dir: true,
-                    is_symlink: false,
-                    size: 0,
-                    mode: 0o755,
-                    mtime_ms: 0,
-                })
-            } else {
-                Ok(StatInfo {
-                    exists: false,
-                    is_file: false,
-                    is_dir: false,
-                    is_symlink: false,
-                    size: 0,
-                    mode: 0,
-                    mtime_ms: 0,
+        fn now_unix_ms(&self) -> u64 {
+            self.unix_ms
+        }
+
+        fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+            // A fixed-seed generator, so tests see the same bytes every run.
+            let mut state = self.random_state.borrow_mut();
+            Ok((0..n)
+                .map(|_| {
+                    *state ^= *state << 13;
+                    *state ^= *state >> 7;
+                    *state ^= *state << 17;
+                    *state as u8
                 })
-            }
+                .collect())
+        }
+
+        fn isatty(&self, fd: i32) -> bool {
+            self.terminal_size(fd).is_some()
+        }
+
+        fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+            let on_terminal = (0..=2).contains(&fd) && !self.pipe_fds.borrow().contains(&fd);
+            self.terminal.filter(|_| on_terminal)
+        }
+
+        fn monotonic_ms(&self) -> f64 {
+            *self.clock_ms.borrow()
+        }
+
+        fn should_cancel(&self) -> Option<CancelReason> {
+            let n = self.cancel_after_spawns?;
+            (self.spawn_calls.borrow().len() >= n).then_some(CancelReason::Cancelled)
+        }
+
+        fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+            let fs = self.fs.borrow();
+            let (is_file, size, mode, mtime_ms) = match fs.node(&fs.resolve(path, true)) {
+                Some(Node::File {
+                    data,
+                    mode,
+                    mtime_ms,
+                }) => (true, data.len() as u64, *mode, *mtime_ms),
+                Some(Node::Dir { mode, mtime_ms, .. }) => (false, 0, *mode, *mtime_ms),
+                // Missing, or a link that does not resolve.
+                _ => {
+                    return Ok(StatInfo {
+                        exists: false,
+                        is_file: false,
+                        is_dir: false,
+                        is_symlink: false,
+                        size: 0,
+                        mode: 0,
+                        mtime_ms: 0,
+                    })
+                }
+            };
+            Ok(StatInfo {
+                exists: true,
+                is_file,
+                is_dir: !is_file,
+                is_symlink: false,
+                size,
+                mode,
+                mtime_ms,
+            })
         }
 
         fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
-            match self.files.borrow().get(path) {
-                Some(data) => Ok(data.clone()),
-                None => Err(HostError::NotFound(path.to_string())),
+            let replayed = self.next_replayed(
+                |event| matches!(event, FixtureEvent::Read { path: p, .. } if p == path),
+                &format!("read {path}"),
+            );
+            if let Some(FixtureEvent::Read { data, .. }) = replayed {
+                return data
+                    .map(String::into_bytes)
+                    .ok_or_else(|| HostError::NotFound(path.to_string()));
             }
+            Self::fault(&self.failing_reads, path)?;
+            let fs = self.fs.borrow();
+            let result = match fs.node(&fs.resolve(path, true)) {
+                Some(Node::File { data, .. }) => Ok(data.clone()),
+                Some(Node::Dir { .. }) => Err(HostError::IsADirectory(path.to_string())),
+                _ => Err(HostError::NotFound(path.to_string())),
+            };
+            self.record(FixtureEvent::Read {
+                path: path.to_string(),
+                data: result
+                    .as_ref()
+                    .ok()
+                    .map(|d| String::from_utf8_lossy(d).into_owned()),
+            });
+            result
         }
 
         fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
-            let mut files = self.files.borrow_mut();
-            match mode {
-                WriteMode::Truncate => {
-                    files.insert(path.to_string(), data.to_vec());
-                }
-                WriteMode::Append => {
-                    let entry = files.entry(path.to_string()).or_default();
-                    entry.extend_from_slice(data);
+            let event = FixtureEvent::Write {
+                path: path.to_string(),
+                data: String::from_utf8_lossy(data).into_owned(),
+                append: mode == WriteMode::Append,
+            };
+            self.next_replayed(|e| *e == event, &format!("{event:?}"));
+            self.record(event);
+            Self::fault(&self.failing_writes, path)?;
+            let used = self.used_bytes();
+            let mut fs = self.fs.borrow_mut();
+            let target = fs.resolve(path, true);
+            let (old, file_mode) = match fs.node(&target) {
+                Some(Node::File { data, mode, .. }) => (data.clone(), *mode),
+                Some(Node::Dir { .. }) => return Err(HostError::IsADirectory(path.to_string())),
+                _ => (Vec::new(), 0o644),
+            };
+            // Like the real VFS, refuse to modify a file without its owner
+            // write bit.
+            if file_mode & 0o200 == 0 {
+                return Err(HostError::PermissionDenied(path.to_string()));
+            }
+            let new = match mode {
+                WriteMode::Truncate => data.to_vec(),
+                WriteMode::Append => [old.as_slice(), data].concat(),
+            };
+            if let Some(limit) = self.quota_bytes {
+                if used - old.len() as u64 + new.len() as u64 > limit {
+                    return Err(HostError::QuotaExceeded(path.to_string()));
                 }
             }
-            Ok(())
+            let mtime_ms = fs.stamp(self.unix_ms);
+            fs.insert(
+                &target,
+                Node::File {
+                    data: new,
+                    mode: file_mode,
+                    mtime_ms,
+                },
+            )
+        }
+
+        fn storage_quota(&self) -> Option<StorageQuota> {
+            Some(StorageQuota {
+                used_bytes: self.used_bytes(),
+                limit_bytes: self.quota_bytes,
+            })
         }
 
         fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
-            let prefix = if path.ends_with('/') {
-                path.to_string()
-            } else {
-                format!("{path}/")
-            };
-            let files = self.files.borrow();
-            let mut entries = HashSet::new();
-            for key in files.keys() {
-                if let Some(rest) = key.strip_prefix(&prefix) {
-                    if let Some(name) = rest.split('/').next() {
-                        if !name.is_empty() {
-                            entries.insert(name.to_string());
-                        }
-                    }
-                }
+            let fs = self.fs.borrow();
+            match fs.node(&fs.resolve(path, true)) {
+                Some(Node::Dir { entries, .. }) => Ok(entries.keys().cloned().collect()),
+                Some(_) => Err(HostError::IoError(format!("{path}: Not a directory"))),
+                None => Err(HostError::NotFound(path.to_string())),
             }
-            for key in &self.dirs {
-                if let Some(rest) = key.strip_prefix(&prefix) {
-                    if let Some(name) = rest.split('/').next() {
-                        if !name.is_empty() {
-                            entries.insert(name.to_string());
-                        }
-                    }
-                }
+        }
+
+        fn mkdir(&self, path: &str) -> Result<(), HostError> {
+            let mut fs = self.fs.borrow_mut();
+            if fs.node(path).is_some() {
+                return Err(HostError::IoError(format!("{path}: File exists")));
             }
-            let mut result: Vec<String> = entries.into_iter().collect();
-            result.sort();
-            Ok(result)
+            let mtime_ms = fs.stamp(self.unix_ms);
+            fs.insert(
+                path,
+                Node::Dir {
+                    entries: BTreeMap::new(),
+                    mode: 0o755,
+                    mtime_ms,
+                },
+            )
         }
 
-        fn mkdir(&self, _path: &str) -> Result<(), HostError> {
+        fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+            let mut fs = self.fs.borrow_mut();
+            match fs.node(path) {
+                None => return Err(HostError::NotFound(path.to_string())),
+                Some(Node::Dir { entries, .. }) if !entries.is_empty() && !recursive => {
+                    return Err(HostError::IoError(format!("{path}: Directory not empty")));
+                }
+                Some(_) => {}
+            }
+            fs.take(path);
             Ok(())
         }
 
-        fn remove(&self, _path: &str, _recursive: bool) -> Result<(), HostError> {
-            Ok(())
+        fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+            let mut fs = self.fs.borrow_mut();
+            let target = fs.resolve(path, true);
+            match fs.node_mut(&target) {
+                Some(Node::File { mode: m, .. } | Node::Dir { mode: m, .. }) => {
+                    *m = mode;
+                    Ok(())
+                }
+                _ => Err(HostError::NotFound(path.to_string())),
+            }
         }
 
-        fn chmod(&self, _path: &str, _mode: u32) -> Result<(), HostError> {
-            Ok(())
+        fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+            let mut fs = self.fs.borrow_mut();
+            let target = fs.resolve(path, true);
+            match fs.node_mut(&target) {
+                Some(Node::File { mtime_ms: m, .. } | Node::Dir { mtime_ms: m, .. }) => {
+                    *m = mtime_ms;
+                    Ok(())
+                }
+                _ => Err(HostError::NotFound(path.to_string())),
+            }
         }
 
+        /// Results registered with `with_glob_result` win; otherwise the
+        /// pattern is matched against the filesystem.
         fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
-            Ok(self.glob_results.get(pattern).cloned().unwrap_or_default())
+            if let Some(matches) = self.glob_results.get(pattern) {
+                return Ok(matches.clone());
+            }
+            let mut matches = Vec::new();
+            self.fs
+                .borrow()
+                .glob("/", &components(pattern), &mut matches);
+            Ok(matches)
         }
 
-        fn rename(&self, _from: &str, _to: &str) -> Result<(), HostError> {
-            Ok(())
+        fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+            let mut fs = self.fs.borrow_mut();
+            if matches!(fs.node(to), Some(Node::Dir { .. })) {
+                return Err(HostError::IsADirectory(to.to_string()));
+            }
+            let node = fs
+                .take(from)
+                .ok_or_else(|| HostError::NotFound(from.to_string()))?;
+            fs.insert(to, node)
         }
 
-        fn symlink(&self, _target: &str, _link_path: &str) -> Result<(), HostError> {
-            Ok(())
+        fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+            let mut fs = self.fs.borrow_mut();
+            if fs.node(link_path).is_some() {
+                return Err(HostError::IoError(format!("{link_path}: File exists")));
+            }
+            fs.insert(link_path, Node::Symlink(target.to_string()))
         }
 
         fn readlink(&self, path: &str) -> Result<String, HostError> {
-            Err(HostError::NotFound(path.to_string()))
+            match self.fs.borrow().node(path) {
+                Some(Node::Symlink(target)) => Ok(target.clone()),
+                _ => Err(HostError::NotFound(path.to_string())),
+            }
         }
 
-        fn fetch(
-            &self,
-            url: &str,
-            _method: &str,
-            _headers: &[(&str, &str)],
-            _body: Option<&str>,
-        ) -> FetchResult {
-            if let Some(result) = self.fetch_results.get(url) {
-                return result.clone();
-            }
-            FetchResult {
-                ok: false,
-                status: 0,
-                headers: Default::default(),
-                body: String::new(),
-                body_base64: None,
-                error: Some("networking not configured".to_string()),
-            }
+        fn fetch(&self, request: &FetchRequest) -> FetchResult {
+            self.fetch_results
+                .get(request.url)
+                .cloned()
+                .unwrap_or_else(FetchResult::denied)
         }
 
         fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError> {
@@ -365,20 +1052,55 @@ pub mod mock {
             if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
                 return Err(HostError::IoError("pipe failed".into()));
             }
+            self.pipe_fds.borrow_mut().extend([fds[0], fds[1]]);
             Ok((fds[0] as i32, fds[1] as i32))
         }
 
         fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+            if let Some(finish) = self.finish_ms.borrow_mut().remove(&pid) {
+                self.advance_clock_to(finish);
+            }
             match self.pid_results.borrow().get(&pid) {
                 Some(result) => Ok(result.clone()),
                 None => Err(HostError::Other(format!("waitpid: unknown pid {pid}"))),
             }
         }
 
+        fn waitpid_timeout(
+            &self,
+            pid: i32,
+            timeout_ms: u32,
+        ) -> Result<Option<SpawnResult>, HostError> {
+            // Mock processes finish as soon as they are spawned, unless
+            // given a latency.
+            let finish = self.finish_ms.borrow().get(&pid).copied();
+            let deadline = *self.clock_ms.borrow() + f64::from(timeout_ms);
+            match finish {
+                Some(finish) if finish > deadline => {
+                    self.advance_clock_to(deadline);
+                    Ok(None)
+                }
+                _ => self.waitpid(pid).map(Some),
+            }
+        }
+
+        fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+            let mut results = self.pid_results.borrow_mut();
+            let Some(result) = results.get_mut(&pid) else {
+                return Err(HostError::NotFound(format!("kill: ({pid})")));
+            };
+            result.exit_code = 128 + signal;
+            self.kills.borrow_mut().push((pid, signal));
+            // A killed child is done at once, however long it had left.
+            self.finish_ms.borrow_mut().remove(&pid);
+            Ok(())
+        }
+
         fn close_fd(&self, fd: i32) -> Result<(), HostError> {
             unsafe {
                 libc::close(fd as libc::c_int);
             }
+            self.pipe_fds.borrow_mut().remove(&fd);
             Ok(())
         }
 
@@ -387,6 +1109,10 @@ pub mod mock {
             if r < 0 {
                 return Err(HostError::IoError(format!("dup({fd}) failed")));
             }
+            let mut pipe_fds = self.pipe_fds.borrow_mut();
+            if pipe_fds.contains(&fd) {
+                pipe_fds.insert(r);
+            }
             Ok(r as i32)
         }
 
@@ -396,6 +1122,12 @@ pub mod mock {
                     "dup2({src_fd}, {dst_fd}) failed"
                 )));
             }
+            let mut pipe_fds = self.pipe_fds.borrow_mut();
+            if pipe_fds.contains(&src_fd) {
+                pipe_fds.insert(dst_fd);
+            } else {
+                pipe_fds.remove(&dst_fd);
+            }
             Ok(())
         }
 
@@ -433,11 +1165,25 @@ pub mod mock {
         }
 
         fn yield_now(&self) -> Result<(), HostError> {
+            // Let time pass for children that are still running.
+            let now = *self.clock_ms.borrow();
+            if self.finish_ms.borrow().values().any(|&f| f > now) {
+                *self.clock_ms.borrow_mut() += 1.0;
+            }
             Ok(())
         }
 
         fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
-            // In tests, spawned processes complete immediately
+            // In tests, spawned processes complete immediately unless
+            // given a latency.
+            if self
+                .finish_ms
+                .borrow()
+                .get(&pid)
+                .is_some_and(|&finish| finish > *self.clock_ms.borrow())
+            {
+                return Ok(-1);
+            }
             let results = self.pid_results.borrow();
             match results.get(&pid) {
                 Some(result) => Ok(result.exit_code),
@@ -450,19 +1196,19 @@ pub mod mock {
         }
 
         fn socket_connect(&self, _host: &str, _port: u16, _tls: bool) -> Result<u32, HostError> {
-            Err(HostError::IoError("sockets not available in test".into()))
+            Err(HostError::Unsupported("sockets".into()))
         }
 
         fn socket_send(&self, _socket_id: u32, _data: &[u8]) -> Result<usize, HostError> {
-            Err(HostError::IoError("sockets not available in test".into()))
+            Err(HostError::Unsupported("sockets".into()))
         }
 
         fn socket_recv(&self, _socket_id: u32, _max_bytes: usize) -> Result<Vec<u8>, HostError> {
-            Err(HostError::IoError("sockets not available in test".into()))
+            Err(HostError::Unsupported("sockets".into()))
         }
 
         fn socket_close(&self, _socket_id: u32) -> Result<(), HostError> {
-            Err(HostError::IoError("sockets not available in test".into()))
+            Err(HostError::Unsupported("sockets".into()))
         }
     }
 }
@@ -0,0 +1,839 @@
+//! Recording sessions as transcripts, for audit and replay.
+//!
+//! Between [`start`] and [`stop`], every command the host runs is written
+//! into a [`Transcript`]: the command line, each simple command it ran as
+//! it stood after expansion, when they ran, the exit status, and the
+//! output that reached the shell's stdout and stderr. A transcript reads
+//! out as JSON, or as an asciicast (asciinema's v2 format) to play back.
+//!
+//! Only output on its way to the shell's own stdout and stderr is
+//! recorded; output that is redirected, piped or substituted is not. The
+//! shell's own writes are noted by [`crate::io`]. Children writing to
+//! those streams are handed a pipe instead by [`TranscriptHost`], which
+//! passes their output on while they are waited for, the way
+//! [`CachingHost`](crate::spawn_cache::CachingHost) does. Background jobs
+//! outlive the command that started them, so they write straight to the
+//! terminal and are not recorded.
+//!
+//! A child's output is stamped with the time it was passed on. The
+//! shell's own output is stamped with the latest reading of the host's
+//! clock, which the executor takes often but not on every write.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::{Serialize, Serializer};
+use serde_json::json;
+
+use crate::control::CancelReason;
+use crate::host::{
+    DuplexChild, FetchRequest, FetchResult, FileChange, FileWatch, HostError, HostInterface,
+    OutputStream, SpawnLimits, SpawnResult, StatInfo, StorageQuota, StreamingChild, TerminalSize,
+    WriteMode,
+};
+use crate::state::ShellState;
+
+/// The size an asciicast gives when stdout is not a terminal.
+const DEFAULT_SIZE: TerminalSize = TerminalSize { cols: 80, rows: 24 };
+
+/// A recorded session. Times are in milliseconds since recording started.
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    /// When recording started, in milliseconds since the Unix epoch.
+    pub started_unix_ms: u64,
+    /// The terminal's size in columns and rows when recording started.
+    pub width: u16,
+    pub height: u16,
+    pub entries: Vec<Entry>,
+    /// The host's monotonic clock when recording started.
+    #[serde(skip)]
+    origin_ms: f64,
+    /// The command running now, once [`TranscriptHost::start`] has opened it.
+    #[serde(skip)]
+    current: Option<Entry>,
+}
+
+/// One command run by the host.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    /// The command line, as the host gave it.
+    pub command: String,
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    pub exit_code: i32,
+    /// The simple commands it ran, in order.
+    pub steps: Vec<Step>,
+    /// What it wrote, in order.
+    pub output: Vec<Output>,
+}
+
+/// A simple command as it was run: its words after expansion.
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    pub argv: Vec<String>,
+    pub at_ms: u64,
+}
+
+/// Output written to one stream at one time.
+#[derive(Debug, Clone, Serialize)]
+pub struct Output {
+    pub at_ms: u64,
+    pub stream: OutputStream,
+    /// Serialized as a string, with invalid UTF-8 replaced.
+    #[serde(serialize_with = "lossy")]
+    pub data: Vec<u8>,
+}
+
+fn lossy<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&String::from_utf8_lossy(data))
+}
+
+impl Transcript {
+    /// Milliseconds from the start of recording to `now_ms`, a reading of
+    /// the host's monotonic clock.
+    fn since_start(&self, now_ms: f64) -> u64 {
+        (now_ms - self.origin_ms).max(0.0) as u64
+    }
+
+    /// The session as an asciicast: a JSON header line, then one
+    /// `[seconds, "o", text]` event per line. Each command is shown typed
+    /// at a `$ ` prompt, and stdout and stderr play back interleaved, as a
+    /// terminal shows them.
+    pub fn to_asciicast(&self) -> String {
+        let header = json!({
+            "version": 2,
+            "width": self.width,
+            "height": self.height,
+            "timestamp": self.started_unix_ms / 1000,
+        });
+        let mut lines = vec![header.to_string()];
+        let mut event = |at_ms: u64, text: &str| {
+            let seconds = at_ms as f64 / 1000.0;
+            lines.push(json!([seconds, "o", text.replace('\n', "\r\n")]).to_string());
+        };
+        for entry in &self.entries {
+            event(entry.start_ms, &format!("$ {}\n", entry.command));
+            for output in &entry.output {
+                event(output.at_ms, &String::from_utf8_lossy(&output.data));
+            }
+        }
+        let mut cast = lines.join("\n");
+        cast.push('\n');
+        cast
+    }
+}
+
+/// Start recording the session, dropping any transcript recorded so far.
+pub fn start(state: &mut ShellState, host: &dyn HostInterface) {
+    let size = host.terminal_size(1).unwrap_or(DEFAULT_SIZE);
+    state.transcript = Some(Transcript {
+        started_unix_ms: host.now_unix_ms(),
+        width: size.cols,
+        height: size.rows,
+        entries: Vec::new(),
+        origin_ms: host.monotonic_ms(),
+        current: None,
+    });
+}
+
+/// Stop recording and return the transcript, if one was being recorded.
+pub fn stop(state: &mut ShellState) -> Option<Transcript> {
+    state.transcript.take()
+}
+
+/// Note that the executor is about to run `argv`, if the command running
+/// is being recorded.
+pub(crate) fn record_command(state: &mut ShellState, host: &dyn HostInterface, argv: &[String]) {
+    let Some(transcript) = state.transcript.as_mut() else {
+        return;
+    };
+    if transcript.current.is_none() {
+        return;
+    }
+    let at_ms = transcript.since_start(host.monotonic_ms());
+    if let Some(entry) = transcript.current.as_mut() {
+        entry.steps.push(Step {
+            argv: argv.to_vec(),
+            at_ms,
+        });
+    }
+}
+
+/// Output caught while a command is recorded.
+struct Sink {
+    /// The shell's fds that lead to its stdout or stderr, and which one.
+    fds: HashMap<i32, OutputStream>,
+    origin_ms: f64,
+    /// The latest reading of the host's monotonic clock.
+    now_ms: f64,
+    output: Vec<Output>,
+    /// Set while spawning a child whose output is not to be captured.
+    untracked: bool,
+}
+
+impl Sink {
+    fn push(&mut self, stream: OutputStream, data: &[u8]) {
+        let at_ms = (self.now_ms - self.origin_ms).max(0.0) as u64;
+        match self.output.last_mut() {
+            Some(last) if last.stream == stream && last.at_ms == at_ms => {
+                last.data.extend_from_slice(data);
+            }
+            _ => self.output.push(Output {
+                at_ms,
+                stream,
+                data: data.to_vec(),
+            }),
+        }
+    }
+}
+
+thread_local! {
+    static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+fn with_sink<T>(f: impl FnOnce(&mut Sink) -> T) -> Option<T> {
+    SINK.with(|sink| sink.borrow_mut().as_mut().map(f))
+}
+
+/// Which of the shell's streams `fd` leads to, if a command is recorded.
+fn stream_of(fd: i32) -> Option<OutputStream> {
+    with_sink(|sink| sink.fds.get(&fd).copied()).flatten()
+}
+
+/// Note that the shell wrote `data` to its `fd`.
+pub(crate) fn record_output(fd: i32, data: &[u8]) {
+    with_sink(|sink| {
+        if let Some(&stream) = sink.fds.get(&fd) {
+            sink.push(stream, data);
+        }
+    });
+}
+
+/// Run `f`, which spawns a child, leaving the child's output uncaptured.
+pub(crate) fn untracked<T>(f: impl FnOnce() -> T) -> T {
+    let previous = with_sink(|sink| std::mem::replace(&mut sink.untracked, true));
+    let result = f();
+    if let Some(previous) = previous {
+        with_sink(|sink| sink.untracked = previous);
+    }
+    result
+}
+
+/// One output stream of a child: the pipe it writes to, and the shell's
+/// own copy of the fd it was asked to write to.
+#[derive(Debug)]
+struct Capture {
+    from: i32,
+    to: i32,
+    stream: OutputStream,
+}
+
+/// A host that records one command's output on the way past; see the
+/// module docs. Everything else passes straight through.
+pub struct TranscriptHost<'a> {
+    host: &'a dyn HostInterface,
+    /// The captures of children not yet reaped, by pid.
+    children: RefCell<HashMap<i32, Vec<Capture>>>,
+    /// Exit statuses of children reaped by `waitpid_nohang`, not yet
+    /// waited for.
+    reaped: RefCell<HashMap<i32, i32>>,
+}
+
+impl<'a> TranscriptHost<'a> {
+    /// Open an entry for `command` in the transcript being recorded, and
+    /// catch its output until [`finish`](Self::finish).
+    pub fn start(state: &mut ShellState, host: &'a dyn HostInterface, command: &str) -> Self {
+        let now_ms = host.monotonic_ms();
+        if let Some(transcript) = state.transcript.as_mut() {
+            transcript.current = Some(Entry {
+                command: command.to_string(),
+                start_ms: transcript.since_start(now_ms),
+                duration_ms: 0,
+                exit_code: 0,
+                steps: Vec::new(),
+                output: Vec::new(),
+            });
+            let sink = Sink {
+                fds: HashMap::from([(1, OutputStream::Stdout), (2, OutputStream::Stderr)]),
+                origin_ms: transcript.origin_ms,
+                now_ms,
+                output: Vec::new(),
+                untracked: false,
+            };
+            SINK.with(|cell| *cell.borrow_mut() = Some(sink));
+        }
+        Self {
+            host,
+            children: RefCell::new(HashMap::new()),
+            reaped: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Close the entry with the command's `exit_code` and add it to the
+    /// transcript. Children still running are cut off from the terminal.
+    pub fn finish(self, state: &mut ShellState, exit_code: i32) {
+        let _ = self.forward_all();
+        for captures in self.children.take().into_values() {
+            self.close(&captures);
+        }
+        let now_ms = self.host.monotonic_ms();
+        let Some(sink) = SINK.with(|cell| cell.borrow_mut().take()) else {
+            return;
+        };
+        let Some(transcript) = state.transcript.as_mut() else {
+            return;
+        };
+        if let Some(mut entry) = transcript.current.take() {
+            entry.duration_ms = transcript
+                .since_start(now_ms)
+                .saturating_sub(entry.start_ms);
+            entry.exit_code = exit_code;
+            entry.output = sink.output;
+            transcript.entries.push(entry);
+        }
+    }
+
+    /// Spawn a child with each of its output streams that leads to the
+    /// shell's stdout or stderr going through a capture pipe instead.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_captured(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        let mut captures = Vec::new();
+        let mut child_fds = [stdout_fd, stderr_fd];
+        let mut write_ends = Vec::new();
+        let mut opened = Ok(());
+        for (i, target) in [stdout_fd, stderr_fd].into_iter().enumerate() {
+            if i == 1 && stderr_fd == stdout_fd {
+                // `2>&1`: one pipe carries both.
+                child_fds[1] = child_fds[0];
+                break;
+            }
+            let Some(stream) = stream_of(target) else {
+                continue;
+            };
+            let pair = self
+                .host
+                .pipe()
+                .and_then(|(r, w)| match self.host.dup(target) {
+                    Ok(to) => Ok((r, w, to)),
+                    Err(e) => {
+                        let _ = self.host.close_fd(r);
+                        let _ = self.host.close_fd(w);
+                        Err(e)
+                    }
+                });
+            match pair {
+                Ok((from, w, to)) => {
+                    child_fds[i] = w;
+                    write_ends.push(w);
+                    captures.push(Capture { from, to, stream });
+                }
+                Err(e) => {
+                    opened = Err(e);
+                    break;
+                }
+            }
+        }
+        let spawned = opened.and_then(|()| {
+            self.host.spawn_with_limits(
+                program,
+                args,
+                env,
+                cwd,
+                stdin_data,
+                stdin_fd,
+                child_fds[0],
+                child_fds[1],
+                nice,
+                limits,
+            )
+        });
+        for fd in write_ends {
+            let _ = self.host.close_fd(fd);
+        }
+        match spawned {
+            Ok(pid) => {
+                self.children.borrow_mut().insert(pid, captures);
+                Ok(pid)
+            }
+            Err(e) => {
+                self.close(&captures);
+                Err(e)
+            }
+        }
+    }
+
+    /// Pass on, and record, whatever `captures` have had written to them.
+    fn forward(&self, captures: &[Capture]) -> Result<(), HostError> {
+        for capture in captures {
+            let chunk = self.host.read_fd(capture.from)?;
+            if !chunk.is_empty() {
+                crate::io::write_to(capture.to, &chunk);
+                self.monotonic_ms();
+                with_sink(|sink| sink.push(capture.stream, &chunk));
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward every capturing child's output, so none of them is left
+    /// stuck on a full pipe while another is waited for.
+    fn forward_all(&self) -> Result<(), HostError> {
+        for captures in self.children.borrow().values() {
+            self.forward(captures)?;
+        }
+        Ok(())
+    }
+
+    fn close(&self, captures: &[Capture]) {
+        for capture in captures {
+            let _ = self.host.close_fd(capture.from);
+            let _ = self.host.close_fd(capture.to);
+        }
+    }
+
+    /// Forward output and, once `pid` has exited, reap it.
+    fn poll(&self, pid: i32) -> Result<Option<SpawnResult>, HostError> {
+        self.forward_all()?;
+        if self.host.waitpid_nohang(pid)? < 0 {
+            return Ok(None);
+        }
+        // Pick up anything written between the forward and the exit.
+        let captures = self.children.borrow_mut().remove(&pid);
+        if let Some(captures) = captures {
+            self.forward(&captures)?;
+            self.close(&captures);
+        }
+        self.host.waitpid(pid).map(Some)
+    }
+
+    fn is_capturing(&self) -> bool {
+        !self.children.borrow().is_empty()
+    }
+}
+
+impl HostInterface for TranscriptHost<'_> {
+    fn spawn(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+    ) -> Result<i32, HostError> {
+        self.spawn_with_limits(
+            program,
+            args,
+            env,
+            cwd,
+            stdin_data,
+            stdin_fd,
+            stdout_fd,
+            stderr_fd,
+            nice,
+            &SpawnLimits::default(),
+        )
+    }
+
+    fn spawn_with_limits(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+        stdin_data: &str,
+        stdin_fd: i32,
+        stdout_fd: i32,
+        stderr_fd: i32,
+        nice: u8,
+        limits: &SpawnLimits,
+    ) -> Result<i32, HostError> {
+        let untracked = with_sink(|sink| sink.untracked).unwrap_or(true);
+        if untracked || (stream_of(stdout_fd).is_none() && stream_of(stderr_fd).is_none()) {
+            return self.host.spawn_with_limits(
+                program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice, limits,
+            );
+        }
+        self.spawn_captured(
+            program, args, env, cwd, stdin_data, stdin_fd, stdout_fd, stderr_fd, nice, limits,
+        )
+    }
+
+    fn spawn_duplex(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<DuplexChild, HostError> {
+        self.host.spawn_duplex(program, args, env, cwd)
+    }
+
+    fn spawn_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: &str,
+    ) -> Result<StreamingChild, HostError> {
+        self.host.spawn_streaming(program, args, env, cwd)
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        self.host.has_tool(name)
+    }
+
+    fn time(&self) -> f64 {
+        self.host.time()
+    }
+
+    fn monotonic_ms(&self) -> f64 {
+        let now_ms = self.host.monotonic_ms();
+        with_sink(|sink| sink.now_ms = now_ms);
+        now_ms
+    }
+
+    fn now_unix_ms(&self) -> u64 {
+        self.host.now_unix_ms()
+    }
+
+    fn monotonic_ns(&self) -> u64 {
+        self.host.monotonic_ns()
+    }
+
+    fn should_cancel(&self) -> Option<CancelReason> {
+        self.host.should_cancel()
+    }
+
+    fn random_bytes(&self, n: usize) -> Result<Vec<u8>, HostError> {
+        self.host.random_bytes(n)
+    }
+
+    fn isatty(&self, fd: i32) -> bool {
+        self.host.isatty(fd)
+    }
+
+    fn terminal_size(&self, fd: i32) -> Option<TerminalSize> {
+        self.host.terminal_size(fd)
+    }
+
+    fn stat(&self, path: &str) -> Result<StatInfo, HostError> {
+        self.host.stat(path)
+    }
+
+    fn read_file(&self, path: &str) -> Result<Vec<u8>, HostError> {
+        self.host.read_file(path)
+    }
+
+    fn write_file(&self, path: &str, data: &[u8], mode: WriteMode) -> Result<(), HostError> {
+        self.host.write_file(path, data, mode)
+    }
+
+    fn storage_quota(&self) -> Option<StorageQuota> {
+        self.host.storage_quota()
+    }
+
+    fn check_writable(&self, path: &str) -> Result<(), HostError> {
+        self.host.check_writable(path)
+    }
+
+    fn readdir(&self, path: &str) -> Result<Vec<String>, HostError> {
+        self.host.readdir(path)
+    }
+
+    fn mkdir(&self, path: &str) -> Result<(), HostError> {
+        self.host.mkdir(path)
+    }
+
+    fn remove(&self, path: &str, recursive: bool) -> Result<(), HostError> {
+        self.host.remove(path, recursive)
+    }
+
+    fn chmod(&self, path: &str, mode: u32) -> Result<(), HostError> {
+        self.host.chmod(path, mode)
+    }
+
+    fn set_mtime(&self, path: &str, mtime_ms: u64) -> Result<(), HostError> {
+        self.host.set_mtime(path, mtime_ms)
+    }
+
+    fn glob(&self, pattern: &str) -> Result<Vec<String>, HostError> {
+        self.host.glob(pattern)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<(), HostError> {
+        self.host.rename(from, to)
+    }
+
+    fn write_file_atomic(&self, path: &str, data: &[u8]) -> Result<(), HostError> {
+        self.host.write_file_atomic(path, data)
+    }
+
+    fn create_temp_file(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.host.create_temp_file(dir, prefix)
+    }
+
+    fn create_temp_dir(&self, dir: &str, prefix: &str) -> Result<String, HostError> {
+        self.host.create_temp_dir(dir, prefix)
+    }
+
+    fn watch(&self, path: &str) -> Result<FileWatch, HostError> {
+        self.host.watch(path)
+    }
+
+    fn wait_for_change(
+        &self,
+        watch: &mut FileWatch,
+        timeout_ms: u32,
+    ) -> Result<Option<FileChange>, HostError> {
+        self.host.wait_for_change(watch, timeout_ms)
+    }
+
+    fn symlink(&self, target: &str, link_path: &str) -> Result<(), HostError> {
+        self.host.symlink(target, link_path)
+    }
+
+    fn readlink(&self, path: &str) -> Result<String, HostError> {
+        self.host.readlink(path)
+    }
+
+    fn canonicalize(&self, path: &str) -> String {
+        self.host.canonicalize(path)
+    }
+
+    fn fetch(&self, request: &FetchRequest) -> FetchResult {
+        self.host.fetch(request)
+    }
+
+    /// Registering a tool can change what a program name runs, so it
+    /// invalidates the cache like a write.
+    fn register_tool(&self, name: &str, wasm_path: &str) -> Result<(), HostError> {
+        self.host.register_tool(name, wasm_path)
+    }
+
+    fn pipe(&self) -> Result<(i32, i32), HostError> {
+        self.host.pipe()
+    }
+
+    fn waitpid(&self, pid: i32) -> Result<SpawnResult, HostError> {
+        if let Some(exit_code) = self.reaped.borrow_mut().remove(&pid) {
+            return Ok(SpawnResult { exit_code });
+        }
+        if !self.is_capturing() {
+            return self.host.waitpid(pid);
+        }
+        loop {
+            if let Some(result) = self.poll(pid)? {
+                return Ok(result);
+            }
+            self.host.yield_now()?;
+        }
+    }
+
+    fn waitpid_timeout(&self, pid: i32, timeout_ms: u32) -> Result<Option<SpawnResult>, HostError> {
+        if let Some(exit_code) = self.reaped.borrow_mut().remove(&pid) {
+            return Ok(Some(SpawnResult { exit_code }));
+        }
+        if !self.is_capturing() {
+            return self.host.waitpid_timeout(pid, timeout_ms);
+        }
+        let deadline = self.host.monotonic_ms() + f64::from(timeout_ms);
+        loop {
+            if let Some(result) = self.poll(pid)? {
+                return Ok(Some(result));
+            }
+            if self.host.monotonic_ms() >= deadline {
+                return Ok(None);
+            }
+            self.host.yield_now()?;
+        }
+    }
+
+    fn kill(&self, pid: i32, signal: i32) -> Result<(), HostError> {
+        self.host.kill(pid, signal)
+    }
+
+    fn close_fd(&self, fd: i32) -> Result<(), HostError> {
+        with_sink(|sink| sink.fds.remove(&fd));
+        self.host.close_fd(fd)
+    }
+
+    fn dup(&self, fd: i32) -> Result<i32, HostError> {
+        let new_fd = self.host.dup(fd)?;
+        if let Some(stream) = stream_of(fd) {
+            with_sink(|sink| sink.fds.insert(new_fd, stream));
+        }
+        Ok(new_fd)
+    }
+
+    fn dup2(&self, src_fd: i32, dst_fd: i32) -> Result<(), HostError> {
+        self.host.dup2(src_fd, dst_fd)?;
+        let stream = stream_of(src_fd);
+        with_sink(|sink| match stream {
+            Some(stream) => sink.fds.insert(dst_fd, stream),
+            None => sink.fds.remove(&dst_fd),
+        });
+        Ok(())
+    }
+
+    fn read_fd(&self, fd: i32) -> Result<Vec<u8>, HostError> {
+        self.host.read_fd(fd)
+    }
+
+    fn write_fd(&self, fd: i32, data: &[u8]) -> Result<(), HostError> {
+        record_output(fd, data);
+        self.host.write_fd(fd, data)
+    }
+
+    fn yield_now(&self) -> Result<(), HostError> {
+        self.forward_all()?;
+        self.host.yield_now()
+    }
+
+    fn waitpid_nohang(&self, pid: i32) -> Result<i32, HostError> {
+        if let Some(&exit_code) = self.reaped.borrow().get(&pid) {
+            return Ok(exit_code);
+        }
+        if !self.is_capturing() {
+            return self.host.waitpid_nohang(pid);
+        }
+        // Polling reaps the child, so keep its status for the waitpid that
+        // follows.
+        match self.poll(pid)? {
+            Some(result) => {
+                self.reaped.borrow_mut().insert(pid, result.exit_code);
+                Ok(result.exit_code)
+            }
+            None => Ok(-1),
+        }
+    }
+
+    fn list_processes(&self) -> Result<String, HostError> {
+        self.host.list_processes()
+    }
+
+    fn socket_connect(&self, host: &str, port: u16, tls: bool) -> Result<u32, HostError> {
+        self.host.socket_connect(host, port, tls)
+    }
+
+    fn socket_send(&self, socket_id: u32, data: &[u8]) -> Result<usize, HostError> {
+        self.host.socket_send(socket_id, data)
+    }
+
+    fn socket_recv(&self, socket_id: u32, max_bytes: usize) -> Result<Vec<u8>, HostError> {
+        self.host.socket_recv(socket_id, max_bytes)
+    }
+
+    fn socket_close(&self, socket_id: u32) -> Result<(), HostError> {
+        self.host.socket_close(socket_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    use crate::rpc::run_command;
+    use crate::test_support::mock::{MockHost, MockSpawnOutput};
+
+    fn greet() -> MockSpawnOutput {
+        MockSpawnOutput {
+            exit_code: 0,
+            stdout: "hello\n".into(),
+            stderr: "warning\n".into(),
+        }
+    }
+
+    #[test]
+    fn records_commands_expansions_and_terminal_output() {
+        let host = MockHost::new()
+            .with_dir("/tmp")
+            .with_spawn_result("greet", greet());
+        let mut state = ShellState::new_default();
+        run_command(&mut state, &host, "echo before");
+        start(&mut state, &host);
+
+        run_command(
+            &mut state,
+            &host,
+            "X=world; echo hi $X; greet; echo quiet > /tmp/out",
+        );
+        run_command(&mut state, &host, "false");
+        let transcript = stop(&mut state).unwrap();
+        run_command(&mut state, &host, "echo after");
+
+        assert_eq!((transcript.width, transcript.height), (80, 24));
+        let [first, second] = &transcript.entries[..] else {
+            panic!("expected two entries: {:?}", transcript.entries);
+        };
+        assert_eq!(
+            first.command,
+            "X=world; echo hi $X; greet; echo quiet > /tmp/out"
+        );
+        let argvs: Vec<_> = first.steps.iter().map(|s| s.argv.join(" ")).collect();
+        assert_eq!(argvs, ["echo hi world", "greet", "echo quiet"]);
+        let output: Vec<_> = first
+            .output
+            .iter()
+            .map(|o| (o.stream, String::from_utf8_lossy(&o.data).into_owned()))
+            .collect();
+        assert_eq!(
+            output,
+            [
+                (OutputStream::Stdout, "hi world\nhello\n".to_string()),
+                (OutputStream::Stderr, "warning\n".to_string()),
+            ]
+        );
+        assert_eq!(host.get_file("/tmp/out").as_deref(), Some("quiet\n"));
+        assert_eq!((first.exit_code, second.exit_code), (0, 1));
+        assert!(second.output.is_empty());
+    }
+
+    #[test]
+    fn transcripts_play_back_as_asciicasts() {
+        let host = MockHost::new()
+            .with_unix_ms(1_700_000_000_500)
+            .with_spawn_duration_ms(250.0)
+            .with_spawn_result("greet", greet());
+        let mut state = ShellState::new_default();
+        start(&mut state, &host);
+        run_command(&mut state, &host, "greet");
+        run_command(&mut state, &host, "greet 2>&1");
+
+        let cast = state.transcript.as_ref().unwrap().to_asciicast();
+        let lines: Vec<Value> = cast
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines[0],
+            json!({ "version": 2, "width": 80, "height": 24, "timestamp": 1_700_000_000u64 })
+        );
+        assert_eq!(
+            lines[1..],
+            [
+                json!([0.0, "o", "$ greet\r\n"]),
+                json!([0.25, "o", "hello\r\n"]),
+                json!([0.25, "o", "warning\r\n"]),
+                json!([0.25, "o", "$ greet 2>&1\r\n"]),
+                json!([0.5, "o", "hello\r\nwarning\r\n"]),
+            ]
+        );
+    }
+}
@@ -4,7 +4,8 @@
 //! host via `HostInterface::fetch` / `register_tool`.
 
 use crate::control::RunResult;
-use crate::host::{HostInterface, WriteMode};
+use crate::host::{FetchRequest, HostInterface, WriteMode};
+use crate::pip_lite;
 use crate::state::ShellState;
 use crate::{shell_eprint, shell_print};
 use serde::{Deserialize, Serialize};
@@ -181,7 +182,12 @@ fn cmd_curl(
     let _ = follow_redirects; // follow-redirect is handled by the host fetch
     let _ = silent; // silence progress output (we don't output progress anyway)
 
-    let result = host.fetch(&url, &method, &header_refs, body);
+    let result = host.fetch(&FetchRequest {
+        method: &method,
+        url: &url,
+        headers: header_refs,
+        body,
+    });
 
     if let Some(ref err) = result.error {
         shell_eprint!("curl: {err}\n");
@@ -200,14 +206,14 @@ fn cmd_curl(
 
     if let Some(ref file) = output_file {
         let resolved = state.resolve_path(file);
-        if let Err(e) = host.write_file(&resolved, result.body.as_bytes(), WriteMode::Truncate) {
+        if let Err(e) = host.write_file(&resolved, &result.body_bytes(), WriteMode::Truncate) {
             shell_eprint!("curl: failed to write {file}: {e}\n");
             return RunResult::exit(1);
         }
         return RunResult::empty();
     }
 
-    shell_print!("{}", result.body);
+    crate::io::write_stdout(&result.body_bytes());
     RunResult::empty()
 }
 
@@ -297,7 +303,7 @@ fn cmd_wget(state: &mut ShellState, host: &dyn HostInterface, args: &[String]) -
         }
     };
 
-    let result = host.fetch(&url, "GET", &[], None);
+    let result = host.fetch(&FetchRequest::get(&url));
 
     if let Some(ref err) = result.error {
         shell_eprint!("wget: {err}\n");
@@ -306,7 +312,7 @@ fn cmd_wget(state: &mut ShellState, host: &dyn HostInterface, args: &[String]) -
 
     // -O - means write to stdout
     if output_file.as_deref() == Some("-") {
-        shell_print!("{}", result.body);
+        crate::io::write_stdout(&result.body_bytes());
         return RunResult::empty();
     }
 
@@ -326,7 +332,7 @@ fn cmd_wget(state: &mut ShellState, host: &dyn HostInterface, args: &[String]) -
     };
 
     let resolved = state.resolve_path(&filename);
-    if let Err(e) = host.write_file(&resolved, result.body.as_bytes(), WriteMode::Truncate) {
+    if let Err(e) = host.write_file(&resolved, &result.body_bytes(), WriteMode::Truncate) {
         shell_eprint!("wget: failed to write {filename}: {e}\n");
         return RunResult::exit(1);
     }
@@ -362,6 +368,10 @@ struct PipPolicy {
     blocked_packages: Option<Vec<String>>,
     #[serde(rename = "maxPackages")]
     max_packages: Option<usize>,
+    /// Simple package index for packages the codepod registry does not
+    /// serve (see `pip_lite`).
+    #[serde(rename = "indexUrl", default)]
+    index_url: Option<String>,
 }
 
 fn read_pip_policy(host: &dyn HostInterface) -> Option<PipPolicy> {
@@ -369,6 +379,21 @@ fn read_pip_policy(host: &dyn HostInterface) -> Option<PipPolicy> {
     serde_json::from_str(&json).ok()
 }
 
+/// Why `policy` refuses the package `name_lower`, if it does.
+fn pip_policy_denial(policy: &PipPolicy, name_lower: &str) -> Option<&'static str> {
+    if let Some(ref allowed) = policy.allowed_packages {
+        if !allowed.iter().any(|a| a.to_lowercase() == name_lower) {
+            return Some(" is not in the allowed packages list");
+        }
+    }
+    if let Some(ref blocked) = policy.blocked_packages {
+        if blocked.iter().any(|b| b.to_lowercase() == name_lower) {
+            return Some(" is blocked by policy");
+        }
+    }
+    None
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct PkgInfo {
     name: String,
@@ -485,7 +510,7 @@ fn fetch_pkg_index(state: &ShellState, host: &dyn HostInterface) -> Result<PkgIn
         .cloned()
         .unwrap_or_else(|| DEFAULT_REGISTRY_URL.to_string());
     let url = format!("{base_url}/pkg-index.json");
-    let result = host.fetch(&url, "GET", &[], None);
+    let result = host.fetch(&FetchRequest::get(&url));
     if let Some(ref err) = result.error {
         return Err(format!("failed to fetch pkg index: {err}"));
     }
@@ -563,14 +588,13 @@ fn pkg_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
         }
 
         // Download and install each tool in the package
-        let _ = host.mkdir("/usr/share/pkg");
-        let _ = host.mkdir("/usr/share/pkg/bin");
+        let _ = host.mkdir_p("/usr/share/pkg/bin");
         let mut total_size = 0;
 
         for (tool_name, wasm_path) in &entry.tools {
             let url = format!("{base_url}/{wasm_path}");
             shell_print!("Downloading {tool_name}...\n");
-            let result = host.fetch(&url, "GET", &[], None);
+            let result = host.fetch(&FetchRequest::get(&url));
             if result.error.is_some() || !result.ok {
                 let err = result.error.unwrap_or_else(|| format!("status {}", result.status));
                 shell_eprint!("pkg install: failed to download {tool_name}: {err}\n");
@@ -602,7 +626,7 @@ fn pkg_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
             name: name.clone(),
             url: format!("registry:{name}"),
             size: total_size,
-            installed_at: host.time() as u64,
+            installed_at: host.now_unix_ms() / 1000,
         });
         write_pkg_metadata(host, &packages);
 
@@ -646,7 +670,7 @@ fn pkg_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
     }
 
     // Fetch the WASM binary
-    let result = host.fetch(url, "GET", &[], None);
+    let result = host.fetch(&FetchRequest::get(url));
     if let Some(ref err) = result.error {
         shell_eprint!("pkg install: download failed: {err}\n");
         return RunResult::exit(1);
@@ -670,8 +694,7 @@ fn pkg_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
     }
 
     // Ensure directories exist
-    let _ = host.mkdir("/usr/share/pkg");
-    let _ = host.mkdir("/usr/share/pkg/bin");
+    let _ = host.mkdir_p("/usr/share/pkg/bin");
 
     // Write binary to VFS
     let wasm_path = format!("/usr/share/pkg/bin/{name}.wasm");
@@ -685,7 +708,7 @@ fn pkg_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
         name: name.clone(),
         url: url.clone(),
         size,
-        installed_at: host.time() as u64,
+        installed_at: host.now_unix_ms() / 1000,
     };
     packages.push(info);
     write_pkg_metadata(host, &packages);
@@ -932,7 +955,7 @@ fn fetch_registry_index(
         .unwrap_or_else(|| DEFAULT_REGISTRY_URL.to_string());
     let url = format!("{base_url}/index.json");
 
-    let result = host.fetch(&url, "GET", &[], None);
+    let result = host.fetch(&FetchRequest::get(&url));
     if let Some(ref err) = result.error {
         return Err(format!("failed to fetch registry: {err}"));
     }
@@ -1070,17 +1093,20 @@ fn resolve_deps(registry: &[PipRegistryEntry], name: &str) -> Vec<String> {
 fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]) -> RunResult {
     // Filter out flags
     let mut no_cache = false;
-    let names: Vec<&str> = args
-        .iter()
-        .map(|s| s.as_str())
-        .filter(|a| {
-            if *a == "--no-cache" {
-                no_cache = true;
-                return false;
+    let mut index_url: Option<String> = None;
+    let mut names: Vec<&str> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--no-cache" => no_cache = true,
+            "-i" | "--index-url" => index_url = iter.next().cloned(),
+            a if a.starts_with("--index-url=") => {
+                index_url = Some(a["--index-url=".len()..].to_string());
             }
-            !a.starts_with('-')
-        })
-        .collect();
+            a if a.starts_with('-') => {}
+            a => names.push(a),
+        }
+    }
 
     if names.is_empty() {
         shell_eprint!("{}", "pip install: no package specified\n");
@@ -1112,7 +1138,8 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
     // Check builtins and already-installed first
     let mut to_resolve: Vec<&str> = Vec::new();
     for name in &names {
-        let name_lower = name.to_lowercase();
+        // A requirement such as `six>=1.16` is looked up by its name.
+        let name_lower = pip_lite::project_name(name).to_lowercase();
         if BUILTIN_PACKAGES
             .iter()
             .any(|(n, _)| n.to_lowercase() == name_lower)
@@ -1129,17 +1156,9 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
         }
         // Check allow/block lists
         if let Some(ref policy) = pip_policy {
-            if let Some(ref allowed) = policy.allowed_packages {
-                if !allowed.iter().any(|a| a.to_lowercase() == name_lower) {
-                    shell_eprint!("pip install: {name} is not in the allowed packages list\n");
-                    return RunResult::exit(1);
-                }
-            }
-            if let Some(ref blocked) = policy.blocked_packages {
-                if blocked.iter().any(|b| b.to_lowercase() == name_lower) {
-                    shell_eprint!("pip install: {name} is blocked by policy\n");
-                    return RunResult::exit(1);
-                }
+            if let Some(denied) = pip_policy_denial(policy, &name_lower) {
+                shell_eprint!("pip install: {}{denied}\n", pip_lite::project_name(name));
+                return RunResult::exit(1);
             }
         }
         to_resolve.push(name);
@@ -1168,14 +1187,88 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
         }
     };
 
+    // Whatever the codepod registry does not serve comes from the
+    // host-provided package index, when there is one.
+    let index_url = index_url
+        .or_else(|| state.env.get("PIP_INDEX_URL").cloned())
+        .or_else(|| pip_policy.as_ref().and_then(|p| p.index_url.clone()));
+    let extensions = read_extension_meta(host);
+    let registry_name = |name: &str| -> Option<String> {
+        let wanted = pip_lite::normalize(name);
+        local_registry
+            .iter()
+            .map(|p| p.name.as_str())
+            .chain(remote_index.packages.keys().map(String::as_str))
+            .chain(
+                extensions
+                    .iter()
+                    .filter(|e| e.python_package.is_some())
+                    .map(|e| e.name.as_str()),
+            )
+            .find(|n| pip_lite::normalize(n) == wanted)
+            .map(str::to_string)
+    };
+
+    let mut registry_names: Vec<String> = Vec::new();
+    let mut resolution = pip_lite::Resolution::default();
+    if let Some(ref index_url) = index_url {
+        let mut requirements = Vec::new();
+        for name in &to_resolve {
+            if let Some(registry) = registry_name(pip_lite::project_name(name)) {
+                registry_names.push(registry);
+                continue;
+            }
+            match pip_lite::Requirement::parse(name) {
+                Ok(requirement) => requirements.push(requirement),
+                Err(e) => {
+                    shell_eprint!("ERROR: {e}\n");
+                    return RunResult::exit(1);
+                }
+            }
+        }
+        let provided = |name: &str| {
+            let wanted = pip_lite::normalize(name);
+            if BUILTIN_PACKAGES
+                .iter()
+                .any(|(n, _)| pip_lite::normalize(n) == wanted)
+                || installed
+                    .iter()
+                    .any(|i| pip_lite::normalize(&i.name) == wanted)
+            {
+                return Some(pip_lite::Provided::Installed);
+            }
+            registry_name(name).map(pip_lite::Provided::Registry)
+        };
+        resolution = match pip_lite::resolve(host, index_url, &requirements, &provided) {
+            Ok(resolution) => resolution,
+            Err(e) => {
+                shell_eprint!("ERROR: {e}\n");
+                return RunResult::exit(1);
+            }
+        };
+        // Dependencies are held to the same allow/block lists.
+        if let Some(ref policy) = pip_policy {
+            let names = resolution.dists.iter().map(|d| &d.name);
+            for name in names.chain(&resolution.registry) {
+                if let Some(denied) = pip_policy_denial(policy, &name.to_lowercase()) {
+                    shell_eprint!("pip install: {name}{denied}\n");
+                    return RunResult::exit(1);
+                }
+            }
+        }
+        registry_names.extend(resolution.registry.iter().cloned());
+    } else {
+        registry_names.extend(to_resolve.iter().map(|name| name.to_string()));
+    }
+
     // Resolve dependencies
     let mut install_order: Vec<String> = Vec::new();
     let mut visited = std::collections::HashSet::new();
-    for name in &to_resolve {
+    for name in &registry_names {
         resolve_registry_deps(&remote_index, name, &installed, &mut visited, &mut install_order);
     }
 
-    if install_order.is_empty() {
+    if install_order.is_empty() && resolution.dists.is_empty() {
         shell_print!("{}", "Requirement already satisfied\n");
         return RunResult::empty();
     }
@@ -1196,7 +1289,7 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
             for (path, content) in &local_pkg.files {
                 let full_path = format!("/usr/lib/python/{path}");
                 if let Some((parent, _)) = full_path.rsplit_once('/') {
-                    let _ = host.mkdir(parent);
+                    let _ = host.mkdir_p(parent);
                 }
                 let _ = host.write_file(&full_path, content.as_bytes(), WriteMode::Truncate);
             }
@@ -1215,7 +1308,7 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
             // Download and install WASM binary if present
             if let Some(ref wasm_path) = pkg.wasm {
                 let wasm_url = format!("{base_url}/{wasm_path}");
-                let result = host.fetch(&wasm_url, "GET", &[], None);
+                let result = host.fetch(&FetchRequest::get(&wasm_url));
                 if result.error.is_some() || !result.ok {
                     let err = result
                         .error
@@ -1240,7 +1333,7 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
 
             // Download and extract wheel
             let wheel_url = format!("{base_url}/{}", pkg.wheel);
-            let result = host.fetch(&wheel_url, "GET", &[], None);
+            let result = host.fetch(&FetchRequest::get(&wheel_url));
             if result.error.is_some() || !result.ok {
                 let err = result
                     .error
@@ -1256,7 +1349,7 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
                     for file in &files {
                         let full_path = format!("/usr/lib/python/{}", file.path);
                         if let Some((parent, _)) = full_path.rsplit_once('/') {
-                            let _ = host.mkdir(parent);
+                            let _ = host.mkdir_p(parent);
                         }
                         let _ = host.write_file(&full_path, file.content.as_bytes(), WriteMode::Truncate);
                     }
@@ -1275,7 +1368,7 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
             if let Some(ref native_path) = pkg.native_wasm {
                 let native_url = format!("{base_url}/{native_path}");
                 shell_print!("  Downloading native module...\n");
-                let result = host.fetch(&native_url, "GET", &[], None);
+                let result = host.fetch(&FetchRequest::get(&native_url));
                 if result.error.is_some() || !result.ok {
                     let err = result.error.unwrap_or_else(|| format!("status {}", result.status));
                     shell_eprint!("pip install: failed to download native WASM: {err}\n");
@@ -1336,6 +1429,30 @@ fn pip_install(state: &mut ShellState, host: &dyn HostInterface, args: &[String]
         }
     }
 
+    // Packages from the package index were downloaded while resolving.
+    for dist in &resolution.dists {
+        shell_print!("Installing {}...\n", dist.filename);
+        if !dist.skipped.is_empty() {
+            shell_eprint!(
+                "Warning: {} ships {} extension source file(s); only its pure-Python parts are installed\n",
+                dist.name,
+                dist.skipped.len()
+            );
+        }
+        match pip_lite::install(host, dist) {
+            Ok(count) => shell_print!("  Installed {count} files for {}\n", dist.name),
+            Err(e) => {
+                shell_eprint!("pip install: {e}\n");
+                return RunResult::exit(1);
+            }
+        }
+        new_installed.push(PipInstalledEntry {
+            name: dist.name.clone(),
+            version: dist.version.clone(),
+        });
+        installed_names.push(format!("{}-{}", dist.name, dist.version));
+    }
+
     write_pip_installed(host, &new_installed);
 
     if !installed_names.is_empty() {
@@ -1367,7 +1484,11 @@ fn pip_uninstall(state: &mut ShellState, host: &dyn HostInterface, args: &[Strin
 
     let mut out = String::new();
     for name in &names {
-        if let Some(pos) = installed.iter().position(|p| p.name == *name) {
+        let wanted = pip_lite::normalize(name);
+        if let Some(pos) = installed
+            .iter()
+            .position(|p| p.name == *name || pip_lite::normalize(&p.name) == wanted)
+        {
             // Remove Python files from VFS
             if let Some(pkg) = registry.iter().find(|p| p.name == *name) {
                 // Collect directories to remove (deduplicated)
@@ -1387,6 +1508,8 @@ fn pip_uninstall(state: &mut ShellState, host: &dyn HostInterface, args: &[Strin
                     let _ = host.remove(dir, true);
                 }
             }
+            // Packages from a simple index are removed via their RECORD
+            pip_lite::uninstall(host, name);
             installed.remove(pos);
             out.push_str(&format!("Successfully uninstalled {name}\n"));
         } else {
@@ -1512,6 +1635,32 @@ fn pip_show(state: &ShellState, host: &dyn HostInterface, args: &[String]) -> Ru
         return RunResult::empty();
     }
 
+    // Check packages installed from a simple index
+    if let Some(metadata) = pip_lite::installed_metadata(host, name) {
+        let field = |key: &str| {
+            metadata
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map_or("", |(_, v)| v.as_str())
+        };
+        let requires: Vec<String> = metadata
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("Requires-Dist"))
+            .filter_map(|(_, v)| pip_lite::Requirement::parse(v).ok())
+            .filter(|r| r.applies(&[]))
+            .map(|r| r.name)
+            .collect();
+        shell_print!(
+            "Name: {}\nVersion: {}\nSummary: {}\nLocation: {}\nRequires: {}\n",
+            field("Name"),
+            field("Version"),
+            field("Summary"),
+            pip_lite::SITE_PACKAGES,
+            requires.join(", ")
+        );
+        return RunResult::empty();
+    }
+
     // Check remote registry
     if let Ok(index) = fetch_registry_index(state, host) {
         let name_lower = name.to_lowercase();
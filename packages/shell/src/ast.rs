@@ -1,9 +1,9 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::token::RedirectType;
 
 /// A word that may contain variable references or command substitutions.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WordPart {
     Literal(String),
     QuotedLiteral(String),
@@ -20,7 +20,7 @@ pub enum WordPart {
 }
 
 /// A shell word composed of one or more parts.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Word {
     pub parts: Vec<WordPart>,
 }
@@ -40,20 +40,20 @@ impl Word {
 }
 
 /// An I/O redirection attached to a command.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Redirect {
     pub redirect_type: RedirectType,
 }
 
 /// A variable assignment preceding a command (e.g. `FOO=bar cmd`).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Assignment {
     pub name: String,
     pub value: String,
 }
 
 /// The operator joining two commands in a list.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ListOp {
     And,        // &&
     Or,         // ||
@@ -62,7 +62,7 @@ pub enum ListOp {
 }
 
 /// A shell command AST node.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Command {
     /// A simple command: words + redirects + optional assignments.
     Simple {
@@ -90,6 +90,12 @@ pub enum Command {
         words: Vec<Word>,
         body: Box<Command>,
     },
+    /// Menu loop: select var in words; do ...; done
+    Select {
+        var: String,
+        words: Vec<Word>,
+        body: Box<Command>,
+    },
     /// C-style for loop: for ((init; cond; step)) do ... done
     CFor {
         init: String,
@@ -118,6 +124,13 @@ pub enum Command {
     Continue,
     /// Negate exit code of a pipeline.
     Negate { body: Box<Command> },
+    /// `time [-p] pipeline`: report the pipeline's wall time on stderr.
+    Timed { body: Box<Command>, posix: bool },
+    /// `coproc [NAME] command`: run command asynchronously, connected to the
+    /// shell by pipes whose fds are stored in the array NAME (default COPROC).
+    Coproc { name: String, body: Box<Command> },
+    /// `timeout DURATION command`: kill the command if it outlives DURATION.
+    Timeout { duration: Word, body: Box<Command> },
     /// Function definition.
     Function { name: String, body: Box<Command> },
     /// Case statement.
@@ -129,7 +142,7 @@ pub enum Command {
 }
 
 /// A single arm of a case statement.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CaseItem {
     pub patterns: Vec<Word>,
     pub body: Box<Command>,
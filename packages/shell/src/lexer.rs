@@ -1,14 +1,35 @@
 use crate::ast::WordPart;
-use crate::token::{RedirectType, Token};
+use crate::token::{RedirectType, Span, Token};
 
 /// Tokenize a shell command string into a vector of tokens.
 pub fn lex(input: &str) -> Vec<Token> {
+    lex_spanned(input)
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
+
+/// Tokenize like [`lex`], pairing each token with the byte range of the
+/// input it was read from.
+pub fn lex_spanned(input: &str) -> Vec<(Token, Span)> {
     let mut tokens = Vec::new();
+    let mut spans = Vec::new();
     let chars: Vec<char> = input.chars().collect();
     let len = chars.len();
+    // Byte offset of each char position, plus one for the end of input.
+    let offsets: Vec<usize> = input
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(input.len()))
+        .collect();
     let mut pos = 0;
+    let mut token_start = 0;
 
     while pos < len {
+        // Tokens pushed by the previous iteration span the text it consumed.
+        close_spans(&mut spans, tokens.len(), offsets[token_start], offsets[pos]);
+        token_start = pos;
+
         // Skip whitespace (but not newlines)
         if chars[pos] == ' ' || chars[pos] == '\t' {
             pos += 1;
@@ -99,7 +120,8 @@ pub fn lex(input: &str) -> Vec<Token> {
                         | Some(Token::Else)
                         | Some(Token::LBrace)
                         | Some(Token::DoubleSemi)
-                );
+                )
+                || (chars[pos] == '{' && follows_coproc(&tokens, chars.get(pos + 1)));
             if is_command_start {
                 if chars[pos] == '{' {
                     tokens.push(Token::LBrace);
@@ -196,11 +218,40 @@ pub fn lex(input: &str) -> Vec<Token> {
             continue;
         }
 
-        // 2>&1, 2>file, 2>>file
+        // {var}>file, {var}>>file, {var}>&- — fd allocated into a variable
+        if chars[pos] == '{' {
+            if let Some((name, end)) = match_fd_var_prefix(&chars, pos) {
+                pos = end;
+                if pos + 2 < len && chars[pos + 1] == '&' && chars[pos + 2] == '-' {
+                    pos += 3;
+                    tokens.push(Token::Redirect(RedirectType::FdVarClose(name)));
+                    continue;
+                }
+                let append = pos + 1 < len && chars[pos + 1] == '>';
+                pos += if append { 2 } else { 1 };
+                skip_whitespace(&chars, &mut pos);
+                let target = read_redirect_target(&chars, &mut pos);
+                tokens.push(Token::Redirect(if append {
+                    RedirectType::FdVarAppend(name, target)
+                } else {
+                    RedirectType::FdVarOverwrite(name, target)
+                }));
+                continue;
+            }
+        }
+
+        // 2>&1, 2>&N, 2>&-, 2>file, 2>>file
         if chars[pos] == '2' && pos + 1 < len && chars[pos + 1] == '>' {
-            if pos + 3 < len && chars[pos + 2] == '&' && chars[pos + 3] == '1' {
-                tokens.push(Token::Redirect(RedirectType::StderrToStdout));
-                pos += 4;
+            if pos + 2 < len && chars[pos + 2] == '&' {
+                pos += 3;
+                let src = read_fd_word(&chars, &mut pos);
+                tokens.push(Token::Redirect(if src == "1" {
+                    RedirectType::StderrToStdout
+                } else if src == "-" {
+                    RedirectType::FdClose(2)
+                } else {
+                    RedirectType::FdDup(2, src)
+                }));
                 continue;
             }
             if pos + 2 < len && chars[pos + 2] == '>' {
@@ -219,6 +270,34 @@ pub fn lex(input: &str) -> Vec<Token> {
             continue;
         }
 
+        // 1>file, 1>>file, 1>&N and N>file, N>>file, N>&M, N>&- for N = 3..9
+        if matches!(chars[pos], '1' | '3'..='9') && pos + 1 < len && chars[pos + 1] == '>' {
+            let fd = chars[pos].to_digit(10).unwrap_or(1) as i32;
+            if pos + 2 < len && chars[pos + 2] == '&' {
+                pos += 3;
+                let src = read_fd_word(&chars, &mut pos);
+                tokens.push(Token::Redirect(if src == "-" {
+                    RedirectType::FdClose(fd)
+                } else if fd == 1 {
+                    RedirectType::StdoutOverwrite(format!("&{src}"))
+                } else {
+                    RedirectType::FdDup(fd, src)
+                }));
+                continue;
+            }
+            let append = pos + 2 < len && chars[pos + 2] == '>';
+            pos += if append { 3 } else { 2 };
+            skip_whitespace(&chars, &mut pos);
+            let target = read_redirect_target(&chars, &mut pos);
+            tokens.push(Token::Redirect(match (fd, append) {
+                (1, false) => RedirectType::StdoutOverwrite(target),
+                (1, true) => RedirectType::StdoutAppend(target),
+                (_, false) => RedirectType::FdOverwrite(fd, target),
+                (_, true) => RedirectType::FdAppend(fd, target),
+            }));
+            continue;
+        }
+
         // Output process substitution: >(cmd)
         if chars[pos] == '>' && pos + 1 < len && chars[pos + 1] == '(' {
             pos += 2;
@@ -229,27 +308,30 @@ pub fn lex(input: &str) -> Vec<Token> {
             continue;
         }
 
-        // > or >> or >&N or < redirects
+        // > or >> or >| or >&N or < redirects
         if chars[pos] == '>' {
             if pos + 1 < len && chars[pos + 1] == '>' {
                 pos += 2;
                 skip_whitespace(&chars, &mut pos);
                 let target = read_redirect_target(&chars, &mut pos);
                 tokens.push(Token::Redirect(RedirectType::StdoutAppend(target)));
+            } else if pos + 1 < len && chars[pos + 1] == '|' {
+                // >| file — force overwrite regardless of noclobber
+                pos += 2;
+                skip_whitespace(&chars, &mut pos);
+                let target = read_redirect_target(&chars, &mut pos);
+                tokens.push(Token::Redirect(RedirectType::StdoutClobber(target)));
             } else if pos + 1 < len && chars[pos + 1] == '&' {
                 // >&N — redirect stdout to fd N
                 pos += 2; // skip >&
-                let mut fd = String::new();
-                while pos < len && chars[pos].is_ascii_digit() {
-                    fd.push(chars[pos]);
-                    pos += 1;
-                }
-                if fd.is_empty() || fd == "-" {
-                    // >& with no fd number — treat as close (>&-) or error
-                    // Just skip; no redirect token emitted
-                } else {
-                    // >&1, >&2, etc — encode as StdoutOverwrite("&N")
-                    tokens.push(Token::Redirect(RedirectType::StdoutOverwrite(format!("&{fd}"))));
+                let fd = read_fd_word(&chars, &mut pos);
+                if fd == "-" {
+                    tokens.push(Token::Redirect(RedirectType::FdClose(1)));
+                } else if !fd.is_empty() {
+                    // >&1, >&2, >&$fd etc — encode as StdoutOverwrite("&N")
+                    tokens.push(Token::Redirect(RedirectType::StdoutOverwrite(format!(
+                        "&{fd}"
+                    ))));
                 }
             } else {
                 pos += 1;
@@ -298,8 +380,8 @@ pub fn lex(input: &str) -> Vec<Token> {
                 }
                 skip_whitespace(&chars, &mut pos);
 
-                // Read delimiter (may be quoted with ' or ")
-                let (delimiter, _quoted) = read_heredoc_delimiter(&chars, &mut pos);
+                // Read delimiter (quoting any part of it disables expansion)
+                let (delimiter, quoted) = read_heredoc_delimiter(&chars, &mut pos);
 
                 // Capture any remaining tokens on this line (e.g. `> /tmp/file`)
                 // before consuming the heredoc body on subsequent lines.
@@ -322,12 +404,15 @@ pub fn lex(input: &str) -> Vec<Token> {
                         pos += 1;
                     }
                     let line: String = chars[line_start..pos].iter().collect();
-                    let trimmed = if strip_tabs {
+                    // The delimiter must be the whole line; `<<-` also
+                    // allows leading tabs. The body keeps its tabs and is
+                    // stripped when the command runs.
+                    let candidate = if strip_tabs {
                         line.trim_start_matches('\t')
                     } else {
                         &line
                     };
-                    if trimmed.trim() == delimiter {
+                    if candidate == delimiter {
                         if pos < len {
                             pos += 1;
                         } // skip delimiter newline
@@ -342,7 +427,7 @@ pub fn lex(input: &str) -> Vec<Token> {
                     }
                 }
 
-                let rtype = match (strip_tabs, _quoted) {
+                let rtype = match (strip_tabs, quoted) {
                     (true, true) => RedirectType::HeredocStripQuoted(content),
                     (true, false) => RedirectType::HeredocStrip(content),
                     (false, true) => RedirectType::HeredocQuoted(content),
@@ -367,6 +452,25 @@ pub fn lex(input: &str) -> Vec<Token> {
                 }
                 continue;
             }
+            if pos + 1 < len && chars[pos + 1] == '>' {
+                // <> file: open read-write on stdin
+                pos += 2;
+                skip_whitespace(&chars, &mut pos);
+                let target = read_redirect_target(&chars, &mut pos);
+                tokens.push(Token::Redirect(RedirectType::ReadWrite(target)));
+                continue;
+            }
+            if pos + 1 < len && chars[pos + 1] == '&' {
+                // <&N: read stdin from fd N; <&-: close stdin
+                pos += 2;
+                let src = read_fd_word(&chars, &mut pos);
+                tokens.push(Token::Redirect(if src == "-" {
+                    RedirectType::FdClose(0)
+                } else {
+                    RedirectType::StdinFrom(format!("&{src}"))
+                }));
+                continue;
+            }
             pos += 1;
             skip_whitespace(&chars, &mut pos);
             let target = read_redirect_target(&chars, &mut pos);
@@ -383,8 +487,16 @@ pub fn lex(input: &str) -> Vec<Token> {
             tokens.push(compound_to_token(parts));
         }
     }
+    close_spans(&mut spans, tokens.len(), offsets[token_start], offsets[len]);
+
+    tokens.into_iter().zip(spans).collect()
+}
 
-    tokens
+/// Give every token past the end of `spans` (up to `count`) the span
+/// `start..end`.
+fn close_spans(spans: &mut Vec<Span>, count: usize, start: usize, end: usize) {
+    spans.truncate(count);
+    spans.resize(count, Span { start, end });
 }
 
 /// Advance `pos` past any spaces and tabs.
@@ -541,7 +653,7 @@ fn read_compound_word(chars: &[char], pos: &mut usize) -> Vec<WordPart> {
                 continue;
             }
             // Special variables: $?, $$, $!, $#, $@, $*, $0-$9
-            if *pos < chars.len() && "?$!#@*".contains(chars[*pos]) {
+            if *pos < chars.len() && "?$!#@*-".contains(chars[*pos]) {
                 let var = chars[*pos].to_string();
                 *pos += 1;
                 parts.push(WordPart::Variable(var));
@@ -562,7 +674,7 @@ fn read_compound_word(chars: &[char], pos: &mut usize) -> Vec<WordPart> {
         // Backtick command substitution
         if ch == '`' {
             *pos += 1;
-            let content = read_until_char(chars, pos, '`');
+            let content = read_backtick_body(chars, pos, false, false);
             parts.push(WordPart::CommandSub(content));
             continue;
         }
@@ -670,6 +782,69 @@ fn read_redirect_target(chars: &[char], pos: &mut usize) -> String {
     result
 }
 
+/// Read the source of an fd duplication (`>&N`): digits, `-`, or a `$name`
+/// reference that the executor resolves at run time.
+fn read_fd_word(chars: &[char], pos: &mut usize) -> String {
+    if *pos < chars.len() && chars[*pos] == '-' {
+        *pos += 1;
+        return "-".to_string();
+    }
+    let mut word = String::new();
+    if *pos < chars.len() && chars[*pos] == '$' {
+        word.push('$');
+        *pos += 1;
+        if *pos < chars.len() && chars[*pos] == '{' {
+            // ${name} or ${name[index]}, kept verbatim up to the closing brace
+            while *pos < chars.len() {
+                word.push(chars[*pos]);
+                *pos += 1;
+                if word.ends_with('}') {
+                    break;
+                }
+            }
+        } else {
+            word.push_str(&read_var_name(chars, pos));
+        }
+        return word;
+    }
+    while *pos < chars.len() && chars[*pos].is_ascii_digit() {
+        word.push(chars[*pos]);
+        *pos += 1;
+    }
+    word
+}
+
+/// Whether a `{` opens the body of `coproc` or `coproc NAME`. The brace must
+/// be followed by whitespace so `coproc cmd {a,b}` still brace-expands.
+fn follows_coproc(tokens: &[Token], next: Option<&char>) -> bool {
+    if !next.is_some_and(|c| c.is_whitespace()) {
+        return false;
+    }
+    let is_coproc = |t: &Token| matches!(t, Token::Word(w) if w == "coproc");
+    match tokens {
+        [.., last] if is_coproc(last) => true,
+        [.., prev, Token::Word(_)] => is_coproc(prev),
+        _ => false,
+    }
+}
+
+/// Match `{name}>` at `pos`, returning the variable name and the position of
+/// the `>`. Used for the `{fd}>file` allocation syntax.
+fn match_fd_var_prefix(chars: &[char], pos: usize) -> Option<(String, usize)> {
+    let mut end = pos + 1;
+    while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+        end += 1;
+    }
+    let name: String = chars[pos + 1..end].iter().collect();
+    if !is_valid_var_name(&name) || end + 1 >= chars.len() {
+        return None;
+    }
+    if chars[end] != '}' || chars[end + 1] != '>' {
+        return None;
+    }
+    Some((name, end + 1))
+}
+
 /// Read until a matching close parenthesis, handling nesting.
 fn read_balanced_parens(chars: &[char], pos: &mut usize) -> String {
     let mut result = String::new();
@@ -704,6 +879,35 @@ fn read_until_char(chars: &[char], pos: &mut usize, terminator: char) -> String
     result
 }
 
+/// Read the body of a `` `cmd` `` substitution. Consumes the closing backtick.
+///
+/// Inside backticks a backslash only quotes `$`, `` ` `` and `\`, so that
+/// `` `echo \`date\`` `` nests one substitution inside another; within
+/// double quotes (`quoted`) it quotes `"` as well. Unless `keep_escapes` is
+/// set those backslashes are removed; any other backslash is kept verbatim.
+fn read_backtick_body(chars: &[char], pos: &mut usize, quoted: bool, keep_escapes: bool) -> String {
+    let mut result = String::new();
+    while *pos < chars.len() && chars[*pos] != '`' {
+        if chars[*pos] == '\\'
+            && *pos + 1 < chars.len()
+            && (matches!(chars[*pos + 1], '$' | '`' | '\\') || quoted && chars[*pos + 1] == '"')
+        {
+            if keep_escapes {
+                result.push('\\');
+            }
+            result.push(chars[*pos + 1]);
+            *pos += 2;
+            continue;
+        }
+        result.push(chars[*pos]);
+        *pos += 1;
+    }
+    if *pos < chars.len() {
+        *pos += 1; // skip closing backtick
+    }
+    result
+}
+
 /// Read a simple variable name (alphanumeric + underscore).
 fn read_var_name(chars: &[char], pos: &mut usize) -> String {
     let mut name = String::new();
@@ -781,7 +985,7 @@ fn lex_double_quoted(chars: &[char], pos: &mut usize) -> Vec<WordPart> {
                 continue;
             }
             // Special variables: $?, $$, $!, $#, $@, $*
-            if *pos < chars.len() && "?$!#@*".contains(chars[*pos]) {
+            if *pos < chars.len() && "?$!#@*-".contains(chars[*pos]) {
                 let var = chars[*pos].to_string();
                 *pos += 1;
                 parts.push(WordPart::Variable(var));
@@ -806,7 +1010,7 @@ fn lex_double_quoted(chars: &[char], pos: &mut usize) -> Vec<WordPart> {
                 parts.push(WordPart::QuotedLiteral(std::mem::take(&mut literal)));
             }
             *pos += 1;
-            let content = read_until_char(chars, pos, '`');
+            let content = read_backtick_body(chars, pos, true, false);
             parts.push(WordPart::CommandSub(content));
             continue;
         }
@@ -832,6 +1036,128 @@ fn lex_double_quoted(chars: &[char], pos: &mut usize) -> Vec<WordPart> {
     parts
 }
 
+/// Whether `input` is a complete command, as opposed to one that continues
+/// on the next line: an open quote, `$(`, `${` or compound command, a
+/// heredoc without its delimiter line, or a trailing `|`, `&&`, `||` or `\`.
+/// Interactive prompts use this to decide when to ask for more input.
+pub fn is_complete(input: &str) -> bool {
+    let chars: Vec<char> = input.chars().collect();
+    let mut pos = 0;
+    // Open quoting contexts, innermost last: '\'', '"', '`', '(' for `$(`
+    // and '{' for `${`.
+    let mut open: Vec<char> = Vec::new();
+    let mut heredocs: Vec<(String, bool)> = Vec::new();
+    while pos < chars.len() {
+        let ch = chars[pos];
+        let top = open.last().copied();
+        if top == Some('\'') {
+            if ch == '\'' {
+                open.pop();
+            }
+            pos += 1;
+            continue;
+        }
+        match ch {
+            '\\' if pos + 1 == chars.len() => return false,
+            '\\' => pos += 1,
+            '"' if top == Some('"') => {
+                open.pop();
+            }
+            '`' if top == Some('`') => {
+                open.pop();
+            }
+            ')' if top == Some('(') => {
+                open.pop();
+            }
+            '}' if top == Some('{') => {
+                open.pop();
+            }
+            '"' | '`' => open.push(ch),
+            '\'' if top != Some('"') => open.push(ch),
+            '(' if top == Some('(') => open.push('('),
+            '$' if matches!(chars.get(pos + 1), Some('(') | Some('{')) => {
+                pos += 1;
+                open.push(chars[pos]);
+            }
+            '#' if open.is_empty() && (pos == 0 || chars[pos - 1].is_whitespace()) => {
+                while pos + 1 < chars.len() && chars[pos + 1] != '\n' {
+                    pos += 1;
+                }
+            }
+            '<' if open.is_empty()
+                && chars.get(pos + 1) == Some(&'<')
+                && chars.get(pos + 2) != Some(&'<') =>
+            {
+                pos += 2;
+                let strip_tabs = chars.get(pos) == Some(&'-');
+                if strip_tabs {
+                    pos += 1;
+                }
+                skip_whitespace(&chars, &mut pos);
+                let (delimiter, _) = read_heredoc_delimiter(&chars, &mut pos);
+                heredocs.push((delimiter, strip_tabs));
+                continue;
+            }
+            '\n' if open.is_empty() && !heredocs.is_empty() => {
+                // Heredoc bodies follow the line that introduced them.
+                for (delimiter, strip_tabs) in std::mem::take(&mut heredocs) {
+                    loop {
+                        if pos + 1 >= chars.len() {
+                            return false;
+                        }
+                        let start = pos + 1;
+                        let end = chars[start..]
+                            .iter()
+                            .position(|&c| c == '\n')
+                            .map_or(chars.len(), |n| start + n);
+                        let line: String = chars[start..end].iter().collect();
+                        pos = end;
+                        let line = if strip_tabs {
+                            line.trim_start_matches('\t')
+                        } else {
+                            &line
+                        };
+                        if line == delimiter {
+                            break;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    if !open.is_empty() || !heredocs.is_empty() {
+        return false;
+    }
+
+    let trimmed = input.trim_end();
+    if trimmed.ends_with('|') || trimmed.ends_with("&&") {
+        return false;
+    }
+
+    // Every compound command must be closed.
+    let mut depth = [0i32; 4]; // if/fi, case/esac, do/done, { }
+    let mut parens = 0i32;
+    for token in lex(input) {
+        match token {
+            Token::If => depth[0] += 1,
+            Token::Fi => depth[0] -= 1,
+            Token::Case => depth[1] += 1,
+            Token::Esac => depth[1] -= 1,
+            Token::Do => depth[2] += 1,
+            Token::Done => depth[2] -= 1,
+            Token::LBrace => depth[3] += 1,
+            Token::RBrace => depth[3] -= 1,
+            Token::LParen => parens += 1,
+            // `)` also ends case patterns, so it never goes below zero.
+            Token::RParen => parens = (parens - 1).max(0),
+            _ => {}
+        }
+    }
+    depth.iter().all(|&d| d <= 0) && parens == 0
+}
+
 /// Parse a raw string for variable/command expansion (like double-quoted content).
 ///
 /// This is used by the executor to expand heredoc/herestring content.
@@ -851,6 +1177,11 @@ pub fn parse_string_expansion(input: &str) -> Vec<WordPart> {
                 pos += 2;
                 continue;
             }
+            if next == '\n' {
+                // Line continuation
+                pos += 2;
+                continue;
+            }
         }
 
         // $ — variable or command substitution
@@ -899,7 +1230,7 @@ pub fn parse_string_expansion(input: &str) -> Vec<WordPart> {
                 parts.push(parse_braced_var(&var));
                 continue;
             }
-            if pos < chars.len() && "?$!#@*".contains(chars[pos]) {
+            if pos < chars.len() && "?$!#@*-".contains(chars[pos]) {
                 let var = chars[pos].to_string();
                 pos += 1;
                 parts.push(WordPart::Variable(var));
@@ -912,7 +1243,12 @@ pub fn parse_string_expansion(input: &str) -> Vec<WordPart> {
                 continue;
             }
             let var = read_var_name(&chars, &mut pos);
-            parts.push(WordPart::Variable(var));
+            if var.is_empty() {
+                // A `$` that starts no expansion is literal.
+                literal.push('$');
+            } else {
+                parts.push(WordPart::Variable(var));
+            }
             continue;
         }
 
@@ -922,7 +1258,7 @@ pub fn parse_string_expansion(input: &str) -> Vec<WordPart> {
                 parts.push(WordPart::QuotedLiteral(std::mem::take(&mut literal)));
             }
             pos += 1;
-            let content = read_until_char(&chars, &mut pos, '`');
+            let content = read_backtick_body(&chars, &mut pos, false, false);
             parts.push(WordPart::CommandSub(content));
             continue;
         }
@@ -1022,7 +1358,8 @@ fn read_word(chars: &[char], pos: &mut usize) -> String {
         if ch == '`' && seen_eq {
             word.push('`');
             *pos += 1;
-            let content = read_until_char(chars, pos, '`');
+            // Keep escapes intact; the assignment value is re-parsed later.
+            let content = read_backtick_body(chars, pos, false, true);
             word.push_str(&content);
             word.push('`');
             continue;
@@ -1170,22 +1507,33 @@ fn parse_braced_var(content: &str) -> WordPart {
     WordPart::Variable(content.to_string())
 }
 
-/// Read a here-document delimiter. May be quoted with ' or ".
-/// Returns (delimiter, was_quoted).
+/// Read a here-document delimiter word, removing any quotes and backslashes
+/// (`'EOF'`, `"EOF"`, `\EOF`, `E"O"F`). Returns (delimiter, was_quoted); a
+/// quoted delimiter keeps the body literal.
 fn read_heredoc_delimiter(chars: &[char], pos: &mut usize) -> (String, bool) {
-    if *pos < chars.len() && (chars[*pos] == '\'' || chars[*pos] == '"') {
-        let quote = chars[*pos];
-        *pos += 1;
-        let delim = read_until_char(chars, pos, quote);
-        (delim, true)
-    } else {
-        let mut delim = String::new();
-        while *pos < chars.len() && !chars[*pos].is_whitespace() && chars[*pos] != '\n' {
-            delim.push(chars[*pos]);
-            *pos += 1;
+    let mut delim = String::new();
+    let mut quoted = false;
+    while *pos < chars.len() {
+        let ch = chars[*pos];
+        match ch {
+            '\'' | '"' => {
+                quoted = true;
+                *pos += 1;
+                delim.push_str(&read_until_char(chars, pos, ch));
+            }
+            '\\' if *pos + 1 < chars.len() => {
+                quoted = true;
+                delim.push(chars[*pos + 1]);
+                *pos += 2;
+            }
+            _ if ch.is_whitespace() || ";|&<>()".contains(ch) => break,
+            _ => {
+                delim.push(ch);
+                *pos += 1;
+            }
         }
-        (delim, false)
     }
+    (delim, quoted)
 }
 
 /// Check whether `name` is a valid shell variable name (starts with letter or
@@ -1326,6 +1674,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn redirect_numbered_fds() {
+        let tokens = lex("cmd 3>log 4>>all 2>&3 3>&- 1>out");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("cmd".into()),
+                Token::Redirect(RedirectType::FdOverwrite(3, "log".into())),
+                Token::Redirect(RedirectType::FdAppend(4, "all".into())),
+                Token::Redirect(RedirectType::FdDup(2, "3".into())),
+                Token::Redirect(RedirectType::FdClose(3)),
+                Token::Redirect(RedirectType::StdoutOverwrite("out".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirect_stderr_dup_multi_digit_fd() {
+        let tokens = lex("cmd 2>&10 2>&1");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("cmd".into()),
+                Token::Redirect(RedirectType::FdDup(2, "10".into())),
+                Token::Redirect(RedirectType::StderrToStdout),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirect_fd_dup_variable() {
+        let tokens = lex("echo hi >&$fd");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("echo".into()),
+                Token::Word("hi".into()),
+                Token::Redirect(RedirectType::StdoutOverwrite("&$fd".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirect_fd_variable_allocation() {
+        let tokens = lex("exec {log}>>app.log {log}>&-");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("exec".into()),
+                Token::Redirect(RedirectType::FdVarAppend("log".into(), "app.log".into())),
+                Token::Redirect(RedirectType::FdVarClose("log".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirect_clobber() {
+        let tokens = lex("echo hello >| file.txt");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("echo".into()),
+                Token::Word("hello".into()),
+                Token::Redirect(RedirectType::StdoutClobber("file.txt".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirect_read_write() {
+        let tokens = lex("cat <> data.txt");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("cat".into()),
+                Token::Redirect(RedirectType::ReadWrite("data.txt".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn redirect_stdin_dup_from_array_element() {
+        let tokens = lex("read -r line <&${COPROC[0]}");
+        assert_eq!(
+            tokens[3],
+            Token::Redirect(RedirectType::StdinFrom("&${COPROC[0]}".into()))
+        );
+        assert_eq!(lex("cat <&-")[1], Token::Redirect(RedirectType::FdClose(0)));
+    }
+
     #[test]
     fn redirect_stderr_to_stdout() {
         let tokens = lex("cmd 2>&1");
@@ -1408,6 +1846,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn backtick_nested_escaped() {
+        let tokens = lex(r"echo `echo \`echo hi\``");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("echo".into()),
+                Token::CommandSub("echo `echo hi`".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn backtick_in_double_quotes_unescapes_dollar() {
+        let tokens = lex(r#"echo "x`echo \$HOME`""#);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("echo".into()),
+                Token::DoubleQuoted(vec![
+                    WordPart::QuotedLiteral("x".into()),
+                    WordPart::CommandSub("echo $HOME".into()),
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn backtick_in_double_quotes_unescapes_quote() {
+        let tokens = lex(r#"echo "`echo \"x\"`" `echo \"y\"`"#);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("echo".into()),
+                Token::CommandSub("echo \"x\"".into()),
+                Token::CommandSub(r#"echo \"y\""#.into()),
+            ]
+        );
+    }
+
     #[test]
     fn escaped_space() {
         let tokens = lex(r"echo hello\ world");
@@ -1564,6 +2042,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn heredoc_quoted_delimiter_forms() {
+        // Any quoting in the delimiter keeps the body literal, and only an
+        // exact delimiter line ends it.
+        for src in [
+            "cat <<\\EOF\n$x\n  EOF\nEOF",
+            "cat <<E\"O\"F\n$x\n  EOF\nEOF",
+        ] {
+            assert_eq!(
+                lex(src),
+                vec![
+                    Token::Word("cat".into()),
+                    Token::Redirect(RedirectType::HeredocQuoted("$x\n  EOF\n".into())),
+                ]
+            );
+        }
+        // <<- accepts a tab-indented delimiter but leaves the body as written.
+        assert_eq!(
+            lex("cat <<-EOF\n\tbody\n\tEOF"),
+            vec![
+                Token::Word("cat".into()),
+                Token::Redirect(RedirectType::HeredocStrip("\tbody\n".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn completeness_of_partial_input() {
+        for done in [
+            "echo hi",
+            "echo 'a' \"b\" $(c) ${d}",
+            "if true; then echo; fi",
+            "case x in a) echo;; esac",
+            "cat <<EOF\nbody\nEOF",
+            "echo hi # it's",
+            "a || b",
+        ] {
+            assert!(is_complete(done), "{done:?}");
+        }
+        for partial in [
+            "echo 'open",
+            "echo \"$(date",
+            "for i in 1 2; do",
+            "if true; then { echo",
+            "cat <<-EOF\n\tbody",
+            "echo a |",
+            "true &&",
+            "echo \\",
+        ] {
+            assert!(!is_complete(partial), "{partial:?}");
+        }
+    }
+
     #[test]
     fn heredoc_followed_by_command() {
         // Heredoc followed by another command on the next line
@@ -1,24 +1,75 @@
 use crate::ast::{Assignment, CaseItem, Command, ListOp, Redirect, Word, WordPart};
-use crate::lexer::lex;
-use crate::token::Token;
+use crate::lexer::lex_spanned;
+use crate::token::{Span, Token};
 
 /// Parse a shell command string into an AST.
+///
+/// Panics on a syntax error; use [`try_parse`] for input that may be
+/// malformed.
 pub fn parse(input: &str) -> Command {
-    let tokens = lex(input);
-    let mut parser = Parser::new(tokens);
-    parser.parse_complete_command()
+    try_parse(input).unwrap_or_else(|e| panic!("{e}"))
 }
 
-struct Parser {
-    tokens: Vec<Token>,
-    pos: usize,
+/// Parse a shell command string into an AST, reporting where it is
+/// malformed.
+pub fn try_parse(input: &str) -> Result<Command, ParseError> {
+    let (tokens, spans) = lex_spanned(input).into_iter().unzip();
+    let mut parser = Parser {
+        source: input,
+        tokens,
+        spans,
+        pos: 0,
+    };
+    let command = parser.parse_complete_command()?;
+    parser.skip_separators();
+    if parser.peek().is_some() {
+        return Err(parser.error());
+    }
+    Ok(command)
+}
+
+/// What kind of syntax error a [`ParseError`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A token that cannot appear where it does, e.g. `fi` without `if`.
+    UnexpectedToken,
+    /// The input ended inside an unfinished construct.
+    UnexpectedEof,
+}
+
+/// A syntax error, located by the byte span of the offending token (an
+/// empty span at the end of input for [`ParseErrorKind::UnexpectedEof`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+    /// The source text of the offending token; empty at end of input.
+    pub token: String,
 }
 
-impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, pos: 0 }
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnexpectedToken => {
+                write!(f, "syntax error near unexpected token `{}'", self.token)
+            }
+            ParseErrorKind::UnexpectedEof => write!(f, "syntax error: unexpected end of file"),
+        }
     }
+}
+
+impl std::error::Error for ParseError {}
+
+type PResult<T> = Result<T, ParseError>;
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    spans: Vec<Span>,
+    pos: usize,
+}
 
+impl Parser<'_> {
     fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.pos)
     }
@@ -29,9 +80,31 @@ impl Parser {
         token
     }
 
-    fn expect(&mut self, expected: &Token) {
-        let token = self.advance();
-        assert_eq!(&token, expected, "expected {:?}, got {:?}", expected, token);
+    fn expect(&mut self, expected: &Token) -> PResult<()> {
+        if self.peek() != Some(expected) {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// A syntax error at the current token.
+    fn error(&self) -> ParseError {
+        match self.spans.get(self.pos) {
+            Some(&span) => ParseError {
+                kind: ParseErrorKind::UnexpectedToken,
+                span,
+                token: self.source[span.start..span.end].to_string(),
+            },
+            None => ParseError {
+                kind: ParseErrorKind::UnexpectedEof,
+                span: Span {
+                    start: self.source.len(),
+                    end: self.source.len(),
+                },
+                token: String::new(),
+            },
+        }
     }
 
     /// Skip optional semicolons and newlines (used between clauses).
@@ -100,7 +173,7 @@ impl Parser {
     // ----------------------------------------------------------------
 
     /// complete_command = list
-    fn parse_complete_command(&mut self) -> Command {
+    fn parse_complete_command(&mut self) -> PResult<Command> {
         self.skip_separators();
         self.parse_list()
     }
@@ -109,8 +182,8 @@ impl Parser {
     ///
     /// Left-associative. Semicolons and newlines followed by a list terminator
     /// or end-of-input are trailing separators, not sequence operators.
-    fn parse_list(&mut self) -> Command {
-        let mut left = self.parse_pipeline();
+    fn parse_list(&mut self) -> PResult<Command> {
+        let mut left = self.parse_pipeline()?;
 
         loop {
             let op = match self.peek() {
@@ -151,9 +224,12 @@ impl Parser {
             if !matches!(op, ListOp::Seq | ListOp::Background) {
                 self.advance();
                 self.skip_newlines();
+                if self.peek().is_none() {
+                    return Err(self.error());
+                }
             }
 
-            let right = self.parse_pipeline();
+            let right = self.parse_pipeline()?;
             left = Command::List {
                 left: Box::new(left),
                 op,
@@ -161,7 +237,7 @@ impl Parser {
             };
         }
 
-        left
+        Ok(left)
     }
 
     /// Skip newlines only (not semicolons).
@@ -171,20 +247,36 @@ impl Parser {
         }
     }
 
-    /// pipeline = [BANG] command (PIPE command)*
-    fn parse_pipeline(&mut self) -> Command {
+    /// pipeline = [TIME [-p]] [BANG] command (PIPE command)*
+    fn parse_pipeline(&mut self) -> PResult<Command> {
+        // `time` is only a keyword at the start of a pipeline.
+        if matches!(self.peek(), Some(Token::Word(w)) if w == "time") {
+            self.advance();
+            let posix = matches!(self.peek(), Some(Token::Word(w)) if w == "-p");
+            if posix {
+                self.advance();
+            }
+            return Ok(Command::Timed {
+                body: Box::new(self.parse_pipeline()?),
+                posix,
+            });
+        }
+
         let negated = matches!(self.peek(), Some(Token::Bang));
         if negated {
             self.advance();
         }
 
-        let first = self.parse_command();
+        let first = self.parse_command()?;
         let mut commands = vec![first];
 
         while let Some(Token::Pipe) = self.peek() {
             self.advance(); // consume pipe
             self.skip_newlines();
-            commands.push(self.parse_command());
+            if self.peek().is_none() {
+                return Err(self.error());
+            }
+            commands.push(self.parse_command()?);
         }
 
         let result = if commands.len() == 1 {
@@ -194,29 +286,29 @@ impl Parser {
         };
 
         if negated {
-            Command::Negate {
+            Ok(Command::Negate {
                 body: Box::new(result),
-            }
+            })
         } else {
-            result
+            Ok(result)
         }
     }
 
     /// command = if_clause | for_clause | while_clause | case_clause | subshell | function_def | simple_command
-    fn parse_command(&mut self) -> Command {
-        match self.peek() {
-            Some(Token::If) => self.parse_if(),
-            Some(Token::For) => self.parse_for(),
-            Some(Token::While) => self.parse_while(),
-            Some(Token::Until) => self.parse_until(),
-            Some(Token::Case) => self.parse_case(),
-            Some(Token::LParen) => self.parse_subshell(),
+    fn parse_command(&mut self) -> PResult<Command> {
+        Ok(match self.peek() {
+            Some(Token::If) => self.parse_if()?,
+            Some(Token::For) => self.parse_for()?,
+            Some(Token::While) => self.parse_while()?,
+            Some(Token::Until) => self.parse_until()?,
+            Some(Token::Case) => self.parse_case()?,
+            Some(Token::LParen) => self.parse_subshell()?,
             Some(Token::LBrace) => {
                 self.advance(); // consume {
                 self.skip_separators();
-                let body = self.parse_list();
+                let body = self.parse_list()?;
                 self.skip_separators();
-                self.expect(&Token::RBrace);
+                self.expect(&Token::RBrace)?;
                 let redirects = self.parse_trailing_redirects();
                 Command::BraceGroup {
                     body: Box::new(body),
@@ -246,6 +338,18 @@ impl Parser {
                 self.advance();
                 Command::Continue
             }
+            Some(Token::Word(w)) if w == "coproc" => self.parse_coproc()?,
+            Some(Token::Word(w)) if w == "select" && self.at_select_keyword() => {
+                self.parse_select()?
+            }
+            Some(Token::Word(w)) if w == "timeout" && self.at_timeout_keyword() => {
+                self.advance();
+                let duration = self.parse_word_token()?;
+                Command::Timeout {
+                    duration,
+                    body: Box::new(self.parse_command()?),
+                }
+            }
             _ => {
                 // Check for function: name() { ... }
                 if let Some(Token::Word(name)) = self.peek() {
@@ -257,20 +361,51 @@ impl Parser {
                         let name = name.clone();
                         self.pos += 3; // consume name ( )
                         self.skip_separators();
-                        self.expect(&Token::LBrace);
+                        self.expect(&Token::LBrace)?;
                         self.skip_separators();
-                        let body = self.parse_list();
+                        let body = self.parse_list()?;
                         self.skip_separators();
-                        self.expect(&Token::RBrace);
-                        return Command::Function {
+                        self.expect(&Token::RBrace)?;
+                        return Ok(Command::Function {
                             name,
                             body: Box::new(body),
-                        };
+                        });
                     }
                 }
-                self.parse_simple_command()
+                let command = self.parse_simple_command();
+                // Nothing here can start a command, e.g. `fi` without `if`.
+                if matches!(&command, Command::Simple { words, redirects, assignments }
+                    if words.is_empty() && redirects.is_empty() && assignments.is_empty())
+                    && !matches!(self.peek(), None | Some(Token::Newline))
+                {
+                    return Err(self.error());
+                }
+                command
             }
-        }
+        })
+    }
+
+    /// `timeout DURATION command` is a keyword only when followed by a
+    /// duration and a command; option forms like `timeout -s KILL 5 cmd`
+    /// fall through to the `timeout` utility.
+    fn at_timeout_keyword(&self) -> bool {
+        let duration = match self.tokens.get(self.pos + 1) {
+            Some(Token::Word(d)) => !d.starts_with('-'),
+            Some(Token::QuotedWord(_) | Token::Variable(_) | Token::DoubleQuoted(_)) => true,
+            _ => false,
+        };
+        duration
+            && matches!(
+                self.tokens.get(self.pos + 2),
+                Some(
+                    Token::Word(_)
+                        | Token::QuotedWord(_)
+                        | Token::Variable(_)
+                        | Token::DoubleQuoted(_)
+                        | Token::LBrace
+                        | Token::LParen
+                )
+            )
     }
 
     /// simple_command = (assignment)* word* (redirect)*
@@ -352,76 +487,155 @@ impl Parser {
     }
 
     /// if_clause = IF list SEMI? THEN list (ELIF list SEMI? THEN list)* (ELSE list)? FI
-    fn parse_if(&mut self) -> Command {
-        self.expect(&Token::If);
-        let condition = self.parse_list();
+    fn parse_if(&mut self) -> PResult<Command> {
+        self.expect(&Token::If)?;
+        let condition = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Then);
-        let then_body = self.parse_list();
+        self.expect(&Token::Then)?;
+        let then_body = self.parse_list()?;
         self.skip_separators();
 
         let mut else_body = None;
 
         if let Some(Token::Elif) = self.peek() {
             // Treat `elif` as a nested if inside the else branch.
-            else_body = Some(Box::new(self.parse_elif()));
+            else_body = Some(Box::new(self.parse_elif()?));
         } else if let Some(Token::Else) = self.peek() {
             self.advance(); // consume else
-            else_body = Some(Box::new(self.parse_list()));
+            else_body = Some(Box::new(self.parse_list()?));
             self.skip_separators();
         }
 
-        self.expect(&Token::Fi);
+        self.expect(&Token::Fi)?;
 
-        Command::If {
+        Ok(Command::If {
             condition: Box::new(condition),
             then_body: Box::new(then_body),
             else_body,
-        }
+        })
     }
 
     /// Parse an elif chain as a nested If command (without consuming an outer Fi).
-    fn parse_elif(&mut self) -> Command {
-        self.expect(&Token::Elif);
-        let condition = self.parse_list();
+    fn parse_elif(&mut self) -> PResult<Command> {
+        self.expect(&Token::Elif)?;
+        let condition = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Then);
-        let then_body = self.parse_list();
+        self.expect(&Token::Then)?;
+        let then_body = self.parse_list()?;
         self.skip_separators();
 
         let mut else_body = None;
 
         if let Some(Token::Elif) = self.peek() {
-            else_body = Some(Box::new(self.parse_elif()));
+            else_body = Some(Box::new(self.parse_elif()?));
         } else if let Some(Token::Else) = self.peek() {
             self.advance();
-            else_body = Some(Box::new(self.parse_list()));
+            else_body = Some(Box::new(self.parse_list()?));
             self.skip_separators();
         }
 
-        Command::If {
+        Ok(Command::If {
             condition: Box::new(condition),
             then_body: Box::new(then_body),
             else_body,
-        }
+        })
     }
 
     /// for_clause = FOR word IN word* SEMI? DO list DONE
     ///           | FOR (( init ; cond ; step )) SEMI? DO list DONE
-    fn parse_for(&mut self) -> Command {
-        self.expect(&Token::For);
+    fn parse_for(&mut self) -> PResult<Command> {
+        self.expect(&Token::For)?;
 
         // Check for C-style: for (( ... ))
         if matches!(self.peek(), Some(Token::DoubleParen(_))) {
             return self.parse_c_for();
         }
 
-        let var = match self.advance() {
-            Token::Word(w) => w,
-            other => panic!("expected variable name after 'for', got {:?}", other),
+        let var = match self.peek() {
+            Some(Token::Word(w)) => w.clone(),
+            _ => return Err(self.error()),
+        };
+        self.advance();
+        self.expect(&Token::In)?;
+        let words = self.parse_in_words();
+
+        self.skip_separators();
+        self.expect(&Token::Do)?;
+        let body = self.parse_list()?;
+        self.skip_separators();
+        self.expect(&Token::Done)?;
+
+        Ok(Command::For {
+            var,
+            words,
+            body: Box::new(body),
+        })
+    }
+
+    /// `select` is only a keyword in the `select NAME in` form, so that
+    /// `select` stays usable as an ordinary command name or argument.
+    fn at_select_keyword(&self) -> bool {
+        matches!(self.tokens.get(self.pos + 1), Some(Token::Word(_)))
+            && matches!(self.tokens.get(self.pos + 2), Some(Token::In))
+    }
+
+    /// select_clause = SELECT word IN word* SEMI? DO list DONE
+    fn parse_select(&mut self) -> PResult<Command> {
+        self.advance(); // consume `select`
+        let var = match self.peek() {
+            Some(Token::Word(w)) => w.clone(),
+            _ => return Err(self.error()),
         };
-        self.expect(&Token::In);
+        self.advance();
+        self.expect(&Token::In)?;
+        let words = self.parse_in_words();
 
+        self.skip_separators();
+        self.expect(&Token::Do)?;
+        let body = self.parse_list()?;
+        self.skip_separators();
+        self.expect(&Token::Done)?;
+
+        Ok(Command::Select {
+            var,
+            words,
+            body: Box::new(body),
+        })
+    }
+
+    /// coproc = COPROC [NAME] command
+    ///
+    /// As in bash, NAME is only recognised when a compound command follows;
+    /// otherwise the first word is the command itself.
+    fn parse_coproc(&mut self) -> PResult<Command> {
+        self.advance(); // consume `coproc`
+        let mut name = "COPROC".to_string();
+        if let Some(Token::Word(w)) = self.peek() {
+            let compound_follows = matches!(
+                self.tokens.get(self.pos + 1),
+                Some(
+                    Token::LBrace
+                        | Token::LParen
+                        | Token::If
+                        | Token::For
+                        | Token::While
+                        | Token::Until
+                        | Token::Case
+                )
+            );
+            if compound_follows {
+                name = w.clone();
+                self.advance();
+            }
+        }
+        Ok(Command::Coproc {
+            name,
+            body: Box::new(self.parse_command()?),
+        })
+    }
+
+    /// The word list after `for NAME in` / `select NAME in`.
+    fn parse_in_words(&mut self) -> Vec<Word> {
         let mut words = Vec::new();
         loop {
             match self.peek() {
@@ -457,26 +671,16 @@ impl Parser {
                 _ => break,
             }
         }
-
-        self.skip_separators();
-        self.expect(&Token::Do);
-        let body = self.parse_list();
-        self.skip_separators();
-        self.expect(&Token::Done);
-
-        Command::For {
-            var,
-            words,
-            body: Box::new(body),
-        }
+        words
     }
 
     /// c_for = FOR DoubleParen SEMI? DO list DONE
-    fn parse_c_for(&mut self) -> Command {
-        let content = match self.advance() {
-            Token::DoubleParen(s) => s,
-            other => panic!("expected ((...)) after 'for', got {:?}", other),
+    fn parse_c_for(&mut self) -> PResult<Command> {
+        let content = match self.peek() {
+            Some(Token::DoubleParen(s)) => s.clone(),
+            _ => return Err(self.error()),
         };
+        self.advance();
 
         // Split on ';' to get init, cond, step
         let parts: Vec<&str> = content.splitn(3, ';').collect();
@@ -485,68 +689,68 @@ impl Parser {
         let step = parts.get(2).map(|s| s.trim()).unwrap_or("").to_string();
 
         self.skip_separators();
-        self.expect(&Token::Do);
-        let body = self.parse_list();
+        self.expect(&Token::Do)?;
+        let body = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Done);
+        self.expect(&Token::Done)?;
 
-        Command::CFor {
+        Ok(Command::CFor {
             init,
             cond,
             step,
             body: Box::new(body),
-        }
+        })
     }
 
     /// while_clause = WHILE list SEMI? DO list DONE
-    fn parse_while(&mut self) -> Command {
-        self.expect(&Token::While);
-        let condition = self.parse_list();
+    fn parse_while(&mut self) -> PResult<Command> {
+        self.expect(&Token::While)?;
+        let condition = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Do);
-        let body = self.parse_list();
+        self.expect(&Token::Do)?;
+        let body = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Done);
+        self.expect(&Token::Done)?;
 
-        Command::While {
+        Ok(Command::While {
             condition: Box::new(condition),
             body: Box::new(body),
-        }
+        })
     }
 
     /// until_clause = UNTIL list SEMI? DO list DONE
     /// Desugars to: while ! condition; do body; done
-    fn parse_until(&mut self) -> Command {
-        self.expect(&Token::Until);
-        let condition = self.parse_list();
+    fn parse_until(&mut self) -> PResult<Command> {
+        self.expect(&Token::Until)?;
+        let condition = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Do);
-        let body = self.parse_list();
+        self.expect(&Token::Do)?;
+        let body = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::Done);
+        self.expect(&Token::Done)?;
 
-        Command::While {
+        Ok(Command::While {
             condition: Box::new(Command::Negate {
                 body: Box::new(condition),
             }),
             body: Box::new(body),
-        }
+        })
     }
 
     /// subshell = LPAREN list RPAREN
-    fn parse_subshell(&mut self) -> Command {
-        self.expect(&Token::LParen);
-        let body = self.parse_list();
+    fn parse_subshell(&mut self) -> PResult<Command> {
+        self.expect(&Token::LParen)?;
+        let body = self.parse_list()?;
         self.skip_separators();
-        self.expect(&Token::RParen);
+        self.expect(&Token::RParen)?;
 
         // Collect trailing redirects: ( cmd ) 2>&1
         let redirects = self.parse_trailing_redirects();
 
-        Command::Subshell {
+        Ok(Command::Subshell {
             body: Box::new(body),
             redirects,
-        }
+        })
     }
 
     /// Parse any trailing redirect tokens (e.g. `2>&1`, `>file`, `<file`).
@@ -562,10 +766,10 @@ impl Parser {
 
     /// case_clause = CASE word IN (case_item)* ESAC
     /// case_item = pattern (PIPE pattern)* RPAREN list DOUBLE_SEMI
-    fn parse_case(&mut self) -> Command {
-        self.expect(&Token::Case);
-        let word = self.parse_word_token();
-        self.expect(&Token::In);
+    fn parse_case(&mut self) -> PResult<Command> {
+        self.expect(&Token::Case)?;
+        let word = self.parse_word_token()?;
+        self.expect(&Token::In)?;
         self.skip_separators();
 
         let mut items = Vec::new();
@@ -576,12 +780,12 @@ impl Parser {
             if matches!(self.peek(), Some(Token::LParen)) {
                 self.advance();
             }
-            patterns.push(self.parse_word_token());
+            patterns.push(self.parse_word_token()?);
             while matches!(self.peek(), Some(Token::Pipe)) {
                 self.advance();
-                patterns.push(self.parse_word_token());
+                patterns.push(self.parse_word_token()?);
             }
-            self.expect(&Token::RParen);
+            self.expect(&Token::RParen)?;
             self.skip_separators();
 
             // Parse body (may be empty)
@@ -589,7 +793,7 @@ impl Parser {
                 self.peek(),
                 Some(Token::DoubleSemi) | Some(Token::Esac) | None
             ) {
-                self.parse_list()
+                self.parse_list()?
             } else {
                 Command::Simple {
                     words: vec![],
@@ -610,53 +814,53 @@ impl Parser {
             }
         }
 
-        self.expect(&Token::Esac);
-        Command::Case { word, items }
+        self.expect(&Token::Esac)?;
+        Ok(Command::Case { word, items })
     }
 
     /// Parse a single word token (Word, QuotedWord, Variable, DoubleQuoted, CommandSub).
-    fn parse_word_token(&mut self) -> Word {
+    fn parse_word_token(&mut self) -> PResult<Word> {
         match self.peek() {
             Some(Token::Word(_)) => {
                 if let Token::Word(w) = self.advance() {
-                    Word::literal(&w)
+                    Ok(Word::literal(&w))
                 } else {
                     unreachable!()
                 }
             }
             Some(Token::QuotedWord(_)) => {
                 if let Token::QuotedWord(w) = self.advance() {
-                    Word {
+                    Ok(Word {
                         parts: vec![WordPart::QuotedLiteral(w)],
-                    }
+                    })
                 } else {
                     unreachable!()
                 }
             }
             Some(Token::Variable(_)) => {
                 if let Token::Variable(v) = self.advance() {
-                    Word::variable(&v)
+                    Ok(Word::variable(&v))
                 } else {
                     unreachable!()
                 }
             }
             Some(Token::DoubleQuoted(_)) => {
                 if let Token::DoubleQuoted(parts) = self.advance() {
-                    Word { parts }
+                    Ok(Word { parts })
                 } else {
                     unreachable!()
                 }
             }
             Some(Token::CommandSub(_)) => {
                 if let Token::CommandSub(c) = self.advance() {
-                    Word {
+                    Ok(Word {
                         parts: vec![WordPart::CommandSub(c)],
-                    }
+                    })
                 } else {
                     unreachable!()
                 }
             }
-            other => panic!("expected word, got {:?}", other),
+            _ => Err(self.error()),
         }
     }
 }
@@ -749,12 +953,13 @@ fn parse_raw_dollar_string(raw: &str) -> Vec<WordPart> {
                 parts.push(part);
                 i = j + 1;
             } else {
-                // $VAR or $1, $?, $#, $@, $*, $$, $!
+                // $VAR or $1, $?, $#, $@, $*, $$, $!, $-
                 let start = i + 1;
                 let mut j = start;
                 if j < len
                     && (chars[j] == '?'
                         || chars[j] == '#'
+                        || chars[j] == '-'
                         || chars[j] == '@'
                         || chars[j] == '*'
                         || chars[j] == '$'
@@ -855,6 +1060,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn coproc_default_and_named() {
+        match parse("coproc cat -n") {
+            Command::Coproc { name, body } => {
+                assert_eq!(name, "COPROC");
+                assert!(matches!(*body, Command::Simple { .. }));
+            }
+            _ => panic!("expected Coproc"),
+        }
+        match parse("coproc NUM { cat -n; }") {
+            Command::Coproc { name, body } => {
+                assert_eq!(name, "NUM");
+                assert!(matches!(*body, Command::BraceGroup { .. }));
+            }
+            _ => panic!("expected Coproc"),
+        }
+    }
+
+    #[test]
+    fn select_loop() {
+        let cmd = parse("select opt in a \"b c\"; do echo $opt; break; done");
+        match cmd {
+            Command::Select { var, words, .. } => {
+                assert_eq!(var, "opt");
+                assert_eq!(words.len(), 2);
+            }
+            _ => panic!("expected Select"),
+        }
+        // Not a keyword outside the `select NAME in` form.
+        assert!(matches!(parse("select"), Command::Simple { .. }));
+    }
+
+    #[test]
+    fn timeout_keyword_wraps_single_command() {
+        let cmd = parse("timeout 5 cat file | wc -l");
+        match cmd {
+            Command::Pipeline { commands } => match &commands[0] {
+                Command::Timeout { duration, body } => {
+                    assert_eq!(*duration, Word::literal("5"));
+                    assert!(matches!(**body, Command::Simple { .. }));
+                }
+                _ => panic!("expected Timeout"),
+            },
+            _ => panic!("expected Pipeline"),
+        }
+        // Option forms and bare words are left to the utility.
+        assert!(matches!(
+            parse("timeout -s KILL 5 cmd"),
+            Command::Simple { .. }
+        ));
+        assert!(matches!(parse("timeout 5"), Command::Simple { .. }));
+    }
+
+    #[test]
+    fn time_keyword_wraps_pipeline() {
+        let cmd = parse("time -p cat file | wc -l");
+        match cmd {
+            Command::Timed { body, posix } => {
+                assert!(posix);
+                assert!(matches!(*body, Command::Pipeline { .. }));
+            }
+            _ => panic!("expected Timed"),
+        }
+        // Only a keyword in command position.
+        assert!(matches!(parse("echo time"), Command::Simple { .. }));
+    }
+
     #[test]
     fn list_and() {
         let cmd = parse("cmd1 && cmd2");
@@ -1144,4 +1416,23 @@ mod tests {
             _ => panic!("expected Simple command"),
         }
     }
+
+    #[test]
+    fn syntax_errors_are_located() {
+        let src = "if true; then\n  echo a\nfi fi\n";
+        let err = try_parse(src).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedToken);
+        assert_eq!(err.token, "fi");
+        assert_eq!(err.span.line_col(src), (3, 4));
+        assert_eq!(err.to_string(), "syntax error near unexpected token `fi'");
+
+        let err = try_parse("for x in a b; do echo $x").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnexpectedEof);
+        assert_eq!(err.span, Span { start: 24, end: 24 });
+
+        for src in ["echo hi |", "a &&", "( echo", "echo )", "case x in a) ;;"] {
+            assert!(try_parse(src).is_err(), "{src:?} should not parse");
+        }
+        assert!(try_parse("").is_ok());
+    }
 }
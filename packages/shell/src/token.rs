@@ -63,15 +63,37 @@ pub enum Token {
     DoubleBracket(String),
 }
 
+/// A byte range `start..end` of the source text a token was read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The 1-based line and column (counted in characters) at which the
+    /// span starts in `source`.
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let before = source.get(..self.start).unwrap_or(source);
+        let line = before.matches('\n').count() + 1;
+        let col = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        (line, col)
+    }
+}
+
 /// The kind of I/O redirection.
-#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum RedirectType {
     /// > file
     StdoutOverwrite(String),
     /// >> file
     StdoutAppend(String),
-    /// < file
+    /// >| file (overwrite even when noclobber is set)
+    StdoutClobber(String),
+    /// < file; `&N` for `<&N` (read from fd N, which may be `$name` or `${name[i]}`)
     StdinFrom(String),
+    /// <> file (open for reading and writing on stdin; created if missing)
+    ReadWrite(String),
     /// 2> file
     StderrOverwrite(String),
     /// 2>> file
@@ -80,6 +102,20 @@ pub enum RedirectType {
     StderrToStdout,
     /// &> file  (both stdout and stderr)
     BothOverwrite(String),
+    /// N> file for a numbered fd (N >= 3)
+    FdOverwrite(i32, String),
+    /// N>> file for a numbered fd (N >= 3)
+    FdAppend(i32, String),
+    /// N>&M — make fd N a copy of fd M; M may be a `$name` reference
+    FdDup(i32, String),
+    /// N>&- — close fd N
+    FdClose(i32),
+    /// {name}> file — allocate a fd >= 10 and store its number in `name`
+    FdVarOverwrite(String, String),
+    /// {name}>> file
+    FdVarAppend(String, String),
+    /// {name}>&- — close the fd whose number is stored in `name`
+    FdVarClose(String),
     /// <<EOF ... content ... EOF (unquoted: expand variables)
     Heredoc(String),
     /// <<'EOF' or <<"EOF" ... content ... EOF (quoted: no expansion)
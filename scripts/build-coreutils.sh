@@ -30,7 +30,7 @@ if [[ "${1:-}" == "--copy-fixtures" ]]; then
   echo ""
   echo "Copying to test fixtures..."
 
-  TOOLS=(cat echo head tail wc sort uniq grep ls mkdir rm cp mv touch tee tr cut basename dirname env printf find sed awk jq du df gzip tar bc dc hostname base64 sha256sum sha1sum sha224sum sha384sum sha512sum md5sum stat xxd rev nproc fmt fold nl expand unexpand paste comm join split strings od cksum truncate tree patch file column cmp timeout numfmt csplit zip unzip arch factor shuf sum link unlink base32 dd tsort nice nohup hostid uptime chown chgrp sudo groups logname users who)
+  TOOLS=(cat echo head tail wc sort uniq grep ls mkdir rm cp mv touch tee tr cut basename dirname env printf find sed awk jq du df gzip tar bc dc hostname base64 sha256sum sha1sum sha224sum sha384sum sha512sum md5sum stat xxd hexdump rev nproc fmt fold nl expand unexpand paste comm join split strings od cksum truncate tree patch file column cmp timeout numfmt csplit zip unzip arch factor shuf sum link unlink base32 dd tsort nice nohup hostid uptime chown chgrp sudo groups logname users who coreutils)
   for tool in "${TOOLS[@]}"; do
     cp "$TARGET_DIR/$tool.wasm" "$FIXTURES_DIR/$tool.wasm"
   done